@@ -0,0 +1,42 @@
+#!/bin/bash
+
+# Compare runtime allocators on an allocation-heavy program
+# Usage: benchmarks/compare_allocators.sh [program.ws] [runs]
+#
+# Builds the release compiler and one runtime archive per allocator, compiles
+# the program against each (--allocator=...), and reports the best wall-clock
+# time of several runs.
+
+set -e
+
+GREEN='\033[0;32m'
+BLUE='\033[0;34m'
+NC='\033[0m' # No Color
+
+PROGRAM="${1:-benchmarks/bench_allocators.ws}"
+RUNS="${2:-5}"
+COMPILER="./target/release/wadescript"
+NAME="$(basename "$PROGRAM" .ws)"
+
+echo -e "${BLUE}Building the compiler and runtime archives (release)...${NC}"
+cargo build --release
+cargo build --lib --release --features mimalloc --target-dir target/alloc/mimalloc
+cargo build --lib --release --features jemalloc --target-dir target/alloc/jemalloc
+
+echo ""
+echo "Program: $PROGRAM (best of $RUNS runs)"
+echo ""
+printf "%-10s %10s\n" "allocator" "seconds"
+for allocator in system mimalloc jemalloc; do
+    "$COMPILER" "$PROGRAM" --quiet --allocator="$allocator"
+    times=""
+    for _ in $(seq "$RUNS"); do
+        # bash's `time` prints only the elapsed seconds with this format
+        times="$times $( { TIMEFORMAT=%3R; time "./$NAME" > /dev/null; } 2>&1 )"
+    done
+    echo "$times" | awk -v name="$allocator" '{ best = $1; for (i = 2; i <= NF; i++) if ($i < best) best = $i; printf "%-10s %10.3f\n", name, best }'
+    rm -f "./$NAME"
+done
+
+echo ""
+echo -e "${GREEN}✓ Done${NC}"
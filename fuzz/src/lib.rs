@@ -0,0 +1,27 @@
+// The compiler is a binary crate that links LLVM, so the fuzz targets build the
+// front-end modules (and the few they depend on) into this library by path.
+#![allow(dead_code)]
+
+#[path = "../../src/ast.rs"]
+pub mod ast;
+#[path = "../../src/error_codes.rs"]
+pub mod error_codes;
+#[path = "../../src/fuzz.rs"]
+pub mod fuzz;
+#[path = "../../src/lexer.rs"]
+pub mod lexer;
+#[path = "../../src/output.rs"]
+pub mod output;
+#[path = "../../src/parser.rs"]
+pub mod parser;
+#[path = "../../src/suggest.rs"]
+pub mod suggest;
+#[path = "../../src/typechecker.rs"]
+pub mod typechecker;
+
+#[path = "../../src/lsp/diagnostics.rs"]
+pub mod lsp_diagnostics;
+
+pub mod lsp {
+    pub use super::lsp_diagnostics as diagnostics;
+}
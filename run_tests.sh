@@ -43,6 +43,11 @@ for test_file in $TEST_DIR/test_*.ws; do
     test_name=$(basename "$test_file" .ws)
     expected_file="${TEST_DIR}/${test_name}.expected"
     executable="./${test_name}"
+    # Input for tests that read standard input; the rest read nothing
+    stdin_file="${TEST_DIR}/${test_name}.stdin"
+    if [ ! -f "$stdin_file" ]; then
+        stdin_file=/dev/null
+    fi
 
     echo "Running: $test_name"
 
@@ -62,7 +67,7 @@ for test_file in $TEST_DIR/test_*.ws; do
     fi
 
     # Run the test and capture output
-    if ! actual_output=$($executable 2>&1); then
+    if ! actual_output=$($executable < "$stdin_file" 2>&1); then
         echo -e "${RED}FAIL${NC}: Runtime error"
         FAILED=$((FAILED + 1))
         echo ""
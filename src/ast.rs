@@ -1,10 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Int,
+    I32,                            // Sized integers for FFI and binary data
+    I16,
+    U8,
     Float,
+    BigInt,                         // Arbitrary-precision integer
+    Decimal,                        // Exact fixed-point decimal
     Bool,
     Str,
     Void,
@@ -12,16 +18,23 @@ pub enum Type {
     List(Box<Type>),                // Dynamic list: list[int]
     Dict(Box<Type>, Box<Type>),     // Dictionary: dict[str, int]
     Optional(Box<Type>),            // Nullable type: str? or Optional[str]
+    Result(Box<Type>, Box<Type>),   // Success or error value: Result[int, str]
     Exception,                      // Exception object type
     Tuple(Vec<Type>),               // Tuple type: (int, str, bool)
     Custom(String),
+    Inferred,                       // `let x = ...`: filled in by the type checker
 }
 
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Type::Int => write!(f, "int"),
+            Type::I32 => write!(f, "i32"),
+            Type::I16 => write!(f, "i16"),
+            Type::U8 => write!(f, "u8"),
             Type::Float => write!(f, "float"),
+            Type::BigInt => write!(f, "bigint"),
+            Type::Decimal => write!(f, "decimal"),
             Type::Bool => write!(f, "bool"),
             Type::Str => write!(f, "str"),
             Type::Void => write!(f, "void"),
@@ -29,6 +42,7 @@ impl fmt::Display for Type {
             Type::List(elem_type) => write!(f, "list[{}]", elem_type),
             Type::Dict(key_type, val_type) => write!(f, "dict[{}, {}]", key_type, val_type),
             Type::Optional(inner_type) => write!(f, "{}?", inner_type),
+            Type::Result(ok_type, err_type) => write!(f, "Result[{}, {}]", ok_type, err_type),
             Type::Exception => write!(f, "Exception"),
             Type::Tuple(types) => {
                 write!(f, "(")?;
@@ -41,6 +55,32 @@ impl fmt::Display for Type {
                 write!(f, ")")
             }
             Type::Custom(name) => write!(f, "{}", name),
+            Type::Inferred => write!(f, "_"),
+        }
+    }
+}
+
+impl Type {
+    /// Integer types in widening order (u8 < i16 < i32 < int). Each one converts
+    /// implicitly to the types after it; narrowing needs `as`.
+    pub fn integer_rank(&self) -> Option<usize> {
+        match self {
+            Type::U8 => Some(0),
+            Type::I16 => Some(1),
+            Type::I32 => Some(2),
+            Type::Int => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Range of values an integer type can hold
+    pub fn integer_range(&self) -> Option<(i64, i64)> {
+        match self {
+            Type::U8 => Some((0, u8::MAX as i64)),
+            Type::I16 => Some((i16::MIN as i64, i16::MAX as i64)),
+            Type::I32 => Some((i32::MIN as i64, i32::MAX as i64)),
+            Type::Int => Some((i64::MIN, i64::MAX)),
+            _ => None,
         }
     }
 }
@@ -48,18 +88,146 @@ impl fmt::Display for Type {
 #[derive(Debug, Clone)]
 pub struct Program {
     pub statements: Vec<Statement>,
-    pub modules: std::collections::HashMap<String, Vec<String>>, // module_name -> function_names
+    pub modules: HashMap<String, Module>, // Imported modules by name
+    pub libraries: Vec<Library>, // Compiled libraries (.wslib) brought in by imports
 }
 
 impl Program {
     pub fn new() -> Self {
         Program {
             statements: Vec::new(),
-            modules: std::collections::HashMap::new(),
+            modules: HashMap::new(),
+            libraries: Vec::new(),
+        }
+    }
+}
+
+/// An imported file or library. The functions a source module defines are
+/// named `module.name` in the program, so modules can't collide.
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+    pub functions: Vec<String>,  // Names callable as `module.name`, including its imports' functions
+    pub classes: Vec<String>,    // Classes the module's own file defines
+    pub imports: Vec<String>,    // Modules it imports, directly or through another module
+}
+
+/// Which function a call names. An unqualified call reaches the calling
+/// module's own function, then one from the modules it can see (all of them
+/// from the program), then a builtin, library or program function.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionNames {
+    modules: HashMap<String, Module>,
+    defined: HashSet<String>,  // Every function the program and its imports define
+}
+
+impl FunctionNames {
+    pub fn new(program: &Program) -> Self {
+        let mut names = FunctionNames { modules: program.modules.clone(), defined: HashSet::new() };
+        names.define(program);
+        names
+    }
+
+    /// Add the modules and functions of another program, as the REPL loads files
+    pub fn define(&mut self, program: &Program) {
+        self.modules.extend(program.modules.iter().map(|(name, module)| (name.clone(), module.clone())));
+        for stmt in &program.statements {
+            if let Statement::FunctionDef { name, .. } = stmt {
+                self.defined.insert(name.clone());
+            }
+        }
+        for function in program.libraries.iter().flat_map(|library| &library.functions) {
+            self.defined.insert(function.name.clone());
+        }
+    }
+
+    pub fn modules(&self) -> &HashMap<String, Module> {
+        &self.modules
+    }
+
+    /// The module whose file defines a class, if it isn't the program's
+    fn class_module(&self, class: &str) -> Option<&str> {
+        self.modules
+            .iter()
+            .find(|(_, module)| module.classes.iter().any(|c| c == class))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The module a top-level definition comes from, if it isn't the program's
+    pub fn statement_module(&self, statement: &Statement) -> Option<String> {
+        let module = match statement {
+            Statement::FunctionDef { name, .. } => name.split_once('.').map(|(module, _)| module),
+            Statement::ClassDef { name, .. } => self.class_module(name),
+            _ => None,
+        };
+        module.map(str::to_string)
+    }
+
+    /// The name an unqualified call to `name` from `module` (None for the
+    /// program itself) refers to
+    pub fn resolve(&self, module: Option<&str>, name: &str) -> Result<String, String> {
+        let own = match module {
+            Some(module) => format!("{}.{}", module, name),
+            None => name.to_string(),
+        };
+        if self.defined.contains(&own) {
+            return Ok(own);
+        }
+        let visible: Vec<&String> = match module {
+            Some(module) => self.modules.get(module).map(|m| m.imports.iter().collect()).unwrap_or_default(),
+            None => self.modules.keys().collect(),
+        };
+        Ok(self.imported(visible, name)?.unwrap_or_else(|| name.to_string()))
+    }
+
+    /// The name `module.name` refers to: the module's own function, one from
+    /// a module it imports, or a compiled library's
+    pub fn resolve_qualified(&self, module: &str, name: &str) -> Result<String, String> {
+        let own = format!("{}.{}", module, name);
+        if self.defined.contains(&own) {
+            return Ok(own);
+        }
+        let imports = self.modules.get(module).map(|m| m.imports.iter().collect()).unwrap_or_default();
+        Ok(self.imported(imports, name)?.unwrap_or_else(|| name.to_string()))
+    }
+
+    /// The function `name` of one of `modules`; an error if several define it
+    fn imported(&self, mut modules: Vec<&String>, name: &str) -> Result<Option<String>, String> {
+        modules.sort();
+        let found: Vec<&String> = modules
+            .into_iter()
+            .filter(|module| self.defined.contains(&format!("{}.{}", module, name)))
+            .collect();
+        match found.as_slice() {
+            [] => Ok(None),
+            [module] => Ok(Some(format!("{}.{}", module, name))),
+            _ => {
+                let calls: Vec<String> = found.iter().map(|module| format!("{}.{}()", module, name)).collect();
+                Err(format!(
+                    "Function '{}' is defined in more than one imported module; call it as {}",
+                    name,
+                    calls.join(" or ")
+                ))
+            }
         }
     }
 }
 
+/// A compiled library imported without its source: the signatures it exports,
+/// and the archive holding the object code to link
+#[derive(Debug, Clone)]
+pub struct Library {
+    pub path: PathBuf,
+    pub target: String,  // Target triple the object code was built for
+    pub functions: Vec<LibraryFunction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LibraryFunction {
+    pub name: String,
+    pub params: Vec<Parameter>,  // No default values: the interface can't carry them
+    pub return_type: Type,
+}
+
 #[derive(Debug, Clone)]
 pub enum Statement {
     VarDecl {
@@ -72,12 +240,16 @@ pub enum Statement {
         params: Vec<Parameter>,
         return_type: Type,
         body: Vec<Statement>,
+        decorators: Vec<Decorator>,  // Function decorators (e.g., @memoize)
+        docstring: Option<String>,   // Leading string literal in the body
     },
     ClassDef {
         name: String,
-        _base_class: Option<String>,  // Reserved for future inheritance support
+        base_class: Option<String>,
         fields: Vec<Field>,
         methods: Vec<Statement>,
+        decorators: Vec<Decorator>,   // Class decorators (e.g., @abstract)
+        docstring: Option<String>,    // Leading string literal in the class body
     },
     If {
         condition: Expression,
@@ -91,6 +263,8 @@ pub enum Statement {
     },
     For {
         variable: String,
+        /// The second name in `for key, value in dict`
+        value_variable: Option<String>,
         iterable: Expression,
         body: Vec<Statement>,
     },
@@ -109,6 +283,10 @@ pub enum Statement {
     Raise {
         exception_type: String,  // e.g., "ValueError", "KeyError"
         message: Expression,     // Error message
+        cause: Option<Expression>, // `raise X("...") from e`
+        line: usize,
+    },
+    Reraise {                    // Bare `raise` inside an except block
         line: usize,
     },
     Expression(Expression),
@@ -136,10 +314,10 @@ pub struct Parameter {
     pub default_value: Option<Expression>,  // Default parameter value
 }
 
-/// Represents a decorator applied to a field (e.g., @arg, @option)
+/// Represents a decorator applied to a field (e.g., @arg, @option) or function (e.g., @memoize)
 #[derive(Debug, Clone)]
 pub struct Decorator {
-    pub name: String,                    // "arg" or "option"
+    pub name: String,                    // "arg", "option", "memoize"
     pub args: HashMap<String, String>,   // Named arguments like help="...", short="v"
 }
 
@@ -148,6 +326,7 @@ pub struct Field {
     pub name: String,
     pub field_type: Type,
     pub decorators: Vec<Decorator>,      // Decorators on this field
+    pub default_value: Option<Expression>,  // Set before init runs; not a constructor argument
 }
 
 #[derive(Debug, Clone)]
@@ -155,6 +334,7 @@ pub struct Field {
 pub enum Expression {
     IntLiteral(i64),
     FloatLiteral(f64),
+    DecimalLiteral(String),     // 19.99d, kept as written
     StringLiteral(String),
     BoolLiteral(bool),
     NoneLiteral,
@@ -178,10 +358,29 @@ pub enum Expression {
         object: Box<Expression>,
         member: String,
     },
+    // `object?.member`: None if object is None, otherwise Optional of the field
+    OptionalMemberAccess {
+        object: Box<Expression>,
+        member: String,
+    },
     Assignment {
         target: String,
         value: Box<Expression>,
     },
+    // `value ?? default`: default is only evaluated when value is None
+    NullCoalesce {
+        value: Box<Expression>,
+        default: Box<Expression>,
+    },
+    // `value?`: unwraps an Ok Result, or returns the Err from the enclosing function
+    Propagate {
+        value: Box<Expression>,
+    },
+    // `value as int` / `value as float`
+    Cast {
+        expr: Box<Expression>,
+        target: Type,
+    },
     ArrayLiteral {
         elements: Vec<Expression>,
     },
@@ -202,10 +401,22 @@ pub enum Expression {
         value: Box<Expression>,
         line: usize,
     },
+    // `object.field = value`
+    FieldAssignment {
+        object: Box<Expression>,
+        field: String,
+        value: Box<Expression>,
+    },
     MethodCall {
         object: Box<Expression>,
         method: String,
         args: Vec<Expression>,
+        named_args: Vec<(String, Expression)>,  // Only module functions take named arguments
+    },
+    // `super.method(args)`: the base class's method, called on `self` without dispatch
+    SuperCall {
+        method: String,
+        args: Vec<Expression>,
     },
     FString {
         parts: Vec<String>,       // String parts between {}
@@ -1,13 +1,54 @@
 use crate::ast::*;
+use crate::typechecker::TypeChecker;
+use crate::runtime::rc::{
+    ELEMS_BIGINT, ELEMS_BOX, ELEMS_DECIMAL, ELEMS_DICT, ELEMS_LIST, ELEMS_OBJECT, ELEMS_PLAIN, ELEMS_TUPLE, RC_REF_COUNT_OFFSET,
+};
 use inkwell::builder::Builder;
 use inkwell::context::Context;
-use inkwell::module::Module;
-use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, StructType};
-use inkwell::values::{BasicMetadataValueEnum, BasicValue, BasicValueEnum, FunctionValue, PointerValue};
+use inkwell::module::{Linkage, Module};
+use inkwell::passes::PassBuilderOptions;
+use inkwell::targets::TargetMachine;
+use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, IntType, StructType};
+use inkwell::values::{BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallSiteValue, FunctionValue, IntValue, PointerValue, StructValue};
+use inkwell::attributes::{Attribute, AttributeLoc};
+use inkwell::intrinsics::Intrinsic;
 use inkwell::basic_block::BasicBlock;
 use inkwell::{AddressSpace, IntPredicate, FloatPredicate};
 use inkwell::debug_info::{AsDIScope, DICompileUnit, DIFlagsConstants, DWARFEmissionKind, DWARFSourceLanguage, DebugInfoBuilder, DISubprogram};
 use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+// Size of the stack buffer handed to setjmp in `try` blocks. Must be at least the
+// platform's jmp_buf: 200 bytes on x86_64 glibc, 312 on aarch64 glibc, 192 on macOS arm64.
+// Keep in sync with JmpBuf in runtime/exceptions.rs.
+const JMP_BUF_SIZE: u32 = 512;
+const JMP_BUF_ALIGN: u32 = 16;
+
+// An if/elif chain comparing one int variable with at least this many constants
+// is lowered to a `switch`, which LLVM turns into a jump table or a binary search
+const MIN_SWITCH_CASES: usize = 3;
+
+// Runtime functions that return a new list (or dict) the caller owns (used by std/compress.ws, std/config.ws,
+// std/csv.ws, std/fs.ws, std/io.ws and std/regex.ws)
+const OWNED_LIST_BUILTINS: &[&str] = &[
+    "compress_gzip",
+    "compress_gunzip",
+    "compress_zlib",
+    "compress_unzlib",
+    "config_parse_toml",
+    "config_parse_ini",
+    "config_list_items",
+    "csv_parse",
+    "file_read_bytes",
+    "file_read_lines",
+    "fs_glob",
+    "fs_list_dir",
+    "regex_find_all",
+    "regex_captures",
+    "regex_split",
+    "to_bytes_be",
+    "to_bytes_le",
+];
 
 // Loop context for break/continue
 struct LoopContext<'ctx> {
@@ -15,6 +56,13 @@ struct LoopContext<'ctx> {
     break_block: BasicBlock<'ctx>,
 }
 
+// Cache state for a @memoize function: the hidden global dict and the key built on entry
+#[derive(Clone, Copy)]
+struct MemoState<'ctx> {
+    cache_global: PointerValue<'ctx>,
+    key_alloca: PointerValue<'ctx>,
+}
+
 pub struct CodeGen<'ctx> {
     context: &'ctx Context,
     module: Module<'ctx>,
@@ -22,12 +70,22 @@ pub struct CodeGen<'ctx> {
     variables: HashMap<String, (PointerValue<'ctx>, BasicTypeEnum<'ctx>, Type)>, // Added AST Type
     functions: HashMap<String, FunctionValue<'ctx>>,
     function_params: HashMap<String, Vec<Parameter>>,  // Store function parameters for named args/defaults
+    function_return_types: HashMap<String, Type>,
+    builtin_return_types: HashMap<String, Type>, // As registered by the type checker
     current_function: Option<FunctionValue<'ctx>>,
+    current_return_type: Option<Type>, // Declared return type of the function being compiled
+    handled_exceptions: Vec<PointerValue<'ctx>>, // Exceptions of the enclosing except blocks, innermost last
     class_types: HashMap<String, StructType<'ctx>>,
     class_fields: HashMap<String, Vec<String>>, // class_name -> field names in order
     class_field_types: HashMap<String, Vec<Type>>, // class_name -> field types in order
+    class_field_defaults: HashMap<String, Vec<Option<Expression>>>, // class_name -> field default values in order
+    class_bases: HashMap<String, String>, // class_name -> base class name
+    vtable_slots: HashMap<String, Vec<String>>, // class_name -> method names in vtable order
     current_class: Option<String>, // Track current class being compiled
     loop_stack: Vec<LoopContext<'ctx>>, // Stack of loop contexts for break/continue
+    // RC variables declared in the current function, which it releases on return.
+    // Parameters, loop variables and REPL globals are borrowed
+    owned_variables: HashSet<String>,
     // RC Optimization: track variables that have been moved (ownership transferred)
     moved_variables: HashSet<String>,
     // RC Optimization: track remaining statements in current scope for last-use analysis
@@ -39,8 +97,18 @@ pub struct CodeGen<'ctx> {
     // RC Optimization Phase 4b: track loop-invariant variables
     loop_nesting_depth: usize,
     loop_invariant_variables: HashSet<String>,
+    // Loop-invariant code motion: `.length` values computed once before a while loop
+    hoisted_lengths: HashMap<String, BasicValueEnum<'ctx>>,
     // REPL: global variables that persist across function scopes
     repl_globals: HashSet<String>,
+    // Imported modules, and which function a call reaches
+    names: FunctionNames,
+    // Module whose file the statement being compiled comes from (None for the program)
+    current_module: Option<String>,
+    // @memoize: cache state for the function currently being compiled
+    memo_state: Option<MemoState<'ctx>>,
+    // --checked-overflow: raise OverflowError instead of wrapping on int +, -, *
+    checked_overflow: bool,
     // Debug info
     debug_builder: DebugInfoBuilder<'ctx>,
     compile_unit: DICompileUnit<'ctx>,
@@ -53,13 +121,29 @@ impl<'ctx> CodeGen<'ctx> {
         let module = context.create_module(module_name);
         let builder = context.create_builder();
 
+        // Identify the compiler in the output (the .comment section on ELF)
+        let ident = context.metadata_node(&[context
+            .metadata_string(concat!("WadeScript version ", env!("CARGO_PKG_VERSION")))
+            .into()]);
+        module.add_global_metadata("llvm.ident", &ident).unwrap();
+
+        // Absolute paths in debug info and exceptions, so the runtime can show
+        // the source line of an error no matter where the program is run from
+        let source_path = std::fs::canonicalize(source_file)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| source_file.to_string());
+        let (file_name, directory) = match source_path.rsplit_once('/') {
+            Some((directory, file_name)) if !directory.is_empty() => (file_name, directory),
+            _ => (source_path.as_str(), "."),
+        };
+
         // Create debug info builder
         let (debug_builder, compile_unit) = module.create_debug_info_builder(
             true, // allow_unresolved
             DWARFSourceLanguage::C, // closest to WadeScript
-            source_file,
-            ".",
-            "WadeScript Compiler",
+            file_name,
+            directory,
+            concat!("WadeScript Compiler ", env!("CARGO_PKG_VERSION")),
             false, // is_optimized
             "",
             0,
@@ -79,26 +163,97 @@ impl<'ctx> CodeGen<'ctx> {
             variables: HashMap::new(),
             functions: HashMap::new(),
             function_params: HashMap::new(),
+            function_return_types: HashMap::new(),
+            builtin_return_types: TypeChecker::builtin_return_types(),
             current_function: None,
+            current_return_type: None,
+            handled_exceptions: Vec::new(),
             class_types: HashMap::new(),
             class_fields: HashMap::new(),
             class_field_types: HashMap::new(),
+            class_field_defaults: HashMap::new(),
+            class_bases: HashMap::new(),
+            vtable_slots: HashMap::new(),
             current_class: None,
             loop_stack: Vec::new(),
+            owned_variables: HashSet::new(),
             moved_variables: HashSet::new(),
             remaining_statements: Vec::new(),
             non_escaping_variables: HashSet::new(),
             pure_functions: HashSet::new(),
             loop_nesting_depth: 0,
             loop_invariant_variables: HashSet::new(),
+            hoisted_lengths: HashMap::new(),
             repl_globals: HashSet::new(),
+            names: FunctionNames::default(),
+            current_module: None,
+            memo_state: None,
+            checked_overflow: false,
             debug_builder,
             compile_unit,
-            source_file: source_file.to_string(),
+            source_file: source_path,
             current_debug_scope: None,
         }
     }
 
+    /// Enable checked integer arithmetic (llvm.s*.with.overflow + OverflowError)
+    pub fn set_checked_overflow(&mut self, enabled: bool) {
+        self.checked_overflow = enabled;
+    }
+
+    /// Remove functions that are unreachable from `main` (e.g. unused std imports).
+    /// Every defined function except `main` is internalized so LLVM's GlobalDCE can drop it.
+    pub fn eliminate_dead_functions(&self, target_machine: &TargetMachine) -> Result<(), String> {
+        let has_main = self.module.get_function("main")
+            .map(|f| f.count_basic_blocks() > 0)
+            .unwrap_or(false);
+        if !has_main {
+            // Nothing to anchor reachability on; keep everything
+            return Ok(());
+        }
+
+        for function in self.module.get_functions() {
+            if function.count_basic_blocks() > 0 && function.get_name().to_bytes() != b"main" {
+                function.set_linkage(Linkage::Internal);
+            }
+        }
+
+        self.module
+            .run_passes("globaldce", target_machine, PassBuilderOptions::create())
+            .map_err(|e| format!("Dead function elimination failed: {}", e))
+    }
+
+    /// Run LLVM's `-O<level>` pipeline over the module (levels 1-3). Traces of
+    /// an optimized program come from the source map written next to it (see
+    /// source_map.rs) rather than from the call stack codegen maintains.
+    pub fn optimize(&self, level: u32, target_machine: &TargetMachine) -> Result<(), String> {
+        self.module
+            .run_passes(&format!("default<O{}>", level), target_machine, PassBuilderOptions::create())
+            .map_err(|e| format!("Optimization failed: {}", e))
+    }
+
+    /// Hide everything in a library's module except its exported functions, so
+    /// its helpers and the std functions compiled into it can't clash with the
+    /// program that links it, and drop what the exports never reach.
+    pub fn internalize_library(&self, exported: &[String], target_machine: &TargetMachine) -> Result<(), String> {
+        let exported: HashSet<String> = exported.iter().map(|name| format!("ws_{}", name)).collect();
+        for function in self.module.get_functions() {
+            let name = function.get_name().to_string_lossy();
+            if function.count_basic_blocks() > 0 && !exported.contains(name.as_ref()) {
+                function.set_linkage(Linkage::Internal);
+            }
+        }
+        for global in self.module.get_globals() {
+            if global.get_initializer().is_some() && global.get_linkage() == Linkage::External {
+                global.set_linkage(Linkage::Internal);
+            }
+        }
+
+        self.module
+            .run_passes("globaldce", target_machine, PassBuilderOptions::create())
+            .map_err(|e| format!("Dead function elimination failed: {}", e))
+    }
+
     pub fn get_module(&self) -> &Module<'ctx> {
         &self.module
     }
@@ -116,14 +271,50 @@ impl<'ctx> CodeGen<'ctx> {
         self.declare_list_functions();
         self.declare_dict_functions();
         self.declare_string_functions();
+        self.declare_float_functions();
+        self.declare_bigint_functions();
+        self.declare_decimal_functions();
         self.declare_io_functions();
         self.declare_cli_functions();
         self.declare_http_functions();
+        self.declare_smtp_functions();
+        self.declare_compress_functions();
+        self.declare_random_functions();
+        self.declare_fs_functions();
+        self.declare_regex_functions();
+        self.declare_bytes_functions();
+        self.declare_time_functions();
+        self.declare_os_functions();
+        self.declare_process_functions();
+        self.declare_path_functions();
+        self.declare_csv_functions();
+        self.declare_config_functions();
+        self.declare_net_functions();
         self.declare_runtime_error_functions();
         self.mark_builtin_pure_functions();
     }
 
-    /// Declare an external user-defined function (for REPL cross-input calls)
+    /// Names of the runtime and C library functions codegen calls, which the
+    /// program's functions and classes can't take (`TypeChecker::set_reserved_names`)
+    pub fn runtime_function_names() -> HashSet<String> {
+        static NAMES: OnceLock<HashSet<String>> = OnceLock::new();
+        NAMES
+            .get_or_init(|| {
+                let context = Context::create();
+                let mut codegen = CodeGen::new(&context, "runtime_names", "<runtime>");
+                codegen.declare_runtime_functions();
+                codegen.functions.into_keys().collect()
+            })
+            .clone()
+    }
+
+    /// The modules a REPL session has loaded, for calls to their functions
+    pub fn set_function_names(&mut self, names: FunctionNames) {
+        self.names = names;
+    }
+
+    /// Declare an external user-defined function (for REPL cross-input calls
+    /// and functions from compiled libraries)
     pub fn declare_external_function(&mut self, name: &str, param_types: &[Type], return_type: &Type) {
         let llvm_param_types: Vec<BasicMetadataTypeEnum> = param_types
             .iter()
@@ -177,19 +368,25 @@ impl<'ctx> CodeGen<'ctx> {
 
     /// Compile a statement for REPL (simplified, no function wrapping)
     pub fn compile_statement_repl(&mut self, stmt: &Statement) -> Result<(), String> {
+        self.current_module = self.names.statement_module(stmt);
         self.compile_statement(stmt)
     }
 
     fn get_llvm_type(&self, ws_type: &Type) -> BasicTypeEnum<'ctx> {
         match ws_type {
             Type::Int => self.context.i64_type().as_basic_type_enum(),
+            Type::I32 => self.context.i32_type().as_basic_type_enum(),
+            Type::I16 => self.context.i16_type().as_basic_type_enum(),
+            Type::U8 => self.context.i8_type().as_basic_type_enum(),
             Type::Float => self.context.f64_type().as_basic_type_enum(),
             Type::Bool => self.context.bool_type().as_basic_type_enum(),
-            Type::Str => self
+            // Bigints are handles to runtime-owned values, like strings
+            Type::Str | Type::BigInt | Type::Decimal => self
                 .context
                 .ptr_type(AddressSpace::default())
                 .as_basic_type_enum(),
             Type::Void => self.context.i64_type().as_basic_type_enum(),
+            Type::Inferred => unreachable!("let types are resolved by the type checker"),
             Type::Array(elem_type, size) => {
                 let elem_llvm_type = self.get_llvm_type(elem_type);
                 elem_llvm_type
@@ -229,6 +426,16 @@ impl<'ctx> CodeGen<'ctx> {
                 .context
                 .ptr_type(AddressSpace::default())
                 .as_basic_type_enum(),
+            Type::Result(ok_type, err_type) => {
+                // Results are passed by value as { i1 is_ok, value, error }, like tuples
+                self.context
+                    .struct_type(&[
+                        self.context.bool_type().as_basic_type_enum(),
+                        self.get_llvm_type(ok_type),
+                        self.get_llvm_type(err_type),
+                    ], false)
+                    .as_basic_type_enum()
+            }
             Type::Tuple(types) => {
                 // Tuples are represented as LLVM struct types
                 let field_types: Vec<BasicTypeEnum> = types
@@ -244,7 +451,363 @@ impl<'ctx> CodeGen<'ctx> {
     fn is_rc_type(&self, ws_type: &Type) -> bool {
         // Note: Str excluded for now because string literals are global constants
         // We'll add proper string RC later (need to distinguish literals from allocated strings)
-        matches!(ws_type, Type::List(_) | Type::Dict(_, _) | Type::Custom(_))
+        matches!(ws_type, Type::List(_) | Type::Dict(_, _) | Type::Custom(_) | Type::BigInt | Type::Decimal)
+            || Self::is_boxed_optional(ws_type)
+    }
+
+    // Helper: `int?`, `float?` and `bool?`, whose values live in an 8-byte RC box
+    // (see coerce_to)
+    fn is_boxed_optional(ws_type: &Type) -> bool {
+        matches!(ws_type, Type::Optional(inner) if matches!(inner.as_ref(), Type::Int | Type::Float | Type::Bool))
+    }
+
+    // Helper: Declared type of an expression, for the cases codegen can resolve on its
+    // own: variables, class fields (following `a.b` / `a?.b` chains), dict, list and
+    // tuple lookups, `??` and `?`, and calls to user-defined and builtin functions
+    fn declared_type(&self, expr: &Expression) -> Option<Type> {
+        match expr {
+            Expression::Variable(name) => Some(self.variables.get(name)?.2.clone()),
+            Expression::StringLiteral(_) | Expression::FString { .. } => Some(Type::Str),
+            // A list literal holds what its first element is
+            Expression::ListLiteral { elements } => Some(Type::List(Box::new(self.declared_type(elements.first()?)?))),
+            Expression::MemberAccess { object, member } | Expression::OptionalMemberAccess { object, member } => {
+                let class_name = self.optional_chain_class(object)?;
+                let field_idx = self.class_fields.get(&class_name)?.iter().position(|f| f == member)?;
+                self.class_field_types.get(&class_name)?.get(field_idx).cloned()
+            }
+            Expression::Call { callee, .. } => match callee.as_ref() {
+                Expression::Variable(name) if self.class_types.contains_key(name) => Some(Type::Custom(name.clone())),
+                // A call that reaches an imported module's function
+                Expression::Variable(name) if self.resolve_function(name) != *name => {
+                    self.function_return_types.get(&self.resolve_function(name)).cloned()
+                }
+                Expression::Variable(name) if name == "checked_add" || name == "checked_mul" => Some(Type::Optional(Box::new(Type::Int))),
+                Expression::Variable(name) if name == "truthy" => Some(Type::Bool),
+                Expression::Variable(name) if name == "int" => Some(Type::Int),
+                Expression::Variable(name) if name == "float" => Some(Type::Float),
+                Expression::Variable(name) if name == "str" || name == "format" || name == "input" => Some(Type::Str),
+                Expression::Variable(name) if name == "read_line" && !self.functions.contains_key(name) => {
+                    Some(Type::Optional(Box::new(Type::Str)))
+                }
+                Expression::Variable(name) if name == "to_bytes_be" || name == "to_bytes_le" => Some(Type::List(Box::new(Type::U8))),
+                Expression::Variable(name) => {
+                    self.function_return_types.get(name).or_else(|| self.builtin_return_types.get(name)).cloned()
+                }
+                _ => None,
+            },
+            // Dict values and list elements are read back as their declared type
+            Expression::Index { object, .. } => match self.declared_type(object)? {
+                Type::Dict(_, val_type) => Some(*val_type),
+                Type::List(elem_type) => Some(*elem_type),
+                Type::Str => Some(Type::Str),
+                _ => None,
+            },
+            Expression::Slice { object, .. } => self.declared_type(object).filter(|t| matches!(t, Type::List(_) | Type::Str)),
+            Expression::TupleIndex { tuple, index, .. } => match self.declared_type(tuple)? {
+                Type::Tuple(types) => types.get(*index).cloned(),
+                _ => None,
+            },
+            // `value ?? default` is what the Optional holds
+            Expression::NullCoalesce { value, default } => match self.declared_type(value) {
+                Some(Type::Optional(inner)) => Some(*inner),
+                _ => self.declared_type(default),
+            },
+            // `value?` is the Result's Ok value
+            Expression::Propagate { value } => match self.declared_type(value)? {
+                Type::Result(ok_type, _) => Some(*ok_type),
+                _ => None,
+            },
+            Expression::Binary { left, op: BinaryOp::Add, right } => [left, right]
+                .into_iter()
+                .find_map(|side| self.declared_type(side).filter(|t| matches!(t, Type::List(_)))),
+            Expression::MethodCall { object, method, args, .. } => match self.declared_type(object) {
+                Some(Type::Custom(class_name)) => self.method_return_type(&class_name, method),
+                Some(Type::List(elem_type)) if matches!(method.as_str(), "pop" | "get" | "remove_at") => Some(*elem_type),
+                Some(Type::List(elem_type)) if method == "filter" => Some(Type::List(elem_type)),
+                Some(Type::Dict(key_type, _)) if method == "keys" => Some(Type::List(key_type)),
+                Some(Type::Dict(_, val_type)) if method == "values" => Some(Type::List(val_type)),
+                Some(Type::Dict(key_type, val_type)) if method == "items" => {
+                    Some(Type::List(Box::new(Type::Tuple(vec![*key_type, *val_type]))))
+                }
+                Some(Type::Dict(_, val_type)) if method == "pop" || method == "get" => Some(*val_type),
+                Some(Type::Dict(_, _)) if method == "remove" => Some(Type::Bool),
+                Some(Type::Str) if method == "split" || method == "chars" => Some(Type::List(Box::new(Type::Str))),
+                Some(Type::Str) if matches!(method.as_str(), "upper" | "lower" | "join" | "strip" | "lstrip" | "rstrip" | "replace") => {
+                    Some(Type::Str)
+                }
+                Some(Type::Str) if method == "find" => Some(Type::Int),
+                Some(Type::Str) if matches!(method.as_str(), "startswith" | "endswith" | "contains") => Some(Type::Bool),
+                Some(Type::List(_)) if method == "map" || method == "reduce" => match args.first() {
+                    Some(Expression::Variable(name)) if method == "map" => {
+                        Some(Type::List(Box::new(self.function_return_types.get(&self.resolve_function(name))?.clone())))
+                    }
+                    Some(Expression::Variable(name)) => self.function_return_types.get(&self.resolve_function(name)).cloned(),
+                    _ => None,
+                },
+                // module.function()
+                None => match object.as_ref() {
+                    Expression::Variable(module) => self.function_return_types.get(&self.module_function(module, method)?).cloned(),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Helper: The runtime-backed number type (bigint or decimal) an expression evaluates
+    // to, if any. These are pointers like strings, so operators on them are routed by
+    // the WadeScript type
+    fn runtime_number_type(&self, expr: &Expression) -> Option<Type> {
+        let ws_type = match expr {
+            Expression::Binary { left, op, right } => {
+                if !matches!(
+                    op,
+                    BinaryOp::Add
+                        | BinaryOp::Subtract
+                        | BinaryOp::Multiply
+                        | BinaryOp::Divide
+                        | BinaryOp::FloorDivide
+                        | BinaryOp::Modulo
+                        | BinaryOp::Power
+                ) {
+                    return None;
+                }
+                self.runtime_number_type(left).or_else(|| self.runtime_number_type(right))?
+            }
+            Expression::Unary { op: UnaryOp::Negate, operand } => return self.runtime_number_type(operand),
+            Expression::Cast { target, .. } => target.clone(),
+            Expression::DecimalLiteral(_) => Type::Decimal,
+            Expression::Call { callee, .. } => match callee.as_ref() {
+                Expression::Variable(name) if !self.function_return_types.contains_key(&self.resolve_function(name)) => match name.as_str() {
+                    "parse_bigint" | "pow_mod" => Type::BigInt,
+                    "parse_decimal" => Type::Decimal,
+                    _ => return None,
+                },
+                _ => self.declared_type(expr)?,
+            },
+            Expression::MethodCall { object, method, .. } if method == "round" => self.runtime_number_type(object)?,
+            _ => self.declared_type(expr)?,
+        };
+        matches!(ws_type, Type::BigInt | Type::Decimal).then_some(ws_type)
+    }
+
+    // Prefix of the runtime functions for a bigint or decimal: bigint_add, decimal_add, ...
+    fn runtime_number_prefix(ws_type: &Type) -> &'static str {
+        if *ws_type == Type::Decimal {
+            "decimal"
+        } else {
+            "bigint"
+        }
+    }
+
+    // Helper: Call a runtime function that returns a value
+    fn call_runtime(&self, name: &str, args: &[BasicMetadataValueEnum<'ctx>]) -> BasicValueEnum<'ctx> {
+        let function = *self.functions.get(name).unwrap();
+        self.builder
+            .build_call(function, args, name)
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+    }
+
+    // Helper: LLVM symbol of a user function or method. Everything user-defined is
+    // under `ws_`, so a function named `free` or `strlen` can't bind to the C
+    // library's; an imported file's functions are already `module.name`, and methods
+    // (and constructors, `ws_<Class>`) are qualified by their class. `main` stays the
+    // C entry point
+    fn symbol_name(&self, name: &str) -> String {
+        match &self.current_class {
+            Some(class_name) => format!("ws_{}.{}", class_name, name),
+            None if name == "main" => name.to_string(),
+            None => format!("ws_{}", name),
+        }
+    }
+
+    // Helper: The function an unqualified call to `name` reaches from the module
+    // being compiled
+    fn resolve_function(&self, name: &str) -> String {
+        self.names
+            .resolve(self.current_module.as_deref(), name)
+            .unwrap_or_else(|_| name.to_string())
+    }
+
+    // Helper: The function `module.name` refers to, when `module` is an imported module
+    fn module_function(&self, module: &str, name: &str) -> Option<String> {
+        if !self.names.modules().contains_key(module) {
+            return None;
+        }
+        self.names.resolve_qualified(module, name).ok()
+    }
+
+    // Convert an int (or bool) value to a bigint or decimal
+    fn int_to_runtime_number(&self, value: BasicValueEnum<'ctx>, ws_type: &Type) -> BasicValueEnum<'ctx> {
+        let int_val = self.widen_to(value, self.context.i64_type().as_basic_type_enum());
+        let from_int = format!("{}_from_int", Self::runtime_number_prefix(ws_type));
+        self.call_runtime(&from_int, &[int_val.into()])
+    }
+
+    // Compile an operator with a bigint or decimal operand as a runtime call. Integer
+    // operands are converted first; comparisons go through the three-way `_cmp`
+    fn compile_runtime_number_binary(
+        &mut self,
+        left: &Expression,
+        op: &BinaryOp,
+        right: &Expression,
+        ws_type: &Type,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let prefix = Self::runtime_number_prefix(ws_type);
+        // Operands made for this operator alone (converted ints, the results of other
+        // operators and calls) are released once it has run
+        let mut left_val = self.compile_expression(left)?;
+        let left_is_temporary = left_val.is_int_value() || !self.is_borrowed_value(left);
+        if left_val.is_int_value() {
+            left_val = self.int_to_runtime_number(left_val, ws_type);
+        }
+        let mut right_val = self.compile_expression(right)?;
+
+        // The exponent stays an int
+        if *op == BinaryOp::Power {
+            let exponent = self.widen_to(right_val, self.context.i64_type().as_basic_type_enum());
+            let result = self.call_runtime("bigint_pow", &[left_val.into(), exponent.into()]);
+            if left_is_temporary {
+                self.build_rc_release_nullable(left_val.into_pointer_value(), ws_type);
+            }
+            return Ok(result);
+        }
+        let right_is_temporary = right_val.is_int_value() || !self.is_borrowed_value(right);
+        if right_val.is_int_value() {
+            right_val = self.int_to_runtime_number(right_val, ws_type);
+        }
+
+        let operation = match op {
+            BinaryOp::Add => "add",
+            BinaryOp::Subtract => "sub",
+            BinaryOp::Multiply => "mul",
+            BinaryOp::Divide | BinaryOp::FloorDivide => "div",
+            BinaryOp::Modulo => "rem",
+            _ => "cmp",
+        };
+        let result = self.call_runtime(&format!("{}_{}", prefix, operation), &[left_val.into(), right_val.into()]);
+        for (value, is_temporary) in [(left_val, left_is_temporary), (right_val, right_is_temporary)] {
+            if is_temporary {
+                self.build_rc_release_nullable(value.into_pointer_value(), ws_type);
+            }
+        }
+        if operation != "cmp" {
+            return Ok(result);
+        }
+
+        let predicate = match op {
+            BinaryOp::Equal => IntPredicate::EQ,
+            BinaryOp::NotEqual => IntPredicate::NE,
+            BinaryOp::Less => IntPredicate::SLT,
+            BinaryOp::Greater => IntPredicate::SGT,
+            BinaryOp::LessEqual => IntPredicate::SLE,
+            BinaryOp::GreaterEqual => IntPredicate::SGE,
+            _ => return Err(format!("Invalid {} operator {:?}", ws_type, op)),
+        };
+        let zero = self.context.i64_type().const_zero();
+        Ok(self.builder
+            .build_int_compare(predicate, result.into_int_value(), zero, "cmp_result")
+            .unwrap()
+            .as_basic_value_enum())
+    }
+
+    // Helper: Set the debug location (the line shown in stack traces) of the
+    // instructions that follow
+    fn set_debug_line(&self, line: usize) {
+        let scope = if let Some(func_scope) = self.current_debug_scope {
+            func_scope.as_debug_info_scope()
+        } else {
+            self.compile_unit.get_file().as_debug_info_scope()
+        };
+        let debug_loc = self.debug_builder.create_debug_location(self.context, line as u32, 0, scope, None);
+        self.builder.set_current_debug_location(debug_loc);
+    }
+
+    // Helper: A variable's stack slot in the entry block of the current function, so
+    // it dominates every use (the scope cleanup at the function's exit reads variables
+    // declared inside branches). RC slots start out null, so that cleanup skips a
+    // variable whose declaration never ran
+    fn build_entry_alloca(&self, var_type: BasicTypeEnum<'ctx>, name: &str, is_rc: bool) -> PointerValue<'ctx> {
+        let Some(entry) = self.current_function.and_then(|f| f.get_first_basic_block()) else {
+            return self.builder.build_alloca(var_type, name).unwrap();
+        };
+        let entry_builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => entry_builder.position_before(&first),
+            None => entry_builder.position_at_end(entry),
+        }
+        let ptr = entry_builder.build_alloca(var_type, name).unwrap();
+        if is_rc {
+            let null_ptr = self.context.ptr_type(AddressSpace::default()).const_null();
+            entry_builder.build_store(ptr, null_ptr).unwrap();
+        }
+        ptr
+    }
+
+    // Helper: `xs` for a `range(xs.length)` iterable over a list
+    fn range_over_list_length<'e>(&self, iterable: &'e Expression) -> Option<&'e Expression> {
+        let Expression::Call { callee, args, .. } = iterable else {
+            return None;
+        };
+        if !matches!(&**callee, Expression::Variable(name) if name == "range") || args.len() != 1 {
+            return None;
+        }
+        match &args[0] {
+            Expression::MemberAccess { object, member } if member == "length" => {
+                matches!(self.declared_type(object), Some(Type::List(_))).then_some(&**object)
+            }
+            _ => None,
+        }
+    }
+
+    // Helper: Class of the object in `object?.field` (or `object.field`)
+    fn optional_chain_class(&self, expr: &Expression) -> Option<String> {
+        match self.declared_type(expr)? {
+            Type::Custom(class_name) => Some(class_name),
+            Type::Optional(inner) => match *inner {
+                Type::Custom(class_name) => Some(class_name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Helper: Class of an expression that evaluates to an object
+    fn object_class(&self, expr: &Expression) -> Option<String> {
+        match self.declared_type(expr)? {
+            Type::Custom(class_name) if self.class_types.contains_key(&class_name) => Some(class_name),
+            _ => None,
+        }
+    }
+
+    // Helper: Class of an object that print() and f-strings format with its to_string()
+    fn printable_class(&self, expr: &Expression) -> Option<String> {
+        match self.declared_type(expr) {
+            Some(Type::Custom(class_name)) => Some(class_name),
+            Some(_) => None,
+            // A constructor call, e.g. print(Point(1, 2))
+            None => match expr {
+                Expression::Call { callee, .. } => match callee.as_ref() {
+                    Expression::Variable(name) if self.class_types.contains_key(name) => Some(name.clone()),
+                    _ => None,
+                },
+                _ => None,
+            },
+        }
+    }
+
+    // Helper: Call an object's to_string() through its vtable, so a subclass
+    // override is the one that runs
+    fn build_to_string_call(&self, class_name: &str, object: BasicValueEnum<'ctx>) -> Result<BasicValueEnum<'ctx>, String> {
+        let to_string = self
+            .find_method(class_name, "to_string")
+            .ok_or_else(|| format!("Class '{}' has no to_string method", class_name))?;
+        let call = self.build_virtual_call(class_name, "to_string", to_string, &[object.into()]);
+        Ok(call.try_as_basic_value().left().unwrap())
     }
 
     // Helper: Infer WadeScript type from LLVM type (used for tuple unpacking)
@@ -278,39 +841,16 @@ impl<'ctx> CodeGen<'ctx> {
                     false
                 }
             }
-            Expression::MemberAccess { object, member } => {
-                // Check if this is accessing a string field on a class
-                if let Expression::Variable(var_name) = &**object {
-                    if let Some((_ptr, _llvm_type, ast_type)) = self.variables.get(var_name) {
-                        if let Type::Custom(class_name) = ast_type {
-                            // Look up field type in class definition
-                            if let Some(field_names) = self.class_fields.get(class_name) {
-                                if let Some(field_idx) = field_names.iter().position(|f| f == member) {
-                                    if let Some(field_types) = self.class_field_types.get(class_name) {
-                                        if let Some(field_type) = field_types.get(field_idx) {
-                                            return field_type == &Type::Str;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                false
-            }
             Expression::FString { .. } => true,
-            Expression::MethodCall { object, method, .. } => {
-                // String methods return strings
-                if let Expression::Variable(var_name) = &**object {
-                    if let Some((_ptr, _llvm_type, ast_type)) = self.variables.get(var_name) {
-                        if ast_type == &Type::Str {
-                            return matches!(method.as_str(), "upper" | "lower");
-                        }
-                    }
-                }
-                false
+            // String methods that return strings, on any string (`", ".join(...)`, `s.strip().lower()`)
+            Expression::MethodCall { object, method, .. }
+                if matches!(method.as_str(), "upper" | "lower" | "join" | "strip" | "lstrip" | "rstrip" | "replace")
+                    && self.is_string_expression(object) =>
+            {
+                true
             }
-            _ => false,
+            // A field (possibly at the end of a chain like `a.b.name`), element or call result
+            _ => self.declared_type(expr) == Some(Type::Str),
         }
     }
 
@@ -321,9 +861,9 @@ impl<'ctx> CodeGen<'ctx> {
             Expression::Call { callee, args, line: _, .. } => {
                 // Check if this is a known pure function
                 let is_pure = if let Expression::Variable(func_name) = &**callee {
-                    self.pure_functions.contains(func_name)
+                    self.pure_functions.contains(&self.resolve_function(func_name))
                 } else if let Expression::MemberAccess { member, .. } = &**callee {
-                    // Module.function() call
+                    // Module.function() call: the module's own functions aren't pure
                     self.pure_functions.contains(member)
                 } else {
                     false
@@ -340,30 +880,36 @@ impl<'ctx> CodeGen<'ctx> {
             // Returning the variable causes escape
             Expression::Variable(name) if name == var_name => false, // Handled separately in Return
             // Method calls on the variable don't cause escape (in-place operations - Phase 4)
-            Expression::MethodCall { object: _, method, args } => {
+            Expression::MethodCall { object: _, method, args, named_args } => {
                 // Check if method is pure (e.g., list.length, list.get)
                 // If method is pure, it doesn't cause the object to escape
                 let is_pure = self.pure_functions.contains(method);
+                let mut all_args = args.iter().chain(named_args.iter().map(|(_, arg)| arg));
 
                 if is_pure {
                     // Pure method - only check if args escape
-                    args.iter().any(|arg| self.expression_uses_variable(arg, var_name))
+                    all_args.any(|arg| self.expression_uses_variable(arg, var_name))
                 } else {
                     // Unknown method - conservatively assume object doesn't escape (in-place)
                     // but args might
-                    args.iter().any(|arg| self.expression_uses_variable(arg, var_name))
+                    all_args.any(|arg| self.expression_uses_variable(arg, var_name))
                 }
             }
+            Expression::SuperCall { args, .. } => {
+                args.iter().any(|arg| self.expression_uses_variable(arg, var_name))
+            }
             // Assignment doesn't cause escape (just moves ownership)
             Expression::Assignment { value, .. } => {
                 self.expression_escapes_variable(value, var_name)
             }
             // Binary/Unary operations don't cause escape
-            Expression::Binary { left, right, .. } => {
+            Expression::Binary { left, right, .. } | Expression::NullCoalesce { value: left, default: right } => {
                 self.expression_escapes_variable(left, var_name) ||
                 self.expression_escapes_variable(right, var_name)
             }
-            Expression::Unary { operand, .. } => {
+            Expression::Unary { operand, .. }
+            | Expression::Cast { expr: operand, .. }
+            | Expression::Propagate { value: operand } => {
                 self.expression_escapes_variable(operand, var_name)
             }
             // Indexing doesn't cause escape
@@ -375,7 +921,12 @@ impl<'ctx> CodeGen<'ctx> {
                 self.expression_escapes_variable(index, var_name) ||
                 self.expression_escapes_variable(value, var_name)
             }
-            Expression::MemberAccess { object, .. } => {
+            // Storing the variable in a field outlives the scope
+            Expression::FieldAssignment { object, value, .. } => {
+                self.expression_escapes_variable(object, var_name) ||
+                self.expression_uses_variable(value, var_name)
+            }
+            Expression::MemberAccess { object, .. } | Expression::OptionalMemberAccess { object, .. } => {
                 self.expression_escapes_variable(object, var_name)
             }
             // Container literals don't cause escape (items are copied)
@@ -433,11 +984,13 @@ impl<'ctx> CodeGen<'ctx> {
     fn expression_uses_variable(&self, expr: &Expression, var_name: &str) -> bool {
         match expr {
             Expression::Variable(name) => name == var_name,
-            Expression::Binary { left, right, .. } => {
+            Expression::Binary { left, right, .. } | Expression::NullCoalesce { value: left, default: right } => {
                 self.expression_uses_variable(left, var_name) ||
                 self.expression_uses_variable(right, var_name)
             }
-            Expression::Unary { operand, .. } => {
+            Expression::Unary { operand, .. }
+            | Expression::Cast { expr: operand, .. }
+            | Expression::Propagate { value: operand } => {
                 self.expression_uses_variable(operand, var_name)
             }
             Expression::Call { args, .. } => {
@@ -454,11 +1007,20 @@ impl<'ctx> CodeGen<'ctx> {
                 self.expression_uses_variable(index, var_name) ||
                 self.expression_uses_variable(value, var_name)
             }
-            Expression::MethodCall { object, args, .. } => {
+            Expression::FieldAssignment { object, value, .. } => {
                 self.expression_uses_variable(object, var_name) ||
-                args.iter().any(|arg| self.expression_uses_variable(arg, var_name))
+                self.expression_uses_variable(value, var_name)
+            }
+            Expression::MethodCall { object, args, named_args, .. } => {
+                self.expression_uses_variable(object, var_name) ||
+                args.iter().any(|arg| self.expression_uses_variable(arg, var_name)) ||
+                named_args.iter().any(|(_, arg)| self.expression_uses_variable(arg, var_name))
+            }
+            // `super.method()` passes self implicitly
+            Expression::SuperCall { args, .. } => {
+                var_name == "self" || args.iter().any(|arg| self.expression_uses_variable(arg, var_name))
             }
-            Expression::MemberAccess { object, .. } => {
+            Expression::MemberAccess { object, .. } | Expression::OptionalMemberAccess { object, .. } => {
                 self.expression_uses_variable(object, var_name)
             }
             Expression::ListLiteral { elements } => {
@@ -529,9 +1091,11 @@ impl<'ctx> CodeGen<'ctx> {
             Statement::While { body, .. } => {
                 body.iter().any(|s| self.statement_assigns_variable(s, var_name))
             }
-            Statement::For { variable, body, .. } => {
-                // Loop variable is implicitly assigned
-                variable == var_name || body.iter().any(|s| self.statement_assigns_variable(s, var_name))
+            Statement::For { variable, value_variable, body, .. } => {
+                // Loop variables are implicitly assigned
+                variable == var_name
+                    || value_variable.as_deref() == Some(var_name)
+                    || body.iter().any(|s| self.statement_assigns_variable(s, var_name))
             }
             _ => false,
         }
@@ -555,6 +1119,173 @@ impl<'ctx> CodeGen<'ctx> {
         invariant_vars
     }
 
+    // OPTIMIZATION: Loop-invariant code motion for `.length` reads.
+    // Returns the variables whose `.length` appears in a while condition and can't
+    // change while the loop runs, so the length can be computed once before the loop.
+    // Strings are immutable, so only reassignment matters. A list can also be resized
+    // through an alias, so nothing in the body may be able to resize any list.
+    fn invariant_length_reads(&self, condition: &Expression, body: &[Statement]) -> Vec<String> {
+        let mut names = Vec::new();
+        self.collect_length_reads(condition, &mut names);
+        names.retain(|name| {
+            let is_list = match self.variables.get(name) {
+                Some((_, _, Type::Str)) => false,
+                Some((_, _, Type::List(_))) => true,
+                _ => return false,
+            };
+            !self.hoisted_lengths.contains_key(name)
+                && !body.iter().any(|stmt| self.statement_may_change_length(stmt, name, is_list))
+        });
+        names
+    }
+
+    // Helper: Variables `v` with `v.length` in an expression
+    fn collect_length_reads(&self, expr: &Expression, names: &mut Vec<String>) {
+        match expr {
+            Expression::MemberAccess { object, member } if member == "length" => {
+                if let Expression::Variable(name) = &**object {
+                    if !names.contains(name) {
+                        names.push(name.clone());
+                    }
+                }
+            }
+            Expression::Binary { left, right, .. } => {
+                self.collect_length_reads(left, names);
+                self.collect_length_reads(right, names);
+            }
+            Expression::Unary { operand, .. } | Expression::Cast { expr: operand, .. } => {
+                self.collect_length_reads(operand, names);
+            }
+            _ => {}
+        }
+    }
+
+    // Helper: Whether a statement in a loop body might reassign `var_name` or, for a
+    // list, resize it. Conservative: anything not understood counts as a change.
+    fn statement_may_change_length(&self, stmt: &Statement, var_name: &str, is_list: bool) -> bool {
+        let changes_expr = |expr: &Expression| self.expression_may_change_length(expr, var_name, is_list);
+        let changes_block = |body: &[Statement]| {
+            body.iter().any(|s| self.statement_may_change_length(s, var_name, is_list))
+        };
+        match stmt {
+            Statement::VarDecl { name, initializer, .. } => {
+                name == var_name || initializer.as_ref().is_some_and(changes_expr)
+            }
+            Statement::TupleUnpack { names, value } => {
+                names.iter().any(|n| n == var_name) || changes_expr(value)
+            }
+            Statement::If { condition, then_branch, elif_branches, else_branch } => {
+                changes_expr(condition)
+                    || changes_block(then_branch)
+                    || elif_branches.iter().any(|(cond, body)| changes_expr(cond) || changes_block(body))
+                    || else_branch.as_deref().is_some_and(changes_block)
+            }
+            Statement::While { condition, body } => changes_expr(condition) || changes_block(body),
+            Statement::For { variable, value_variable, iterable, body } => {
+                variable == var_name
+                    || value_variable.as_deref() == Some(var_name)
+                    || changes_expr(iterable)
+                    || changes_block(body)
+            }
+            Statement::Try { try_block, except_clauses, finally_block } => {
+                changes_block(try_block)
+                    || except_clauses.iter().any(|clause| {
+                        clause.var_name.as_deref() == Some(var_name) || changes_block(&clause.body)
+                    })
+                    || finally_block.as_deref().is_some_and(changes_block)
+            }
+            Statement::Expression(expr) | Statement::Return(Some(expr)) | Statement::Assert { condition: expr, .. } => {
+                changes_expr(expr)
+            }
+            Statement::Raise { message, cause, .. } => {
+                changes_expr(message) || cause.as_ref().is_some_and(changes_expr)
+            }
+            Statement::Return(None)
+            | Statement::Break
+            | Statement::Continue
+            | Statement::Pass
+            | Statement::Reraise { .. } => false,
+            Statement::FunctionDef { .. } | Statement::ClassDef { .. } | Statement::Import { .. } => true,
+        }
+    }
+
+    // Helper: Expression half of statement_may_change_length
+    fn expression_may_change_length(&self, expr: &Expression, var_name: &str, is_list: bool) -> bool {
+        let changes = |e: &Expression| self.expression_may_change_length(e, var_name, is_list);
+        match expr {
+            Expression::Assignment { target, value } => target == var_name || changes(value),
+            // map(), filter(), reduce() and sort_by() pass the elements to a function,
+            // which could resize a list they refer to, as with a call
+            Expression::MethodCall { object, method, args, named_args }
+                if matches!(method.as_str(), "map" | "filter" | "reduce" | "sort_by") && !self.is_string_expression(object) =>
+            {
+                let reaches_list = !self.repl_globals.is_empty() || self.may_reference_list(object);
+                (is_list && reaches_list) || changes(object) || args.iter().any(changes) || named_args.iter().any(|(_, arg)| changes(arg))
+            }
+            // Any other method could resize a list, except reads and string methods
+            Expression::MethodCall { object, method, args, named_args } => {
+                let read_only = matches!(method.as_str(), "get" | "index_of" | "contains" | "sort" | "reverse" | "shuffle") || self.is_string_expression(object);
+                (is_list && !read_only) || changes(object) || args.iter().any(changes) || named_args.iter().any(|(_, arg)| changes(arg))
+            }
+            Expression::SuperCall { args, .. } => is_list || args.iter().any(changes),
+            // A function can only reach a list that is passed to it (or, in the REPL, a global)
+            Expression::Call { args, named_args, .. } => {
+                let reaches_list = !self.repl_globals.is_empty()
+                    || args.iter().chain(named_args.iter().map(|(_, arg)| arg)).any(|arg| self.may_reference_list(arg));
+                (is_list && reaches_list)
+                    || args.iter().any(changes)
+                    || named_args.iter().any(|(_, arg)| changes(arg))
+            }
+            Expression::Binary { left, right, .. } | Expression::NullCoalesce { value: left, default: right } => {
+                changes(left) || changes(right)
+            }
+            Expression::Unary { operand, .. }
+            | Expression::Cast { expr: operand, .. }
+            | Expression::Propagate { value: operand }
+            | Expression::MemberAccess { object: operand, .. }
+            | Expression::OptionalMemberAccess { object: operand, .. }
+            | Expression::TupleIndex { tuple: operand, .. } => changes(operand),
+            Expression::Index { object, index, .. } => changes(object) || changes(index),
+            Expression::IndexAssignment { index, value, .. } => changes(index) || changes(value),
+            Expression::FieldAssignment { object, value, .. } => changes(object) || changes(value),
+            Expression::ArrayLiteral { elements }
+            | Expression::ListLiteral { elements }
+            | Expression::TupleLiteral { elements }
+            | Expression::FString { expressions: elements, .. } => elements.iter().any(changes),
+            Expression::DictLiteral { pairs } => pairs.iter().any(|(k, v)| changes(k) || changes(v)),
+            Expression::Slice { object, start, end, step, .. } => {
+                changes(object) || [start, end, step].into_iter().flatten().any(|e| changes(e))
+            }
+            Expression::IntLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::DecimalLiteral(_)
+            | Expression::StringLiteral(_)
+            | Expression::BoolLiteral(_)
+            | Expression::NoneLiteral
+            | Expression::Variable(_) => false,
+        }
+    }
+
+    // Helper: Whether an expression might evaluate to a list, or to something holding one
+    fn may_reference_list(&self, expr: &Expression) -> bool {
+        match self.declared_type(expr) {
+            Some(ws_type) => {
+                ws_type.integer_rank().is_none() && !matches!(ws_type, Type::Float | Type::Bool | Type::Str)
+            }
+            None => !matches!(
+                expr,
+                Expression::IntLiteral(_)
+                    | Expression::FloatLiteral(_)
+                    | Expression::StringLiteral(_)
+                    | Expression::BoolLiteral(_)
+                    | Expression::Binary { .. }
+                    | Expression::Unary { .. }
+                    | Expression::Cast { .. }
+                    | Expression::FString { .. }
+            ),
+        }
+    }
+
     // Helper: Collect all variables used in a statement
     fn collect_used_variables(&self, stmt: &Statement, vars: &mut HashSet<String>) {
         match stmt {
@@ -607,11 +1338,13 @@ impl<'ctx> CodeGen<'ctx> {
             Expression::Variable(name) => {
                 vars.insert(name.clone());
             }
-            Expression::Binary { left, right, .. } => {
+            Expression::Binary { left, right, .. } | Expression::NullCoalesce { value: left, default: right } => {
                 self.collect_used_variables_in_expr(left, vars);
                 self.collect_used_variables_in_expr(right, vars);
             }
-            Expression::Unary { operand, .. } => {
+            Expression::Unary { operand, .. }
+            | Expression::Cast { expr: operand, .. }
+            | Expression::Propagate { value: operand } => {
                 self.collect_used_variables_in_expr(operand, vars);
             }
             Expression::Call { args, .. } => {
@@ -632,13 +1365,23 @@ impl<'ctx> CodeGen<'ctx> {
                 self.collect_used_variables_in_expr(index, vars);
                 self.collect_used_variables_in_expr(value, vars);
             }
-            Expression::MethodCall { object, args, .. } => {
+            Expression::FieldAssignment { object, value, .. } => {
+                self.collect_used_variables_in_expr(object, vars);
+                self.collect_used_variables_in_expr(value, vars);
+            }
+            Expression::MethodCall { object, args, named_args, .. } => {
                 self.collect_used_variables_in_expr(object, vars);
+                for arg in args.iter().chain(named_args.iter().map(|(_, arg)| arg)) {
+                    self.collect_used_variables_in_expr(arg, vars);
+                }
+            }
+            Expression::SuperCall { args, .. } => {
+                vars.insert("self".to_string());
                 for arg in args {
                     self.collect_used_variables_in_expr(arg, vars);
                 }
             }
-            Expression::MemberAccess { object, .. } => {
+            Expression::MemberAccess { object, .. } | Expression::OptionalMemberAccess { object, .. } => {
                 self.collect_used_variables_in_expr(object, vars);
             }
             Expression::ListLiteral { elements } => {
@@ -664,26 +1407,14 @@ impl<'ctx> CodeGen<'ctx> {
     // Inline RC retain: increment reference count
     fn build_rc_retain_inline(&self, ptr: PointerValue<'ctx>) {
         let i64_type = self.context.i64_type();
-        let i8_type = self.context.i8_type();
-
-        // ptr points to object data
-        // header is 8 bytes before: [ref_count: i64][data...]
 
-        // Get header pointer: ptr - 8
-        let minus_8 = i64_type.const_int((-8i64) as u64, false);
-        let header = unsafe {
-            self.builder.build_gep(
-                i8_type,
-                ptr,
-                &[minus_8],
-                "rc_header"
-            ).unwrap()
-        };
+        // ptr points to object data; the count lives in the RcHeader before it
+        let ref_count = self.build_rc_ref_count_ptr(ptr);
 
         // Load current count
         let count = self.builder.build_load(
             i64_type,
-            header,
+            ref_count,
             "ref_count"
         ).unwrap().into_int_value();
 
@@ -695,12 +1426,26 @@ impl<'ctx> CodeGen<'ctx> {
         ).unwrap();
 
         // Store back
-        self.builder.build_store(header, new_count).unwrap();
+        self.builder.build_store(ref_count, new_count).unwrap();
+    }
+
+    // Address of an RC object's reference count, from its data pointer
+    fn build_rc_ref_count_ptr(&self, ptr: PointerValue<'ctx>) -> PointerValue<'ctx> {
+        let offset = self.context.i64_type().const_int(RC_REF_COUNT_OFFSET as u64, true);
+        unsafe {
+            self.builder
+                .build_gep(self.context.i8_type(), ptr, &[offset], "rc_ref_count")
+                .unwrap()
+        }
     }
 
-    // Release all RC variables in current scope (except moved/non-escaping variables)
+    // Release the RC variables the current function owns (except moved/non-escaping variables)
     fn release_scope_variables(&self) {
         for (name, (ptr, var_type, ast_type)) in &self.variables {
+            if !self.owned_variables.contains(name) || self.repl_globals.contains(name) {
+                continue;
+            }
+
             // Skip variables that have been moved (ownership transferred)
             if self.moved_variables.contains(name) {
                 continue;
@@ -726,7 +1471,7 @@ impl<'ctx> CodeGen<'ctx> {
                     self.builder.build_conditional_branch(is_null, continue_block, release_block).unwrap();
 
                     self.builder.position_at_end(release_block);
-                    self.build_rc_release_inline(obj_ptr);
+                    self.build_rc_release_inline(obj_ptr, ast_type);
                     self.builder.build_unconditional_branch(continue_block).unwrap();
 
                     self.builder.position_at_end(continue_block);
@@ -735,20 +1480,92 @@ impl<'ctx> CodeGen<'ctx> {
         }
     }
 
+    // Whether an expression reads a reference that something else owns (a variable,
+    // field or element), so storing it needs a retain. Calls, constructors and
+    // literals hand over a new reference, and so do ints, floats and bools: storing
+    // one into an Optional boxes it anew
+    fn is_borrowed_value(&self, expr: &Expression) -> bool {
+        if matches!(self.declared_type(expr), Some(Type::Int | Type::Float | Type::Bool)) {
+            return false;
+        }
+        match expr {
+            Expression::Call { callee, .. } => match callee.as_ref() {
+                Expression::Variable(name) => {
+                    !self.function_return_types.contains_key(&self.resolve_function(name))
+                        && !self.class_types.contains_key(name)
+                        && !OWNED_LIST_BUILTINS.contains(&name.as_str())
+                        // These box their result or build a new number
+                        && !matches!(
+                            name.as_str(),
+                            "checked_add" | "checked_mul" | "parse_bool" | "parse_bigint" | "parse_decimal" | "pow_mod"
+                        )
+                }
+                _ => true,
+            },
+            // pop() and remove_at() hand over the reference the list (or dict) held;
+            // map(), filter() and a dict's keys(), values() and items() build a new list,
+            // reduce() returns what the function did, a dict's get() retains its result,
+            // and a str's split() builds a new list
+            Expression::MethodCall { object, method, .. } => match self.declared_type(object) {
+                Some(Type::Custom(_)) => false,
+                Some(Type::List(_)) => !matches!(method.as_str(), "pop" | "remove_at" | "map" | "filter" | "reduce"),
+                Some(Type::Dict(_, _)) => !matches!(method.as_str(), "keys" | "values" | "items" | "pop" | "get"),
+                Some(Type::Str) => method != "split",
+                // d.round(n) builds a new decimal
+                _ => !(method == "round" && self.runtime_number_type(object) == Some(Type::Decimal)),
+            },
+            Expression::ListLiteral { .. } | Expression::DictLiteral { .. } | Expression::Slice { .. } => false,
+            Expression::IntLiteral(_) | Expression::FloatLiteral(_) | Expression::BoolLiteral(_) => false,
+            Expression::DecimalLiteral(_) => false,
+            // A cast to bigint or decimal converts into a new value unless it already is one
+            Expression::Cast { expr, target: Type::BigInt | Type::Decimal } => {
+                self.runtime_number_type(expr).is_some() && self.is_borrowed_value(expr)
+            }
+            // Operators build new values (xs + ys a new list)
+            Expression::Binary { .. } | Expression::Unary { .. } => false,
+            _ => true,
+        }
+    }
+
+    // Release an RC value that may be null
+    fn build_rc_release_nullable(&self, ptr: PointerValue<'ctx>, ast_type: &Type) {
+        let function = self.current_function.unwrap();
+        let is_null = self.builder.build_is_null(ptr, "is_null").unwrap();
+        let release_block = self.context.append_basic_block(function, "rc_release");
+        let continue_block = self.context.append_basic_block(function, "rc_released");
+        self.builder.build_conditional_branch(is_null, continue_block, release_block).unwrap();
+
+        self.builder.position_at_end(release_block);
+        self.build_rc_release_inline(ptr, ast_type);
+        self.builder.build_unconditional_branch(continue_block).unwrap();
+
+        self.builder.position_at_end(continue_block);
+    }
+
+    // Retain an RC value that may be null (an unset variable or field)
+    fn build_rc_retain_nullable(&self, ptr: PointerValue<'ctx>) {
+        let function = self.current_function.unwrap();
+        let is_null = self.builder.build_is_null(ptr, "is_null").unwrap();
+        let retain_block = self.context.append_basic_block(function, "rc_retain");
+        let continue_block = self.context.append_basic_block(function, "rc_retained");
+        self.builder.build_conditional_branch(is_null, continue_block, retain_block).unwrap();
+
+        self.builder.position_at_end(retain_block);
+        self.build_rc_retain_inline(ptr);
+        self.builder.build_unconditional_branch(continue_block).unwrap();
+
+        self.builder.position_at_end(continue_block);
+    }
+
     // Inline RC release: decrement reference count and free if zero
-    fn build_rc_release_inline(&self, ptr: PointerValue<'ctx>) {
+    fn build_rc_release_inline(&self, ptr: PointerValue<'ctx>, ast_type: &Type) {
         let i64_type = self.context.i64_type();
-        let i8_type = self.context.i8_type();
         let function = self.current_function.unwrap();
 
-        // Get header
-        let minus_8 = i64_type.const_int((-8i64) as u64, false);
-        let header = unsafe {
-            self.builder.build_gep(i8_type, ptr, &[minus_8], "rc_header").unwrap()
-        };
+        let ref_count = self.build_rc_ref_count_ptr(ptr);
 
         // Load count
-        let count = self.builder.build_load(i64_type, header, "ref_count")
+        let count = self.builder.build_load(i64_type, ref_count, "ref_count")
             .unwrap().into_int_value();
 
         // Decrement
@@ -759,7 +1576,7 @@ impl<'ctx> CodeGen<'ctx> {
         ).unwrap();
 
         // Store
-        self.builder.build_store(header, new_count).unwrap();
+        self.builder.build_store(ref_count, new_count).unwrap();
 
         // Check if we hit zero
         let is_zero = self.builder.build_int_compare(
@@ -774,33 +1591,1446 @@ impl<'ctx> CodeGen<'ctx> {
 
         self.builder.build_conditional_branch(is_zero, free_block, continue_block).unwrap();
 
-        // Free block: deallocate memory
+        // Free block: objects go through their class's drop function (found in
+        // the vtable, so a subclass instance runs its own); the runtime owns the
+        // allocation layout
         self.builder.position_at_end(free_block);
-        let free_fn = self.functions.get("free").unwrap();
-        self.builder.build_call(*free_fn, &[header.into()], "").unwrap();
+        if matches!(ast_type, Type::Custom(_)) {
+            let ptr_type = self.context.ptr_type(AddressSpace::default());
+            let vtable = self.builder.build_load(ptr_type, ptr, "vtable").unwrap().into_pointer_value();
+            let drop_index = self.context.i64_type().const_int(-1i64 as u64, true);
+            let drop_entry = unsafe {
+                self.builder.build_gep(ptr_type, vtable, &[drop_index], "drop_entry").unwrap()
+            };
+            let drop_fn = self.builder.build_load(ptr_type, drop_entry, "drop").unwrap().into_pointer_value();
+            let drop_type = self.context.void_type().fn_type(&[ptr_type.into()], false);
+            self.builder.build_indirect_call(drop_type, drop_fn, &[ptr.into()], "").unwrap();
+        } else {
+            // Lists and dicts release their elements as they go; bigints and
+            // decimals drop their digits
+            let free_fn = match ast_type {
+                Type::List(_) => "list_free",
+                Type::Dict(_, _) => "dict_free",
+                Type::BigInt => "bigint_free",
+                Type::Decimal => "decimal_free",
+                _ => "rc_free",
+            };
+            let free_fn = self.functions.get(free_fn).unwrap();
+            self.builder.build_call(*free_fn, &[ptr.into()], "").unwrap();
+        }
         self.builder.build_unconditional_branch(continue_block).unwrap();
 
         // Continue
         self.builder.position_at_end(continue_block);
     }
 
-    pub fn compile_program(&mut self, program: &Program) -> Result<(), String> {
-        self.declare_printf();
-        self.declare_memory_functions();
-        self.declare_builtin_functions();
-        self.declare_list_functions();
-        self.declare_dict_functions();
-        self.declare_string_functions();
-        self.declare_io_functions();
-        self.declare_cli_functions();
-        self.declare_http_functions();
-        self.declare_runtime_error_functions();
+    // The type checker lets an int flow into a float slot (and a narrower int into a
+    // wider one); insert the conversion so the value matches the LLVM slot type
+    fn widen_to(&self, value: BasicValueEnum<'ctx>, target: BasicTypeEnum<'ctx>) -> BasicValueEnum<'ctx> {
+        match (value, target) {
+            (BasicValueEnum::IntValue(int_val), BasicTypeEnum::FloatType(float_type)) => {
+                if Self::is_unsigned_int(int_val.get_type()) {
+                    self.builder
+                        .build_unsigned_int_to_float(int_val, float_type, "widen")
+                        .unwrap()
+                        .as_basic_value_enum()
+                } else {
+                    self.builder
+                        .build_signed_int_to_float(int_val, float_type, "widen")
+                        .unwrap()
+                        .as_basic_value_enum()
+                }
+            }
+            (BasicValueEnum::IntValue(int_val), BasicTypeEnum::IntType(int_type))
+                if int_val.get_type().get_bit_width() < int_type.get_bit_width() =>
+            {
+                if Self::is_unsigned_int(int_val.get_type()) {
+                    self.builder.build_int_z_extend(int_val, int_type, "widen").unwrap().as_basic_value_enum()
+                } else {
+                    self.builder.build_int_s_extend(int_val, int_type, "widen").unwrap().as_basic_value_enum()
+                }
+            }
+            // Narrowing only reaches here for int literals stored into sized ints (range-checked
+            // by the type checker) and for explicit `as` casts, which wrap
+            (BasicValueEnum::IntValue(int_val), BasicTypeEnum::IntType(int_type))
+                if int_val.get_type().get_bit_width() > int_type.get_bit_width() =>
+            {
+                self.builder.build_int_truncate(int_val, int_type, "narrow").unwrap().as_basic_value_enum()
+            }
+            // Builtin arguments: the only builtin pointer parameters an integer may be
+            // passed to are bigints (decimal slots are converted by coerce_to)
+            (BasicValueEnum::IntValue(_), BasicTypeEnum::PointerType(_)) => {
+                self.int_to_runtime_number(value, &Type::BigInt)
+            }
+            // Strings and objects stored into i64 list slots
+            (BasicValueEnum::PointerValue(ptr), BasicTypeEnum::IntType(int_type)) => {
+                self.builder.build_ptr_to_int(ptr, int_type, "ptr_slot").unwrap().as_basic_value_enum()
+            }
+            _ => value,
+        }
+    }
 
-        // Phase 4: Mark built-in pure functions (don't cause escape)
-        self.mark_builtin_pure_functions();
+    // `5` or `-5`
+    fn is_int_literal(expr: &Expression) -> bool {
+        match expr {
+            Expression::IntLiteral(_) => true,
+            Expression::Unary { op: UnaryOp::Negate, operand } => matches!(**operand, Expression::IntLiteral(_)),
+            _ => false,
+        }
+    }
 
-        for statement in &program.statements {
-            self.compile_statement(statement)?;
+    // Helper: Value of `5` or `-5`
+    fn int_literal_value(expr: &Expression) -> Option<i64> {
+        match expr {
+            Expression::IntLiteral(value) => Some(*value),
+            Expression::Unary { op: UnaryOp::Negate, operand } => match operand.as_ref() {
+                Expression::IntLiteral(value) => Some(value.wrapping_neg()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Helper: When every condition of an if/elif chain compares the same int variable
+    // with constants (`op == 1`, `op == 2 or op == 3`), returns the variable and each
+    // branch's constants, so the chain can dispatch through one `switch`
+    fn int_switch_chain(
+        &self,
+        condition: &Expression,
+        elif_branches: &[(Expression, Vec<Statement>)],
+    ) -> Option<(String, Vec<Vec<i64>>)> {
+        let mut variable = None;
+        let mut constants = Vec::new();
+        for cond in std::iter::once(condition).chain(elif_branches.iter().map(|(cond, _)| cond)) {
+            let mut branch_constants = Vec::new();
+            Self::collect_switch_constants(cond, &mut variable, &mut branch_constants)?;
+            constants.push(branch_constants);
+        }
+
+        let variable = variable?.clone();
+        if constants.iter().map(Vec::len).sum::<usize>() < MIN_SWITCH_CASES {
+            return None;
+        }
+        // A constant the variable can't hold never matches; leave that to the plain chain
+        let (min, max) = self.declared_type(&Expression::Variable(variable.clone()))?.integer_range()?;
+        if constants.iter().flatten().any(|value| !(min..=max).contains(value)) {
+            return None;
+        }
+        Some((variable, constants))
+    }
+
+    fn collect_switch_constants<'a>(
+        condition: &'a Expression,
+        variable: &mut Option<&'a String>,
+        constants: &mut Vec<i64>,
+    ) -> Option<()> {
+        match condition {
+            Expression::Binary { left, op: BinaryOp::Or, right } => {
+                Self::collect_switch_constants(left, variable, constants)?;
+                Self::collect_switch_constants(right, variable, constants)
+            }
+            Expression::Binary { left, op: BinaryOp::Equal, right } => {
+                let (name, value) = match (left.as_ref(), right.as_ref()) {
+                    (Expression::Variable(name), other) | (other, Expression::Variable(name)) => {
+                        (name, Self::int_literal_value(other)?)
+                    }
+                    _ => return None,
+                };
+                if *variable.get_or_insert(name) != name {
+                    return None;
+                }
+                constants.push(value);
+                Some(())
+            }
+            _ => None,
+        }
+    }
+
+    // Compile an if/elif chain found by int_switch_chain as a `switch` on the variable.
+    // A constant repeated in a later branch is dropped: the earlier branch wins, as it
+    // would in the chain.
+    fn compile_if_switch(
+        &mut self,
+        variable: &str,
+        constants: &[Vec<i64>],
+        bodies: &[&Vec<Statement>],
+        else_branch: Option<&Vec<Statement>>,
+    ) -> Result<(), String> {
+        let function = self.current_function.ok_or("If statement outside of function")?;
+        let value = self.compile_expression(&Expression::Variable(variable.to_string()))?.into_int_value();
+
+        let merge_block = self.context.append_basic_block(function, "ifcont");
+        let default_block = match else_branch {
+            Some(_) => self.context.append_basic_block(function, "else"),
+            None => merge_block,
+        };
+        let case_blocks: Vec<BasicBlock> =
+            bodies.iter().map(|_| self.context.append_basic_block(function, "case")).collect();
+
+        let mut seen = HashSet::new();
+        let mut cases = Vec::new();
+        for (branch_constants, block) in constants.iter().zip(&case_blocks) {
+            for &constant in branch_constants {
+                if seen.insert(constant) {
+                    cases.push((value.get_type().const_int(constant as u64, true), *block));
+                }
+            }
+        }
+        self.builder.build_switch(value, default_block, &cases).unwrap();
+
+        for (body, block) in bodies.iter().zip(&case_blocks) {
+            self.builder.position_at_end(*block);
+            for stmt in body.iter() {
+                self.compile_statement(stmt)?;
+            }
+            if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                self.builder.build_unconditional_branch(merge_block).unwrap();
+            }
+        }
+
+        if let Some(else_body) = else_branch {
+            self.builder.position_at_end(default_block);
+            for stmt in else_body {
+                self.compile_statement(stmt)?;
+            }
+            if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                self.builder.build_unconditional_branch(merge_block).unwrap();
+            }
+        }
+
+        self.builder.position_at_end(merge_block);
+        Ok(())
+    }
+
+    // Bools (i1) and u8 (i8) are the unsigned integer types; i16, i32 and int are signed.
+    // The width alone tells them apart, so codegen doesn't need the WadeScript type.
+    fn is_unsigned_int(int_type: IntType<'ctx>) -> bool {
+        matches!(int_type.get_bit_width(), 1 | 8)
+    }
+
+    // Convert a value to the representation of `ws_type`: widen numbers, and box
+    // int/float/bool values stored into an Optional (None is a null pointer). The box
+    // is a new RC allocation, released like a list once its owner is done with it
+    fn coerce_to(&self, value: BasicValueEnum<'ctx>, ws_type: &Type) -> BasicValueEnum<'ctx> {
+        if let Type::Optional(inner) = ws_type {
+            if Self::is_boxed_optional(ws_type) && !value.is_pointer_value() {
+                let inner_value = self.widen_to(value, self.get_llvm_type(inner));
+                let rc_alloc_fn = *self.functions.get("rc_alloc").unwrap();
+                let size = self.context.i64_type().const_int(8, false);
+                let boxed = self.builder
+                    .build_call(rc_alloc_fn, &[size.into()], "optional_box")
+                    .unwrap()
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap()
+                    .into_pointer_value();
+                self.builder.build_store(boxed, inner_value).unwrap();
+                return boxed.as_basic_value_enum();
+            }
+        }
+        if matches!(ws_type, Type::BigInt | Type::Decimal) && value.is_int_value() {
+            return self.int_to_runtime_number(value, ws_type);
+        }
+        self.widen_to(value, self.get_llvm_type(ws_type))
+    }
+
+    // Helper: A value as the i64 slot a dict stores for a key or value: ints and
+    // bools widened, floats as their bits, pointers (strings, objects) as addresses
+    fn to_dict_slot(&self, value: BasicValueEnum<'ctx>) -> IntValue<'ctx> {
+        let i64_type = self.context.i64_type();
+        match value {
+            BasicValueEnum::FloatValue(float_val) => self.builder
+                .build_bit_cast(float_val, i64_type, "float_bits")
+                .unwrap()
+                .into_int_value(),
+            BasicValueEnum::PointerValue(ptr_val) => self.builder
+                .build_ptr_to_int(ptr_val, i64_type, "ptr_bits")
+                .unwrap(),
+            _ => self.widen_to(value, i64_type.as_basic_type_enum()).into_int_value(),
+        }
+    }
+
+    // Helper: Read a dict slot back as a value of `ws_type`
+    fn from_dict_slot(&self, slot: IntValue<'ctx>, ws_type: &Type) -> BasicValueEnum<'ctx> {
+        match self.get_llvm_type(ws_type) {
+            BasicTypeEnum::IntType(int_type) if int_type.get_bit_width() < 64 => self.builder
+                .build_int_truncate(slot, int_type, "dict_int")
+                .unwrap()
+                .as_basic_value_enum(),
+            BasicTypeEnum::FloatType(float_type) => self.builder
+                .build_bit_cast(slot, float_type, "dict_float")
+                .unwrap(),
+            BasicTypeEnum::PointerType(ptr_type) => self.builder
+                .build_int_to_ptr(slot, ptr_type, "dict_ptr")
+                .unwrap()
+                .as_basic_value_enum(),
+            _ => slot.as_basic_value_enum(),
+        }
+    }
+
+    // Helper: Key and value types of a dict expression, when codegen can resolve them
+    fn dict_types(&self, expr: &Expression) -> Option<(Type, Type)> {
+        match self.declared_type(expr)? {
+            Type::Dict(key_type, val_type) => Some((*key_type, *val_type)),
+            _ => None,
+        }
+    }
+
+    // Helper: Name of the dict runtime function for a key: dict_get for str keys,
+    // dict_get_int for everything hashed by value (ints, bools, objects by identity)
+    fn dict_function(operation: &str, str_keys: bool) -> String {
+        if str_keys {
+            format!("dict_{}", operation)
+        } else {
+            format!("dict_{}_int", operation)
+        }
+    }
+
+    // Helper: Store a pair in a dict. str keys are passed as pointers (the runtime
+    // copies them); other keys and all values go in i64 slots
+    fn build_dict_set(&self, dict: BasicValueEnum<'ctx>, key: BasicValueEnum<'ctx>, str_keys: bool, value: BasicValueEnum<'ctx>) {
+        let key_arg: BasicMetadataValueEnum = if str_keys { key.into() } else { self.to_dict_slot(key).into() };
+        let function = *self.functions.get(&Self::dict_function("set", str_keys)).unwrap();
+        self.builder
+            .build_call(function, &[dict.into(), key_arg, self.to_dict_slot(value).into()], "")
+            .unwrap();
+    }
+
+    // Helper: Retain an RC value going into a list or dict if the caller doesn't hand
+    // over its reference. The collection releases it again when the element is
+    // replaced or the collection is freed.
+    fn retain_for_collection(&self, value: BasicValueEnum<'ctx>, expr: &Expression) {
+        if value.is_pointer_value() && self.is_borrowed_value(expr) {
+            self.build_rc_retain_nullable(value.into_pointer_value());
+        }
+    }
+
+    // Helper: Element type of a list expression, when codegen can resolve it
+    fn list_element_type(&self, expr: &Expression) -> Option<Type> {
+        match self.declared_type(expr)? {
+            Type::List(elem_type) => Some(*elem_type),
+            _ => None,
+        }
+    }
+
+    // Helper: Whether an expression is a list: a literal, or of a declared list type
+    fn is_list_expression(&self, expr: &Expression) -> bool {
+        matches!(expr, Expression::ListLiteral { .. }) || matches!(self.declared_type(expr), Some(Type::List(_)))
+    }
+
+    // Helper: Suffix of the list runtime functions for an LLVM element type. Elements
+    // are 8-byte slots: floats and pointers (strings, collections, objects) have their
+    // own functions, ints and bools are widened into the i64 ones
+    fn list_suffix(elem_type: BasicTypeEnum<'ctx>) -> &'static str {
+        match elem_type {
+            BasicTypeEnum::FloatType(_) => "f64",
+            BasicTypeEnum::PointerType(_) => "ptr",
+            _ => "i64",
+        }
+    }
+
+    // Helper: Call list_push_* / list_set_* with `value` as the last argument, picking
+    // the function from the value's type. A tuple goes in as a box of its own
+    fn build_list_store(
+        &self,
+        operation: &str,
+        args: &[BasicMetadataValueEnum<'ctx>],
+        value: BasicValueEnum<'ctx>,
+        elem_type: Option<&Type>,
+    ) {
+        let value = match (value, elem_type) {
+            (BasicValueEnum::StructValue(tuple), Some(Type::Tuple(field_types))) => {
+                self.build_tuple_box(tuple, field_types).as_basic_value_enum()
+            }
+            _ => value,
+        };
+        let suffix = Self::list_suffix(value.get_type());
+        let value = if suffix == "i64" {
+            self.widen_to(value, self.context.i64_type().as_basic_type_enum())
+        } else {
+            value
+        };
+        let function = *self.functions.get(&format!("list_{}_{}", operation, suffix)).unwrap();
+        let mut call_args = args.to_vec();
+        call_args.push(value.into());
+        self.builder.build_call(function, &call_args, "").unwrap();
+    }
+
+    // Helper: Call list_get_* / list_pop_* for an element type (i64 when unknown) and
+    // narrow the slot back to bools and sized ints, or unbox a tuple. A popped tuple's
+    // box is never released, since the tuple's fields are borrowed from it
+    fn build_list_load(&self, operation: &str, args: &[BasicMetadataValueEnum<'ctx>], elem_type: Option<&Type>) -> BasicValueEnum<'ctx> {
+        let llvm_type = match elem_type {
+            Some(Type::Tuple(_)) | None => self.context.i64_type().as_basic_type_enum(),
+            Some(elem_type) => self.get_llvm_type(elem_type),
+        };
+        let function = *self.functions.get(&format!("list_{}_{}", operation, Self::list_suffix(llvm_type))).unwrap();
+        let element = self.builder
+            .build_call(function, args, "element")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap();
+        match (llvm_type, elem_type) {
+            (_, Some(tuple_type @ Type::Tuple(_))) => self.from_list_slot(element.into_int_value(), tuple_type),
+            (BasicTypeEnum::IntType(int_type), _) if int_type.get_bit_width() < 64 => self.builder
+                .build_int_truncate(element.into_int_value(), int_type, "list_int")
+                .unwrap()
+                .as_basic_value_enum(),
+            _ => element,
+        }
+    }
+
+    // Helper: Box a tuple for a list slot the way rc.rs's tuple_box does: an RC block
+    // of i64 words holding the field count, then each field's ELEMS_* kind and slot.
+    // The box takes its own reference to RC fields and releases them when it is freed
+    fn build_tuple_box(&self, tuple: StructValue<'ctx>, field_types: &[Type]) -> PointerValue<'ctx> {
+        let i64_type = self.context.i64_type();
+        let size = i64_type.const_int(8 * (1 + 2 * field_types.len() as u64), false);
+        let boxed = self.call_runtime("rc_alloc", &[size.into()]).into_pointer_value();
+        let word = |index: usize| unsafe {
+            self.builder
+                .build_gep(i64_type, boxed, &[i64_type.const_int(index as u64, false)], "tuple_word")
+                .unwrap()
+        };
+        self.builder.build_store(boxed, i64_type.const_int(field_types.len() as u64, false)).unwrap();
+        for (i, field_type) in field_types.iter().enumerate() {
+            let field = self.builder.build_extract_value(tuple, i as u32, "tuple_field").unwrap();
+            let kind = Self::elems_kind(field_type);
+            if kind != ELEMS_PLAIN && field.is_pointer_value() {
+                self.build_rc_retain_nullable(field.into_pointer_value());
+            }
+            self.builder.build_store(word(1 + 2 * i), i64_type.const_int(kind as u64, false)).unwrap();
+            self.builder.build_store(word(2 + 2 * i), self.to_dict_slot(field)).unwrap();
+        }
+        boxed
+    }
+
+    // Helper: Read a list slot back as a value of `elem_type`: a tuple is loaded field
+    // by field from its box (see build_tuple_box), anything else is the slot itself
+    fn from_list_slot(&self, slot: IntValue<'ctx>, elem_type: &Type) -> BasicValueEnum<'ctx> {
+        let Type::Tuple(field_types) = elem_type else {
+            return self.from_dict_slot(slot, elem_type);
+        };
+        let i64_type = self.context.i64_type();
+        let boxed = self.builder
+            .build_int_to_ptr(slot, self.context.ptr_type(AddressSpace::default()), "tuple_box")
+            .unwrap();
+        let mut tuple = self.get_llvm_type(elem_type).into_struct_type().get_undef();
+        for (i, field_type) in field_types.iter().enumerate() {
+            let word = unsafe {
+                self.builder
+                    .build_gep(i64_type, boxed, &[i64_type.const_int(2 + 2 * i as u64, false)], "tuple_word")
+                    .unwrap()
+            };
+            let field_slot = self.builder.build_load(i64_type, word, "tuple_slot").unwrap().into_int_value();
+            let field = self.from_dict_slot(field_slot, field_type);
+            tuple = self.builder
+                .build_insert_value(tuple, field, i as u32, "tuple")
+                .unwrap()
+                .into_struct_value();
+        }
+        tuple.as_basic_value_enum()
+    }
+
+    // Helper: Call list_index_of_* for the argument of index_of() / contains(). Strings
+    // compare by contents, other pointers (collections, objects) by identity
+    fn build_list_index_of(&mut self, list: BasicValueEnum<'ctx>, list_expr: &Expression, value_expr: &Expression) -> Result<IntValue<'ctx>, String> {
+        let elem_type = self.list_element_type(list_expr);
+        let value = match &elem_type {
+            Some(elem_type) => self.compile_expression_as(value_expr, elem_type)?,
+            None => self.compile_expression(value_expr)?,
+        };
+        let suffix = match elem_type {
+            Some(Type::Str) => "str",
+            _ => Self::list_suffix(value.get_type()),
+        };
+        let value = if suffix == "i64" {
+            self.widen_to(value, self.context.i64_type().as_basic_type_enum())
+        } else {
+            value
+        };
+        let function = *self.functions.get(&format!("list_index_of_{}", suffix)).unwrap();
+        Ok(self.builder
+            .build_call(function, &[list.into(), value.into()], "index")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value())
+    }
+
+    // Helper: Run `body` on each element of a list, in order. The length is read
+    // once; list_get still checks every index
+    fn build_list_each(
+        &mut self,
+        list: BasicValueEnum<'ctx>,
+        elem_type: Option<&Type>,
+        mut body: impl FnMut(&mut Self, BasicValueEnum<'ctx>) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let function = self.current_function.ok_or("List method outside of function")?;
+        let i64_type = self.context.i64_type();
+        let length = self.call_runtime("list_length", &[list.into()]).into_int_value();
+        let idx_alloca = self.builder.build_alloca(i64_type, "_each_idx").unwrap();
+        self.builder.build_store(idx_alloca, i64_type.const_zero()).unwrap();
+
+        let cond_block = self.context.append_basic_block(function, "each_cond");
+        let body_block = self.context.append_basic_block(function, "each_body");
+        let end_block = self.context.append_basic_block(function, "each_end");
+        self.builder.build_unconditional_branch(cond_block).unwrap();
+
+        self.builder.position_at_end(cond_block);
+        let idx = self.builder.build_load(i64_type, idx_alloca, "idx").unwrap().into_int_value();
+        let more = self.builder.build_int_compare(IntPredicate::SLT, idx, length, "more").unwrap();
+        self.builder.build_conditional_branch(more, body_block, end_block).unwrap();
+
+        self.builder.position_at_end(body_block);
+        let item = self.build_list_load("get", &[list.into(), idx.into()], elem_type);
+        body(self, item)?;
+        let next = self.builder.build_int_add(idx, i64_type.const_int(1, false), "next_idx").unwrap();
+        self.builder.build_store(idx_alloca, next).unwrap();
+        self.builder.build_unconditional_branch(cond_block).unwrap();
+
+        self.builder.position_at_end(end_block);
+        Ok(())
+    }
+
+    // Helper: Call the function passed by name to map(), filter(), reduce() or
+    // sort_by(), converting the arguments to its parameter types
+    fn call_passed_function(&mut self, name: &str, args: &[BasicValueEnum<'ctx>]) -> Result<BasicValueEnum<'ctx>, String> {
+        let function = *self.functions.get(name).ok_or_else(|| format!("Undefined function '{}'", name))?;
+        let params = self.function_params.get(name).cloned().unwrap_or_default();
+        let mut arg_values: Vec<BasicMetadataValueEnum> = args
+            .iter()
+            .enumerate()
+            .map(|(i, &arg)| match params.get(i) {
+                Some(param) => self.coerce_to(arg, &param.param_type).into(),
+                None => arg.into(),
+            })
+            .collect();
+        self.widen_call_args(function, &mut arg_values);
+        let call = self.builder.build_call(function, &arg_values, "calltmp").unwrap();
+        call.try_as_basic_value().left().ok_or_else(|| format!("Function '{}' returns nothing", name))
+    }
+
+    // Helper: The `before(a, b) -> i64` that list_sort_by calls with two element slots:
+    // it reads them back as `elem_type` and calls the function passed to sort_by().
+    // One per function and slot type, built on first use
+    fn sort_by_comparator(&mut self, name: &str, elem_type: Option<&Type>) -> Result<FunctionValue<'ctx>, String> {
+        let i64_type = self.context.i64_type();
+        let slot_type = match elem_type {
+            Some(elem_type) => self.get_llvm_type(elem_type),
+            None => i64_type.as_basic_type_enum(),
+        };
+        let comparator_name = format!("sort_by.{}.{}", name, slot_type.print_to_string().to_string_lossy());
+        if let Some(comparator) = self.module.get_function(&comparator_name) {
+            return Ok(comparator);
+        }
+
+        let fn_type = i64_type.fn_type(&[i64_type.into(), i64_type.into()], false);
+        let comparator = self.module.add_function(&comparator_name, fn_type, Some(Linkage::Internal));
+        let saved_block = self.builder.get_insert_block();
+        let saved_location = self.builder.get_current_debug_location();
+        // No debug scope of its own: the caller's location would point into another function
+        self.builder.unset_current_debug_location();
+        let entry = self.context.append_basic_block(comparator, "entry");
+        self.builder.position_at_end(entry);
+
+        let slots: Vec<BasicValueEnum> = comparator
+            .get_param_iter()
+            .map(|slot| match elem_type {
+                Some(elem_type) => self.from_list_slot(slot.into_int_value(), elem_type),
+                None => slot,
+            })
+            .collect();
+        let before = self.call_passed_function(name, &slots)?;
+        let before = self.builder
+            .build_int_z_extend(before.into_int_value(), i64_type, "before")
+            .unwrap();
+        self.builder.build_return(Some(&before)).unwrap();
+
+        if let Some(block) = saved_block {
+            self.builder.position_at_end(block);
+        }
+        if let Some(location) = saved_location {
+            self.builder.set_current_debug_location(location);
+        }
+        Ok(comparator)
+    }
+
+    // Helper: The ELEMS_* kind (see rc.rs) of a collection holding `ws_type`: which
+    // lists, dicts and objects the runtime releases when it frees the collection.
+    // Matches is_rc_type, so everything stored with a retain is released again
+    fn elems_kind(ws_type: &Type) -> i64 {
+        match ws_type {
+            Type::List(_) => ELEMS_LIST,
+            Type::Dict(_, _) => ELEMS_DICT,
+            Type::Custom(_) => ELEMS_OBJECT,
+            Type::BigInt => ELEMS_BIGINT,
+            Type::Decimal => ELEMS_DECIMAL,
+            Type::Tuple(_) => ELEMS_TUPLE,
+            _ if Self::is_boxed_optional(ws_type) => ELEMS_BOX,
+            _ => ELEMS_PLAIN,
+        }
+    }
+
+    // Helper: `elems_kind` as an argument for list_create / dict_create. Without a
+    // declared type the collection holds plain values
+    fn elems_kind_arg(&self, ws_type: Option<&Type>) -> (i64, BasicMetadataValueEnum<'ctx>) {
+        let kind = ws_type.map_or(ELEMS_PLAIN, Self::elems_kind);
+        (kind, self.context.i64_type().const_int(kind as u64, false).into())
+    }
+
+    // Build a list literal. With the declared element type (`xs: list[float] = [1, 2.5]`)
+    // each element is converted to it; otherwise the elements' own values decide, and
+    // the first one's type says what the list holds
+    fn compile_list_literal(&mut self, elements: &[Expression], elem_type: Option<&Type>) -> Result<BasicValueEnum<'ctx>, String> {
+        let held_type = match elem_type {
+            Some(elem_type) => Some(elem_type.clone()),
+            None => elements.first().and_then(|element| self.declared_type(element)),
+        };
+        let (kind, kind_arg) = self.elems_kind_arg(held_type.as_ref());
+        let list_ptr = self.call_runtime("list_create", &[kind_arg]);
+
+        for element in elements {
+            let value = match elem_type {
+                Some(elem_type) => self.compile_expression_as(element, elem_type)?,
+                None => self.compile_expression(element)?,
+            };
+            if kind != ELEMS_PLAIN {
+                self.retain_for_collection(value, element);
+            }
+            self.build_list_store("push", &[list_ptr.into()], value, held_type.as_ref());
+        }
+
+        Ok(list_ptr)
+    }
+
+    // Compile an expression for a slot of a known type. `Ok(x)` and `Err(e)` only get a
+    // concrete struct type here, since the literal alone does not say what the other side is
+    fn compile_expression_as(&mut self, expr: &Expression, ws_type: &Type) -> Result<BasicValueEnum<'ctx>, String> {
+        if let (Type::Result(ok_type, err_type), Some((is_ok, payload))) = (ws_type, self.result_constructor(expr)) {
+            let payload_type = if is_ok { ok_type } else { err_type };
+            let payload = self.compile_expression_as(payload, payload_type)?;
+            return Ok(self.build_result(ws_type, is_ok, payload));
+        }
+        if let (Type::Dict(key_type, val_type), Expression::DictLiteral { pairs }) = (ws_type, expr) {
+            return self.compile_dict_literal(pairs, Some((key_type.as_ref(), val_type.as_ref())));
+        }
+        if let (Type::List(elem_type), Expression::ListLiteral { elements }) = (ws_type, expr) {
+            return self.compile_list_literal(elements, Some(elem_type.as_ref()));
+        }
+        let value = self.compile_expression(expr)?;
+        Ok(self.coerce_to(value, ws_type))
+    }
+
+    // Build a dict literal. With the declared type of the slot it goes into
+    // (`d: dict[int, float] = {...}`) keys and values are converted to it; otherwise
+    // the literal's own values decide, and str keys are the pointers that aren't objects
+    fn compile_dict_literal(
+        &mut self,
+        pairs: &[(Expression, Expression)],
+        dict_types: Option<(&Type, &Type)>,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let (key_type, val_type) = match dict_types {
+            Some((key_type, val_type)) => (Some(key_type.clone()), Some(val_type.clone())),
+            None => match pairs.first() {
+                Some((key_expr, val_expr)) => (self.declared_type(key_expr), self.declared_type(val_expr)),
+                None => (None, None),
+            },
+        };
+        let (key_kind, key_kind_arg) = self.elems_kind_arg(key_type.as_ref());
+        let (val_kind, val_kind_arg) = self.elems_kind_arg(val_type.as_ref());
+        let dict_ptr = self.call_runtime("dict_create", &[key_kind_arg, val_kind_arg]);
+
+        for (key_expr, val_expr) in pairs {
+            let (key_value, val_value, str_keys) = match dict_types {
+                Some((key_type, val_type)) => (
+                    self.compile_expression_as(key_expr, key_type)?,
+                    self.compile_expression_as(val_expr, val_type)?,
+                    *key_type == Type::Str,
+                ),
+                None => {
+                    let key_value = self.compile_expression(key_expr)?;
+                    let val_value = self.compile_expression(val_expr)?;
+                    let str_keys = key_value.is_pointer_value() && self.object_class(key_expr).is_none();
+                    (key_value, val_value, str_keys)
+                }
+            };
+            if key_kind != ELEMS_PLAIN {
+                self.retain_for_collection(key_value, key_expr);
+            }
+            if val_kind != ELEMS_PLAIN {
+                self.retain_for_collection(val_value, val_expr);
+            }
+            self.build_dict_set(dict_ptr, key_value, str_keys, val_value);
+        }
+
+        Ok(dict_ptr)
+    }
+
+    // `Ok(x)` / `Err(e)`: whether it is Ok, and the wrapped expression
+    fn result_constructor<'e>(&self, expr: &'e Expression) -> Option<(bool, &'e Expression)> {
+        match expr {
+            Expression::Call { callee, args, .. } if args.len() == 1 => match callee.as_ref() {
+                Expression::Variable(name)
+                    if (name == "Ok" || name == "Err") && self.resolve_function(name) == *name && !self.functions.contains_key(name) =>
+                {
+                    Some((name == "Ok", &args[0]))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Build a Result value: field 0 is the Ok flag, field 1 the value, field 2 the error
+    fn build_result(&self, result_type: &Type, is_ok: bool, payload: BasicValueEnum<'ctx>) -> BasicValueEnum<'ctx> {
+        let struct_type = self.get_llvm_type(result_type).into_struct_type();
+        let tag = self.context.bool_type().const_int(is_ok as u64, false);
+        let tagged = self.builder
+            .build_insert_value(struct_type.const_zero(), tag, 0, "result_tag")
+            .unwrap()
+            .into_struct_value();
+        self.builder
+            .build_insert_value(tagged, payload, if is_ok { 1 } else { 2 }, "result")
+            .unwrap()
+            .into_struct_value()
+            .as_basic_value_enum()
+    }
+
+    // Return a value from the current function: @memoize store, RC cleanup, and the
+    // call stack pop
+    fn build_value_return(&self, return_value: BasicValueEnum<'ctx>) {
+        // @memoize: record the result before leaving the function
+        self.build_memo_store(return_value);
+
+        // Release all RC variables before returning (except moved ones)
+        self.release_scope_variables();
+
+        // Pop function from call stack after computing return value
+        let pop_call_stack_fn = *self.functions.get("pop_call_stack").unwrap();
+        self.builder.build_call(pop_call_stack_fn, &[], "").unwrap();
+
+        self.builder.build_return(Some(&return_value)).unwrap();
+    }
+
+    // Helper: Compile a call's arguments in parameter order, filling in named
+    // arguments and defaults for user-defined functions
+    fn compile_call_args(
+        &mut self,
+        func_name: &str,
+        args: &[Expression],
+        named_args: &[(String, Expression)],
+    ) -> Result<Vec<BasicMetadataValueEnum<'ctx>>, String> {
+        let mut arg_values: Vec<BasicMetadataValueEnum> = Vec::new();
+
+        if let Some(params) = self.function_params.get(func_name).cloned() {
+            // User-defined function with param info
+            let mut final_args: Vec<Option<BasicValueEnum>> = vec![None; params.len()];
+
+            // Fill in positional arguments
+            for (i, arg) in args.iter().enumerate() {
+                let arg_val = self.compile_expression_as(arg, &params[i].param_type)?;
+                final_args[i] = Some(arg_val);
+            }
+
+            // Fill in named arguments
+            for (name, value) in named_args {
+                if let Some(idx) = params.iter().position(|p| &p.name == name) {
+                    let arg_val = self.compile_expression_as(value, &params[idx].param_type)?;
+                    final_args[idx] = Some(arg_val);
+                }
+            }
+
+            // Fill in defaults for missing arguments
+            for (i, param) in params.iter().enumerate() {
+                if final_args[i].is_none() {
+                    if let Some(ref default_expr) = param.default_value {
+                        let default_val = self.compile_expression_as(default_expr, &param.param_type)?;
+                        final_args[i] = Some(default_val);
+                    }
+                }
+            }
+
+            // Convert to arg_values
+            for (arg_opt, param) in final_args.into_iter().zip(&params) {
+                if let Some(val) = arg_opt {
+                    arg_values.push(self.coerce_to(val, &param.param_type).into());
+                }
+            }
+        } else {
+            // Built-in function - just use positional args
+            for arg in args {
+                let arg_val = self.compile_expression(arg)?;
+                arg_values.push(arg_val.into());
+            }
+        }
+
+        Ok(arg_values)
+    }
+
+    // Widen call arguments to the callee's parameter types (varargs are left alone)
+    fn widen_call_args(&self, function: FunctionValue<'ctx>, args: &mut [BasicMetadataValueEnum<'ctx>]) {
+        let param_types = function.get_type().get_param_types();
+        for (arg, param_type) in args.iter_mut().zip(param_types) {
+            if let BasicMetadataValueEnum::IntValue(int_val) = *arg {
+                *arg = self.widen_to(int_val.as_basic_value_enum(), param_type).into();
+            }
+        }
+    }
+
+    // is_ok(), is_err(), unwrap(), unwrap_err(), and unwrap_or() on a Result.
+    // `err_type` is the declared error type, when codegen can tell
+    fn compile_result_method(
+        &mut self,
+        result: StructValue<'ctx>,
+        method: &str,
+        args: &[Expression],
+        err_type: Option<Type>,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let is_ok = self.builder.build_extract_value(result, 0, "is_ok").unwrap().into_int_value();
+        match method {
+            "is_ok" => Ok(is_ok.as_basic_value_enum()),
+            "is_err" => Ok(self.builder.build_not(is_ok, "is_err").unwrap().as_basic_value_enum()),
+            "unwrap_or" => {
+                let ok_value = self.builder.build_extract_value(result, 1, "ok_value").unwrap();
+                let default = self.compile_expression(&args[0])?;
+                let default = self.widen_to(default, ok_value.get_type());
+                Ok(self.builder.build_select(is_ok, ok_value, default, "unwrap_or").unwrap())
+            }
+            _ => {
+                // unwrap() / unwrap_err(): raise ValueError on the wrong variant
+                let function = self.current_function.ok_or("unwrap() outside of function")?;
+                let wants_ok = method == "unwrap";
+                let fail_block = self.context.append_basic_block(function, "unwrap_fail");
+                let ok_block = self.context.append_basic_block(function, "unwrap_ok");
+                if wants_ok {
+                    self.builder.build_conditional_branch(is_ok, ok_block, fail_block).unwrap();
+                } else {
+                    self.builder.build_conditional_branch(is_ok, fail_block, ok_block).unwrap();
+                }
+
+                self.builder.position_at_end(fail_block);
+                let message = if wants_ok && err_type == Some(Type::Str) {
+                    // Include the error string: "unwrap() called on Err: <error>"
+                    let error = self.builder.build_extract_value(result, 2, "error").unwrap();
+                    let strlen_fn = *self.functions.get("strlen").unwrap();
+                    let malloc_fn = *self.functions.get("malloc").unwrap();
+                    let sprintf_fn = *self.functions.get("sprintf").unwrap();
+                    let error_len = self.builder
+                        .build_call(strlen_fn, &[error.into()], "error_len")
+                        .unwrap()
+                        .try_as_basic_value()
+                        .left()
+                        .unwrap()
+                        .into_int_value();
+                    let size = self.builder
+                        .build_int_add(error_len, self.context.i64_type().const_int(32, false), "message_size")
+                        .unwrap();
+                    let buffer = self.builder
+                        .build_call(malloc_fn, &[size.into()], "unwrap_message")
+                        .unwrap()
+                        .try_as_basic_value()
+                        .left()
+                        .unwrap()
+                        .into_pointer_value();
+                    let fmt = self.builder.build_global_string_ptr("unwrap() called on Err: %s", "unwrap_fmt").unwrap();
+                    self.builder.build_call(
+                        sprintf_fn,
+                        &[buffer.into(), fmt.as_pointer_value().into(), error.into()],
+                        ""
+                    ).unwrap();
+                    buffer
+                } else {
+                    let text = if wants_ok { "unwrap() called on an Err value" } else { "unwrap_err() called on an Ok value" };
+                    self.builder.build_global_string_ptr(text, "unwrap_msg").unwrap().as_pointer_value()
+                };
+                let type_str = self.builder.build_global_string_ptr("ValueError", "exc_type").unwrap();
+                let file_str = self.builder.build_global_string_ptr(&self.source_file, "exc_file").unwrap();
+                let exception_raise_fn = *self.functions.get("exception_raise").unwrap();
+                self.builder.build_call(
+                    exception_raise_fn,
+                    &[
+                        type_str.as_pointer_value().into(),
+                        message.into(),
+                        file_str.as_pointer_value().into(),
+                        self.context.i64_type().const_zero().into(),
+                    ],
+                    ""
+                ).unwrap();
+                self.builder.build_unreachable().unwrap();
+
+                self.builder.position_at_end(ok_block);
+                let field = if wants_ok { 1 } else { 2 };
+                Ok(self.builder.build_extract_value(result, field, "unwrapped").unwrap())
+            }
+        }
+    }
+
+    // Call an llvm.*.with.overflow intrinsic: the wrapped result and the overflow bit
+    fn build_overflow_intrinsic(
+        &self,
+        intrinsic_name: &str,
+        lhs: IntValue<'ctx>,
+        rhs: IntValue<'ctx>,
+    ) -> Result<(BasicValueEnum<'ctx>, IntValue<'ctx>), String> {
+        let intrinsic = Intrinsic::find(intrinsic_name)
+            .ok_or(format!("LLVM intrinsic '{}' not found", intrinsic_name))?;
+        let intrinsic_fn = intrinsic
+            .get_declaration(&self.module, &[lhs.get_type().into()])
+            .ok_or(format!("Failed to declare LLVM intrinsic '{}'", intrinsic_name))?;
+
+        // Result is { iN result, i1 overflowed }
+        let result = self.builder
+            .build_call(intrinsic_fn, &[lhs.into(), rhs.into()], "checked_result")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_struct_value();
+        let value = self.builder.build_extract_value(result, 0, "checked_value").unwrap();
+        let overflowed = self.builder
+            .build_extract_value(result, 1, "overflowed")
+            .unwrap()
+            .into_int_value();
+        Ok((value, overflowed))
+    }
+
+    // Checked integer arithmetic: call an llvm.s*.with.overflow intrinsic and raise
+    // OverflowError when the overflow bit is set
+    fn build_checked_int_op(
+        &self,
+        intrinsic_name: &str,
+        lhs: IntValue<'ctx>,
+        rhs: IntValue<'ctx>,
+        operation: &str,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let (value, overflowed) = self.build_overflow_intrinsic(intrinsic_name, lhs, rhs)?;
+        self.build_overflow_check(overflowed, operation)?;
+        Ok(value)
+    }
+
+    // Checked signed division: MIN / -1 is one past MAX, and LLVM leaves it undefined
+    // (x86 traps), so raise OverflowError before dividing
+    fn build_checked_division(&self, lhs: IntValue<'ctx>, rhs: IntValue<'ctx>, operation: &str) -> Result<(), String> {
+        let int_type = lhs.get_type();
+        let min = int_type.const_int(1 << (int_type.get_bit_width() - 1), false);
+        let is_min = self.builder.build_int_compare(IntPredicate::EQ, lhs, min, "is_min").unwrap();
+        let is_minus_one = self.builder.build_int_compare(IntPredicate::EQ, rhs, int_type.const_all_ones(), "is_minus_one").unwrap();
+        let overflowed = self.builder.build_and(is_min, is_minus_one, "div_overflow").unwrap();
+        self.build_overflow_check(overflowed, operation)
+    }
+
+    // Raise OverflowError from `operation` when `overflowed` is set
+    fn build_overflow_check(&self, overflowed: IntValue<'ctx>, operation: &str) -> Result<(), String> {
+        let function = self.current_function.ok_or("Checked arithmetic outside of function")?;
+        let overflow_block = self.context.append_basic_block(function, "overflow");
+        let ok_block = self.context.append_basic_block(function, "no_overflow");
+        self.builder.build_conditional_branch(overflowed, overflow_block, ok_block).unwrap();
+
+        // Overflow: raise OverflowError (does not return)
+        self.builder.position_at_end(overflow_block);
+        let type_str = self.builder.build_global_string_ptr("OverflowError", "exc_type").unwrap();
+        let message = format!("Integer overflow in {}", operation);
+        let message_str = self.builder.build_global_string_ptr(&message, "overflow_msg").unwrap();
+        let file_str = self.builder.build_global_string_ptr(&self.source_file, "exc_file").unwrap();
+        let exception_raise_fn = *self.functions.get("exception_raise").unwrap();
+        self.builder.build_call(
+            exception_raise_fn,
+            &[
+                type_str.as_pointer_value().into(),
+                message_str.as_pointer_value().into(),
+                file_str.as_pointer_value().into(),
+                self.context.i64_type().const_zero().into(),
+            ],
+            ""
+        ).unwrap();
+        self.builder.build_unreachable().unwrap();
+
+        self.builder.position_at_end(ok_block);
+        Ok(())
+    }
+
+    // checked_add(a, b) / checked_mul(a, b): the int result boxed as an int?, or None
+    // (a null pointer) when it overflows
+    fn build_checked_builtin(&mut self, name: &str, args: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
+        if args.len() != 2 {
+            return Err(format!("{}() takes exactly 2 arguments", name));
+        }
+        let function = self.current_function.ok_or(format!("{}() outside of function", name))?;
+        let lhs = self.compile_expression_as(&args[0], &Type::Int)?.into_int_value();
+        let rhs = self.compile_expression_as(&args[1], &Type::Int)?.into_int_value();
+        let intrinsic_name = if name == "checked_add" { "llvm.sadd.with.overflow" } else { "llvm.smul.with.overflow" };
+        let (value, overflowed) = self.build_overflow_intrinsic(intrinsic_name, lhs, rhs)?;
+        Ok(self.build_optional_unless(function, overflowed, value, &Type::Int, "checked"))
+    }
+
+    // parse_bool(s): the runtime gives 1, 0, or -1 for text that isn't a boolean,
+    // which becomes None
+    fn build_parse_bool(&mut self, args: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
+        if args.len() != 1 {
+            return Err("parse_bool() takes exactly 1 argument".to_string());
+        }
+        let function = self.current_function.ok_or("parse_bool() outside of function")?;
+        let text = self.compile_expression(&args[0])?;
+        let parsed = self.call_runtime("str_parse_bool", &[text.into()]).into_int_value();
+        let i64_type = self.context.i64_type();
+        let invalid = self.builder
+            .build_int_compare(IntPredicate::SLT, parsed, i64_type.const_zero(), "not_bool")
+            .unwrap();
+        let value = self.builder
+            .build_int_truncate(parsed, self.context.bool_type(), "parsed_bool")
+            .unwrap();
+        Ok(self.build_optional_unless(function, invalid, value.into(), &Type::Bool, "parse_bool"))
+    }
+
+    // Helper: `value` boxed as an Optional of `inner`, or None (a null pointer) when
+    // `is_none` is set. Only the non-None path allocates the box
+    fn build_optional_unless(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        is_none: IntValue<'ctx>,
+        value: BasicValueEnum<'ctx>,
+        inner: &Type,
+        name: &str,
+    ) -> BasicValueEnum<'ctx> {
+        let some_block = self.context.append_basic_block(function, &format!("{}_some", name));
+        let merge_block = self.context.append_basic_block(function, &format!("{}_merge", name));
+        let none_end_block = self.builder.get_insert_block().unwrap();
+        self.builder.build_conditional_branch(is_none, merge_block, some_block).unwrap();
+
+        self.builder.position_at_end(some_block);
+        let boxed = self.coerce_to(value, &Type::Optional(Box::new(inner.clone())));
+        let some_end_block = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(merge_block).unwrap();
+
+        self.builder.position_at_end(merge_block);
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let none = ptr_type.const_null().as_basic_value_enum();
+        let phi = self.builder.build_phi(ptr_type, name).unwrap();
+        phi.add_incoming(&[(&none, none_end_block), (&boxed, some_end_block)]);
+        phi.as_basic_value()
+    }
+
+    // truthy(x): zero, an empty str, list or dict, and None are False; everything
+    // else is True. The typechecker only lets those types through
+    fn build_truthy(&mut self, args: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
+        if args.len() != 1 {
+            return Err("truthy() takes exactly 1 argument".to_string());
+        }
+        let value = self.compile_expression(&args[0])?;
+        let i64_type = self.context.i64_type();
+        let result = match value {
+            BasicValueEnum::FloatValue(float_val) => self.builder
+                .build_float_compare(FloatPredicate::UNE, float_val, self.context.f64_type().const_zero(), "truthy")
+                .unwrap(),
+            BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => int_val,
+            BasicValueEnum::IntValue(int_val) => self.builder
+                .build_int_compare(IntPredicate::NE, int_val, int_val.get_type().const_zero(), "truthy")
+                .unwrap(),
+            BasicValueEnum::PointerValue(ptr_val) => {
+                let length_fn = match self.declared_type(&args[0]) {
+                    Some(Type::Optional(_)) => {
+                        return Ok(self.builder.build_is_not_null(ptr_val, "truthy").unwrap().into());
+                    }
+                    Some(Type::Dict(_, _)) => "dict_length",
+                    _ if self.is_string_expression(&args[0]) => "str_length",
+                    _ if self.is_list_expression(&args[0]) => "list_length",
+                    _ => return Err("truthy() can't tell the type of its argument; assign it to a typed variable first".to_string()),
+                };
+                let length = self.call_runtime(length_fn, &[ptr_val.into()]).into_int_value();
+                self.builder
+                    .build_int_compare(IntPredicate::SGT, length, i64_type.const_zero(), "truthy")
+                    .unwrap()
+            }
+            _ => return Err("truthy() takes an int, float, bool, str, list, dict or optional value".to_string()),
+        };
+        Ok(result.into())
+    }
+
+    // Helper: Compare two pointer operands. Strings compare by content, ordered byte by
+    // byte by the sign of strcmp (so "B" < "a"). Lists, dicts and objects have no
+    // text to compare, so `==` and `!=` ask whether both sides are the same one.
+    // Optionals are equal when both are None or both hold equal values
+    fn build_pointer_comparison(
+        &self,
+        op: &BinaryOp,
+        left: &Expression,
+        right: &Expression,
+        left_ptr: PointerValue<'ctx>,
+        right_ptr: PointerValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let predicate = match op {
+            BinaryOp::Equal => IntPredicate::EQ,
+            BinaryOp::NotEqual => IntPredicate::NE,
+            BinaryOp::Less => IntPredicate::SLT,
+            BinaryOp::Greater => IntPredicate::SGT,
+            BinaryOp::LessEqual => IntPredicate::SLE,
+            _ => IntPredicate::SGE,
+        };
+        let (left_type, right_type) = (self.declared_type(left), self.declared_type(right));
+        let optional = [&left_type, &right_type].iter().any(|t| matches!(t, Some(Type::Optional(_))));
+        let unwrap_optional = |ws_type: Option<Type>| match ws_type {
+            Some(Type::Optional(inner)) => Some(*inner),
+            ws_type => ws_type,
+        };
+        let (left_type, right_type) = (unwrap_optional(left_type), unwrap_optional(right_type));
+
+        // None is a null pointer, so comparing identities needs no null check
+        let is_reference = |ws_type: &Option<Type>| matches!(ws_type, Some(Type::List(_) | Type::Dict(_, _) | Type::Custom(_)));
+        if is_reference(&left_type) || is_reference(&right_type) {
+            return Ok(self.builder
+                .build_int_compare(predicate, left_ptr, right_ptr, "same_ref")
+                .unwrap()
+                .as_basic_value_enum());
+        }
+
+        let value_type = match left_type.or(right_type) {
+            Some(value_type @ (Type::Str | Type::Int | Type::Float | Type::Bool | Type::BigInt | Type::Decimal))
+                if optional || value_type == Type::Str => value_type,
+            _ => return Err(format!("Cannot compile {:?}: the type of an operand is unknown to codegen", op)),
+        };
+        if !optional {
+            let order = self.call_runtime("strcmp", &[left_ptr.into(), right_ptr.into()]).into_int_value();
+            return Ok(self.builder
+                .build_int_compare(predicate, order, self.context.i32_type().const_zero(), "strcmp_cmp")
+                .unwrap()
+                .as_basic_value_enum());
+        }
+
+        // Optionals only have `==` and `!=`. The values are only read when both are
+        // present; otherwise they are equal if both are None
+        let function = self.current_function.ok_or("Comparison outside of function")?;
+        let left_present = self.builder.build_is_not_null(left_ptr, "left_present").unwrap();
+        let right_present = self.builder.build_is_not_null(right_ptr, "right_present").unwrap();
+        let both_present = self.builder.build_and(left_present, right_present, "both_present").unwrap();
+        let both_none = self.builder.build_int_compare(IntPredicate::EQ, left_ptr, right_ptr, "both_none").unwrap();
+        let check_block = self.builder.get_insert_block().unwrap();
+        let compare_block = self.context.append_basic_block(function, "optional_compare");
+        let merge_block = self.context.append_basic_block(function, "optional_compared");
+        self.builder.build_conditional_branch(both_present, compare_block, merge_block).unwrap();
+
+        self.builder.position_at_end(compare_block);
+        let values_equal = if value_type == Type::Str {
+            let order = self.call_runtime("strcmp", &[left_ptr.into(), right_ptr.into()]).into_int_value();
+            self.builder
+                .build_int_compare(IntPredicate::EQ, order, self.context.i32_type().const_zero(), "same_text")
+                .unwrap()
+        } else if matches!(value_type, Type::BigInt | Type::Decimal) {
+            // bigint? and decimal? are the runtime handle itself
+            let cmp = format!("{}_cmp", Self::runtime_number_prefix(&value_type));
+            let order = self.call_runtime(&cmp, &[left_ptr.into(), right_ptr.into()]).into_int_value();
+            self.builder
+                .build_int_compare(IntPredicate::EQ, order, self.context.i64_type().const_zero(), "same_number")
+                .unwrap()
+        } else {
+            // int?, float? and bool? hold their value in a box
+            let llvm_type = self.get_llvm_type(&value_type);
+            let left_val = self.builder.build_load(llvm_type, left_ptr, "left_unboxed").unwrap();
+            let right_val = self.builder.build_load(llvm_type, right_ptr, "right_unboxed").unwrap();
+            if value_type == Type::Float {
+                self.builder
+                    .build_float_compare(FloatPredicate::OEQ, left_val.into_float_value(), right_val.into_float_value(), "same_value")
+                    .unwrap()
+            } else {
+                self.builder
+                    .build_int_compare(IntPredicate::EQ, left_val.into_int_value(), right_val.into_int_value(), "same_value")
+                    .unwrap()
+            }
+        };
+        self.builder.build_unconditional_branch(merge_block).unwrap();
+
+        self.builder.position_at_end(merge_block);
+        let equal = self.builder.build_phi(self.context.bool_type(), "optional_equal").unwrap();
+        equal.add_incoming(&[(&both_none, check_block), (&values_equal, compare_block)]);
+        let equal = equal.as_basic_value().into_int_value();
+        Ok(if *op == BinaryOp::NotEqual {
+            self.builder.build_not(equal, "optional_not_equal").unwrap().as_basic_value_enum()
+        } else {
+            equal.as_basic_value_enum()
+        })
+    }
+
+    // int(s) / float(s) parse a str in the runtime, which raises on bad text. str(x)
+    // gives the same text an f-string would
+    fn build_conversion(&mut self, name: &str, args: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
+        if args.len() != 1 {
+            return Err(format!("{}() takes exactly 1 argument", name));
+        }
+        let value = self.compile_expression(&args[0])?;
+        match name {
+            "int" => Ok(self.call_runtime("str_to_int", &[value.into()])),
+            "float" => Ok(self.call_runtime("str_to_float", &[value.into()])),
+            _ => self.build_text(&args[0], value),
+        }
+    }
+
+    // Text of an already compiled value, as str() and f-strings show it: objects
+    // through to_string(), bools as True/False, numbers in their shortest form
+    fn build_text(&mut self, expr: &Expression, value: BasicValueEnum<'ctx>) -> Result<BasicValueEnum<'ctx>, String> {
+        if let Some(class_name) = self.printable_class(expr) {
+            return self.build_to_string_call(&class_name, value);
+        }
+        if let Some(ws_type) = self.runtime_number_type(expr) {
+            let to_str = format!("{}_to_str", Self::runtime_number_prefix(&ws_type));
+            return Ok(self.call_runtime(&to_str, &[value.into()]));
+        }
+        match value {
+            BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => {
+                let true_str = self.builder.build_global_string_ptr("True", "str_true").unwrap();
+                let false_str = self.builder.build_global_string_ptr("False", "str_false").unwrap();
+                Ok(self.builder
+                    .build_select(int_val, true_str.as_pointer_value(), false_str.as_pointer_value(), "bool_str")
+                    .unwrap())
+            }
+            BasicValueEnum::IntValue(_) => {
+                let widened = self.widen_to(value, self.context.i64_type().as_basic_type_enum());
+                Ok(self.call_runtime("int_to_str", &[widened.into()]))
+            }
+            BasicValueEnum::FloatValue(_) => Ok(self.call_runtime("float_to_str", &[value.into()])),
+            // Already a str
+            _ => Ok(value),
+        }
+    }
+
+    // format(template, ...): the arguments' text, as str() gives it, is collected in a
+    // temporary list[str] that the runtime fills the template's placeholders from
+    fn build_format(&mut self, args: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
+        let (template, values) = args.split_first().ok_or("format() takes a template and the values to fill it")?;
+        let template_val = self.compile_expression(template)?;
+        let plain = self.context.i64_type().const_int(ELEMS_PLAIN as u64, false);
+        let texts = self.call_runtime("list_create", &[plain.into()]).into_pointer_value();
+        for value in values {
+            let compiled = self.compile_expression(value)?;
+            let text = self.build_text(value, compiled)?;
+            self.build_list_store("push", &[texts.into()], text, Some(&Type::Str));
+        }
+        let result = self.call_runtime("str_format", &[template_val.into(), texts.into()]);
+        self.build_rc_release_inline(texts, &Type::List(Box::new(Type::Str)));
+        Ok(result)
+    }
+
+    // input() or input(prompt): print the prompt, then read a line from standard input
+    fn build_input(&mut self, args: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
+        let prompt = match args.first() {
+            Some(prompt) => self.compile_expression(prompt)?,
+            None => self.context.ptr_type(AddressSpace::default()).const_null().into(),
+        };
+        Ok(self.call_runtime("stdin_input", &[prompt.into()]))
+    }
+
+    // @memoize: build the cache key from the arguments and return early on a cache hit.
+    // The cache is a hidden global dict[str, int] created lazily on first call; results
+    // are stored as i64 (bools zero-extended, floats bitcast, strings as pointers).
+    fn build_memo_lookup(&mut self, name: &str, params: &[Parameter], return_type: &Type) -> Result<(), String> {
+        let function = self.current_function.ok_or("@memoize outside of function")?;
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+
+        // Hidden per-function cache global, initialized to null
+        let cache_global = self.module.add_global(ptr_type, None, &format!("__memo_cache_{}", name));
+        cache_global.set_initializer(&ptr_type.const_null());
+        cache_global.set_linkage(inkwell::module::Linkage::Internal);
+        let cache_ptr = cache_global.as_pointer_value();
+
+        // Create the dict on first call
+        let init_block = self.context.append_basic_block(function, "memo_init");
+        let ready_block = self.context.append_basic_block(function, "memo_ready");
+        let cache = self.builder.build_load(ptr_type, cache_ptr, "memo_cache").unwrap().into_pointer_value();
+        let is_null = self.builder.build_is_null(cache, "memo_cache_null").unwrap();
+        self.builder.build_conditional_branch(is_null, init_block, ready_block).unwrap();
+
+        self.builder.position_at_end(init_block);
+        let dict_create_fn = *self.functions.get("dict_create").unwrap();
+        let plain = self.context.i64_type().const_int(ELEMS_PLAIN as u64, false);
+        let new_cache = self.builder
+            .build_call(dict_create_fn, &[plain.into(), plain.into()], "memo_new_cache")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap();
+        self.builder.build_store(cache_ptr, new_cache).unwrap();
+        self.builder.build_unconditional_branch(ready_block).unwrap();
+
+        self.builder.position_at_end(ready_block);
+
+        // Build the key with a single sprintf: "i<int>|", "b<bool>|", "s<len>:<str>|" per argument.
+        // The length prefix keeps string arguments containing '|' from colliding.
+        let strlen_fn = *self.functions.get("strlen").unwrap();
+        let mut key_format = String::new();
+        let mut format_args: Vec<BasicMetadataValueEnum> = Vec::new();
+        let mut buffer_size = i64_type.const_int(1, false);
+        for param in params {
+            let (param_ptr, param_llvm_type, _) = *self.variables.get(&param.name).unwrap();
+            let value = self.builder.build_load(param_llvm_type, param_ptr, "memo_arg").unwrap();
+            match param.param_type {
+                Type::Int => {
+                    key_format.push_str("i%lld|");
+                    format_args.push(value.into());
+                    buffer_size = self.builder
+                        .build_int_add(buffer_size, i64_type.const_int(22, false), "memo_size")
+                        .unwrap();
+                }
+                Type::Bool => {
+                    key_format.push_str("b%lld|");
+                    let widened = self.builder
+                        .build_int_z_extend(value.into_int_value(), i64_type, "memo_bool")
+                        .unwrap();
+                    format_args.push(widened.into());
+                    buffer_size = self.builder
+                        .build_int_add(buffer_size, i64_type.const_int(3, false), "memo_size")
+                        .unwrap();
+                }
+                Type::Str => {
+                    key_format.push_str("s%lld:%s|");
+                    let len = self.builder
+                        .build_call(strlen_fn, &[value.into()], "memo_strlen")
+                        .unwrap()
+                        .try_as_basic_value()
+                        .left()
+                        .unwrap()
+                        .into_int_value();
+                    format_args.push(len.into());
+                    format_args.push(value.into());
+                    let with_prefix = self.builder
+                        .build_int_add(len, i64_type.const_int(23, false), "memo_str_size")
+                        .unwrap();
+                    buffer_size = self.builder.build_int_add(buffer_size, with_prefix, "memo_size").unwrap();
+                }
+                _ => return Err(format!("@memoize does not support parameter type {}", param.param_type)),
+            }
+        }
+
+        let malloc_fn = *self.functions.get("malloc").unwrap();
+        let key = self.builder
+            .build_call(malloc_fn, &[buffer_size.into()], "memo_key")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_pointer_value();
+        let fmt = self.builder.build_global_string_ptr(&key_format, "memo_key_fmt").unwrap();
+        let sprintf_fn = *self.functions.get("sprintf").unwrap();
+        let mut sprintf_args: Vec<BasicMetadataValueEnum> = vec![key.into(), fmt.as_pointer_value().into()];
+        sprintf_args.extend(format_args);
+        self.builder.build_call(sprintf_fn, &sprintf_args, "").unwrap();
+
+        // Cache hit: return the stored result without running the body
+        let cache = self.builder.build_load(ptr_type, cache_ptr, "memo_cache").unwrap();
+        let dict_has_fn = *self.functions.get("dict_has").unwrap();
+        let has = self.builder
+            .build_call(dict_has_fn, &[cache.into(), key.into()], "memo_has")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        let is_hit = self.builder
+            .build_int_compare(IntPredicate::NE, has, has.get_type().const_zero(), "memo_is_hit")
+            .unwrap();
+
+        let hit_block = self.context.append_basic_block(function, "memo_hit");
+        let miss_block = self.context.append_basic_block(function, "memo_miss");
+        self.builder.build_conditional_branch(is_hit, hit_block, miss_block).unwrap();
+
+        self.builder.position_at_end(hit_block);
+        let dict_get_fn = *self.functions.get("dict_get").unwrap();
+        let cached = self.builder
+            .build_call(dict_get_fn, &[cache.into(), key.into()], "memo_cached")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        let free_fn = *self.functions.get("free").unwrap();
+        self.builder.build_call(free_fn, &[key.into()], "").unwrap();
+        let pop_call_stack_fn = *self.functions.get("pop_call_stack").unwrap();
+        self.builder.build_call(pop_call_stack_fn, &[], "").unwrap();
+        let result = match return_type {
+            Type::Int => cached.as_basic_value_enum(),
+            Type::Bool => self.builder
+                .build_int_compare(IntPredicate::NE, cached, i64_type.const_zero(), "memo_bool_result")
+                .unwrap()
+                .as_basic_value_enum(),
+            Type::Float => self.builder
+                .build_bit_cast(cached, self.context.f64_type(), "memo_float_result")
+                .unwrap(),
+            _ => self.builder
+                .build_int_to_ptr(cached, ptr_type, "memo_str_result")
+                .unwrap()
+                .as_basic_value_enum(),
+        };
+        self.builder.build_return(Some(&result)).unwrap();
+
+        // Cache miss: keep the key for the store on return
+        self.builder.position_at_end(miss_block);
+        let key_alloca = self.builder.build_alloca(ptr_type, "memo_key_slot").unwrap();
+        self.builder.build_store(key_alloca, key).unwrap();
+
+        self.memo_state = Some(MemoState { cache_global: cache_ptr, key_alloca });
+        Ok(())
+    }
+
+    // @memoize: store the result under the key built on entry (no-op for other functions)
+    fn build_memo_store(&self, value: BasicValueEnum<'ctx>) {
+        let Some(state) = self.memo_state else {
+            return;
+        };
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+
+        let encoded = if value.is_pointer_value() {
+            self.builder
+                .build_ptr_to_int(value.into_pointer_value(), i64_type, "memo_encoded")
+                .unwrap()
+        } else if value.is_float_value() {
+            self.builder
+                .build_bit_cast(value, i64_type, "memo_encoded")
+                .unwrap()
+                .into_int_value()
+        } else {
+            self.builder
+                .build_int_z_extend_or_bit_cast(value.into_int_value(), i64_type, "memo_encoded")
+                .unwrap()
+        };
+
+        let cache = self.builder.build_load(ptr_type, state.cache_global, "memo_cache").unwrap();
+        let key = self.builder.build_load(ptr_type, state.key_alloca, "memo_key").unwrap();
+        let dict_set_fn = *self.functions.get("dict_set").unwrap();
+        self.builder
+            .build_call(dict_set_fn, &[cache.into(), key.into(), encoded.into()], "")
+            .unwrap();
+
+        // dict_set copies the key, so the buffer can be freed
+        let free_fn = *self.functions.get("free").unwrap();
+        self.builder.build_call(free_fn, &[key.into()], "").unwrap();
+    }
+
+    pub fn compile_program(&mut self, program: &Program) -> Result<(), String> {
+        self.declare_runtime_functions();
+
+        // Functions from compiled libraries are defined in their object code
+        for function in program.libraries.iter().flat_map(|library| &library.functions) {
+            let param_types: Vec<Type> = function.params.iter().map(|p| p.param_type.clone()).collect();
+            self.declare_external_function(&function.name, &param_types, &function.return_type);
+            self.function_params.insert(function.name.clone(), function.params.clone());
+            self.function_return_types.insert(function.name.clone(), function.return_type.clone());
+        }
+
+        self.names = FunctionNames::new(program);
+        for statement in &program.statements {
+            self.current_module = self.names.statement_module(statement);
+            self.compile_statement(statement)?;
+        }
+        self.current_module = None;
+
+        // Unwind tables for every function, so traces can walk through frames
+        // that optimization proves can't unwind
+        let uwtable = Attribute::get_named_enum_kind_id("uwtable");
+        for function in self.module.get_functions().filter(|f| f.count_basic_blocks() > 0) {
+            // 2 is uwtable(async), what clang emits
+            function.add_attribute(AttributeLoc::Function, self.context.create_enum_attribute(uwtable, 2));
         }
 
         // Finalize debug info
@@ -809,6 +3039,28 @@ impl<'ctx> CodeGen<'ctx> {
         Ok(())
     }
 
+    /// Mark a runtime function as free of side effects so LLVM can CSE repeated
+    /// calls and hoist them out of loops: it always returns, never unwinds, and
+    /// either only reads memory (`reads_memory`) or only looks at its arguments.
+    /// Only for functions that can't raise, since raising writes exception state.
+    fn add_pure_attributes(&self, function: FunctionValue<'ctx>, reads_memory: bool) {
+        let memory = Attribute::get_named_enum_kind_id("memory");
+        if memory != 0 {
+            // memory(...) packs two bits (ref = 1) for each of argmem, inaccessiblemem
+            // and other memory, so 0b010101 is memory(read)
+            let memory_effects = if reads_memory { 0b01_01_01 } else { 0 };
+            function.add_attribute(AttributeLoc::Function, self.context.create_enum_attribute(memory, memory_effects));
+        } else {
+            // LLVM before 16 spells these readonly/readnone
+            let kind_id = Attribute::get_named_enum_kind_id(if reads_memory { "readonly" } else { "readnone" });
+            function.add_attribute(AttributeLoc::Function, self.context.create_enum_attribute(kind_id, 0));
+        }
+        for name in ["nounwind", "willreturn", "nosync"] {
+            let kind_id = Attribute::get_named_enum_kind_id(name);
+            function.add_attribute(AttributeLoc::Function, self.context.create_enum_attribute(kind_id, 0));
+        }
+    }
+
     fn declare_printf(&mut self) {
         let i32_type = self.context.i32_type();
         let str_type = self.context.ptr_type(AddressSpace::default());
@@ -844,18 +3096,9 @@ impl<'ctx> CodeGen<'ctx> {
         // strlen(str) -> i64
         let strlen_type = i64_type.fn_type(&[ptr_type.into()], false);
         let strlen_fn = self.module.add_function("strlen", strlen_type, None);
+        self.add_pure_attributes(strlen_fn, true);
         self.functions.insert("strlen".to_string(), strlen_fn);
 
-        // strcpy(dest, src) -> ptr
-        let strcpy_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
-        let strcpy_fn = self.module.add_function("strcpy", strcpy_type, None);
-        self.functions.insert("strcpy".to_string(), strcpy_fn);
-
-        // strcat(dest, src) -> ptr
-        let strcat_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
-        let strcat_fn = self.module.add_function("strcat", strcat_type, None);
-        self.functions.insert("strcat".to_string(), strcat_fn);
-
         // sprintf(dest, format, ...) -> i32 (variadic)
         let i32_type = self.context.i32_type();
         let sprintf_type = i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], true);
@@ -865,6 +3108,7 @@ impl<'ctx> CodeGen<'ctx> {
         // strcmp(str1, str2) -> i32
         let strcmp_type = i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
         let strcmp_fn = self.module.add_function("strcmp", strcmp_type, None);
+        self.add_pure_attributes(strcmp_fn, true);
         self.functions.insert("strcmp".to_string(), strcmp_fn);
 
         // RC functions
@@ -882,6 +3126,11 @@ impl<'ctx> CodeGen<'ctx> {
         let rc_release_type = self.context.void_type().fn_type(&[ptr_type.into()], false);
         let rc_release_fn = self.module.add_function("rc_release", rc_release_type, None);
         self.functions.insert("rc_release".to_string(), rc_release_fn);
+
+        // rc_free(ptr) -> void (after an inline release reaches zero)
+        let rc_free_type = self.context.void_type().fn_type(&[ptr_type.into()], false);
+        let rc_free_fn = self.module.add_function("rc_free", rc_free_type, None);
+        self.functions.insert("rc_free".to_string(), rc_free_fn);
     }
 
     fn declare_builtin_functions(&mut self) {
@@ -950,68 +3199,79 @@ impl<'ctx> CodeGen<'ctx> {
         self.builder.position_at_end(merge_block);
         self.builder.build_return(None).unwrap();
         self.functions.insert("print_bool".to_string(), print_bool_fn);
-    }
 
-    fn declare_list_functions(&mut self) {
-        let ptr_type = self.context.ptr_type(AddressSpace::default());
-        let i64_type = self.context.i64_type();
-        let void_type = self.context.void_type();
-
-        // List structure in memory: { ptr data, i64 length, i64 capacity }
-        // Total size: 24 bytes (8 + 8 + 8)
-
-        // list_create_i64() -> ptr (creates empty list for i64 elements)
-        let list_create_type = ptr_type.fn_type(&[], false);
-        let list_create_fn = self.module.add_function("list_create_i64", list_create_type, None);
-        let entry = self.context.append_basic_block(list_create_fn, "entry");
+        // is_nan(float) -> bool: NaN is the only value unordered with itself
+        let float_check_type = self.context.bool_type().fn_type(&[f64_type.into()], false);
+        let is_nan_fn = self.module.add_function("is_nan", float_check_type, None);
+        let entry = self.context.append_basic_block(is_nan_fn, "entry");
         self.builder.position_at_end(entry);
-
-        // Allocate list struct (24 bytes) with RC
-        let rc_alloc = self.functions.get("rc_alloc").unwrap();
-        let struct_size = self.context.i64_type().const_int(24, false);
-        let list_ptr = self.builder
-            .build_call(*rc_alloc, &[struct_size.into()], "list_ptr")
+        let arg = is_nan_fn.get_nth_param(0).unwrap().into_float_value();
+        let is_nan = self.builder.build_float_compare(FloatPredicate::UNO, arg, arg, "is_nan").unwrap();
+        self.builder.build_return(Some(&is_nan)).unwrap();
+        self.functions.insert("is_nan".to_string(), is_nan_fn);
+
+        // is_finite(float) -> bool: |x| < inf is false for both infinities and NaN
+        let is_finite_fn = self.module.add_function("is_finite", float_check_type, None);
+        let entry = self.context.append_basic_block(is_finite_fn, "entry");
+        self.builder.position_at_end(entry);
+        let arg = is_finite_fn.get_nth_param(0).unwrap().into_float_value();
+        let fabs = Intrinsic::find("llvm.fabs").unwrap()
+            .get_declaration(&self.module, &[f64_type.into()])
+            .unwrap();
+        let magnitude = self.builder
+            .build_call(fabs, &[arg.into()], "magnitude")
             .unwrap()
             .try_as_basic_value()
             .left()
             .unwrap()
-            .into_pointer_value();
+            .into_float_value();
+        let is_finite = self.builder
+            .build_float_compare(FloatPredicate::OLT, magnitude, f64_type.const_float(f64::INFINITY), "is_finite")
+            .unwrap();
+        self.builder.build_return(Some(&is_finite)).unwrap();
+        self.functions.insert("is_finite".to_string(), is_finite_fn);
+
+        // float_to_bits(float) -> int and bits_to_float(int) -> float reinterpret the IEEE 754 bits
+        let to_bits_fn = self.module.add_function("float_to_bits", i64_type.fn_type(&[f64_type.into()], false), None);
+        let entry = self.context.append_basic_block(to_bits_fn, "entry");
+        self.builder.position_at_end(entry);
+        let arg = to_bits_fn.get_nth_param(0).unwrap();
+        let bits = self.builder.build_bit_cast(arg, i64_type, "bits").unwrap();
+        self.builder.build_return(Some(&bits)).unwrap();
+        self.functions.insert("float_to_bits".to_string(), to_bits_fn);
 
-        // Initialize: data = NULL, length = 0, capacity = 0
-        let zero = i64_type.const_zero();
-        let null_ptr = ptr_type.const_null();
+        let from_bits_fn = self.module.add_function("bits_to_float", f64_type.fn_type(&[i64_type.into()], false), None);
+        let entry = self.context.append_basic_block(from_bits_fn, "entry");
+        self.builder.position_at_end(entry);
+        let arg = from_bits_fn.get_nth_param(0).unwrap();
+        let value = self.builder.build_bit_cast(arg, f64_type, "value").unwrap();
+        self.builder.build_return(Some(&value)).unwrap();
+        self.functions.insert("bits_to_float".to_string(), from_bits_fn);
+
+        // byte_swap(int) -> int reverses the 8 bytes of an int
+        let bswap = Intrinsic::find("llvm.bswap").unwrap()
+            .get_declaration(&self.module, &[i64_type.into()])
+            .unwrap();
+        self.functions.insert("byte_swap".to_string(), bswap);
+    }
 
-        // Store data pointer (offset 0)
-        let data_ptr = self.builder.build_pointer_cast(list_ptr, ptr_type, "").unwrap();
-        self.builder.build_store(data_ptr, null_ptr).unwrap();
+    fn declare_list_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+        let void_type = self.context.void_type();
 
-        // Store length (offset 8)
-        let list_as_i64_ptr = self.builder.build_pointer_cast(list_ptr, ptr_type, "").unwrap();
-        let length_ptr = unsafe {
-            self.builder.build_gep(
-                ptr_type,
-                list_as_i64_ptr,
-                &[self.context.i64_type().const_int(1, false)],
-                "length_ptr"
-            ).unwrap()
-        };
-        let length_ptr_typed = self.builder.build_pointer_cast(length_ptr, ptr_type, "").unwrap();
-        self.builder.build_store(length_ptr_typed, zero).unwrap();
+        // List structure in memory: { ptr data, i64 length, i64 capacity, i64 elem_kind }
+        // Total size: 32 bytes; list_length below reads the length inline
 
-        // Store capacity (offset 16)
-        let capacity_ptr = unsafe {
-            self.builder.build_gep(
-                ptr_type,
-                list_as_i64_ptr,
-                &[self.context.i64_type().const_int(2, false)],
-                "capacity_ptr"
-            ).unwrap()
-        };
-        let capacity_ptr_typed = self.builder.build_pointer_cast(capacity_ptr, ptr_type, "").unwrap();
-        self.builder.build_store(capacity_ptr_typed, zero).unwrap();
+        // list_create(elem_kind) -> ptr (elem_kind is one of rc.rs's ELEMS_*)
+        let list_create_type = ptr_type.fn_type(&[i64_type.into()], false);
+        let list_create_fn = self.module.add_function("list_create", list_create_type, None);
+        self.functions.insert("list_create".to_string(), list_create_fn);
 
-        self.builder.build_return(Some(&list_ptr)).unwrap();
-        self.functions.insert("list_create_i64".to_string(), list_create_fn);
+        // list_free(list_ptr) -> void: the last release, which releases the elements too
+        let list_free_type = void_type.fn_type(&[ptr_type.into()], false);
+        let list_free_fn = self.module.add_function("list_free", list_free_type, None);
+        self.functions.insert("list_free".to_string(), list_free_fn);
 
         // list_push_i64(list_ptr, value) -> void
         let list_push_type = void_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
@@ -1028,14 +3288,86 @@ impl<'ctx> CodeGen<'ctx> {
         let list_set_fn = self.module.add_function("list_set_i64", list_set_type, None);
         self.functions.insert("list_set_i64".to_string(), list_set_fn);
 
-        // list_pop_i64(list_ptr) -> i64
-        let list_pop_type = i64_type.fn_type(&[ptr_type.into()], false);
-        let list_pop_fn = self.module.add_function("list_pop_i64", list_pop_type, None);
-        self.functions.insert("list_pop_i64".to_string(), list_pop_fn);
+        // list_pop_i64(list_ptr) -> i64
+        let list_pop_type = i64_type.fn_type(&[ptr_type.into()], false);
+        let list_pop_fn = self.module.add_function("list_pop_i64", list_pop_type, None);
+        self.functions.insert("list_pop_i64".to_string(), list_pop_fn);
+
+        // Typed variants for floats and pointers (strings, collections, objects):
+        // list_push_f64(list_ptr, value), list_get_ptr(list_ptr, index) -> ptr, ...
+        let typed_elements = [
+            ("f64", self.context.f64_type().as_basic_type_enum()),
+            ("ptr", ptr_type.as_basic_type_enum()),
+        ];
+        for (suffix, elem_type) in typed_elements {
+            let push_type = void_type.fn_type(&[ptr_type.into(), elem_type.into()], false);
+            let get_type = elem_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+            let set_type = void_type.fn_type(&[ptr_type.into(), i64_type.into(), elem_type.into()], false);
+            let pop_type = elem_type.fn_type(&[ptr_type.into()], false);
+            for (operation, fn_type) in [("push", push_type), ("get", get_type), ("set", set_type), ("pop", pop_type)] {
+                let name = format!("list_{}_{}", operation, suffix);
+                let function = self.module.add_function(&name, fn_type, None);
+                self.functions.insert(name, function);
+            }
+        }
+
+        // list_insert_i64(list_ptr, index, value), list_remove_at_ptr(list_ptr, index) -> ptr,
+        // list_index_of_f64(list_ptr, value) -> i64, ... for every element type
+        let all_elements = [("i64", i64_type.as_basic_type_enum())].into_iter().chain(typed_elements);
+        for (suffix, elem_type) in all_elements {
+            let insert_type = void_type.fn_type(&[ptr_type.into(), i64_type.into(), elem_type.into()], false);
+            let remove_at_type = elem_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+            let index_of_type = i64_type.fn_type(&[ptr_type.into(), elem_type.into()], false);
+            for (operation, fn_type) in [("insert", insert_type), ("remove_at", remove_at_type), ("index_of", index_of_type)] {
+                let name = format!("list_{}_{}", operation, suffix);
+                let function = self.module.add_function(&name, fn_type, None);
+                self.functions.insert(name, function);
+            }
+        }
+
+        // list_index_of_str(list_ptr, str) -> i64: strings compare by contents
+        let list_index_of_str_type = i64_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let list_index_of_str_fn = self.module.add_function("list_index_of_str", list_index_of_str_type, None);
+        self.functions.insert("list_index_of_str".to_string(), list_index_of_str_fn);
+
+        // list_clear(list_ptr) -> void: releases the elements, keeps the capacity
+        let list_clear_type = void_type.fn_type(&[ptr_type.into()], false);
+        let list_clear_fn = self.module.add_function("list_clear", list_clear_type, None);
+        self.functions.insert("list_clear".to_string(), list_clear_fn);
+
+        // list_reverse(list_ptr) -> void: in place
+        let list_reverse_type = void_type.fn_type(&[ptr_type.into()], false);
+        let list_reverse_fn = self.module.add_function("list_reverse", list_reverse_type, None);
+        self.functions.insert("list_reverse".to_string(), list_reverse_fn);
+
+        // list_extend(list_ptr, other_ptr) -> void: appends other's elements
+        let list_extend_type = void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let list_extend_fn = self.module.add_function("list_extend", list_extend_type, None);
+        self.functions.insert("list_extend".to_string(), list_extend_fn);
+
+        // list_concat(left_ptr, right_ptr) -> ptr (returns new list)
+        let list_concat_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let list_concat_fn = self.module.add_function("list_concat", list_concat_type, None);
+        self.functions.insert("list_concat".to_string(), list_concat_fn);
+
+        // list_sort_i64(list_ptr), list_sort_f64, list_sort_str -> void: in place, ascending
+        let list_sort_type = void_type.fn_type(&[ptr_type.into()], false);
+        for suffix in ["i64", "f64", "str"] {
+            let name = format!("list_sort_{}", suffix);
+            let function = self.module.add_function(&name, list_sort_type, None);
+            self.functions.insert(name, function);
+        }
+
+        // list_sort_by(list_ptr, before_fn) -> void: in place, stable; before_fn(a, b) is
+        // nonzero when slot a goes first
+        let list_sort_by_type = void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let list_sort_by_fn = self.module.add_function("list_sort_by", list_sort_by_type, None);
+        self.functions.insert("list_sort_by".to_string(), list_sort_by_fn);
 
         // list_length(list_ptr) -> i64
         let list_length_type = i64_type.fn_type(&[ptr_type.into()], false);
         let list_length_fn = self.module.add_function("list_length", list_length_type, None);
+        self.add_pure_attributes(list_length_fn, true);
         let entry = self.context.append_basic_block(list_length_fn, "entry");
         self.builder.position_at_end(entry);
 
@@ -1063,15 +3395,25 @@ impl<'ctx> CodeGen<'ctx> {
         let i32_type = self.context.i32_type();
         let void_type = self.context.void_type();
 
-        // Dict structure in memory: { ptr buckets, i64 capacity, i64 length }
+        // Dict structure in memory: { ptr buckets, i64 capacity, i64 length, i64 key_kind }
         // Uses hash table with separate chaining for collisions
-        // Total size: 24 bytes (8 + 8 + 8)
-
-        // dict_create() -> ptr (creates empty hash table dict)
-        let dict_create_type = ptr_type.fn_type(&[], false);
+        // Total size: 32 bytes (8 + 8 + 8 + 8)
+        //
+        // Keys and values travel as i64 slots (see to_dict_slot). str keys use
+        // dict_set/dict_get/dict_has; int, sized int, bool and object keys use the
+        // _int variants, which hash the slot itself.
+
+        // dict_create(key_elems, value_elems) -> ptr (creates empty hash table dict;
+        // the kinds are rc.rs's ELEMS_*)
+        let dict_create_type = ptr_type.fn_type(&[i64_type.into(), i64_type.into()], false);
         let dict_create_fn = self.module.add_function("dict_create", dict_create_type, None);
         self.functions.insert("dict_create".to_string(), dict_create_fn);
 
+        // dict_free(dict_ptr) -> void: the last release, which releases keys and values too
+        let dict_free_type = void_type.fn_type(&[ptr_type.into()], false);
+        let dict_free_fn = self.module.add_function("dict_free", dict_free_type, None);
+        self.functions.insert("dict_free".to_string(), dict_free_fn);
+
         // dict_set(dict_ptr, key_str, value_int) -> void
         let dict_set_type = void_type.fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false);
         let dict_set_fn = self.module.add_function("dict_set", dict_set_type, None);
@@ -1085,17 +3427,80 @@ impl<'ctx> CodeGen<'ctx> {
         // dict_has(dict_ptr, key_str) -> i32 (returns 1 if exists, 0 otherwise)
         let dict_has_type = i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
         let dict_has_fn = self.module.add_function("dict_has", dict_has_type, None);
+        self.add_pure_attributes(dict_has_fn, true);
         self.functions.insert("dict_has".to_string(), dict_has_fn);
 
+        // dict_set_int(dict_ptr, key, value) -> void
+        let dict_set_int_type = void_type.fn_type(&[ptr_type.into(), i64_type.into(), i64_type.into()], false);
+        let dict_set_int_fn = self.module.add_function("dict_set_int", dict_set_int_type, None);
+        self.functions.insert("dict_set_int".to_string(), dict_set_int_fn);
+
+        // dict_get_int(dict_ptr, key) -> i64
+        let dict_get_int_type = i64_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+        let dict_get_int_fn = self.module.add_function("dict_get_int", dict_get_int_type, None);
+        self.functions.insert("dict_get_int".to_string(), dict_get_int_fn);
+
+        // dict_has_int(dict_ptr, key) -> i32
+        let dict_has_int_type = i32_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+        let dict_has_int_fn = self.module.add_function("dict_has_int", dict_has_int_type, None);
+        self.add_pure_attributes(dict_has_int_fn, true);
+        self.functions.insert("dict_has_int".to_string(), dict_has_int_fn);
+
         // dict_length(dict_ptr) -> i64 (runtime function)
         let dict_length_type = i64_type.fn_type(&[ptr_type.into()], false);
         let dict_length_fn = self.module.add_function("dict_length", dict_length_type, None);
+        self.add_pure_attributes(dict_length_fn, true);
         self.functions.insert("dict_length".to_string(), dict_length_fn);
 
         // dict_get_keys(dict_ptr) -> ptr (returns list of keys)
         let dict_get_keys_type = ptr_type.fn_type(&[ptr_type.into()], false);
         let dict_get_keys_fn = self.module.add_function("dict_get_keys", dict_get_keys_type, None);
         self.functions.insert("dict_get_keys".to_string(), dict_get_keys_fn);
+
+        // dict_keys(dict_ptr), dict_values(dict_ptr), dict_items(dict_ptr) -> ptr: new
+        // lists that own their elements
+        let dict_list_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        for name in ["dict_keys", "dict_values", "dict_items"] {
+            let function = self.module.add_function(name, dict_list_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // dict_remove(dict_ptr, key_str) -> i32 (1 if the key was there)
+        let dict_remove_type = i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let dict_remove_fn = self.module.add_function("dict_remove", dict_remove_type, None);
+        self.functions.insert("dict_remove".to_string(), dict_remove_fn);
+
+        // dict_remove_int(dict_ptr, key) -> i32
+        let dict_remove_int_type = i32_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+        let dict_remove_int_fn = self.module.add_function("dict_remove_int", dict_remove_int_type, None);
+        self.functions.insert("dict_remove_int".to_string(), dict_remove_int_fn);
+
+        // dict_pop(dict_ptr, key_str, default) -> i64: the value, whose reference
+        // passes to the caller, or the default (retained)
+        let dict_pop_type = i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false);
+        let dict_pop_fn = self.module.add_function("dict_pop", dict_pop_type, None);
+        self.functions.insert("dict_pop".to_string(), dict_pop_fn);
+
+        // dict_pop_int(dict_ptr, key, default) -> i64
+        let dict_pop_int_type = i64_type.fn_type(&[ptr_type.into(), i64_type.into(), i64_type.into()], false);
+        let dict_pop_int_fn = self.module.add_function("dict_pop_int", dict_pop_int_type, None);
+        self.functions.insert("dict_pop_int".to_string(), dict_pop_int_fn);
+
+        // dict_get_or(dict_ptr, key_str, default) -> i64: the value or the default,
+        // retained for the caller
+        let dict_get_or_type = i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false);
+        let dict_get_or_fn = self.module.add_function("dict_get_or", dict_get_or_type, None);
+        self.functions.insert("dict_get_or".to_string(), dict_get_or_fn);
+
+        // dict_get_or_int(dict_ptr, key, default) -> i64
+        let dict_get_or_int_type = i64_type.fn_type(&[ptr_type.into(), i64_type.into(), i64_type.into()], false);
+        let dict_get_or_int_fn = self.module.add_function("dict_get_or_int", dict_get_or_int_type, None);
+        self.functions.insert("dict_get_or_int".to_string(), dict_get_or_int_fn);
+
+        // dict_clear(dict_ptr) -> void
+        let dict_clear_type = void_type.fn_type(&[ptr_type.into()], false);
+        let dict_clear_fn = self.module.add_function("dict_clear", dict_clear_type, None);
+        self.functions.insert("dict_clear".to_string(), dict_clear_fn);
     }
 
     fn declare_string_functions(&mut self) {
@@ -1106,8 +3511,14 @@ impl<'ctx> CodeGen<'ctx> {
         // str_length(str_ptr) -> i64
         let str_length_type = i64_type.fn_type(&[ptr_type.into()], false);
         let str_length_fn = self.module.add_function("str_length", str_length_type, None);
+        self.add_pure_attributes(str_length_fn, true);
         self.functions.insert("str_length".to_string(), str_length_fn);
 
+        // str_byte_length(str_ptr) -> i64
+        let str_byte_length_fn = self.module.add_function("str_byte_length", str_length_type, None);
+        self.add_pure_attributes(str_byte_length_fn, true);
+        self.functions.insert("str_byte_length".to_string(), str_byte_length_fn);
+
         // str_upper(str_ptr) -> ptr (returns new string)
         let str_upper_type = ptr_type.fn_type(&[ptr_type.into()], false);
         let str_upper_fn = self.module.add_function("str_upper", str_upper_type, None);
@@ -1121,6 +3532,7 @@ impl<'ctx> CodeGen<'ctx> {
         // str_contains(str_ptr, substring_ptr) -> i32
         let str_contains_type = i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
         let str_contains_fn = self.module.add_function("str_contains", str_contains_type, None);
+        self.add_pure_attributes(str_contains_fn, true);
         self.functions.insert("str_contains".to_string(), str_contains_fn);
 
         // str_char_at(str_ptr, index) -> ptr (returns single-char string)
@@ -1128,6 +3540,11 @@ impl<'ctx> CodeGen<'ctx> {
         let str_char_at_fn = self.module.add_function("str_char_at", str_char_at_type, None);
         self.functions.insert("str_char_at".to_string(), str_char_at_fn);
 
+        // str_chars(str_ptr) -> ptr (returns new list[str], one per character)
+        let str_chars_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        let str_chars_fn = self.module.add_function("str_chars", str_chars_type, None);
+        self.functions.insert("str_chars".to_string(), str_chars_fn);
+
         // list_slice_i64(list_ptr, start, end, step) -> ptr (returns new list)
         let list_slice_type = ptr_type.fn_type(&[ptr_type.into(), i64_type.into(), i64_type.into(), i64_type.into()], false);
         let list_slice_fn = self.module.add_function("list_slice_i64", list_slice_type, None);
@@ -1137,6 +3554,253 @@ impl<'ctx> CodeGen<'ctx> {
         let str_slice_type = ptr_type.fn_type(&[ptr_type.into(), i64_type.into(), i64_type.into(), i64_type.into()], false);
         let str_slice_fn = self.module.add_function("str_slice", str_slice_type, None);
         self.functions.insert("str_slice".to_string(), str_slice_fn);
+
+        // str_repeat(str_ptr, count) -> ptr (returns new string)
+        let str_repeat_type = ptr_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+        let str_repeat_fn = self.module.add_function("str_repeat", str_repeat_type, None);
+        self.functions.insert("str_repeat".to_string(), str_repeat_fn);
+
+        // str_split(str_ptr, sep_ptr) -> ptr (returns new list[str])
+        let str_split_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let str_split_fn = self.module.add_function("str_split", str_split_type, None);
+        self.functions.insert("str_split".to_string(), str_split_fn);
+
+        // str_join(sep_ptr, list_ptr) -> ptr (returns new string)
+        let str_join_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let str_join_fn = self.module.add_function("str_join", str_join_type, None);
+        self.functions.insert("str_join".to_string(), str_join_fn);
+
+        // str_parse_bool(str_ptr) -> i64 (1, 0, or -1 if not a boolean), exposed as parse_bool()
+        let str_parse_bool_type = i64_type.fn_type(&[ptr_type.into()], false);
+        let str_parse_bool_fn = self.module.add_function("str_parse_bool", str_parse_bool_type, None);
+        self.functions.insert("str_parse_bool".to_string(), str_parse_bool_fn);
+
+        // str_to_int(str_ptr) -> i64 / str_to_float(str_ptr) -> f64, exposed as int() and
+        // float(); both raise on bad text, so they aren't marked pure
+        let str_to_int_type = i64_type.fn_type(&[ptr_type.into()], false);
+        let str_to_int_fn = self.module.add_function("str_to_int", str_to_int_type, None);
+        self.functions.insert("str_to_int".to_string(), str_to_int_fn);
+        let str_to_float_type = self.context.f64_type().fn_type(&[ptr_type.into()], false);
+        let str_to_float_fn = self.module.add_function("str_to_float", str_to_float_type, None);
+        self.functions.insert("str_to_float".to_string(), str_to_float_fn);
+
+        // int_to_str(i64) -> ptr (returns new string), used by str()
+        let int_to_str_type = ptr_type.fn_type(&[i64_type.into()], false);
+        let int_to_str_fn = self.module.add_function("int_to_str", int_to_str_type, None);
+        self.functions.insert("int_to_str".to_string(), int_to_str_fn);
+
+        // str_replace(str_ptr, old_ptr, new_ptr) -> ptr (returns new string)
+        let str_replace_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false);
+        let str_replace_fn = self.module.add_function("str_replace", str_replace_type, None);
+        self.functions.insert("str_replace".to_string(), str_replace_fn);
+
+        // str_find(str_ptr, sub_ptr) -> i64 (character index, or -1)
+        let str_find_type = i64_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let str_find_fn = self.module.add_function("str_find", str_find_type, None);
+        self.add_pure_attributes(str_find_fn, true);
+        self.functions.insert("str_find".to_string(), str_find_fn);
+
+        // str_starts_with / str_ends_with(str_ptr, affix_ptr) -> i32
+        let str_affix_type = i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        for name in ["str_starts_with", "str_ends_with"] {
+            let function = self.module.add_function(name, str_affix_type, None);
+            self.add_pure_attributes(function, true);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // str_strip / lstrip / rstrip(str_ptr) -> ptr (returns new string)
+        let str_strip_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        for name in ["str_strip", "str_lstrip", "str_rstrip"] {
+            let function = self.module.add_function(name, str_strip_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // str_concat(left_ptr, right_ptr) -> ptr (returns new string), for str + str
+        let str_concat_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let str_concat_fn = self.module.add_function("str_concat", str_concat_type, None);
+        self.functions.insert("str_concat".to_string(), str_concat_fn);
+
+        // str_format(template_ptr, args_list) -> ptr (returns new string)
+        let str_format_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let str_format_fn = self.module.add_function("str_format", str_format_type, None);
+        self.functions.insert("str_format".to_string(), str_format_fn);
+
+        // f-string buffer: str_builder_new(capacity) -> ptr, str_builder_append(builder, str_ptr),
+        // str_builder_finish(builder) -> ptr (returns new string and frees the buffer)
+        let builder_new_type = ptr_type.fn_type(&[i64_type.into()], false);
+        let builder_new_fn = self.module.add_function("str_builder_new", builder_new_type, None);
+        self.functions.insert("str_builder_new".to_string(), builder_new_fn);
+        let builder_append_type = self.context.void_type().fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let builder_append_fn = self.module.add_function("str_builder_append", builder_append_type, None);
+        self.functions.insert("str_builder_append".to_string(), builder_append_fn);
+        let builder_finish_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        let builder_finish_fn = self.module.add_function("str_builder_finish", builder_finish_type, None);
+        self.functions.insert("str_builder_finish".to_string(), builder_finish_fn);
+
+        // StringBuilder handles (used by std/strings.ws)
+        let handle_new_type = i64_type.fn_type(&[i64_type.into()], false);
+        let handle_new_fn = self.module.add_function("string_builder_new", handle_new_type, None);
+        self.functions.insert("string_builder_new".to_string(), handle_new_fn);
+        let handle_append_type = self.context.void_type().fn_type(&[i64_type.into(), ptr_type.into()], false);
+        let handle_append_fn = self.module.add_function("string_builder_append", handle_append_type, None);
+        self.functions.insert("string_builder_append".to_string(), handle_append_fn);
+        let handle_length_type = i64_type.fn_type(&[i64_type.into()], false);
+        let handle_length_fn = self.module.add_function("string_builder_length", handle_length_type, None);
+        self.functions.insert("string_builder_length".to_string(), handle_length_fn);
+        let handle_build_type = ptr_type.fn_type(&[i64_type.into()], false);
+        let handle_build_fn = self.module.add_function("string_builder_build", handle_build_type, None);
+        self.functions.insert("string_builder_build".to_string(), handle_build_fn);
+        let handle_void_type = self.context.void_type().fn_type(&[i64_type.into()], false);
+        for name in ["string_builder_clear", "string_builder_free"] {
+            let function = self.module.add_function(name, handle_void_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+    }
+
+    fn declare_float_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+        let f64_type = self.context.f64_type();
+
+        // float_to_str(value) -> ptr (shortest %g-style text)
+        let float_to_str_type = ptr_type.fn_type(&[f64_type.into()], false);
+        let float_to_str_fn = self.module.add_function("float_to_str", float_to_str_type, None);
+        self.functions.insert("float_to_str".to_string(), float_to_str_fn);
+
+        // float_format(value, digits) -> ptr, exposed as format_float()
+        let float_format_type = ptr_type.fn_type(&[f64_type.into(), i64_type.into()], false);
+        let float_format_fn = self.module.add_function("float_format", float_format_type, None);
+        self.functions.insert("format_float".to_string(), float_format_fn);
+
+        // float_round(value, digits) -> f64, exposed as round()
+        let float_round_type = f64_type.fn_type(&[f64_type.into(), i64_type.into()], false);
+        let float_round_fn = self.module.add_function("float_round", float_round_type, None);
+        self.add_pure_attributes(float_round_fn, false);
+        self.functions.insert("round".to_string(), float_round_fn);
+
+        // floor/ceil/trunc map straight onto the LLVM intrinsics
+        for (name, intrinsic_name) in [("floor", "llvm.floor"), ("ceil", "llvm.ceil"), ("trunc", "llvm.trunc")] {
+            let intrinsic = Intrinsic::find(intrinsic_name).unwrap();
+            let function = intrinsic.get_declaration(&self.module, &[f64_type.into()]).unwrap();
+            self.functions.insert(name.to_string(), function);
+        }
+    }
+
+    fn declare_bigint_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+        let f64_type = self.context.f64_type();
+
+        // Conversions: bigint_from_int(i64), bigint_from_str(ptr), exposed as parse_bigint()
+        let from_int_type = ptr_type.fn_type(&[i64_type.into()], false);
+        let from_int_fn = self.module.add_function("bigint_from_int", from_int_type, None);
+        self.functions.insert("bigint_from_int".to_string(), from_int_fn);
+
+        let unary_ptr_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        let from_str_fn = self.module.add_function("bigint_from_str", unary_ptr_type, None);
+        self.functions.insert("parse_bigint".to_string(), from_str_fn);
+
+        let to_str_fn = self.module.add_function("bigint_to_str", unary_ptr_type, None);
+        self.functions.insert("bigint_to_str".to_string(), to_str_fn);
+
+        let to_int_type = i64_type.fn_type(&[ptr_type.into()], false);
+        let to_int_fn = self.module.add_function("bigint_to_int", to_int_type, None);
+        self.add_pure_attributes(to_int_fn, true);
+        self.functions.insert("bigint_to_int".to_string(), to_int_fn);
+
+        let to_float_type = f64_type.fn_type(&[ptr_type.into()], false);
+        let to_float_fn = self.module.add_function("bigint_to_float", to_float_type, None);
+        self.add_pure_attributes(to_float_fn, true);
+        self.functions.insert("bigint_to_float".to_string(), to_float_fn);
+
+        // Arithmetic: each call returns a new bigint
+        let binary_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        for name in ["bigint_add", "bigint_sub", "bigint_mul", "bigint_div", "bigint_rem"] {
+            let function = self.module.add_function(name, binary_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        let neg_fn = self.module.add_function("bigint_neg", unary_ptr_type, None);
+        self.functions.insert("bigint_neg".to_string(), neg_fn);
+
+        // bigint_pow(base, exponent: i64)
+        let pow_type = ptr_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+        let pow_fn = self.module.add_function("bigint_pow", pow_type, None);
+        self.functions.insert("bigint_pow".to_string(), pow_fn);
+
+        // bigint_pow_mod(base, exponent, modulus), exposed as pow_mod()
+        let pow_mod_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false);
+        let pow_mod_fn = self.module.add_function("bigint_pow_mod", pow_mod_type, None);
+        self.functions.insert("pow_mod".to_string(), pow_mod_fn);
+
+        // bigint_cmp(a, b) -> -1, 0 or 1
+        let cmp_type = i64_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let cmp_fn = self.module.add_function("bigint_cmp", cmp_type, None);
+        self.add_pure_attributes(cmp_fn, true);
+        self.functions.insert("bigint_cmp".to_string(), cmp_fn);
+
+        // bigint_free(value) -> void: the last release
+        let free_type = self.context.void_type().fn_type(&[ptr_type.into()], false);
+        let free_fn = self.module.add_function("bigint_free", free_type, None);
+        self.functions.insert("bigint_free".to_string(), free_fn);
+    }
+
+    fn declare_decimal_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+        let f64_type = self.context.f64_type();
+
+        // Conversions: decimal_from_str(ptr) is exposed as parse_decimal()
+        let from_int_type = ptr_type.fn_type(&[i64_type.into()], false);
+        let from_int_fn = self.module.add_function("decimal_from_int", from_int_type, None);
+        self.functions.insert("decimal_from_int".to_string(), from_int_fn);
+
+        let unary_ptr_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        let from_str_fn = self.module.add_function("decimal_from_str", unary_ptr_type, None);
+        self.functions.insert("parse_decimal".to_string(), from_str_fn);
+
+        let from_float_type = ptr_type.fn_type(&[f64_type.into()], false);
+        let from_float_fn = self.module.add_function("decimal_from_float", from_float_type, None);
+        self.functions.insert("decimal_from_float".to_string(), from_float_fn);
+
+        let to_str_fn = self.module.add_function("decimal_to_str", unary_ptr_type, None);
+        self.functions.insert("decimal_to_str".to_string(), to_str_fn);
+
+        let to_int_type = i64_type.fn_type(&[ptr_type.into()], false);
+        let to_int_fn = self.module.add_function("decimal_to_int", to_int_type, None);
+        self.add_pure_attributes(to_int_fn, true);
+        self.functions.insert("decimal_to_int".to_string(), to_int_fn);
+
+        let to_float_type = f64_type.fn_type(&[ptr_type.into()], false);
+        let to_float_fn = self.module.add_function("decimal_to_float", to_float_type, None);
+        self.add_pure_attributes(to_float_fn, true);
+        self.functions.insert("decimal_to_float".to_string(), to_float_fn);
+
+        // Arithmetic: each call returns a new decimal
+        let binary_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        for name in ["decimal_add", "decimal_sub", "decimal_mul", "decimal_div"] {
+            let function = self.module.add_function(name, binary_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        let neg_fn = self.module.add_function("decimal_neg", unary_ptr_type, None);
+        self.functions.insert("decimal_neg".to_string(), neg_fn);
+
+        // decimal_round(value, digits: i64), the d.round(n) method
+        let round_type = ptr_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+        let round_fn = self.module.add_function("decimal_round", round_type, None);
+        self.functions.insert("decimal_round".to_string(), round_fn);
+
+        // decimal_cmp(a, b) -> -1, 0 or 1
+        let cmp_type = i64_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let cmp_fn = self.module.add_function("decimal_cmp", cmp_type, None);
+        self.add_pure_attributes(cmp_fn, true);
+        self.functions.insert("decimal_cmp".to_string(), cmp_fn);
+
+        // decimal_free(value) -> void: the last release
+        let free_type = self.context.void_type().fn_type(&[ptr_type.into()], false);
+        let free_fn = self.module.add_function("decimal_free", free_type, None);
+        self.functions.insert("decimal_free".to_string(), free_fn);
     }
 
     fn declare_io_functions(&mut self) {
@@ -1173,6 +3837,41 @@ impl<'ctx> CodeGen<'ctx> {
         let file_exists_type = i64_type.fn_type(&[ptr_type.into()], false);
         let file_exists_fn = self.module.add_function("file_exists", file_exists_type, None);
         self.functions.insert("file_exists".to_string(), file_exists_fn);
+
+        // file_seek(handle, offset, whence) -> i64 (new position)
+        let file_seek_type = i64_type.fn_type(&[i64_type.into(), i64_type.into(), i64_type.into()], false);
+        let file_seek_fn = self.module.add_function("file_seek", file_seek_type, None);
+        self.functions.insert("file_seek".to_string(), file_seek_fn);
+
+        // file_read_bytes(handle, n) -> ptr (new list[u8])
+        let file_read_bytes_type = ptr_type.fn_type(&[i64_type.into(), i64_type.into()], false);
+        let file_read_bytes_fn = self.module.add_function("file_read_bytes", file_read_bytes_type, None);
+        self.functions.insert("file_read_bytes".to_string(), file_read_bytes_fn);
+
+        // file_read_lines(handle) -> ptr (new list[str])
+        let file_read_lines_type = ptr_type.fn_type(&[i64_type.into()], false);
+        let file_read_lines_fn = self.module.add_function("file_read_lines", file_read_lines_type, None);
+        self.functions.insert("file_read_lines".to_string(), file_read_lines_fn);
+
+        // file_write_bytes(handle, list_ptr) -> void
+        let file_write_bytes_type = void_type.fn_type(&[i64_type.into(), ptr_type.into()], false);
+        let file_write_bytes_fn = self.module.add_function("file_write_bytes", file_write_bytes_type, None);
+        self.functions.insert("file_write_bytes".to_string(), file_write_bytes_fn);
+
+        // file_flush(handle) -> void
+        let file_flush_type = void_type.fn_type(&[i64_type.into()], false);
+        let file_flush_fn = self.module.add_function("file_flush", file_flush_type, None);
+        self.functions.insert("file_flush".to_string(), file_flush_fn);
+
+        // stdin_input(prompt_ptr) -> ptr (new string), for input()
+        let stdin_input_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        let stdin_input_fn = self.module.add_function("stdin_input", stdin_input_type, None);
+        self.functions.insert("stdin_input".to_string(), stdin_input_fn);
+
+        // stdin_read_line() -> ptr (new string, or null at end of input), for read_line()
+        let stdin_read_line_type = ptr_type.fn_type(&[], false);
+        let stdin_read_line_fn = self.module.add_function("stdin_read_line", stdin_read_line_type, None);
+        self.functions.insert("stdin_read_line".to_string(), stdin_read_line_fn);
     }
 
     fn declare_cli_functions(&mut self) {
@@ -1286,6 +3985,183 @@ impl<'ctx> CodeGen<'ctx> {
         self.functions.insert("http_response_free".to_string(), free_fn);
     }
 
+    fn declare_smtp_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+
+        // smtp_send(host: ptr, port: i64, security: ptr, username: ptr, password: ptr,
+        //           from: ptr, to: ptr, subject: ptr, body: ptr) -> ptr (error, empty on success)
+        let send_type = ptr_type.fn_type(
+            &[
+                ptr_type.into(),
+                i64_type.into(),
+                ptr_type.into(),
+                ptr_type.into(),
+                ptr_type.into(),
+                ptr_type.into(),
+                ptr_type.into(),
+                ptr_type.into(),
+                ptr_type.into(),
+            ],
+            false,
+        );
+        let send_fn = self.module.add_function("smtp_send", send_type, None);
+        self.functions.insert("smtp_send".to_string(), send_fn);
+    }
+
+    fn declare_compress_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let void_type = self.context.void_type();
+
+        // compress_gzip / gunzip / zlib / unzlib(data: ptr) -> ptr (new list[u8])
+        let list_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        for name in ["compress_gzip", "compress_gunzip", "compress_zlib", "compress_unzlib"] {
+            let function = self.module.add_function(name, list_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // compress_read_gzip(path: ptr) -> ptr
+        let read_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        let read_fn = self.module.add_function("compress_read_gzip", read_type, None);
+        self.functions.insert("compress_read_gzip".to_string(), read_fn);
+
+        // compress_write_gzip(path: ptr, content: ptr) -> void
+        let write_type = void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let write_fn = self.module.add_function("compress_write_gzip", write_type, None);
+        self.functions.insert("compress_write_gzip".to_string(), write_fn);
+    }
+
+    fn declare_random_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+
+        // random_uuid4() -> ptr
+        let uuid_type = ptr_type.fn_type(&[], false);
+        let uuid_fn = self.module.add_function("random_uuid4", uuid_type, None);
+        self.functions.insert("random_uuid4".to_string(), uuid_fn);
+
+        // random_hex_string / random_token_string(length: i64) -> ptr
+        let string_type = ptr_type.fn_type(&[i64_type.into()], false);
+        for name in ["random_hex_string", "random_token_string"] {
+            let function = self.module.add_function(name, string_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // random_seed(seed: i64) -> void
+        let seed_type = self.context.void_type().fn_type(&[i64_type.into()], false);
+        let seed_fn = self.module.add_function("random_seed", seed_type, None);
+        self.functions.insert("random_seed".to_string(), seed_fn);
+
+        // random_int_between(lo: i64, hi: i64) -> i64
+        let int_type = i64_type.fn_type(&[i64_type.into(), i64_type.into()], false);
+        let int_fn = self.module.add_function("random_int_between", int_type, None);
+        self.functions.insert("random_int_between".to_string(), int_fn);
+
+        // random_unit_float() -> f64
+        let float_type = self.context.f64_type().fn_type(&[], false);
+        let float_fn = self.module.add_function("random_unit_float", float_type, None);
+        self.functions.insert("random_unit_float".to_string(), float_fn);
+
+        // list_shuffle(list_ptr) -> void, for `items.shuffle()`
+        let shuffle_type = self.context.void_type().fn_type(&[ptr_type.into()], false);
+        let shuffle_fn = self.module.add_function("list_shuffle", shuffle_type, None);
+        self.functions.insert("list_shuffle".to_string(), shuffle_fn);
+    }
+
+    fn declare_fs_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+        let void_type = self.context.void_type();
+
+        // fs_glob(pattern: ptr) -> ptr (new list[str])
+        let glob_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        let glob_fn = self.module.add_function("fs_glob", glob_type, None);
+        self.functions.insert("fs_glob".to_string(), glob_fn);
+
+        // fs_list_dir(path: ptr) -> ptr (new list[str])
+        let list_dir_fn = self.module.add_function("fs_list_dir", glob_type, None);
+        self.functions.insert("fs_list_dir".to_string(), list_dir_fn);
+
+        // fs_mkdir / mkdirs / remove_file(path: ptr) -> void
+        let path_type = void_type.fn_type(&[ptr_type.into()], false);
+        for name in ["fs_mkdir", "fs_mkdirs", "fs_remove_file"] {
+            let function = self.module.add_function(name, path_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // fs_remove_dir(path: ptr, recursive: i64) -> void
+        let remove_dir_type = void_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+        let remove_dir_fn = self.module.add_function("fs_remove_dir", remove_dir_type, None);
+        self.functions.insert("fs_remove_dir".to_string(), remove_dir_fn);
+
+        // fs_rename / copy(from: ptr, to: ptr) -> void
+        let two_paths_type = void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        for name in ["fs_rename", "fs_copy"] {
+            let function = self.module.add_function(name, two_paths_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // fs_file_size / is_dir / is_file(path: ptr) -> i64
+        let query_type = i64_type.fn_type(&[ptr_type.into()], false);
+        for name in ["fs_file_size", "fs_is_dir", "fs_is_file"] {
+            let function = self.module.add_function(name, query_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+    }
+
+    fn declare_regex_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+        let void_type = self.context.void_type();
+
+        // regex_compile(pattern: ptr) -> i64 (handle)
+        let compile_type = i64_type.fn_type(&[ptr_type.into()], false);
+        let compile_fn = self.module.add_function("regex_compile", compile_type, None);
+        self.functions.insert("regex_compile".to_string(), compile_fn);
+
+        // regex_is_match(handle: i64, text: ptr) -> i64 (1 or 0)
+        let is_match_type = i64_type.fn_type(&[i64_type.into(), ptr_type.into()], false);
+        let is_match_fn = self.module.add_function("regex_is_match", is_match_type, None);
+        self.functions.insert("regex_is_match".to_string(), is_match_fn);
+
+        // regex_find(handle, text) -> ptr (new string, or null)
+        // regex_find_all / regex_captures / regex_split(handle, text) -> ptr (new list[str])
+        let text_type = ptr_type.fn_type(&[i64_type.into(), ptr_type.into()], false);
+        for name in ["regex_find", "regex_find_all", "regex_captures", "regex_split"] {
+            let function = self.module.add_function(name, text_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // regex_replace(handle: i64, text: ptr, replacement: ptr) -> ptr (new string)
+        let replace_type = ptr_type.fn_type(&[i64_type.into(), ptr_type.into(), ptr_type.into()], false);
+        let replace_fn = self.module.add_function("regex_replace", replace_type, None);
+        self.functions.insert("regex_replace".to_string(), replace_fn);
+
+        // regex_free(handle: i64) -> void
+        let free_type = void_type.fn_type(&[i64_type.into()], false);
+        let free_fn = self.module.add_function("regex_free", free_type, None);
+        self.functions.insert("regex_free".to_string(), free_fn);
+    }
+
+    fn declare_bytes_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+
+        // bytes_pack_be / le(value: i64, size: i64) -> ptr (new list[u8]), exposed as to_bytes_be / le()
+        let pack_type = ptr_type.fn_type(&[i64_type.into(), i64_type.into()], false);
+        // bytes_unpack_be / le(bytes: ptr) -> i64, exposed as from_bytes_be / le()
+        let unpack_type = i64_type.fn_type(&[ptr_type.into()], false);
+        for (name, runtime_name, fn_type) in [
+            ("to_bytes_be", "bytes_pack_be", pack_type),
+            ("to_bytes_le", "bytes_pack_le", pack_type),
+            ("from_bytes_be", "bytes_unpack_be", unpack_type),
+            ("from_bytes_le", "bytes_unpack_le", unpack_type),
+        ] {
+            let function = self.module.add_function(runtime_name, fn_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+    }
+
     fn mark_builtin_pure_functions(&mut self) {
         // Phase 4: Mark functions that don't cause their RC parameters to escape
         // These functions either:
@@ -1299,12 +4175,44 @@ impl<'ctx> CodeGen<'ctx> {
         self.pure_functions.insert("list_push_i64".to_string());
         self.pure_functions.insert("list_set_i64".to_string());
         self.pure_functions.insert("list_pop_i64".to_string());
+        self.pure_functions.insert("list_get_f64".to_string());
+        self.pure_functions.insert("list_push_f64".to_string());
+        self.pure_functions.insert("list_set_f64".to_string());
+        self.pure_functions.insert("list_pop_f64".to_string());
+        self.pure_functions.insert("list_get_ptr".to_string());
+        self.pure_functions.insert("list_pop_ptr".to_string());
+        self.pure_functions.insert("list_insert_i64".to_string());
+        self.pure_functions.insert("list_insert_f64".to_string());
+        self.pure_functions.insert("list_clear".to_string());
+        for suffix in ["i64", "f64", "ptr"] {
+            self.pure_functions.insert(format!("list_remove_at_{}", suffix));
+            self.pure_functions.insert(format!("list_index_of_{}", suffix));
+        }
+        self.pure_functions.insert("list_index_of_str".to_string());
+        for suffix in ["i64", "f64", "str"] {
+            self.pure_functions.insert(format!("list_sort_{}", suffix));
+        }
+        self.pure_functions.insert("list_reverse".to_string());
+        self.pure_functions.insert("list_extend".to_string());
+        self.pure_functions.insert("list_concat".to_string());
 
         // Dict functions - all non-escaping
         self.pure_functions.insert("dict_length".to_string());
         self.pure_functions.insert("dict_get".to_string());
         self.pure_functions.insert("dict_set".to_string());
         self.pure_functions.insert("dict_has".to_string());
+        self.pure_functions.insert("dict_get_int".to_string());
+        self.pure_functions.insert("dict_has_int".to_string());
+        self.pure_functions.insert("dict_keys".to_string());
+        self.pure_functions.insert("dict_values".to_string());
+        self.pure_functions.insert("dict_items".to_string());
+        self.pure_functions.insert("dict_remove".to_string());
+        self.pure_functions.insert("dict_remove_int".to_string());
+        self.pure_functions.insert("dict_pop".to_string());
+        self.pure_functions.insert("dict_pop_int".to_string());
+        self.pure_functions.insert("dict_clear".to_string());
+        self.pure_functions.insert("dict_get_or".to_string());
+        self.pure_functions.insert("dict_get_or_int".to_string());
 
         // String functions - all non-escaping for input strings
         self.pure_functions.insert("str_length".to_string());
@@ -1312,6 +4220,22 @@ impl<'ctx> CodeGen<'ctx> {
         self.pure_functions.insert("str_lower".to_string());
         self.pure_functions.insert("str_contains".to_string());
         self.pure_functions.insert("str_char_at".to_string());
+        self.pure_functions.insert("str_byte_length".to_string());
+        self.pure_functions.insert("str_chars".to_string());
+        self.pure_functions.insert("str_split".to_string());
+        self.pure_functions.insert("str_join".to_string());
+        self.pure_functions.insert("str_strip".to_string());
+        self.pure_functions.insert("str_parse_bool".to_string());
+        self.pure_functions.insert("str_replace".to_string());
+        self.pure_functions.insert("str_find".to_string());
+        self.pure_functions.insert("str_starts_with".to_string());
+        self.pure_functions.insert("str_ends_with".to_string());
+        self.pure_functions.insert("str_lstrip".to_string());
+        self.pure_functions.insert("str_rstrip".to_string());
+        self.pure_functions.insert("str_concat".to_string());
+        self.pure_functions.insert("str_format".to_string());
+        self.pure_functions.insert("str_builder_append".to_string());
+        self.pure_functions.insert("string_builder_append".to_string());
 
         // Print functions - non-escaping
         self.pure_functions.insert("print_int".to_string());
@@ -1326,6 +4250,256 @@ impl<'ctx> CodeGen<'ctx> {
         self.pure_functions.insert("file_write".to_string());
         self.pure_functions.insert("file_close".to_string());
         self.pure_functions.insert("file_exists".to_string());
+        self.pure_functions.insert("file_seek".to_string());
+        self.pure_functions.insert("file_write_bytes".to_string());
+        self.pure_functions.insert("file_flush".to_string());
+        self.pure_functions.insert("stdin_input".to_string());
+        self.pure_functions.insert("stdin_read_line".to_string());
+
+        // Functions returning new lists (compression, config, CSV, file bytes and lines, fs_glob and list_dir, regex, to_bytes) - read their input and build new values
+        for name in OWNED_LIST_BUILTINS {
+            self.pure_functions.insert(name.to_string());
+        }
+        self.pure_functions.insert("from_bytes_be".to_string());
+        self.pure_functions.insert("from_bytes_le".to_string());
+        self.pure_functions.insert("compress_read_gzip".to_string());
+        self.pure_functions.insert("compress_write_gzip".to_string());
+
+        // Regex matching - reads the text and builds new strings
+        for name in ["regex_compile", "regex_is_match", "regex_find", "regex_replace"] {
+            self.pure_functions.insert(name.to_string());
+        }
+
+        // Random identifiers and numbers - take no RC parameters, and a shuffle
+        // only reorders the list it is given
+        for name in [
+            "random_uuid4",
+            "random_hex_string",
+            "random_token_string",
+            "random_seed",
+            "random_int_between",
+            "random_unit_float",
+            "list_shuffle",
+        ] {
+            self.pure_functions.insert(name.to_string());
+        }
+
+        // Clock and date functions - only read the format and text strings
+        for name in ["time_now", "time_now_ms", "time_monotonic", "time_sleep_ms", "time_format", "time_parse"] {
+            self.pure_functions.insert(name.to_string());
+        }
+
+        // Environment variables - copy the name and value strings
+        for name in ["env_get", "env_set", "env_unset"] {
+            self.pure_functions.insert(name.to_string());
+        }
+
+        // Subprocesses - copy the program, arguments and input before running
+        for name in ["process_run", "process_exit_code", "process_stdout", "process_stderr", "process_free"] {
+            self.pure_functions.insert(name.to_string());
+        }
+
+        // TCP sockets - copy the host and data strings
+        for name in [
+            "tcp_connect",
+            "tcp_listen",
+            "tcp_accept",
+            "tcp_read",
+            "tcp_read_line",
+            "tcp_write",
+            "tcp_set_timeout",
+            "tcp_local_port",
+            "tcp_close",
+        ] {
+            self.pure_functions.insert(name.to_string());
+        }
+
+        // CSV formatting - reads the fields and builds a new string
+        self.pure_functions.insert("csv_format_row".to_string());
+
+        // Path manipulation - reads the paths and builds new strings
+        for name in ["path_join", "path_dirname", "path_basename", "path_extension", "path_normalize", "path_absolute"] {
+            self.pure_functions.insert(name.to_string());
+        }
+
+        // File system operations - only read the path strings
+        for name in [
+            "fs_mkdir",
+            "fs_mkdirs",
+            "fs_remove_file",
+            "fs_remove_dir",
+            "fs_rename",
+            "fs_copy",
+            "fs_file_size",
+            "fs_is_dir",
+            "fs_is_file",
+        ] {
+            self.pure_functions.insert(name.to_string());
+        }
+    }
+
+    fn declare_time_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+
+        // time_now() / time_now_ms() -> i64 (Unix seconds / milliseconds)
+        let now_type = i64_type.fn_type(&[], false);
+        for name in ["time_now", "time_now_ms"] {
+            let function = self.module.add_function(name, now_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // time_monotonic() -> f64 (seconds)
+        let monotonic_type = self.context.f64_type().fn_type(&[], false);
+        let monotonic_fn = self.module.add_function("time_monotonic", monotonic_type, None);
+        self.functions.insert("time_monotonic".to_string(), monotonic_fn);
+
+        // time_sleep_ms(ms: i64) -> void
+        let sleep_type = self.context.void_type().fn_type(&[i64_type.into()], false);
+        let sleep_fn = self.module.add_function("time_sleep_ms", sleep_type, None);
+        self.functions.insert("time_sleep_ms".to_string(), sleep_fn);
+
+        // time_format(seconds: i64, format: ptr) -> ptr (new string)
+        let format_type = ptr_type.fn_type(&[i64_type.into(), ptr_type.into()], false);
+        let format_fn = self.module.add_function("time_format", format_type, None);
+        self.functions.insert("time_format".to_string(), format_fn);
+
+        // time_parse(text: ptr, format: ptr) -> i64 (seconds)
+        let parse_type = i64_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let parse_fn = self.module.add_function("time_parse", parse_type, None);
+        self.functions.insert("time_parse".to_string(), parse_fn);
+    }
+
+    fn declare_os_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let void_type = self.context.void_type();
+
+        // env_get(name: ptr) -> ptr (new string, or null if not set)
+        let get_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        let get_fn = self.module.add_function("env_get", get_type, None);
+        self.functions.insert("env_get".to_string(), get_fn);
+
+        // env_set(name: ptr, value: ptr) -> void
+        let set_type = void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let set_fn = self.module.add_function("env_set", set_type, None);
+        self.functions.insert("env_set".to_string(), set_fn);
+
+        // env_unset(name: ptr) -> void
+        let unset_type = void_type.fn_type(&[ptr_type.into()], false);
+        let unset_fn = self.module.add_function("env_unset", unset_type, None);
+        self.functions.insert("env_unset".to_string(), unset_fn);
+    }
+
+    fn declare_process_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+
+        // process_run(program: ptr, args: ptr (list[str]), stdin: ptr) -> i64 (handle)
+        let run_type = i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false);
+        let run_fn = self.module.add_function("process_run", run_type, None);
+        self.functions.insert("process_run".to_string(), run_fn);
+
+        // process_exit_code(handle: i64) -> i64
+        let exit_code_type = i64_type.fn_type(&[i64_type.into()], false);
+        let exit_code_fn = self.module.add_function("process_exit_code", exit_code_type, None);
+        self.functions.insert("process_exit_code".to_string(), exit_code_fn);
+
+        // process_stdout / process_stderr(handle: i64) -> ptr (new string)
+        let output_type = ptr_type.fn_type(&[i64_type.into()], false);
+        for name in ["process_stdout", "process_stderr"] {
+            let function = self.module.add_function(name, output_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // process_free(handle: i64) -> void
+        let free_type = self.context.void_type().fn_type(&[i64_type.into()], false);
+        let free_fn = self.module.add_function("process_free", free_type, None);
+        self.functions.insert("process_free".to_string(), free_fn);
+    }
+
+    fn declare_path_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+
+        // path_join(head: ptr, tail: ptr) -> ptr (new string)
+        let join_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        let join_fn = self.module.add_function("path_join", join_type, None);
+        self.functions.insert("path_join".to_string(), join_fn);
+
+        // path_dirname / basename / extension / normalize / absolute(path: ptr) -> ptr (new string)
+        let part_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        for name in ["path_dirname", "path_basename", "path_extension", "path_normalize", "path_absolute"] {
+            let function = self.module.add_function(name, part_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+    }
+
+    fn declare_csv_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+
+        // csv_parse(text: ptr, delimiter: ptr) -> ptr (new list[list[str]])
+        // csv_format_row(fields: ptr (list[str]), delimiter: ptr) -> ptr (new string)
+        let csv_type = ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false);
+        for name in ["csv_parse", "csv_format_row"] {
+            let function = self.module.add_function(name, csv_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+    }
+
+    fn declare_config_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+
+        // config_parse_toml / config_parse_ini(text: ptr) -> ptr (new dict[str, dict[str, str]])
+        // config_list_items(value: ptr) -> ptr (new list[str])
+        let parse_type = ptr_type.fn_type(&[ptr_type.into()], false);
+        for name in ["config_parse_toml", "config_parse_ini", "config_list_items"] {
+            let function = self.module.add_function(name, parse_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+    }
+
+    fn declare_net_functions(&mut self) {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let i64_type = self.context.i64_type();
+        let void_type = self.context.void_type();
+
+        // tcp_connect / tcp_listen(host: ptr, port: i64) -> i64 (handle)
+        let open_type = i64_type.fn_type(&[ptr_type.into(), i64_type.into()], false);
+        for name in ["tcp_connect", "tcp_listen"] {
+            let function = self.module.add_function(name, open_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // tcp_accept / tcp_local_port(handle: i64) -> i64
+        let handle_type = i64_type.fn_type(&[i64_type.into()], false);
+        for name in ["tcp_accept", "tcp_local_port"] {
+            let function = self.module.add_function(name, handle_type, None);
+            self.functions.insert(name.to_string(), function);
+        }
+
+        // tcp_read(handle: i64, max_bytes: i64) -> ptr (new string)
+        let read_type = ptr_type.fn_type(&[i64_type.into(), i64_type.into()], false);
+        let read_fn = self.module.add_function("tcp_read", read_type, None);
+        self.functions.insert("tcp_read".to_string(), read_fn);
+
+        // tcp_read_line(handle: i64) -> ptr (new string, or null at the end)
+        let read_line_type = ptr_type.fn_type(&[i64_type.into()], false);
+        let read_line_fn = self.module.add_function("tcp_read_line", read_line_type, None);
+        self.functions.insert("tcp_read_line".to_string(), read_line_fn);
+
+        // tcp_write(handle: i64, data: ptr) -> void
+        let write_type = void_type.fn_type(&[i64_type.into(), ptr_type.into()], false);
+        let write_fn = self.module.add_function("tcp_write", write_type, None);
+        self.functions.insert("tcp_write".to_string(), write_fn);
+
+        // tcp_set_timeout(handle: i64, ms: i64) -> void
+        let timeout_type = void_type.fn_type(&[i64_type.into(), i64_type.into()], false);
+        let timeout_fn = self.module.add_function("tcp_set_timeout", timeout_type, None);
+        self.functions.insert("tcp_set_timeout".to_string(), timeout_fn);
+
+        // tcp_close(handle: i64) -> void
+        let close_type = void_type.fn_type(&[i64_type.into()], false);
+        let close_fn = self.module.add_function("tcp_close", close_type, None);
+        self.functions.insert("tcp_close".to_string(), close_fn);
     }
 
     fn declare_runtime_error_functions(&mut self) {
@@ -1352,6 +4526,19 @@ impl<'ctx> CodeGen<'ctx> {
         let exception_raise_fn = self.module.add_function("exception_raise", exception_raise_type, None);
         self.functions.insert("exception_raise".to_string(), exception_raise_fn);
 
+        // exception_raise_from(type, message, file, line, cause) -> noreturn
+        let exception_raise_from_type = void_type.fn_type(
+            &[ptr_type.into(), ptr_type.into(), ptr_type.into(), i64_type.into(), ptr_type.into()],
+            false
+        );
+        let exception_raise_from_fn = self.module.add_function("exception_raise_from", exception_raise_from_type, None);
+        self.functions.insert("exception_raise_from".to_string(), exception_raise_from_fn);
+
+        // exception_reraise(exception) -> noreturn
+        let exception_reraise_type = void_type.fn_type(&[ptr_type.into()], false);
+        let exception_reraise_fn = self.module.add_function("exception_reraise", exception_reraise_type, None);
+        self.functions.insert("exception_reraise".to_string(), exception_reraise_fn);
+
         // exception_push_handler(jmp_buf) -> void
         let exception_push_handler_type = void_type.fn_type(&[ptr_type.into()], false);
         let exception_push_handler_fn = self.module.add_function("exception_push_handler", exception_push_handler_type, None);
@@ -1380,10 +4567,19 @@ impl<'ctx> CodeGen<'ctx> {
         // setjmp(jmp_buf) -> i32
         let setjmp_type = i32_type.fn_type(&[ptr_type.into()], false);
         let setjmp_fn = self.module.add_function("setjmp", setjmp_type, None);
+        let returns_twice = Attribute::get_named_enum_kind_id("returns_twice");
+        setjmp_fn.add_attribute(AttributeLoc::Function, self.context.create_enum_attribute(returns_twice, 0));
         self.functions.insert("setjmp".to_string(), setjmp_fn);
     }
 
     fn compile_statement(&mut self, statement: &Statement) -> Result<(), String> {
+        // Statements after a return, raise, break or continue never run, and the
+        // block they would go in already ends
+        let after_terminator = self.builder.get_insert_block().is_some_and(|block| block.get_terminator().is_some());
+        if after_terminator && self.current_function.is_some() && !matches!(statement, Statement::FunctionDef { .. }) {
+            return Ok(());
+        }
+
         match statement {
             Statement::VarDecl {
                 name,
@@ -1393,30 +4589,47 @@ impl<'ctx> CodeGen<'ctx> {
                 let var_type = self.get_llvm_type(type_annotation);
 
                 // Check if this is a REPL variable (already declared as external global)
-                let ptr = if let Some((existing_ptr, _, _)) = self.variables.get(name) {
+                let (ptr, redeclared) = if let Some((existing_ptr, _, _)) = self.variables.get(name) {
                     // REPL variable: use existing global pointer
-                    *existing_ptr
+                    (*existing_ptr, false)
                 } else {
-                    // Normal variable: create local alloca
-                    self.builder.build_alloca(var_type, name).unwrap()
+                    // Normal variable: create local alloca. In a loop body the
+                    // declaration runs again, and the last iteration's value is
+                    // released before the slot is reused
+                    let ptr = self.build_entry_alloca(var_type, name, self.is_rc_type(type_annotation));
+                    (ptr, self.is_rc_type(type_annotation) && self.loop_nesting_depth > 0)
                 };
 
-                if let Some(init_expr) = initializer {
-                    let init_value = self.compile_expression(init_expr)?;
+                let init_value = match initializer {
+                    Some(init_expr) => {
+                        let init_value = self.compile_expression_as(init_expr, type_annotation)?;
 
-                    // For RC types, retain the initial value (it starts with ref_count=1 from allocation)
-                    // No need to retain here since the allocation already gives us ownership
+                        // For RC types, a new allocation already gives us ownership (it starts
+                        // with ref_count=1); a reference read from elsewhere is retained
+                        if self.is_rc_type(type_annotation) && init_value.is_pointer_value() && self.is_borrowed_value(init_expr) {
+                            self.build_rc_retain_nullable(init_value.into_pointer_value());
+                        }
+                        Some(init_value)
+                    }
+                    None => None,
+                };
 
+                if redeclared {
+                    let old_val = self.builder.build_load(var_type, ptr, "old_val").unwrap();
+                    self.build_rc_release_nullable(old_val.into_pointer_value(), type_annotation);
+                }
+                if let Some(init_value) = init_value {
                     self.builder.build_store(ptr, init_value).unwrap();
-                } else {
+                } else if self.is_rc_type(type_annotation) {
                     // Initialize RC types to null to prevent releasing garbage
-                    if self.is_rc_type(type_annotation) {
-                        let null_ptr = self.context.ptr_type(AddressSpace::default()).const_null();
-                        self.builder.build_store(ptr, null_ptr).unwrap();
-                    }
+                    let null_ptr = self.context.ptr_type(AddressSpace::default()).const_null();
+                    self.builder.build_store(ptr, null_ptr).unwrap();
                 }
 
                 self.variables.insert(name.clone(), (ptr, var_type, type_annotation.clone()));
+                if self.is_rc_type(type_annotation) {
+                    self.owned_variables.insert(name.clone());
+                }
                 Ok(())
             }
 
@@ -1425,6 +4638,8 @@ impl<'ctx> CodeGen<'ctx> {
                 params,
                 return_type,
                 body,
+                decorators,
+                ..
             } => {
                 let param_types: Vec<BasicMetadataTypeEnum> = params
                     .iter()
@@ -1445,19 +4660,12 @@ impl<'ctx> CodeGen<'ctx> {
                     name.clone()
                 };
 
-                // Mangle function names to avoid C symbol conflicts
-                // Exception: "main" is the C entry point, can't be mangled
-                let mangled_name = if name == "main" {
-                    name.clone()
-                } else {
-                    format!("ws_{}", name)
-                };
-
-                let function = self.module.add_function(&mangled_name, fn_type, None);
+                let function = self.module.add_function(&self.symbol_name(name), fn_type, None);
                 self.functions.insert(function_key.clone(), function);
 
                 // Store function parameters for named args/defaults handling
-                self.function_params.insert(function_key, params.clone());
+                self.function_params.insert(function_key.clone(), params.clone());
+                self.function_return_types.insert(function_key, return_type.clone());
 
                 // Create debug info for this function
                 let di_file = self.compile_unit.get_file();
@@ -1485,12 +4693,26 @@ impl<'ctx> CodeGen<'ctx> {
                 // Attach debug info to the function
                 function.set_subprogram(di_subprogram);
 
+                // @inline / @noinline: translate to LLVM function attributes
+                for decorator in decorators {
+                    let attribute_name = match decorator.name.as_str() {
+                        "inline" => "alwaysinline",
+                        "noinline" => "noinline",
+                        _ => continue,
+                    };
+                    let kind_id = Attribute::get_named_enum_kind_id(attribute_name);
+                    let attribute = self.context.create_enum_attribute(kind_id, 0);
+                    function.add_attribute(AttributeLoc::Function, attribute);
+                }
+
                 // Save previous scope and set current scope to this function
                 let saved_debug_scope = self.current_debug_scope;
                 self.current_debug_scope = Some(di_subprogram);
 
                 let entry = self.context.append_basic_block(function, "entry");
                 self.builder.position_at_end(entry);
+                // The builder still has the caller's location; entry code must use this function's scope
+                self.set_debug_line(1);
 
                 // Push function name onto call stack for stack traces
                 let func_name_str = self.builder.build_global_string_ptr(name, "func_name").unwrap();
@@ -1510,9 +4732,11 @@ impl<'ctx> CodeGen<'ctx> {
                     .collect();
                 self.variables.clear();
                 self.variables.extend(repl_vars);
+                let saved_owned_variables = std::mem::take(&mut self.owned_variables);
                 self.moved_variables.clear(); // Clear moved set for new function scope
                 self.non_escaping_variables.clear(); // Clear non-escaping set for new function scope
                 self.current_function = Some(function);
+                self.current_return_type = Some(return_type.clone());
 
                 for (i, param) in params.iter().enumerate() {
                     let param_value = function.get_nth_param(i as u32).unwrap();
@@ -1525,13 +4749,20 @@ impl<'ctx> CodeGen<'ctx> {
                     self.variables.insert(param.name.clone(), (alloca, param_type, param.param_type.clone()));
                 }
 
+                // @memoize: return the cached result early if these arguments were seen before
+                if decorators.iter().any(|d| d.name == "memoize") {
+                    self.build_memo_lookup(name, params, return_type)?;
+                }
+
                 // OPTIMIZATION Phase 3: Escape Analysis
                 // Analyze which RC variables don't escape the function scope
                 // These can skip RC operations entirely
                 if body.len() < 100 {  // Only analyze simple functions
                     for stmt in body.iter() {
                         if let Statement::VarDecl { name, type_annotation, .. } = stmt {
-                            if self.is_rc_type(type_annotation) {
+                            // Objects are always released, so their deinit runs, and so are
+                            // Optional boxes (nothing else frees them)
+                            if matches!(type_annotation, Type::List(_) | Type::Dict(_, _)) {
                                 // Check if this variable escapes
                                 let escapes = body.iter().any(|s| self.statement_escapes_variable(s, name));
 
@@ -1544,7 +4775,6 @@ impl<'ctx> CodeGen<'ctx> {
                     }
                 }
 
-                let mut has_return = false;
                 for (i, stmt) in body.iter().enumerate() {
                     // OPTIMIZATION: Set remaining statements for last-use analysis
                     // Only analyze for simple cases to avoid performance issues with large functions
@@ -1555,12 +4785,10 @@ impl<'ctx> CodeGen<'ctx> {
                     };
 
                     self.compile_statement(stmt)?;
-                    if matches!(stmt, Statement::Return(_)) {
-                        has_return = true;
-                    }
                 }
 
-                if !has_return {
+                // Falling off the end returns a default value
+                if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
                     // Release all RC variables before returning
                     self.release_scope_variables();
 
@@ -1573,6 +4801,9 @@ impl<'ctx> CodeGen<'ctx> {
                     } else {
                         let default_value = match return_type {
                             Type::Int => self.context.i64_type().const_zero().as_basic_value_enum(),
+                            Type::I32 | Type::I16 | Type::U8 => {
+                                self.get_llvm_type(return_type).into_int_type().const_zero().as_basic_value_enum()
+                            }
                             Type::Float => self.context.f64_type().const_zero().as_basic_value_enum(),
                             Type::Bool => self.context.bool_type().const_zero().as_basic_value_enum(),
                             _ => self
@@ -1581,12 +4812,16 @@ impl<'ctx> CodeGen<'ctx> {
                                 .const_null()
                                 .as_basic_value_enum(),
                         };
+                        self.build_memo_store(default_value);
                         self.builder.build_return(Some(&default_value)).unwrap();
                     }
                 }
 
                 self.variables = saved_variables;
+                self.owned_variables = saved_owned_variables;
                 self.current_function = None;
+                self.current_return_type = None;
+                self.memo_state = None;
 
                 // Restore previous debug scope
                 self.current_debug_scope = saved_debug_scope;
@@ -1594,20 +4829,47 @@ impl<'ctx> CodeGen<'ctx> {
                 Ok(())
             }
 
-            Statement::ClassDef { name, fields, methods, .. } => {
+            Statement::ClassDef { name, base_class, fields, methods, .. } => {
+                // The constructor is registered under the class name
+                // Inherited fields and vtable slots come first, so a subclass
+                // pointer is also a valid pointer to its base class
+                let (mut field_names, mut ast_field_types, mut field_defaults, mut slots) = match base_class {
+                    Some(base) => {
+                        self.class_bases.insert(name.clone(), base.clone());
+                        (
+                            self.class_fields[base].clone(),
+                            self.class_field_types[base].clone(),
+                            self.class_field_defaults[base].clone(),
+                            self.vtable_slots[base].clone(),
+                        )
+                    }
+                    None => (Vec::new(), Vec::new(), Vec::new(), Vec::new()),
+                };
+
+                // Overrides reuse the base method's slot; new methods get a new one
+                for method in methods {
+                    if let Statement::FunctionDef { name: method_name, .. } = method {
+                        if !slots.contains(method_name) {
+                            slots.push(method_name.clone());
+                        }
+                    }
+                }
+                self.vtable_slots.insert(name.clone(), slots);
+
                 // Store field names in order
-                let field_names: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
+                field_names.extend(fields.iter().map(|f| f.name.clone()));
                 self.class_fields.insert(name.clone(), field_names);
 
                 // Store field types in order
-                let ast_field_types: Vec<Type> = fields.iter().map(|f| f.field_type.clone()).collect();
-                self.class_field_types.insert(name.clone(), ast_field_types);
+                ast_field_types.extend(fields.iter().map(|f| f.field_type.clone()));
+                self.class_field_types.insert(name.clone(), ast_field_types.clone());
 
-                // Create LLVM struct type for the class
-                let field_types: Vec<BasicTypeEnum> = fields
-                    .iter()
-                    .map(|f| self.get_llvm_type(&f.field_type))
-                    .collect();
+                field_defaults.extend(fields.iter().map(|f| f.default_value.clone()));
+                self.class_field_defaults.insert(name.clone(), field_defaults);
+
+                // Create LLVM struct type for the class: the vtable pointer, then the fields
+                let mut field_types: Vec<BasicTypeEnum> = vec![self.context.ptr_type(AddressSpace::default()).into()];
+                field_types.extend(ast_field_types.iter().map(|t| self.get_llvm_type(t)));
 
                 let struct_type = self.context.struct_type(&field_types, false);
                 self.class_types.insert(name.clone(), struct_type);
@@ -1623,8 +4885,10 @@ impl<'ctx> CodeGen<'ctx> {
                 // Clear class context
                 self.current_class = None;
 
-                // Generate constructor function (after methods are compiled)
-                self.generate_constructor(name, fields)?;
+                // Generate the drop function, vtable and constructor (after methods are compiled)
+                let drop_fn = self.generate_drop_function(name);
+                let vtable = self.generate_vtable(name, drop_fn);
+                self.generate_constructor(name, &ast_field_types, vtable)?;
 
                 Ok(())
             }
@@ -1635,6 +4899,12 @@ impl<'ctx> CodeGen<'ctx> {
                 elif_branches,
                 else_branch,
             } => {
+                if let Some((variable, constants)) = self.int_switch_chain(condition, elif_branches) {
+                    let bodies: Vec<&Vec<Statement>> =
+                        std::iter::once(&*then_branch).chain(elif_branches.iter().map(|(_, body)| body)).collect();
+                    return self.compile_if_switch(&variable, &constants, &bodies, else_branch.as_ref());
+                }
+
                 let cond_value = self.compile_expression(condition)?;
                 let cond_bool = if cond_value.is_int_value() {
                     cond_value.into_int_value()
@@ -1739,6 +5009,17 @@ impl<'ctx> CodeGen<'ctx> {
                     }
                 }
 
+                // Loop-invariant code motion: compute `.length` reads in the condition
+                // once, before the loop, when the body can't change them
+                let hoisted = self.invariant_length_reads(condition, body);
+                for name in &hoisted {
+                    let length = self.compile_expression(&Expression::MemberAccess {
+                        object: Box::new(Expression::Variable(name.clone())),
+                        member: "length".to_string(),
+                    })?;
+                    self.hoisted_lengths.insert(name.clone(), length);
+                }
+
                 let cond_block = self.context.append_basic_block(function, "while_cond");
                 let body_block = self.context.append_basic_block(function, "while_body");
                 let after_block = self.context.append_basic_block(function, "after_while");
@@ -1772,6 +5053,9 @@ impl<'ctx> CodeGen<'ctx> {
                 }
 
                 self.builder.position_at_end(after_block);
+                for name in &hoisted {
+                    self.hoisted_lengths.remove(name);
+                }
 
                 // OPTIMIZATION Phase 4b: Cleanup loop-invariant tracking
                 self.loop_nesting_depth -= 1;
@@ -1782,7 +5066,7 @@ impl<'ctx> CodeGen<'ctx> {
                 Ok(())
             }
 
-            Statement::For { variable, iterable, body } => {
+            Statement::For { variable, value_variable, iterable, body } => {
                 // Desugar for loop to while loop:
                 // for item in list {
                 //     body
@@ -1813,43 +5097,49 @@ impl<'ctx> CodeGen<'ctx> {
                     }
                 }
 
+                // `for i in range(xs.length)` counts i up to the list's length
+                // directly instead of building the range list first
+                let range_list = self.range_over_list_length(iterable);
+
                 // Evaluate iterable once and store it
-                let iterable_val = self.compile_expression(iterable)?;
+                let iterable_val = self.compile_expression(range_list.unwrap_or(iterable))?;
                 let iterable_type = iterable_val.get_type();
                 let iterable_alloca = self.builder.build_alloca(iterable_type, "_iterable").unwrap();
                 self.builder.build_store(iterable_alloca, iterable_val).unwrap();
 
                 // Determine the type of iterable: string, dict, or list
+                // (Indices: the loop variable is the index into a list)
                 #[derive(PartialEq)]
-                enum IterableKind { String, Dict, List }
-
-                let iterable_kind = if let Expression::Variable(var_name) = iterable {
-                    if let Some((_ptr, _llvm_type, ast_type)) = self.variables.get(var_name) {
-                        match ast_type {
-                            Type::Str => IterableKind::String,
-                            Type::Dict(_, _) => IterableKind::Dict,
-                            _ => IterableKind::List,
-                        }
-                    } else {
-                        IterableKind::List
-                    }
-                } else if matches!(iterable, Expression::StringLiteral(_)) {
-                    IterableKind::String
-                } else if matches!(iterable, Expression::DictLiteral { .. }) {
+                enum IterableKind { String, Dict, List, Indices }
+
+                // Any expression with a dict type iterates as a dict (a field, a
+                // call's result), not just a dict variable
+                let iterable_kind = if range_list.is_some() {
+                    IterableKind::Indices
+                } else if matches!(iterable, Expression::DictLiteral { .. }) || self.dict_types(iterable).is_some() {
                     IterableKind::Dict
+                } else if self.is_string_expression(iterable) {
+                    IterableKind::String
                 } else {
                     IterableKind::List
                 };
 
-                // For dicts, convert to list of keys first
+                // Key and value types of a dict (str keys for literals)
+                let (key_type, value_type) = self.dict_types(iterable).unwrap_or((Type::Str, Type::Int));
+
+                // For dicts, iterate over a snapshot of the keys. The snapshot owns
+                // its keys, so the body may remove entries without leaving the loop
+                // variable dangling; it is released after the loop. Strings are walked
+                // the same way, over a list of their characters, so each step is O(1)
+                // however the UTF-8 text is encoded
                 let ptr_type = self.context.ptr_type(AddressSpace::default());
-                let (actual_iterable_alloca, actual_iterable_type) = if iterable_kind == IterableKind::Dict {
-                    // Call dict_get_keys to get list of keys
+                let (actual_iterable_alloca, actual_iterable_type) = if matches!(iterable_kind, IterableKind::Dict | IterableKind::String) {
                     let iterable_loaded = self.builder.build_load(iterable_type, iterable_alloca, "").unwrap();
-                    let dict_get_keys_fn = self.functions.get("dict_get_keys").unwrap();
+                    let snapshot_fn = if iterable_kind == IterableKind::Dict { "dict_keys" } else { "str_chars" };
+                    let snapshot_fn = self.functions.get(snapshot_fn).unwrap();
                     let keys_list = self
                         .builder
-                        .build_call(*dict_get_keys_fn, &[iterable_loaded.into()], "keys_list")
+                        .build_call(*snapshot_fn, &[iterable_loaded.into()], "keys_list")
                         .unwrap()
                         .try_as_basic_value()
                         .left()
@@ -1863,12 +5153,8 @@ impl<'ctx> CodeGen<'ctx> {
 
                 // Get length using appropriate function
                 let iterable_loaded = self.builder.build_load(actual_iterable_type, actual_iterable_alloca, "").unwrap();
-                let length_fn = if iterable_kind == IterableKind::String {
-                    self.functions.get("str_length").unwrap()
-                } else {
-                    // Both lists and dict keys (which are now a list) use list_length
-                    self.functions.get("list_length").unwrap()
-                };
+                // Lists, dict keys and string characters are all lists by now
+                let length_fn = self.functions.get("list_length").unwrap();
                 let length = self
                     .builder
                     .build_call(*length_fn, &[iterable_loaded.into()], "length")
@@ -1910,45 +5196,28 @@ impl<'ctx> CodeGen<'ctx> {
                 let iterable_loaded = self.builder.build_load(actual_iterable_type, actual_iterable_alloca, "").unwrap();
                 let idx_loaded = self.builder.build_load(i64_type, idx_alloca, "").unwrap();
 
-                let (item_val, item_ast_type) = if iterable_kind == IterableKind::String {
-                    // For strings, use str_char_at
-                    let str_char_at_fn = self.functions.get("str_char_at").unwrap();
-                    let char_val = self
-                        .builder
-                        .build_call(*str_char_at_fn, &[iterable_loaded.into(), idx_loaded.into()], "char")
-                        .unwrap()
-                        .try_as_basic_value()
-                        .left()
-                        .unwrap();
+                let (item_val, item_ast_type) = if iterable_kind == IterableKind::Indices {
+                    (idx_loaded, Type::Int)
+                } else if iterable_kind == IterableKind::String {
+                    let char_val = self.build_list_load("get", &[iterable_loaded.into(), idx_loaded.into()], Some(&Type::Str));
                     (char_val, Type::Str)
                 } else if iterable_kind == IterableKind::Dict {
-                    // For dicts, we iterate over keys list - get string pointer from list
+                    // For dicts, we iterate over keys list - get the key's slot from list
                     let list_get_fn = self.functions.get("list_get_i64").unwrap();
-                    let key_ptr_as_i64 = self
+                    let key_slot = self
                         .builder
-                        .build_call(*list_get_fn, &[iterable_loaded.into(), idx_loaded.into()], "key_ptr")
+                        .build_call(*list_get_fn, &[iterable_loaded.into(), idx_loaded.into()], "key_slot")
                         .unwrap()
                         .try_as_basic_value()
                         .left()
                         .unwrap();
-                    // Convert i64 back to pointer
-                    let key_ptr = self.builder.build_int_to_ptr(
-                        key_ptr_as_i64.into_int_value(),
-                        ptr_type,
-                        "key"
-                    ).unwrap();
-                    (key_ptr.as_basic_value_enum(), Type::Str)
+                    // Convert the slot back to the declared key type
+                    (self.from_dict_slot(key_slot.into_int_value(), &key_type), key_type.clone())
                 } else {
-                    // For lists, use list_get_i64
-                    let list_get_fn = self.functions.get("list_get_i64").unwrap();
-                    let item_val = self
-                        .builder
-                        .build_call(*list_get_fn, &[iterable_loaded.into(), idx_loaded.into()], "item")
-                        .unwrap()
-                        .try_as_basic_value()
-                        .left()
-                        .unwrap();
-                    (item_val, Type::Int)
+                    // For lists, read the element as its declared type
+                    let elem_type = self.list_element_type(iterable);
+                    let item_val = self.build_list_load("get", &[iterable_loaded.into(), idx_loaded.into()], elem_type.as_ref());
+                    (item_val, elem_type.unwrap_or(Type::Int))
                 };
 
                 // Declare loop variable
@@ -1956,6 +5225,26 @@ impl<'ctx> CodeGen<'ctx> {
                 self.builder.build_store(item_alloca, item_val).unwrap();
                 self.variables.insert(variable.clone(), (item_alloca, item_val.get_type(), item_ast_type));
 
+                // `for k, v in d` looks up each key's value; like the key, it is borrowed from the dict
+                if let Some(value_variable) = value_variable {
+                    let str_keys = key_type == Type::Str;
+                    let key_arg: BasicMetadataValueEnum = if str_keys { item_val.into() } else { self.to_dict_slot(item_val).into() };
+                    let dict_loaded = self.builder.build_load(iterable_type, iterable_alloca, "").unwrap();
+                    let dict_get = *self.functions.get(&Self::dict_function("get", str_keys)).unwrap();
+                    let slot = self
+                        .builder
+                        .build_call(dict_get, &[dict_loaded.into(), key_arg], "dict_value")
+                        .unwrap()
+                        .try_as_basic_value()
+                        .left()
+                        .unwrap()
+                        .into_int_value();
+                    let value_val = self.from_dict_slot(slot, &value_type);
+                    let value_alloca = self.builder.build_alloca(value_val.get_type(), value_variable).unwrap();
+                    self.builder.build_store(value_alloca, value_val).unwrap();
+                    self.variables.insert(value_variable.clone(), (value_alloca, value_val.get_type(), value_type.clone()));
+                }
+
                 // Push loop context for break/continue (continue goes to increment block)
                 self.loop_stack.push(LoopContext {
                     continue_block: incr_block,
@@ -1986,8 +5275,18 @@ impl<'ctx> CodeGen<'ctx> {
                 // After block
                 self.builder.position_at_end(after_block);
 
-                // Remove loop variable from scope
+                // Release the dict's key snapshot or the string's characters
+                if matches!(iterable_kind, IterableKind::Dict | IterableKind::String) {
+                    let snapshot_type = if iterable_kind == IterableKind::Dict { key_type } else { Type::Str };
+                    let keys_list = self.builder.build_load(ptr_type, actual_iterable_alloca, "keys_list").unwrap().into_pointer_value();
+                    self.build_rc_release_nullable(keys_list, &Type::List(Box::new(snapshot_type)));
+                }
+
+                // Remove loop variables from scope
                 self.variables.remove(variable);
+                if let Some(value_variable) = value_variable {
+                    self.variables.remove(value_variable);
+                }
 
                 // OPTIMIZATION Phase 4b: Cleanup loop-invariant tracking
                 self.loop_nesting_depth -= 1;
@@ -2001,8 +5300,10 @@ impl<'ctx> CodeGen<'ctx> {
             Statement::Return(expr) => {
                 if let Some(e) = expr {
                     // OPTIMIZATION: If returning a local variable, mark it as moved (transfer ownership)
-                    // This skips the release, eliminating unnecessary RC operations
-                    if let Expression::Variable(var_name) = e {
+                    // This skips the release, eliminating unnecessary RC operations.
+                    // `return Ok(xs)` hands over xs the same way.
+                    let returned = self.result_constructor(e).map_or(e, |(_, payload)| payload);
+                    if let Expression::Variable(var_name) = returned {
                         if let Some((_, _, ast_type)) = self.variables.get(var_name) {
                             if self.is_rc_type(ast_type) {
                                 // Mark variable as moved - it will not be released
@@ -2012,16 +5313,18 @@ impl<'ctx> CodeGen<'ctx> {
                     }
 
                     // Compute return value first (may call other functions)
-                    let return_value = self.compile_expression(e)?;
-
-                    // Release all RC variables before returning (except moved ones)
-                    self.release_scope_variables();
-
-                    // Pop function from call stack after computing return value
-                    let pop_call_stack_fn = *self.functions.get("pop_call_stack").unwrap();
-                    self.builder.build_call(pop_call_stack_fn, &[], "").unwrap();
-
-                    self.builder.build_return(Some(&return_value)).unwrap();
+                    let return_value = match self.current_return_type.clone() {
+                        Some(return_type) => self.compile_expression_as(e, &return_type)?,
+                        None => self.compile_expression(e)?,
+                    };
+                    // Callers own what they get back: a parameter, field or element
+                    // is retained on the way out
+                    let returns_rc = self.current_return_type.as_ref().is_some_and(|t| self.is_rc_type(t));
+                    let returns_owned_local = matches!(e, Expression::Variable(name) if self.owned_variables.contains(name));
+                    if returns_rc && return_value.is_pointer_value() && !returns_owned_local && self.is_borrowed_value(e) {
+                        self.build_rc_retain_nullable(return_value.into_pointer_value());
+                    }
+                    self.build_value_return(return_value);
                 } else {
                     // Release all RC variables before returning
                     self.release_scope_variables();
@@ -2104,9 +5407,35 @@ impl<'ctx> CodeGen<'ctx> {
             Statement::Try { try_block, except_clauses, finally_block } => {
                 let function = self.current_function.ok_or("Try statement outside of function")?;
 
-                // Allocate jmp_buf on stack (200 bytes)
-                let jmp_buf_type = self.context.i8_type().array_type(200);
+                // After a longjmp, variables the try block assigned must be read back
+                // from memory, so -O levels leave functions with a try unoptimized.
+                // optnone needs noinline, which wins over @inline
+                let alwaysinline = Attribute::get_named_enum_kind_id("alwaysinline");
+                function.remove_enum_attribute(AttributeLoc::Function, alwaysinline);
+                for name in ["optnone", "noinline"] {
+                    let kind_id = Attribute::get_named_enum_kind_id(name);
+                    function.add_attribute(AttributeLoc::Function, self.context.create_enum_attribute(kind_id, 0));
+                }
+
+                // Allocate jmp_buf on stack (sized and aligned for every supported target)
+                let jmp_buf_type = self.context.i8_type().array_type(JMP_BUF_SIZE);
                 let jmp_buf_alloca = self.builder.build_alloca(jmp_buf_type, "jmp_buf").unwrap();
+                jmp_buf_alloca
+                    .as_instruction_value()
+                    .unwrap()
+                    .set_alignment(JMP_BUF_ALIGN)
+                    .map_err(|e| format!("Failed to align jmp_buf: {}", e))?;
+
+                // An exception that no except clause handles is parked here while the
+                // finally block runs, then raised again
+                let pending_exc = if finally_block.is_some() {
+                    let ptr_type = self.context.ptr_type(AddressSpace::default());
+                    let pending = self.builder.build_alloca(ptr_type, "pending_exc").unwrap();
+                    self.builder.build_store(pending, ptr_type.const_null()).unwrap();
+                    Some(pending)
+                } else {
+                    None
+                };
 
                 // Push exception handler
                 let exception_push_handler_fn = *self.functions.get("exception_push_handler").unwrap();
@@ -2144,8 +5473,11 @@ impl<'ctx> CodeGen<'ctx> {
                 for stmt in try_block {
                     self.compile_statement(stmt)?;
                 }
-                // If we reach here, no exception was raised
+                // If we reach here, no exception was raised, so the handler is still ours to pop.
+                // On the exception path exception_raise has already popped it.
+                let exception_pop_handler_fn = *self.functions.get("exception_pop_handler").unwrap();
                 if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                    self.builder.build_call(exception_pop_handler_fn, &[], "").unwrap();
                     self.builder.build_unconditional_branch(finally_block_label).unwrap();
                 }
 
@@ -2160,31 +5492,16 @@ impl<'ctx> CodeGen<'ctx> {
                     "current_exc"
                 ).unwrap().try_as_basic_value().left().unwrap().into_pointer_value();
 
-                // Get exception handler functions (needed in finally block)
-                let exception_pop_handler_fn = *self.functions.get("exception_pop_handler").unwrap();
+                let unhandled_block = self.context.append_basic_block(function, "unhandled");
 
                 // If no except clauses, jump straight to unhandled
                 if except_clauses.is_empty() {
-                    let unhandled_block = self.context.append_basic_block(function, "unhandled");
                     self.builder.build_unconditional_branch(unhandled_block).unwrap();
-
-                    // Unhandled exception: pop handler and re-raise
-                    self.builder.position_at_end(unhandled_block);
-                    self.builder.build_call(exception_pop_handler_fn, &[], "").unwrap();
-                    // Execute finally before re-raising
-                    if finally_block.is_some() {
-                        self.builder.build_unconditional_branch(finally_block_label).unwrap();
-                    } else {
-                        // TODO: Re-raise the exception
-                        self.builder.build_unreachable().unwrap();
-                    }
                 } else {
                     // Generate except clause matching
                     let mut next_except_block = self.context.append_basic_block(function, "except_check");
                     self.builder.build_unconditional_branch(next_except_block).unwrap();
 
-                    let unhandled_block = self.context.append_basic_block(function, "unhandled");
-
                     for (i, except_clause) in except_clauses.iter().enumerate() {
                     self.builder.position_at_end(next_except_block);
 
@@ -2229,13 +5546,18 @@ impl<'ctx> CodeGen<'ctx> {
                         self.variables.insert(var_name.clone(), (exc_var_alloca, exc_ptr_type.as_basic_type_enum(), Type::Exception));
                     }
 
+                    // A bare `raise` in the body re-raises this exception
+                    self.handled_exceptions.push(current_exc);
                     for stmt in &except_clause.body {
                         self.compile_statement(stmt)?;
                     }
+                    self.handled_exceptions.pop();
 
-                    // Clear exception
-                    let exception_clear_fn = *self.functions.get("exception_clear").unwrap();
-                    self.builder.build_call(exception_clear_fn, &[], "").unwrap();
+                    // Clear exception (unless the body already left, e.g. with `raise`)
+                    if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                        let exception_clear_fn = *self.functions.get("exception_clear").unwrap();
+                        self.builder.build_call(exception_clear_fn, &[], "").unwrap();
+                    }
 
                     // Remove variable binding if present
                     if let Some(ref var_name) = except_clause.var_name {
@@ -2248,20 +5570,23 @@ impl<'ctx> CodeGen<'ctx> {
 
                         next_except_block = next_check;
                     }
+                }
 
-                    // Unhandled exception: pop handler and re-raise
-                    self.builder.position_at_end(unhandled_block);
-                    self.builder.build_call(exception_pop_handler_fn, &[], "").unwrap();
-                    // TODO: Re-raise the exception
+                // Unhandled exception: propagate it to the next enclosing handler,
+                // running the finally block first
+                self.builder.position_at_end(unhandled_block);
+                let exception_reraise_fn = *self.functions.get("exception_reraise").unwrap();
+                if let Some(pending) = pending_exc {
+                    self.builder.build_store(pending, current_exc).unwrap();
+                    self.builder.build_unconditional_branch(finally_block_label).unwrap();
+                } else {
+                    self.builder.build_call(exception_reraise_fn, &[current_exc.into()], "").unwrap();
                     self.builder.build_unreachable().unwrap();
                 }
 
                 // Finally block
                 self.builder.position_at_end(finally_block_label);
 
-                // Pop exception handler
-                self.builder.build_call(exception_pop_handler_fn, &[], "").unwrap();
-
                 if let Some(finally) = finally_block {
                     for stmt in finally {
                         self.compile_statement(stmt)?;
@@ -2269,14 +5594,29 @@ impl<'ctx> CodeGen<'ctx> {
                 }
 
                 if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
-                    self.builder.build_unconditional_branch(end_block).unwrap();
+                    if let Some(pending) = pending_exc {
+                        let ptr_type = self.context.ptr_type(AddressSpace::default());
+                        let pending_val = self.builder.build_load(ptr_type, pending, "pending").unwrap().into_pointer_value();
+                        let has_pending = self.builder.build_is_not_null(pending_val, "has_pending").unwrap();
+                        let reraise_block = self.context.append_basic_block(function, "finally_reraise");
+                        self.builder.build_conditional_branch(has_pending, reraise_block, end_block).unwrap();
+
+                        self.builder.position_at_end(reraise_block);
+                        self.builder.build_call(exception_reraise_fn, &[pending_val.into()], "").unwrap();
+                        self.builder.build_unreachable().unwrap();
+                    } else {
+                        self.builder.build_unconditional_branch(end_block).unwrap();
+                    }
                 }
 
                 self.builder.position_at_end(end_block);
                 Ok(())
             }
 
-            Statement::Raise { exception_type, message, line } => {
+            Statement::Raise { exception_type, message, cause, line } => {
+                // Attribute the raise to its line in the stack trace
+                self.set_debug_line(*line);
+
                 // Compile the message expression
                 let message_value = self.compile_expression(message)?;
 
@@ -2289,18 +5629,22 @@ impl<'ctx> CodeGen<'ctx> {
                 // Create line number constant
                 let line_const = self.context.i64_type().const_int(*line as u64, false);
 
-                // Call exception_raise(type, message, file, line)
-                let exception_raise_fn = *self.functions.get("exception_raise").unwrap();
-                self.builder.build_call(
-                    exception_raise_fn,
-                    &[
-                        type_str.as_pointer_value().into(),
-                        message_value.into(),
-                        file_str.as_pointer_value().into(),
-                        line_const.into(),
-                    ],
-                    ""
-                ).unwrap();
+                // Call exception_raise(type, message, file, line), or
+                // exception_raise_from(..., cause) to chain `raise ... from e`
+                let mut raise_args: Vec<BasicMetadataValueEnum> = vec![
+                    type_str.as_pointer_value().into(),
+                    message_value.into(),
+                    file_str.as_pointer_value().into(),
+                    line_const.into(),
+                ];
+                let raise_name = if let Some(cause) = cause {
+                    raise_args.push(self.compile_expression(cause)?.into());
+                    "exception_raise_from"
+                } else {
+                    "exception_raise"
+                };
+                let exception_raise_fn = *self.functions.get(raise_name).unwrap();
+                self.builder.build_call(exception_raise_fn, &raise_args, "").unwrap();
 
                 // exception_raise doesn't return, but we need unreachable to mark this
                 self.builder.build_unreachable().unwrap();
@@ -2309,7 +5653,30 @@ impl<'ctx> CodeGen<'ctx> {
             }
 
             Statement::Expression(expr) => {
-                self.compile_expression(expr)?;
+                let value = self.compile_expression(expr)?;
+                // A new value nothing keeps (`xs.pop()` on its own line) is released.
+                // `-> void` parses as Custom("void"), and those return no object
+                if let Some(ws_type) = self.declared_type(expr) {
+                    if self.is_rc_type(&ws_type)
+                        && ws_type != Type::Custom("void".to_string())
+                        && value.is_pointer_value()
+                        && !self.is_borrowed_value(expr)
+                    {
+                        self.build_rc_release_nullable(value.into_pointer_value(), &ws_type);
+                    }
+                }
+                Ok(())
+            }
+
+            Statement::Reraise { line } => {
+                self.set_debug_line(*line);
+                let exception = *self
+                    .handled_exceptions
+                    .last()
+                    .ok_or("'raise' without an exception outside of an except block")?;
+                let exception_reraise_fn = *self.functions.get("exception_reraise").unwrap();
+                self.builder.build_call(exception_reraise_fn, &[exception.into()], "").unwrap();
+                self.builder.build_unreachable().unwrap();
                 Ok(())
             }
 
@@ -2321,6 +5688,10 @@ impl<'ctx> CodeGen<'ctx> {
             }
 
             Statement::TupleUnpack { names, value } => {
+                let element_types = match self.declared_type(value) {
+                    Some(Type::Tuple(types)) => types,
+                    _ => Vec::new(),
+                };
                 // Compile the tuple expression
                 let tuple_value = self.compile_expression(value)?;
 
@@ -2338,8 +5709,11 @@ impl<'ctx> CodeGen<'ctx> {
                     // Store the extracted value
                     self.builder.build_store(ptr, element_value).unwrap();
 
-                    // Get the WadeScript type - infer from the LLVM type
-                    let ws_type = self.infer_ws_type_from_llvm(elem_type);
+                    // Get the WadeScript type, inferring it from the LLVM type if the tuple's is unknown
+                    let ws_type = match element_types.get(i) {
+                        Some(ws_type) => ws_type.clone(),
+                        None => self.infer_ws_type_from_llvm(elem_type),
+                    };
 
                     // Add to variables table
                     self.variables.insert(name.clone(), (ptr, elem_type, ws_type));
@@ -2365,6 +5739,12 @@ impl<'ctx> CodeGen<'ctx> {
                 Ok(string_value.as_pointer_value().as_basic_value_enum())
             }
 
+            // Decimal literals are parsed by the runtime so no digits are lost
+            Expression::DecimalLiteral(digits) => {
+                let text = self.builder.build_global_string_ptr(digits, "decimal_literal").unwrap();
+                Ok(self.call_runtime("parse_decimal", &[text.as_pointer_value().into()]))
+            }
+
             Expression::BoolLiteral(b) => Ok(self
                 .context
                 .bool_type()
@@ -2378,6 +5758,14 @@ impl<'ctx> CodeGen<'ctx> {
                 .as_basic_value_enum()),
 
             Expression::Variable(name) => {
+                // Builtin float constants, unless a variable hides them
+                if !self.variables.contains_key(name) {
+                    match name.as_str() {
+                        "nan" => return Ok(self.context.f64_type().const_float(f64::NAN).as_basic_value_enum()),
+                        "inf" => return Ok(self.context.f64_type().const_float(f64::INFINITY).as_basic_value_enum()),
+                        _ => {}
+                    }
+                }
                 let (ptr, var_type, _ast_type) = self
                     .variables
                     .get(name)
@@ -2386,71 +5774,134 @@ impl<'ctx> CodeGen<'ctx> {
             }
 
             Expression::Binary { left, op, right } => {
+                if !matches!(op, BinaryOp::And | BinaryOp::Or) {
+                    if let Some(ws_type) = self.runtime_number_type(left).or_else(|| self.runtime_number_type(right)) {
+                        return self.compile_runtime_number_binary(left, op, right, &ws_type);
+                    }
+                }
+
                 let left_val = self.compile_expression(left)?;
                 let right_val = self.compile_expression(right)?;
 
-                match op {
-                    BinaryOp::Add => {
-                        // Check for string concatenation first
-                        if left_val.is_pointer_value() && right_val.is_pointer_value() {
-                            // String concatenation
-                            let left_str = left_val.into_pointer_value();
-                            let right_str = right_val.into_pointer_value();
-
-                            // Get string lengths
-                            let strlen_fn = *self.functions.get("strlen").unwrap();
-                            let left_len = self.builder
-                                .build_call(strlen_fn, &[left_str.into()], "left_len")
-                                .unwrap()
-                                .try_as_basic_value()
-                                .left()
-                                .unwrap()
-                                .into_int_value();
-
-                            let right_len = self.builder
-                                .build_call(strlen_fn, &[right_str.into()], "right_len")
-                                .unwrap()
-                                .try_as_basic_value()
-                                .left()
-                                .unwrap()
-                                .into_int_value();
-
-                            // Calculate total length (left_len + right_len + 1 for null terminator)
-                            let total_len = self.builder
-                                .build_int_add(left_len, right_len, "total_len")
-                                .unwrap();
-                            let total_len_with_null = self.builder
-                                .build_int_add(
-                                    total_len,
-                                    self.context.i64_type().const_int(1, false),
-                                    "total_len_with_null",
-                                )
-                                .unwrap();
+                // int <op> float: the type checker types this as float, so widen the int side
+                let (left_val, right_val) = match (left_val, right_val) {
+                    (BasicValueEnum::IntValue(_), BasicValueEnum::FloatValue(float_val)) => {
+                        (self.widen_to(left_val, float_val.get_type().as_basic_type_enum()), right_val)
+                    }
+                    (BasicValueEnum::FloatValue(float_val), BasicValueEnum::IntValue(_)) => {
+                        (left_val, self.widen_to(right_val, float_val.get_type().as_basic_type_enum()))
+                    }
+                    // Sized ints: an int literal takes the other operand's type (`b + 1` with
+                    // b: u8 is u8 arithmetic); otherwise the narrower operand is widened
+                    (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r))
+                        if l.get_type().get_bit_width() != r.get_type().get_bit_width() =>
+                    {
+                        let (l_type, r_type) = (l.get_type(), r.get_type());
+                        if l_type.get_bit_width() < r_type.get_bit_width() {
+                            if Self::is_int_literal(right) {
+                                (left_val, self.widen_to(right_val, l_type.as_basic_type_enum()))
+                            } else {
+                                (self.widen_to(left_val, r_type.as_basic_type_enum()), right_val)
+                            }
+                        } else if Self::is_int_literal(left) {
+                            (self.widen_to(left_val, r_type.as_basic_type_enum()), right_val)
+                        } else {
+                            (left_val, self.widen_to(right_val, l_type.as_basic_type_enum()))
+                        }
+                    }
+                    _ => (left_val, right_val),
+                };
 
-                            // Allocate memory for new string
-                            let malloc_fn = *self.functions.get("malloc").unwrap();
-                            let new_str = self.builder
-                                .build_call(malloc_fn, &[total_len_with_null.into()], "concat_str")
-                                .unwrap()
-                                .try_as_basic_value()
-                                .left()
-                                .unwrap()
-                                .into_pointer_value();
+                // u8 compares, divides, and detects overflow as unsigned
+                let unsigned = matches!(left_val, BasicValueEnum::IntValue(v) if Self::is_unsigned_int(v.get_type()));
+
+                // x == None / x != None: test the pointer itself rather than strcmp-ing a null
+                if matches!(op, BinaryOp::Equal | BinaryOp::NotEqual)
+                    && (matches!(**left, Expression::NoneLiteral) || matches!(**right, Expression::NoneLiteral))
+                {
+                    let value = if matches!(**left, Expression::NoneLiteral) { right_val } else { left_val };
+                    if value.is_pointer_value() {
+                        let ptr = value.into_pointer_value();
+                        let result = if *op == BinaryOp::Equal {
+                            self.builder.build_is_null(ptr, "is_none").unwrap()
+                        } else {
+                            self.builder.build_is_not_null(ptr, "is_some").unwrap()
+                        };
+                        return Ok(result.as_basic_value_enum());
+                    }
+                }
 
-                            // Copy first string
-                            let strcpy_fn = *self.functions.get("strcpy").unwrap();
-                            self.builder
-                                .build_call(strcpy_fn, &[new_str.into(), left_str.into()], "")
-                                .unwrap();
+                if matches!(
+                    op,
+                    BinaryOp::Equal
+                        | BinaryOp::NotEqual
+                        | BinaryOp::Less
+                        | BinaryOp::Greater
+                        | BinaryOp::LessEqual
+                        | BinaryOp::GreaterEqual
+                ) && left_val.is_pointer_value()
+                    && right_val.is_pointer_value()
+                {
+                    return self.build_pointer_comparison(op, left, right, left_val.into_pointer_value(), right_val.into_pointer_value());
+                }
 
-                            // Concatenate second string
-                            let strcat_fn = *self.functions.get("strcat").unwrap();
-                            self.builder
-                                .build_call(strcat_fn, &[new_str.into(), right_str.into()], "")
-                                .unwrap();
+                // bigints and decimals were routed above, so the only pointers arithmetic
+                // takes are strs (`a + b`, `"-" * n`) and lists (`xs + ys`). Anything else
+                // is a value whose type codegen couldn't work out
+                let arithmetic = matches!(
+                    op,
+                    BinaryOp::Add
+                        | BinaryOp::Subtract
+                        | BinaryOp::Multiply
+                        | BinaryOp::Divide
+                        | BinaryOp::FloorDivide
+                        | BinaryOp::Modulo
+                        | BinaryOp::Power
+                );
+                if arithmetic && (left_val.is_pointer_value() || right_val.is_pointer_value()) {
+                    let known = match op {
+                        BinaryOp::Add => {
+                            self.is_string_expression(left)
+                                || self.is_string_expression(right)
+                                || self.is_list_expression(left)
+                                || self.is_list_expression(right)
+                        }
+                        BinaryOp::Multiply => [(left, left_val), (right, right_val)]
+                            .iter()
+                            .all(|(side, value)| !value.is_pointer_value() || self.is_string_expression(side)),
+                        _ => false,
+                    };
+                    if !known {
+                        return Err(format!("Cannot compile {:?}: the type of an operand is unknown to codegen", op));
+                    }
+                }
 
-                            Ok(new_str.as_basic_value_enum())
+                match op {
+                    BinaryOp::Add if self.is_list_expression(left) || self.is_list_expression(right) => {
+                        // xs + ys: a new list holding its own references to the elements
+                        let list_concat = *self.functions.get("list_concat").unwrap();
+                        let result = self.builder
+                            .build_call(list_concat, &[left_val.into(), right_val.into()], "concat_list")
+                            .unwrap()
+                            .try_as_basic_value()
+                            .left()
+                            .unwrap();
+                        Ok(result)
+                    }
+                    BinaryOp::Add => {
+                        // Check for string concatenation first
+                        if left_val.is_pointer_value() && right_val.is_pointer_value() {
+                            // String concatenation, allocated at the combined length
+                            Ok(self.call_runtime("str_concat", &[left_val.into(), right_val.into()]))
                         } else if left_val.is_int_value() {
+                            if self.checked_overflow {
+                                return self.build_checked_int_op(
+                                    if unsigned { "llvm.uadd.with.overflow" } else { "llvm.sadd.with.overflow" },
+                                    left_val.into_int_value(),
+                                    right_val.into_int_value(),
+                                    "addition",
+                                );
+                            }
                             Ok(self
                                 .builder
                                 .build_int_add(
@@ -2475,6 +5926,14 @@ impl<'ctx> CodeGen<'ctx> {
 
                     BinaryOp::Subtract => {
                         if left_val.is_int_value() {
+                            if self.checked_overflow {
+                                return self.build_checked_int_op(
+                                    if unsigned { "llvm.usub.with.overflow" } else { "llvm.ssub.with.overflow" },
+                                    left_val.into_int_value(),
+                                    right_val.into_int_value(),
+                                    "subtraction",
+                                );
+                            }
                             Ok(self
                                 .builder
                                 .build_int_sub(
@@ -2498,7 +5957,31 @@ impl<'ctx> CodeGen<'ctx> {
                     }
 
                     BinaryOp::Multiply => {
+                        // String repetition: str * int or int * str
+                        if left_val.is_pointer_value() || right_val.is_pointer_value() {
+                            let (string, count) = if left_val.is_pointer_value() {
+                                (left_val, right_val)
+                            } else {
+                                (right_val, left_val)
+                            };
+                            let str_repeat_fn = *self.functions.get("str_repeat").unwrap();
+                            return Ok(self.builder
+                                .build_call(str_repeat_fn, &[string.into(), count.into()], "repeat_str")
+                                .unwrap()
+                                .try_as_basic_value()
+                                .left()
+                                .unwrap());
+                        }
+
                         if left_val.is_int_value() {
+                            if self.checked_overflow {
+                                return self.build_checked_int_op(
+                                    if unsigned { "llvm.umul.with.overflow" } else { "llvm.smul.with.overflow" },
+                                    left_val.into_int_value(),
+                                    right_val.into_int_value(),
+                                    "multiplication",
+                                );
+                            }
                             Ok(self
                                 .builder
                                 .build_int_mul(
@@ -2523,15 +6006,16 @@ impl<'ctx> CodeGen<'ctx> {
 
                     BinaryOp::Divide => {
                         if left_val.is_int_value() {
-                            Ok(self
-                                .builder
-                                .build_int_signed_div(
-                                    left_val.into_int_value(),
-                                    right_val.into_int_value(),
-                                    "divtmp",
-                                )
-                                .unwrap()
-                                .as_basic_value_enum())
+                            let (l, r) = (left_val.into_int_value(), right_val.into_int_value());
+                            if self.checked_overflow && !unsigned {
+                                self.build_checked_division(l, r, "division")?;
+                            }
+                            let quotient = if unsigned {
+                                self.builder.build_int_unsigned_div(l, r, "divtmp")
+                            } else {
+                                self.builder.build_int_signed_div(l, r, "divtmp")
+                            };
+                            Ok(quotient.unwrap().as_basic_value_enum())
                         } else {
                             Ok(self
                                 .builder
@@ -2545,25 +6029,40 @@ impl<'ctx> CodeGen<'ctx> {
                         }
                     }
 
-                    BinaryOp::Modulo => Ok(self
-                        .builder
-                        .build_int_signed_rem(
-                            left_val.into_int_value(),
-                            right_val.into_int_value(),
-                            "modtmp",
-                        )
-                        .unwrap()
-                        .as_basic_value_enum()),
+                    BinaryOp::Modulo => {
+                        let (l, r) = (left_val.into_int_value(), right_val.into_int_value());
+                        // MIN % -1 is 0, but srem overflows on the way there; x % 1 is 0 too
+                        let r = if self.checked_overflow && !unsigned {
+                            let is_minus_one = self.builder
+                                .build_int_compare(IntPredicate::EQ, r, r.get_type().const_all_ones(), "is_minus_one")
+                                .unwrap();
+                            self.builder
+                                .build_select(is_minus_one, r.get_type().const_int(1, false), r, "rem_divisor")
+                                .unwrap()
+                                .into_int_value()
+                        } else {
+                            r
+                        };
+                        let remainder = if unsigned {
+                            self.builder.build_int_unsigned_rem(l, r, "modtmp")
+                        } else {
+                            self.builder.build_int_signed_rem(l, r, "modtmp")
+                        };
+                        Ok(remainder.unwrap().as_basic_value_enum())
+                    }
 
-                    BinaryOp::FloorDivide => Ok(self
-                        .builder
-                        .build_int_signed_div(
-                            left_val.into_int_value(),
-                            right_val.into_int_value(),
-                            "floordivtmp",
-                        )
-                        .unwrap()
-                        .as_basic_value_enum()),
+                    BinaryOp::FloorDivide => {
+                        let (l, r) = (left_val.into_int_value(), right_val.into_int_value());
+                        if self.checked_overflow && !unsigned {
+                            self.build_checked_division(l, r, "floor division")?;
+                        }
+                        let quotient = if unsigned {
+                            self.builder.build_int_unsigned_div(l, r, "floordivtmp")
+                        } else {
+                            self.builder.build_int_signed_div(l, r, "floordivtmp")
+                        };
+                        Ok(quotient.unwrap().as_basic_value_enum())
+                    }
 
                     BinaryOp::Power => {
                         Err("Power operator not yet implemented".to_string())
@@ -2581,28 +6080,6 @@ impl<'ctx> CodeGen<'ctx> {
                                 )
                                 .unwrap()
                                 .as_basic_value_enum())
-                        } else if left_val.is_pointer_value() {
-                            // String comparison using strcmp
-                            let strcmp_fn = self.functions.get("strcmp").unwrap();
-                            let cmp_result = self
-                                .builder
-                                .build_call(
-                                    *strcmp_fn,
-                                    &[left_val.into(), right_val.into()],
-                                    "strcmp_result",
-                                )
-                                .unwrap()
-                                .try_as_basic_value()
-                                .left()
-                                .unwrap()
-                                .into_int_value();
-                            // strcmp returns 0 if equal, so compare result == 0
-                            let zero = self.context.i32_type().const_int(0, false);
-                            Ok(self
-                                .builder
-                                .build_int_compare(IntPredicate::EQ, cmp_result, zero, "streq")
-                                .unwrap()
-                                .as_basic_value_enum())
                         } else {
                             Ok(self
                                 .builder
@@ -2629,33 +6106,12 @@ impl<'ctx> CodeGen<'ctx> {
                                 )
                                 .unwrap()
                                 .as_basic_value_enum())
-                        } else if left_val.is_pointer_value() {
-                            // String comparison using strcmp
-                            let strcmp_fn = self.functions.get("strcmp").unwrap();
-                            let cmp_result = self
-                                .builder
-                                .build_call(
-                                    *strcmp_fn,
-                                    &[left_val.into(), right_val.into()],
-                                    "strcmp_result",
-                                )
-                                .unwrap()
-                                .try_as_basic_value()
-                                .left()
-                                .unwrap()
-                                .into_int_value();
-                            // strcmp returns 0 if equal, so compare result != 0
-                            let zero = self.context.i32_type().const_int(0, false);
-                            Ok(self
-                                .builder
-                                .build_int_compare(IntPredicate::NE, cmp_result, zero, "strne")
-                                .unwrap()
-                                .as_basic_value_enum())
                         } else {
                             Ok(self
                                 .builder
                                 .build_float_compare(
-                                    FloatPredicate::ONE,
+                                    // Unordered: NaN != NaN is true, as in IEEE 754
+                                    FloatPredicate::UNE,
                                     left_val.into_float_value(),
                                     right_val.into_float_value(),
                                     "netmp",
@@ -2670,7 +6126,7 @@ impl<'ctx> CodeGen<'ctx> {
                             Ok(self
                                 .builder
                                 .build_int_compare(
-                                    IntPredicate::SLT,
+                                    if unsigned { IntPredicate::ULT } else { IntPredicate::SLT },
                                     left_val.into_int_value(),
                                     right_val.into_int_value(),
                                     "lttmp",
@@ -2696,7 +6152,7 @@ impl<'ctx> CodeGen<'ctx> {
                             Ok(self
                                 .builder
                                 .build_int_compare(
-                                    IntPredicate::SGT,
+                                    if unsigned { IntPredicate::UGT } else { IntPredicate::SGT },
                                     left_val.into_int_value(),
                                     right_val.into_int_value(),
                                     "gttmp",
@@ -2722,7 +6178,7 @@ impl<'ctx> CodeGen<'ctx> {
                             Ok(self
                                 .builder
                                 .build_int_compare(
-                                    IntPredicate::SLE,
+                                    if unsigned { IntPredicate::ULE } else { IntPredicate::SLE },
                                     left_val.into_int_value(),
                                     right_val.into_int_value(),
                                     "letmp",
@@ -2748,7 +6204,7 @@ impl<'ctx> CodeGen<'ctx> {
                             Ok(self
                                 .builder
                                 .build_int_compare(
-                                    IntPredicate::SGE,
+                                    if unsigned { IntPredicate::UGE } else { IntPredicate::SGE },
                                     left_val.into_int_value(),
                                     right_val.into_int_value(),
                                     "getmp",
@@ -2802,10 +6258,26 @@ impl<'ctx> CodeGen<'ctx> {
                         .as_basic_value_enum()),
 
                     UnaryOp::Negate => {
-                        if operand_val.is_int_value() {
+                        if let Some(ws_type) = self.runtime_number_type(operand) {
+                            let neg = format!("{}_neg", Self::runtime_number_prefix(&ws_type));
+                            Ok(self.call_runtime(&neg, &[operand_val.into()]))
+                        } else if operand_val.is_pointer_value() {
+                            Err("Cannot compile Negate: the type of the operand is unknown to codegen".to_string())
+                        } else if operand_val.is_int_value() {
+                            let int_val = operand_val.into_int_value();
+                            if self.checked_overflow {
+                                // -MIN doesn't fit, and neither does -x of a nonzero u8
+                                let unsigned = Self::is_unsigned_int(int_val.get_type());
+                                return self.build_checked_int_op(
+                                    if unsigned { "llvm.usub.with.overflow" } else { "llvm.ssub.with.overflow" },
+                                    int_val.get_type().const_zero(),
+                                    int_val,
+                                    "negation",
+                                );
+                            }
                             Ok(self
                                 .builder
-                                .build_int_neg(operand_val.into_int_value(), "negtmp")
+                                .build_int_neg(int_val, "negtmp")
                                 .unwrap()
                                 .as_basic_value_enum())
                         } else {
@@ -2819,27 +6291,126 @@ impl<'ctx> CodeGen<'ctx> {
                 }
             }
 
-            Expression::Call { callee, args, named_args, line } => {
-                // Set debug location for this call
-                let scope = if let Some(func_scope) = self.current_debug_scope {
-                    func_scope.as_debug_info_scope()
+            Expression::NullCoalesce { value, default } => {
+                let function = self.current_function.ok_or("'??' outside of function")?;
+                let optional_val = self.compile_expression(value)?.into_pointer_value();
+                let is_none = self.builder.build_is_null(optional_val, "is_none").unwrap();
+
+                let default_block = self.context.append_basic_block(function, "coalesce_default");
+                let unwrap_block = self.context.append_basic_block(function, "coalesce_unwrap");
+                let merge_block = self.context.append_basic_block(function, "coalesce_merge");
+                self.builder.build_conditional_branch(is_none, default_block, unwrap_block).unwrap();
+
+                // The default is only evaluated when the value is None
+                self.builder.position_at_end(default_block);
+                let inner_is_float = matches!(
+                    self.declared_type(value),
+                    Some(Type::Optional(inner)) if *inner == Type::Float
+                );
+                let mut default_val = self.compile_expression(default)?;
+                if inner_is_float {
+                    default_val = self.widen_to(default_val, self.context.f64_type().as_basic_type_enum());
+                }
+                let default_end_block = self.builder.get_insert_block().unwrap();
+                self.builder.build_unconditional_branch(merge_block).unwrap();
+
+                // Pointer-like values (str, list, class, or an Optional default) are used as-is;
+                // int/float/bool are unboxed
+                self.builder.position_at_end(unwrap_block);
+                let unwrapped = if default_val.is_pointer_value() {
+                    optional_val.as_basic_value_enum()
                 } else {
-                    self.compile_unit.get_file().as_debug_info_scope()
+                    self.builder.build_load(default_val.get_type(), optional_val, "unboxed").unwrap()
                 };
-                let debug_loc = self.debug_builder.create_debug_location(
-                    self.context,
-                    *line as u32,
-                    0, // column
-                    scope,
-                    None,
-                );
-                self.builder.set_current_debug_location(debug_loc);
+                self.builder.build_unconditional_branch(merge_block).unwrap();
+
+                self.builder.position_at_end(merge_block);
+                let phi = self.builder.build_phi(default_val.get_type(), "coalesced").unwrap();
+                phi.add_incoming(&[(&default_val, default_end_block), (&unwrapped, unwrap_block)]);
+                Ok(phi.as_basic_value())
+            }
+
+            Expression::Propagate { value } => {
+                let function = self.current_function.ok_or("'?' outside of function")?;
+                let return_type = self.current_return_type.clone().ok_or("'?' outside of function")?;
+                let Type::Result(_, return_err_type) = &return_type else {
+                    return Err("'?' can only be used in a function that returns a Result".to_string());
+                };
+
+                let result = self.compile_expression(value)?.into_struct_value();
+                let is_ok = self.builder.build_extract_value(result, 0, "is_ok").unwrap().into_int_value();
+                let err_block = self.context.append_basic_block(function, "propagate_err");
+                let ok_block = self.context.append_basic_block(function, "propagate_ok");
+                self.builder.build_conditional_branch(is_ok, ok_block, err_block).unwrap();
+
+                // Err: return it from the enclosing function, re-wrapped in its Result type
+                self.builder.position_at_end(err_block);
+                let error = self.builder.build_extract_value(result, 2, "error").unwrap();
+                let error = self.coerce_to(error, return_err_type);
+                let propagated = self.build_result(&return_type, false, error);
+                self.build_value_return(propagated);
+
+                self.builder.position_at_end(ok_block);
+                Ok(self.builder.build_extract_value(result, 1, "ok_value").unwrap())
+            }
+
+            Expression::Cast { expr, target } => {
+                let value = self.compile_expression(expr)?;
+                let target_type = self.get_llvm_type(target);
+                // bigint/decimal -> int keeps the low 64 bits, then narrows like any int
+                if let Some(source_type) = self.runtime_number_type(expr) {
+                    if source_type == *target {
+                        return Ok(value);
+                    }
+                    let prefix = Self::runtime_number_prefix(&source_type);
+                    let conversion = if *target == Type::Float { "to_float" } else { "to_int" };
+                    let converted = self.call_runtime(&format!("{}_{}", prefix, conversion), &[value.into()]);
+                    return Ok(self.widen_to(converted, target_type));
+                }
+                if matches!(target, Type::BigInt | Type::Decimal) {
+                    return Ok(if value.is_float_value() {
+                        self.call_runtime("decimal_from_float", &[value.into()])
+                    } else {
+                        self.int_to_runtime_number(value, target)
+                    });
+                }
+                match (value, target_type) {
+                    // float -> integer truncates toward zero; NaN becomes 0 and
+                    // out-of-range values saturate at the target type's limits
+                    (BasicValueEnum::FloatValue(float_val), BasicTypeEnum::IntType(int_type)) => {
+                        let intrinsic_name = if Self::is_unsigned_int(int_type) {
+                            "llvm.fptoui.sat"
+                        } else {
+                            "llvm.fptosi.sat"
+                        };
+                        let intrinsic = Intrinsic::find(intrinsic_name)
+                            .ok_or(format!("LLVM intrinsic '{}' not found", intrinsic_name))?;
+                        let float_to_int = intrinsic
+                            .get_declaration(&self.module, &[int_type.into(), float_val.get_type().into()])
+                            .ok_or(format!("Failed to declare LLVM intrinsic '{}'", intrinsic_name))?;
+                        Ok(self.builder
+                            .build_call(float_to_int, &[float_val.into()], "ftoi")
+                            .unwrap()
+                            .try_as_basic_value()
+                            .left()
+                            .unwrap())
+                    }
+                    // int -> float rounds to the nearest representable float; between integer
+                    // types, widening preserves the value and narrowing wraps; bool -> int is 0 or 1
+                    (BasicValueEnum::IntValue(_), _) => Ok(self.widen_to(value, target_type)),
+                    (value, _) => Ok(value),
+                }
+            }
+
+            Expression::Call { callee, args, named_args, line } => {
+                // Set debug location for this call
+                self.set_debug_line(*line);
 
                 // Check if this is a module.function() call
                 if let Expression::MemberAccess { object, member } = &**callee {
-                    if let Expression::Variable(_module_name) = &**object {
-                        // Module.function() call - extract function name and call it
-                        // Module name already validated by type checker, just use function name
+                    if let Expression::Variable(module_name) = &**object {
+                        // Module.function() call - module name already validated by type checker
+                        let member = &self.module_function(module_name, member).unwrap_or_else(|| member.clone());
                         let function = if let Some(&func) = self.functions.get(member) {
                             func
                         } else if let Some(func) = self.module.get_function(member) {
@@ -2848,12 +6419,9 @@ impl<'ctx> CodeGen<'ctx> {
                             return Err(format!("Undefined function '{}'", member));
                         };
 
-                        let mut arg_values: Vec<BasicMetadataValueEnum> = Vec::new();
-                        for arg in args {
-                            let arg_val = self.compile_expression(arg)?;
-                            arg_values.push(arg_val.into());
-                        }
+                        let mut arg_values = self.compile_call_args(member, args, named_args)?;
 
+                        self.widen_call_args(function, &mut arg_values);
                         let call_site_value = self
                             .builder
                             .build_call(function, &arg_values, "calltmp")
@@ -2867,7 +6435,87 @@ impl<'ctx> CodeGen<'ctx> {
                     }
                 }
 
+                // A name that reaches an imported module's function calls it,
+                // even when a builtin has the same name
+                if let Expression::Variable(func_name) = &**callee {
+                    let function = self.resolve_function(func_name);
+                    if function != *func_name {
+                        return self.compile_expression(&Expression::Call {
+                            callee: Box::new(Expression::Variable(function)),
+                            args: args.clone(),
+                            named_args: named_args.clone(),
+                            line: *line,
+                        });
+                    }
+                }
+
                 if let Expression::Variable(func_name) = &**callee {
+                    // Generic print() dispatches on the argument's LLVM type
+                    // (unless the program defines its own print)
+                    if func_name == "print" && !self.functions.contains_key("print") {
+                        if args.len() != 1 {
+                            return Err("print() takes exactly 1 argument".to_string());
+                        }
+                        let value = self.compile_expression(&args[0])?;
+                        let (printer, value) = if let Some(class_name) = self.printable_class(&args[0]) {
+                            ("print_str", self.build_to_string_call(&class_name, value)?)
+                        } else if let Some(ws_type) = self.runtime_number_type(&args[0]) {
+                            let to_str = format!("{}_to_str", Self::runtime_number_prefix(&ws_type));
+                            ("print_str", self.call_runtime(&to_str, &[value.into()]))
+                        } else if value.is_float_value() {
+                            let float_to_str = *self.functions.get("float_to_str").unwrap();
+                            let text = self.builder
+                                .build_call(float_to_str, &[value.into()], "float_str")
+                                .unwrap()
+                                .try_as_basic_value()
+                                .left()
+                                .unwrap();
+                            ("print_str", text)
+                        } else if value.is_int_value() && value.into_int_value().get_type().get_bit_width() == 1 {
+                            ("print_bool", value)
+                        } else if value.is_int_value() {
+                            ("print_int", self.widen_to(value, self.context.i64_type().as_basic_type_enum()))
+                        } else {
+                            ("print_str", value)
+                        };
+                        let print_fn = *self.functions.get(printer).unwrap();
+                        self.builder.build_call(print_fn, &[value.into()], "").unwrap();
+                        return Ok(self.context.i64_type().const_zero().as_basic_value_enum());
+                    }
+
+                    // Ok()/Err() outside a typed slot (see compile_expression_as)
+                    if self.result_constructor(expression).is_some() {
+                        return Err(format!(
+                            "Cannot infer the Result type of {}(...); assign it to a typed variable, pass it as an argument, or return it",
+                            func_name
+                        ));
+                    }
+
+                    if func_name == "checked_add" || func_name == "checked_mul" {
+                        return self.build_checked_builtin(func_name, args);
+                    }
+                    // parse_bool() unless the program replaces it
+                    if func_name == "parse_bool" && !self.functions.contains_key("parse_bool") {
+                        return self.build_parse_bool(args);
+                    }
+                    if func_name == "truthy" {
+                        return self.build_truthy(args);
+                    }
+                    if matches!(func_name.as_str(), "int" | "float" | "str") {
+                        return self.build_conversion(func_name, args);
+                    }
+                    if func_name == "format" {
+                        return self.build_format(args);
+                    }
+                    if func_name == "input" {
+                        return self.build_input(args);
+                    }
+                    // read_line() reads standard input unless the program (or
+                    // std/io.ws) defines its own read_line
+                    if func_name == "read_line" && !self.functions.contains_key("read_line") {
+                        return Ok(self.call_runtime("stdin_read_line", &[]));
+                    }
+
                     // Handle range() as a special built-in
                     if func_name == "range" {
                         if args.len() != 1 {
@@ -2878,10 +6526,11 @@ impl<'ctx> CodeGen<'ctx> {
                         let n_int = n.into_int_value();
 
                         // Create empty list
-                        let list_create = *self.functions.get("list_create_i64").unwrap();
+                        let list_create = *self.functions.get("list_create").unwrap();
+                        let plain = self.context.i64_type().const_int(ELEMS_PLAIN as u64, false);
                         let list_ptr = self
                             .builder
-                            .build_call(list_create, &[], "range_list")
+                            .build_call(list_create, &[plain.into()], "range_list")
                             .unwrap()
                             .try_as_basic_value()
                             .left()
@@ -2932,64 +6581,22 @@ impl<'ctx> CodeGen<'ctx> {
                         self.builder.build_store(counter, next_i).unwrap();
                         self.builder.build_unconditional_branch(loop_header).unwrap();
 
-                        // After loop
-                        self.builder.position_at_end(loop_exit);
-                        return Ok(list_ptr.as_basic_value_enum());
-                    }
-
-                    let function = if let Some(&func) = self.functions.get(func_name) {
-                        func
-                    } else if let Some(func) = self.module.get_function(func_name) {
-                        func
-                    } else {
-                        return Err(format!("Undefined function '{}'", func_name));
-                    };
-
-                    // Build argument list, handling named args and defaults
-                    let mut arg_values: Vec<BasicMetadataValueEnum> = Vec::new();
-
-                    if let Some(params) = self.function_params.get(func_name).cloned() {
-                        // User-defined function with param info
-                        let mut final_args: Vec<Option<BasicValueEnum>> = vec![None; params.len()];
-
-                        // Fill in positional arguments
-                        for (i, arg) in args.iter().enumerate() {
-                            let arg_val = self.compile_expression(arg)?;
-                            final_args[i] = Some(arg_val);
-                        }
-
-                        // Fill in named arguments
-                        for (name, value) in named_args {
-                            if let Some(idx) = params.iter().position(|p| &p.name == name) {
-                                let arg_val = self.compile_expression(value)?;
-                                final_args[idx] = Some(arg_val);
-                            }
-                        }
-
-                        // Fill in defaults for missing arguments
-                        for (i, param) in params.iter().enumerate() {
-                            if final_args[i].is_none() {
-                                if let Some(ref default_expr) = param.default_value {
-                                    let default_val = self.compile_expression(default_expr)?;
-                                    final_args[i] = Some(default_val);
-                                }
-                            }
-                        }
-
-                        // Convert to arg_values
-                        for arg_opt in final_args {
-                            if let Some(val) = arg_opt {
-                                arg_values.push(val.into());
-                            }
-                        }
-                    } else {
-                        // Built-in function - just use positional args
-                        for arg in args {
-                            let arg_val = self.compile_expression(arg)?;
-                            arg_values.push(arg_val.into());
-                        }
+                        // After loop
+                        self.builder.position_at_end(loop_exit);
+                        return Ok(list_ptr.as_basic_value_enum());
                     }
 
+                    let function = if let Some(&func) = self.functions.get(func_name) {
+                        func
+                    } else if let Some(func) = self.module.get_function(func_name) {
+                        func
+                    } else {
+                        return Err(format!("Undefined function '{}'", func_name));
+                    };
+
+                    let mut arg_values = self.compile_call_args(func_name, args, named_args)?;
+
+                    self.widen_call_args(function, &mut arg_values);
                     let call_site_value = self
                         .builder
                         .build_call(function, &arg_values, "calltmp")
@@ -3006,43 +6613,28 @@ impl<'ctx> CodeGen<'ctx> {
             }
 
             Expression::MemberAccess { object, member } => {
-                // Check if this is a field access on a class instance
-                if let Expression::Variable(var_name) = &**object {
-                    if let Some((_ptr, _llvm_type, ast_type)) = self.variables.get(var_name) {
-                        if let Type::Custom(class_name) = ast_type {
-                            // This is a class instance field access
-                            let struct_type = *self.class_types.get(class_name).unwrap();
-                            let field_names = self.class_fields.get(class_name).unwrap().clone();
-
-                            // Find field index
-                            if let Some(field_idx) = field_names.iter().position(|f| f == member) {
-                                // Get the object pointer
-                                let obj_val = self.compile_expression(object)?;
-                                let obj_ptr = obj_val.into_pointer_value();
-
-                                // Get field type from struct
-                                let field_type = struct_type.get_field_type_at_index(field_idx as u32).unwrap();
-
-                                // Get field pointer
-                                let field_ptr = self
-                                    .builder
-                                    .build_struct_gep(struct_type, obj_ptr, field_idx as u32, member)
-                                    .unwrap();
-
-                                // Load the field value
-                                let field_val = self
-                                    .builder
-                                    .build_load(field_type, field_ptr, member)
-                                    .unwrap();
-
-                                return Ok(field_val);
-                            }
-                        }
+                // Field access on a class instance: a variable, another field, or a
+                // call that returns one (`get_user().address.city`)
+                if let Some(class_name) = self.object_class(object) {
+                    if let Some(field_idx) = self.class_field_index(&class_name, member) {
+                        let struct_type = *self.class_types.get(&class_name).unwrap();
+                        let obj_ptr = self.compile_expression(object)?.into_pointer_value();
+                        let field_type = struct_type.get_field_type_at_index(field_idx).unwrap();
+                        let field_ptr = self
+                            .builder
+                            .build_struct_gep(struct_type, obj_ptr, field_idx, member)
+                            .unwrap();
+                        return Ok(self.builder.build_load(field_type, field_ptr, member).unwrap());
                     }
                 }
 
                 // Handle .length property for lists and strings
                 if member == "length" {
+                    if let Expression::Variable(name) = &**object {
+                        if let Some(&length) = self.hoisted_lengths.get(name) {
+                            return Ok(length);
+                        }
+                    }
                     let obj_val = self.compile_expression(object)?;
 
                     // Determine the type of object to call the right function
@@ -3062,6 +6654,9 @@ impl<'ctx> CodeGen<'ctx> {
                         .left()
                         .unwrap();
                     Ok(length)
+                } else if member == "byte_length" && self.is_string_expression(object) {
+                    let obj_val = self.compile_expression(object)?;
+                    Ok(self.call_runtime("str_byte_length", &[obj_val.into()]))
                 } else {
                     Err(format!("Member access '{}' not implemented", member))
                 }
@@ -3077,11 +6672,16 @@ impl<'ctx> CodeGen<'ctx> {
                 // OPTIMIZATION: Check if this is a last-use move (x = y, where y is never used again)
                 let is_move = if let Expression::Variable(source_name) = &**value {
                     if let Some((_, _, source_type)) = self.variables.get(source_name) {
-                        if self.is_rc_type(source_type) {
-                            // Check if source variable is used in remaining statements
-                            let is_last_use = !self.remaining_statements.iter().any(|stmt| {
-                                self.statement_uses_variable(stmt, source_name)
-                            });
+                        if self.is_rc_type(source_type) && self.owned_variables.contains(source_name) {
+                            // Check if source variable is used (or given a new value, which
+                            // would release the moved one) in remaining statements. Only the
+                            // function's own statements are tracked, so a loop body, which
+                            // runs again, never moves
+                            let is_last_use = self.loop_nesting_depth == 0
+                                && !self.remaining_statements.iter().any(|stmt| {
+                                    self.statement_uses_variable(stmt, source_name)
+                                        || self.statement_assigns_variable(stmt, source_name)
+                                });
 
                             if is_last_use {
                                 // Mark source as moved - ownership transferred
@@ -3100,21 +6700,24 @@ impl<'ctx> CodeGen<'ctx> {
                     false
                 };
 
-                let new_val = self.compile_expression(value)?;
+                let new_val = self.compile_expression_as(value, &ast_type)?;
 
                 // Add RC logic for ref-counted types
                 if self.is_rc_type(&ast_type) && new_val.is_pointer_value() {
                     let new_ptr = new_val.into_pointer_value();
 
-                    // OPTIMIZATION: Skip retain if this is a move
-                    if !is_move {
+                    // OPTIMIZATION: Skip retain if this is a move. A new allocation
+                    // is already owned
+                    if !is_move && self.is_borrowed_value(value) {
                         // Retain new value
-                        self.build_rc_retain_inline(new_ptr);
+                        self.build_rc_retain_nullable(new_ptr);
                     }
 
-                    // Load and release old value
+                    // Load and release old value, unless it was borrowed (a parameter
+                    // or loop variable)
+                    let owns_old = self.owned_variables.contains(target) || self.repl_globals.contains(target);
                     let old_val = self.builder.build_load(var_type, ptr, "old_val").unwrap();
-                    if old_val.is_pointer_value() {
+                    if owns_old && old_val.is_pointer_value() {
                         let old_ptr = old_val.into_pointer_value();
                         // Check if not null before releasing
                         let is_null = self.builder.build_is_null(old_ptr, "is_null").unwrap();
@@ -3125,7 +6728,7 @@ impl<'ctx> CodeGen<'ctx> {
                         self.builder.build_conditional_branch(is_null, store_block, release_block).unwrap();
 
                         self.builder.position_at_end(release_block);
-                        self.build_rc_release_inline(old_ptr);
+                        self.build_rc_release_inline(old_ptr, &ast_type);
                         self.builder.build_unconditional_branch(store_block).unwrap();
 
                         self.builder.position_at_end(store_block);
@@ -3140,110 +6743,49 @@ impl<'ctx> CodeGen<'ctx> {
                 Err("Array literals not yet fully implemented in codegen".to_string())
             }
 
-            Expression::ListLiteral { elements } => {
-                // For now, only support int lists
-                // Create empty list
-                let list_create = self.functions.get("list_create_i64").unwrap();
-                let list_ptr = self
-                    .builder
-                    .build_call(*list_create, &[], "list")
-                    .unwrap()
-                    .try_as_basic_value()
-                    .left()
-                    .unwrap();
-
-                // Add each element by calling list_push_i64
-                if !elements.is_empty() {
-                    let list_push = *self.functions.get("list_push_i64").unwrap();
-
-                    for element in elements {
-                        let element_value = self.compile_expression(element)?;
-                        self.builder
-                            .build_call(list_push, &[list_ptr.into(), element_value.into()], "")
-                            .unwrap();
-                    }
-                }
-
-                Ok(list_ptr)
-            }
-
-            Expression::DictLiteral { pairs } => {
-                // Create empty dict
-                let dict_create = self.functions.get("dict_create").unwrap();
-                let dict_ptr = self
-                    .builder
-                    .build_call(*dict_create, &[], "dict")
-                    .unwrap()
-                    .try_as_basic_value()
-                    .left()
-                    .unwrap();
-
-                // Add each key-value pair
-                if !pairs.is_empty() {
-                    let dict_set = *self.functions.get("dict_set").unwrap();
+            Expression::ListLiteral { elements } => self.compile_list_literal(elements, None),
 
-                    for (key_expr, val_expr) in pairs {
-                        let key_value = self.compile_expression(key_expr)?;
-                        let val_value = self.compile_expression(val_expr)?;
-
-                        // For now, assume keys are strings and values are ints
-                        self.builder
-                            .build_call(dict_set, &[dict_ptr.into(), key_value.into(), val_value.into()], "")
-                            .unwrap();
-                    }
-                }
-
-                Ok(dict_ptr)
-            }
+            Expression::DictLiteral { pairs } => self.compile_dict_literal(pairs, None),
 
             Expression::Index { object, index, line } => {
                 let obj_val = self.compile_expression(object)?;
                 let idx_val = self.compile_expression(index)?;
 
                 // Set debug location for this operation
-                let scope = if let Some(func_scope) = self.current_debug_scope {
-                    func_scope.as_debug_info_scope()
-                } else {
-                    self.compile_unit.get_file().as_debug_info_scope()
-                };
-                let debug_loc = self.debug_builder.create_debug_location(
-                    self.context,
-                    *line as u32,
-                    0, // column
-                    scope,
-                    None,
-                );
-                self.builder.set_current_debug_location(debug_loc);
-
-                // Check if this is dict access (string key) or list access (int index)
-                if idx_val.is_pointer_value() {
-                    // Dict access with string key (no line parameter needed)
-                    let dict_get = self.functions.get("dict_get").unwrap();
-                    let result = self
+                self.set_debug_line(*line);
+
+                // Dicts are recognized by their declared type; a pointer index (a str
+                // key) is the fallback for dicts codegen can't resolve
+                let dict_types = self.dict_types(object);
+                if dict_types.is_some() || idx_val.is_pointer_value() {
+                    let str_keys = match &dict_types {
+                        Some((key_type, _)) => *key_type == Type::Str,
+                        None => true,
+                    };
+                    let key_arg: BasicMetadataValueEnum = if str_keys { idx_val.into() } else { self.to_dict_slot(idx_val).into() };
+                    let dict_get = *self.functions.get(&Self::dict_function("get", str_keys)).unwrap();
+                    let slot = self
                         .builder
-                        .build_call(*dict_get, &[obj_val.into(), idx_val.into()], "dict_value")
+                        .build_call(dict_get, &[obj_val.into(), key_arg], "dict_value")
                         .unwrap()
                         .try_as_basic_value()
                         .left()
-                        .unwrap();
-                    Ok(result)
+                        .unwrap()
+                        .into_int_value();
+                    match dict_types {
+                        Some((_, val_type)) => Ok(self.from_dict_slot(slot, &val_type)),
+                        None => Ok(slot.as_basic_value_enum()),
+                    }
                 } else {
                     // List access with int index (no line parameter needed)
-                    let list_get = self.functions.get("list_get_i64").unwrap();
-                    let result = self
-                        .builder
-                        .build_call(*list_get, &[obj_val.into(), idx_val.into()], "element")
-                        .unwrap()
-                        .try_as_basic_value()
-                        .left()
-                        .unwrap();
-                    Ok(result)
+                    let elem_type = self.list_element_type(object);
+                    Ok(self.build_list_load("get", &[obj_val.into(), idx_val.into()], elem_type.as_ref()))
                 }
             }
 
             Expression::IndexAssignment { object, index, value, line } => {
                 // Get the object (dict or list) and load its value
-                let (obj_ptr, obj_llvm_type, _) = self.variables.get(object)
+                let (obj_ptr, obj_llvm_type, obj_type) = self.variables.get(object)
                     .ok_or_else(|| format!("Undefined variable '{}'", object))?
                     .clone();
 
@@ -3251,87 +6793,140 @@ impl<'ctx> CodeGen<'ctx> {
                 let obj_val = self.builder.build_load(obj_llvm_type, obj_ptr, object)
                     .unwrap();
 
+                if let Type::Dict(key_type, val_type) = &obj_type {
+                    let idx_val = self.compile_expression_as(index, key_type)?;
+                    let val_val = self.compile_expression_as(value, val_type)?;
+
+                    // Set debug location for this operation
+                    self.set_debug_line(*line);
+
+                    let str_keys = **key_type == Type::Str;
+                    if !str_keys {
+                        self.retain_for_collection(idx_val, index);
+                    }
+                    if self.is_rc_type(val_type) {
+                        self.retain_for_collection(val_val, value);
+                    }
+                    self.build_dict_set(obj_val, idx_val, str_keys, val_val);
+                    return Ok(self.context.i64_type().const_zero().as_basic_value_enum());
+                }
+
                 let idx_val = self.compile_expression(index)?;
-                let val_val = self.compile_expression(value)?;
+                let val_val = match &obj_type {
+                    Type::List(elem_type) => {
+                        let val_val = self.compile_expression_as(value, elem_type)?;
+                        if self.is_rc_type(elem_type) {
+                            self.retain_for_collection(val_val, value);
+                        }
+                        val_val
+                    }
+                    _ => self.compile_expression(value)?,
+                };
 
                 // Set debug location for this operation
-                let scope = if let Some(func_scope) = self.current_debug_scope {
-                    func_scope.as_debug_info_scope()
-                } else {
-                    self.compile_unit.get_file().as_debug_info_scope()
-                };
-                let debug_loc = self.debug_builder.create_debug_location(
-                    self.context,
-                    *line as u32,
-                    0, // column
-                    scope,
-                    None,
-                );
-                self.builder.set_current_debug_location(debug_loc);
+                self.set_debug_line(*line);
 
                 // Check if this is dict assignment (string key) or list assignment (int index)
                 if idx_val.is_pointer_value() {
                     // Dict assignment with string key
-                    let dict_set = self.functions.get("dict_set")
-                        .ok_or("dict_set function not found")?;
-                    self.builder.build_call(*dict_set,
-                        &[obj_val.into(), idx_val.into(), val_val.into()], "")
-                        .unwrap();
+                    let val_val = self.widen_to(val_val, self.context.i64_type().as_basic_type_enum());
+                    self.build_dict_set(obj_val, idx_val, true, val_val);
                 } else {
                     // List assignment with int index (no line parameter needed)
-                    let list_set = self.functions.get("list_set_i64")
-                        .ok_or("list_set_i64 function not found")?;
-                    self.builder.build_call(*list_set,
-                        &[obj_val.into(), idx_val.into(), val_val.into()], "")
-                        .unwrap();
+                    let elem_type = match &obj_type {
+                        Type::List(elem_type) => Some(elem_type.as_ref()),
+                        _ => None,
+                    };
+                    self.build_list_store("set", &[obj_val.into(), idx_val.into()], val_val, elem_type);
                 }
 
                 // Return void
                 Ok(self.context.i64_type().const_zero().as_basic_value_enum())
             }
 
-            Expression::MethodCall { object, method, args } => {
-                // Check if this is a class method call FIRST
-                if let Expression::Variable(var_name) = &**object {
-                    if let Some((_ptr, _llvm_type, ast_type)) = self.variables.get(var_name) {
-                        if let Type::Custom(class_name) = ast_type {
-                            // This is a class method call
-                            let method_full_name = format!("{}::{}", class_name, method);
-                            if let Some(&func) = self.functions.get(&method_full_name) {
-                                // Get the object value (pointer to struct)
-                                let obj_val = self.compile_expression(object)?;
-
-                                // Build arguments: self + user args
-                                let mut arg_values: Vec<BasicMetadataValueEnum> = vec![obj_val.into()];
-                                for arg in args {
-                                    let arg_val = self.compile_expression(arg)?;
-                                    arg_values.push(arg_val.into());
-                                }
+            Expression::FieldAssignment { object, field, value } => {
+                let class_name = match self.declared_type(object) {
+                    Some(Type::Custom(class_name)) => class_name,
+                    _ => return Err(format!("Cannot determine the class for '.{} = ...'", field)),
+                };
+                let struct_type = *self.class_types.get(&class_name)
+                    .ok_or_else(|| format!("Unknown class '{}'", class_name))?;
+                let field_idx = self.class_field_index(&class_name, field)
+                    .ok_or_else(|| format!("Class '{}' has no field '{}'", class_name, field))?;
+                let field_type = self.class_field_types[&class_name][field_idx as usize - 1].clone();
+
+                let obj_ptr = self.compile_expression(object)?.into_pointer_value();
+                let val = self.compile_expression_as(value, &field_type)?;
+                let field_ptr = self.builder
+                    .build_struct_gep(struct_type, obj_ptr, field_idx, field)
+                    .unwrap();
+
+                // The object owns its RC fields (released by its drop function)
+                if self.is_rc_type(&field_type) && val.is_pointer_value() {
+                    if self.is_borrowed_value(value) {
+                        self.build_rc_retain_nullable(val.into_pointer_value());
+                    }
+                    let ptr_type = self.context.ptr_type(AddressSpace::default());
+                    let old_val = self.builder.build_load(ptr_type, field_ptr, "old_field").unwrap().into_pointer_value();
+                    self.builder.build_store(field_ptr, val).unwrap();
+                    self.build_rc_release_nullable(old_val, &field_type);
+                } else {
+                    self.builder.build_store(field_ptr, val).unwrap();
+                }
 
-                                let call_site_value = self
-                                    .builder
-                                    .build_call(func, &arg_values, "method_call")
-                                    .unwrap();
+                Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+            }
 
-                                if let Some(return_value) = call_site_value.try_as_basic_value().left() {
-                                    return Ok(return_value);
-                                } else {
-                                    return Ok(self.context.i64_type().const_zero().as_basic_value_enum());
-                                }
-                            }
+            Expression::MethodCall { object, method, args, named_args } => {
+                // d.round(n) must be matched before the module-call path below,
+                // which would otherwise pick up the float round() builtin
+                if method == "round" && self.runtime_number_type(object) == Some(Type::Decimal) {
+                    let value = self.compile_expression(object)?;
+                    let digits = self.compile_expression(&args[0])?;
+                    let digits = self.widen_to(digits, self.context.i64_type().as_basic_type_enum());
+                    let rounded = self.call_runtime("decimal_round", &[value.into(), digits.into()]);
+                    // `(a + b).round(2)` rounds a value made just for the call
+                    if !self.is_borrowed_value(object) {
+                        self.build_rc_release_nullable(value.into_pointer_value(), &Type::Decimal);
+                    }
+                    return Ok(rounded);
+                }
+
+                // Check if this is a class method call FIRST, on any expression that
+                // evaluates to an object (`a.b.c()`, `make().run()`)
+                if let Some(class_name) = self.object_class(object) {
+                    // This is a class method call, possibly inherited
+                    if let Some(func) = self.find_method(&class_name, method) {
+                        // Get the object value (pointer to struct)
+                        let obj_val = self.compile_expression(object)?;
+
+                        // Build arguments: self + user args
+                        let mut arg_values: Vec<BasicMetadataValueEnum> = vec![obj_val.into()];
+                        for arg in args {
+                            let arg_val = self.compile_expression(arg)?;
+                            arg_values.push(arg_val.into());
+                        }
+
+                        self.widen_call_args(func, &mut arg_values);
+                        let call_site_value = self.build_virtual_call(&class_name, method, func, &arg_values);
+
+                        if let Some(return_value) = call_site_value.try_as_basic_value().left() {
+                            return Ok(return_value);
+                        } else {
+                            return Ok(self.context.i64_type().const_zero().as_basic_value_enum());
                         }
                     }
+                }
 
+                if let Expression::Variable(object_name) = &**object {
                     // If not a class instance, check if this is a module.function() call
                     // Check if this method exists as a regular function
+                    let method = &self.module_function(object_name, method).unwrap_or_else(|| method.clone());
                     if let Some(&func) = self.functions.get(method) {
                         // This is a module function call
-                        let mut arg_values: Vec<BasicMetadataValueEnum> = Vec::new();
-                        for arg in args {
-                            let arg_val = self.compile_expression(arg)?;
-                            arg_values.push(arg_val.into());
-                        }
+                        let mut arg_values = self.compile_call_args(method, args, named_args)?;
 
+                        self.widen_call_args(func, &mut arg_values);
                         let call_site_value = self
                             .builder
                             .build_call(func, &arg_values, "calltmp")
@@ -3344,12 +6939,9 @@ impl<'ctx> CodeGen<'ctx> {
                         }
                     } else if let Some(func) = self.module.get_function(method) {
                         // This is a module function call from the current module
-                        let mut arg_values: Vec<BasicMetadataValueEnum> = Vec::new();
-                        for arg in args {
-                            let arg_val = self.compile_expression(arg)?;
-                            arg_values.push(arg_val.into());
-                        }
+                        let mut arg_values = self.compile_call_args(method, args, named_args)?;
 
+                        self.widen_call_args(func, &mut arg_values);
                         let call_site_value = self
                             .builder
                             .build_call(func, &arg_values, "calltmp")
@@ -3365,16 +6957,70 @@ impl<'ctx> CodeGen<'ctx> {
 
                 let obj_val = self.compile_expression(object)?;
 
+                if obj_val.is_struct_value()
+                    && matches!(method.as_str(), "is_ok" | "is_err" | "unwrap" | "unwrap_err" | "unwrap_or")
+                {
+                    let err_type = match self.declared_type(object) {
+                        Some(Type::Result(_, err_type)) => Some(*err_type),
+                        _ => None,
+                    };
+                    return self.compile_result_method(obj_val.into_struct_value(), method, args, err_type);
+                }
+
                 match method.as_str() {
+                    // The runtime releases the dict's references to the removed keys and
+                    // values, except that pop() hands the value over to the caller.
+                    // get(key, default) leaves the entry and retains what it returns
+                    "remove" | "pop" | "clear" | "get" if self.dict_types(object).is_some() => {
+                        let (key_type, val_type) = self.dict_types(object).unwrap();
+                        if method == "clear" {
+                            if !args.is_empty() {
+                                return Err("clear() takes no arguments".to_string());
+                            }
+                            let dict_clear = *self.functions.get("dict_clear").unwrap();
+                            self.builder.build_call(dict_clear, &[obj_val.into()], "").unwrap();
+                            return Ok(self.context.i64_type().const_zero().as_basic_value_enum());
+                        }
+
+                        let expected_args = if method == "remove" { 1 } else { 2 };
+                        if args.len() != expected_args {
+                            return Err(format!("{}() takes exactly {} argument(s)", method, expected_args));
+                        }
+                        let str_keys = key_type == Type::Str;
+                        let key = self.compile_expression_as(&args[0], &key_type)?;
+                        let key_arg: BasicMetadataValueEnum = if str_keys { key.into() } else { self.to_dict_slot(key).into() };
+                        let operation = if method == "get" { "get_or" } else { method.as_str() };
+                        let function = Self::dict_function(operation, str_keys);
+                        if method == "remove" {
+                            let found = self.call_runtime(&function, &[obj_val.into(), key_arg]).into_int_value();
+                            let removed = self.builder
+                                .build_int_compare(IntPredicate::NE, found, self.context.i32_type().const_zero(), "removed")
+                                .unwrap();
+                            return Ok(removed.as_basic_value_enum());
+                        }
+                        let default = self.compile_expression_as(&args[1], &val_type)?;
+                        let slot = self
+                            .call_runtime(&function, &[obj_val.into(), key_arg, self.to_dict_slot(default).into()])
+                            .into_int_value();
+                        Ok(self.from_dict_slot(slot, &val_type))
+                    }
+
                     "push" => {
                         if args.len() != 1 {
                             return Err("push() takes exactly 1 argument".to_string());
                         }
-                        let arg_val = self.compile_expression(&args[0])?;
-                        let list_push = *self.functions.get("list_push_i64").unwrap();
-                        self.builder
-                            .build_call(list_push, &[obj_val.into(), arg_val.into()], "")
-                            .unwrap();
+                        let elem_type = self.list_element_type(object);
+                        let arg_val = match &elem_type {
+                            Some(elem_type) => {
+                                let arg_val = self.compile_expression_as(&args[0], elem_type)?;
+                                if self.is_rc_type(elem_type) {
+                                    self.retain_for_collection(arg_val, &args[0]);
+                                }
+                                arg_val
+                            }
+                            None => self.compile_expression(&args[0])?,
+                        };
+                        self.build_list_store("push", &[obj_val.into()], arg_val, elem_type.as_ref());
                         // push returns void, return a dummy value
                         Ok(self.context.i64_type().const_zero().as_basic_value_enum())
                     }
@@ -3383,15 +7029,8 @@ impl<'ctx> CodeGen<'ctx> {
                         if !args.is_empty() {
                             return Err("pop() takes no arguments".to_string());
                         }
-                        let list_pop = *self.functions.get("list_pop_i64").unwrap();
-                        let result = self
-                            .builder
-                            .build_call(list_pop, &[obj_val.into()], "pop_result")
-                            .unwrap()
-                            .try_as_basic_value()
-                            .left()
-                            .unwrap();
-                        Ok(result)
+                        let elem_type = self.list_element_type(object);
+                        Ok(self.build_list_load("pop", &[obj_val.into()], elem_type.as_ref()))
                     }
 
                     "get" => {
@@ -3399,17 +7038,191 @@ impl<'ctx> CodeGen<'ctx> {
                             return Err("get() takes exactly 1 argument".to_string());
                         }
                         let idx_val = self.compile_expression(&args[0])?;
-                        let list_get = *self.functions.get("list_get_i64").unwrap();
-                        let result = self
-                            .builder
-                            .build_call(list_get, &[obj_val.into(), idx_val.into()], "get_result")
-                            .unwrap()
-                            .try_as_basic_value()
-                            .left()
+                        let elem_type = self.list_element_type(object);
+                        Ok(self.build_list_load("get", &[obj_val.into(), idx_val.into()], elem_type.as_ref()))
+                    }
+
+                    "insert" => {
+                        if args.len() != 2 {
+                            return Err("insert() takes exactly 2 arguments".to_string());
+                        }
+                        let idx_val = self.compile_expression(&args[0])?;
+                        let elem_type = self.list_element_type(object);
+                        let arg_val = match &elem_type {
+                            Some(elem_type) => {
+                                let arg_val = self.compile_expression_as(&args[1], elem_type)?;
+                                if self.is_rc_type(elem_type) {
+                                    self.retain_for_collection(arg_val, &args[1]);
+                                }
+                                arg_val
+                            }
+                            None => self.compile_expression(&args[1])?,
+                        };
+                        self.build_list_store("insert", &[obj_val.into(), idx_val.into()], arg_val, elem_type.as_ref());
+                        Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+                    }
+
+                    "remove_at" => {
+                        if args.len() != 1 {
+                            return Err("remove_at() takes exactly 1 argument".to_string());
+                        }
+                        let idx_val = self.compile_expression(&args[0])?;
+                        let elem_type = self.list_element_type(object);
+                        Ok(self.build_list_load("remove_at", &[obj_val.into(), idx_val.into()], elem_type.as_ref()))
+                    }
+
+                    "index_of" => {
+                        if args.len() != 1 {
+                            return Err("index_of() takes exactly 1 argument".to_string());
+                        }
+                        Ok(self.build_list_index_of(obj_val, object, &args[0])?.as_basic_value_enum())
+                    }
+
+                    "contains" if !self.is_string_expression(object) => {
+                        if args.len() != 1 {
+                            return Err("contains() takes exactly 1 argument".to_string());
+                        }
+                        let index = self.build_list_index_of(obj_val, object, &args[0])?;
+                        let not_found = self.context.i64_type().const_all_ones();
+                        let found = self.builder
+                            .build_int_compare(IntPredicate::NE, index, not_found, "contains")
                             .unwrap();
+                        Ok(found.as_basic_value_enum())
+                    }
+
+                    "clear" => {
+                        if !args.is_empty() {
+                            return Err("clear() takes no arguments".to_string());
+                        }
+                        let list_clear = *self.functions.get("list_clear").unwrap();
+                        self.builder.build_call(list_clear, &[obj_val.into()], "").unwrap();
+                        Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+                    }
+
+                    "sort" => {
+                        if !args.is_empty() {
+                            return Err("sort() takes no arguments".to_string());
+                        }
+                        // Bools are 0/1 in their slots and sort with the ints
+                        let suffix = match self.list_element_type(object) {
+                            Some(Type::Float) => "f64",
+                            Some(Type::Str) => "str",
+                            _ => "i64",
+                        };
+                        let list_sort = *self.functions.get(&format!("list_sort_{}", suffix)).unwrap();
+                        self.builder.build_call(list_sort, &[obj_val.into()], "").unwrap();
+                        Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+                    }
+
+                    // The runtime sorts, calling back into a comparator built for the function
+                    "sort_by" => {
+                        let name = match args.first() {
+                            Some(Expression::Variable(name)) if args.len() == 1 => self.resolve_function(name),
+                            _ => return Err("sort_by() takes the name of a function".to_string()),
+                        };
+                        let elem_type = self.list_element_type(object);
+                        let comparator = self.sort_by_comparator(&name, elem_type.as_ref())?;
+                        let comparator_ptr = comparator.as_global_value().as_pointer_value();
+                        let list_sort_by = *self.functions.get("list_sort_by").unwrap();
+                        self.builder.build_call(list_sort_by, &[obj_val.into(), comparator_ptr.into()], "").unwrap();
+                        Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+                    }
+
+                    "keys" | "values" | "items" if self.dict_types(object).is_some() => {
+                        if !args.is_empty() {
+                            return Err(format!("{}() takes no arguments", method));
+                        }
+                        Ok(self.call_runtime(&format!("dict_{}", method), &[obj_val.into()]))
+                    }
+
+                    "map" | "filter" | "reduce" => {
+                        let expected_args = if method == "reduce" { 2 } else { 1 };
+                        let name = match args.first() {
+                            Some(Expression::Variable(name)) if args.len() == expected_args => self.resolve_function(name),
+                            _ => return Err(format!("{}() takes the name of a function", method)),
+                        };
+                        let elem_type = self.list_element_type(object);
+
+                        if method == "reduce" {
+                            let total_type = self.function_params.get(&name).and_then(|params| params.first()).map(|p| p.param_type.clone());
+                            let init = match &total_type {
+                                Some(total_type) => self.compile_expression_as(&args[1], total_type)?,
+                                None => self.compile_expression(&args[1])?,
+                            };
+                            let total_llvm_type = init.get_type();
+                            let total_alloca = self.builder.build_alloca(total_llvm_type, "_total").unwrap();
+                            self.builder.build_store(total_alloca, init).unwrap();
+                            self.build_list_each(obj_val, elem_type.as_ref(), |this, item| {
+                                let total = this.builder.build_load(total_llvm_type, total_alloca, "total").unwrap();
+                                let next = this.call_passed_function(&name, &[total, item])?;
+                                let next = this.widen_to(next, total_llvm_type);
+                                this.builder.build_store(total_alloca, next).unwrap();
+                                Ok(())
+                            })?;
+                            return Ok(self.builder.build_load(total_llvm_type, total_alloca, "reduced").unwrap());
+                        }
+
+                        // map() holds what the function returns, filter() the list's own elements
+                        let held_type = if method == "map" { self.function_return_types.get(&name).cloned() } else { elem_type.clone() };
+                        let (_, kind_arg) = self.elems_kind_arg(held_type.as_ref());
+                        let result = self.call_runtime("list_create", &[kind_arg]);
+                        let retain_kept = elem_type.as_ref().is_some_and(|t| self.is_rc_type(t));
+                        self.build_list_each(obj_val, elem_type.as_ref(), |this, item| {
+                            let value = this.call_passed_function(&name, &[item])?;
+                            if method == "map" {
+                                // The function's result is owned, so the new list takes it over
+                                this.build_list_store("push", &[result.into()], value, held_type.as_ref());
+                                return Ok(());
+                            }
+
+                            let function = this.current_function.unwrap();
+                            let keep_block = this.context.append_basic_block(function, "filter_keep");
+                            let next_block = this.context.append_basic_block(function, "filter_next");
+                            this.builder
+                                .build_conditional_branch(value.into_int_value(), keep_block, next_block)
+                                .unwrap();
+                            this.builder.position_at_end(keep_block);
+                            if retain_kept {
+                                this.build_rc_retain_nullable(item.into_pointer_value());
+                            }
+                            this.build_list_store("push", &[result.into()], item, held_type.as_ref());
+                            this.builder.build_unconditional_branch(next_block).unwrap();
+                            this.builder.position_at_end(next_block);
+                            Ok(())
+                        })?;
                         Ok(result)
                     }
 
+                    "reverse" => {
+                        if !args.is_empty() {
+                            return Err("reverse() takes no arguments".to_string());
+                        }
+                        let list_reverse = *self.functions.get("list_reverse").unwrap();
+                        self.builder.build_call(list_reverse, &[obj_val.into()], "").unwrap();
+                        Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+                    }
+
+                    // Shuffles with the random module's generator, so seed() makes it repeatable
+                    "shuffle" => {
+                        if !args.is_empty() {
+                            return Err("shuffle() takes no arguments".to_string());
+                        }
+                        let list_shuffle = *self.functions.get("list_shuffle").unwrap();
+                        self.builder.build_call(list_shuffle, &[obj_val.into()], "").unwrap();
+                        Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+                    }
+
+                    "extend" => {
+                        if args.len() != 1 {
+                            return Err("extend() takes exactly 1 argument".to_string());
+                        }
+                        // The list takes its own references to the elements
+                        let other = self.compile_expression(&args[0])?;
+                        let list_extend = *self.functions.get("list_extend").unwrap();
+                        self.builder.build_call(list_extend, &[obj_val.into(), other.into()], "").unwrap();
+                        Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+                    }
+
                     "upper" => {
                         if !args.is_empty() {
                             return Err("upper() takes no arguments".to_string());
@@ -3425,6 +7238,65 @@ impl<'ctx> CodeGen<'ctx> {
                         Ok(result)
                     }
 
+                    "split" => {
+                        if args.len() != 1 {
+                            return Err("split() takes exactly 1 argument".to_string());
+                        }
+                        let sep_val = self.compile_expression(&args[0])?;
+                        Ok(self.call_runtime("str_split", &[obj_val.into(), sep_val.into()]))
+                    }
+
+                    "chars" => {
+                        if !args.is_empty() {
+                            return Err("chars() takes no arguments".to_string());
+                        }
+                        Ok(self.call_runtime("str_chars", &[obj_val.into()]))
+                    }
+
+                    // sep.join(parts): the separator is the object
+                    "join" => {
+                        if args.len() != 1 {
+                            return Err("join() takes exactly 1 argument".to_string());
+                        }
+                        let parts_val = self.compile_expression(&args[0])?;
+                        Ok(self.call_runtime("str_join", &[obj_val.into(), parts_val.into()]))
+                    }
+
+                    "replace" => {
+                        if args.len() != 2 {
+                            return Err("replace() takes exactly 2 arguments".to_string());
+                        }
+                        let old_val = self.compile_expression(&args[0])?;
+                        let new_val = self.compile_expression(&args[1])?;
+                        Ok(self.call_runtime("str_replace", &[obj_val.into(), old_val.into(), new_val.into()]))
+                    }
+
+                    "find" => {
+                        if args.len() != 1 {
+                            return Err("find() takes exactly 1 argument".to_string());
+                        }
+                        let sub_val = self.compile_expression(&args[0])?;
+                        Ok(self.call_runtime("str_find", &[obj_val.into(), sub_val.into()]))
+                    }
+
+                    "startswith" | "endswith" => {
+                        if args.len() != 1 {
+                            return Err(format!("{}() takes exactly 1 argument", method));
+                        }
+                        let affix_val = self.compile_expression(&args[0])?;
+                        let runtime_name = if method == "startswith" { "str_starts_with" } else { "str_ends_with" };
+                        let found = self.call_runtime(runtime_name, &[obj_val.into(), affix_val.into()]).into_int_value();
+                        let zero = self.context.i32_type().const_zero();
+                        Ok(self.builder.build_int_compare(IntPredicate::NE, found, zero, method).unwrap().into())
+                    }
+
+                    "strip" | "lstrip" | "rstrip" => {
+                        if !args.is_empty() {
+                            return Err(format!("{}() takes no arguments", method));
+                        }
+                        Ok(self.call_runtime(&format!("str_{}", method), &[obj_val.into()]))
+                    }
+
                     "lower" => {
                         if !args.is_empty() {
                             return Err("lower() takes no arguments".to_string());
@@ -3445,102 +7317,102 @@ impl<'ctx> CodeGen<'ctx> {
                             return Err("contains() takes exactly 1 argument".to_string());
                         }
                         let arg_val = self.compile_expression(&args[0])?;
-                        let str_contains = *self.functions.get("str_contains").unwrap();
-                        let result = self
-                            .builder
-                            .build_call(str_contains, &[obj_val.into(), arg_val.into()], "contains_result")
-                            .unwrap()
-                            .try_as_basic_value()
-                            .left()
-                            .unwrap();
-                        // Convert i32 result to i64 for consistency
-                        let result_i64 = self.builder.build_int_z_extend(
-                            result.into_int_value(),
-                            self.context.i64_type(),
-                            "contains_i64"
-                        ).unwrap();
-                        Ok(result_i64.as_basic_value_enum())
+                        let found = self.call_runtime("str_contains", &[obj_val.into(), arg_val.into()]).into_int_value();
+                        let zero = self.context.i32_type().const_zero();
+                        Ok(self.builder.build_int_compare(IntPredicate::NE, found, zero, "contains").unwrap().into())
                     }
 
                     _ => Err(format!("Unknown method '{}'", method)),
                 }
             }
 
+            Expression::SuperCall { method, args } => {
+                // A direct call to the base implementation, bypassing the vtable
+                let base = self.current_class.as_ref()
+                    .and_then(|class_name| self.class_bases.get(class_name))
+                    .ok_or_else(|| format!("'super.{}()' used outside a subclass method", method))?;
+                let func = self.find_method(base, method)
+                    .ok_or_else(|| format!("Base class '{}' has no method '{}'", base, method))?;
+                let self_val = self.current_function.unwrap().get_first_param().unwrap();
+
+                let mut arg_values: Vec<BasicMetadataValueEnum> = vec![self_val.into()];
+                for arg in args {
+                    let arg_val = self.compile_expression(arg)?;
+                    arg_values.push(arg_val.into());
+                }
+
+                self.widen_call_args(func, &mut arg_values);
+                let call_site_value = self.builder.build_call(func, &arg_values, "super_call").unwrap();
+
+                if let Some(return_value) = call_site_value.try_as_basic_value().left() {
+                    Ok(return_value)
+                } else {
+                    Ok(self.context.i64_type().const_zero().as_basic_value_enum())
+                }
+            }
+
             Expression::FString { parts, expressions } => {
-                // F-string implementation: concatenate parts and formatted expressions
-                let i64_type = self.context.i64_type();
-
-                // Start with a reasonably sized buffer to avoid buffer overflow
-                // Using 1024 bytes which should be enough for most f-strings
-                let malloc_fn = *self.functions.get("malloc").unwrap();
-                let initial_size = i64_type.const_int(1024, false);
-                let result_str = self.builder
-                    .build_call(malloc_fn, &[initial_size.into()], "fstring_result")
-                    .unwrap()
-                    .try_as_basic_value()
-                    .left()
-                    .unwrap()
-                    .into_pointer_value();
+                // Append the literal parts and each value's text to a buffer that grows
+                // as needed, then copy the result out once at its final length
+                let literal_len: usize = parts.iter().map(String::len).sum();
+                let capacity = self.context.i64_type().const_int((literal_len + 16 * expressions.len()) as u64, false);
+                let builder = self.call_runtime("str_builder_new", &[capacity.into()]);
+                let append_fn = *self.functions.get("str_builder_append").unwrap();
 
-                // Initialize with empty string (null terminator at start)
-                self.builder.build_store(result_str, i64_type.const_int(0, false)).unwrap();
-
-                let strcat_fn = *self.functions.get("strcat").unwrap();
-                let sprintf_fn = *self.functions.get("sprintf").unwrap();
-
-                // Iterate through parts and expressions
                 for (i, part) in parts.iter().enumerate() {
-                    // Add the string part if not empty
                     if !part.is_empty() {
                         let part_str = self.builder.build_global_string_ptr(part, &format!("fstr_part_{}", i)).unwrap();
-                        self.builder.build_call(strcat_fn, &[result_str.into(), part_str.as_pointer_value().into()], "").unwrap();
+                        self.builder
+                            .build_call(append_fn, &[builder.into(), part_str.as_pointer_value().into()], "")
+                            .unwrap();
                     }
 
-                    // Add the expression value if there is one
                     if i < expressions.len() {
                         let expr_val = self.compile_expression(&expressions[i])?;
-
-                        // Allocate buffer for formatted value (100 bytes should be enough)
-                        let buffer_size = i64_type.const_int(100, false);
-                        let buffer = self.builder
-                            .build_call(malloc_fn, &[buffer_size.into()], &format!("expr_buffer_{}", i))
-                            .unwrap()
-                            .try_as_basic_value()
-                            .left()
-                            .unwrap()
-                            .into_pointer_value();
-
-                        // Format the value based on its type
-                        if expr_val.is_int_value() {
-                            let fmt = self.builder.build_global_string_ptr("%lld", "int_fmt").unwrap();
-                            self.builder.build_call(
-                                sprintf_fn,
-                                &[buffer.into(), fmt.as_pointer_value().into(), expr_val.into()],
-                                ""
-                            ).unwrap();
-                        } else if expr_val.is_float_value() {
-                            let fmt = self.builder.build_global_string_ptr("%g", "float_fmt").unwrap();
-                            self.builder.build_call(
-                                sprintf_fn,
-                                &[buffer.into(), fmt.as_pointer_value().into(), expr_val.into()],
-                                ""
-                            ).unwrap();
-                        } else if expr_val.is_pointer_value() {
-                            // Assume it's a string
-                            let fmt = self.builder.build_global_string_ptr("%s", "str_fmt").unwrap();
-                            self.builder.build_call(
-                                sprintf_fn,
-                                &[buffer.into(), fmt.as_pointer_value().into(), expr_val.into()],
-                                ""
-                            ).unwrap();
+                        let text = self.build_text(&expressions[i], expr_val)?;
+                        // Tuples and Results have no text form and add nothing
+                        if text.is_pointer_value() {
+                            self.builder.build_call(append_fn, &[builder.into(), text.into()], "").unwrap();
                         }
-
-                        // Concatenate the formatted value
-                        self.builder.build_call(strcat_fn, &[result_str.into(), buffer.into()], "").unwrap();
                     }
                 }
 
-                Ok(result_str.as_basic_value_enum())
+                Ok(self.call_runtime("str_builder_finish", &[builder.into()]))
+            }
+
+            Expression::OptionalMemberAccess { object, member } => {
+                let class_name = self
+                    .optional_chain_class(object)
+                    .ok_or_else(|| format!("Cannot determine the class for '?.{}'", member))?;
+                let struct_type = *self.class_types.get(&class_name)
+                    .ok_or_else(|| format!("Unknown class '{}'", class_name))?;
+                let field_idx = self.class_field_index(&class_name, member)
+                    .ok_or_else(|| format!("Class '{}' has no field '{}'", class_name, member))?;
+                // The type checker only allows pointer-typed fields, so None is a null pointer
+                let field_type = struct_type.get_field_type_at_index(field_idx).unwrap().into_pointer_type();
+
+                let obj_ptr = self.compile_expression(object)?.into_pointer_value();
+                let function = self.builder.get_insert_block().unwrap().get_parent().unwrap();
+                let check_block = self.builder.get_insert_block().unwrap();
+                let load_block = self.context.append_basic_block(function, "optchain_load");
+                let merge_block = self.context.append_basic_block(function, "optchain_merge");
+
+                // Short-circuit to None when the object is None
+                let is_none = self.builder.build_is_null(obj_ptr, "optchain_is_none").unwrap();
+                self.builder.build_conditional_branch(is_none, merge_block, load_block).unwrap();
+
+                self.builder.position_at_end(load_block);
+                let field_ptr = self.builder
+                    .build_struct_gep(struct_type, obj_ptr, field_idx, member)
+                    .unwrap();
+                let field_val = self.builder.build_load(field_type, field_ptr, member).unwrap();
+                let load_end_block = self.builder.get_insert_block().unwrap();
+                self.builder.build_unconditional_branch(merge_block).unwrap();
+
+                self.builder.position_at_end(merge_block);
+                let phi = self.builder.build_phi(field_type, "optchain").unwrap();
+                phi.add_incoming(&[(&field_type.const_null(), check_block), (&field_val, load_end_block)]);
+                Ok(phi.as_basic_value())
             }
 
             Expression::TupleLiteral { elements } => {
@@ -3633,52 +7505,249 @@ impl<'ctx> CodeGen<'ctx> {
         }
     }
 
-    fn generate_constructor(&mut self, class_name: &str, fields: &[Field]) -> Result<(), String> {
+    /// The compiled `class_name.method`, looking through the base classes
+    fn find_method(&self, class_name: &str, method: &str) -> Option<FunctionValue<'ctx>> {
+        let mut class = Some(class_name);
+        while let Some(current) = class {
+            if let Some(&function) = self.functions.get(&format!("{}::{}", current, method)) {
+                return Some(function);
+            }
+            class = self.class_bases.get(current).map(String::as_str);
+        }
+        None
+    }
+
+    /// Return type of `class_name.method`, which may be inherited
+    fn method_return_type(&self, class_name: &str, method: &str) -> Option<Type> {
+        let mut class = Some(class_name);
+        while let Some(current) = class {
+            if let Some(return_type) = self.function_return_types.get(&format!("{}::{}", current, method)) {
+                return Some(return_type.clone());
+            }
+            class = self.class_bases.get(current).map(String::as_str);
+        }
+        None
+    }
+
+    /// LLVM struct index of a class field, past the vtable pointer in slot 0
+    fn class_field_index(&self, class_name: &str, field: &str) -> Option<u32> {
+        let position = self.class_fields.get(class_name)?.iter().position(|f| f == field)?;
+        Some(position as u32 + 1)
+    }
+
+    /// Emit the vtable for a class: the drop function, then one function pointer
+    /// per slot, each resolved to the most derived implementation. Objects point
+    /// at the first slot, so the drop function sits at index -1
+    fn generate_vtable(&mut self, class_name: &str, drop_fn: FunctionValue<'ctx>) -> PointerValue<'ctx> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let mut entries = vec![drop_fn.as_global_value().as_pointer_value()];
+        entries.extend(self.vtable_slots[class_name].iter().map(|method| {
+            self.find_method(class_name, method)
+                .unwrap()
+                .as_global_value()
+                .as_pointer_value()
+        }));
+
+        let vtable_type = ptr_type.array_type(entries.len() as u32);
+        let vtable = self.module.add_global(vtable_type, None, &format!("__vtable_{}", class_name));
+        vtable.set_initializer(&ptr_type.const_array(&entries));
+        vtable.set_constant(true);
+        vtable.set_linkage(Linkage::Private);
+        let i64_type = self.context.i64_type();
+        unsafe {
+            vtable
+                .as_pointer_value()
+                .const_in_bounds_gep(vtable_type, &[i64_type.const_zero(), i64_type.const_int(1, false)])
+        }
+    }
+
+    /// Emit `__drop_<class>`, run when an object's count reaches zero: each deinit
+    /// from the class up to its root, then the release of its RC fields, then the free
+    fn generate_drop_function(&mut self, class_name: &str) -> FunctionValue<'ctx> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let fn_type = self.context.void_type().fn_type(&[ptr_type.into()], false);
+        let function = self.module.add_function(&format!("__drop_{}", class_name), fn_type, None);
+        function.set_linkage(Linkage::Private);
+
+        let saved_block = self.builder.get_insert_block();
+        let saved_function = self.current_function.replace(function);
+        self.builder.position_at_end(self.context.append_basic_block(function, "entry"));
+        let obj_ptr = function.get_nth_param(0).unwrap().into_pointer_value();
+
+        // Hold a reference while deinit runs, so a copy of self made and dropped
+        // there doesn't free the object a second time
+        let ref_count = self.build_rc_ref_count_ptr(obj_ptr);
+        self.builder.build_store(ref_count, self.context.i64_type().const_int(1, false)).unwrap();
+        let mut class = Some(class_name.to_string());
+        while let Some(current) = class {
+            if let Some(&deinit) = self.functions.get(&format!("{}::deinit", current)) {
+                self.builder.build_call(deinit, &[obj_ptr.into()], "").unwrap();
+            }
+            class = self.class_bases.get(&current).cloned();
+        }
+
+        let struct_type = self.class_types[class_name];
+        for (i, field_type) in self.class_field_types[class_name].clone().iter().enumerate() {
+            if self.is_rc_type(field_type) {
+                let field_ptr = self
+                    .builder
+                    .build_struct_gep(struct_type, obj_ptr, i as u32 + 1, &format!("field_{}", i))
+                    .unwrap();
+                let value = self.builder.build_load(ptr_type, field_ptr, "field_value").unwrap().into_pointer_value();
+                self.build_rc_release_nullable(value, field_type);
+            }
+        }
+
+        let rc_free_fn = *self.functions.get("rc_free").unwrap();
+        self.builder.build_call(rc_free_fn, &[obj_ptr.into()], "").unwrap();
+        self.builder.build_return(None).unwrap();
+
+        self.current_function = saved_function;
+        if let Some(block) = saved_block {
+            self.builder.position_at_end(block);
+        }
+        function
+    }
+
+    /// Call `class_name.method` through the object's vtable, so an override in
+    /// the object's runtime class is the one that runs
+    fn build_virtual_call(
+        &self,
+        class_name: &str,
+        method: &str,
+        static_fn: FunctionValue<'ctx>,
+        args: &[BasicMetadataValueEnum<'ctx>],
+    ) -> CallSiteValue<'ctx> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let obj_ptr = args[0].into_pointer_value();
+        let slot = self.vtable_slots[class_name].iter().position(|m| m == method).unwrap();
+
+        let vtable = self.builder.build_load(ptr_type, obj_ptr, "vtable").unwrap().into_pointer_value();
+        let slot_index = self.context.i64_type().const_int(slot as u64, false);
+        let entry_ptr = unsafe {
+            self.builder.build_gep(ptr_type, vtable, &[slot_index], "vtable_entry").unwrap()
+        };
+        let method_ptr = self.builder.build_load(ptr_type, entry_ptr, method).unwrap().into_pointer_value();
+
+        // Overrides share the base signature (checked by the type checker), and
+        // `self` is a pointer either way
+        self.builder
+            .build_indirect_call(static_fn.get_type(), method_ptr, args, "method_call")
+            .unwrap()
+    }
+
+    /// Parameters after `self` of the nearest init, when it takes any. Such an
+    /// init replaces the field-order constructor
+    fn custom_init_params(&self, class_name: &str) -> Option<Vec<Parameter>> {
+        let mut class = Some(class_name);
+        while let Some(current) = class {
+            if let Some(params) = self.function_params.get(&format!("{}::init", current)) {
+                return (params.len() > 1).then(|| params[1..].to_vec());
+            }
+            class = self.class_bases.get(current).map(String::as_str);
+        }
+        None
+    }
+
+    fn generate_constructor(&mut self, class_name: &str, field_types: &[Type], vtable: PointerValue<'ctx>) -> Result<(), String> {
         // Get the struct type
         let struct_type = *self.class_types.get(class_name).unwrap();
         let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let field_names = self.class_fields[class_name].clone();
+        let field_defaults = self.class_field_defaults[class_name].clone();
+
+        // The constructor takes the custom init's parameters, or else the fields
+        // without a default value, in order
+        let init_params = self.custom_init_params(class_name);
+        let params: Vec<Parameter> = match &init_params {
+            Some(params) => params.clone(),
+            None => field_names
+                .iter()
+                .zip(field_types)
+                .zip(&field_defaults)
+                .filter(|(_, default)| default.is_none())
+                .map(|((name, field_type), _)| Parameter {
+                    name: name.clone(),
+                    param_type: field_type.clone(),
+                    default_value: None,
+                })
+                .collect(),
+        };
 
         // Create constructor function signature
-        let param_types: Vec<BasicMetadataTypeEnum> = fields
+        let param_types: Vec<BasicMetadataTypeEnum> = params
             .iter()
-            .map(|f| self.get_llvm_type(&f.field_type).into())
+            .map(|p| self.get_llvm_type(&p.param_type).into())
             .collect();
 
         let fn_type = ptr_type.fn_type(&param_types, false);
-        let function = self.module.add_function(class_name, fn_type, None);
+        let function = self.module.add_function(&format!("ws_{}", class_name), fn_type, None);
         self.functions.insert(class_name.to_string(), function);
+        // Calls fill in named arguments and init's defaults like any function call
+        self.function_params.insert(class_name.to_string(), params);
 
         // Create entry block
         let entry = self.context.append_basic_block(function, "entry");
         self.builder.position_at_end(entry);
 
-        // Allocate memory for the struct
+        // Default values are compiled inside the constructor
+        let saved_function = self.current_function.replace(function);
+        let saved_variables = std::mem::take(&mut self.variables);
+
+        // Allocate the object with an RC header; the caller gets the first reference
         let size = struct_type.size_of().unwrap();
-        let malloc_fn = self.functions.get("malloc").unwrap();
+        let rc_alloc_fn = self.functions.get("rc_alloc").unwrap();
         let ptr = self
             .builder
-            .build_call(*malloc_fn, &[size.into()], "obj_ptr")
+            .build_call(*rc_alloc_fn, &[size.into()], "obj_ptr")
             .unwrap()
             .try_as_basic_value()
             .left()
             .unwrap()
             .into_pointer_value();
 
-        // Initialize each field
-        for (i, _field) in fields.iter().enumerate() {
+        // Install the vtable, then initialize each field: from its default value,
+        // from its argument, or (with a custom init) to zero for init to set
+        let vtable_ptr = self.builder.build_struct_gep(struct_type, ptr, 0, "vtable_ptr").unwrap();
+        self.builder.build_store(vtable_ptr, vtable).unwrap();
+        let mut next_param = 0;
+        for (i, field_type) in field_types.iter().enumerate() {
+            // The object owns its RC fields: arguments are borrowed from the caller
+            let value = if let Some(default) = &field_defaults[i] {
+                let value = self.compile_expression_as(default, field_type)?;
+                if self.is_rc_type(field_type) && value.is_pointer_value() && self.is_borrowed_value(default) {
+                    self.build_rc_retain_nullable(value.into_pointer_value());
+                }
+                value
+            } else if init_params.is_none() {
+                next_param += 1;
+                let value = function.get_nth_param(next_param - 1).unwrap();
+                if self.is_rc_type(field_type) {
+                    self.build_rc_retain_nullable(value.into_pointer_value());
+                }
+                value
+            } else {
+                self.get_llvm_type(field_type).const_zero()
+            };
             let field_ptr = self
                 .builder
-                .build_struct_gep(struct_type, ptr, i as u32, &format!("field_{}", i))
+                .build_struct_gep(struct_type, ptr, i as u32 + 1, &format!("field_{}", i))
                 .unwrap();
-            let param_val = function.get_nth_param(i as u32).unwrap();
-            self.builder.build_store(field_ptr, param_val).unwrap();
+            self.builder.build_store(field_ptr, value).unwrap();
         }
 
-        // Call init method if it exists
-        let init_method_name = format!("{}::init", class_name);
-        if let Some(&init_fn) = self.functions.get(&init_method_name) {
+        self.variables = saved_variables;
+        self.current_function = saved_function;
+
+        // Call init method if it exists (possibly inherited), passing a custom
+        // init the constructor's arguments
+        if let Some(init_fn) = self.find_method(class_name, "init") {
+            let mut init_args: Vec<BasicMetadataValueEnum> = vec![ptr.into()];
+            if init_params.is_some() {
+                init_args.extend(function.get_param_iter().map(BasicMetadataValueEnum::from));
+            }
             self.builder
-                .build_call(init_fn, &[ptr.into()], "init_call")
+                .build_call(init_fn, &init_args, "init_call")
                 .unwrap();
         }
 
@@ -3688,3 +7757,1027 @@ impl<'ctx> CodeGen<'ctx> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn compile_to_ir(source: &str, checked_overflow: bool) -> Result<String, String> {
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse();
+        let context = Context::create();
+        let mut codegen = CodeGen::new(&context, "test_module", "test.ws");
+        codegen.set_checked_overflow(checked_overflow);
+        codegen.compile_program(&program)?;
+        Ok(codegen.get_module().print_to_string().to_string())
+    }
+
+    const ARITHMETIC: &str = "def calc(a: int, b: int) -> int {\n    return a * b + a - b\n}\n";
+
+    #[test]
+    fn test_int_widened_to_float_parameter_and_return() {
+        let source = "def half(x: float) -> float {\n    return x / 2.0\n}\n\ndef as_float(n: int) -> float {\n    return n\n}\n\ndef main() -> int {\n    n: int = 5\n    y: float = half(n)\n    return 0\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // One conversion for the argument, one for the return value
+        assert_eq!(ir.matches("sitofp i64").count(), 2, "{}", ir);
+    }
+
+    #[test]
+    fn test_float_to_int_cast_saturates() {
+        let source = "def whole(x: float) -> int {\n    return x as int\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("@llvm.fptosi.sat.i64.f64"), "{}", ir);
+    }
+
+    #[test]
+    fn test_null_coalescing_boxes_and_unboxes_ints() {
+        let source = "def pick(x: int?) -> int {\n    return x ?? 0\n}\n\ndef main() -> int {\n    return pick(5)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // The int argument is boxed for the int? parameter and loaded back out by ??
+        assert!(ir.contains("%optional_box = call ptr @rc_alloc(i64 8)"), "{}", ir);
+        assert!(ir.contains("coalesce_default"), "{}", ir);
+        assert!(ir.contains("%unboxed = load i64"), "{}", ir);
+    }
+
+    #[test]
+    fn test_optional_box_is_released() {
+        let source = "def main() -> int {\n    n: int = 5\n    x: int? = n\n    y: int? = x\n    return y ?? 0\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // The box made for x is shared with y (one retain), and both release it
+        let main = &ir[ir.find("define i64 @main").unwrap()..];
+        assert_eq!(main.matches("optional_box = call ptr @rc_alloc(i64 8)").count(), 1, "{}", main);
+        let releases = main.lines().filter(|line| line.trim_start().starts_with("%scope_val") && line.contains("= load ptr"));
+        assert_eq!(releases.count(), 2, "{}", main);
+        assert!(main.contains("call void @rc_free"), "{}", main);
+    }
+
+    #[test]
+    fn test_u8_arithmetic_is_unsigned() {
+        let source = "def scale(b: u8, d: u8) -> bool {\n    q: u8 = b / d\n    return q < 200\n}\n\ndef to_int(b: u8) -> int {\n    return b\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("@ws_scale(i8 %0, i8 %1)"), "{}", ir);
+        assert!(ir.contains("udiv i8"), "{}", ir);
+        // The literal 200 is compared as a u8, not widened
+        assert!(ir.contains("icmp ult i8"), "{}", ir);
+        assert!(ir.contains("zext i8"), "{}", ir);
+    }
+
+    #[test]
+    fn test_signed_sized_ints_sign_extend() {
+        let source = "def widen(h: i16) -> i32 {\n    return h\n}\n\ndef narrow(n: int) -> u8 {\n    return n as u8\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("sext i16"), "{}", ir);
+        assert!(ir.contains("trunc i64"), "{}", ir);
+    }
+
+    #[test]
+    fn test_rounding_builtins_lower_to_intrinsics_and_runtime() {
+        let source = "def f(x: float) -> float {\n    return floor(x) + ceil(x) + trunc(x) + round(x, 2)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("@llvm.floor.f64"), "{}", ir);
+        assert!(ir.contains("@llvm.ceil.f64"), "{}", ir);
+        assert!(ir.contains("@llvm.trunc.f64"), "{}", ir);
+        assert!(ir.contains("call double @float_round(double"), "{}", ir);
+    }
+
+    #[test]
+    fn test_nan_constant_and_unordered_not_equal() {
+        let source = "def f(x: float) -> bool {\n    return x != nan\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("fcmp une double %"), "{}", ir);
+        assert!(ir.contains("0x7FF8000000000000"), "{}", ir);
+        assert!(ir.contains("fcmp uno double"), "{}", ir);
+    }
+
+    #[test]
+    fn test_bigint_operators_call_runtime() {
+        let source = "def f(a: bigint, n: int) -> bool {\n    b: bigint = a * n + 1\n    print(b)\n    return b < a\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("define i1 @ws_f(ptr"), "{}", ir);
+        assert!(ir.contains("call ptr @bigint_mul(ptr"), "{}", ir);
+        assert!(ir.contains("call ptr @bigint_add(ptr"), "{}", ir);
+        assert_eq!(ir.matches("call ptr @bigint_from_int(i64").count(), 2, "{}", ir);
+        assert!(ir.contains("call i64 @bigint_cmp(ptr"), "{}", ir);
+        assert!(ir.contains("call ptr @bigint_to_str(ptr"), "{}", ir);
+        // Never compared as a string
+        assert!(!ir.contains("call i32 @strcmp("), "{}", ir);
+    }
+
+    #[test]
+    fn test_bigint_temporaries_are_freed() {
+        let source = "def f(a: bigint, n: int) -> bigint {\n    b: bigint = a * n + 1\n    return b\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // n and 1 converted to bigints, and a * n; a is the caller's and b is returned
+        assert_eq!(ir.matches("call void @bigint_free(ptr").count(), 3, "{}", ir);
+    }
+
+    #[test]
+    fn test_bigint_through_tuples_coalesce_and_propagate() {
+        let source = "def maybe() -> bigint? {\n    return None\n}\n\ndef parse(s: str) -> Result[bigint, str] {\n    return Ok(parse_bigint(s))\n}\n\ndef f() -> bigint {\n    t: (bigint, int) = (parse_bigint(\"5\"), 2)\n    x: bigint = t.0 * 3\n    y: bigint = (maybe() ?? parse_bigint(\"1\")) + 1\n    return -t.0\n}\n\ndef g() -> Result[bigint, str] {\n    z: bigint = parse(\"7\")? * 2\n    return Ok(z)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call ptr @bigint_mul(ptr"), "{}", f);
+        assert!(f.contains("call ptr @bigint_add(ptr"), "{}", f);
+        assert!(f.contains("call ptr @bigint_neg(ptr"), "{}", f);
+        let g = ir.split("define ").find(|f| f.contains("@ws_g(")).unwrap();
+        assert!(g.contains("call ptr @bigint_mul(ptr"), "{}", g);
+        // Never treated as a string
+        assert!(!ir.contains("@str_repeat(") && !ir.contains("@str_concat("), "{}", ir);
+    }
+
+    #[test]
+    fn test_unknown_pointer_operand_is_an_error() {
+        // A tuple literal's element types aren't tracked, so this can't be compiled as str * int
+        let source = "def f() -> int {\n    x: int = (parse_bigint(\"5\"), 2).0 * 3\n    return 0\n}\n";
+        let err = compile_to_ir(source, false).unwrap_err();
+        assert!(err.contains("unknown to codegen"), "{}", err);
+    }
+
+    #[test]
+    fn test_decimal_operators_call_runtime() {
+        let source = "def f(a: decimal, n: int) -> bool {\n    b: decimal = a * n + 0.01d\n    print(b.round(2))\n    return b < a\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("call ptr @decimal_mul(ptr"), "{}", ir);
+        assert!(ir.contains("call ptr @decimal_add(ptr"), "{}", ir);
+        assert!(ir.contains("call ptr @decimal_from_int(i64"), "{}", ir);
+        assert!(ir.contains("call ptr @decimal_from_str(ptr"), "{}", ir);
+        assert!(ir.contains("call ptr @decimal_round(ptr"), "{}", ir);
+        assert!(ir.contains("call i64 @decimal_cmp(ptr"), "{}", ir);
+        // Never routed through bigint or float helpers
+        assert!(!ir.contains("call ptr @bigint_"), "{}", ir);
+        assert!(!ir.contains("call double @float_round"), "{}", ir);
+    }
+
+    #[test]
+    fn test_decimal_through_tuples_and_coalesce() {
+        let source = "def price() -> decimal? {\n    return None\n}\n\ndef f() -> decimal {\n    t: (decimal, int) = (parse_decimal(\"1.25\"), 2)\n    total: decimal = t.0 * 2\n    return (price() ?? 0.50d) - t.0\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("call ptr @decimal_mul(ptr"), "{}", ir);
+        assert!(ir.contains("call ptr @decimal_sub(ptr"), "{}", ir);
+        assert!(!ir.contains("@str_repeat("), "{}", ir);
+    }
+
+    #[test]
+    fn test_range_over_list_length_is_an_index_loop() {
+        let source = "def f(xs: list[int]) -> int {\n    total: int = 0\n    for i in range(xs.length) {\n        total = total + i * xs[i]\n    }\n    return total\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(!ir.contains("range_list"), "{}", ir);
+        assert_eq!(ir.matches("call i64 @list_length(").count(), 1, "{}", ir);
+    }
+
+    #[test]
+    fn test_float_formatting_uses_shortest_repr() {
+        let source = "def f(x: float) -> str {\n    print(x)\n    return f\"{x}\"\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(!ir.contains("%g"), "{}", ir);
+        assert_eq!(ir.matches("call ptr @float_to_str(double").count(), 2, "{}", ir);
+    }
+
+    #[test]
+    fn test_fstrings_and_concatenation_are_sized_by_the_runtime() {
+        let source = "def f(name: str, n: int, ok: bool) -> str {\n    s: str = name + \"!\"\n    return f\"{name} has {n} items: {ok}{s}\"\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert_eq!(f.matches("call ptr @str_concat(ptr").count(), 1, "{}", f);
+        assert_eq!(f.matches("call ptr @str_builder_new(i64 77)").count(), 1, "{}", f);
+        // Two literal parts and four values
+        assert_eq!(f.matches("call void @str_builder_append(ptr").count(), 6, "{}", f);
+        assert!(f.contains("call ptr @int_to_str(i64"), "{}", f);
+        assert!(f.contains("call ptr @str_builder_finish(ptr"), "{}", f);
+        // No fixed-size scratch buffers
+        assert!(!f.contains("@malloc(") && !f.contains("@sprintf("), "{}", f);
+    }
+
+    #[test]
+    fn test_result_is_a_by_value_struct() {
+        let source = "def parse(s: str) -> Result[int, str] {\n    return Err(s)\n}\n\ndef twice(s: str) -> Result[int, str] {\n    n: int = parse(s)?\n    return Ok(n * 2)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("define { i1, i64, ptr } @ws_parse(ptr"), "{}", ir);
+        assert!(ir.contains("propagate_err"), "{}", ir);
+        // No heap allocation on either path
+        assert!(!ir.contains("@malloc(i64 8)"), "{}", ir);
+    }
+
+    #[test]
+    fn test_untyped_result_constructor_is_rejected() {
+        let source = "def f() -> int {\n    Ok(1)\n    return 0\n}\n";
+        let err = compile_to_ir(source, false).unwrap_err();
+        assert!(err.contains("Cannot infer the Result type of Ok(...)"), "{}", err);
+    }
+
+    #[test]
+    fn test_optional_chaining_null_check_and_phi() {
+        let source = "class User {\n    name: str\n}\n\ndef name_of(user: User?) -> str? {\n    return user?.name\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("optchain_merge"), "{}", ir);
+        assert!(ir.contains("phi ptr"), "{}", ir);
+    }
+
+    #[test]
+    fn test_none_comparison_does_not_call_strcmp() {
+        let source = "def is_missing(name: str?) -> bool {\n    return name == None\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(!ir.contains("call i32 @strcmp"), "{}", ir);
+    }
+
+    #[test]
+    fn test_string_comparisons_use_strcmp() {
+        let source = "def before(a: str, b: str) -> bool {\n    return a < b or a >= b and a != b\n}\n\ndef same(xs: list[int], ys: list[int]) -> bool {\n    return xs == ys\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let before = ir.split("define ").find(|f| f.contains("@ws_before(")).unwrap();
+        assert_eq!(before.matches("call i32 @strcmp(ptr").count(), 3, "{}", before);
+        assert!(before.contains("icmp slt i32"), "{}", before);
+        assert!(before.contains("icmp sge i32"), "{}", before);
+        // Lists compare by identity, not by running strcmp over the list header
+        let same = ir.split("define ").find(|f| f.contains("@ws_same(")).unwrap();
+        assert!(!same.contains("@strcmp"), "{}", same);
+        assert!(same.contains("icmp eq ptr"), "{}", same);
+    }
+
+    #[test]
+    fn test_optional_and_tuple_comparisons_are_null_safe() {
+        let source = "class Node {\n    v: int\n}\n\ndef refs(a: Node?, b: Node?, xs: list[int]?, ys: list[int]) -> bool {\n    t: (list[int], int) = (ys, 1)\n    u: (list[int], int) = (ys, 2)\n    return a == b and xs != ys and t.0 == u.0\n}\n\ndef values(s: str?, n: int?, m: int?) -> bool {\n    return s == \"x\" and n != m\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // Identity comparisons, None included
+        let refs = ir.split("define ").find(|f| f.contains("@ws_refs(")).unwrap();
+        assert!(!refs.contains("@strcmp"), "{}", refs);
+        assert!(refs.contains("icmp eq ptr") && refs.contains("icmp ne ptr"), "{}", refs);
+        // strcmp and the box loads only run once both sides are present
+        let values = ir.split("define ").find(|f| f.contains("@ws_values(")).unwrap();
+        assert!(values.contains("optional_compare"), "{}", values);
+        assert_eq!(values.matches("call i32 @strcmp(ptr").count(), 1, "{}", values);
+        assert!(values.contains("load i64, ptr"), "{}", values);
+    }
+
+    #[test]
+    fn test_optional_bigint_and_decimal_comparisons() {
+        let source = "def same(a: bigint?, b: bigint?, c: decimal?, d: decimal?) -> bool {\n    return a == b and c != d and a != None\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let same = ir.split("define ").find(|f| f.contains("@ws_same(")).unwrap();
+        // Compared by value once both sides are present
+        assert!(same.contains("optional_compare"), "{}", same);
+        assert_eq!(same.matches("call i64 @bigint_cmp(ptr").count(), 1, "{}", same);
+        assert_eq!(same.matches("call i64 @decimal_cmp(ptr").count(), 1, "{}", same);
+    }
+
+    #[test]
+    fn test_comparing_unknown_pointers_is_an_error() {
+        let source = "def f(xs: list[int]) -> bool {\n    return (xs, 1).0 == (xs, 2).0\n}\n";
+        let err = compile_to_ir(source, false).unwrap_err();
+        assert!(err.contains("unknown to codegen"), "{}", err);
+    }
+
+    #[test]
+    fn test_inline_decorators_set_attributes() {
+        let source = "@inline\ndef square(n: int) -> int {\n    return n * n\n}\n\n@noinline\ndef cube(n: int) -> int {\n    return n * n * n\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("alwaysinline"));
+        assert!(ir.contains("noinline"));
+    }
+
+    #[test]
+    fn test_functions_with_try_are_not_optimized() {
+        let source = "@inline\ndef guarded(n: int) -> int {\n    result: int = 0\n    try {\n        result = n + 1\n    } except ValueError {\n        result = -1\n    }\n    return result\n}\n\ndef plain(n: int) -> int {\n    return n\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // The attribute group a function's definition or declaration refers to
+        let attributes = |function: &str| {
+            let header = ir.lines().find(|line| line.contains(&format!("@{}(", function)) && !line.contains("call")).unwrap();
+            let group = header.split_whitespace().find(|word| word.starts_with('#')).unwrap();
+            ir.lines().find(|line| line.starts_with(&format!("attributes {} =", group))).unwrap().to_string()
+        };
+        let guarded = attributes("ws_guarded");
+        assert!(guarded.contains("optnone") && guarded.contains("noinline"), "{}", guarded);
+        assert!(!guarded.contains("alwaysinline"), "{}", guarded);
+        assert!(attributes("setjmp").contains("returns_twice"), "{}", ir);
+
+        let plain = attributes("ws_plain");
+        assert!(plain.contains("uwtable") && !plain.contains("optnone"), "{}", plain);
+    }
+
+    #[test]
+    fn test_eliminate_dead_functions() {
+        use inkwell::targets::{CodeModel, InitializationConfig, RelocMode, Target};
+        use inkwell::OptimizationLevel;
+
+        let source = "def used() -> int {\n    return 1\n}\n\ndef unused() -> int {\n    return 2\n}\n\ndef main() -> int {\n    return used()\n}\n";
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse();
+        let context = Context::create();
+        let mut codegen = CodeGen::new(&context, "test_module", "test.ws");
+        codegen.compile_program(&program).unwrap();
+
+        Target::initialize_native(&InitializationConfig::default()).unwrap();
+        let triple = TargetMachine::get_default_triple();
+        let target_machine = Target::from_triple(&triple)
+            .unwrap()
+            .create_target_machine(&triple, "generic", "", OptimizationLevel::None, RelocMode::Default, CodeModel::Default)
+            .unwrap();
+        codegen.eliminate_dead_functions(&target_machine).unwrap();
+
+        let module = codegen.get_module();
+        assert!(module.get_function("main").is_some());
+        assert!(module.get_function("ws_used").is_some());
+        assert!(module.get_function("ws_unused").is_none());
+    }
+
+    #[test]
+    fn test_try_jmp_buf_fits_target() {
+        let source = "def main() -> int {\n    try {\n        raise ValueError(\"x\")\n    } except ValueError {\n        return 1\n    }\n    return 0\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("alloca [512 x i8], align 16"));
+    }
+
+    #[test]
+    fn test_unmatched_exception_is_reraised() {
+        let source = "def f(n: int) -> void {\n    try {\n        n = n + 1\n    } except KeyError {\n        raise\n    }\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // Once from the unmatched path and once for the bare raise
+        assert_eq!(ir.matches("call void @exception_reraise(ptr %current_exc)").count(), 2, "{}", ir);
+        // Only the path that finishes the try block pops the handler
+        assert_eq!(ir.matches("call void @exception_pop_handler()").count(), 1, "{}", ir);
+    }
+
+    #[test]
+    fn test_raise_sets_debug_location() {
+        let source = "def f() -> void {\n    x: int = 1\n    raise ValueError(\"bad\")\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let raise_call = ir.lines().find(|l| l.contains("call void @exception_raise(")).unwrap();
+        assert!(raise_call.contains("!dbg"), "{}", ir);
+        assert!(ir.contains("line: 3"), "{}", ir);
+    }
+
+    #[test]
+    fn test_raise_from_passes_cause() {
+        let source = "def f() -> void {\n    try {\n        raise ValueError(\"bad\")\n    } except ValueError as e {\n        raise RuntimeError(\"failed\") from e\n    }\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert_eq!(ir.matches("call void @exception_raise(").count(), 1, "{}", ir);
+        assert_eq!(ir.matches("call void @exception_raise_from(").count(), 1, "{}", ir);
+    }
+
+    #[test]
+    fn test_subclass_layout_starts_with_base_fields() {
+        let source = "class Animal {\n    legs: int\n    def count(self: Animal) -> int {\n        return self.legs\n    }\n}\nclass Bird(Animal) {\n    wingspan: float\n}\ndef f() -> int {\n    b: Bird = Bird(2, 0.5)\n    return b.count()\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("define ptr @ws_Bird(i64 %0, double %1)"), "{}", ir);
+        assert!(ir.contains("@__vtable_Bird = private constant [2 x ptr] [ptr @__drop_Bird, ptr @ws_Animal.count]"), "{}", ir);
+        assert!(ir.contains("call i64 %count(ptr"), "{}", ir);
+    }
+
+    #[test]
+    fn test_override_replaces_vtable_slot() {
+        let source = "class Animal {
+    legs: int
+    def sound(self: Animal) -> str {
+        return \"...\"
+    }
+    def count(self: Animal) -> int {
+        return self.legs
+    }
+}
+class Dog(Animal) {
+    tricks: int
+    def sound(self: Dog) -> str {
+        return \"woof\"
+    }
+    def learn(self: Dog) -> int {
+        return self.tricks + 1
+    }
+}
+def speak(a: Animal) -> str {
+    return a.sound()
+}
+";
+        let ir = compile_to_ir(source, false).unwrap();
+        // The override keeps the base slot; new methods are appended
+        assert!(ir.contains("@__vtable_Animal = private constant [3 x ptr] [ptr @__drop_Animal, ptr @ws_Animal.sound, ptr @ws_Animal.count]"), "{}", ir);
+        assert!(ir.contains("@__vtable_Dog = private constant [4 x ptr] [ptr @__drop_Dog, ptr @ws_Dog.sound, ptr @ws_Animal.count, ptr @ws_Dog.learn]"), "{}", ir);
+        // The constructor installs the vtable, pointing past the drop function
+        assert!(ir.contains("@__vtable_Dog, i64 0, i64 1)"), "{}", ir);
+        assert!(ir.contains("call ptr %sound(ptr"), "{}", ir);
+    }
+
+    #[test]
+    fn test_deinit_runs_from_drop_function() {
+        let source = "class Handle {
+    fd: int
+    def deinit(self: Handle) -> void {
+        print(self.fd)
+    }
+}
+class Pair {
+    left: Handle
+    def deinit(self: Pair) -> void {
+        print(0)
+    }
+}
+def f() -> void {
+    p: Pair = Pair(Handle(3))
+}
+";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("define private void @__drop_Pair(ptr %0)"), "{}", ir);
+        assert!(ir.contains("call ptr @ws_Pair.deinit(ptr %0)"), "{}", ir);
+        assert!(ir.contains("call void @rc_free(ptr %0)"), "{}", ir);
+        // Objects get an RC header, and the last release goes through vtable[-1]
+        assert!(ir.contains("call ptr @rc_alloc("), "{}", ir);
+        assert!(ir.contains("call void %drop(ptr"), "{}", ir);
+    }
+
+    #[test]
+    fn test_object_field_assignment_retains_and_releases() {
+        let source = "class Tag {\n    name: str\n}\nclass Holder {\n    tag: Tag\n    count: int\n}\ndef swap(h: Holder, t: Tag) -> void {\n    h.tag = t\n}\ndef bump(h: Holder) -> void {\n    h.count = 2\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let function = |name: &str| ir.split("define ").find(|f| f.contains(&format!("@{}(", name))).unwrap().to_string();
+        let swap = function("ws_swap");
+        // The parameter is borrowed, so the field takes its own reference
+        assert!(swap.contains("rc_retain:"), "{}", swap);
+        assert!(swap.contains("%old_field = load ptr"), "{}", swap);
+        assert!(swap.contains("call void %drop(ptr"), "{}", swap);
+        let bump = function("ws_bump");
+        assert!(!bump.contains("old_field"), "{}", bump);
+    }
+
+    #[test]
+    fn test_member_access_on_chains_and_call_results() {
+        let source = "class Address {\n    zip: int\n    def code(self: Address) -> int {\n        return self.zip\n    }\n}\nclass User {\n    address: Address\n}\ndef get_user() -> User {\n    return User(Address(1100))\n}\ndef f(u: User) -> int {\n    return u.address.zip + get_user().address.code()\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call ptr @ws_get_user()"), "{}", f);
+        let address_loads = f.lines().filter(|line| line.trim_start().starts_with("%address") && line.contains("= load ptr"));
+        assert_eq!(address_loads.count(), 2, "{}", f);
+        assert!(f.contains("call i64 %code(ptr"), "{}", f);
+    }
+
+    #[test]
+    fn test_dict_functions_follow_key_and_value_types() {
+        let source = "class Point {\n    x: int\n}\ndef lookup(names: dict[int, str], id: int) -> str {\n    return names[id]\n}\ndef mark(seen: dict[Point, float], p: Point, w: float) -> void {\n    seen[p] = w\n}\ndef weight(seen: dict[Point, float], p: Point) -> float {\n    return seen[p]\n}\ndef count(ages: dict[str, int], name: str) -> int {\n    return ages[name]\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let function = |name: &str| ir.split("define ").find(|f| f.contains(&format!("@{}(", name))).unwrap().to_string();
+        let lookup = function("ws_lookup");
+        assert!(lookup.contains("call i64 @dict_get_int(ptr"), "{}", lookup);
+        assert!(lookup.contains("inttoptr i64 %dict_value to ptr"), "{}", lookup);
+        // Objects are keyed by identity and kept alive by the dict
+        let mark = function("ws_mark");
+        assert!(mark.contains("ptrtoint ptr"), "{}", mark);
+        assert!(mark.contains("bitcast double"), "{}", mark);
+        assert!(mark.contains("call void @dict_set_int(ptr"), "{}", mark);
+        assert!(mark.contains("rc_retain:"), "{}", mark);
+        let weight = function("ws_weight");
+        assert!(weight.contains("bitcast i64 %dict_value to double"), "{}", weight);
+        let count = function("ws_count");
+        assert!(count.contains("call i64 @dict_get(ptr"), "{}", count);
+    }
+
+    #[test]
+    fn test_list_functions_follow_element_type() {
+        let source = "class Point {\n    x: int\n}\ndef first(names: list[str]) -> str {\n    return names[0]\n}\ndef add(scores: list[float], n: int) -> float {\n    scores.push(n)\n    scores[0] = 2.5\n    return scores.pop()\n}\ndef keep(points: list[Point], p: Point) -> int {\n    points.push(p)\n    return points[0].x\n}\ndef flags() -> bool {\n    xs: list[bool] = [True, False]\n    return xs[1]\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let function = |name: &str| ir.split("define ").find(|f| f.contains(&format!("@{}(", name))).unwrap().to_string();
+        let first = function("ws_first");
+        assert!(first.contains("call ptr @list_get_ptr(ptr"), "{}", first);
+        let add = function("ws_add");
+        assert!(add.contains("sitofp i64"), "{}", add);
+        assert!(add.contains("call void @list_push_f64(ptr"), "{}", add);
+        assert!(add.contains("call void @list_set_f64(ptr"), "{}", add);
+        assert!(add.contains("call double @list_pop_f64(ptr"), "{}", add);
+        // A pushed object is kept alive by the list
+        let keep = function("ws_keep");
+        assert!(keep.contains("rc_retain:"), "{}", keep);
+        assert!(keep.contains("call void @list_push_ptr(ptr"), "{}", keep);
+        assert!(keep.contains("call ptr @list_get_ptr(ptr"), "{}", keep);
+        let flags = function("ws_flags");
+        assert!(flags.contains("call void @list_push_i64(ptr"), "{}", flags);
+        assert!(flags.contains("trunc i64 %element to i1"), "{}", flags);
+    }
+
+    #[test]
+    fn test_list_editing_methods() {
+        let source = "def names(xs: list[str]) -> bool {\n    xs.insert(0, \"ada\")\n    gone: str = xs.remove_at(1)\n    return xs.contains(\"grace\")\n}\ndef scores(xs: list[float]) -> int {\n    xs.clear()\n    return xs.index_of(1)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let function = |name: &str| ir.split("define ").find(|f| f.contains(&format!("@{}(", name))).unwrap().to_string();
+        let names = function("ws_names");
+        assert!(names.contains("call void @list_insert_ptr(ptr"), "{}", names);
+        assert!(names.contains("call ptr @list_remove_at_ptr(ptr"), "{}", names);
+        // Strings are found by contents
+        assert!(names.contains("call i64 @list_index_of_str(ptr"), "{}", names);
+        assert!(names.contains("icmp ne i64 %index, -1"), "{}", names);
+        let scores = function("ws_scores");
+        assert!(scores.contains("call void @list_clear(ptr"), "{}", scores);
+        assert!(scores.contains("call i64 @list_index_of_f64(ptr"), "{}", scores);
+    }
+
+    #[test]
+    fn test_list_sort_follows_element_type() {
+        let source = "def order(a: list[int], b: list[float], c: list[str], d: list[bool]) -> void {\n    a.sort()\n    b.sort()\n    c.sort()\n    d.sort()\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let order = ir.split("define ").find(|f| f.contains("@ws_order(")).unwrap();
+        assert_eq!(order.matches("call void @list_sort_i64(ptr").count(), 2, "{}", order);
+        assert!(order.contains("call void @list_sort_f64(ptr"), "{}", order);
+        assert!(order.contains("call void @list_sort_str(ptr"), "{}", order);
+    }
+
+    #[test]
+    fn test_list_map_filter_reduce_call_the_named_function() {
+        let source = "class Point {\n    x: int\n}\n\
+                      def to_point(x: int) -> Point {\n    return Point(x)\n}\n\
+                      def is_far(p: Point) -> bool {\n    return p.x > 1\n}\n\
+                      def add(total: float, x: int) -> float {\n    return total + x\n}\n\
+                      def f(xs: list[int]) -> float {\n    points: list[Point] = xs.map(to_point)\n    far: list[Point] = points.filter(is_far)\n    return xs.reduce(add, 0)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call ptr @ws_to_point(i64"), "{}", f);
+        assert!(f.contains("call i1 @ws_is_far(ptr"), "{}", f);
+        assert!(f.contains("call double @ws_add(double"), "{}", f);
+        // Both new lists hold objects, and filter() retains the ones it keeps
+        assert_eq!(f.matches(&format!("call ptr @list_create(i64 {})", ELEMS_OBJECT)).count(), 2, "{}", f);
+        assert!(f.contains("filter_keep"), "{}", f);
+    }
+
+    #[test]
+    fn test_list_sort_by_passes_a_comparator() {
+        let source = "def larger(a: float, b: float) -> bool {\n    return a > b\n}\n\
+                      def f(xs: list[float], ys: list[float]) -> void {\n    xs.sort_by(larger)\n    ys.sort_by(larger)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert_eq!(f.matches("call void @list_sort_by(ptr %").count(), 2, "{}", f);
+        assert!(f.contains("@sort_by.larger.double)"), "{}", f);
+        // Both calls share one comparator, which reads the slots back as floats
+        let comparator = ir.split("define ").find(|f| f.contains("@sort_by.larger.double(")).unwrap();
+        assert_eq!(ir.matches("define internal i64 @sort_by.larger.double").count(), 1, "{}", ir);
+        assert!(comparator.contains("bitcast i64"), "{}", comparator);
+        assert!(comparator.contains("call i1 @ws_larger(double"), "{}", comparator);
+    }
+
+    #[test]
+    fn test_dict_keys_and_values_are_owned_lists() {
+        let source = "def f(ages: dict[str, float]) -> float {\n    names: list[str] = ages.keys()\n    values: list[float] = ages.values()\n    return values[0]\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call ptr @dict_keys(ptr"), "{}", f);
+        assert!(f.contains("call ptr @dict_values(ptr"), "{}", f);
+        assert!(f.contains("call double @list_get_f64(ptr"), "{}", f);
+    }
+
+    #[test]
+    fn test_dict_items_are_boxed_tuples() {
+        let source = "def f(ages: dict[str, float]) -> float {\n    pairs: list[(str, float)] = ages.items()\n    pairs.push((\"ada\", 1.5))\n    name, age = pairs[0]\n    return age + pairs.pop().1\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call ptr @dict_items(ptr"), "{}", f);
+        // push() boxes the tuple; reading it back loads each field from its box
+        assert!(f.contains("call ptr @rc_alloc(i64 40)"), "{}", f);
+        assert!(f.contains("call void @list_push_ptr(ptr"), "{}", f);
+        assert!(f.contains("call i64 @list_get_i64(ptr"), "{}", f);
+        assert!(f.contains("call i64 @list_pop_i64(ptr"), "{}", f);
+        assert!(f.contains("bitcast i64 %tuple_slot"), "{}", f);
+    }
+
+    #[test]
+    fn test_dict_remove_pop_and_clear() {
+        let source = "def f(ages: dict[str, float], seen: dict[int, bool]) -> float {\n    gone: bool = seen.remove(3)\n    age: float = ages.pop(\"ada\", 0.5)\n    seen.clear()\n    return age\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call i32 @dict_remove_int(ptr"), "{}", f);
+        // The default travels as float bits, like any dict value
+        assert!(f.contains("call i64 @dict_pop(ptr"), "{}", f);
+        assert!(f.contains("bitcast i64"), "{}", f);
+        assert!(f.contains("call void @dict_clear(ptr"), "{}", f);
+    }
+
+    #[test]
+    fn test_string_split_and_join() {
+        let source = "def f(line: str) -> str {\n    parts: list[str] = line.split(\",\")\n    return \" | \".join(parts)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call ptr @str_split(ptr"), "{}", f);
+        assert!(f.contains("call ptr @str_join(ptr"), "{}", f);
+    }
+
+    #[test]
+    fn test_strings_iterate_by_character() {
+        let source = "def f(word: str) -> int {\n    n: int = 0\n    for c in word {\n        n = n + c.byte_length\n    }\n    letters: list[str] = word.chars()\n    return n - letters.length\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        // One pass over the text up front, not a scan per character
+        assert!(f.matches("call ptr @str_chars(ptr").count() == 2, "{}", f);
+        assert!(!f.contains("@str_char_at("), "{}", f);
+        assert!(!f.contains("@str_length("), "{}", f);
+        assert!(f.contains("call i64 @str_byte_length(ptr"), "{}", f);
+    }
+
+    #[test]
+    fn test_parse_bool_and_truthy() {
+        let source = "def f(flag: str, xs: list[int], name: str, ages: dict[str, int], n: int?, ratio: float) -> bool {\n    on: bool = parse_bool(flag) ?? False\n    return truthy(xs) and truthy(name) and truthy(ages) and truthy(n) and truthy(ratio) and on\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call i64 @str_parse_bool(ptr"), "{}", f);
+        assert!(f.contains("phi ptr [ null"), "{}", f);
+        assert!(f.contains("@list_length("), "{}", f);
+        assert!(f.contains("@str_length("), "{}", f);
+        assert!(f.contains("@dict_length("), "{}", f);
+        assert!(f.contains("icmp ne ptr"), "{}", f);
+        assert!(f.contains("fcmp une double"), "{}", f);
+    }
+
+    #[test]
+    fn test_string_number_conversions() {
+        let source = "def f(count: str, ratio: str, flag: bool, small: u8) -> str {\n    n: int = int(count)\n    x: float = float(ratio)\n    return str(n) + str(x) + str(flag) + str(small)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call i64 @str_to_int(ptr"), "{}", f);
+        assert!(f.contains("call double @str_to_float(ptr"), "{}", f);
+        assert!(f.contains("call ptr @int_to_str(i64"), "{}", f);
+        assert!(f.contains("call ptr @float_to_str(double"), "{}", f);
+        assert!(f.contains("select i1"), "{}", f);
+        assert!(f.contains("zext i8"), "{}", f);
+    }
+
+    #[test]
+    fn test_format_fills_a_runtime_template() {
+        let source = "def f(template: str, name: str, age: int) -> str {\n    return format(template, name, age)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        // The values' text goes into a list the runtime fills the template from
+        assert_eq!(f.matches("call void @list_push_ptr(ptr").count(), 2, "{}", f);
+        assert!(f.contains("call ptr @int_to_str(i64"), "{}", f);
+        assert!(f.contains("call ptr @str_format(ptr"), "{}", f);
+    }
+
+    #[test]
+    fn test_input_and_read_line_read_stdin() {
+        let source = "def f() -> int {\n    name: str = input(\"Name: \")\n    blank: str = input()\n    line: str? = read_line()\n    return name.length + blank.length\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert_eq!(f.matches("call ptr @stdin_input(ptr").count(), 2, "{}", f);
+        assert!(f.contains("@stdin_input(ptr null)"), "{}", f);
+        assert!(f.contains("call ptr @stdin_read_line()"), "{}", f);
+
+        // std/io.ws's read_line(handle) replaces the builtin
+        let source = "@shadows_builtin\ndef read_line(handle: int) -> str {\n    return \"\"\n}\ndef f() -> int {\n    return read_line(1).length\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("@ws_read_line(i64 1)"), "{}", f);
+        assert!(!f.contains("stdin_read_line"), "{}", f);
+    }
+
+    #[test]
+    fn test_string_replace_find_and_affixes() {
+        let source = "def f(path: str) -> int {\n    if path.startswith(\"/\") and path.endswith(\".ws\") {\n        return path.replace(\"/\", \".\").find(\".ws\")\n    }\n    return -1\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call i32 @str_starts_with(ptr"), "{}", f);
+        assert!(f.contains("call i32 @str_ends_with(ptr"), "{}", f);
+        assert!(f.contains("call ptr @str_replace(ptr"), "{}", f);
+        assert!(f.contains("call i64 @str_find(ptr"), "{}", f);
+    }
+
+    #[test]
+    fn test_string_strip_methods() {
+        let source = "def f(line: str) -> str {\n    key: str = line.lstrip()\n    return key.rstrip().strip().upper()\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call ptr @str_lstrip(ptr"), "{}", f);
+        assert!(f.contains("call ptr @str_rstrip(ptr"), "{}", f);
+        assert!(f.contains("call ptr @str_strip(ptr"), "{}", f);
+    }
+
+    #[test]
+    fn test_dict_get_with_default() {
+        let source = "def f(ages: dict[str, int], names: dict[int, str]) -> int {\n    name: str = names.get(1, \"nobody\")\n    return ages.get(name, 0)\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("call i64 @dict_get_or_int(ptr"), "{}", f);
+        assert!(f.contains("call i64 @dict_get_or(ptr"), "{}", f);
+        // A missing key gives the default instead of a key error
+        assert!(!f.contains("@dict_get("), "{}", f);
+    }
+
+    #[test]
+    fn test_for_over_dict_field_iterates_key_snapshot() {
+        let source = "class Team {\n    scores: dict[int, float]\n}\ndef total(team: Team) -> float {\n    sum: float = 0.0\n    for id, score in team.scores {\n        sum = sum + score\n    }\n    return sum\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let total = ir.split("define ").find(|f| f.contains("@ws_total(")).unwrap();
+        // A dict reached through a field still iterates its keys, never the dict as a list
+        assert!(total.contains("call ptr @dict_keys(ptr"), "{}", total);
+        assert!(!total.contains("@dict_get_keys"), "{}", total);
+        assert!(!total.contains("@list_get_f64"), "{}", total);
+        // Each value is looked up by its key and read back as float bits
+        assert!(total.contains("call i64 @dict_get_int(ptr"), "{}", total);
+        assert!(total.contains("bitcast i64"), "{}", total);
+    }
+
+    #[test]
+    fn test_int_elif_chain_becomes_switch() {
+        let source = "def kind(c: int) -> int {\n    if c == 32 or c == 9 {\n        return 1\n    } elif c == 40 {\n        return 2\n    } elif 41 == c {\n        return 3\n    } elif c == 40 {\n        return 4\n    } else {\n        return 0\n    }\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let kind = ir.split("define ").find(|f| f.contains("@ws_kind(")).unwrap();
+        assert!(kind.contains("switch i64"), "{}", kind);
+        // The repeated 40 goes to the first branch that tests it
+        assert_eq!(kind.matches("i64 40, label").count(), 1, "{}", kind);
+
+        // Chains that mix variables or compare with non-constants stay comparisons
+        let source = "def f(a: int, b: int) -> int {\n    if a == 1 {\n        return 1\n    } elif b == 2 {\n        return 2\n    } elif a == b {\n        return 3\n    }\n    return 0\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(!ir.contains("switch"), "{}", ir);
+    }
+
+    #[test]
+    fn test_list_reverse_extend_and_concat() {
+        let source = "def join(a: list[int], b: list[int]) -> list[int] {\n    a.reverse()\n    a.shuffle()\n    a.extend(b)\n    both: list[int] = a + b\n    return both + [1]\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let join = ir.split("define ").find(|f| f.contains("@ws_join(")).unwrap();
+        assert!(join.contains("call void @list_reverse(ptr"), "{}", join);
+        assert!(join.contains("call void @list_shuffle(ptr"), "{}", join);
+        assert!(join.contains("call void @list_extend(ptr"), "{}", join);
+        assert_eq!(join.matches("call ptr @list_concat(ptr").count(), 2, "{}", join);
+        // Lists never take the string concatenation path
+        assert!(!join.contains("str_concat"), "{}", join);
+    }
+
+    #[test]
+    fn test_nested_collections_are_created_with_element_kinds() {
+        let source = "class Point {\n    x: int\n}\ndef grid() -> int {\n    rows: list[list[int]] = [[1, 2], [3]]\n    return rows[1][0]\n}\ndef groups() -> int {\n    by_name: dict[str, list[Point]] = {\"a\": [Point(1)]}\n    return by_name[\"a\"][0].x\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let function = |name: &str| ir.split("define ").find(|f| f.contains(&format!("@{}(", name))).unwrap().to_string();
+        let grid = function("ws_grid");
+        assert!(grid.contains(&format!("call ptr @list_create(i64 {})", ELEMS_LIST)), "{}", grid);
+        assert!(grid.contains(&format!("call ptr @list_create(i64 {})", ELEMS_PLAIN)), "{}", grid);
+        // The inner element is read from the row the outer list hands out
+        assert_eq!(grid.matches("call ptr @list_get_ptr(ptr").count(), 1, "{}", grid);
+        assert!(grid.contains("call void @list_free(ptr"), "{}", grid);
+        let groups = function("ws_groups");
+        assert!(groups.contains(&format!("call ptr @dict_create(i64 {}, i64 {})", ELEMS_PLAIN, ELEMS_LIST)), "{}", groups);
+        assert!(groups.contains(&format!("call ptr @list_create(i64 {})", ELEMS_OBJECT)), "{}", groups);
+        assert!(groups.contains("call void @dict_free(ptr"), "{}", groups);
+    }
+
+    #[test]
+    fn test_print_and_fstring_call_to_string() {
+        let source = "class Point {
+    x: int
+    def to_string(self: Point) -> str {
+        return \"point\"
+    }
+}
+def show(p: Point) -> str {
+    print(p)
+    print(Point(1))
+    return f\"at {p}\"
+}
+";
+        let ir = compile_to_ir(source, false).unwrap();
+        let show = &ir[ir.find("define ptr @ws_show(").unwrap()..];
+        let show = &show[..show.find("\n}").unwrap()];
+        // Dispatched through the vtable, once per use
+        assert_eq!(show.matches("call ptr %to_string").count(), 3, "{}", show);
+        assert_eq!(show.matches("call void @print_str(").count(), 2, "{}", show);
+    }
+
+    #[test]
+    fn test_custom_init_constructor() {
+        let source = "class Account {
+    owner: str
+    balance: int = 100
+    def init(self: Account, name: str, bonus: int = 5) -> void {
+        self.owner = name
+        self.balance += bonus
+    }
+}
+def open(name: str) -> Account {
+    return Account(name)
+}
+";
+        let ir = compile_to_ir(source, false).unwrap();
+        // The constructor takes init's parameters, not the fields
+        assert!(ir.contains("define ptr @ws_Account(ptr %0, i64 %1)"), "{}", ir);
+        let constructor = &ir[ir.find("define ptr @ws_Account(").unwrap()..];
+        let constructor = &constructor[..constructor.find("\n}").unwrap()];
+        assert!(constructor.contains("store i64 100"), "{}", constructor);
+        assert!(constructor.contains("store ptr null"), "{}", constructor);
+        // `-> void` parses as Custom("void"), so init returns a (null) pointer
+        assert!(constructor.contains("call ptr @ws_Account.init(ptr %obj_ptr, ptr %0, i64 %1)"), "{}", constructor);
+        // The caller fills in init's default
+        let open = &ir[ir.find("define ptr @ws_open(").unwrap()..];
+        let open = &open[..open.find("\n}").unwrap()];
+        assert!(open.contains("@ws_Account(ptr") && open.contains(", i64 5)"), "{}", open);
+    }
+
+    #[test]
+    fn test_defaulted_fields_are_not_constructor_arguments() {
+        let source = "class Counter {\n    name: str\n    count: int = 0\n}\ndef make() -> Counter {\n    return Counter(\"clicks\")\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("define ptr @ws_Counter(ptr %0)"), "{}", ir);
+    }
+
+    #[test]
+    fn test_super_call_is_direct() {
+        let source = "class Animal {\n    legs: int\n    def sound(self: Animal) -> str {\n        return \"...\"\n    }\n}\nclass Dog(Animal) {\n    def sound(self: Dog) -> str {\n        return super.sound()\n    }\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let dog_sound = &ir[ir.find("define ptr @ws_Dog.sound(").unwrap()..];
+        let dog_sound = &dog_sound[..dog_sound.find("\n}").unwrap()];
+        assert!(dog_sound.contains("call ptr @ws_Animal.sound(ptr %0)"), "{}", ir);
+        assert!(!dog_sound.contains("vtable"), "{}", ir);
+    }
+
+    #[test]
+    fn test_user_symbols_are_namespaced() {
+        let source = "class Shape {\n    size: int\n    def area(self: Shape) -> int {\n        return self.size\n    }\n    def main(self: Shape) -> int {\n        return 0\n    }\n}\ndef area(s: Shape) -> int {\n    return s.area()\n}\ndef main() -> int {\n    return area(Shape(2))\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // A method and a function of the same name get their own symbols
+        assert!(ir.contains("define i64 @ws_Shape.area(ptr"), "{}", ir);
+        assert!(ir.contains("define i64 @ws_area(ptr"), "{}", ir);
+        assert!(ir.contains("define i64 @ws_Shape.main(ptr"), "{}", ir);
+        assert!(ir.contains("define i64 @main()"), "{}", ir);
+        assert!(ir.contains("call ptr @ws_Shape(i64 2)"), "{}", ir);
+        assert!(!ir.contains(".1("), "{}", ir);
+
+        // The names the type checker keeps the program from taking
+        let reserved = CodeGen::runtime_function_names();
+        assert!(["strlen", "free", "list_length", "print_int"].iter().all(|name| reserved.contains(*name)));
+    }
+
+    #[test]
+    fn test_module_functions_are_namespaced() {
+        // What the loader builds for a program that imports two modules with a helper() each
+        let mut program = Program::new();
+        for (module, name, body) in [("a", "helper", "return 1"), ("a", "twice", "return helper() * 2"), ("b", "helper", "return 2")] {
+            let source = format!("def {}() -> int {{\n    {}\n}}\n", name, body);
+            let mut function = Parser::new(Lexer::new(source)).parse().statements.remove(0);
+            if let Statement::FunctionDef { name, .. } = &mut function {
+                *name = format!("{}.{}", module, name);
+            }
+            program.statements.push(function);
+        }
+        let main = "def main() -> int {\n    return a.twice() + b.helper()\n}\n";
+        program.statements.extend(Parser::new(Lexer::new(main.to_string())).parse().statements);
+        for (module, functions) in [("a", vec!["helper", "twice"]), ("b", vec!["helper"])] {
+            let functions = functions.into_iter().map(String::from).collect();
+            program.modules.insert(module.to_string(), crate::ast::Module { functions, ..Default::default() });
+        }
+
+        let context = Context::create();
+        let mut codegen = CodeGen::new(&context, "test_module", "test.ws");
+        codegen.compile_program(&program).unwrap();
+        let ir = codegen.get_module().print_to_string().to_string();
+        assert!(ir.contains("define i64 @ws_a.helper()"), "{}", ir);
+        assert!(ir.contains("define i64 @ws_b.helper()"), "{}", ir);
+        // A module's own helper() is the one its functions call
+        let twice = &ir[ir.find("define i64 @ws_a.twice()").unwrap()..];
+        assert!(twice[..twice.find("\n}").unwrap()].contains("call i64 @ws_a.helper()"), "{}", twice);
+        let main = &ir[ir.find("define i64 @main()").unwrap()..];
+        assert!(main.contains("call i64 @ws_a.twice()") && main.contains("call i64 @ws_b.helper()"), "{}", main);
+    }
+
+    #[test]
+    fn test_module_is_stamped_with_compiler_version() {
+        let ir = compile_to_ir("def main() -> int {\n    return 0\n}\n", false).unwrap();
+        let version = env!("CARGO_PKG_VERSION");
+        assert!(ir.contains("!llvm.ident"), "{}", ir);
+        assert!(ir.contains(&format!("!\"WadeScript version {}\"", version)), "{}", ir);
+        assert!(ir.contains(&format!("producer: \"WadeScript Compiler {}\"", version)), "{}", ir);
+    }
+
+    #[test]
+    fn test_inline_rc_uses_runtime_header_layout() {
+        let source = "def f() -> int {\n    a: list[int] = [1]\n    b: list[int] = [2, 3]\n    a = b\n    return a.length + b.length\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let body = &ir[ir.find("@ws_f(").unwrap()..];
+        let body = &body[..body.find("\n}").unwrap()];
+        // The count is reached at the offset the runtime defines
+        let ref_count_offset = format!("i64 {}", RC_REF_COUNT_OFFSET);
+        assert!(
+            body.lines().any(|line| line.contains("getelementptr i8, ptr") && line.contains(&ref_count_offset)),
+            "{}",
+            body
+        );
+        // A count that drops to zero is freed by the runtime, which knows the layout
+        assert!(body.contains("call void @list_free(ptr"), "{}", body);
+        assert!(!body.contains("call void @free("), "{}", body);
+    }
+
+    /// The part of `function`'s IR from the `while_cond` block on
+    fn loop_ir<'a>(ir: &'a str, function: &str) -> &'a str {
+        let body = &ir[ir.find(&format!("@ws_{}(", function)).unwrap()..];
+        let body = &body[..body.find("\n}").unwrap()];
+        &body[body.find("while_cond").unwrap()..]
+    }
+
+    #[test]
+    fn test_invariant_length_hoisted_out_of_while() {
+        let source = "def count(xs: list[int], s: str) -> int {\n    i: int = 0\n    total: int = 0\n    while i < xs.length and i < s.length {\n        total = total + xs[i]\n        print_int(total)\n        i = i + 1\n    }\n    return total\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let in_loop = loop_ir(&ir, "count");
+        assert!(!in_loop.contains("@list_length("), "{}", ir);
+        assert!(!in_loop.contains("@str_length("), "{}", ir);
+    }
+
+    #[test]
+    fn test_length_not_hoisted_when_body_resizes() {
+        // Pushing to the list itself
+        let source = "def grow(xs: list[int]) -> void {\n    while xs.length < 10 {\n        xs.push(1)\n    }\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(loop_ir(&ir, "grow").contains("@list_length("), "{}", ir);
+
+        // Passing any list to a function, which could be an alias
+        let source = "def fill(ys: list[int]) -> void {\n    ys.push(1)\n}\ndef grow(xs: list[int], ys: list[int]) -> void {\n    while xs.length < 10 {\n        fill(ys)\n    }\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(loop_ir(&ir, "grow").contains("@list_length("), "{}", ir);
+
+        // Mapping a function over lists that holds one of them
+        let source = "def add_one(ys: list[int]) -> int {\n    ys.push(1)\n    return ys.length\n}\ndef grow(outer: list[list[int]]) -> void {\n    xs: list[int] = outer[0]\n    while xs.length < 10 {\n        sizes: list[int] = outer.map(add_one)\n    }\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        // map() reads lengths in the body too, so look at the condition alone
+        let in_loop = loop_ir(&ir, "grow");
+        let condition = &in_loop[..in_loop.find("while_body").unwrap()];
+        assert!(condition.contains("@list_length("), "{}", ir);
+
+        // Reassigning a string
+        let source = "def shrink(s: str) -> void {\n    while s.length > 0 {\n        s = \"\"\n    }\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(loop_ir(&ir, "shrink").contains("@str_length("), "{}", ir);
+    }
+
+    /// The function attributes on the declaration or definition of `name`
+    fn function_attributes<'a>(ir: &'a str, name: &str) -> &'a str {
+        let signature = ir
+            .lines()
+            .find(|l| (l.starts_with("declare") || l.starts_with("define")) && l.contains(&format!("@{}(", name)))
+            .unwrap();
+        let group = signature.rsplit_once('#').unwrap().1.trim_end_matches(" {");
+        let attributes = format!("attributes #{} = ", group);
+        ir.lines().find_map(|l| l.strip_prefix(attributes.as_str())).unwrap()
+    }
+
+    #[test]
+    fn test_pure_runtime_functions_have_attributes() {
+        let ir = compile_to_ir("def f(s: str) -> int {\n    return s.length\n}\n", false).unwrap();
+        for name in ["str_length", "strcmp", "dict_has", "list_length", "bigint_cmp", "decimal_to_int"] {
+            let attributes = function_attributes(&ir, name);
+            assert!(attributes.contains("nounwind") && attributes.contains("willreturn"), "{}: {}", name, attributes);
+            assert!(attributes.contains("memory(read)") || attributes.contains("readonly"), "{}: {}", name, attributes);
+        }
+        let attributes = function_attributes(&ir, "float_round");
+        assert!(attributes.contains("memory(none)") || attributes.contains("readnone"), "{}", attributes);
+
+        // Functions that allocate or may raise are left alone
+        assert!(!ir.lines().any(|l| l.starts_with("declare") && l.contains("@list_get_i64(") && l.contains('#')));
+        assert!(!ir.lines().any(|l| l.starts_with("declare") && l.contains("@dict_get(") && l.contains('#')));
+    }
+
+    #[test]
+    fn test_checked_overflow_emits_intrinsics() {
+        let ir = compile_to_ir(ARITHMETIC, true).unwrap();
+        assert!(ir.contains("llvm.sadd.with.overflow.i64"));
+        assert!(ir.contains("llvm.ssub.with.overflow.i64"));
+        assert!(ir.contains("llvm.smul.with.overflow.i64"));
+        assert!(ir.contains("OverflowError"));
+    }
+
+    #[test]
+    fn test_checked_negation_and_division() {
+        let source = "def f(a: int, b: int) -> int {\n    return -a + a / b + a // b + a % b\n}\n";
+        let ir = compile_to_ir(source, true).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("@llvm.ssub.with.overflow.i64(i64 0,"), "{}", f);
+        // MIN / -1 and MIN // -1 raise; MIN % -1 divides by 1 instead
+        assert_eq!(f.matches("-9223372036854775808").count(), 2, "{}", f);
+        assert!(f.contains("select i1"), "{}", f);
+        assert!(ir.contains("Integer overflow in negation"), "{}", ir);
+        assert!(ir.contains("Integer overflow in division"), "{}", ir);
+        assert!(ir.contains("Integer overflow in floor division"), "{}", ir);
+    }
+
+    #[test]
+    fn test_wrapping_arithmetic_by_default() {
+        let ir = compile_to_ir(ARITHMETIC, false).unwrap();
+        assert!(!ir.contains("with.overflow"));
+        assert!(!ir.contains("OverflowError"));
+    }
+
+    #[test]
+    fn test_checked_builtins_return_none_on_overflow() {
+        let source = "def f(a: int, b: i32) -> int {\n    sum: int = checked_add(a, b) ?? 0\n    return checked_mul(a, 3) ?? -1\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("@llvm.sadd.with.overflow.i64("), "{}", f);
+        assert!(f.contains("@llvm.smul.with.overflow.i64("), "{}", f);
+        // The result is boxed, or null on overflow; nothing is raised
+        assert!(f.contains("phi ptr [ null"), "{}", f);
+        assert!(!f.contains("OverflowError"), "{}", f);
+    }
+
+    #[test]
+    fn test_bit_conversion_builtins() {
+        let source = "def f(x: float) -> int {\n    data: list[u8] = to_bytes_be(float_to_bits(x), 8)\n    return byte_swap(from_bytes_le(data))\n}\n";
+        let ir = compile_to_ir(source, false).unwrap();
+        assert!(ir.contains("bitcast double %0 to i64"), "{}", ir);
+        let f = ir.split("define ").find(|f| f.contains("@ws_f(")).unwrap();
+        assert!(f.contains("@bytes_pack_be("), "{}", f);
+        assert!(f.contains("@bytes_unpack_le("), "{}", f);
+        assert!(f.contains("@llvm.bswap.i64("), "{}", f);
+    }
+}
@@ -0,0 +1,176 @@
+// Crash reports for internal compiler errors. A panic on the main thread is
+// caught at the top of main and written to a local report file with the
+// compiler version, the panic and its backtrace, and the start of the source
+// file being compiled (unless WADESCRIPT_CRASH_SOURCE=0). Nothing is sent
+// anywhere; the user decides whether to attach the file to a bug report.
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::env;
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::output;
+
+/// Source lines copied into a report
+const MAX_SOURCE_LINES: usize = 200;
+
+/// What the panic hook saw of the last panic on the main thread
+struct PanicRecord {
+    message: String,
+    location: Option<String>,
+    // Symbolized only if a report is written: the LSP panics on every parse error
+    backtrace: Backtrace,
+}
+
+static LAST_PANIC: Mutex<Option<PanicRecord>> = Mutex::new(None);
+
+/// Record main-thread panics for the crash report instead of printing them.
+/// Other threads (the LSP's workers) keep the default hook.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() != Some("main") {
+            default_hook(info);
+            return;
+        }
+        let record = PanicRecord {
+            message: payload_message(info.payload()),
+            location: info.location().map(|location| location.to_string()),
+            backtrace: Backtrace::force_capture(),
+        };
+        *LAST_PANIC.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(record);
+    }));
+}
+
+pub fn payload_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(non-string panic payload)".to_string())
+}
+
+/// Write a report for the panic that just unwound out of main. Returns a one-line
+/// summary of the panic and the report's path, or the report itself when no
+/// file could be written.
+pub fn write_report(args: &[String]) -> (String, Result<PathBuf, String>) {
+    let record = LAST_PANIC.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+    let (message, location, backtrace) = match &record {
+        Some(record) => (record.message.as_str(), record.location.as_deref(), record.backtrace.to_string()),
+        None => ("(panic was not recorded)", None, String::new()),
+    };
+    let summary = match location {
+        Some(location) => format!("{} (at {})", message, location),
+        None => message.to_string(),
+    };
+
+    let current_file = output::current_file();
+    let source = match (&current_file, env::var("WADESCRIPT_CRASH_SOURCE").as_deref()) {
+        (None, _) => Err("no source file was being compiled".to_string()),
+        (Some(_), Ok("0")) => Err("omitted (WADESCRIPT_CRASH_SOURCE=0)".to_string()),
+        (Some(file), _) => fs::read_to_string(file)
+            .map(|text| (file.clone(), text))
+            .map_err(|e| format!("{} could not be read: {}", file, e)),
+    };
+    let report = render_report(message, location, &backtrace, args, source);
+
+    let stem = current_file
+        .as_deref()
+        .and_then(|file| Path::new(file).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "wadescript".to_string());
+    let file_name = format!("{}.crash.txt", stem);
+    // The working directory may be read-only; fall back to the temp directory
+    for path in [PathBuf::from(&file_name), env::temp_dir().join(&file_name)] {
+        if fs::write(&path, &report).is_ok() {
+            return (summary, Ok(path));
+        }
+    }
+    (summary, Err(report))
+}
+
+fn render_report(
+    message: &str,
+    location: Option<&str>,
+    backtrace: &str,
+    args: &[String],
+    source: Result<(String, String), String>,
+) -> String {
+    let mut report = String::from("WadeScript crash report\n\n");
+    report.push_str("The compiler panicked. This report was written locally and has not been sent\n");
+    report.push_str("anywhere; review it before attaching it to a bug report.\n\n");
+    report.push_str(&format!("Version:  wadescript {}\n", env!("CARGO_PKG_VERSION")));
+    report.push_str(&format!("Platform: {} {}\n", env::consts::OS, env::consts::ARCH));
+    report.push_str(&format!("Command:  {}\n", args.join(" ")));
+
+    report.push_str("\n## Panic\n\n");
+    if let Some(location) = location {
+        report.push_str(&format!("at {}:\n", location));
+    }
+    report.push_str(message);
+    report.push('\n');
+
+    report.push_str("\n## Backtrace\n\n");
+    report.push_str(if backtrace.is_empty() { "(unavailable)\n" } else { backtrace });
+    if !report.ends_with('\n') {
+        report.push('\n');
+    }
+
+    match source {
+        Ok((file, text)) => {
+            let total = text.lines().count();
+            let shown = total.min(MAX_SOURCE_LINES);
+            report.push_str(&format!("\n## Source ({}, lines 1-{} of {})\n\n", file, shown, total));
+            for (i, line) in text.lines().take(shown).enumerate() {
+                report.push_str(&format!("{:>4} | {}\n", i + 1, line));
+            }
+        }
+        Err(note) => report.push_str(&format!("\n## Source\n\n{}\n", note)),
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(args: &[String], source: Result<(String, String), String>) -> String {
+        let backtrace = "   0: wadescript::codegen::CodeGen::compile_expression\n";
+        render_report("Undefined variable: x", Some("src/codegen.rs:120:9"), backtrace, args, source)
+    }
+
+    #[test]
+    fn test_render_report() {
+        let args = vec!["wadescript".to_string(), "app.ws".to_string()];
+        let source = "def main() -> int {\n    return x\n}\n".to_string();
+        let report = render(&args, Ok(("app.ws".to_string(), source)));
+        assert!(report.contains(&format!("Version:  wadescript {}", env!("CARGO_PKG_VERSION"))), "{}", report);
+        assert!(report.contains("Command:  wadescript app.ws"), "{}", report);
+        assert!(report.contains("at src/codegen.rs:120:9:\nUndefined variable: x\n"), "{}", report);
+        assert!(report.contains("CodeGen::compile_expression"), "{}", report);
+        assert!(report.contains("## Source (app.ws, lines 1-3 of 3)"), "{}", report);
+        assert!(report.contains("   2 |     return x\n"), "{}", report);
+    }
+
+    #[test]
+    fn test_render_report_limits_and_omits_source() {
+        let long: String = (1..=MAX_SOURCE_LINES + 50).map(|i| format!("# line {}\n", i)).collect();
+        let report = render(&[], Ok(("big.ws".to_string(), long)));
+        assert!(report.contains(&format!("lines 1-{} of {}", MAX_SOURCE_LINES, MAX_SOURCE_LINES + 50)), "{}", report);
+        assert!(!report.contains(&format!("# line {}\n", MAX_SOURCE_LINES + 1)), "{}", report);
+
+        let report = render(&[], Err("omitted (WADESCRIPT_CRASH_SOURCE=0)".to_string()));
+        assert!(report.ends_with("## Source\n\nomitted (WADESCRIPT_CRASH_SOURCE=0)\n"), "{}", report);
+    }
+
+    #[test]
+    fn test_payload_message() {
+        assert_eq!(payload_message(&"static message"), "static message");
+        assert_eq!(payload_message(&"owned".to_string()), "owned");
+        assert_eq!(payload_message(&42), "(non-string panic payload)");
+    }
+}
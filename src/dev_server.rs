@@ -0,0 +1,337 @@
+//! Development server with hot reload (`wadescript dev <file.ws>`)
+//!
+//! The program defines a request handler:
+//!
+//!     def handle(method: str, path: str, body: str) -> str
+//!
+//! which is JIT-compiled and called for every request; what it returns is the
+//! response body. The program and its imports are polled for changes, and a
+//! change recompiles them in-process and swaps in the new handler while the
+//! listener stays open. A version that fails to compile leaves the previous
+//! handler serving.
+
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use inkwell::context::Context;
+use inkwell::execution_engine::JitFunction;
+
+use crate::ast::{Program, Statement, Type};
+use crate::codegen::CodeGen;
+use crate::crash;
+use crate::error_codes;
+use crate::jit::{HandlerFn, JitEngine};
+use crate::output::{self, paint};
+use crate::typechecker::TypeChecker;
+use crate::verify;
+
+const HANDLER: &str = "handle";
+const HANDLER_SIGNATURE: &str = "def handle(method: str, path: str, body: str) -> str";
+const DEFAULT_PORT: u16 = 8080;
+/// How often the sources are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Largest request head accepted, in bytes
+const MAX_HEAD: usize = 64 * 1024;
+
+/// A compiled version of the program
+struct Handler {
+    // Keeps its execution engine alive; dropping it frees the previous version's code
+    function: JitFunction<'static, HandlerFn>,
+}
+
+/// Modification times of the watched files (None: missing)
+type Snapshot = BTreeMap<PathBuf, Option<SystemTime>>;
+
+fn snapshot(paths: &HashSet<PathBuf>) -> Snapshot {
+    paths
+        .iter()
+        .map(|path| (path.clone(), fs::metadata(path).and_then(|m| m.modified()).ok()))
+        .collect()
+}
+
+/// `wadescript dev <file.ws> [--port <n>]`
+pub fn run(args: &[String]) -> Result<(), String> {
+    let usage = || "Usage: wadescript dev <file.ws> [--port <n>]".to_string();
+    let (file, port) = match args {
+        [file] => (file.clone(), DEFAULT_PORT),
+        [file, flag, port] if flag == "--port" => {
+            (file.clone(), port.parse().map_err(|_| format!("Invalid port '{}'", port))?)
+        }
+        _ => return Err(usage()),
+    };
+    let file = if file.ends_with(".ws") { file } else { format!("{}.ws", file) };
+
+    // A syntax error is reported and waited out instead of ending the server
+    output::set_parse_errors_exit(false);
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| format!("Cannot listen on port {}: {}", port, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Cannot configure the listener: {}", e))?;
+
+    let mut watched: HashSet<PathBuf> = HashSet::from([PathBuf::from(&file)]);
+    let mut handler = build(&file, &mut watched);
+    let mut versions = snapshot(&watched);
+    println!("Serving {} on http://127.0.0.1:{} (Ctrl+C to stop)", file, port);
+
+    let mut last_poll = Instant::now();
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(e) = serve(stream, handler.as_ref()) {
+                    eprintln!("{} {}", paint("33;1", "Request failed:"), e);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(10)),
+            Err(e) => eprintln!("{} {}", paint("33;1", "Accept failed:"), e),
+        }
+
+        if last_poll.elapsed() < POLL_INTERVAL {
+            continue;
+        }
+        last_poll = Instant::now();
+        let current = snapshot(&watched);
+        if current == versions {
+            continue;
+        }
+        versions = current;
+        let started = Instant::now();
+        if let Some(new_handler) = build(&file, &mut watched) {
+            handler = Some(new_handler);
+            println!("Reloaded {} in {} ms", file, started.elapsed().as_millis());
+        } else if handler.is_some() {
+            eprintln!("{}", paint("90", "Still serving the previous version"));
+        }
+        // Imports may have been added or removed
+        versions = snapshot(&watched);
+    }
+}
+
+/// Compile the program and look up its handler, reporting any error. The
+/// files it loaded are added to `watched`.
+fn build(file: &str, watched: &mut HashSet<PathBuf>) -> Option<Handler> {
+    let mut imported = HashSet::new();
+    let loaded = panic::catch_unwind(AssertUnwindSafe(|| crate::load_program_with_imports(file, &mut imported)));
+    watched.extend(imported);
+    let mut program = match loaded {
+        Ok(Ok(program)) => program,
+        Ok(Err(e)) => {
+            crate::report_error("Error loading program", error_codes::LOAD_ERROR, &e);
+            return None;
+        }
+        Err(payload) => {
+            crate::report_error("Parse error", error_codes::PARSE_ERROR, &crash::payload_message(payload.as_ref()));
+            return None;
+        }
+    };
+    output::set_current_file(file);
+    if let Some(library) = program.libraries.first() {
+        let message = format!(
+            "the dev server can't link the compiled library '{}'; import the module's source instead",
+            library.path.display()
+        );
+        crate::report_error("Error loading program", error_codes::LOAD_ERROR, &message);
+        return None;
+    }
+
+    let mut type_checker = TypeChecker::new();
+    type_checker.set_reserved_names(CodeGen::runtime_function_names());
+    if let Err(e) = type_checker.check_program(&mut program) {
+        crate::report_error("Type error", error_codes::classify_type_error(&e), &e);
+        return None;
+    }
+    for warning in type_checker.warnings() {
+        crate::report_warning(error_codes::classify_warning(warning), warning);
+    }
+    if let Err(e) = check_handler(&program) {
+        crate::report_error("Error loading program", error_codes::LOAD_ERROR, &e);
+        return None;
+    }
+
+    // Each version gets its own context; like the REPL's, it lives for the rest of the process
+    let context: &'static Context = Box::leak(Box::new(Context::create()));
+    let mut codegen = CodeGen::new(context, "wadescript_dev", file);
+    let compiled = codegen
+        .compile_program(&program)
+        .and_then(|()| verify::verify_module(codegen.get_module()));
+    if let Err(e) = compiled {
+        crate::report_error("Compilation error", error_codes::CODEGEN_ERROR, &e);
+        return None;
+    }
+
+    let loaded = JitEngine::new(context).and_then(|jit| {
+        jit.add_module(codegen.take_module())?;
+        unsafe { jit.get_function::<HandlerFn>(&format!("ws_{}", HANDLER)) }
+    });
+    match loaded {
+        Ok(function) => Some(Handler { function }),
+        Err(e) => {
+            crate::report_error("Compilation error", error_codes::CODEGEN_ERROR, &e);
+            None
+        }
+    }
+}
+
+/// The program must define the handler with the expected signature
+fn check_handler(program: &Program) -> Result<(), String> {
+    let handler = program.statements.iter().find_map(|statement| match statement {
+        Statement::FunctionDef { name, params, return_type, .. } if name == HANDLER => Some((params, return_type)),
+        _ => None,
+    });
+    match handler {
+        None => Err(format!("No request handler found; define `{}`", HANDLER_SIGNATURE)),
+        Some((params, return_type)) => {
+            let takes_strings = params.len() == 3 && params.iter().all(|p| p.param_type == Type::Str);
+            if takes_strings && *return_type == Type::Str {
+                Ok(())
+            } else {
+                Err(format!("The request handler must be declared as `{}`", HANDLER_SIGNATURE))
+            }
+        }
+    }
+}
+
+/// Method, path, and Content-Length from a request head (the request line and headers)
+fn parse_head(head: &str) -> Result<(String, String, usize), String> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(path), Some(_version)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("Malformed request line '{}'", request_line));
+    };
+
+    let mut content_length = 0;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid Content-Length '{}'", value.trim()))?;
+            }
+        }
+    }
+    Ok((method.to_string(), path.to_string(), content_length))
+}
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Read one request, run the handler on it, and write the response
+fn serve(mut stream: TcpStream, handler: Option<&Handler>) -> io::Result<()> {
+    // Accepted sockets inherit the listener's non-blocking mode on some platforms
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end;
+        }
+        if data.len() > MAX_HEAD {
+            return stream.write_all(response("431 Request Header Fields Too Large", "").as_bytes());
+        }
+        let n = stream.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        data.extend_from_slice(&buffer[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+    let (method, path, content_length) = match parse_head(&head) {
+        Ok(request) => request,
+        Err(e) => return stream.write_all(response("400 Bad Request", &e).as_bytes()),
+    };
+    let mut body = data.split_off(head_end + 4);
+    if body.len() < content_length {
+        let start = body.len();
+        body.resize(content_length, 0);
+        stream.read_exact(&mut body[start..])?;
+    }
+    body.truncate(content_length);
+
+    let reply = match handler {
+        None => response("503 Service Unavailable", "The program has not compiled yet; see the dev server's output\n"),
+        Some(handler) => match call_handler(handler, &method, &path, &body) {
+            Ok(text) => response("200 OK", &text),
+            Err(e) => response("400 Bad Request", &e),
+        },
+    };
+    eprintln!("{}", paint("90", &format!("{} {}", method, path)));
+    stream.write_all(reply.as_bytes())
+}
+
+fn call_handler(handler: &Handler, method: &str, path: &str, body: &[u8]) -> Result<String, String> {
+    let method = CString::new(method).map_err(|_| "The method contains a NUL byte".to_string())?;
+    let path = CString::new(path).map_err(|_| "The path contains a NUL byte".to_string())?;
+    let body = CString::new(body).map_err(|_| "The body contains a NUL byte".to_string())?;
+    unsafe {
+        let result = handler.function.call(
+            method.as_ptr() as *const u8,
+            path.as_ptr() as *const u8,
+            body.as_ptr() as *const u8,
+        );
+        if result.is_null() {
+            return Ok(String::new());
+        }
+        Ok(CStr::from_ptr(result as *const c_char).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> Program {
+        Parser::new(Lexer::new(source.to_string())).parse()
+    }
+
+    #[test]
+    fn test_check_handler() {
+        let good = parse("def handle(method: str, path: str, body: str) -> str {\n    return path\n}\n");
+        assert!(check_handler(&good).is_ok());
+
+        let missing = parse("def main() -> int {\n    return 0\n}\n");
+        assert!(check_handler(&missing).unwrap_err().contains("No request handler"));
+
+        let wrong = parse("def handle(path: str) -> str {\n    return path\n}\n");
+        assert!(check_handler(&wrong).unwrap_err().contains(HANDLER_SIGNATURE));
+    }
+
+    #[test]
+    fn test_parse_head() {
+        let head = "POST /items?id=3 HTTP/1.1\r\nHost: localhost\r\ncontent-length: 12";
+        assert_eq!(parse_head(head).unwrap(), ("POST".to_string(), "/items?id=3".to_string(), 12));
+        assert_eq!(parse_head("GET / HTTP/1.1").unwrap().2, 0);
+        assert!(parse_head("GET").is_err());
+        assert!(parse_head("GET / HTTP/1.1\r\nContent-Length: lots").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_tracks_missing_files() {
+        let path = std::env::temp_dir().join(format!("ws_dev_{}.ws", std::process::id()));
+        let paths = HashSet::from([path.clone()]);
+        assert_eq!(snapshot(&paths)[&path], None);
+        fs::write(&path, "def handle() -> void {\n}\n").unwrap();
+        assert!(snapshot(&paths)[&path].is_some());
+        fs::remove_file(&path).ok();
+    }
+}
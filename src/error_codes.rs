@@ -0,0 +1,816 @@
+//! Stable diagnostic codes
+//!
+//! Every compiler diagnostic gets a code (e.g. E0102) that stays the same across
+//! releases, so it can be searched for, linked from the LSP, and explained with
+//! `wadescript explain E0102`.
+//!
+//! Type errors are plain `String`s, so their code is derived from the message
+//! text by `classify_type_error`. When adding a new type error message, make
+//! sure it matches the right entry's patterns (or add a new entry here and in
+//! docs/ERROR_CODES.md).
+
+/// Documentation page that `codeDescription` links point to (one anchor per code)
+pub const ERROR_CODES_URL: &str = "https://github.com/WadeSeidule/wadescript/blob/main/docs/ERROR_CODES.md";
+
+pub const PARSE_ERROR: &str = "E0001";
+pub const LOAD_ERROR: &str = "E0002";
+pub const GENERIC_TYPE_ERROR: &str = "E0199";
+pub const CODEGEN_ERROR: &str = "E0200";
+pub const INTERNAL_ERROR: &str = "E0201";
+pub const LINK_ERROR: &str = "E0300";
+pub const TARGET_ERROR: &str = "E0301";
+pub const LIBRARY_ERROR: &str = "E0302";
+pub const UNUSED_RESULT_WARNING: &str = "W0001";
+pub const SOURCE_MAP_WARNING: &str = "W0004";
+
+/// A diagnostic code with its extended explanation
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub title: &'static str,
+    /// Substrings of type error messages that map to this code
+    pub patterns: &'static [&'static str],
+    /// Markdown: description, an example that triggers it, and how to fix it
+    pub explanation: &'static str,
+}
+
+/// All diagnostic codes, in order. Type error classification checks entries
+/// top to bottom, so more specific patterns must come first.
+pub fn get_error_codes() -> Vec<ErrorCode> {
+    vec![
+        ErrorCode {
+            code: "E0001",
+            title: "Syntax error",
+            patterns: &[],
+            explanation: r#"The parser found a token it did not expect.
+
+```wadescript
+def main( -> int {      # missing parameter list
+    return 0
+}
+```
+
+Fix the code at the reported line and column. The message names what was
+expected and what was found (`got: ...`)."#,
+        },
+        ErrorCode {
+            code: "E0002",
+            title: "Could not load program",
+            patterns: &[],
+            explanation: r#"A source file or import could not be read, or imports form a cycle.
+
+```wadescript
+import "utils"           # utils.ws does not exist next to this file
+```
+
+Check the path (imports are relative to the importing file; standard library
+modules are plain names like `import "io"`) and remove circular imports."#,
+        },
+        ErrorCode {
+            code: "E0100",
+            title: "Undefined variable",
+            patterns: &["Undefined variable"],
+            explanation: r#"A name is used before it is declared, or outside the scope it was declared in.
+
+```wadescript
+def main() -> int {
+    if True {
+        y: int = 1
+    }
+    return y             # y is only visible inside the if block
+}
+```
+
+Declare the variable before use, in a scope that encloses every use. If a
+similarly named variable is in scope, the error suggests it
+(`Undefined variable 'countr'; did you mean 'counter'?`)."#,
+        },
+        ErrorCode {
+            code: "E0101",
+            title: "Undefined function or module member",
+            patterns: &["Undefined function", "has no function"],
+            explanation: r#"A call refers to a function that does not exist (or is defined later in the file).
+
+```wadescript
+def main() -> int {
+    return helper()      # helper is not defined
+}
+```
+
+Functions must be defined before they are called. For module functions,
+check the `import` and the spelling of `module.function`; a close match is
+suggested when there is one."#,
+        },
+        ErrorCode {
+            code: "E0102",
+            title: "Type mismatch in declaration or assignment",
+            patterns: &["Type mismatch in variable", "Cannot assign"],
+            explanation: r#"The value's type does not match the declared type of the variable.
+
+```wadescript
+count: int = "three"
+```
+
+Change the annotation or convert the value so both sides agree."#,
+        },
+        ErrorCode {
+            code: "E0103",
+            title: "Invalid operand types",
+            patterns: &["Invalid operands", "Cannot compare", "Logical operators require", "Not operator requires", "Negate operator requires"],
+            explanation: r#"An operator was applied to types it does not support.
+
+```wadescript
+x: int = "a" - 1         # '-' is not defined for str
+ok: bool = not 5         # 'not' needs a bool
+```
+
+Use operands of compatible types: numbers for arithmetic, `bool` for
+`and`/`or`/`not`, `str + str` for concatenation and `str * int` for repetition."#,
+        },
+        ErrorCode {
+            code: "E0104",
+            title: "Wrong number of arguments",
+            patterns: &["arguments, got", "missing required argument", "takes no arguments", "takes exactly"],
+            explanation: r#"A function, method, or constructor was called with too many or too few arguments.
+
+```wadescript
+def add(a: int, b: int) -> int {
+    return a + b
+}
+x: int = add(1)          # missing argument 'b'
+```
+
+Pass every parameter that has no default value, and no more than the
+function declares."#,
+        },
+        ErrorCode {
+            code: "E0105",
+            title: "Argument type mismatch",
+            patterns: &["Argument ", "Named argument", "argument must be", "argument type mismatch"],
+            explanation: r#"An argument's type does not match the parameter's declared type.
+
+```wadescript
+def square(n: int) -> int {
+    return n * n
+}
+s: int = square("4")
+```
+
+Pass a value of the parameter's type."#,
+        },
+        ErrorCode {
+            code: "E0106",
+            title: "Return type mismatch",
+            patterns: &["Return type mismatch"],
+            explanation: r#"A `return` statement's value does not match the function's declared return type.
+
+```wadescript
+def name() -> str {
+    return 42
+}
+```
+
+Return a value of the declared type, or change the `->` annotation."#,
+        },
+        ErrorCode {
+            code: "E0107",
+            title: "Condition must be bool",
+            patterns: &["condition must be bool", "Assert condition must be bool"],
+            explanation: r#"`if`, `elif`, `while`, and `assert` conditions must have type `bool`.
+
+```wadescript
+n: int = 3
+if n {                   # int is not a bool
+    print_int(n)
+}
+```
+
+Compare explicitly: `if n != 0 { ... }`."#,
+        },
+        ErrorCode {
+            code: "E0108",
+            title: "Unknown field, method, or property",
+            patterns: &["has no field", "has no method", "has no property", "Unknown method", "Unknown property", "has no methods"],
+            explanation: r#"The member accessed does not exist on the value's type.
+
+```wadescript
+s: str = "hi"
+n: int = s.size          # str has 'length', not 'size'
+```
+
+Check the spelling and the type of the value. See the method lists in the
+documentation for `str`, `list`, and `dict`."#,
+        },
+        ErrorCode {
+            code: "E0109",
+            title: "Private member access",
+            patterns: &["Cannot access private"],
+            explanation: r#"Fields and methods whose names start with `_` are private to their class.
+
+```wadescript
+class Account {
+    _balance: int
+}
+a: Account = Account(10)
+b: int = a._balance
+```
+
+Add a public method that exposes what callers need."#,
+        },
+        ErrorCode {
+            code: "E0110",
+            title: "Invalid indexing, slicing, iteration, or unpacking",
+            patterns: &["Cannot iterate", "Cannot index", "Cannot slice", "Slice start", "Slice end", "Slice step", "Tuple index", "Cannot unpack", "Tuple unpacking", "index must be int"],
+            explanation: r#"The value's type does not support the operation, or a tuple index is out of range.
+
+```wadescript
+n: int = 5
+for x in n {             # int is not iterable
+}
+p: (int, int) = (1, 2)
+z: int = p.2             # tuple has only 2 elements
+```
+
+Iterate over lists, arrays, dicts, and strings; index with `int`; keep tuple
+indices within the tuple's length."#,
+        },
+        ErrorCode {
+            code: "E0111",
+            title: "Cannot infer type",
+            patterns: &["Cannot infer type of"],
+            explanation: r#"An empty `[]` or `{}`, or `None`, has nothing to infer a type from.
+
+```wadescript
+let items = []
+let name = None
+```
+
+Add a type annotation: `let items: list[int] = []` or `let name: str? = None`.
+A `let` initializer must also produce a value, so calling a void function is
+rejected."#,
+        },
+        ErrorCode {
+            code: "E0112",
+            title: "Inconsistent element types",
+            patterns: &["Inconsistent", "Dict key type mismatch"],
+            explanation: r#"All elements of a list, array, or dict literal must have the same type.
+
+```wadescript
+xs: list[int] = [1, "two", 3]
+```
+
+Make all elements the same type."#,
+        },
+        ErrorCode {
+            code: "E0113",
+            title: "Invalid decorator",
+            patterns: &["decorator", "@memoize", "@inline", "@noinline"],
+            explanation: r#"A decorator is unknown or not allowed where it is used.
+
+```wadescript
+@cached                  # unknown decorator
+def f(n: int) -> int {
+    return n
+}
+```
+
+Function decorators are `@memoize`, `@inline`, and `@noinline`; methods and
+classes also accept `@abstract`, top-level functions and classes
+`@shadows_builtin`; field decorators are `@arg` and `@option`.
+See `docs/DECORATORS.md` for the restrictions of each."#,
+        },
+        ErrorCode {
+            code: "E0114",
+            title: "Invalid parameter list",
+            patterns: &["without default follows", "specified multiple times", "has no parameter named", "does not take named arguments", "must have 'self' parameter"],
+            explanation: r#"A function's parameters, or the named arguments passed to it, are malformed.
+
+```wadescript
+def greet(greeting: str = "hi", name: str) -> void {   # default before required
+}
+greet(name="a", name="b")                              # duplicate named argument
+```
+
+Put parameters with defaults after required ones, name each argument once,
+and give methods an explicit `self` parameter."#,
+        },
+        ErrorCode {
+            code: "E0115",
+            title: "Missing return in strict mode",
+            patterns: &["does not return a value on every path"],
+            explanation: r#"With `--strict`, a function with a return type must return (or raise) on
+every path through its body.
+
+```wadescript
+def sign(n: int) -> int {
+    if n > 0 {
+        return 1
+    }
+}                        # falls off the end when n <= 0
+```
+
+Add a final `return`, or an `else` branch that returns."#,
+        },
+        ErrorCode {
+            code: "E0116",
+            title: "Mixed int/float comparison in strict mode",
+            patterns: &["requires an explicit conversion"],
+            explanation: r#"With `--strict`, comparing an `int` with a `float` is an error instead of an
+implicit conversion.
+
+```wadescript
+count: int = 3
+if count < 2.5 {         # int compared with float
+}
+```
+
+Compare values of the same type, e.g. write the literal as `3.0` or keep both
+sides `int`."#,
+        },
+        ErrorCode {
+            code: "E0117",
+            title: "Unchecked Optional in strict mode",
+            patterns: &["must be checked against None"],
+            explanation: r#"With `--strict`, an Optional value cannot be used as its inner type (`.length`,
+indexing) until it has been checked.
+
+```wadescript
+names: list[str]? = load()
+n: int = names.length    # names may be None
+```
+
+Check it first; inside the branch the variable can be used directly:
+
+```wadescript
+if names != None {
+    n: int = names.length
+}
+```"#,
+        },
+        ErrorCode {
+            code: "E0118",
+            title: "Shadowed variable in strict mode",
+            patterns: &["shadows an existing variable"],
+            explanation: r#"With `--strict`, declaring a variable (or parameter, loop variable, or
+`except` binding) with the name of a variable that is already visible is an
+error.
+
+```wadescript
+total: int = 0
+def add(total: int) -> int {   # parameter shadows the global 'total'
+    return total
+}
+```
+
+Rename one of the two variables."#,
+        },
+        ErrorCode {
+            code: "E0119",
+            title: "Invalid optional chaining",
+            patterns: &["Optional chaining"],
+            explanation: r#"`?.` reads a field of an Optional class instance, producing None when the
+instance is None. It only applies to Optional class values, and only to fields
+that can hold None themselves (`str`, lists, dicts, and class instances).
+
+```wadescript
+user: User = load()
+n: str? = user?.name     # user is not Optional; use user.name
+addr: Address? = None
+z: int? = addr?.zip      # int fields cannot be chained yet
+```
+
+Use `.` on non-Optional values, and check `addr != None` before reading numeric
+fields."#,
+        },
+        ErrorCode {
+            code: "E0120",
+            title: "Invalid cast",
+            patterns: &["Cannot cast"],
+            explanation: r#"`as` converts only between numeric types (`int`, `i32`, `i16`, `u8`, `float`),
+plus `bool` to an integer type.
+
+```wadescript
+n: int = "42" as int     # strings cannot be cast
+x: str = 3 as str        # 'as' targets must be numeric
+```
+
+Use `cli.parse_int` to parse a string and an f-string to format a number."#,
+        },
+        ErrorCode {
+            code: "E0121",
+            title: "Invalid null coalescing",
+            patterns: &["'??'"],
+            explanation: r#"The left side of `??` must be an Optional, and the default must have its inner
+type (or be an Optional of the same type).
+
+```wadescript
+count: int = 3
+n: int = count ?? 0          # count is not Optional; use count directly
+name: str? = None
+m: int = name ?? 0           # default must be str
+```"#,
+        },
+        ErrorCode {
+            code: "E0122",
+            title: "Invalid Result propagation",
+            patterns: &["'?'", "full Result type"],
+            explanation: r#"`?` unwraps an `Ok` value or returns the `Err` from the enclosing function. It needs a
+`Result` operand, and the function must return a `Result` whose error type accepts the
+propagated error. `Ok(...)` and `Err(...)` only describe one side of a Result, so a
+`let` declaration needs the full type written out.
+
+```wadescript
+def total(a: str) -> int {
+    n: int = parse(a)?           # total must return Result[..., str]
+    return n
+}
+let r = Ok(1)                    # write 'let r: Result[int, str] = Ok(1)'
+```"#,
+        },
+        ErrorCode {
+            code: "E0123",
+            title: "Abstract class instantiated",
+            patterns: &["abstract class", "abstract method"],
+            explanation: r#"A class marked `@abstract`, or one with `@abstract` methods that have no
+override yet, can't be constructed. `super` can't call an abstract method either,
+since it has no implementation.
+
+```wadescript
+class Shape {
+    @abstract
+    def area(self: Shape) -> float {
+        pass
+    }
+}
+s: Shape = Shape()       # Shape.area is not implemented
+```
+
+Construct a subclass that overrides every abstract method instead."#,
+        },
+        ErrorCode {
+            code: "E0124",
+            title: "Object cannot be converted to a string",
+            patterns: &["cannot be converted to a string"],
+            explanation: r#"`print()` and f-strings format a class instance by calling its
+`to_string(self) -> str` method. The class (or one of its bases) has no such
+method, or it takes extra parameters or returns something other than `str`.
+
+```wadescript
+class Point {
+    x: int
+    y: int
+}
+p: Point = Point(1, 2)
+print(p)                 # Point has no to_string
+```
+
+Define `def to_string(self: Point) -> str { return f"({self.x}, {self.y})" }`."#,
+        },
+        ErrorCode {
+            code: "E0125",
+            title: "Invalid deinit",
+            patterns: &["'deinit'", ".deinit' must"],
+            explanation: r#"`deinit` runs automatically when the last reference to an object goes
+away, so it must be declared as `def deinit(self: C) -> void` and can't be
+called from code, not even with `super.deinit()`: a base class's `deinit` runs
+on its own right after the subclass's.
+
+```wadescript
+class Log {
+    fd: int
+
+    def deinit(self: Log, flush: bool) -> void {    # deinit takes only self
+        print(f"closing {self.fd}")
+    }
+}
+```
+
+Move the extra work into a regular method and call it before dropping the object."#,
+        },
+        ErrorCode {
+            code: "E0126",
+            title: "Unsupported dict type",
+            patterns: &["Unsupported dict"],
+            explanation: r#"Dict keys are hashed by value: `str` keys by their contents, integer and
+`bool` keys by their value, and objects by identity (the same object, not an
+equal one). Other key types, such as `float` or `list`, can't be hashed. Each
+value is stored in a single 64-bit slot, so tuples, Results, and fixed-size
+arrays can't be dict values.
+
+```wadescript
+weights: dict[float, int] = {}       # float keys are not supported
+```
+
+Key the dict by a supported type (for example, an `int` id), and wrap a
+compound value in a class."#,
+        },
+        ErrorCode {
+            code: "E0127",
+            title: "Name defined more than once",
+            patterns: &[
+                "is defined more than once",
+                "is defined as both a function and a class",
+                "is defined in more than one imported module",
+            ],
+            explanation: r#"A function is defined twice in one file, a class twice across a program and
+everything it imports, or a function and a class share a name. Each imported
+file's functions belong to its module, so two modules may both define
+`area()`; an unqualified call that could reach either is an error too.
+
+```wadescript
+import "shapes"          # shapes.ws defines area()
+import "rooms"           # and so does rooms.ws
+
+def main() -> int {
+    return area(2, 3)    # which one? write shapes.area(2, 3)
+}
+```
+
+Rename one of the definitions, or call the function through its module.
+Methods are not affected: a method can share its name with a function or with
+another class's method."#,
+        },
+        ErrorCode {
+            code: "E0128",
+            title: "Name of a builtin",
+            patterns: &[
+                "shadows the builtin",
+                "which can't be replaced",
+                "@shadows_builtin, but",
+                "has the name of a runtime function",
+            ],
+            explanation: r#"A top-level function or class has the name of a builtin. Most builtins
+(`range`, `print_int`, `round`, ...) can't be replaced, because calls to them
+compile straight to the runtime; rename the definition.
+
+`print`, `Ok`, `Err` and the builtin exception types (`Exception`,
+`ValueError`, ...) can be replaced. Say so with `@shadows_builtin`, so a
+reader knows the builtin is hidden on purpose:
+
+```wadescript
+@shadows_builtin
+def print(message: str) -> void {
+    print_str("[app] " + message)
+}
+```
+
+Marking a definition that doesn't collide with a builtin is also an error.
+
+The program's functions and classes also can't take the name of a runtime
+function the compiler calls itself (`strlen`, `list_length`, ...), which can't
+be replaced either."#,
+        },
+        ErrorCode {
+            code: "E0199",
+            title: "Type error",
+            patterns: &[],
+            explanation: r#"A type error that has no more specific code. Read the message for details."#,
+        },
+        ErrorCode {
+            code: "E0200",
+            title: "Code generation error",
+            patterns: &[],
+            explanation: r#"The program type-checked but could not be compiled to LLVM IR.
+This usually means a language feature is not implemented for the types
+involved (for example the `**` power operator, which is not implemented yet).
+
+Rewrite the expression using a supported form, and please report it if the
+message looks like a compiler bug."#,
+        },
+        ErrorCode {
+            code: "E0201",
+            title: "Internal compiler error",
+            patterns: &[],
+            explanation: r#"The compiler generated invalid LLVM IR for a program that type-checked.
+This is a bug in the compiler, not in your program.
+
+The error names the function the bad IR is in and the source lines it covers,
+and the compiler writes the whole module to `<program>.ice.ll`. Please report
+the bug with the source file and that dump. Until it is fixed, rewriting the
+code on the reported lines in a different form usually avoids it.
+
+If the compiler crashes outright, it writes a crash report to
+`<program>.crash.txt` (or the temp directory) with the compiler version, the
+panic and its backtrace, and the start of the source file. The report stays on
+your machine; review it before attaching it to a bug report, or set
+`WADESCRIPT_CRASH_SOURCE=0` to leave the source out."#,
+        },
+        ErrorCode {
+            code: "E0300",
+            title: "Linking failed",
+            patterns: &[],
+            explanation: r#"`clang` could not link the object file with the runtime library.
+
+Make sure `clang` is installed and on `PATH`, the runtime was built with
+`cargo build`, and, for `--target`, that a sysroot for that target is available."#,
+        },
+        ErrorCode {
+            code: "E0301",
+            title: "Unsupported target",
+            patterns: &[],
+            explanation: r#"LLVM does not support the requested `--target` triple, or the runtime archive
+for that target (or for the `--allocator` chosen) has not been built.
+
+Check `llvm-config --targets-built`, then build the runtime with
+`cargo build --lib --target <triple>`. The compiler prints the exact command,
+including the `--features` and `--target-dir` an allocator archive needs."#,
+        },
+        ErrorCode {
+            code: "E0302",
+            title: "Library error",
+            patterns: &[],
+            explanation: r#"A module could not be packaged with `--emit-lib`, or a compiled library
+(`.wslib`) could not be linked into the program.
+
+```wadescript
+def area(w: int, h: int = 1) -> int {   # defaults can't be exported yet
+    return w * h
+}
+```
+
+A library exports the functions of the file being compiled (not `main`).
+Their parameters can't have default values, and their signatures can't use
+class types. A library can't import another compiled library. When linking,
+the library must have been built for the same `--target` as the program."#,
+        },
+        ErrorCode {
+            code: "W0001",
+            title: "Unused result",
+            patterns: &["is unused (strict mode)"],
+            explanation: r#"Warning (with `--strict`): a function or method returns a value that the
+call ignores.
+
+```wadescript
+compute(10)              # the int result is discarded
+```
+
+Use the result, or assign it to a variable if it is intentionally unused."#,
+        },
+        ErrorCode {
+            code: "W0002",
+            title: "Index loop that only reads elements",
+            patterns: &["iterate over the list directly"],
+            explanation: r#"Warning: a `for i in range(xs.length)` loop uses `i` only to read `xs[i]`.
+
+```wadescript
+for i in range(prices.length) {
+    total = total + prices[i]
+}
+```
+
+Iterate over the elements instead: `for price in prices { ... }`. The loop
+compiles to a plain index loop either way (no range list is built), so this
+is about readability, not speed. Loops that also use `i` for anything else,
+or assign `xs[i]`, are not reported."#,
+        },
+        ErrorCode {
+            code: "W0003",
+            title: "Variable named after a builtin function",
+            patterns: &["which calls still reach"],
+            explanation: r#"Warning: a variable or parameter has the name of a builtin function.
+
+```wadescript
+range: int = 10
+for i in range(range) { ... }   # range() is still the builtin
+```
+
+Calls still reach the builtin, so the code works, but it reads as if the
+variable were being called. Rename the variable."#,
+        },
+        ErrorCode {
+            code: "W0004",
+            title: "No source map",
+            patterns: &[],
+            explanation: r#"Warning: a program built with `-O1` to `-O3` has no source map, so runtime
+error traces may skip or misplace lines.
+
+```bash
+wadescript app.ws -O2    # app.wsmap is written next to app
+```
+
+The source map is read from the debug info of the linked executable. Check
+that the executable (or its `.dSYM` bundle on macOS) can be read, and that
+the linker kept its debug info. Without a map, traces fall back to that debug
+info, or to the list of called functions."#,
+        },
+    ]
+}
+
+/// Look up a code (case-insensitive, e.g. "e0102")
+pub fn lookup_error_code(code: &str) -> Option<ErrorCode> {
+    get_error_codes()
+        .into_iter()
+        .find(|entry| entry.code.eq_ignore_ascii_case(code))
+}
+
+/// Code for a type checker warning message
+pub fn classify_warning(message: &str) -> &'static str {
+    get_error_codes()
+        .into_iter()
+        .filter(|entry| entry.code.starts_with('W'))
+        .find(|entry| entry.patterns.iter().any(|pattern| message.contains(pattern)))
+        .map(|entry| entry.code)
+        .unwrap_or(UNUSED_RESULT_WARNING)
+}
+
+/// Code for a type checker error message
+pub fn classify_type_error(message: &str) -> &'static str {
+    get_error_codes()
+        .into_iter()
+        .find(|entry| entry.patterns.iter().any(|pattern| message.contains(pattern)))
+        .map(|entry| entry.code)
+        .unwrap_or(GENERIC_TYPE_ERROR)
+}
+
+/// Text printed by `wadescript explain <code>`
+pub fn explain(code: &str) -> Result<String, String> {
+    let entry = lookup_error_code(code).ok_or_else(|| {
+        format!("Unknown error code '{}' (codes look like E0102)", code)
+    })?;
+    Ok(format!("{}: {}\n\n{}\n", entry.code, entry.title, entry.explanation))
+}
+
+/// URL of the documentation anchor for a code
+pub fn error_code_url(code: &str) -> String {
+    format!("{}#{}", ERROR_CODES_URL, code.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_duplicate_codes() {
+        let mut seen = std::collections::HashSet::new();
+        for entry in get_error_codes() {
+            assert!(seen.insert(entry.code), "Duplicate error code: {}", entry.code);
+        }
+    }
+
+    #[test]
+    fn test_classify_type_errors() {
+        assert_eq!(classify_type_error("Undefined variable 'x'"), "E0100");
+        assert_eq!(classify_type_error("Type mismatch in variable 'x': expected int, got str"), "E0102");
+        assert_eq!(classify_type_error("Invalid operands for Subtract: str and int"), "E0103");
+        assert_eq!(classify_type_error("Function 'add' expects 2 arguments, got 1"), "E0104");
+        assert_eq!(classify_type_error("Argument 1 of function 'f': expected int, got str"), "E0105");
+        assert_eq!(classify_type_error("Return type mismatch: expected str, got int"), "E0106");
+        assert_eq!(classify_type_error("Unknown decorator '@cached' on function 'f'"), "E0113");
+        assert_eq!(
+            classify_type_error("Comparing int with float requires an explicit conversion (strict mode)"),
+            "E0116"
+        );
+        assert_eq!(classify_type_error("Cannot instantiate abstract class 'Shape'"), "E0123");
+        assert_eq!(
+            classify_type_error("Class 'Box' cannot be converted to a string: define 'to_string(self: Box) -> str'"),
+            "E0124"
+        );
+        assert_eq!(
+            classify_type_error("'deinit' cannot be called directly; it runs when the last reference to a 'Log' goes away"),
+            "E0125"
+        );
+        assert_eq!(classify_type_error("Method 'Log.deinit' must take only self and return nothing"), "E0125");
+        assert_eq!(
+            classify_type_error("Unsupported dict key type float: keys must be str, an integer type, bool, or a class"),
+            "E0126"
+        );
+        assert_eq!(classify_type_error("The function 'f' is defined more than once"), "E0127");
+        assert_eq!(
+            classify_type_error("Function 'area' is defined in more than one imported module; call it as a.area() or b.area()"),
+            "E0127"
+        );
+        assert_eq!(
+            classify_type_error("Function 'strlen' has the name of a runtime function, which is reserved; rename it"),
+            "E0128"
+        );
+        assert_eq!(
+            classify_type_error("Function 'range' has the name of the builtin function 'range', which can't be replaced; rename it"),
+            "E0128"
+        );
+        assert_eq!(classify_type_error("Something unexpected"), GENERIC_TYPE_ERROR);
+    }
+
+    #[test]
+    fn test_classify_warnings() {
+        assert_eq!(classify_warning("Result of 'compute' (int) is unused (strict mode)"), UNUSED_RESULT_WARNING);
+        assert_eq!(
+            classify_warning("'for i in range(xs.length)' only uses i to read xs[i]; iterate over the list directly with 'for item in xs'"),
+            "W0002"
+        );
+        assert_eq!(
+            classify_warning("Variable 'round' has the name of the builtin function 'round', which calls still reach; consider renaming it"),
+            "W0003"
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let text = explain("e0100").unwrap();
+        assert!(text.starts_with("E0100: Undefined variable"));
+        assert!(explain("E9999").unwrap_err().contains("Unknown error code"));
+    }
+
+    #[test]
+    fn test_error_code_url() {
+        assert!(error_code_url("E0102").ends_with("ERROR_CODES.md#e0102"));
+    }
+}
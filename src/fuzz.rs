@@ -0,0 +1,102 @@
+// Entry points for the cargo-fuzz targets in fuzz/. Only compiled under
+// `--cfg fuzzing`, which cargo-fuzz sets for every crate it builds, and for
+// the unit tests below.
+//
+// The lexer and parser report bad input by panicking, so a panic alone is not a
+// bug. These entry points run the front end under catch_unwind and only let
+// panics through whose message points at a broken compiler (an out-of-bounds
+// index, an unwrap on None, arithmetic overflow, ...), which is what the fuzzer
+// then reports as a crash.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::lexer::Lexer;
+use crate::output::set_parse_errors_exit;
+use crate::parser::Parser;
+use crate::typechecker::TypeChecker;
+
+/// Panic messages that mean the compiler itself went wrong rather than
+/// rejecting the input
+const BUG_PANICS: &[&str] = &[
+    "index out of bounds",
+    "called `Option::unwrap()`",
+    "called `Result::unwrap()`",
+    "attempt to ",
+    "is not a char boundary",
+    "byte index",
+    "slice index",
+    "not yet implemented",
+    "internal error: entered unreachable code",
+];
+
+/// Tokenize arbitrary bytes
+pub fn fuzz_lex(data: &[u8]) {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    run_guarded(|| {
+        Lexer::new(source.to_string()).tokenize();
+    });
+}
+
+/// Tokenize, parse and type check arbitrary bytes, the way the CLI and the
+/// language server do before any code is generated
+pub fn fuzz_frontend(data: &[u8]) {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    set_parse_errors_exit(false);
+    run_guarded(|| {
+        let tokens = Lexer::new(source.to_string()).tokenize();
+        let mut program = Parser::new_from_tokens(tokens).parse();
+        // Type errors are returned, never panicked
+        let _ = TypeChecker::new().check_program(&mut program);
+    });
+}
+
+/// Run `f`, swallowing panics that report a user error and re-raising the rest
+fn run_guarded(f: impl FnOnce()) {
+    // Keep the expected error panics out of the fuzzer's output
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    panic::set_hook(previous_hook);
+
+    if let Err(payload) = result {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        if is_bug_panic(&message) {
+            panic!("front end crashed: {}", message);
+        }
+    }
+}
+
+fn is_bug_panic(message: &str) -> bool {
+    BUG_PANICS.iter().any(|pattern| message.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncated_programs_do_not_crash() {
+        let source = include_str!("../tests/test_inheritance.ws");
+        for (end, _) in source.char_indices() {
+            fuzz_frontend(source[..end].as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_bug_panics_are_reraised() {
+        assert!(panic::catch_unwind(|| run_guarded(|| panic!("Expected class name"))).is_ok());
+        assert!(panic::catch_unwind(|| run_guarded(|| {
+            let empty: Vec<u8> = Vec::new();
+            let _ = empty[1];
+        }))
+        .is_err());
+    }
+}
@@ -0,0 +1,238 @@
+// Helpers for `--emit-llvm=<function>` and `--diff-ir`: pick one function's IR
+// out of the module, and diff an IR dump against a previous one.
+
+use inkwell::module::Module;
+use inkwell::values::AnyValue;
+
+use crate::suggest::did_you_mean;
+
+/// Lines of unchanged IR shown around each change
+const DIFF_CONTEXT: usize = 3;
+
+/// IR of a single function, looked up by its LLVM name (`ws_fib`, `main`) or by
+/// its WadeScript name (`fib`, `Dog.speak`, `math_lib.square`, or just `speak`
+/// when one class or module has it)
+pub fn function_ir(module: &Module, name: &str) -> Result<String, String> {
+    let defined: Vec<String> = module
+        .get_functions()
+        .filter(|function| function.count_basic_blocks() > 0)
+        .map(|function| function.get_name().to_string_lossy().into_owned())
+        .collect();
+    let methods: Vec<&String> = defined
+        .iter()
+        .filter(|symbol| symbol.starts_with("ws_") && symbol.ends_with(&format!(".{}", name)))
+        .collect();
+
+    let function = module
+        .get_function(name)
+        .or_else(|| module.get_function(&format!("ws_{}", name)))
+        .filter(|function| function.count_basic_blocks() > 0)
+        .or_else(|| match methods.as_slice() {
+            [method] => module.get_function(method),
+            _ => None,
+        });
+    match function {
+        Some(function) => Ok(function.print_to_string().to_string()),
+        None if methods.len() > 1 => {
+            let qualified: Vec<&str> = methods.iter().map(|symbol| &symbol["ws_".len()..]).collect();
+            Err(format!(
+                "Several classes or modules define '{}'; name one of: {}",
+                name,
+                qualified.join(", ")
+            ))
+        }
+        None => {
+            let candidates = defined
+                .iter()
+                .map(|name| name.strip_prefix("ws_").unwrap_or(name));
+            Err(format!(
+                "No function '{}' is defined in the module{}",
+                name,
+                did_you_mean(name, candidates)
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Unified diff of two IR dumps, or None when they are identical
+pub fn diff_ir(old_label: &str, old: &str, new_label: &str, new: &str) -> Option<String> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let edits = line_edits(&old_lines, &new_lines);
+    if edits.iter().all(|edit| *edit == Edit::Keep) {
+        return None;
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    // Position in old and new before each edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for edit in &edits {
+        positions.push((old_pos, new_pos));
+        match edit {
+            Edit::Keep => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Edit::Remove => old_pos += 1,
+            Edit::Add => new_pos += 1,
+        }
+    }
+    positions.push((old_pos, new_pos));
+
+    let mut start = 0;
+    while let Some(first_change) = edits[start..].iter().position(|edit| *edit != Edit::Keep) {
+        let first_change = start + first_change;
+        // Extend the hunk while the next change is within two contexts of the last one
+        let mut end = first_change;
+        let mut last_change = first_change;
+        while end < edits.len() {
+            if edits[end] != Edit::Keep {
+                last_change = end;
+            } else if end - last_change > 2 * DIFF_CONTEXT {
+                break;
+            }
+            end += 1;
+        }
+        let hunk_start = first_change.saturating_sub(DIFF_CONTEXT).max(start);
+        let hunk_end = (last_change + 1 + DIFF_CONTEXT).min(edits.len());
+
+        let (old_start, new_start) = positions[hunk_start];
+        let (old_end, new_end) = positions[hunk_end];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + 1,
+            old_end - old_start,
+            new_start + 1,
+            new_end - new_start
+        ));
+        for (edit, &(old_pos, new_pos)) in edits[hunk_start..hunk_end].iter().zip(&positions[hunk_start..]) {
+            match edit {
+                Edit::Keep => out.push_str(&format!(" {}\n", old_lines[old_pos])),
+                Edit::Remove => out.push_str(&format!("-{}\n", old_lines[old_pos])),
+                Edit::Add => out.push_str(&format!("+{}\n", new_lines[new_pos])),
+            }
+        }
+        start = hunk_end;
+    }
+    Some(out)
+}
+
+/// Shortest edit script turning `old` into `new`, from their longest common
+/// subsequence. The unchanged prefix and suffix are skipped so the quadratic
+/// table only covers the region that differs.
+fn line_edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j]: length of the common subsequence of old_mid[i..] and new_mid[j..]
+    let width = new_mid.len() + 1;
+    let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut edits = vec![Edit::Keep; prefix];
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            edits.push(Edit::Keep);
+            i += 1;
+            j += 1;
+        } else if j == new_mid.len() || (i < old_mid.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+            edits.push(Edit::Remove);
+            i += 1;
+        } else {
+            edits.push(Edit::Add);
+            j += 1;
+        }
+    }
+    edits.resize(edits.len() + suffix, Edit::Keep);
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use inkwell::context::Context;
+
+    #[test]
+    fn test_diff_ir_identical() {
+        let ir = "define i64 @ws_f() {\nentry:\n  ret i64 1\n}\n";
+        assert_eq!(diff_ir("old.ll", ir, "new", ir), None);
+    }
+
+    #[test]
+    fn test_diff_ir_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let new = "a\nb\nC\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\n";
+        let diff = diff_ir("old.ll", old, "new", new).unwrap();
+        assert_eq!(
+            diff,
+            "--- old.ll\n+++ new\n@@ -1,6 +1,6 @@\n a\n b\n-c\n+C\n d\n e\n f\n@@ -11,3 +11,4 @@\n k\n l\n m\n+n\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_ir_nearby_changes_share_a_hunk() {
+        let old = "a\nb\nc\nd\ne\n";
+        let new = "a\nB\nc\nD\ne\n";
+        let diff = diff_ir("old.ll", old, "new", new).unwrap();
+        assert_eq!(diff.matches("@@ -").count(), 1, "{}", diff);
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n-d\n+D\n e\n"), "{}", diff);
+    }
+
+    #[test]
+    fn test_function_ir_lookup() {
+        let context = Context::create();
+        let module = context.create_module("test");
+        let fn_type = context.i64_type().fn_type(&[], false);
+        let function = module.add_function("ws_answer", fn_type, None);
+        let builder = context.create_builder();
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+        builder.build_return(Some(&context.i64_type().const_int(42, false))).unwrap();
+        module.add_function("printf", fn_type, None);
+
+        let ir = function_ir(&module, "answer").unwrap();
+        assert!(ir.contains("define i64 @ws_answer()"), "{}", ir);
+        assert!(!ir.contains("printf"), "{}", ir);
+        assert_eq!(function_ir(&module, "ws_answer").unwrap(), ir);
+
+        // Declarations have no IR worth showing
+        let err = function_ir(&module, "printf").unwrap_err();
+        assert!(err.contains("No function 'printf'"), "{}", err);
+        let err = function_ir(&module, "answr").unwrap_err();
+        assert!(err.contains("did you mean 'answer'?"), "{}", err);
+
+        // Methods are found by their class-qualified name, or by their own when unambiguous
+        for symbol in ["ws_Dog.speak", "ws_Dog.fetch", "ws_Cat.speak"] {
+            let method = module.add_function(symbol, fn_type, None);
+            builder.position_at_end(context.append_basic_block(method, "entry"));
+            builder.build_return(Some(&context.i64_type().const_int(0, false))).unwrap();
+        }
+        assert!(function_ir(&module, "fetch").unwrap().contains("@ws_Dog.fetch()"));
+        assert!(function_ir(&module, "Cat.speak").unwrap().contains("@ws_Cat.speak()"));
+        let err = function_ir(&module, "speak").unwrap_err();
+        assert!(err.contains("name one of: Dog.speak, Cat.speak"), "{}", err);
+    }
+}
@@ -3,7 +3,7 @@
 //! Provides LLVM JIT compilation support using inkwell's ExecutionEngine.
 
 use inkwell::context::Context;
-use inkwell::execution_engine::{ExecutionEngine, JitFunction};
+use inkwell::execution_engine::{ExecutionEngine, JitFunction, UnsafeFunctionPointer};
 use inkwell::module::Module;
 use inkwell::targets::{InitializationConfig, Target};
 use inkwell::OptimizationLevel;
@@ -75,6 +75,12 @@ impl<'ctx> JitEngine<'ctx> {
             .map_err(|e| format!("Failed to get function '{}': {:?}", name, e))
     }
 
+    /// Get a JIT-compiled function of any signature by its symbol name
+    pub unsafe fn get_function<F: UnsafeFunctionPointer>(&self, name: &str) -> Result<JitFunction<'ctx, F>, String> {
+        self.execution_engine.get_function::<F>(name)
+            .map_err(|e| format!("Failed to get function '{}': {:?}", name, e))
+    }
+
     /// Generate a unique name for a REPL entry function
     pub fn next_entry_name(&mut self) -> String {
         self.input_counter += 1;
@@ -104,6 +110,9 @@ impl<'ctx> JitEngine<'ctx> {
 /// Type alias for REPL entry functions (no args, returns i64)
 pub type ReplEntryFn = unsafe extern "C" fn() -> i64;
 
+/// Type alias for the dev server's request handler (method, path, body) -> response body
+pub type HandlerFn = unsafe extern "C" fn(*const u8, *const u8, *const u8) -> *const u8;
+
 /// Type alias for REPL expression functions that return int (reserved for future use)
 #[allow(dead_code)]
 pub type ReplIntFn = unsafe extern "C" fn() -> i64;
@@ -27,9 +27,11 @@ pub fn get_keywords() -> Vec<&'static str> {
         "if", "elif", "else", "while", "for", "in",
         "break", "continue", "pass", "return",
         // Functions and classes
-        "def", "class",
+        "def", "class", "super",
+        // Declarations
+        "let",
         // Exception handling
-        "try", "except", "finally", "raise", "as",
+        "try", "except", "finally", "raise", "from", "as",
         // Imports
         "import",
         // Testing
@@ -45,8 +47,8 @@ pub fn get_keywords() -> Vec<&'static str> {
 /// These must match the type tokens in lexer.rs
 pub fn get_type_keywords() -> Vec<&'static str> {
     vec![
-        "int", "float", "str", "bool", "void",
-        "list", "dict", "array", "Optional",
+        "int", "i32", "i16", "u8", "float", "bigint", "decimal", "str", "bool", "void",
+        "list", "dict", "array", "Optional", "Result",
     ]
 }
 
@@ -75,17 +77,169 @@ pub fn get_builtin_functions() -> Vec<BuiltinFunction> {
             signature: "(value: bool) -> void",
             description: "Print a boolean to stdout",
         },
+        BuiltinFunction {
+            name: "print",
+            signature: "(value: int | float | bool | str) -> void",
+            description: "Print any scalar to stdout; floats use the shortest exact form",
+        },
         // Utility functions
         BuiltinFunction {
             name: "range",
             signature: "(n: int) -> list[int]",
             description: "Return a list of integers from 0 to n-1",
         },
+        BuiltinFunction {
+            name: "checked_add",
+            signature: "(a: int, b: int) -> int?",
+            description: "a + b, or None if it overflows",
+        },
+        BuiltinFunction {
+            name: "checked_mul",
+            signature: "(a: int, b: int) -> int?",
+            description: "a * b, or None if it overflows",
+        },
+        // Result constructors
+        BuiltinFunction {
+            name: "Ok",
+            signature: "(value: T) -> Result[T, E]",
+            description: "Wrap a success value in a Result",
+        },
+        BuiltinFunction {
+            name: "Err",
+            signature: "(error: E) -> Result[T, E]",
+            description: "Wrap an error in a Result",
+        },
+        // Float functions
+        BuiltinFunction {
+            name: "round",
+            signature: "(x: float, digits: int) -> float",
+            description: "Round to a number of decimal places (halves round to even)",
+        },
+        BuiltinFunction {
+            name: "floor",
+            signature: "(x: float) -> float",
+            description: "Round down to the nearest whole number",
+        },
+        BuiltinFunction {
+            name: "ceil",
+            signature: "(x: float) -> float",
+            description: "Round up to the nearest whole number",
+        },
+        BuiltinFunction {
+            name: "trunc",
+            signature: "(x: float) -> float",
+            description: "Round toward zero, dropping the fractional part",
+        },
+        BuiltinFunction {
+            name: "is_nan",
+            signature: "(x: float) -> bool",
+            description: "True if x is NaN (not a number)",
+        },
+        BuiltinFunction {
+            name: "is_finite",
+            signature: "(x: float) -> bool",
+            description: "True unless x is NaN or infinite",
+        },
+        BuiltinFunction {
+            name: "parse_bool",
+            signature: "(s: str) -> bool?",
+            description: "Parse true/false, yes/no, on/off or 1/0; None if s is not a boolean",
+        },
+        BuiltinFunction {
+            name: "int",
+            signature: "(s: str) -> int",
+            description: "Parse an int; raises ValueError if s is not a number",
+        },
+        BuiltinFunction {
+            name: "float",
+            signature: "(s: str) -> float",
+            description: "Parse a float; raises ValueError if s is not a number",
+        },
+        BuiltinFunction {
+            name: "str",
+            signature: "(x: int | float | bool | str | object) -> str",
+            description: "The text of a value, as an f-string would show it",
+        },
+        BuiltinFunction {
+            name: "format",
+            signature: "(template: str, ...values) -> str",
+            description: "The template with each {} (or {n}) replaced by the text of a value",
+        },
+        BuiltinFunction {
+            name: "input",
+            signature: "(prompt: str = \"\") -> str",
+            description: "Print the prompt and read a line from standard input; EOFError at the end",
+        },
+        BuiltinFunction {
+            name: "read_line",
+            signature: "() -> str?",
+            description: "The next line of standard input, or None at the end",
+        },
+        BuiltinFunction {
+            name: "truthy",
+            signature: "(x: int | float | bool | str | list | dict | T?) -> bool",
+            description: "False for zero, empty str/list/dict and None; True otherwise",
+        },
+        BuiltinFunction {
+            name: "float_to_bits",
+            signature: "(x: float) -> int",
+            description: "The IEEE 754 bits of a float, as an int",
+        },
+        BuiltinFunction {
+            name: "bits_to_float",
+            signature: "(bits: int) -> float",
+            description: "The float with the given IEEE 754 bits",
+        },
+        BuiltinFunction {
+            name: "byte_swap",
+            signature: "(x: int) -> int",
+            description: "Reverse the 8 bytes of an int",
+        },
+        BuiltinFunction {
+            name: "to_bytes_be",
+            signature: "(value: int, size: int) -> list[u8]",
+            description: "value as size bytes, most significant first (network order)",
+        },
+        BuiltinFunction {
+            name: "to_bytes_le",
+            signature: "(value: int, size: int) -> list[u8]",
+            description: "value as size bytes, least significant first",
+        },
+        BuiltinFunction {
+            name: "from_bytes_be",
+            signature: "(bytes: list[u8]) -> int",
+            description: "Unsigned int from up to 8 big-endian bytes",
+        },
+        BuiltinFunction {
+            name: "from_bytes_le",
+            signature: "(bytes: list[u8]) -> int",
+            description: "Unsigned int from up to 8 little-endian bytes",
+        },
+        BuiltinFunction {
+            name: "parse_bigint",
+            signature: "(s: str) -> bigint",
+            description: "Parse a decimal string into a bigint; raises ValueError if invalid",
+        },
+        BuiltinFunction {
+            name: "pow_mod",
+            signature: "(base: bigint, exp: bigint, m: bigint) -> bigint",
+            description: "(base ** exp) % m computed without the full power",
+        },
+        BuiltinFunction {
+            name: "parse_decimal",
+            signature: "(s: str) -> decimal",
+            description: "Parse a decimal string like \"19.99\"; raises ValueError if invalid",
+        },
+        BuiltinFunction {
+            name: "format_float",
+            signature: "(x: float, digits: int) -> str",
+            description: "Format with exactly `digits` digits after the decimal point",
+        },
         // File I/O functions
         BuiltinFunction {
             name: "file_open",
             signature: "(path: str, mode: str) -> int",
-            description: "Open a file and return a handle. Mode: \"r\", \"w\", \"a\"",
+            description: "Open a file and return a handle. Mode: \"r\", \"w\", \"a\", optionally followed by \"b\"",
         },
         BuiltinFunction {
             name: "file_read",
@@ -112,6 +266,31 @@ pub fn get_builtin_functions() -> Vec<BuiltinFunction> {
             signature: "(path: str) -> int",
             description: "Check if a file exists (returns 1 or 0)",
         },
+        BuiltinFunction {
+            name: "file_seek",
+            signature: "(handle: int, offset: int, whence: int) -> int",
+            description: "Move within a file from the start (0), current position (1) or end (2); returns the new position",
+        },
+        BuiltinFunction {
+            name: "file_read_bytes",
+            signature: "(handle: int, n: int) -> list[u8]",
+            description: "Read up to n bytes from a file",
+        },
+        BuiltinFunction {
+            name: "file_read_lines",
+            signature: "(handle: int) -> list[str]",
+            description: "Read the remaining lines of a file",
+        },
+        BuiltinFunction {
+            name: "file_write_bytes",
+            signature: "(handle: int, data: list[u8]) -> void",
+            description: "Write bytes to a file",
+        },
+        BuiltinFunction {
+            name: "file_flush",
+            signature: "(handle: int) -> void",
+            description: "Make sure everything written to a file is on disk",
+        },
         // CLI functions
         BuiltinFunction {
             name: "cli_get_argc",
@@ -214,6 +393,348 @@ pub fn get_builtin_functions() -> Vec<BuiltinFunction> {
             signature: "(handle: int) -> void",
             description: "Free an HTTP response handle",
         },
+        // SMTP functions
+        BuiltinFunction {
+            name: "smtp_send",
+            signature: "(host: str, port: int, security: str, username: str, password: str, sender: str, to: str, subject: str, body: str) -> str",
+            description: "Send a plain-text email, returns the error or an empty string",
+        },
+        // Compression functions
+        BuiltinFunction {
+            name: "compress_gzip",
+            signature: "(data: list[u8]) -> list[u8]",
+            description: "Compress bytes to the gzip format",
+        },
+        BuiltinFunction {
+            name: "compress_gunzip",
+            signature: "(data: list[u8]) -> list[u8]",
+            description: "Decompress gzip data",
+        },
+        BuiltinFunction {
+            name: "compress_zlib",
+            signature: "(data: list[u8]) -> list[u8]",
+            description: "Compress bytes to the zlib format",
+        },
+        BuiltinFunction {
+            name: "compress_unzlib",
+            signature: "(data: list[u8]) -> list[u8]",
+            description: "Decompress zlib data",
+        },
+        BuiltinFunction {
+            name: "compress_read_gzip",
+            signature: "(path: str) -> str",
+            description: "Read a gzip-compressed text file",
+        },
+        BuiltinFunction {
+            name: "compress_write_gzip",
+            signature: "(path: str, content: str) -> void",
+            description: "Write text to a gzip-compressed file",
+        },
+        // Random identifier and number functions
+        BuiltinFunction {
+            name: "random_uuid4",
+            signature: "() -> str",
+            description: "Generate a random version 4 UUID",
+        },
+        BuiltinFunction {
+            name: "random_hex_string",
+            signature: "(length: int) -> str",
+            description: "Generate a string of random hex digits",
+        },
+        BuiltinFunction {
+            name: "random_token_string",
+            signature: "(length: int) -> str",
+            description: "Generate a string of random URL-safe characters",
+        },
+        BuiltinFunction {
+            name: "random_seed",
+            signature: "(seed: int) -> void",
+            description: "Restart the number generator from a seed",
+        },
+        BuiltinFunction {
+            name: "random_int_between",
+            signature: "(lo: int, hi: int) -> int",
+            description: "A random int from lo to hi, both included",
+        },
+        BuiltinFunction {
+            name: "random_unit_float",
+            signature: "() -> float",
+            description: "A random float in [0.0, 1.0)",
+        },
+        // File system functions
+        BuiltinFunction {
+            name: "fs_glob",
+            signature: "(pattern: str) -> list[str]",
+            description: "List the paths matching a glob pattern, sorted",
+        },
+        BuiltinFunction {
+            name: "fs_list_dir",
+            signature: "(path: str) -> list[str]",
+            description: "Names of the entries in a directory, sorted",
+        },
+        BuiltinFunction {
+            name: "fs_mkdir",
+            signature: "(path: str) -> void",
+            description: "Create a directory whose parent exists",
+        },
+        BuiltinFunction {
+            name: "fs_mkdirs",
+            signature: "(path: str) -> void",
+            description: "Create a directory and any missing parents",
+        },
+        BuiltinFunction {
+            name: "fs_remove_file",
+            signature: "(path: str) -> void",
+            description: "Delete a file",
+        },
+        BuiltinFunction {
+            name: "fs_remove_dir",
+            signature: "(path: str, recursive: int) -> void",
+            description: "Delete an empty directory, or with recursive 1 everything in it",
+        },
+        BuiltinFunction {
+            name: "fs_rename",
+            signature: "(source: str, destination: str) -> void",
+            description: "Move or rename a file or directory",
+        },
+        BuiltinFunction {
+            name: "fs_copy",
+            signature: "(source: str, destination: str) -> void",
+            description: "Copy a file",
+        },
+        BuiltinFunction {
+            name: "fs_file_size",
+            signature: "(path: str) -> int",
+            description: "Size of a file in bytes",
+        },
+        BuiltinFunction {
+            name: "fs_is_dir",
+            signature: "(path: str) -> int",
+            description: "Check if a path is a directory (returns 1 or 0)",
+        },
+        BuiltinFunction {
+            name: "fs_is_file",
+            signature: "(path: str) -> int",
+            description: "Check if a path is a regular file (returns 1 or 0)",
+        },
+        // Regular expression functions
+        BuiltinFunction {
+            name: "regex_compile",
+            signature: "(pattern: str) -> int",
+            description: "Compile a regular expression and return its handle",
+        },
+        BuiltinFunction {
+            name: "regex_is_match",
+            signature: "(handle: int, text: str) -> int",
+            description: "1 if the pattern matches anywhere in text, 0 if not",
+        },
+        BuiltinFunction {
+            name: "regex_find",
+            signature: "(handle: int, text: str) -> str?",
+            description: "The first match, or None",
+        },
+        BuiltinFunction {
+            name: "regex_find_all",
+            signature: "(handle: int, text: str) -> list[str]",
+            description: "Every non-overlapping match, in order",
+        },
+        BuiltinFunction {
+            name: "regex_captures",
+            signature: "(handle: int, text: str) -> list[str]",
+            description: "The whole first match and its groups, or an empty list",
+        },
+        BuiltinFunction {
+            name: "regex_replace",
+            signature: "(handle: int, text: str, replacement: str) -> str",
+            description: "Replace every match; $1 or ${name} insert a group",
+        },
+        BuiltinFunction {
+            name: "regex_split",
+            signature: "(handle: int, text: str) -> list[str]",
+            description: "The pieces of text between matches",
+        },
+        BuiltinFunction {
+            name: "regex_free",
+            signature: "(handle: int) -> void",
+            description: "Free a compiled regular expression",
+        },
+        // Clock and date functions
+        BuiltinFunction {
+            name: "time_now",
+            signature: "() -> int",
+            description: "Seconds since 1970-01-01 00:00:00 UTC",
+        },
+        BuiltinFunction {
+            name: "time_now_ms",
+            signature: "() -> int",
+            description: "Milliseconds since 1970-01-01 00:00:00 UTC",
+        },
+        BuiltinFunction {
+            name: "time_monotonic",
+            signature: "() -> float",
+            description: "Seconds on a clock that never goes backwards",
+        },
+        BuiltinFunction {
+            name: "time_sleep_ms",
+            signature: "(ms: int) -> void",
+            description: "Pause for ms milliseconds",
+        },
+        BuiltinFunction {
+            name: "time_format",
+            signature: "(seconds: int, format: str) -> str",
+            description: "Format a UTC timestamp with %Y, %m, %d, %H, %M, %S, ...",
+        },
+        BuiltinFunction {
+            name: "time_parse",
+            signature: "(text: str, format: str) -> int",
+            description: "Parse a date laid out as format into a UTC timestamp",
+        },
+        // Environment variable functions
+        BuiltinFunction {
+            name: "env_get",
+            signature: "(name: str) -> str?",
+            description: "The value of an environment variable, or None if it isn't set",
+        },
+        BuiltinFunction {
+            name: "env_set",
+            signature: "(name: str, value: str) -> void",
+            description: "Set an environment variable for this process and its children",
+        },
+        BuiltinFunction {
+            name: "env_unset",
+            signature: "(name: str) -> void",
+            description: "Remove an environment variable",
+        },
+        // Subprocess functions
+        BuiltinFunction {
+            name: "process_run",
+            signature: "(program: str, args: list[str], stdin: str) -> int",
+            description: "Run a program to completion and return a handle to its output",
+        },
+        BuiltinFunction {
+            name: "process_exit_code",
+            signature: "(handle: int) -> int",
+            description: "The exit code of a finished process, or -1 if a signal killed it",
+        },
+        BuiltinFunction {
+            name: "process_stdout",
+            signature: "(handle: int) -> str",
+            description: "What a finished process wrote to standard output",
+        },
+        BuiltinFunction {
+            name: "process_stderr",
+            signature: "(handle: int) -> str",
+            description: "What a finished process wrote to standard error",
+        },
+        BuiltinFunction {
+            name: "process_free",
+            signature: "(handle: int) -> void",
+            description: "Free a finished process's output",
+        },
+        // Path functions
+        BuiltinFunction {
+            name: "path_join",
+            signature: "(head: str, tail: str) -> str",
+            description: "Join two paths with one \"/\"; an absolute tail replaces head",
+        },
+        BuiltinFunction {
+            name: "path_dirname",
+            signature: "(path: str) -> str",
+            description: "Everything before the last path component",
+        },
+        BuiltinFunction {
+            name: "path_basename",
+            signature: "(path: str) -> str",
+            description: "The last path component",
+        },
+        BuiltinFunction {
+            name: "path_extension",
+            signature: "(path: str) -> str",
+            description: "The extension of the last component, with its \".\", or \"\"",
+        },
+        BuiltinFunction {
+            name: "path_normalize",
+            signature: "(path: str) -> str",
+            description: "Remove \".\", repeated slashes and resolvable \"..\" components",
+        },
+        BuiltinFunction {
+            name: "path_absolute",
+            signature: "(path: str) -> str",
+            description: "A normalized absolute path, relative to the current directory",
+        },
+        // CSV functions
+        BuiltinFunction {
+            name: "csv_parse",
+            signature: "(text: str, delimiter: str) -> list[list[str]]",
+            description: "Parse CSV text into rows of fields",
+        },
+        BuiltinFunction {
+            name: "csv_format_row",
+            signature: "(fields: list[str], delimiter: str) -> str",
+            description: "One row as a CSV line, quoting fields that need it",
+        },
+        // Config file functions
+        BuiltinFunction {
+            name: "config_parse_toml",
+            signature: "(text: str) -> dict[str, dict[str, str]]",
+            description: "Parse TOML into sections of string values",
+        },
+        BuiltinFunction {
+            name: "config_parse_ini",
+            signature: "(text: str) -> dict[str, dict[str, str]]",
+            description: "Parse INI into sections of string values",
+        },
+        BuiltinFunction {
+            name: "config_list_items",
+            signature: "(value: str) -> list[str]",
+            description: "The items of a TOML array value",
+        },
+        // TCP socket functions
+        BuiltinFunction {
+            name: "tcp_connect",
+            signature: "(host: str, port: int) -> int",
+            description: "Connect to a TCP server, returns a connection handle",
+        },
+        BuiltinFunction {
+            name: "tcp_listen",
+            signature: "(host: str, port: int) -> int",
+            description: "Listen for TCP connections, returns a listener handle",
+        },
+        BuiltinFunction {
+            name: "tcp_accept",
+            signature: "(listener: int) -> int",
+            description: "Wait for the next client, returns a connection handle",
+        },
+        BuiltinFunction {
+            name: "tcp_read",
+            signature: "(conn: int, max_bytes: int) -> str",
+            description: "Up to max_bytes of received data, or \"\" once the connection is closed",
+        },
+        BuiltinFunction {
+            name: "tcp_read_line",
+            signature: "(conn: int) -> str?",
+            description: "The next received line, or None once the connection is closed",
+        },
+        BuiltinFunction {
+            name: "tcp_write",
+            signature: "(conn: int, data: str) -> void",
+            description: "Send all of data",
+        },
+        BuiltinFunction {
+            name: "tcp_set_timeout",
+            signature: "(conn: int, ms: int) -> void",
+            description: "Raise OSError when a read or write waits longer than ms (0 = forever)",
+        },
+        BuiltinFunction {
+            name: "tcp_local_port",
+            signature: "(handle: int) -> int",
+            description: "The local port of a listener or connection",
+        },
+        BuiltinFunction {
+            name: "tcp_close",
+            signature: "(handle: int) -> void",
+            description: "Close a listener or connection",
+        },
     ]
 }
 
@@ -223,18 +744,54 @@ pub fn get_list_methods() -> Vec<(&'static str, &'static str, &'static str)> {
         ("push", "(item: T) -> void", "Add an item to the end of the list"),
         ("pop", "() -> T", "Remove and return the last item"),
         ("get", "(index: int) -> T", "Get item at index"),
+        ("insert", "(index: int, item: T) -> void", "Insert an item before index, shifting the rest up"),
+        ("remove_at", "(index: int) -> T", "Remove and return the item at index"),
+        ("index_of", "(item: T) -> int", "Index of the first equal item, or -1"),
+        ("contains", "(item: T) -> bool", "Whether the list has an equal item"),
+        ("clear", "() -> void", "Remove every item"),
+        ("sort", "() -> void", "Sort int, float, str or bool items in place, ascending"),
+        ("sort_by", "(before: (T, T) -> bool) -> void", "Sort in place, stable; before(a, b) is True when a goes first"),
+        ("reverse", "() -> void", "Reverse the items in place"),
+        ("shuffle", "() -> void", "Put the items in a random order, in place (see the random module's seed)"),
+        ("extend", "(other: list[T]) -> void", "Append every item of another list"),
+        ("map", "(f: (T) -> U) -> list[U]", "A new list of f(item) for each item; f names a function"),
+        ("filter", "(f: (T) -> bool) -> list[T]", "A new list of the items f returns True for"),
+        ("reduce", "(f: (U, T) -> U, init: U) -> U", "Fold the items into one value, starting from init"),
         ("length", "int", "Number of items in the list (property)"),
     ]
 }
 
+/// Get dict method signatures for LSP
+pub fn get_dict_methods() -> Vec<(&'static str, &'static str, &'static str)> {
+    vec![
+        ("keys", "() -> list[K]", "A new list of the keys, in the same order as values()"),
+        ("values", "() -> list[V]", "A new list of the values, in the same order as keys()"),
+        ("items", "() -> list[(K, V)]", "A new list of (key, value) tuples, in the same order as keys()"),
+        ("get", "(key: K, default: V) -> V", "The value for a key, or default if missing"),
+        ("remove", "(key: K) -> bool", "Remove a key; returns whether it was there"),
+        ("pop", "(key: K, default: V) -> V", "Remove a key and return its value, or default if missing"),
+        ("clear", "() -> void", "Remove every entry"),
+    ]
+}
+
 /// Get string method signatures for LSP
 pub fn get_string_methods() -> Vec<(&'static str, &'static str, &'static str)> {
     vec![
         ("upper", "() -> str", "Convert to uppercase"),
         ("lower", "() -> str", "Convert to lowercase"),
+        ("strip", "() -> str", "Remove leading and trailing whitespace"),
+        ("lstrip", "() -> str", "Remove leading whitespace"),
+        ("rstrip", "() -> str", "Remove trailing whitespace"),
         ("contains", "(substr: str) -> bool", "Check if contains substring"),
+        ("find", "(substr: str) -> int", "Index of the first occurrence of substr, or -1"),
+        ("startswith", "(prefix: str) -> bool", "Check if the string starts with prefix"),
+        ("endswith", "(suffix: str) -> bool", "Check if the string ends with suffix"),
+        ("replace", "(old: str, new: str) -> str", "Replace every occurrence of old with new"),
         ("split", "(delimiter: str) -> list[str]", "Split string by delimiter"),
-        ("length", "int", "Length of the string (property)"),
+        ("join", "(parts: list[str]) -> str", "Join the parts with this string between them"),
+        ("chars", "() -> list[str]", "The characters of the string, one string each"),
+        ("length", "int", "Number of characters in the string (property)"),
+        ("byte_length", "int", "Number of bytes in the string's UTF-8 encoding (property)"),
     ]
 }
 
@@ -272,7 +829,7 @@ pub fn get_stdlib_modules() -> Vec<StdLibModule> {
                 StdLibFunction {
                     name: "open",
                     signature: "(path: str, mode: str) -> int",
-                    description: "Open a file. Mode: \"r\", \"w\", \"a\". Returns file handle.",
+                    description: "Open a file. Mode: \"r\", \"w\", \"a\", or \"rb\", \"wb\", \"ab\". Returns file handle.",
                 },
                 StdLibFunction {
                     name: "read",
@@ -284,11 +841,41 @@ pub fn get_stdlib_modules() -> Vec<StdLibModule> {
                     signature: "(handle: int) -> str",
                     description: "Read a single line from file",
                 },
+                StdLibFunction {
+                    name: "lines",
+                    signature: "(handle: int) -> list[str]",
+                    description: "Read the remaining lines of a file, for `for line in io.lines(handle)`",
+                },
+                StdLibFunction {
+                    name: "read_bytes",
+                    signature: "(handle: int, n: int) -> list[u8]",
+                    description: "Read up to n bytes; an empty list at end of file",
+                },
                 StdLibFunction {
                     name: "write",
                     signature: "(handle: int, content: str) -> void",
                     description: "Write a string to file",
                 },
+                StdLibFunction {
+                    name: "write_bytes",
+                    signature: "(handle: int, data: list[u8]) -> void",
+                    description: "Write bytes to file",
+                },
+                StdLibFunction {
+                    name: "seek",
+                    signature: "(handle: int, offset: int, whence: int = 0) -> int",
+                    description: "Move to offset from the start (0), current position (1) or end (2); returns the new position",
+                },
+                StdLibFunction {
+                    name: "tell",
+                    signature: "(handle: int) -> int",
+                    description: "The current position in the file",
+                },
+                StdLibFunction {
+                    name: "flush",
+                    signature: "(handle: int) -> void",
+                    description: "Make sure everything written is stored on disk",
+                },
                 StdLibFunction {
                     name: "close",
                     signature: "(handle: int) -> void",
@@ -393,12 +980,504 @@ pub fn get_stdlib_modules() -> Vec<StdLibModule> {
                 },
             ],
         },
+        // smtp module
+        StdLibModule {
+            name: "smtp",
+            description: "SMTP client for sending plain-text email",
+            functions: vec![StdLibFunction {
+                name: "send",
+                signature: "(server: SmtpServer, sender: str, to: str, subject: str, body: str) -> Result[bool, str]",
+                description: "Send a plain-text email to one or more comma-separated addresses",
+            }],
+            classes: vec![StdLibClass {
+                name: "SmtpServer",
+                fields: vec![
+                    ("host", "str"),
+                    ("port", "int"),
+                    ("security", "str"),
+                    ("username", "str"),
+                    ("password", "str"),
+                ],
+                description: "SMTP server address, security (\"starttls\", \"tls\" or \"none\") and login",
+            }],
+        },
+        // compress module
+        StdLibModule {
+            name: "compress",
+            description: "gzip and zlib compression",
+            functions: vec![
+                StdLibFunction {
+                    name: "gzip",
+                    signature: "(data: list[u8]) -> list[u8]",
+                    description: "Compress bytes to the gzip format",
+                },
+                StdLibFunction {
+                    name: "gunzip",
+                    signature: "(data: list[u8]) -> list[u8]",
+                    description: "Decompress gzip data, raising ValueError if it is invalid",
+                },
+                StdLibFunction {
+                    name: "zlib",
+                    signature: "(data: list[u8]) -> list[u8]",
+                    description: "Compress bytes to the zlib format",
+                },
+                StdLibFunction {
+                    name: "unzlib",
+                    signature: "(data: list[u8]) -> list[u8]",
+                    description: "Decompress zlib data, raising ValueError if it is invalid",
+                },
+                StdLibFunction {
+                    name: "read_gzip",
+                    signature: "(path: str) -> str",
+                    description: "Read a gzip-compressed text file",
+                },
+                StdLibFunction {
+                    name: "write_gzip",
+                    signature: "(path: str, content: str) -> void",
+                    description: "Write text to a gzip-compressed file",
+                },
+            ],
+            classes: vec![],
+        },
+        // random module
+        StdLibModule {
+            name: "random",
+            description: "UUIDs, random tokens and seedable random numbers",
+            functions: vec![
+                StdLibFunction {
+                    name: "uuid4",
+                    signature: "() -> str",
+                    description: "Generate a random version 4 UUID",
+                },
+                StdLibFunction {
+                    name: "random_hex",
+                    signature: "(length: int) -> str",
+                    description: "Generate length random lowercase hex digits",
+                },
+                StdLibFunction {
+                    name: "random_token",
+                    signature: "(length: int) -> str",
+                    description: "Generate length random URL-safe characters",
+                },
+                StdLibFunction {
+                    name: "random_int",
+                    signature: "(lo: int, hi: int) -> int",
+                    description: "A random int from lo to hi, both included",
+                },
+                StdLibFunction {
+                    name: "random_float",
+                    signature: "() -> float",
+                    description: "A random float in [0.0, 1.0)",
+                },
+                StdLibFunction {
+                    name: "seed",
+                    signature: "(n: int) -> void",
+                    description: "Make random_int, random_float and shuffle() repeat the same sequence",
+                },
+            ],
+            classes: vec![],
+        },
+        // fs module
+        StdLibModule {
+            name: "fs",
+            description: "File system helpers",
+            functions: vec![
+                StdLibFunction {
+                    name: "glob",
+                    signature: "(pattern: str) -> list[str]",
+                    description: "List the paths matching a pattern like \"src/**/*.ws\", sorted",
+                },
+                StdLibFunction {
+                    name: "list_dir",
+                    signature: "(path: str) -> list[str]",
+                    description: "Names of the entries in a directory, sorted",
+                },
+                StdLibFunction {
+                    name: "mkdir",
+                    signature: "(path: str) -> void",
+                    description: "Create a directory; its parent must exist",
+                },
+                StdLibFunction {
+                    name: "mkdirs",
+                    signature: "(path: str) -> void",
+                    description: "Create a directory and any missing parents",
+                },
+                StdLibFunction {
+                    name: "remove_file",
+                    signature: "(path: str) -> void",
+                    description: "Delete a file",
+                },
+                StdLibFunction {
+                    name: "remove_dir",
+                    signature: "(path: str, recursive: bool = False) -> void",
+                    description: "Delete an empty directory, or everything in it with recursive",
+                },
+                StdLibFunction {
+                    name: "rename",
+                    signature: "(source: str, destination: str) -> void",
+                    description: "Move or rename a file or directory",
+                },
+                StdLibFunction {
+                    name: "copy",
+                    signature: "(source: str, destination: str) -> void",
+                    description: "Copy a file, replacing the destination",
+                },
+                StdLibFunction {
+                    name: "file_size",
+                    signature: "(path: str) -> int",
+                    description: "Size of a file in bytes",
+                },
+                StdLibFunction {
+                    name: "is_dir",
+                    signature: "(path: str) -> bool",
+                    description: "Check if a path is a directory",
+                },
+                StdLibFunction {
+                    name: "is_file",
+                    signature: "(path: str) -> bool",
+                    description: "Check if a path is a regular file",
+                },
+            ],
+            classes: vec![],
+        },
+        // strings module
+        StdLibModule {
+            name: "strings",
+            description: "StringBuilder for building long strings",
+            functions: vec![],
+            classes: vec![StdLibClass {
+                name: "StringBuilder",
+                fields: vec![("handle", "int")],
+                description: "Growable text buffer: append(text), length(), build() and clear()",
+            }],
+        },
+        // regex module
+        StdLibModule {
+            name: "regex",
+            description: "Regular expressions",
+            functions: vec![],
+            classes: vec![StdLibClass {
+                name: "Regex",
+                fields: vec![("pattern", "str"), ("handle", "int")],
+                description: "Compiled pattern: is_match, find, find_all, captures, replace and split",
+            }],
+        },
+        // time module
+        StdLibModule {
+            name: "time",
+            description: "Clocks, sleeping, and formatting and parsing dates",
+            functions: vec![
+                StdLibFunction {
+                    name: "now",
+                    signature: "() -> int",
+                    description: "The current Unix time in seconds",
+                },
+                StdLibFunction {
+                    name: "now_ms",
+                    signature: "() -> int",
+                    description: "The current Unix time in milliseconds",
+                },
+                StdLibFunction {
+                    name: "monotonic",
+                    signature: "() -> float",
+                    description: "Seconds on a clock that never goes backwards, for timing code",
+                },
+                StdLibFunction {
+                    name: "sleep",
+                    signature: "(ms: int) -> void",
+                    description: "Pause for ms milliseconds",
+                },
+                StdLibFunction {
+                    name: "format_time",
+                    signature: "(seconds: int, layout: str) -> str",
+                    description: "Format a Unix time in UTC, e.g. format_time(t, \"%Y-%m-%d %H:%M:%S\")",
+                },
+                StdLibFunction {
+                    name: "parse_time",
+                    signature: "(text: str, layout: str) -> int",
+                    description: "Parse a date written in layout into a Unix time",
+                },
+                StdLibFunction {
+                    name: "iso_time",
+                    signature: "(seconds: int) -> str",
+                    description: "A Unix time as an ISO 8601 UTC string, e.g. \"2024-02-29T13:05:09Z\"",
+                },
+            ],
+            classes: vec![],
+        },
+        // os module
+        StdLibModule {
+            name: "os",
+            description: "Environment variables",
+            functions: vec![
+                StdLibFunction {
+                    name: "get_env",
+                    signature: "(name: str) -> str?",
+                    description: "The value of an environment variable, or None if it isn't set",
+                },
+                StdLibFunction {
+                    name: "get_env_or",
+                    signature: "(name: str, fallback: str) -> str",
+                    description: "The value of an environment variable, or fallback if it isn't set",
+                },
+                StdLibFunction {
+                    name: "set_env",
+                    signature: "(name: str, value: str) -> void",
+                    description: "Set an environment variable for this program and the programs it starts",
+                },
+                StdLibFunction {
+                    name: "unset_env",
+                    signature: "(name: str) -> void",
+                    description: "Remove an environment variable",
+                },
+            ],
+            classes: vec![],
+        },
+        // process module
+        StdLibModule {
+            name: "process",
+            description: "Running other programs and capturing their output",
+            functions: vec![
+                StdLibFunction {
+                    name: "run",
+                    signature: "(program: str, args: list[str] = [], stdin: str = \"\") -> ProcessResult",
+                    description: "Run a program with arguments, without a shell, and wait for it",
+                },
+                StdLibFunction {
+                    name: "shell",
+                    signature: "(command: str, stdin: str = \"\") -> ProcessResult",
+                    description: "Run a command line with sh -c, so pipes and redirects work",
+                },
+            ],
+            classes: vec![StdLibClass {
+                name: "ProcessResult",
+                fields: vec![("exit_code", "int"), ("stdout", "str"), ("stderr", "str")],
+                description: "A finished process: its exit code and captured output",
+            }],
+        },
+        // path module
+        StdLibModule {
+            name: "path",
+            description: "Joining and splitting file paths",
+            functions: vec![
+                StdLibFunction {
+                    name: "join",
+                    signature: "(head: str, tail: str) -> str",
+                    description: "Join two paths with one \"/\"; an absolute tail replaces head",
+                },
+                StdLibFunction {
+                    name: "dirname",
+                    signature: "(p: str) -> str",
+                    description: "The directory part: \"src/main.ws\" -> \"src\"",
+                },
+                StdLibFunction {
+                    name: "basename",
+                    signature: "(p: str) -> str",
+                    description: "The last component: \"src/main.ws\" -> \"main.ws\"",
+                },
+                StdLibFunction {
+                    name: "extension",
+                    signature: "(p: str) -> str",
+                    description: "The extension with its dot: \"main.ws\" -> \".ws\", or \"\"",
+                },
+                StdLibFunction {
+                    name: "stem",
+                    signature: "(p: str) -> str",
+                    description: "The last component without its extension: \"src/main.ws\" -> \"main\"",
+                },
+                StdLibFunction {
+                    name: "normalize",
+                    signature: "(p: str) -> str",
+                    description: "Remove \".\", repeated slashes and \"..\" where possible",
+                },
+                StdLibFunction {
+                    name: "absolute",
+                    signature: "(p: str) -> str",
+                    description: "A normalized absolute path, relative to the current directory",
+                },
+                StdLibFunction {
+                    name: "is_absolute",
+                    signature: "(p: str) -> bool",
+                    description: "Whether the path starts with \"/\"",
+                },
+            ],
+            classes: vec![],
+        },
+        // csv module
+        StdLibModule {
+            name: "csv",
+            description: "Reading and writing CSV text and files",
+            functions: vec![
+                StdLibFunction {
+                    name: "parse_rows",
+                    signature: "(text: str, delimiter: str = \",\") -> list[list[str]]",
+                    description: "Split CSV text into rows of fields",
+                },
+                StdLibFunction {
+                    name: "parse_dicts",
+                    signature: "(text: str, delimiter: str = \",\") -> list[dict[str, str]]",
+                    description: "Parse CSV text with a header row into one dict per row",
+                },
+                StdLibFunction {
+                    name: "read_rows",
+                    signature: "(file_path: str, delimiter: str = \",\") -> list[list[str]]",
+                    description: "Read a CSV file into rows of fields",
+                },
+                StdLibFunction {
+                    name: "read_dicts",
+                    signature: "(file_path: str, delimiter: str = \",\") -> list[dict[str, str]]",
+                    description: "Read a CSV file with a header row into dicts",
+                },
+                StdLibFunction {
+                    name: "format_row",
+                    signature: "(fields: list[str], delimiter: str = \",\") -> str",
+                    description: "One row as a CSV line, quoting fields that need it",
+                },
+                StdLibFunction {
+                    name: "format_rows",
+                    signature: "(rows: list[list[str]], delimiter: str = \",\") -> str",
+                    description: "Rows as CSV text, one line each",
+                },
+                StdLibFunction {
+                    name: "write_rows",
+                    signature: "(file_path: str, rows: list[list[str]], delimiter: str = \",\") -> void",
+                    description: "Write rows to a CSV file",
+                },
+                StdLibFunction {
+                    name: "write_dicts",
+                    signature: "(file_path: str, columns: list[str], records: list[dict[str, str]], delimiter: str = \",\") -> void",
+                    description: "Write a header of columns, then each dict's values in that order",
+                },
+            ],
+            classes: vec![],
+        },
+        // config module
+        StdLibModule {
+            name: "config",
+            description: "Loading settings from TOML and INI files",
+            functions: vec![
+                StdLibFunction {
+                    name: "parse_toml",
+                    signature: "(text: str) -> dict[str, dict[str, str]]",
+                    description: "Parse TOML text into sections of settings",
+                },
+                StdLibFunction {
+                    name: "parse_ini",
+                    signature: "(text: str) -> dict[str, dict[str, str]]",
+                    description: "Parse INI text into sections of settings",
+                },
+                StdLibFunction {
+                    name: "load",
+                    signature: "(file_path: str) -> dict[str, dict[str, str]]",
+                    description: "Read a config file, as INI for .ini/.cfg/.conf and TOML otherwise",
+                },
+                StdLibFunction {
+                    name: "has_key",
+                    signature: "(settings: dict[str, dict[str, str]], section: str, key: str) -> bool",
+                    description: "Whether a section has a setting",
+                },
+                StdLibFunction {
+                    name: "get_str",
+                    signature: "(settings: dict[str, dict[str, str]], section: str, key: str, fallback: str = \"\") -> str",
+                    description: "A setting as text, or fallback if missing",
+                },
+                StdLibFunction {
+                    name: "get_int",
+                    signature: "(settings: dict[str, dict[str, str]], section: str, key: str, fallback: int = 0) -> int",
+                    description: "A setting as an int, or fallback if missing",
+                },
+                StdLibFunction {
+                    name: "get_float",
+                    signature: "(settings: dict[str, dict[str, str]], section: str, key: str, fallback: float = 0.0) -> float",
+                    description: "A setting as a float, or fallback if missing",
+                },
+                StdLibFunction {
+                    name: "get_bool",
+                    signature: "(settings: dict[str, dict[str, str]], section: str, key: str, fallback: bool = False) -> bool",
+                    description: "A setting as a bool (true/false, yes/no, on/off, 1/0), or fallback if missing",
+                },
+                StdLibFunction {
+                    name: "get_list",
+                    signature: "(settings: dict[str, dict[str, str]], section: str, key: str) -> list[str]",
+                    description: "The items of an array setting, or an empty list if missing",
+                },
+                StdLibFunction {
+                    name: "sections",
+                    signature: "(settings: dict[str, dict[str, str]]) -> list[str]",
+                    description: "The names of all sections",
+                },
+            ],
+            classes: vec![],
+        },
+        // net module
+        StdLibModule {
+            name: "net",
+            description: "TCP clients and servers",
+            functions: vec![
+                StdLibFunction {
+                    name: "connect",
+                    signature: "(host: str, port: int) -> int",
+                    description: "Connect to a TCP server, returns a connection handle",
+                },
+                StdLibFunction {
+                    name: "listen",
+                    signature: "(host: str, port: int) -> int",
+                    description: "Listen for connections, returns a listener handle (port 0 picks a free port)",
+                },
+                StdLibFunction {
+                    name: "accept",
+                    signature: "(server: int) -> int",
+                    description: "Wait for the next client, returns a connection handle",
+                },
+                StdLibFunction {
+                    name: "recv",
+                    signature: "(conn: int, max_bytes: int = 4096) -> str",
+                    description: "Up to max_bytes of received data, or \"\" once the connection is closed",
+                },
+                StdLibFunction {
+                    name: "recv_line",
+                    signature: "(conn: int) -> str?",
+                    description: "The next line without its line break, or None once the connection is closed",
+                },
+                StdLibFunction {
+                    name: "recv_all",
+                    signature: "(conn: int) -> str",
+                    description: "Everything until the other side closes the connection",
+                },
+                StdLibFunction {
+                    name: "sendall",
+                    signature: "(conn: int, data: str) -> void",
+                    description: "Send all of data",
+                },
+                StdLibFunction {
+                    name: "send_line",
+                    signature: "(conn: int, line: str) -> void",
+                    description: "Send a line followed by a newline",
+                },
+                StdLibFunction {
+                    name: "set_timeout",
+                    signature: "(conn: int, ms: int) -> void",
+                    description: "Make reads and sends raise OSError after waiting ms milliseconds (0 = forever)",
+                },
+                StdLibFunction {
+                    name: "local_port",
+                    signature: "(handle: int) -> int",
+                    description: "The local port of a listener or connection",
+                },
+                StdLibFunction {
+                    name: "close_socket",
+                    signature: "(handle: int) -> void",
+                    description: "Close a connection or listener",
+                },
+            ],
+            classes: vec![],
+        },
     ]
 }
 
 /// Get stdlib module names for import completion
 pub fn get_stdlib_module_names() -> Vec<&'static str> {
-    vec!["io", "cli", "http"]
+    vec!["io", "cli", "http", "smtp", "compress", "random", "fs", "strings", "regex", "time", "os", "process", "path", "csv", "config", "net"]
 }
 
 #[cfg(test)]
@@ -462,6 +1541,8 @@ mod tests {
         assert!(io.functions.iter().any(|f| f.name == "read"));
         assert!(io.functions.iter().any(|f| f.name == "write"));
         assert!(io.functions.iter().any(|f| f.name == "close"));
+        assert!(io.functions.iter().any(|f| f.name == "seek"));
+        assert!(io.functions.iter().any(|f| f.name == "lines"));
     }
 
     #[test]
@@ -480,4 +1561,127 @@ mod tests {
         assert!(!http.classes.is_empty());
         assert!(http.classes.iter().any(|c| c.name == "HttpResponse"));
     }
+
+    #[test]
+    fn test_stdlib_smtp_module() {
+        let modules = get_stdlib_modules();
+        let smtp = modules.iter().find(|m| m.name == "smtp").expect("smtp module not found");
+        assert!(smtp.functions.iter().any(|f| f.name == "send"));
+        assert!(smtp.classes.iter().any(|c| c.name == "SmtpServer"));
+    }
+
+    #[test]
+    fn test_stdlib_compress_module() {
+        let modules = get_stdlib_modules();
+        let compress = modules.iter().find(|m| m.name == "compress").expect("compress module not found");
+        for name in ["gzip", "gunzip", "zlib", "unzlib", "read_gzip", "write_gzip"] {
+            assert!(compress.functions.iter().any(|f| f.name == name), "missing {}", name);
+        }
+    }
+
+    #[test]
+    fn test_stdlib_random_module() {
+        let modules = get_stdlib_modules();
+        let random = modules.iter().find(|m| m.name == "random").expect("random module not found");
+        for name in ["uuid4", "random_hex", "random_token", "random_int", "random_float", "seed"] {
+            assert!(random.functions.iter().any(|f| f.name == name), "missing {}", name);
+        }
+        assert!(get_stdlib_module_names().contains(&"random"));
+    }
+
+    #[test]
+    fn test_stdlib_fs_module() {
+        let modules = get_stdlib_modules();
+        let fs = modules.iter().find(|m| m.name == "fs").expect("fs module not found");
+        assert!(fs.functions.iter().any(|f| f.name == "glob"));
+        assert!(fs.functions.iter().any(|f| f.name == "list_dir"));
+        assert!(fs.functions.iter().any(|f| f.name == "remove_dir"));
+        assert!(get_stdlib_module_names().contains(&"fs"));
+    }
+
+    #[test]
+    fn test_stdlib_strings_module() {
+        let modules = get_stdlib_modules();
+        let strings = modules.iter().find(|m| m.name == "strings").expect("strings module not found");
+        assert!(strings.classes.iter().any(|c| c.name == "StringBuilder"));
+        assert!(get_stdlib_module_names().contains(&"strings"));
+    }
+
+    #[test]
+    fn test_stdlib_regex_module() {
+        let modules = get_stdlib_modules();
+        let regex = modules.iter().find(|m| m.name == "regex").expect("regex module not found");
+        assert!(regex.classes.iter().any(|c| c.name == "Regex"));
+        assert!(get_stdlib_module_names().contains(&"regex"));
+    }
+
+    #[test]
+    fn test_stdlib_time_module() {
+        let modules = get_stdlib_modules();
+        let time = modules.iter().find(|m| m.name == "time").expect("time module not found");
+        for name in ["now", "now_ms", "monotonic", "sleep", "format_time", "parse_time", "iso_time"] {
+            assert!(time.functions.iter().any(|f| f.name == name), "missing {}", name);
+        }
+        assert!(get_stdlib_module_names().contains(&"time"));
+    }
+
+    #[test]
+    fn test_stdlib_os_module() {
+        let modules = get_stdlib_modules();
+        let os = modules.iter().find(|m| m.name == "os").expect("os module not found");
+        for name in ["get_env", "get_env_or", "set_env", "unset_env"] {
+            assert!(os.functions.iter().any(|f| f.name == name), "missing {}", name);
+        }
+        assert!(get_stdlib_module_names().contains(&"os"));
+    }
+
+    #[test]
+    fn test_stdlib_process_module() {
+        let modules = get_stdlib_modules();
+        let process = modules.iter().find(|m| m.name == "process").expect("process module not found");
+        assert!(process.functions.iter().any(|f| f.name == "run"));
+        assert!(process.functions.iter().any(|f| f.name == "shell"));
+        assert!(process.classes.iter().any(|c| c.name == "ProcessResult"));
+        assert!(get_stdlib_module_names().contains(&"process"));
+    }
+
+    #[test]
+    fn test_stdlib_path_module() {
+        let modules = get_stdlib_modules();
+        let path = modules.iter().find(|m| m.name == "path").expect("path module not found");
+        for name in ["join", "dirname", "basename", "extension", "normalize", "absolute"] {
+            assert!(path.functions.iter().any(|f| f.name == name), "missing {}", name);
+        }
+        assert!(get_stdlib_module_names().contains(&"path"));
+    }
+
+    #[test]
+    fn test_stdlib_csv_module() {
+        let modules = get_stdlib_modules();
+        let csv = modules.iter().find(|m| m.name == "csv").expect("csv module not found");
+        assert!(csv.functions.iter().any(|f| f.name == "read_dicts"));
+        assert!(csv.functions.iter().any(|f| f.name == "write_rows"));
+        assert!(!csv.functions.iter().any(|f| f.name.starts_with('_')), "internal helpers are not listed");
+        assert!(get_stdlib_module_names().contains(&"csv"));
+    }
+
+    #[test]
+    fn test_stdlib_config_module() {
+        let modules = get_stdlib_modules();
+        let config = modules.iter().find(|m| m.name == "config").expect("config module not found");
+        assert!(config.functions.iter().any(|f| f.name == "load"));
+        assert!(config.functions.iter().any(|f| f.name == "get_bool"));
+        assert!(!config.functions.iter().any(|f| f.name.starts_with('_')), "internal helpers are not listed");
+        assert!(get_stdlib_module_names().contains(&"config"));
+    }
+
+    #[test]
+    fn test_stdlib_net_module() {
+        let modules = get_stdlib_modules();
+        let net = modules.iter().find(|m| m.name == "net").expect("net module not found");
+        for name in ["connect", "listen", "accept", "recv_line", "sendall", "close_socket"] {
+            assert!(net.functions.iter().any(|f| f.name == name), "net.{} not listed", name);
+        }
+        assert!(get_stdlib_module_names().contains(&"net"));
+    }
 }
@@ -53,6 +53,7 @@ pub enum Token {
     // Literals
     IntLiteral(i64),
     FloatLiteral(f64),
+    DecimalLiteral(String),
     StringLiteral(String),
     FStringLiteral(String), // Raw f-string with {} placeholders
     BoolLiteral(bool),
@@ -66,6 +67,7 @@ pub enum Token {
     Elif,
     Else,
     While,
+    Let,
     For,
     In,
     Return,
@@ -77,7 +79,9 @@ pub enum Token {
     Except,
     Finally,
     Raise,
+    Super,
     As,
+    From,
     And,
     Or,
     Not,
@@ -87,12 +91,18 @@ pub enum Token {
 
     // Types
     IntType,
+    I32Type,
+    I16Type,
+    U8Type,
     FloatType,
+    BigIntType,
+    DecimalType,
     BoolType,
     StrType,
     ListType,
     DictType,
     Optional,   // Optional[T] syntax for nullable types
+    ResultType, // Result[T, E]
 
     // Operators
     Plus,
@@ -129,6 +139,8 @@ pub enum Token {
     Arrow,
     Dot,
     Question,   // ? for nullable type suffix (str?)
+    QuestionDot, // ?. optional chaining (user?.name)
+    DoubleQuestion, // ?? null coalescing (count ?? 0)
     At,         // @ for decorators
 
     // Special
@@ -142,6 +154,126 @@ impl fmt::Display for Token {
     }
 }
 
+/// Words that lex as keywords or type names instead of identifiers
+const KEYWORDS: &[(&str, Token)] = &[
+    ("def", Token::Def),
+    ("class", Token::Class),
+    ("import", Token::Import),
+    ("if", Token::If),
+    ("elif", Token::Elif),
+    ("else", Token::Else),
+    ("while", Token::While),
+    ("let", Token::Let),
+    ("for", Token::For),
+    ("in", Token::In),
+    ("return", Token::Return),
+    ("pass", Token::Pass),
+    ("break", Token::Break),
+    ("continue", Token::Continue),
+    ("assert", Token::Assert),
+    ("try", Token::Try),
+    ("except", Token::Except),
+    ("finally", Token::Finally),
+    ("raise", Token::Raise),
+    ("super", Token::Super),
+    ("as", Token::As),
+    ("from", Token::From),
+    ("and", Token::And),
+    ("or", Token::Or),
+    ("not", Token::Not),
+    ("True", Token::True),
+    ("False", Token::False),
+    ("None", Token::None),
+    ("int", Token::IntType),
+    ("i32", Token::I32Type),
+    ("i16", Token::I16Type),
+    ("u8", Token::U8Type),
+    ("float", Token::FloatType),
+    ("bigint", Token::BigIntType),
+    ("decimal", Token::DecimalType),
+    ("bool", Token::BoolType),
+    ("str", Token::StrType),
+    ("list", Token::ListType),
+    ("dict", Token::DictType),
+    ("Optional", Token::Optional),
+    ("Result", Token::ResultType),
+];
+
+/// The token for a keyword, or None for an ordinary identifier
+pub fn keyword(word: &str) -> Option<Token> {
+    KEYWORDS.iter().find(|(text, _)| *text == word).map(|(_, token)| token.clone())
+}
+
+/// The word a keyword token is spelled with
+pub fn keyword_text(token: &Token) -> Option<&'static str> {
+    KEYWORDS.iter().find(|(_, keyword)| keyword == token).map(|(text, _)| *text)
+}
+
+/// Whether a `{` after this token opens a dict literal rather than a block:
+/// `x = {`, `return {`, `f(a, {` and `{"k": {` start dicts, while blocks follow
+/// a condition, a `)` or a keyword like `else`
+fn brace_opens_literal(previous: &Token) -> bool {
+    matches!(
+        previous,
+        Token::Equal
+            | Token::PlusEqual
+            | Token::MinusEqual
+            | Token::StarEqual
+            | Token::SlashEqual
+            | Token::LeftParen
+            | Token::LeftBracket
+            | Token::Comma
+            | Token::Colon
+            | Token::Return
+            | Token::In
+            | Token::Plus
+            | Token::DoubleEqual
+            | Token::NotEqual
+            | Token::DoubleQuestion
+    )
+}
+
+/// Append a decoded f-string escape. Escaped braces (\{, \u{7B}) are literal text:
+/// double them so the f-string splitter treats them like {{ and }}
+fn push_fstring_escape(string: &mut String, escaped: &str) {
+    for c in escaped.chars() {
+        if c == '{' || c == '}' {
+            string.push(c);
+        }
+        string.push(c);
+    }
+}
+
+/// The heredoc layout of a triple-quoted body (see `read_triple_string`).
+/// A body that doesn't start with a newline is kept as written
+fn dedent_heredoc(raw: &str, start_line: usize) -> String {
+    let Some(body) = raw.strip_prefix('\n').or_else(|| raw.strip_prefix("\r\n")) else {
+        return raw.to_string();
+    };
+    let Some((text, indent)) = body.rsplit_once('\n') else {
+        return body.to_string();
+    };
+    if !indent.chars().all(|c| c == ' ' || c == '\t') {
+        // The closing quotes follow text on the same line: keep the indentation
+        return body.to_string();
+    }
+    let text = text.strip_suffix('\r').unwrap_or(text);
+    let lines: Vec<&str> = text
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| match line.strip_prefix(indent) {
+            Some(rest) => rest,
+            None if line.trim_end_matches('\r').trim().is_empty() => "",
+            None => panic!(
+                "Line {} is indented less than the closing quotes of the string starting at line {}",
+                start_line + i + 1,
+                start_line
+            ),
+        })
+        .collect();
+    lines.join("\n")
+}
+
 pub struct Lexer {
     input: Vec<char>,
     #[allow(dead_code)]
@@ -199,6 +331,11 @@ impl Lexer {
         self.input.get(self.position + offset).copied()
     }
 
+    /// Whether the current character opens a triple-quoted string (`"""` or `'''`)
+    fn starts_triple_quote(&self) -> bool {
+        self.current_char.is_some() && self.peek(1) == self.current_char && self.peek(2) == self.current_char
+    }
+
     fn skip_whitespace(&mut self) {
         while let Some(ch) = self.current_char {
             if ch == ' ' || ch == '\t' || ch == '\r' {
@@ -217,6 +354,25 @@ impl Lexer {
         }
     }
 
+    /// Skip a /* ... */ comment (may span lines; not nested)
+    fn skip_block_comment(&mut self) {
+        let start_line = self.line;
+        self.advance(); // skip '/'
+        self.advance(); // skip '*'
+
+        loop {
+            match self.current_char {
+                None => panic!("Unterminated block comment starting at line {}", start_line),
+                Some('*') if self.peek(1) == Some('/') => {
+                    self.advance();
+                    self.advance();
+                    return;
+                }
+                Some(_) => self.advance(),
+            }
+        }
+    }
+
     fn read_number(&mut self) -> Token {
         let mut num_str = String::new();
         let mut is_float = false;
@@ -234,6 +390,14 @@ impl Lexer {
             }
         }
 
+        // A `d` suffix makes a decimal literal: 19.99d
+        if self.current_char == Some('d')
+            && !self.peek(1).is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            self.advance();
+            return Token::DecimalLiteral(num_str);
+        }
+
         if is_float {
             Token::FloatLiteral(num_str.parse().unwrap())
         } else {
@@ -241,6 +405,57 @@ impl Lexer {
         }
     }
 
+    /// Decode the escape sequence after a backslash (current char is the one after '\\').
+    /// Supports \n \t \r \\ \' \" \{ \} and \u{XXXX} (NUL is rejected: strings are C strings).
+    /// Unknown escapes are kept verbatim (backslash included), like Python.
+    fn read_escape_sequence(&mut self) -> Option<String> {
+        let escaped = self.current_char?;
+        self.advance();
+        let decoded = match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '\\' => '\\',
+            '\'' => '\'',
+            '"' => '"',
+            '{' => '{',
+            '}' => '}',
+            'u' => return Some(self.read_unicode_escape().to_string()),
+            _ => return Some(format!("\\{}", escaped)),
+        };
+        Some(decoded.to_string())
+    }
+
+    /// Read the `{XXXX}` part of a \u{XXXX} escape (1-6 hex digits)
+    fn read_unicode_escape(&mut self) -> char {
+        if self.current_char != Some('{') {
+            panic!("Invalid unicode escape at line {}: expected '{{' after \\u", self.line);
+        }
+        self.advance();
+
+        let mut hex = String::new();
+        while let Some(ch) = self.current_char {
+            if ch == '}' {
+                break;
+            }
+            hex.push(ch);
+            self.advance();
+        }
+        if self.current_char != Some('}') {
+            panic!("Invalid unicode escape at line {}: missing closing '}}'", self.line);
+        }
+        self.advance();
+
+        if hex.is_empty() || hex.len() > 6 {
+            panic!("Invalid unicode escape '\\u{{{}}}' at line {}: expected 1-6 hex digits", hex, self.line);
+        }
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .filter(|c| *c != '\0')
+            .unwrap_or_else(|| panic!("Invalid unicode escape '\\u{{{}}}' at line {}", hex, self.line))
+    }
+
     fn read_string(&mut self, quote: char) -> Token {
         let mut string = String::new();
         self.advance(); // skip opening quote
@@ -251,18 +466,8 @@ impl Lexer {
                 break;
             } else if ch == '\\' {
                 self.advance();
-                if let Some(escaped) = self.current_char {
-                    let escaped_char = match escaped {
-                        'n' => '\n',
-                        't' => '\t',
-                        'r' => '\r',
-                        '\\' => '\\',
-                        '\'' => '\'',
-                        '"' => '"',
-                        _ => escaped,
-                    };
-                    string.push(escaped_char);
-                    self.advance();
+                if let Some(escaped) = self.read_escape_sequence() {
+                    string.push_str(&escaped);
                 }
             } else {
                 string.push(ch);
@@ -283,20 +488,8 @@ impl Lexer {
                 break;
             } else if ch == '\\' {
                 self.advance();
-                if let Some(escaped) = self.current_char {
-                    let escaped_char = match escaped {
-                        'n' => '\n',
-                        't' => '\t',
-                        'r' => '\r',
-                        '\\' => '\\',
-                        '\'' => '\'',
-                        '"' => '"',
-                        '{' => '{',
-                        '}' => '}',
-                        _ => escaped,
-                    };
-                    string.push(escaped_char);
-                    self.advance();
+                if let Some(escaped) = self.read_escape_sequence() {
+                    push_fstring_escape(&mut string, &escaped);
                 }
             } else {
                 string.push(ch);
@@ -307,6 +500,67 @@ impl Lexer {
         Token::FStringLiteral(string)
     }
 
+    /// Read a triple-quoted string (`"""..."""`, or `f"""..."""` when `fstring`).
+    /// A body that starts on the line after the opening quotes is a heredoc: that
+    /// first newline is dropped, and when the closing quotes sit on a line of their
+    /// own, their indentation is removed from every line along with the last newline
+    fn read_triple_string(&mut self, quote: char, fstring: bool) -> Token {
+        let start_line = self.line;
+        for _ in 0..3 {
+            self.advance(); // skip opening quotes
+        }
+
+        // Collect the raw body; escapes are decoded after dedenting, so an escaped
+        // newline doesn't count as a line break
+        let mut raw = String::new();
+        loop {
+            match self.current_char {
+                None => panic!("Unterminated triple-quoted string starting at line {}", start_line),
+                Some(ch) if ch == quote && self.peek(1) == Some(quote) && self.peek(2) == Some(quote) => {
+                    for _ in 0..3 {
+                        self.advance(); // skip closing quotes
+                    }
+                    break;
+                }
+                Some('\\') => {
+                    raw.push('\\');
+                    self.advance();
+                    if let Some(escaped) = self.current_char {
+                        raw.push(escaped);
+                        self.advance();
+                    }
+                }
+                Some(ch) => {
+                    raw.push(ch);
+                    self.advance();
+                }
+            }
+        }
+
+        let body = dedent_heredoc(&raw, start_line);
+        let mut decoder = Lexer::new(body);
+        decoder.line = start_line;
+        let mut string = String::new();
+        while let Some(ch) = decoder.current_char {
+            decoder.advance();
+            if ch != '\\' {
+                string.push(ch);
+            } else if let Some(escaped) = decoder.read_escape_sequence() {
+                if fstring {
+                    push_fstring_escape(&mut string, &escaped);
+                } else {
+                    string.push_str(&escaped);
+                }
+            }
+        }
+
+        if fstring {
+            Token::FStringLiteral(string)
+        } else {
+            Token::StringLiteral(string)
+        }
+    }
+
     fn read_identifier(&mut self) -> Token {
         let mut ident = String::new();
 
@@ -319,41 +573,7 @@ impl Lexer {
             }
         }
 
-        match ident.as_str() {
-            "def" => Token::Def,
-            "class" => Token::Class,
-            "import" => Token::Import,
-            "if" => Token::If,
-            "elif" => Token::Elif,
-            "else" => Token::Else,
-            "while" => Token::While,
-            "for" => Token::For,
-            "in" => Token::In,
-            "return" => Token::Return,
-            "pass" => Token::Pass,
-            "break" => Token::Break,
-            "continue" => Token::Continue,
-            "assert" => Token::Assert,
-            "try" => Token::Try,
-            "except" => Token::Except,
-            "finally" => Token::Finally,
-            "raise" => Token::Raise,
-            "as" => Token::As,
-            "and" => Token::And,
-            "or" => Token::Or,
-            "not" => Token::Not,
-            "True" => Token::True,
-            "False" => Token::False,
-            "None" => Token::None,
-            "int" => Token::IntType,
-            "float" => Token::FloatType,
-            "bool" => Token::BoolType,
-            "str" => Token::StrType,
-            "list" => Token::ListType,
-            "dict" => Token::DictType,
-            "Optional" => Token::Optional,
-            _ => Token::Identifier(ident),
-        }
+        keyword(&ident).unwrap_or(Token::Identifier(ident))
     }
 
     pub fn next_token(&mut self) -> TokenWithLocation {
@@ -365,6 +585,11 @@ impl Lexer {
                 continue;
             }
 
+            if self.current_char == Some('/') && self.peek(1) == Some('*') {
+                self.skip_block_comment();
+                continue;
+            }
+
             // Capture location at start of token
             let location = self.current_location();
 
@@ -384,7 +609,11 @@ impl Lexer {
                         let next_char = self.input[self.position + 1];
                         if next_char == '"' || next_char == '\'' {
                             self.advance(); // skip 'f'
-                            let token = self.read_fstring(next_char);
+                            let token = if self.starts_triple_quote() {
+                                self.read_triple_string(next_char, true)
+                            } else {
+                                self.read_fstring(next_char)
+                            };
                             return self.make_token(token, location);
                         }
                     }
@@ -396,12 +625,12 @@ impl Lexer {
                     let token = self.read_identifier();
                     return self.make_token(token, location);
                 }
-                Some('"') => {
-                    let token = self.read_string('"');
-                    return self.make_token(token, location);
-                }
-                Some('\'') => {
-                    let token = self.read_string('\'');
+                Some(quote @ ('"' | '\'')) => {
+                    let token = if self.starts_triple_quote() {
+                        self.read_triple_string(quote, false)
+                    } else {
+                        self.read_string(quote)
+                    };
                     return self.make_token(token, location);
                 }
                 Some('+') => {
@@ -543,6 +772,14 @@ impl Lexer {
                 }
                 Some('?') => {
                     self.advance();
+                    if self.current_char == Some('.') {
+                        self.advance();
+                        return self.make_token(Token::QuestionDot, location);
+                    }
+                    if self.current_char == Some('?') {
+                        self.advance();
+                        return self.make_token(Token::DoubleQuestion, location);
+                    }
                     return self.make_token(Token::Question, location);
                 }
                 Some('@') => {
@@ -556,13 +793,30 @@ impl Lexer {
         }
     }
 
+    /// Tokenize the whole input. Inside parentheses, brackets and dict literals a
+    /// newline doesn't end the statement, so it is dropped (implicit line continuation)
     pub fn tokenize(&mut self) -> Vec<TokenWithLocation> {
-        let mut tokens = Vec::new();
+        let mut tokens: Vec<TokenWithLocation> = Vec::new();
+        // One entry per open delimiter: true if newlines inside it are insignificant
+        let mut open_delimiters: Vec<bool> = Vec::new();
         loop {
             let token_with_loc = self.next_token();
-            if token_with_loc.token == Token::Eof {
-                tokens.push(token_with_loc);
-                break;
+            match token_with_loc.token {
+                Token::Eof => {
+                    tokens.push(token_with_loc);
+                    break;
+                }
+                Token::Newline if open_delimiters.last() == Some(&true) => continue,
+                Token::LeftParen | Token::LeftBracket => open_delimiters.push(true),
+                Token::LeftBrace => {
+                    let literal = open_delimiters.last() == Some(&true)
+                        || tokens.last().is_some_and(|previous| brace_opens_literal(&previous.token));
+                    open_delimiters.push(literal);
+                }
+                Token::RightParen | Token::RightBracket | Token::RightBrace => {
+                    open_delimiters.pop();
+                }
+                _ => {}
             }
             tokens.push(token_with_loc);
         }
@@ -627,6 +881,12 @@ mod tests {
         assert_eq!(tokens[7].token, Token::Break);
         assert_eq!(tokens[8].token, Token::Continue);
         assert_eq!(tokens[9].token, Token::Assert);
+
+        let tokens = Lexer::new("let".to_string()).tokenize();
+        assert_eq!(tokens[0].token, Token::Let);
+
+        let tokens = Lexer::new("super".to_string()).tokenize();
+        assert_eq!(tokens[0].token, Token::Super);
     }
 
     #[test]
@@ -754,4 +1014,184 @@ mod tests {
         let tokens = lexer.tokenize();
         assert_eq!(tokens[0].token, Token::FStringLiteral("Hello {name}".to_string()));
     }
+
+    #[test]
+    fn test_string_escapes() {
+        let mut lexer = Lexer::new(r#""a\nb\tc\\d\"e\'f""#.to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[0].token, Token::StringLiteral("a\nb\tc\\d\"e'f".to_string()));
+    }
+
+    #[test]
+    fn test_unicode_escape() {
+        let mut lexer = Lexer::new(r#""caf\u{e9} \u{1F600}""#.to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[0].token, Token::StringLiteral("café 😀".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid unicode escape")]
+    fn test_invalid_unicode_escape() {
+        let mut lexer = Lexer::new(r#""\u{110000}""#.to_string());
+        lexer.tokenize();
+    }
+
+    #[test]
+    fn test_unknown_escape_kept_verbatim() {
+        let mut lexer = Lexer::new(r#""\d+\.""#.to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[0].token, Token::StringLiteral("\\d+\\.".to_string()));
+    }
+
+    #[test]
+    fn test_fstring_escaped_braces() {
+        // Escaped braces become doubled so the f-string splitter keeps them literal
+        let mut lexer = Lexer::new(r#"f"\{x\} {y}\n""#.to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[0].token, Token::FStringLiteral("{{x}} {y}\n".to_string()));
+    }
+
+    #[test]
+    fn test_triple_quoted_strings() {
+        // Quotes and newlines need no escaping; escapes still work
+        let mut lexer = Lexer::new("\"\"\"say \"hi\"\nnext\\tline\"\"\" x".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[0].token, Token::StringLiteral("say \"hi\"\nnext\tline".to_string()));
+        assert_eq!(tokens[1].token, Token::Identifier("x".to_string()));
+        assert_eq!(tokens[1].start.line, 2);
+
+        let mut lexer = Lexer::new("'''it's'''".to_string());
+        assert_eq!(lexer.tokenize()[0].token, Token::StringLiteral("it's".to_string()));
+    }
+
+    #[test]
+    fn test_heredoc_dedent() {
+        // The closing quotes' indentation is removed from every line; deeper lines keep the rest
+        let source = "page = f\"\"\"\n    <ul>\n      <li>{name}</li>\n\n    </ul>\n    \"\"\"\n";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[2].token, Token::FStringLiteral("<ul>\n  <li>{name}</li>\n\n</ul>".to_string()));
+
+        // Closing quotes after text keep the body as written, minus the first newline
+        let mut lexer = Lexer::new("\"\"\"\n  a\n  b\"\"\"".to_string());
+        assert_eq!(lexer.tokenize()[0].token, Token::StringLiteral("  a\n  b".to_string()));
+
+        // An escaped newline is text, not a line of the heredoc
+        let mut lexer = Lexer::new("f\"\"\"\n    a\\n\\{b\\}\n    \"\"\"".to_string());
+        assert_eq!(lexer.tokenize()[0].token, Token::FStringLiteral("a\n{{b}}".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Line 3 is indented less than the closing quotes")]
+    fn test_heredoc_underindented_line() {
+        let mut lexer = Lexer::new("\"\"\"\n    a\n  b\n    \"\"\"".to_string());
+        lexer.tokenize();
+    }
+
+    #[test]
+    #[should_panic(expected = "Unterminated triple-quoted string starting at line 1")]
+    fn test_unterminated_triple_quoted_string() {
+        let mut lexer = Lexer::new("\"\"\"abc\"\"".to_string());
+        lexer.tokenize();
+    }
+
+    #[test]
+    fn test_block_comment() {
+        let mut lexer = Lexer::new("x /* inline */ = 1\n/* spans\n two lines */\ny".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[0].token, Token::Identifier("x".to_string()));
+        assert_eq!(tokens[1].token, Token::Equal);
+        assert_eq!(tokens[2].token, Token::IntLiteral(1));
+        assert_eq!(tokens[3].token, Token::Newline);
+        assert_eq!(tokens[4].token, Token::Newline);
+        assert_eq!(tokens[5].token, Token::Identifier("y".to_string()));
+        // Line numbers keep counting inside the comment
+        assert_eq!(tokens[5].start.line, 4);
+    }
+
+    #[test]
+    fn test_division_is_not_comment() {
+        let mut lexer = Lexer::new("a / b".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[1].token, Token::Slash);
+    }
+
+    #[test]
+    fn test_sized_int_type_keywords() {
+        let mut lexer = Lexer::new("i32 i16 u8 u16".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[0].token, Token::I32Type);
+        assert_eq!(tokens[1].token, Token::I16Type);
+        assert_eq!(tokens[2].token, Token::U8Type);
+        assert_eq!(tokens[3].token, Token::Identifier("u16".to_string()));
+    }
+
+    #[test]
+    fn test_bigint_type_keyword() {
+        let mut lexer = Lexer::new("n: bigint = bigints".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[2].token, Token::BigIntType);
+        assert_eq!(tokens[4].token, Token::Identifier("bigints".to_string()));
+    }
+
+    #[test]
+    fn test_decimal_literal() {
+        let mut lexer = Lexer::new("price: decimal = 19.99d + 3d\nx = 2do".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[2].token, Token::DecimalType);
+        assert_eq!(tokens[4].token, Token::DecimalLiteral("19.99".to_string()));
+        assert_eq!(tokens[6].token, Token::DecimalLiteral("3".to_string()));
+        // The suffix must end the number
+        assert_eq!(tokens[10].token, Token::IntLiteral(2));
+        assert_eq!(tokens[11].token, Token::Identifier("do".to_string()));
+    }
+
+    #[test]
+    fn test_optional_chaining_token() {
+        let mut lexer = Lexer::new("user?.name".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[0].token, Token::Identifier("user".to_string()));
+        assert_eq!(tokens[1].token, Token::QuestionDot);
+        assert_eq!(tokens[2].token, Token::Identifier("name".to_string()));
+    }
+
+    #[test]
+    fn test_null_coalescing_token() {
+        let mut lexer = Lexer::new("count ?? 0\nname: str?".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[1].token, Token::DoubleQuestion);
+        assert_eq!(tokens[2].token, Token::IntLiteral(0));
+        // A single '?' is still the optional type suffix
+        assert_eq!(tokens[7].token, Token::Question);
+    }
+
+    #[test]
+    fn test_result_type_and_propagate_tokens() {
+        let mut lexer = Lexer::new("r: Result[int, str] = f()?".to_string());
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens[2].token, Token::ResultType);
+        assert_eq!(tokens[12].token, Token::Question);
+    }
+
+    #[test]
+    fn test_newlines_inside_delimiters_are_dropped() {
+        let source = "xs = [\n    1,\n    2,\n]\nf(a,\n  b)\nd = {\n    \"k\": {\n        \"n\": 1\n    }\n}\nif x {\n    y\n}\n";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+        let lines: Vec<&[Token]> = tokens.split(|token| *token == Token::Newline).collect();
+        assert_eq!(lines[0].len(), 8, "{:?}", lines[0]); // xs = [ 1 , 2 , ]
+        assert_eq!(lines[1].len(), 6, "{:?}", lines[1]); // f ( a , b )
+        assert_eq!(lines[2].len(), 11, "{:?}", lines[2]); // d = { "k" : { "n" : 1 } }
+        // A block's braces keep their newlines
+        assert_eq!(lines[3], [Token::If, Token::Identifier("x".to_string()), Token::LeftBrace]);
+        assert_eq!(lines[4], [Token::Identifier("y".to_string())]);
+        assert_eq!(lines[5], [Token::RightBrace]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unterminated block comment")]
+    fn test_unterminated_block_comment() {
+        let mut lexer = Lexer::new("/* never closed".to_string());
+        lexer.tokenize();
+    }
 }
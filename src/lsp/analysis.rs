@@ -5,10 +5,12 @@ use std::collections::HashMap;
 use tower_lsp::lsp_types::*;
 
 use crate::ast::{Statement, Type};
+use crate::codegen::CodeGen;
 use crate::language_defs::{
     get_keywords, get_type_keywords, get_builtin_functions,
-    get_list_methods, get_string_methods, get_stdlib_modules, get_stdlib_module_names
+    get_list_methods, get_dict_methods, get_string_methods, get_stdlib_modules, get_stdlib_module_names
 };
+use crate::error_codes::{classify_type_error, INTERNAL_ERROR, PARSE_ERROR};
 use crate::lexer::Lexer;
 use crate::parser::Parser;
 use crate::typechecker::TypeChecker;
@@ -26,6 +28,7 @@ pub struct SymbolInfo {
     pub column: usize,
     pub end_line: usize,
     pub end_column: usize,
+    pub documentation: Option<String>,  // Docstring of a function or class
 }
 
 /// The main analyzer that provides all LSP functionality
@@ -45,47 +48,45 @@ impl Analyzer {
     pub fn analyze(&self, source: &str) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
 
-        // Try to lex
-        let mut lexer = Lexer::new(source.to_string());
-        let tokens = lexer.tokenize();
+        // Lex and parse; both report errors by panicking
+        let parse_result = std::panic::catch_unwind(|| {
+            let tokens = Lexer::new(source.to_string()).tokenize();
+            Parser::new_from_tokens(tokens).parse()
+        });
 
-        // Check for lexer errors (panics in current impl, so we catch them)
-        // For now, assume lexer succeeds if we get here
-
-        // Try to parse
-        let mut parser = Parser::new_from_tokens(tokens);
-        let parse_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            parser.parse()
-        }));
-
-        let program = match parse_result {
+        let mut program = match parse_result {
             Ok(prog) => prog,
             Err(e) => {
-                // Extract panic message
-                let msg = if let Some(s) = e.downcast_ref::<&str>() {
-                    s.to_string()
-                } else if let Some(s) = e.downcast_ref::<String>() {
-                    s.clone()
-                } else {
-                    "Parse error".to_string()
-                };
-
-                if let Some(ws_error) = parse_error_message(&msg) {
-                    diagnostics.push(ws_error.to_diagnostic());
-                } else {
-                    diagnostics.push(WsError::error(msg, 1, 1).to_diagnostic());
-                }
+                let msg = panic_message(e.as_ref()).unwrap_or_else(|| "Parse error".to_string());
+                let ws_error = parse_error_message(&msg)
+                    .unwrap_or_else(|| WsError::error(msg, 1, 1))
+                    .with_code(PARSE_ERROR);
+                diagnostics.push(ws_error.to_diagnostic());
                 return diagnostics;
             }
         };
 
-        // Try to type check
-        let mut type_checker = TypeChecker::new();
-        if let Err(type_error) = type_checker.check_program(&program) {
-            if let Some(ws_error) = parse_error_message(&type_error) {
+        // Try to type check. A panic here is a type checker bug, but it should
+        // not take the server down with it.
+        let check_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut type_checker = TypeChecker::new();
+            type_checker.set_reserved_names(CodeGen::runtime_function_names());
+            type_checker.check_program(&mut program)
+        }));
+        match check_result {
+            Ok(Ok(())) => {}
+            Ok(Err(type_error)) => {
+                let code = classify_type_error(&type_error);
+                let ws_error = parse_error_message(&type_error)
+                    .unwrap_or_else(|| WsError::error(type_error, 1, 1))
+                    .with_code(code);
+                diagnostics.push(ws_error.to_diagnostic());
+            }
+            Err(e) => {
+                let msg = panic_message(e.as_ref()).unwrap_or_default();
+                let ws_error = WsError::error(format!("Internal compiler error while type checking: {}", msg), 1, 1)
+                    .with_code(INTERNAL_ERROR);
                 diagnostics.push(ws_error.to_diagnostic());
-            } else {
-                diagnostics.push(WsError::error(type_error, 1, 1).to_diagnostic());
             }
         }
 
@@ -97,20 +98,28 @@ impl Analyzer {
         let (line, col) = lsp_position_to_ws(&position);
         let symbols = self.collect_symbols(source);
 
-        // Find symbol at position
-        for sym in &symbols {
-            if sym.line == line && col >= sym.column && col <= sym.end_column {
-                let type_info = sym.symbol_type.as_deref().unwrap_or("unknown");
-                return Some(format!(
-                    "**{}** ({})\n\nType: `{}`",
-                    sym.name,
-                    format!("{:?}", sym.kind).to_lowercase(),
-                    type_info
-                ));
-            }
+        // Find symbol at position, falling back to the definition of the word under the cursor
+        let word = self.get_word_at(source, line, col);
+        let sym = symbols
+            .iter()
+            .find(|sym| sym.line == line && col >= sym.column && col <= sym.end_column)
+            .or_else(|| {
+                let word = word.as_ref()?;
+                symbols.iter().find(|sym| &sym.name == word)
+            })?;
+
+        let type_info = sym.symbol_type.as_deref().unwrap_or("unknown");
+        let mut info = format!(
+            "**{}** ({})\n\nType: `{}`",
+            sym.name,
+            format!("{:?}", sym.kind).to_lowercase(),
+            type_info
+        );
+        if let Some(doc) = &sym.documentation {
+            info.push_str("\n\n---\n\n");
+            info.push_str(doc);
         }
-
-        None
+        Some(info)
     }
 
     /// Get completion items at a position
@@ -132,6 +141,7 @@ impl Analyzer {
                 label: sym.name.clone(),
                 kind: Some(kind),
                 detail: sym.symbol_type,
+                documentation: sym.documentation.map(Documentation::String),
                 ..Default::default()
             });
         }
@@ -176,6 +186,17 @@ impl Analyzer {
             });
         }
 
+        // Add dict methods
+        for (name, sig, desc) in get_dict_methods() {
+            items.push(CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::METHOD),
+                detail: Some(format!("dict.{}{}", name, sig)),
+                documentation: Some(Documentation::String(desc.to_string())),
+                ..Default::default()
+            });
+        }
+
         // Add string methods
         for (name, sig, desc) in get_string_methods() {
             items.push(CompletionItem {
@@ -443,14 +464,11 @@ impl Analyzer {
     fn collect_symbols(&self, source: &str) -> Vec<SymbolInfo> {
         let mut symbols = Vec::new();
 
-        // Try to parse the source
-        let mut lexer = Lexer::new(source.to_string());
-        let tokens = lexer.tokenize();
-        let mut parser = Parser::new_from_tokens(tokens);
-
-        let program = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            parser.parse()
-        })) {
+        // Try to lex and parse the source
+        let program = match std::panic::catch_unwind(|| {
+            let tokens = Lexer::new(source.to_string()).tokenize();
+            Parser::new_from_tokens(tokens).parse()
+        }) {
             Ok(prog) => prog,
             Err(_) => return symbols, // Return empty on parse error
         };
@@ -469,7 +487,8 @@ impl Analyzer {
                 name,
                 params,
                 return_type,
-                body: _,
+                docstring,
+                ..
             } => {
                 let param_types: Vec<String> = params
                     .iter()
@@ -489,6 +508,7 @@ impl Analyzer {
                     column: 1,
                     end_line: 1,
                     end_column: name.len(),
+                    documentation: docstring.clone(),
                 });
 
                 // Add parameters
@@ -501,14 +521,16 @@ impl Analyzer {
                         column: 1,
                         end_line: 1,
                         end_column: param.name.len(),
+                        documentation: None,
                     });
                 }
             }
             Statement::ClassDef {
                 name,
-                _base_class: _,
                 fields,
                 methods,
+                docstring,
+                ..
             } => {
                 symbols.push(SymbolInfo {
                     name: name.clone(),
@@ -518,6 +540,7 @@ impl Analyzer {
                     column: 1,
                     end_line: 1,
                     end_column: name.len(),
+                    documentation: docstring.clone(),
                 });
 
                 // Add fields
@@ -530,6 +553,7 @@ impl Analyzer {
                         column: 1,
                         end_line: 1,
                         end_column: field.name.len(),
+                        documentation: None,
                     });
                 }
 
@@ -547,6 +571,7 @@ impl Analyzer {
                     column: 1,
                     end_line: 1,
                     end_column: name.len(),
+                    documentation: None,
                 });
             }
             _ => {}
@@ -612,7 +637,12 @@ fn is_identifier_char(c: char) -> bool {
 fn format_type(ty: &Type) -> String {
     match ty {
         Type::Int => "int".to_string(),
+        Type::I32 => "i32".to_string(),
+        Type::I16 => "i16".to_string(),
+        Type::U8 => "u8".to_string(),
         Type::Float => "float".to_string(),
+        Type::BigInt => "bigint".to_string(),
+        Type::Decimal => "decimal".to_string(),
         Type::Bool => "bool".to_string(),
         Type::Str => "str".to_string(),
         Type::Void => "void".to_string(),
@@ -621,7 +651,10 @@ fn format_type(ty: &Type) -> String {
         Type::Array(inner, size) => format!("array[{}, {}]", format_type(inner), size),
         Type::Custom(name) => name.clone(),
         Type::Optional(inner) => format!("{}?", format_type(inner)),
+        Type::Result(ok, err) => format!("Result[{}, {}]", format_type(ok), format_type(err)),
         Type::Exception => "Exception".to_string(),
+        // `let` without annotation; the LSP does not run inference for symbols
+        Type::Inferred => "inferred".to_string(),
         Type::Tuple(types) => {
             let inner = types.iter().map(|t| format_type(t)).collect::<Vec<_>>().join(", ");
             format!("({})", inner)
@@ -634,3 +667,64 @@ impl Default for Analyzer {
         Self::new()
     }
 }
+
+/// Message of a caught panic, if it carried one
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_error_diagnostic_has_code() {
+        let source = "def main() -> int {\n    x: int = \"a\"\n    return 0\n}\n";
+        let diagnostics = Analyzer::new().analyze(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, Some(NumberOrString::String("E0102".to_string())));
+    }
+
+    #[test]
+    fn test_malformed_source_reports_diagnostic() {
+        crate::output::set_parse_errors_exit(false);
+        let analyzer = Analyzer::new();
+        for source in ["def main( -> int {\n", "x: int = 1 $\n", "class {"] {
+            let diagnostics = analyzer.analyze(source);
+            assert_eq!(diagnostics.len(), 1, "{:?}", source);
+            assert_eq!(diagnostics[0].code, Some(NumberOrString::String("E0001".to_string())));
+        }
+    }
+
+    #[test]
+    fn test_hover_shows_docstring() {
+        let source = "def area(w: int, h: int) -> int {\n    \"Area of a rectangle.\"\n    return w * h\n}\n\ndef main() -> int {\n    return area(2, 3)\n}\n";
+        let analyzer = Analyzer::new();
+        // Hover over `area` in the call on line 7
+        let hover = analyzer.hover(source, Position { line: 6, character: 12 }).unwrap();
+        assert!(hover.contains("**area**"));
+        assert!(hover.contains("Area of a rectangle."));
+    }
+
+    #[test]
+    fn test_format_keeps_one_line_blocks() {
+        let analyzer = Analyzer::new();
+        let source = "def f(x: int) -> int {\n    if x < 0 { x = -x; return x }\n    a: int = 1; b: int = 2\n    return a + b\n}\n";
+        assert!(analyzer.format(source).is_none());
+
+        let edits = analyzer.format("def f() -> int {\nif True { return 1 }\nreturn 0\n}\n").unwrap();
+        assert_eq!(edits[0].new_text, "def f() -> int {\n    if True { return 1 }\n    return 0\n}\n");
+    }
+
+    #[test]
+    fn test_hover_without_docstring() {
+        let source = "def main() -> int {\n    return 0\n}\n";
+        let analyzer = Analyzer::new();
+        let hover = analyzer.hover(source, Position { line: 0, character: 5 }).unwrap();
+        assert!(hover.contains("**main**"));
+        assert!(!hover.contains("---"));
+    }
+}
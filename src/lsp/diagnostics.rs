@@ -1,13 +1,21 @@
 /// Convert WadeScript errors to LSP diagnostics
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use tower_lsp::lsp_types::{
+    CodeDescription, Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, Url,
+};
+
+use crate::error_codes::error_code_url;
 
 /// A parse or type error from the WadeScript compiler
+/// Shared by the LSP and the driver's `--message-format=json` output.
+/// A line/column of 0 means the error has no source location (e.g. link errors).
 #[derive(Debug, Clone)]
 pub struct WsError {
     pub message: String,
     pub line: usize,
     pub column: usize,
     pub severity: WsErrorSeverity,
+    pub file: Option<String>,
+    pub code: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -24,6 +32,8 @@ impl WsError {
             line,
             column,
             severity: WsErrorSeverity::Error,
+            file: None,
+            code: None,
         }
     }
 
@@ -33,9 +43,46 @@ impl WsError {
             line,
             column,
             severity: WsErrorSeverity::Warning,
+            file: None,
+            code: None,
+        }
+    }
+
+    pub fn with_file(mut self, file: &str) -> Self {
+        self.file = Some(file.to_string());
+        self
+    }
+
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    fn severity_name(&self) -> &'static str {
+        match self.severity {
+            WsErrorSeverity::Error => "error",
+            WsErrorSeverity::Warning => "warning",
+            WsErrorSeverity::Info => "info",
         }
     }
 
+    /// Convert to a single-line JSON object (for `--message-format=json`)
+    pub fn to_json(&self) -> String {
+        let span = if self.line > 0 {
+            serde_json::json!({ "line": self.line, "column": self.column.max(1) })
+        } else {
+            serde_json::Value::Null
+        };
+        serde_json::json!({
+            "file": self.file,
+            "span": span,
+            "severity": self.severity_name(),
+            "code": self.code,
+            "message": self.message,
+        })
+        .to_string()
+    }
+
     /// Convert to LSP Diagnostic
     pub fn to_diagnostic(&self) -> Diagnostic {
         // WadeScript uses 1-indexed, LSP uses 0-indexed
@@ -60,6 +107,11 @@ impl WsError {
             }),
             source: Some("wadescript".to_string()),
             message: self.message.clone(),
+            code: self.code.clone().map(NumberOrString::String),
+            // Link the code to its explanation (same text as `wadescript explain`)
+            code_description: self.code.as_ref().and_then(|code| {
+                Url::parse(&error_code_url(code)).ok().map(|href| CodeDescription { href })
+            }),
             ..Default::default()
         }
     }
@@ -67,6 +119,20 @@ impl WsError {
 
 /// Parse error messages from the compiler output and convert to WsErrors
 pub fn parse_error_message(error: &str) -> Option<WsError> {
+    if let Some(located) = parse_located_error(error) {
+        return Some(located);
+    }
+
+    // Generic error - put at line 1
+    if !error.is_empty() {
+        return Some(WsError::error(error.to_string(), 1, 1));
+    }
+
+    None
+}
+
+/// Like `parse_error_message`, but only succeeds when the message carries a location
+pub fn parse_located_error(error: &str) -> Option<WsError> {
     // Try to parse error messages in various formats
     // Format: "Error at line X, column Y: message"
     // Format: "line X: message"
@@ -98,11 +164,6 @@ pub fn parse_error_message(error: &str) -> Option<WsError> {
         }
     }
 
-    // Generic error - put at line 1
-    if !error.is_empty() {
-        return Some(WsError::error(error.to_string(), 1, 1));
-    }
-
     None
 }
 
@@ -135,5 +196,47 @@ mod tests {
         assert_eq!(diag.range.start.line, 0); // 0-indexed
         assert_eq!(diag.range.start.character, 0);
         assert_eq!(diag.severity, Some(DiagnosticSeverity::ERROR));
+        assert!(diag.code.is_none());
+    }
+
+    #[test]
+    fn test_to_diagnostic_with_code() {
+        let ws_error = WsError::error("Undefined variable 'x'".to_string(), 2, 3).with_code("E0100");
+        let diag = ws_error.to_diagnostic();
+        assert_eq!(diag.code, Some(NumberOrString::String("E0100".to_string())));
+        let href = diag.code_description.unwrap().href;
+        assert!(href.as_str().ends_with("ERROR_CODES.md#e0100"));
+    }
+
+    #[test]
+    fn test_parse_located_error_requires_location() {
+        assert!(parse_located_error("Undefined variable 'x'").is_none());
+        let located = parse_located_error("line 3: Undefined variable 'x'").unwrap();
+        assert_eq!(located.line, 3);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let ws_error = WsError::error("Type mismatch".to_string(), 4, 7)
+            .with_file("main.ws")
+            .with_code("E0001");
+        let value: serde_json::Value = serde_json::from_str(&ws_error.to_json()).unwrap();
+        assert_eq!(value["file"], "main.ws");
+        assert_eq!(value["span"]["line"], 4);
+        assert_eq!(value["span"]["column"], 7);
+        assert_eq!(value["severity"], "error");
+        assert_eq!(value["code"], "E0001");
+        assert_eq!(value["message"], "Type mismatch");
+    }
+
+    #[test]
+    fn test_to_json_without_location() {
+        let ws_error = WsError::error("Linking failed".to_string(), 0, 0);
+        let json = ws_error.to_json();
+        assert!(!json.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value["span"].is_null());
+        assert!(value["file"].is_null());
+        assert!(value["code"].is_null());
     }
 }
@@ -1,64 +1,48 @@
 mod ast;
 mod codegen;
+mod crash;
+mod dev_server;
+mod error_codes;
+#[cfg(any(test, fuzzing))]
+#[allow(dead_code)]
+mod fuzz;
+mod ir_dump;
 mod jit;
 mod language_defs;
 mod lexer;
 mod lsp;
+mod output;
 mod parser;
 mod repl;
 mod runtime;
 mod runtime_symbols;
+mod source_map;
+mod suggest;
+mod toolchain;
 mod typechecker;
+mod verify;
+mod wslib;
 
-use ast::{Program, Statement};
+use ast::{Module, Program, Statement};
 use codegen::CodeGen;
 use inkwell::context::Context;
-use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine};
+use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple};
 use inkwell::OptimizationLevel;
 use lexer::Lexer;
+use lsp::diagnostics::{parse_located_error, WsError};
+use output::{paint, ColorChoice, MessageFormat, Verbosity};
 use parser::Parser;
 use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use toolchain::Allocator;
 use typechecker::TypeChecker;
 
 /// Get the standard library directory path
 fn get_std_lib_dir() -> Option<PathBuf> {
-    // Try multiple locations in order:
-    // 1. Relative to executable (for installed version)
-    // 2. Relative to current working directory (for development)
-    // 3. Relative to cargo manifest directory (for development)
-
-    // Try relative to executable
-    if let Ok(exe_path) = env::current_exe() {
-        if let Some(exe_dir) = exe_path.parent() {
-            let std_path = exe_dir.join("std");
-            if std_path.exists() {
-                return Some(std_path);
-            }
-            // Also try parent directory (for target/debug/wadescript -> std/)
-            if let Some(parent) = exe_dir.parent() {
-                if let Some(grandparent) = parent.parent() {
-                    let std_path = grandparent.join("std");
-                    if std_path.exists() {
-                        return Some(std_path);
-                    }
-                }
-            }
-        }
-    }
-
-    // Try relative to current working directory
-    if let Ok(cwd) = env::current_dir() {
-        let std_path = cwd.join("std");
-        if std_path.exists() {
-            return Some(std_path);
-        }
-    }
-
-    None
+    toolchain::find_std_dir()
 }
 
 /// Check if an import path is a standard library module
@@ -78,10 +62,35 @@ fn is_std_lib_import(path: &str) -> bool {
     false
 }
 
+/// Compiler version, also stamped into std/VERSION and compiled programs
+const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Make sure a std directory was shipped with this compiler. A std from another
+/// release can disagree with the compiler's builtins and runtime, which would
+/// otherwise show up as confusing type errors inside std modules.
+fn check_std_version(std_dir: &Path) -> Result<(), String> {
+    let stamp = std_dir.join("VERSION");
+    let version = fs::read_to_string(&stamp).map_err(|_| {
+        format!(
+            "Standard library in {:?} has no VERSION file, so it cannot be matched to WadeScript {}; use the std directory that came with this compiler",
+            std_dir, COMPILER_VERSION
+        )
+    })?;
+    let version = version.trim();
+    if version != COMPILER_VERSION {
+        return Err(format!(
+            "Standard library in {:?} is for WadeScript {}, but this compiler is WadeScript {}; use the std directory that came with this compiler",
+            std_dir, version, COMPILER_VERSION
+        ));
+    }
+    Ok(())
+}
+
 /// Resolve a standard library import to its file path
 fn resolve_std_import(module_name: &str) -> Result<PathBuf, String> {
     let std_dir = get_std_lib_dir()
         .ok_or_else(|| format!("Standard library not found for import '{}'", module_name))?;
+    check_std_version(&std_dir)?;
 
     let module_path = std_dir.join(format!("{}.ws", module_name));
 
@@ -109,6 +118,7 @@ fn load_program_with_imports(file_path: &str, imported: &mut HashSet<PathBuf>) -
     imported.insert(abs_path.clone());
 
     // Read and parse the file
+    output::set_current_file(&file_path_with_ext);
     let source_code = fs::read_to_string(&abs_path).map_err(|e| format!("Error reading file '{}': {}", file_path_with_ext, e))?;
     let lexer = Lexer::new(source_code);
     let mut parser = Parser::new(lexer);
@@ -141,25 +151,51 @@ fn load_program_with_imports(file_path: &str, imported: &mut HashSet<PathBuf>) -
                 .unwrap_or(path)
                 .to_string();
 
-            // Recursively load the imported file
-            let imported_program = load_program_with_imports(import_path_str, imported)?;
+            // A compiled library stands in for a module whose source isn't there
+            let library_path = import_path.with_extension(wslib::EXTENSION);
+            if !import_path.exists() && library_path.exists() {
+                let abs_library_path = fs::canonicalize(&library_path)
+                    .map_err(|e| format!("Cannot resolve path '{}': {}", library_path.display(), e))?;
+                // Declared and linked once, however many files import it
+                if imported.insert(abs_library_path) {
+                    let library = wslib::load(&library_path)?;
+                    let functions = library.functions.iter().map(|f| f.name.clone()).collect();
+                    result_program.modules.insert(module_name, Module { functions, ..Module::default() });
+                    result_program.libraries.push(library);
+                }
+                continue;
+            }
 
-            // Extract function names from imported program
-            let mut function_names = Vec::new();
-            for stmt in &imported_program.statements {
-                if let Statement::FunctionDef { name, .. } = stmt {
-                    function_names.push(name.clone());
+            // Recursively load the imported file
+            let mut imported_program = load_program_with_imports(import_path_str, imported)?;
+
+            // The file's own functions become `module.name`; its imports'
+            // already carry their module
+            let mut module = Module { imports: imported_program.modules.keys().cloned().collect(), ..Module::default() };
+            let nested_classes: HashSet<&String> = imported_program.modules.values().flat_map(|m| &m.classes).collect();
+            for stmt in &mut imported_program.statements {
+                match stmt {
+                    Statement::FunctionDef { name, .. } => {
+                        if !name.contains('.') {
+                            *name = format!("{}.{}", module_name, name);
+                        }
+                        let bare = name.rsplit('.').next().unwrap_or(name).to_string();
+                        if !module.functions.contains(&bare) {
+                            module.functions.push(bare);
+                        }
+                    }
+                    Statement::ClassDef { name, .. } if !nested_classes.contains(name) => module.classes.push(name.clone()),
+                    _ => {}
                 }
             }
 
             // Register this module
-            result_program.modules.insert(module_name, function_names);
+            result_program.modules.insert(module_name, module);
 
             // Merge statements and modules
             result_program.statements.extend(imported_program.statements);
-            for (mod_name, func_names) in imported_program.modules {
-                result_program.modules.insert(mod_name, func_names);
-            }
+            result_program.modules.extend(imported_program.modules);
+            result_program.libraries.extend(imported_program.libraries);
         } else {
             // Add non-import statements from current file
             result_program.statements.push(statement.clone());
@@ -169,20 +205,160 @@ fn load_program_with_imports(file_path: &str, imported: &mut HashSet<PathBuf>) -
     Ok(result_program)
 }
 
+/// Parse a `--relocation-model=` value into an LLVM relocation model
+fn parse_relocation_model(value: &str) -> Result<RelocMode, String> {
+    match value {
+        "default" => Ok(RelocMode::Default),
+        "pic" => Ok(RelocMode::PIC),
+        "static" => Ok(RelocMode::Static),
+        "dynamic-no-pic" => Ok(RelocMode::DynamicNoPic),
+        other => Err(format!(
+            "Unknown relocation model '{}' (expected default, pic, static, or dynamic-no-pic)",
+            other
+        )),
+    }
+}
+
+/// The code generator's optimization level for `-O<level>`
+fn codegen_optimization_level(level: u32) -> OptimizationLevel {
+    match level {
+        0 => OptimizationLevel::None,
+        1 => OptimizationLevel::Less,
+        2 => OptimizationLevel::Default,
+        _ => OptimizationLevel::Aggressive,
+    }
+}
+
+/// Path of the runtime archive to link against: from the install prefix or
+/// cargo's target directory, else the development path so errors can name it
+fn runtime_lib_path(target_triple: Option<&str>, allocator: Allocator) -> String {
+    toolchain::find_runtime_lib(target_triple, allocator)
+        .unwrap_or_else(|| toolchain::dev_runtime_lib(target_triple, allocator))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// `wadescript install [--prefix <dir>]`: copy this compiler, its runtime
+/// archive, and std into the prefix, where later compiles find them
+fn install_toolchain(args: &[String]) -> Result<(), String> {
+    let prefix = match args {
+        [] => toolchain::default_prefix().ok_or("Cannot find the home directory; pass --prefix <dir>")?,
+        [flag, dir] if flag == "--prefix" => PathBuf::from(dir),
+        _ => return Err("Usage: wadescript install [--prefix <dir>]".to_string()),
+    };
+    let compiler = env::current_exe().map_err(|e| format!("Cannot locate the running compiler: {}", e))?;
+    let runtime_lib = toolchain::find_runtime_lib(None, Allocator::System).ok_or_else(|| {
+        format!(
+            "Runtime library {} not found; build it with `cargo build{}` first",
+            toolchain::RUNTIME_LIB,
+            if cfg!(debug_assertions) { "" } else { " --release" }
+        )
+    })?;
+    let std_dir = get_std_lib_dir().ok_or("Standard library directory not found")?;
+    check_std_version(&std_dir)?;
+
+    // Archives built for other allocators are installed too, when there are any
+    let allocator_libs: Vec<(Allocator, PathBuf)> = Allocator::ALTERNATIVES
+        .into_iter()
+        .filter_map(|allocator| Some((allocator, toolchain::find_runtime_lib(None, allocator)?)))
+        .collect();
+
+    for path in toolchain::install(&prefix, &compiler, &runtime_lib, &allocator_libs, &std_dir)? {
+        println!("Installed {}", path.display());
+    }
+    println!("Add {} to your PATH to use it", prefix.join("bin").display());
+    Ok(())
+}
+
+/// Print a driver error with a highlighted label and code (e.g. "Type error[E0102]:"),
+/// or as a JSON line under `--message-format=json`
+fn report_error(label: &str, code: &str, message: &str) {
+    if output::json_diagnostics() {
+        // Keep the location when the message carries one (e.g. "line 3: ...")
+        let diagnostic = parse_located_error(message)
+            .unwrap_or_else(|| WsError::error(message.to_string(), 0, 0));
+        output::emit_json_diagnostic(diagnostic.with_code(code));
+    } else {
+        eprintln!("{} {}", paint("31;1", &format!("{}[{}]:", label, code)), message);
+    }
+}
+
+fn report_warning(code: &str, message: &str) {
+    if output::json_diagnostics() {
+        output::emit_json_diagnostic(WsError::warning(message.to_string(), 0, 0).with_code(code));
+    } else {
+        eprintln!("{} {}", paint("33;1", &format!("Warning[{}]:", code)), message);
+    }
+}
+
 fn main() {
+    // Panics are compiler bugs: turn them into a crash report instead of a Rust backtrace
+    crash::install_panic_hook();
+    if std::panic::catch_unwind(run).is_err() {
+        let args: Vec<String> = env::args().collect();
+        let (summary, written) = crash::write_report(&args);
+        let note = match written {
+            Ok(path) => format!(
+                "A crash report was written to '{}' (nothing was sent anywhere); please attach it to a bug report",
+                path.display()
+            ),
+            Err(report) => {
+                eprintln!("{}", report);
+                "The crash report above could not be written to a file; please include it in a bug report".to_string()
+            }
+        };
+        report_error(
+            "Internal compiler error",
+            error_codes::INTERNAL_ERROR,
+            &format!("the compiler crashed: {}\nThis is a compiler bug, not a problem with your program. {}", summary, note),
+        );
+        std::process::exit(101);
+    }
+}
+
+fn run() {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 2 {
-        eprintln!("Usage: wadescript <input_file.ws> [--emit-llvm]");
-        eprintln!("       wadescript repl");
+        eprintln!("Usage: wadescript <input_file.ws> [--emit-llvm[=<function>]] [--diff-ir <previous.ll>]");
+        eprintln!("                                  [-O0|-O1|-O2|-O3] [--checked-overflow] [--strict]");
+        eprintln!("                                  [--relocation-model=<model>] [--pie | --no-pie]");
+        eprintln!("                                  [--target <triple>] [--allocator=system|mimalloc|jemalloc]");
+        eprintln!("                                  [--color=auto|always|never] [--quiet | --verbose]");
+        eprintln!("                                  [--message-format=human|json] [--emit-lib]");
+        eprintln!("       wadescript explain <code>");
+        eprintln!("       wadescript repl [--load <file.ws>]... [--no-rc]");
+        eprintln!("       wadescript dev <file.ws> [--port <n>]");
         eprintln!("       wadescript lsp");
+        eprintln!("       wadescript install [--prefix <dir>]");
         std::process::exit(1);
     }
 
+    // Check for explain command (extended description of a diagnostic code)
+    if args[1] == "explain" {
+        let Some(code) = args.get(2) else {
+            eprintln!("Usage: wadescript explain <code>  (e.g. wadescript explain E0102)");
+            std::process::exit(1);
+        };
+        match error_codes::explain(code) {
+            Ok(text) => print!("{}", text),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     // Check for REPL command
     if args[1] == "repl" {
+        let options = repl::ReplOptions::parse(&args[2..]).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            eprintln!("Usage: wadescript repl [--load <file.ws>]... [--no-rc]");
+            std::process::exit(1);
+        });
         match repl::Repl::new() {
-            Ok(mut r) => r.run(),
+            Ok(mut r) => r.run(&options),
             Err(e) => {
                 eprintln!("Failed to start REPL: {}", e);
                 std::process::exit(1);
@@ -191,8 +367,19 @@ fn main() {
         return;
     }
 
+    // Check for dev command (serve a handler, reloading it when the source changes)
+    if args[1] == "dev" {
+        if let Err(e) = dev_server::run(&args[2..]) {
+            eprintln!("{} {}", paint("31;1", "Dev server failed:"), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Check for LSP command
     if args[1] == "lsp" {
+        // Report parse errors as diagnostics instead of exiting the server
+        output::set_parse_errors_exit(false);
         tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .build()
@@ -201,76 +388,338 @@ fn main() {
         return;
     }
 
+    // Check for install command (copy the toolchain into a prefix)
+    if args[1] == "install" {
+        if let Err(e) = install_toolchain(&args[2..]) {
+            eprintln!("{} {}", paint("31;1", "Install failed:"), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let input_file = &args[1];
-    let emit_llvm = args.len() > 2 && args[2] == "--emit-llvm";
+    let mut emit_llvm = false;
+    // Some: print only this function's IR
+    let mut emit_function: Option<String> = None;
+    // Some: diff the IR against this earlier dump instead of printing it
+    let mut diff_ir_path: Option<String> = None;
+    // Package the module as a compiled library instead of linking an executable
+    let mut emit_lib = false;
+    // -O<level>: 0 (the default) keeps every line and frame for debuggers
+    let mut opt_level = 0;
+    let mut checked_overflow = false;
+    let mut strict = false;
+    let mut reloc_mode = RelocMode::Default;
+    // None: use the linker's default; Some(true/false): pass -pie / -no-pie
+    let mut link_pie: Option<bool> = None;
+    // None: compile for the host
+    let mut target_triple: Option<String> = None;
+    // Which runtime archive to link: each is built with a different allocator
+    let mut allocator = Allocator::System;
+    let mut color = ColorChoice::Auto;
+    let mut verbosity = Verbosity::Normal;
+    let mut flags = args[2..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--emit-llvm" => emit_llvm = true,
+            "--emit-lib" => emit_lib = true,
+            "--diff-ir" => {
+                let path = flags.next().unwrap_or_else(|| {
+                    eprintln!("--diff-ir requires the path of a previous IR dump");
+                    std::process::exit(1);
+                });
+                diff_ir_path = Some(path.clone());
+            }
+            "-O0" | "-O1" | "-O2" | "-O3" => opt_level = flag[2..].parse().unwrap(),
+            "--checked-overflow" => checked_overflow = true,
+            "--strict" => strict = true,
+            "--pie" => {
+                reloc_mode = RelocMode::PIC;
+                link_pie = Some(true);
+            }
+            "--no-pie" => {
+                reloc_mode = RelocMode::Static;
+                link_pie = Some(false);
+            }
+            "--target" => {
+                let triple = flags.next().unwrap_or_else(|| {
+                    eprintln!("--target requires a target triple (e.g. aarch64-unknown-linux-gnu)");
+                    std::process::exit(1);
+                });
+                target_triple = Some(triple.clone());
+            }
+            "--quiet" | "-q" => verbosity = Verbosity::Quiet,
+            "--verbose" | "-v" => verbosity = Verbosity::Verbose,
+            _ if flag.starts_with("--message-format=") => {
+                let format = MessageFormat::parse(&flag["--message-format=".len()..]).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                });
+                output::set_message_format(format);
+            }
+            _ if flag.starts_with("--emit-llvm=") => {
+                emit_llvm = true;
+                emit_function = Some(flag["--emit-llvm=".len()..].to_string());
+            }
+            _ if flag.starts_with("--color=") => {
+                color = ColorChoice::parse(&flag["--color=".len()..]).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                });
+            }
+            _ if flag.starts_with("--allocator=") => {
+                allocator = Allocator::parse(&flag["--allocator=".len()..]).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                });
+            }
+            _ if flag.starts_with("--relocation-model=") => {
+                let value = &flag["--relocation-model=".len()..];
+                reloc_mode = parse_relocation_model(value).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                });
+            }
+            _ => {
+                eprintln!("Unknown option '{}'", flag);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    output::set_color_enabled(color.resolve_from_env());
+    let verbose = |message: &str| {
+        if verbosity == Verbosity::Verbose {
+            eprintln!("{}", paint("90", message));
+        }
+    };
 
     let mut imported = HashSet::new();
-    let program = load_program_with_imports(input_file, &mut imported).unwrap_or_else(|err| {
-        eprintln!("Error loading program: {}", err);
+    let mut program = load_program_with_imports(input_file, &mut imported).unwrap_or_else(|err| {
+        report_error("Error loading program", error_codes::LOAD_ERROR, &err);
         std::process::exit(1);
     });
+    // Imports are merged, so later diagnostics are reported against the entry file
+    output::set_current_file(input_file);
+    verbose(&format!("Parsed {} ({} file(s), {} statements)", input_file, imported.len(), program.statements.len()));
 
     let mut type_checker = TypeChecker::new();
-    if let Err(e) = type_checker.check_program(&program) {
-        eprintln!("Type error: {}", e);
+    type_checker.set_strict(strict);
+    type_checker.set_reserved_names(CodeGen::runtime_function_names());
+    if let Err(e) = type_checker.check_program(&mut program) {
+        report_error("Type error", error_codes::classify_type_error(&e), &e);
         std::process::exit(1);
     }
+    if verbosity != Verbosity::Quiet {
+        for warning in type_checker.warnings() {
+            report_warning(error_codes::classify_warning(warning), warning);
+        }
+    }
+    verbose("Type check passed");
+
+    let library_exports = emit_lib.then(|| {
+        wslib::exports(&program).unwrap_or_else(|e| {
+            report_error("Cannot build library", error_codes::LIBRARY_ERROR, &e);
+            std::process::exit(1);
+        })
+    });
 
     let context = Context::create();
     let mut codegen = CodeGen::new(&context, "wadescript_module", input_file);
+    codegen.set_checked_overflow(checked_overflow);
 
     if let Err(e) = codegen.compile_program(&program) {
-        eprintln!("Compilation error: {}", e);
+        report_error("Compilation error", error_codes::CODEGEN_ERROR, &e);
         std::process::exit(1);
     }
-
-    let module = codegen.get_module();
-
-    if emit_llvm {
-        println!("{}", module.print_to_string().to_string());
-        return;
+    verbose("Generated LLVM IR");
+
+    // Catch invalid IR here, where it can be traced back to the source, rather than in clang
+    if let Err(e) = verify::verify_module(codegen.get_module()) {
+        let dump_file = format!("{}.ice.ll", Path::new(input_file).file_stem().unwrap().to_string_lossy());
+        let dump_note = match codegen.get_module().print_to_file(&dump_file) {
+            Ok(()) => format!("the generated IR was written to '{}'", dump_file),
+            Err(err) => format!("the generated IR could not be written to '{}': {}", dump_file, err),
+        };
+        report_error(
+            "Internal compiler error",
+            error_codes::INTERNAL_ERROR,
+            &format!("{}\nThis is a compiler bug; please report it with the source file ({})", e, dump_note),
+        );
+        std::process::exit(1);
     }
+    verbose("Verified LLVM IR");
 
-    Target::initialize_native(&InitializationConfig::default()).unwrap();
-
-    let target_triple = TargetMachine::get_default_triple();
-    let target = Target::from_triple(&target_triple).unwrap();
-    // Use no optimization to preserve debug information
+    // Cross compilation needs every backend LLVM was built with; native only needs the host
+    let triple = match &target_triple {
+        Some(name) => {
+            Target::initialize_all(&InitializationConfig::default());
+            TargetTriple::create(name)
+        }
+        None => {
+            Target::initialize_native(&InitializationConfig::default()).unwrap();
+            TargetMachine::get_default_triple()
+        }
+    };
+    let target = Target::from_triple(&triple).unwrap_or_else(|err| {
+        report_error("Unsupported target", error_codes::TARGET_ERROR, &format!("'{}': {}", triple.as_str().to_string_lossy(), err));
+        std::process::exit(1);
+    });
     let target_machine = target
         .create_target_machine(
-            &target_triple,
+            &triple,
             "generic",
             "",
-            OptimizationLevel::None,
-            RelocMode::Default,
+            codegen_optimization_level(opt_level),
+            reloc_mode,
             CodeModel::Default,
         )
         .unwrap();
 
+    // Record the target in the module so IR and objects agree on layout
+    codegen.get_module().set_triple(&triple);
+    codegen.get_module().set_data_layout(&target_machine.get_target_data().get_data_layout());
+
+    // Drop functions that main never reaches (unused std imports, helpers); a
+    // library keeps what its exports reach
+    let eliminated = match &library_exports {
+        Some(functions) => {
+            let names: Vec<String> = functions.iter().map(|f| f.name.clone()).collect();
+            codegen.internalize_library(&names, &target_machine)
+        }
+        None => codegen.eliminate_dead_functions(&target_machine),
+    };
+    if let Err(e) = eliminated {
+        report_error("Compilation error", error_codes::CODEGEN_ERROR, &e);
+        std::process::exit(1);
+    }
+    verbose(&format!("Target: {}", triple.as_str().to_string_lossy()));
+
+    if opt_level > 0 {
+        if let Err(e) = codegen.optimize(opt_level, &target_machine) {
+            report_error("Compilation error", error_codes::CODEGEN_ERROR, &e);
+            std::process::exit(1);
+        }
+        verbose(&format!("Optimized at -O{}", opt_level));
+    }
+
+    let module = codegen.get_module();
+
+    if emit_llvm || diff_ir_path.is_some() {
+        let ir = match &emit_function {
+            Some(name) => ir_dump::function_ir(module, name).unwrap_or_else(|err| {
+                report_error("Error", error_codes::CODEGEN_ERROR, &err);
+                std::process::exit(1);
+            }),
+            None => module.print_to_string().to_string(),
+        };
+        let Some(previous_path) = &diff_ir_path else {
+            println!("{}", ir);
+            return;
+        };
+
+        let previous = fs::read_to_string(previous_path).unwrap_or_else(|err| {
+            report_error("Error loading IR dump", error_codes::LOAD_ERROR, &format!("'{}': {}", previous_path, err));
+            std::process::exit(1);
+        });
+        // Like diff(1): exit status 1 when the IR changed
+        if let Some(diff) = ir_dump::diff_ir(previous_path, &previous, input_file, &ir) {
+            print!("{}", diff);
+            std::process::exit(1);
+        }
+        verbose(&format!("IR matches {}", previous_path));
+        return;
+    }
+
     let output_base = Path::new(input_file).file_stem().unwrap().to_str().unwrap();
+
+    if let Some(functions) = &library_exports {
+        let library_file = format!("{}.{}", output_base, wslib::EXTENSION);
+        let object = target_machine.write_to_memory_buffer(module, FileType::Object).unwrap();
+        let target = triple.as_str().to_string_lossy();
+        if let Err(e) = wslib::write(Path::new(&library_file), &target, functions, object.as_slice()) {
+            report_error("Cannot build library", error_codes::LIBRARY_ERROR, &e);
+            std::process::exit(1);
+        }
+        if verbosity != Verbosity::Quiet && !output::json_diagnostics() {
+            println!("Compiled library to '{}' ({} function(s) exported)", library_file, functions.len());
+        }
+        return;
+    }
+
     let obj_file = format!("{}.o", output_base);
     let exe_file = output_base;
 
     target_machine
         .write_to_file(module, FileType::Object, Path::new(&obj_file))
         .unwrap();
+    verbose(&format!("Wrote object file {}", obj_file));
 
-    // Get the runtime library path (matches build profile)
-    let runtime_lib = if cfg!(debug_assertions) {
-        "target/debug/libwadescript_runtime.a"
-    } else {
-        "target/release/libwadescript_runtime.a"
-    };
+    // Get the runtime library path (matches build profile and target)
+    let runtime_lib = runtime_lib_path(target_triple.as_deref(), allocator);
+    if (target_triple.is_some() || allocator != Allocator::System) && !Path::new(&runtime_lib).exists() {
+        let label = if allocator != Allocator::System {
+            format!("Runtime library for allocator {} not found", allocator.name())
+        } else {
+            "Runtime library for target not found".to_string()
+        };
+        report_error(&label, error_codes::TARGET_ERROR, &runtime_lib);
+        eprintln!("Build it with: {}", allocator.build_command(target_triple.as_deref()));
+        fs::remove_file(&obj_file).ok();
+        std::process::exit(1);
+    }
+    verbose(&format!("Runtime library ({} allocator): {}", allocator.name(), runtime_lib));
+
+    // Each compiled library's object code is linked from a temporary file
+    let target = triple.as_str().to_string_lossy();
+    let mut library_objects = Vec::new();
+    for library in &program.libraries {
+        let extracted = if library.target != target {
+            Err(format!("'{}' was built for {}, not {}", library.path.display(), library.target, target))
+        } else {
+            let stem = library.path.file_stem().unwrap().to_string_lossy();
+            let library_obj = format!("{}.{}.o", output_base, stem);
+            wslib::object(&library.path)
+                .and_then(|object| fs::write(&library_obj, object).map_err(|e| format!("Cannot write '{}': {}", library_obj, e)))
+                .map(|()| library_obj)
+        };
+        match extracted {
+            Ok(library_obj) => library_objects.push(library_obj),
+            Err(e) => {
+                report_error("Cannot link library", error_codes::LIBRARY_ERROR, &e);
+                fs::remove_file(&obj_file).ok();
+                std::process::exit(1);
+            }
+        }
+    }
 
     // Link with clang (preserve debug information with -g)
+    let target_arg = target_triple.as_ref().map(|t| format!("--target={}", t));
+    let mut link_args = vec!["-g", obj_file.as_str()];
+    link_args.extend(library_objects.iter().map(|obj| obj.as_str()));
+    link_args.extend([runtime_lib.as_str(), "-o", exe_file]);
+    if let Some(arg) = &target_arg {
+        link_args.push(arg);
+    }
+    match link_pie {
+        Some(true) => link_args.push("-pie"),
+        Some(false) => link_args.push("-no-pie"),
+        None => {}
+    }
+    verbose(&format!("Linking: clang {}", link_args.join(" ")));
     let output = Command::new("clang")
-        .args(&["-g", &obj_file, runtime_lib, "-o", exe_file])
+        .args(&link_args)
         .output()
         .expect("Failed to link object file with clang");
 
     if !output.status.success() {
-        eprintln!("Linking failed:");
-        eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if output::json_diagnostics() {
+            report_error("Linking failed", error_codes::LINK_ERROR, &format!("Linking failed: {}", stderr.trim()));
+        } else {
+            eprintln!("{}", paint("31;1", &format!("Linking failed[{}]:", error_codes::LINK_ERROR)));
+            eprintln!("{}", stderr);
+        }
         std::process::exit(1);
     }
 
@@ -282,8 +731,104 @@ fn main() {
             .output();
     }
 
-    // Clean up object file
+    // Optimized code is traced through a source map next to the executable.
+    // One left by an earlier build is removed even if no new one is written
+    fs::remove_file(runtime::source_map::sidecar_path(Path::new(exe_file))).ok();
+    if opt_level > 0 {
+        match source_map::write(Path::new(exe_file)) {
+            Ok(path) => verbose(&format!("Wrote source map {}", path.display())),
+            Err(e) => report_warning(
+                error_codes::SOURCE_MAP_WARNING,
+                &format!("no source map was written, so runtime error traces may be incomplete: {}", e),
+            ),
+        }
+    }
+
+    // Clean up object files
     fs::remove_file(&obj_file).ok();
+    for library_obj in &library_objects {
+        fs::remove_file(library_obj).ok();
+    }
+
+    // stdout carries only diagnostics in JSON mode
+    if verbosity != Verbosity::Quiet && !output::json_diagnostics() {
+        println!("Compiled successfully to '{}'", exe_file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_relocation_model() {
+        assert_eq!(parse_relocation_model("default"), Ok(RelocMode::Default));
+        assert_eq!(parse_relocation_model("pic"), Ok(RelocMode::PIC));
+        assert_eq!(parse_relocation_model("static"), Ok(RelocMode::Static));
+        assert_eq!(parse_relocation_model("dynamic-no-pic"), Ok(RelocMode::DynamicNoPic));
+    }
 
-    println!("Compiled successfully to '{}'", exe_file);
+    #[test]
+    fn test_codegen_optimization_level() {
+        assert_eq!(codegen_optimization_level(0), OptimizationLevel::None);
+        assert_eq!(codegen_optimization_level(1), OptimizationLevel::Less);
+        assert_eq!(codegen_optimization_level(2), OptimizationLevel::Default);
+        assert_eq!(codegen_optimization_level(3), OptimizationLevel::Aggressive);
+    }
+
+    #[test]
+    fn test_runtime_lib_path() {
+        let path = runtime_lib_path(None, Allocator::System);
+        assert!(path.ends_with("/libwadescript_runtime.a"), "{}", path);
+        let path = runtime_lib_path(None, Allocator::Jemalloc);
+        assert!(path.contains("jemalloc/"), "{}", path);
+        let path = runtime_lib_path(Some("aarch64-unknown-linux-gnu"), Allocator::System);
+        assert!(path.contains("aarch64-unknown-linux-gnu/"), "{}", path);
+        assert!(path.ends_with("/libwadescript_runtime.a"), "{}", path);
+    }
+
+    #[test]
+    fn test_aarch64_target_machine() {
+        // Skipped when LLVM was built without the AArch64 backend
+        Target::initialize_all(&InitializationConfig::default());
+        let triple = TargetTriple::create("aarch64-unknown-linux-gnu");
+        let Ok(target) = Target::from_triple(&triple) else {
+            return;
+        };
+        let machine = target
+            .create_target_machine(&triple, "generic", "", OptimizationLevel::None, RelocMode::PIC, CodeModel::Default)
+            .unwrap();
+        let layout = machine.get_target_data().get_data_layout();
+        assert!(layout.as_str().to_string_lossy().starts_with("e-m:e"));
+    }
+
+    #[test]
+    fn test_check_std_version() {
+        let dir = env::temp_dir().join(format!("ws_std_version_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let err = check_std_version(&dir).unwrap_err();
+        assert!(err.contains("has no VERSION file"), "{}", err);
+
+        fs::write(dir.join("VERSION"), "0.0.1\n").unwrap();
+        let err = check_std_version(&dir).unwrap_err();
+        assert!(err.contains("is for WadeScript 0.0.1"), "{}", err);
+        assert!(err.contains(&format!("this compiler is WadeScript {}", COMPILER_VERSION)), "{}", err);
+
+        fs::write(dir.join("VERSION"), format!("{}\n", COMPILER_VERSION)).unwrap();
+        assert_eq!(check_std_version(&dir), Ok(()));
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_repo_std_matches_compiler_version() {
+        let std_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("std");
+        assert_eq!(check_std_version(&std_dir), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_relocation_model_unknown() {
+        let err = parse_relocation_model("pie").unwrap_err();
+        assert!(err.contains("Unknown relocation model 'pie'"));
+    }
 }
@@ -0,0 +1,178 @@
+// Driver output settings: colored diagnostics, verbosity, and message format
+//
+// Color and message format are process-wide switches because diagnostics are
+// printed from several places (parser errors, type errors, link failures).
+
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::lsp::diagnostics::WsError;
+
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(true);
+static JSON_DIAGNOSTICS: AtomicBool = AtomicBool::new(false);
+static PARSE_ERRORS_EXIT: AtomicBool = AtomicBool::new(true);
+// Source file currently being parsed, reported in JSON diagnostics
+static CURRENT_FILE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Value of the `--color` flag
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn parse(value: &str) -> Result<ColorChoice, String> {
+        match value {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!(
+                "Unknown color mode '{}' (expected auto, always, or never)",
+                other
+            )),
+        }
+    }
+
+    /// Decide whether to emit ANSI colors.
+    /// `auto` colors only when stderr is a terminal and NO_COLOR is unset or empty.
+    pub fn resolve(self, no_color_env: Option<&str>, stderr_is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let no_color = no_color_env.map(|v| !v.is_empty()).unwrap_or(false);
+                !no_color && stderr_is_terminal
+            }
+        }
+    }
+
+    /// Resolve against the real environment and stderr
+    pub fn resolve_from_env(self) -> bool {
+        let no_color = std::env::var("NO_COLOR").ok();
+        self.resolve(no_color.as_deref(), std::io::stderr().is_terminal())
+    }
+}
+
+/// Value of the `--message-format` flag
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+impl MessageFormat {
+    pub fn parse(value: &str) -> Result<MessageFormat, String> {
+        match value {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            other => Err(format!(
+                "Unknown message format '{}' (expected human or json)",
+                other
+            )),
+        }
+    }
+}
+
+/// How much the driver prints besides errors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+pub fn set_color_enabled(enabled: bool) {
+    COLOR_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn color_enabled() -> bool {
+    COLOR_ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_message_format(format: MessageFormat) {
+    JSON_DIAGNOSTICS.store(format == MessageFormat::Json, Ordering::Relaxed);
+}
+
+/// True when diagnostics should be printed as JSON lines instead of text
+pub fn json_diagnostics() -> bool {
+    JSON_DIAGNOSTICS.load(Ordering::Relaxed)
+}
+
+/// Parse errors exit the process by default (the compiler and REPL). The LSP and
+/// fuzz targets turn this off to get a panic carrying the located message instead,
+/// which they catch.
+pub fn set_parse_errors_exit(exit: bool) {
+    PARSE_ERRORS_EXIT.store(exit, Ordering::Relaxed);
+}
+
+pub fn parse_errors_exit() -> bool {
+    PARSE_ERRORS_EXIT.load(Ordering::Relaxed)
+}
+
+pub fn set_current_file(file: &str) {
+    if let Ok(mut current) = CURRENT_FILE.lock() {
+        *current = Some(file.to_string());
+    }
+}
+
+pub fn current_file() -> Option<String> {
+    CURRENT_FILE.lock().ok().and_then(|f| f.clone())
+}
+
+/// Print a diagnostic as one JSON line on stdout, tagged with the current file
+/// unless it already names one
+pub fn emit_json_diagnostic(diagnostic: WsError) {
+    let diagnostic = match (&diagnostic.file, current_file()) {
+        (None, Some(file)) => diagnostic.with_file(&file),
+        _ => diagnostic,
+    };
+    println!("{}", diagnostic.to_json());
+}
+
+/// Wrap text in an ANSI style (e.g. "31;1" for bold red) when color is enabled
+pub fn paint(style: &str, text: &str) -> String {
+    if color_enabled() {
+        format!("\x1b[{}m{}\x1b[0m", style, text)
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_choice() {
+        assert_eq!(ColorChoice::parse("auto"), Ok(ColorChoice::Auto));
+        assert_eq!(ColorChoice::parse("always"), Ok(ColorChoice::Always));
+        assert_eq!(ColorChoice::parse("never"), Ok(ColorChoice::Never));
+        assert!(ColorChoice::parse("sometimes").unwrap_err().contains("Unknown color mode"));
+    }
+
+    #[test]
+    fn test_parse_message_format() {
+        assert_eq!(MessageFormat::parse("human"), Ok(MessageFormat::Human));
+        assert_eq!(MessageFormat::parse("json"), Ok(MessageFormat::Json));
+        assert!(MessageFormat::parse("xml").unwrap_err().contains("Unknown message format"));
+    }
+
+    #[test]
+    fn test_resolve_auto() {
+        assert!(ColorChoice::Auto.resolve(None, true));
+        assert!(!ColorChoice::Auto.resolve(None, false));
+        assert!(!ColorChoice::Auto.resolve(Some("1"), true));
+        // An empty NO_COLOR does not disable color
+        assert!(ColorChoice::Auto.resolve(Some(""), true));
+    }
+
+    #[test]
+    fn test_resolve_explicit() {
+        // Explicit choices override NO_COLOR and terminal detection
+        assert!(ColorChoice::Always.resolve(Some("1"), false));
+        assert!(!ColorChoice::Never.resolve(None, true));
+    }
+}
@@ -1,5 +1,8 @@
 use crate::ast::*;
-use crate::lexer::{Lexer, SourceLocation, Token, TokenWithLocation};
+use crate::lexer::{keyword_text, Lexer, SourceLocation, Token, TokenWithLocation};
+use crate::error_codes::PARSE_ERROR;
+use crate::lsp::diagnostics::WsError;
+use crate::output::{emit_json_diagnostic, json_diagnostics, paint, parse_errors_exit};
 
 pub struct Parser {
     tokens: Vec<TokenWithLocation>,
@@ -55,9 +58,21 @@ impl Parser {
 
     fn parse_error(&self, message: &str) -> ! {
         let location = self.peek_location();
-        eprintln!("\n\x1b[31;1mParse Error:\x1b[0m {}", message);
-        eprintln!("  \x1b[90mat {}\x1b[0m", location);
-        eprintln!("  \x1b[90mgot: {:?}\x1b[0m", self.peek());
+        if !parse_errors_exit() {
+            // Same format as the LSP's parse_located_error
+            panic!(
+                "Error at line {}, column {}: {} (got {:?})",
+                location.line, location.column, message, self.peek()
+            );
+        }
+        if json_diagnostics() {
+            let message = format!("{} (got {:?})", message, self.peek());
+            emit_json_diagnostic(WsError::error(message, location.line, location.column).with_code(PARSE_ERROR));
+            std::process::exit(1);
+        }
+        eprintln!("\n{} {}", paint("31;1", &format!("Parse Error[{}]:", PARSE_ERROR)), message);
+        eprintln!("  {}", paint("90", &format!("at {}", location)));
+        eprintln!("  {}", paint("90", &format!("got: {:?}", self.peek())));
         std::process::exit(1);
     }
 
@@ -69,17 +84,62 @@ impl Parser {
         }
     }
 
+    /// Consume the name of a function, class, parameter, field or variable (`what`).
+    /// A keyword in its place gets its own message rather than a token dump.
+    fn expect_name(&mut self, what: &str, message: &str) -> String {
+        if let Token::Identifier(name) = self.peek() {
+            let name = name.clone();
+            self.advance();
+            return name;
+        }
+        match keyword_text(self.peek()) {
+            Some(word) => self.parse_error(&format!(
+                "'{}' is a reserved word and can't be used as a {} name",
+                word, what
+            )),
+            None => self.parse_error(message),
+        }
+    }
+
+    /// After an item of a comma-separated list: consume the comma and tell whether
+    /// another item follows. A trailing comma right before `closing` ends the list
+    fn list_continues(&mut self, closing: &Token) -> bool {
+        self.match_token(&[Token::Comma]) && !self.check(closing)
+    }
+
     fn skip_newlines(&mut self) {
         while self.match_token(&[Token::Newline]) {}
     }
 
+    /// Skip what may separate two statements: newlines, and `;` for several
+    /// statements on one line (`a = 1; b = 2`)
+    fn skip_separators(&mut self) {
+        while self.match_token(&[Token::Newline, Token::Semicolon]) {}
+    }
+
+    /// True where a statement has ended: `return` and `raise` without a value
+    fn at_statement_end(&self) -> bool {
+        matches!(self.peek(), Token::Newline | Token::Semicolon | Token::RightBrace | Token::Eof)
+    }
+
+    /// Consume the `{` opening the body of `what`. Bodies always need braces; a
+    /// short one fits on the condition's line: `if x > 0 { return x }`
+    fn body_start(&mut self, what: &str) {
+        if !self.match_token(&[Token::LeftBrace]) {
+            self.parse_error(&format!(
+                "Expected '{{' after {} (bodies need braces, even on one line: {{ ... }})",
+                what
+            ));
+        }
+    }
+
     pub fn parse(&mut self) -> Program {
         let mut statements = Vec::new();
         self.skip_newlines();
 
         while !self.is_at_end() {
             statements.push(self.statement());
-            self.skip_newlines();
+            self.skip_separators();
         }
 
         let mut program = Program::new();
@@ -92,10 +152,12 @@ impl Parser {
 
         match self.peek() {
             Token::Def => self.function_def(),
+            Token::At => self.decorated_definition(),
             Token::Class => self.class_def(),
             Token::Import => self.import_statement(),
             Token::If => self.if_statement(),
             Token::While => self.while_statement(),
+            Token::Let => self.let_statement(),
             Token::For => self.for_statement(),
             Token::Return => self.return_statement(),
             Token::Break => {
@@ -225,24 +287,56 @@ impl Parser {
         Statement::Import { path }
     }
 
+    /// Parse a top-level function or class definition preceded by one or more decorators
+    fn decorated_definition(&mut self) -> Statement {
+        let mut decorators = Vec::new();
+        while self.check(&Token::At) {
+            decorators.push(self.parse_decorator());
+            self.skip_newlines();
+        }
+        if !self.check(&Token::Class) {
+            return self.function_def_with_decorators(decorators);
+        }
+
+        let mut class = self.class_def();
+        if let Statement::ClassDef { decorators: ref mut class_decorators, .. } = class {
+            *class_decorators = decorators;
+        }
+        class
+    }
+
+    /// Parse a function definition preceded by one or more decorators
+    fn decorated_function_def(&mut self) -> Statement {
+        let mut decorators = Vec::new();
+        while self.check(&Token::At) {
+            decorators.push(self.parse_decorator());
+            self.skip_newlines();
+        }
+        self.function_def_with_decorators(decorators)
+    }
+
+    fn function_def_with_decorators(&mut self, decorators: Vec<crate::ast::Decorator>) -> Statement {
+        if !self.check(&Token::Def) {
+            self.parse_error("Expected 'def' after function decorator");
+        }
+
+        let mut function = self.function_def();
+        if let Statement::FunctionDef { decorators: ref mut fn_decorators, .. } = function {
+            *fn_decorators = decorators;
+        }
+        function
+    }
+
     fn function_def(&mut self) -> Statement {
         self.consume(Token::Def, "Expected 'def'");
-        let name = if let Token::Identifier(n) = self.advance() {
-            n
-        } else {
-            self.parse_error("Expected function name after 'def'");
-        };
+        let name = self.expect_name("function", "Expected function name after 'def'");
 
         self.consume(Token::LeftParen, "Expected '(' after function name");
         let mut params = Vec::new();
 
         if !self.check(&Token::RightParen) {
             loop {
-                let param_name = if let Token::Identifier(n) = self.advance() {
-                    n
-                } else {
-                    self.parse_error("Expected parameter name in function definition");
-                };
+                let param_name = self.expect_name("parameter", "Expected parameter name in function definition");
 
                 self.consume(Token::Colon, "Expected ':' after parameter name");
                 let param_type = self.parse_type();
@@ -260,7 +354,7 @@ impl Parser {
                     default_value,
                 });
 
-                if !self.match_token(&[Token::Comma]) {
+                if !self.list_continues(&Token::RightParen) {
                     break;
                 }
             }
@@ -275,24 +369,32 @@ impl Parser {
         };
 
         self.consume(Token::LeftBrace, "Expected '{' before function body");
-        let body = self.block();
+        let mut body = self.block();
         self.consume(Token::RightBrace, "Expected '}' after function body");
 
+        // Docstring: a string literal as the first statement of the body
+        let docstring = match body.first() {
+            Some(Statement::Expression(Expression::StringLiteral(doc))) => {
+                let doc = doc.clone();
+                body.remove(0);
+                Some(doc)
+            }
+            _ => None,
+        };
+
         Statement::FunctionDef {
             name,
             params,
             return_type,
             body,
+            decorators: Vec::new(),
+            docstring,
         }
     }
 
     fn class_def(&mut self) -> Statement {
         self.consume(Token::Class, "Expected 'class'");
-        let name = if let Token::Identifier(n) = self.advance() {
-            n
-        } else {
-            panic!("Expected class name");
-        };
+        let name = self.expect_name("class", "Expected class name after 'class'");
 
         let base_class = if self.match_token(&[Token::LeftParen]) {
             let base = if let Token::Identifier(n) = self.advance() {
@@ -312,6 +414,15 @@ impl Parser {
 
         self.skip_newlines();
 
+        // Docstring: a string literal before the first field or method
+        let docstring = if let Token::StringLiteral(doc) = self.peek().clone() {
+            self.advance();
+            self.skip_newlines();
+            Some(doc)
+        } else {
+            None
+        };
+
         // Parse field declarations first (with optional decorators)
         while !self.check(&Token::RightBrace) && !self.is_at_end() && !self.check(&Token::Def) {
             // Collect decorators before field
@@ -321,24 +432,38 @@ impl Parser {
                 self.skip_newlines();
             }
 
-            // Field declaration: name: type
-            if let Token::Identifier(field_name) = self.advance() {
-                self.consume(Token::Colon, "Expected ':' after field name");
-                let field_type = self.parse_type();
-                fields.push(crate::ast::Field {
-                    name: field_name,
-                    field_type,
-                    decorators,
-                });
+            // Decorators followed by 'def' belong to the first method
+            if self.check(&Token::Def) {
+                methods.push(self.function_def_with_decorators(decorators));
                 self.skip_newlines();
-            } else {
-                panic!("Expected field name in class body");
+                break;
             }
+
+            // Field declaration: name: type, with an optional default value
+            let field_name = self.expect_name("field", "Expected field name in class body");
+            self.consume(Token::Colon, "Expected ':' after field name");
+            let field_type = self.parse_type();
+            let default_value = if self.match_token(&[Token::Equal]) {
+                Some(self.expression())
+            } else {
+                None
+            };
+            fields.push(crate::ast::Field {
+                name: field_name,
+                field_type,
+                decorators,
+                default_value,
+            });
+            self.skip_newlines();
         }
 
         // Parse method definitions
         while !self.check(&Token::RightBrace) && !self.is_at_end() {
-            methods.push(self.function_def());
+            if self.check(&Token::At) {
+                methods.push(self.decorated_function_def());
+            } else {
+                methods.push(self.function_def());
+            }
             self.skip_newlines();
         }
 
@@ -346,30 +471,32 @@ impl Parser {
 
         Statement::ClassDef {
             name,
-            _base_class: base_class,
+            base_class,
             fields,
             methods,
+            decorators: Vec::new(),
+            docstring,
         }
     }
 
     fn if_statement(&mut self) -> Statement {
         self.consume(Token::If, "Expected 'if'");
         let condition = self.expression();
-        self.consume(Token::LeftBrace, "Expected '{' after if condition");
+        self.body_start("if condition");
         let then_branch = self.block();
         self.consume(Token::RightBrace, "Expected '}' after if body");
 
         let mut elif_branches = Vec::new();
         while self.match_token(&[Token::Elif]) {
             let elif_condition = self.expression();
-            self.consume(Token::LeftBrace, "Expected '{' after elif condition");
+            self.body_start("elif condition");
             let elif_body = self.block();
             self.consume(Token::RightBrace, "Expected '}' after elif body");
             elif_branches.push((elif_condition, elif_body));
         }
 
         let else_branch = if self.match_token(&[Token::Else]) {
-            self.consume(Token::LeftBrace, "Expected '{' after else");
+            self.body_start("else");
             let else_body = self.block();
             self.consume(Token::RightBrace, "Expected '}' after else body");
             Some(else_body)
@@ -385,10 +512,34 @@ impl Parser {
         }
     }
 
+    /// Parse `let name = expr` (type inferred) or `let name: type = expr`
+    fn let_statement(&mut self) -> Statement {
+        self.consume(Token::Let, "Expected 'let'");
+        let name = self.expect_name("variable", "Expected variable name after 'let'");
+
+        let type_annotation = if self.match_token(&[Token::Colon]) {
+            self.parse_type()
+        } else {
+            Type::Inferred
+        };
+
+        if !self.match_token(&[Token::Equal]) {
+            self.parse_error(&format!("Expected '=' after 'let {}' (let declarations need an initializer)", name));
+        }
+        let initializer = self.expression();
+        self.skip_newlines();
+
+        Statement::VarDecl {
+            name,
+            type_annotation,
+            initializer: Some(initializer),
+        }
+    }
+
     fn while_statement(&mut self) -> Statement {
         self.consume(Token::While, "Expected 'while'");
         let condition = self.expression();
-        self.consume(Token::LeftBrace, "Expected '{' after while condition");
+        self.body_start("while condition");
         let body = self.block();
         self.consume(Token::RightBrace, "Expected '}' after while body");
 
@@ -402,15 +553,26 @@ impl Parser {
         } else {
             panic!("Expected variable name in for loop");
         };
+        // `for key, value in dict`
+        let value_variable = if self.match_token(&[Token::Comma]) {
+            if let Token::Identifier(n) = self.advance() {
+                Some(n)
+            } else {
+                panic!("Expected value variable name after ',' in for loop");
+            }
+        } else {
+            None
+        };
 
         self.consume(Token::In, "Expected 'in' in for loop");
         let iterable = self.expression();
-        self.consume(Token::LeftBrace, "Expected '{' after for clause");
+        self.body_start("for clause");
         let body = self.block();
         self.consume(Token::RightBrace, "Expected '}' after for body");
 
         Statement::For {
             variable,
+            value_variable,
             iterable,
             body,
         }
@@ -418,7 +580,7 @@ impl Parser {
 
     fn return_statement(&mut self) -> Statement {
         self.consume(Token::Return, "Expected 'return'");
-        let value = if self.check(&Token::Newline) || self.is_at_end() {
+        let value = if self.at_statement_end() {
             None
         } else {
             Some(self.expression())
@@ -487,7 +649,13 @@ impl Parser {
         let line = self.tokens[self.current].location().line;
         self.consume(Token::Raise, "Expected 'raise'");
 
-        // Parse exception type (required)
+        // Bare `raise` re-raises the exception being handled
+        if self.at_statement_end() {
+            self.skip_newlines();
+            return Statement::Reraise { line };
+        }
+
+        // Parse exception type
         let exception_type = if let Token::Identifier(exc_type) = self.advance() {
             exc_type
         } else {
@@ -498,11 +666,19 @@ impl Parser {
         self.consume(Token::LeftParen, "Expected '(' after exception type");
         let message = self.expression();
         self.consume(Token::RightParen, "Expected ')' after exception message");
+
+        // Optional cause: raise RuntimeError("...") from e
+        let cause = if self.match_token(&[Token::From]) {
+            Some(self.expression())
+        } else {
+            None
+        };
         self.skip_newlines();
 
         Statement::Raise {
             exception_type,
             message,
+            cause,
             line,
         }
     }
@@ -513,7 +689,7 @@ impl Parser {
 
         while !self.check(&Token::RightBrace) && !self.is_at_end() {
             statements.push(self.statement());
-            self.skip_newlines();
+            self.skip_separators();
         }
 
         statements
@@ -553,7 +729,7 @@ impl Parser {
 
                     args.insert(key, value);
 
-                    if !self.match_token(&[Token::Comma]) {
+                    if !self.list_continues(&Token::RightParen) {
                         break;
                     }
                 }
@@ -588,10 +764,30 @@ impl Parser {
                 self.advance();
                 Type::Int
             }
+            Token::I32Type => {
+                self.advance();
+                Type::I32
+            }
+            Token::I16Type => {
+                self.advance();
+                Type::I16
+            }
+            Token::U8Type => {
+                self.advance();
+                Type::U8
+            }
             Token::FloatType => {
                 self.advance();
                 Type::Float
             }
+            Token::BigIntType => {
+                self.advance();
+                Type::BigInt
+            }
+            Token::DecimalType => {
+                self.advance();
+                Type::Decimal
+            }
             Token::BoolType => {
                 self.advance();
                 Type::Bool
@@ -624,6 +820,15 @@ impl Parser {
                 self.consume(Token::RightBracket, "Expected ']' after Optional inner type");
                 return Type::Optional(inner_type);
             }
+            Token::ResultType => {
+                self.advance();
+                self.consume(Token::LeftBracket, "Expected '[' after 'Result'");
+                let ok_type = Box::new(self.parse_type());
+                self.consume(Token::Comma, "Expected ',' after Result value type");
+                let err_type = Box::new(self.parse_type());
+                self.consume(Token::RightBracket, "Expected ']' after Result error type");
+                Type::Result(ok_type, err_type)
+            }
             Token::Identifier(name) => {
                 let name = name.clone();
                 self.advance();
@@ -686,6 +891,23 @@ impl Parser {
                 };
             }
 
+            // For field assignments: p.x += 1 becomes p.x = p.x + 1
+            if let Expression::MemberAccess { object, member } = expr {
+                let new_value = Box::new(Expression::Binary {
+                    left: Box::new(Expression::MemberAccess {
+                        object: object.clone(),
+                        member: member.clone(),
+                    }),
+                    op: binary_op,
+                    right: right_value,
+                });
+                return Expression::FieldAssignment {
+                    object,
+                    field: member,
+                    value: new_value,
+                };
+            }
+
             // For index assignments: arr[i] += 1 becomes arr[i] = arr[i] + 1
             if let Expression::Index { object, index, line } = expr {
                 if let Expression::Variable(obj_name) = *object.clone() {
@@ -721,6 +943,15 @@ impl Parser {
                 };
             }
 
+            // Check if this is a field assignment (e.g., self.x = x)
+            if let Expression::MemberAccess { object, member } = expr {
+                return Expression::FieldAssignment {
+                    object,
+                    field: member,
+                    value,
+                };
+            }
+
             // Check if this is an index assignment (e.g., arr[0] = x or dict["key"] = x)
             if let Expression::Index { object, index, line } = expr {
                 // Extract the object variable name
@@ -791,7 +1022,7 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Expression {
-        let mut expr = self.term();
+        let mut expr = self.null_coalesce();
 
         while self.match_token(&[Token::Less, Token::Greater, Token::LessEqual, Token::GreaterEqual]) {
             let op = match &self.tokens[self.current - 1].token {
@@ -801,7 +1032,7 @@ impl Parser {
                 Token::GreaterEqual => BinaryOp::GreaterEqual,
                 _ => unreachable!(),
             };
-            let right = Box::new(self.term());
+            let right = Box::new(self.null_coalesce());
             expr = Expression::Binary {
                 left: Box::new(expr),
                 op,
@@ -812,6 +1043,22 @@ impl Parser {
         expr
     }
 
+    // `a ?? b` binds tighter than comparisons (`count ?? 0 > 5` compares the unwrapped
+    // value) and is right-associative, so `a ?? b ?? 0` tries a, then b, then 0
+    fn null_coalesce(&mut self) -> Expression {
+        let expr = self.term();
+
+        if self.match_token(&[Token::DoubleQuestion]) {
+            let default = Box::new(self.null_coalesce());
+            return Expression::NullCoalesce {
+                value: Box::new(expr),
+                default,
+            };
+        }
+
+        expr
+    }
+
     fn term(&mut self) -> Expression {
         let mut expr = self.factor();
 
@@ -833,7 +1080,7 @@ impl Parser {
     }
 
     fn factor(&mut self) -> Expression {
-        let mut expr = self.unary();
+        let mut expr = self.cast();
 
         while self.match_token(&[Token::Star, Token::Slash, Token::Percent, Token::DoubleSlash]) {
             let op = match &self.tokens[self.current - 1].token {
@@ -843,7 +1090,7 @@ impl Parser {
                 Token::DoubleSlash => BinaryOp::FloorDivide,
                 _ => unreachable!(),
             };
-            let right = Box::new(self.unary());
+            let right = Box::new(self.cast());
             expr = Expression::Binary {
                 left: Box::new(expr),
                 op,
@@ -854,6 +1101,21 @@ impl Parser {
         expr
     }
 
+    // `x as int` binds tighter than `*` but looser than unary minus: `-x as int` is `(-x) as int`
+    fn cast(&mut self) -> Expression {
+        let mut expr = self.unary();
+
+        while self.match_token(&[Token::As]) {
+            let target = self.parse_type();
+            expr = Expression::Cast {
+                expr: Box::new(expr),
+                target,
+            };
+        }
+
+        expr
+    }
+
     fn unary(&mut self) -> Expression {
         if self.match_token(&[Token::Not, Token::Minus]) {
             let op = match &self.tokens[self.current - 1].token {
@@ -883,49 +1145,55 @@ impl Parser {
         expr
     }
 
+    /// The arguments of a call, up to the closing ')': positional ones, then `name=value` ones
+    fn arguments(&mut self) -> (Vec<Expression>, Vec<(String, Expression)>) {
+        let mut args = Vec::new();
+        let mut named_args = Vec::new();
+        let mut seen_named = false;
+
+        if !self.check(&Token::RightParen) {
+            loop {
+                // Check if this is a named argument: identifier followed by '='
+                let is_named = if let Token::Identifier(_) = self.peek() {
+                    // Look ahead for '='
+                    self.current + 1 < self.tokens.len() &&
+                        matches!(self.tokens[self.current + 1].token, Token::Equal)
+                } else {
+                    false
+                };
+
+                if is_named {
+                    seen_named = true;
+                    let name = if let Token::Identifier(n) = self.advance() {
+                        n
+                    } else {
+                        unreachable!()
+                    };
+                    self.consume(Token::Equal, "Expected '=' in named argument");
+                    let value = self.expression();
+                    named_args.push((name, value));
+                } else {
+                    if seen_named {
+                        self.parse_error("Positional arguments must come before named arguments");
+                    }
+                    args.push(self.expression());
+                }
+
+                if !self.list_continues(&Token::RightParen) {
+                    break;
+                }
+            }
+        }
+        (args, named_args)
+    }
+
     fn call(&mut self) -> Expression {
         let mut expr = self.primary();
 
         loop {
             if self.match_token(&[Token::LeftParen]) {
                 let line = self.tokens[self.current - 1].location().line; // Capture line of '('
-                let mut args = Vec::new();
-                let mut named_args = Vec::new();
-                let mut seen_named = false;
-
-                if !self.check(&Token::RightParen) {
-                    loop {
-                        // Check if this is a named argument: identifier followed by '='
-                        let is_named = if let Token::Identifier(_) = self.peek() {
-                            // Look ahead for '='
-                            self.current + 1 < self.tokens.len() &&
-                                matches!(self.tokens[self.current + 1].token, Token::Equal)
-                        } else {
-                            false
-                        };
-
-                        if is_named {
-                            seen_named = true;
-                            let name = if let Token::Identifier(n) = self.advance() {
-                                n
-                            } else {
-                                unreachable!()
-                            };
-                            self.consume(Token::Equal, "Expected '=' in named argument");
-                            let value = self.expression();
-                            named_args.push((name, value));
-                        } else {
-                            if seen_named {
-                                self.parse_error("Positional arguments must come before named arguments");
-                            }
-                            args.push(self.expression());
-                        }
-
-                        if !self.match_token(&[Token::Comma]) {
-                            break;
-                        }
-                    }
-                }
+                let (args, named_args) = self.arguments();
                 self.consume(Token::RightParen, "Expected ')' after arguments");
                 expr = Expression::Call {
                     callee: Box::new(expr),
@@ -1035,20 +1303,13 @@ impl Parser {
 
                 // Check if this is a method call
                 if self.match_token(&[Token::LeftParen]) {
-                    let mut args = Vec::new();
-                    if !self.check(&Token::RightParen) {
-                        loop {
-                            args.push(self.expression());
-                            if !self.match_token(&[Token::Comma]) {
-                                break;
-                            }
-                        }
-                    }
+                    let (args, named_args) = self.arguments();
                     self.consume(Token::RightParen, "Expected ')' after method arguments");
                     expr = Expression::MethodCall {
                         object: Box::new(expr),
                         method: member,
                         args,
+                        named_args,
                     };
                 } else {
                     expr = Expression::MemberAccess {
@@ -1056,6 +1317,23 @@ impl Parser {
                         member,
                     };
                 }
+            } else if self.match_token(&[Token::QuestionDot]) {
+                let member = if let Token::Identifier(n) = self.peek().clone() {
+                    self.advance();
+                    n
+                } else {
+                    self.parse_error("Expected field name after '?.'");
+                };
+                if self.check(&Token::LeftParen) {
+                    self.parse_error("Optional chaining only supports field access, not method calls");
+                }
+                expr = Expression::OptionalMemberAccess {
+                    object: Box::new(expr),
+                    member,
+                };
+            } else if self.match_token(&[Token::Question]) {
+                // Postfix `?` propagates the error of a Result
+                expr = Expression::Propagate { value: Box::new(expr) };
             } else {
                 break;
             }
@@ -1136,6 +1414,10 @@ impl Parser {
                 self.advance();
                 Expression::FloatLiteral(f)
             }
+            Token::DecimalLiteral(digits) => {
+                self.advance();
+                Expression::DecimalLiteral(digits)
+            }
             Token::StringLiteral(s) => {
                 self.advance();
                 Expression::StringLiteral(s)
@@ -1160,6 +1442,39 @@ impl Parser {
                 self.advance();
                 Expression::Variable(name)
             }
+            // int("42"), float("3.5"), str(42): type names called like functions convert
+            Token::IntType | Token::FloatType | Token::StrType
+                if matches!(self.tokens.get(self.current + 1).map(|t| &t.token), Some(Token::LeftParen)) =>
+            {
+                let name = match self.advance() {
+                    Token::IntType => "int",
+                    Token::FloatType => "float",
+                    _ => "str",
+                };
+                Expression::Variable(name.to_string())
+            }
+            Token::Super => {
+                self.advance();
+                self.consume(Token::Dot, "Expected '.' after 'super'");
+                let method = if let Token::Identifier(n) = self.peek().clone() {
+                    self.advance();
+                    n
+                } else {
+                    self.parse_error("Expected method name after 'super.'");
+                };
+                self.consume(Token::LeftParen, "Expected '(' after super method name");
+                let mut args = Vec::new();
+                if !self.check(&Token::RightParen) {
+                    loop {
+                        args.push(self.expression());
+                        if !self.list_continues(&Token::RightParen) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(Token::RightParen, "Expected ')' after method arguments");
+                Expression::SuperCall { method, args }
+            }
             Token::LeftParen => {
                 self.advance();
 
@@ -1205,7 +1520,7 @@ impl Parser {
                 if !self.check(&Token::RightBracket) {
                     loop {
                         elements.push(self.expression());
-                        if !self.match_token(&[Token::Comma]) {
+                        if !self.list_continues(&Token::RightBracket) {
                             break;
                         }
                     }
@@ -1228,7 +1543,7 @@ impl Parser {
                         let value = self.expression();
                         pairs.push((key, value));
 
-                        if !self.match_token(&[Token::Comma]) {
+                        if !self.list_continues(&Token::RightBrace) {
                             break;
                         }
                     }
@@ -1272,7 +1587,7 @@ mod tests {
         let program = parse_source("def add(a: int, b: int) -> int { return a + b }");
         assert_eq!(program.statements.len(), 1);
 
-        if let Statement::FunctionDef { name, params, return_type, body } = &program.statements[0] {
+        if let Statement::FunctionDef { name, params, return_type, body, .. } = &program.statements[0] {
             assert_eq!(name, "add");
             assert_eq!(params.len(), 2);
             assert_eq!(params[0].name, "a");
@@ -1314,6 +1629,29 @@ class Person {
         }
     }
 
+    #[test]
+    fn test_parse_class_with_base() {
+        let program = parse_source("class Dog(Animal) {\n    tricks: int\n}\n");
+        if let Statement::ClassDef { name, base_class, fields, .. } = &program.statements[0] {
+            assert_eq!(name, "Dog");
+            assert_eq!(base_class.as_deref(), Some("Animal"));
+            assert_eq!(fields.len(), 1);
+        } else {
+            panic!("Expected ClassDef statement");
+        }
+    }
+
+    #[test]
+    fn test_parse_field_defaults() {
+        let program = parse_source("class Counter {\n    name: str\n    count: int = 0\n}\n");
+        if let Statement::ClassDef { fields, .. } = &program.statements[0] {
+            assert!(fields[0].default_value.is_none());
+            assert!(matches!(fields[1].default_value, Some(Expression::IntLiteral(0))));
+        } else {
+            panic!("Expected ClassDef statement");
+        }
+    }
+
     #[test]
     fn test_parse_if_statement() {
         let program = parse_source("if x > 0 { y = 1 }");
@@ -1369,8 +1707,9 @@ if x > 10 {
         let program = parse_source("for i in items { print_int(i) }");
         assert_eq!(program.statements.len(), 1);
 
-        if let Statement::For { variable, iterable, body } = &program.statements[0] {
+        if let Statement::For { variable, value_variable, iterable, body } = &program.statements[0] {
             assert_eq!(variable, "i");
+            assert!(value_variable.is_none());
             assert!(matches!(iterable, Expression::Variable(_)));
             assert_eq!(body.len(), 1);
         } else {
@@ -1378,6 +1717,18 @@ if x > 10 {
         }
     }
 
+    #[test]
+    fn test_parse_for_key_value_loop() {
+        let program = parse_source("for name, age in ages { print_int(age) }");
+
+        if let Statement::For { variable, value_variable, .. } = &program.statements[0] {
+            assert_eq!(variable, "name");
+            assert_eq!(value_variable.as_deref(), Some("age"));
+        } else {
+            panic!("Expected For statement");
+        }
+    }
+
     #[test]
     fn test_parse_break_continue() {
         let program = parse_source("while True { break }");
@@ -1631,6 +1982,26 @@ if x > 10 {
         }
     }
 
+    #[test]
+    fn test_parse_field_assignment() {
+        let program = parse_source("self.x = 42\np.count += 1");
+
+        if let Statement::Expression(Expression::FieldAssignment { object, field, value }) = &program.statements[0] {
+            assert!(matches!(&**object, Expression::Variable(name) if name == "self"));
+            assert_eq!(field, "x");
+            assert!(matches!(**value, Expression::IntLiteral(42)));
+        } else {
+            panic!("Expected FieldAssignment");
+        }
+        // Compound assignment reads the field back
+        if let Statement::Expression(Expression::FieldAssignment { field, value, .. }) = &program.statements[1] {
+            assert_eq!(field, "count");
+            assert!(matches!(&**value, Expression::Binary { left, op: BinaryOp::Add, .. } if matches!(**left, Expression::MemberAccess { .. })));
+        } else {
+            panic!("Expected FieldAssignment");
+        }
+    }
+
     #[test]
     fn test_parse_function_call() {
         let program = parse_source("print_int(42)");
@@ -1647,7 +2018,7 @@ if x > 10 {
     fn test_parse_method_call() {
         let program = parse_source("obj.method(1, 2)");
 
-        if let Statement::Expression(Expression::MethodCall { object, method, args }) = &program.statements[0] {
+        if let Statement::Expression(Expression::MethodCall { object, method, args, .. }) = &program.statements[0] {
             assert!(matches!(**object, Expression::Variable(_)));
             assert_eq!(method, "method");
             assert_eq!(args.len(), 2);
@@ -1656,6 +2027,19 @@ if x > 10 {
         }
     }
 
+    #[test]
+    fn test_parse_method_call_named_args() {
+        let program = parse_source("helpers.scale(5, offset=1)");
+
+        if let Statement::Expression(Expression::MethodCall { args, named_args, .. }) = &program.statements[0] {
+            assert_eq!(args.len(), 1);
+            assert_eq!(named_args.len(), 1);
+            assert_eq!(named_args[0].0, "offset");
+        } else {
+            panic!("Expected MethodCall expression");
+        }
+    }
+
     #[test]
     fn test_parse_member_access() {
         let program = parse_source("x = obj.field");
@@ -1820,7 +2204,7 @@ if x > 10 {
         let program = parse_source(r#"raise ValueError("Test error")"#);
         assert_eq!(program.statements.len(), 1);
 
-        if let Statement::Raise { exception_type, message, line: _ } = &program.statements[0] {
+        if let Statement::Raise { exception_type, message, cause: None, line: _ } = &program.statements[0] {
             assert_eq!(exception_type, "ValueError");
             assert!(matches!(message, Expression::StringLiteral(_)));
         } else {
@@ -1828,6 +2212,31 @@ if x > 10 {
         }
     }
 
+    #[test]
+    fn test_parse_bare_raise() {
+        let program = parse_source("try {\n    x = 1\n} except {\n    raise\n}\n");
+        if let Statement::Try { except_clauses, .. } = &program.statements[0] {
+            assert!(matches!(except_clauses[0].body[0], Statement::Reraise { line: 4 }));
+        } else {
+            panic!("Expected Try statement");
+        }
+    }
+
+    #[test]
+    fn test_parse_raise_from() {
+        let program = parse_source("try {\n    x = 1\n} except ValueError as e {\n    raise RuntimeError(\"failed\") from e\n}\n");
+        if let Statement::Try { except_clauses, .. } = &program.statements[0] {
+            if let Statement::Raise { exception_type, cause: Some(Expression::Variable(cause)), .. } = &except_clauses[0].body[0] {
+                assert_eq!(exception_type, "RuntimeError");
+                assert_eq!(cause, "e");
+            } else {
+                panic!("Expected raise with a cause");
+            }
+        } else {
+            panic!("Expected Try statement");
+        }
+    }
+
     #[test]
     fn test_parse_try_except() {
         let source = r#"
@@ -2048,4 +2457,407 @@ def get_point() -> (int, int) {
             panic!("Expected FunctionDef");
         }
     }
+
+    #[test]
+    fn test_parse_function_docstring() {
+        let source = r#"
+def area(w: int, h: int) -> int {
+    "Area of a w x h rectangle."
+    return w * h
+}
+"#;
+        let program = parse_source(source);
+
+        if let Statement::FunctionDef { docstring, body, .. } = &program.statements[0] {
+            assert_eq!(docstring.as_deref(), Some("Area of a w x h rectangle."));
+            // The docstring is not part of the body
+            assert_eq!(body.len(), 1);
+        } else {
+            panic!("Expected FunctionDef");
+        }
+    }
+
+    #[test]
+    fn test_parse_class_docstring() {
+        let source = r#"
+class Point {
+    "A 2D point."
+    x: int
+    y: int
+
+    def norm(self: Point) -> int {
+        "Manhattan norm."
+        return self.x + self.y
+    }
+}
+"#;
+        let program = parse_source(source);
+
+        if let Statement::ClassDef { docstring, fields, methods, .. } = &program.statements[0] {
+            assert_eq!(docstring.as_deref(), Some("A 2D point."));
+            assert_eq!(fields.len(), 2);
+            if let Statement::FunctionDef { docstring, .. } = &methods[0] {
+                assert_eq!(docstring.as_deref(), Some("Manhattan norm."));
+            } else {
+                panic!("Expected method FunctionDef");
+            }
+        } else {
+            panic!("Expected ClassDef");
+        }
+    }
+
+    #[test]
+    fn test_parse_without_docstring() {
+        let source = r#"
+def f() -> int {
+    /* block comments are ignored */
+    return 1
+}
+"#;
+        let program = parse_source(source);
+
+        if let Statement::FunctionDef { docstring, body, .. } = &program.statements[0] {
+            assert!(docstring.is_none());
+            assert_eq!(body.len(), 1);
+        } else {
+            panic!("Expected FunctionDef");
+        }
+    }
+
+    #[test]
+    fn test_parse_let() {
+        let program = parse_source("let x = 1 + 2\nlet y: float = 1.5\n");
+
+        if let Statement::VarDecl { name, type_annotation, initializer } = &program.statements[0] {
+            assert_eq!(name, "x");
+            assert_eq!(*type_annotation, Type::Inferred);
+            assert!(initializer.is_some());
+        } else {
+            panic!("Expected VarDecl");
+        }
+        if let Statement::VarDecl { type_annotation, .. } = &program.statements[1] {
+            assert_eq!(*type_annotation, Type::Float);
+        } else {
+            panic!("Expected VarDecl");
+        }
+    }
+
+    #[test]
+    fn test_parse_optional_chaining() {
+        let program = parse_source("c: str? = user?.address?.city\n");
+
+        if let Statement::VarDecl { initializer: Some(Expression::OptionalMemberAccess { object, member }), .. } =
+            &program.statements[0]
+        {
+            assert_eq!(member, "city");
+            assert!(matches!(&**object, Expression::OptionalMemberAccess { member, .. } if member == "address"));
+        } else {
+            panic!("Expected optional member access");
+        }
+    }
+
+    #[test]
+    fn test_parse_sized_int_types() {
+        let program = parse_source("def f(b: u8, h: i16) -> i32 {\n    return 0\n}\nbytes: list[u8]? = None\n");
+
+        if let Statement::FunctionDef { params, return_type, .. } = &program.statements[0] {
+            assert_eq!(params[0].param_type, Type::U8);
+            assert_eq!(params[1].param_type, Type::I16);
+            assert_eq!(*return_type, Type::I32);
+        } else {
+            panic!("Expected FunctionDef");
+        }
+        if let Statement::VarDecl { type_annotation, .. } = &program.statements[1] {
+            assert_eq!(*type_annotation, Type::Optional(Box::new(Type::List(Box::new(Type::U8)))));
+        } else {
+            panic!("Expected VarDecl");
+        }
+    }
+
+    #[test]
+    fn test_parse_cast_precedence() {
+        let program = parse_source("n: int = 2 * -x as int\n");
+
+        // 2 * ((-x) as int)
+        if let Statement::VarDecl { initializer: Some(Expression::Binary { op: BinaryOp::Multiply, right, .. }), .. } =
+            &program.statements[0]
+        {
+            if let Expression::Cast { expr, target } = &**right {
+                assert_eq!(*target, Type::Int);
+                assert!(matches!(&**expr, Expression::Unary { op: UnaryOp::Negate, .. }));
+            } else {
+                panic!("Expected Cast, got {:?}", right);
+            }
+        } else {
+            panic!("Expected multiplication");
+        }
+    }
+
+    #[test]
+    fn test_parse_null_coalescing() {
+        let program = parse_source("ok: bool = a ?? b ?? 0 > 1\n");
+
+        // ((a ?? (b ?? 0)) > 1)
+        if let Statement::VarDecl { initializer: Some(Expression::Binary { op: BinaryOp::Greater, left, .. }), .. } =
+            &program.statements[0]
+        {
+            if let Expression::NullCoalesce { value, default } = &**left {
+                assert!(matches!(&**value, Expression::Variable(name) if name == "a"));
+                assert!(matches!(&**default, Expression::NullCoalesce { .. }));
+            } else {
+                panic!("Expected NullCoalesce, got {:?}", left);
+            }
+        } else {
+            panic!("Expected comparison");
+        }
+    }
+
+    #[test]
+    fn test_parse_result_type_and_propagate() {
+        let program = parse_source("n: int = parse(s)? + 1\nr: Result[int, str] = Ok(1)\n");
+
+        // (parse(s)?) + 1
+        if let Statement::VarDecl { initializer: Some(Expression::Binary { op: BinaryOp::Add, left, .. }), .. } =
+            &program.statements[0]
+        {
+            if let Expression::Propagate { value } = &**left {
+                assert!(matches!(&**value, Expression::Call { .. }));
+            } else {
+                panic!("Expected Propagate, got {:?}", left);
+            }
+        } else {
+            panic!("Expected addition");
+        }
+
+        if let Statement::VarDecl { type_annotation, .. } = &program.statements[1] {
+            assert_eq!(*type_annotation, Type::Result(Box::new(Type::Int), Box::new(Type::Str)));
+        } else {
+            panic!("Expected variable declaration");
+        }
+    }
+
+    #[test]
+    fn test_parse_bigint_type() {
+        let program = parse_source("def f(n: bigint) -> list[bigint] {\n    return [n]\n}\n");
+        if let Statement::FunctionDef { params, return_type, .. } = &program.statements[0] {
+            assert_eq!(params[0].param_type, Type::BigInt);
+            assert_eq!(*return_type, Type::List(Box::new(Type::BigInt)));
+        } else {
+            panic!("Expected function definition");
+        }
+    }
+
+    #[test]
+    fn test_parse_decimal_literal() {
+        let program = parse_source("price: decimal = -19.99d\n");
+        if let Statement::VarDecl { type_annotation, initializer, .. } = &program.statements[0] {
+            assert_eq!(*type_annotation, Type::Decimal);
+            let Some(Expression::Unary { op: UnaryOp::Negate, operand }) = initializer else {
+                panic!("Expected negation");
+            };
+            assert!(matches!(operand.as_ref(), Expression::DecimalLiteral(digits) if digits == "19.99"));
+        } else {
+            panic!("Expected variable declaration");
+        }
+    }
+
+    #[test]
+    fn test_parse_function_decorator() {
+        let source = r#"
+@memoize
+def fib(n: int) -> int {
+    return n
+}
+"#;
+        let program = parse_source(source);
+
+        if let Statement::FunctionDef { name, decorators, .. } = &program.statements[0] {
+            assert_eq!(name, "fib");
+            assert_eq!(decorators.len(), 1);
+            assert_eq!(decorators[0].name, "memoize");
+        } else {
+            panic!("Expected FunctionDef");
+        }
+    }
+
+    #[test]
+    fn test_parse_method_decorator() {
+        let source = r#"
+class Counter {
+    count: int
+
+    @memoize
+    def double(self: Counter) -> int {
+        return self.count * 2
+    }
+}
+"#;
+        let program = parse_source(source);
+
+        if let Statement::ClassDef { fields, methods, .. } = &program.statements[0] {
+            assert_eq!(fields.len(), 1);
+            assert_eq!(methods.len(), 1);
+            if let Statement::FunctionDef { decorators, .. } = &methods[0] {
+                assert_eq!(decorators[0].name, "memoize");
+            } else {
+                panic!("Expected FunctionDef");
+            }
+        } else {
+            panic!("Expected ClassDef");
+        }
+    }
+
+    #[test]
+    fn test_parse_super_call() {
+        let program = parse_source("super.describe(1, x)\n");
+        if let Statement::Expression(Expression::SuperCall { method, args }) = &program.statements[0] {
+            assert_eq!(method, "describe");
+            assert_eq!(args.len(), 2);
+        } else {
+            panic!("Expected SuperCall, got {:?}", program.statements[0]);
+        }
+    }
+
+    #[test]
+    fn test_parse_trailing_commas_and_multiline_literals() {
+        let source = r#"
+def total(
+    a: int,
+    b: int = 2,
+) -> int {
+    return a + b
+}
+
+def main() -> int {
+    xs: list[int] = [
+        1,
+        2,
+    ]
+    ages: dict[str, int] = {
+        "ada": 36,
+        "alan": 41,
+    }
+    n: int = total(
+        xs[0],
+        b=xs.get(1,),
+    )
+    return n
+}
+"#;
+        let program = parse_source(source);
+        let Statement::FunctionDef { params, .. } = &program.statements[0] else {
+            panic!("Expected function definition");
+        };
+        assert_eq!(params.len(), 2);
+        let Statement::FunctionDef { body, .. } = &program.statements[1] else {
+            panic!("Expected function definition");
+        };
+        assert_eq!(body.len(), 4);
+        assert!(matches!(&body[0], Statement::VarDecl { initializer: Some(Expression::ListLiteral { elements }), .. } if elements.len() == 2));
+        assert!(matches!(&body[1], Statement::VarDecl { initializer: Some(Expression::DictLiteral { pairs }), .. } if pairs.len() == 2));
+        assert!(matches!(
+            &body[2],
+            Statement::VarDecl { initializer: Some(Expression::Call { args, named_args, .. }), .. } if args.len() == 1 && named_args.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_parse_type_name_conversion_calls() {
+        let program = parse_source("n: int = int(\"42\")\ns: str = str(float(\"3.5\"))\n");
+        let callee_name = |statement: &Statement| match statement {
+            Statement::VarDecl { initializer: Some(Expression::Call { callee, .. }), .. } => match callee.as_ref() {
+                Expression::Variable(name) => name.clone(),
+                other => panic!("Expected a named callee, got {:?}", other),
+            },
+            other => panic!("Expected a call initializer, got {:?}", other),
+        };
+        assert_eq!(callee_name(&program.statements[0]), "int");
+        assert_eq!(callee_name(&program.statements[1]), "str");
+    }
+
+    #[test]
+    fn test_parse_abstract_class_and_method() {
+        let source = r#"
+@abstract
+class Shape {
+    @abstract
+    def area(self: Shape) -> float {
+        pass
+    }
+}
+"#;
+        let program = parse_source(source);
+
+        if let Statement::ClassDef { decorators, methods, .. } = &program.statements[0] {
+            assert_eq!(decorators[0].name, "abstract");
+            if let Statement::FunctionDef { decorators, .. } = &methods[0] {
+                assert_eq!(decorators[0].name, "abstract");
+            } else {
+                panic!("Expected FunctionDef");
+            }
+        } else {
+            panic!("Expected ClassDef");
+        }
+    }
+
+    #[test]
+    fn test_parse_error_panics_when_not_exiting() {
+        crate::output::set_parse_errors_exit(false);
+        let result = std::panic::catch_unwind(|| parse_source("def main() -> int {\n    return (1 2\n}\n"));
+        let payload = result.unwrap_err();
+        let message = payload.downcast_ref::<String>().unwrap();
+        assert!(message.starts_with("Error at line 2, column 15: Expected ')'"), "{}", message);
+        let error = crate::lsp::diagnostics::parse_located_error(message).unwrap();
+        assert_eq!((error.line, error.column), (2, 15));
+    }
+
+    #[test]
+    fn test_keyword_as_name_is_reported() {
+        crate::output::set_parse_errors_exit(false);
+        let message = |source: &'static str| {
+            let payload = std::panic::catch_unwind(|| parse_source(source)).unwrap_err();
+            payload.downcast_ref::<String>().unwrap().clone()
+        };
+        let err = message("def list() -> int {\n    return 0\n}\n");
+        assert!(err.contains("'list' is a reserved word and can't be used as a function name"), "{}", err);
+        let err = message("def f(from: int) -> int {\n    return 0\n}\n");
+        assert!(err.contains("'from' is a reserved word and can't be used as a parameter name"), "{}", err);
+        let err = message("class Point {\n    str: int\n}\n");
+        assert!(err.contains("'str' is a reserved word and can't be used as a field name"), "{}", err);
+        let err = message("def main() -> int {\n    let None = 1\n    return 0\n}\n");
+        assert!(err.contains("'None' is a reserved word and can't be used as a variable name"), "{}", err);
+        let err = message("class 3 {\n}\n");
+        assert!(err.contains("Expected class name after 'class'"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_semicolons_and_one_line_blocks() {
+        let source = "def f(x: int) -> int {\n    a: int = 1; b: int = 2;\n    if x > 0 { return a } else { a = b; return }\n    while x > 0 { x = x - 1; continue }\n    try { raise ValueError(\"v\") } except { raise }\n    return a + b\n}\n";
+        let program = parse_source(source);
+        let Statement::FunctionDef { body, .. } = &program.statements[0] else {
+            panic!("Expected function definition");
+        };
+        assert_eq!(body.len(), 6);
+        let Statement::If { then_branch, else_branch: Some(else_branch), .. } = &body[2] else {
+            panic!("Expected if statement");
+        };
+        assert!(matches!(&then_branch[..], [Statement::Return(Some(_))]));
+        assert!(matches!(&else_branch[..], [Statement::Expression(Expression::Assignment { .. }), Statement::Return(None)]));
+        let Statement::While { body: loop_body, .. } = &body[3] else {
+            panic!("Expected while loop");
+        };
+        assert!(matches!(&loop_body[..], [Statement::Expression(Expression::Assignment { .. }), Statement::Continue]));
+        let Statement::Try { except_clauses, .. } = &body[4] else {
+            panic!("Expected try statement");
+        };
+        assert!(matches!(&except_clauses[0].body[..], [Statement::Reraise { .. }]));
+    }
+
+    #[test]
+    fn test_body_without_braces_is_reported() {
+        crate::output::set_parse_errors_exit(false);
+        let payload = std::panic::catch_unwind(|| parse_source("def f(x: int) -> int {\n    if x > 0 return x\n    return 0\n}\n")).unwrap_err();
+        let err = payload.downcast_ref::<String>().unwrap();
+        assert!(err.contains("Expected '{' after if condition (bodies need braces, even on one line"), "{}", err);
+    }
 }
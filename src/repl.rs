@@ -1,8 +1,14 @@
 //! WadeScript REPL (Read-Eval-Print Loop)
 //!
 //! Interactive interpreter using LLVM JIT compilation.
+//!
+//! A session starts by evaluating the user's preamble (`~/.wsreplrc`) and then
+//! the definitions of any files given with `--load`.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 use inkwell::context::Context;
@@ -15,6 +21,41 @@ use crate::lexer::Lexer;
 use crate::parser::Parser;
 use crate::typechecker::TypeChecker;
 
+/// Preamble evaluated at the start of every session, relative to $HOME
+const RC_FILE: &str = ".wsreplrc";
+
+/// Startup options: `wadescript repl [--load <file>]... [--no-rc]`
+#[derive(Debug, Default, PartialEq)]
+pub struct ReplOptions {
+    /// Files whose definitions are loaded before the first prompt
+    pub load: Vec<String>,
+    /// Skip the preamble
+    pub no_rc: bool,
+}
+
+impl ReplOptions {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = ReplOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--load" => {
+                    let file = args.next().ok_or("--load needs a file")?;
+                    options.load.push(file.clone());
+                }
+                "--no-rc" => options.no_rc = true,
+                _ => return Err(format!("Unknown repl option '{}'", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// The preamble's path, if $HOME is set
+fn rc_path() -> Option<PathBuf> {
+    env::var_os("HOME").filter(|home| !home.is_empty()).map(|home| PathBuf::from(home).join(RC_FILE))
+}
+
 /// Persistent variable in REPL
 struct ReplVariable {
     ws_type: Type,
@@ -54,16 +95,24 @@ impl Repl {
     fn type_size(ws_type: &Type) -> usize {
         match ws_type {
             Type::Int => 8,    // i64
+            Type::I32 => 4,
+            Type::I16 => 2,
+            Type::U8 => 1,
             Type::Float => 8,  // f64
             Type::Bool => 1,   // i1 (stored as byte)
             Type::Str => 8,    // pointer
+            Type::BigInt => 8, // pointer to runtime value
+            Type::Decimal => 8,
             Type::Void => 0,
             Type::List(_) => 8,  // pointer
             Type::Dict(_, _) => 8,  // pointer
             Type::Array(inner, size) => Self::type_size(inner) * (*size as usize),
             Type::Optional(_) => 8,  // pointer (nullable)
+            // Stored inline as struct { ok flag, value, error }
+            Type::Result(ok, err) => 1 + Self::type_size(ok) + Self::type_size(err),
             Type::Custom(_) => 8,  // pointer to struct
             Type::Exception => 8,  // pointer
+            Type::Inferred => unreachable!("let types are resolved before allocation"),
             Type::Tuple(types) => {
                 // Tuples are stored inline as struct, sum of element sizes
                 types.iter().map(|t| Self::type_size(t)).sum()
@@ -103,10 +152,12 @@ impl Repl {
         // Leak the context to get 'static lifetime for JIT
         let context = Box::leak(Box::new(Context::create()));
         let jit = JitEngine::new(context)?;
+        let mut type_checker = TypeChecker::new();
+        type_checker.set_reserved_names(CodeGen::runtime_function_names());
 
         Ok(Repl {
             context,
-            type_checker: TypeChecker::new(),
+            type_checker,
             variables: HashMap::new(),
             user_functions: HashMap::new(),
             functions: HashMap::new(),
@@ -116,13 +167,15 @@ impl Repl {
     }
 
     /// Run the REPL main loop
-    pub fn run(&mut self) {
+    pub fn run(&mut self, options: &ReplOptions) {
         use std::io;
         use std::os::unix::io::AsRawFd;
 
-        println!("WadeScript REPL v0.1.0");
+        println!("WadeScript REPL v{}", env!("CARGO_PKG_VERSION"));
         println!("Type 'exit' or Ctrl+D to quit\n");
 
+        self.bootstrap(options);
+
         // Check if stdin is a TTY
         let stdin_is_tty = unsafe { libc::isatty(io::stdin().as_raw_fd()) } != 0;
 
@@ -135,6 +188,65 @@ impl Repl {
         println!("Goodbye!");
     }
 
+    /// Evaluate the preamble, then load each `--load` file. A failure is
+    /// reported and the session starts without it.
+    fn bootstrap(&mut self, options: &ReplOptions) {
+        if let Some(path) = rc_path().filter(|path| !options.no_rc && path.exists()) {
+            let result = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| self.eval(&source));
+            if let Err(e) = result {
+                eprintln!("\x1b[31mError in {}:\x1b[0m {}", path.display(), e);
+            }
+        }
+
+        for file in &options.load {
+            match self.load_file(file) {
+                Ok(names) => println!("Loaded {}: {}", file, names.join(", ")),
+                Err(e) => eprintln!("\x1b[31mError loading {}:\x1b[0m {}", file, e),
+            }
+        }
+    }
+
+    /// Compile the functions and classes of a file and its imports into the
+    /// session. The file's `main` is its entry point, not a definition, so it
+    /// is left out and nothing runs. Returns the names that were defined.
+    fn load_file(&mut self, file: &str) -> Result<Vec<String>, String> {
+        let mut imported = HashSet::new();
+        let loaded = crate::load_program_with_imports(file, &mut imported)?;
+        if let Some(library) = loaded.libraries.first() {
+            return Err(format!(
+                "it imports the compiled library '{}', which the REPL can't link; import the module's source instead",
+                library.path.display()
+            ));
+        }
+        let definitions = Self::definitions(loaded);
+        let names = definitions
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::FunctionDef { name, .. } | Statement::ClassDef { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        self.eval_program(definitions)?;
+        Ok(names)
+    }
+
+    /// Keep only the functions (other than `main`) and classes of a program
+    fn definitions(program: Program) -> Program {
+        let statements = program
+            .statements
+            .into_iter()
+            .filter(|stmt| match stmt {
+                Statement::FunctionDef { name, .. } => name != "main",
+                Statement::ClassDef { .. } => true,
+                _ => false,
+            })
+            .collect();
+        Program { statements, modules: program.modules, libraries: program.libraries }
+    }
+
     /// Run interactive mode with rustyline
     fn run_interactive(&mut self) {
         let mut rl = match DefaultEditor::new() {
@@ -305,6 +417,23 @@ impl Repl {
         vars
     }
 
+    /// Infer the types of top-level `let` declarations, in order, so later
+    /// declarations in the same input can refer to earlier ones
+    fn resolve_let_types(&mut self, statements: &mut [Statement]) -> Result<(), String> {
+        for stmt in statements {
+            if let Statement::VarDecl { name, type_annotation, initializer } = stmt {
+                if *type_annotation == Type::Inferred {
+                    let init_expr = initializer
+                        .as_ref()
+                        .ok_or_else(|| format!("Cannot infer type of '{}' without an initializer", name))?;
+                    *type_annotation = self.type_checker.infer_initializer_type(name, init_expr)?;
+                }
+                self.type_checker.register_repl_variable(name, type_annotation);
+            }
+        }
+        Ok(())
+    }
+
     /// Evaluate a REPL input
     fn eval(&mut self, input: &str) -> Result<Option<String>, String> {
         // Parse the input
@@ -313,19 +442,26 @@ impl Repl {
 
         // Try to parse as a program (statements)
         let program = parser.parse();
+        self.eval_program(program)
+    }
 
+    /// Type check, compile and run a parsed REPL input
+    fn eval_program(&mut self, mut program: Program) -> Result<Option<String>, String> {
         if program.statements.is_empty() {
             return Ok(None);
         }
 
-        // Extract variable declarations from this input
-        let new_vars = Self::extract_var_declarations(&program.statements);
-
         // Register existing persisted variables with type checker
         for (name, var) in &self.variables {
             self.type_checker.register_repl_variable(name, &var.ws_type);
         }
 
+        // Variables are allocated before compilation, so `let` types must be known now
+        self.resolve_let_types(&mut program.statements)?;
+
+        // Extract variable declarations from this input
+        let new_vars = Self::extract_var_declarations(&program.statements);
+
         // Allocate memory for new variables BEFORE compilation
         for (name, var_type) in &new_vars {
             self.allocate_variable(name, var_type);
@@ -337,10 +473,10 @@ impl Repl {
         let entry_name = self.jit.next_entry_name();
 
         // Wrap in function for compilation
-        let wrapped_program = self.wrap_in_function(&program, &entry_name);
+        let mut wrapped_program = self.wrap_in_function(&program, &entry_name);
 
         // Type check the wrapped program
-        self.type_checker.check_program(&wrapped_program)?;
+        self.type_checker.check_program(&mut wrapped_program)?;
 
         // Compile to LLVM IR
         let module = self.compile_repl_input_direct(&wrapped_program, &new_vars)?;
@@ -376,6 +512,7 @@ impl Repl {
 
         // Declare runtime functions
         codegen.declare_runtime_functions();
+        codegen.set_function_names(self.type_checker.names().clone());
 
         // Declare all previously defined user functions as external
         for (name, func_info) in &self.user_functions {
@@ -446,6 +583,8 @@ impl Repl {
             params: vec![],
             return_type: Type::Int,
             body,
+            decorators: vec![],
+            docstring: None,
         };
 
         // Add module-level definitions first, then entry function
@@ -454,7 +593,9 @@ impl Repl {
 
         Program {
             statements: all_statements,
-            modules: std::collections::HashMap::new(),
+            // Loaded files keep their imports' module names for qualified calls
+            modules: program.modules.clone(),
+            libraries: Vec::new(),
         }
     }
 }
@@ -464,3 +605,31 @@ impl Default for Repl {
         Self::new().expect("Failed to create REPL")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(ReplOptions::parse(&[]).unwrap(), ReplOptions::default());
+        let options = ReplOptions::parse(&args(&["--load", "a.ws", "--no-rc", "--load", "b.ws"])).unwrap();
+        assert_eq!(options.load, vec!["a.ws", "b.ws"]);
+        assert!(options.no_rc);
+        assert!(ReplOptions::parse(&args(&["--load"])).is_err());
+        assert!(ReplOptions::parse(&args(&["a.ws"])).is_err());
+    }
+
+    #[test]
+    fn test_definitions_leave_out_main_and_statements() {
+        let source = "def area(w: int, h: int) -> int {\n    return w * h\n}\n\ndef main() -> int {\n    return area(2, 3)\n}\n\nx: int = 1\n";
+        let program = Parser::new(Lexer::new(source.to_string())).parse();
+        let definitions = Repl::definitions(program);
+        assert_eq!(definitions.statements.len(), 1);
+        assert!(matches!(&definitions.statements[0], Statement::FunctionDef { name, .. } if name == "area"));
+    }
+}
@@ -0,0 +1,211 @@
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::ffi::CStr;
+use std::mem::size_of;
+use std::os::raw::c_char;
+use std::ptr;
+
+use super::exceptions::raise;
+use super::rc::{rc_alloc, rc_free};
+use super::string::alloc_str;
+
+/// Value used for a null handle, e.g. a bigint variable that was never assigned
+static ZERO: BigInt = BigInt::ZERO;
+
+/// Borrow the value behind a bigint handle
+fn get<'a>(value: *const BigInt) -> &'a BigInt {
+    if value.is_null() {
+        &ZERO
+    } else {
+        unsafe { &*value }
+    }
+}
+
+/// Move a bigint into a new RC allocation and hand out its handle.
+/// Bigints are immutable, so handles are shared by retaining them.
+fn new_handle(value: BigInt) -> *mut BigInt {
+    let handle = rc_alloc(size_of::<BigInt>() as i64) as *mut BigInt;
+    unsafe { handle.write(value) };
+    handle
+}
+
+/// Free a bigint whose reference count has reached zero, like list_free
+#[no_mangle]
+pub extern "C" fn bigint_free(value: *mut BigInt) {
+    if value.is_null() {
+        return;
+    }
+    unsafe { ptr::drop_in_place(value) };
+    rc_free(value as *mut u8);
+}
+
+fn check_divisor(divisor: &BigInt) {
+    if divisor.is_zero() {
+        raise("ZeroDivisionError", "bigint division by zero".to_string());
+    }
+}
+
+/// Convert an int to a bigint
+#[no_mangle]
+pub extern "C" fn bigint_from_int(value: i64) -> *mut BigInt {
+    new_handle(BigInt::from(value))
+}
+
+/// Parse a decimal string (optional leading sign, `_` separators allowed)
+#[no_mangle]
+pub extern "C" fn bigint_from_str(text: *const c_char) -> *mut BigInt {
+    let text = unsafe { CStr::from_ptr(text) }.to_string_lossy();
+    let digits = text.trim().replace('_', "");
+    match digits.parse::<BigInt>() {
+        Ok(value) => new_handle(value),
+        Err(_) => raise("ValueError", format!("invalid bigint literal: '{}'", text)),
+    }
+}
+
+/// Decimal representation of a bigint
+#[no_mangle]
+pub extern "C" fn bigint_to_str(value: *const BigInt) -> *mut u8 {
+    unsafe { alloc_str(get(value).to_string().as_bytes()) }
+}
+
+/// Convert to int, keeping the low 64 bits like a sized integer cast
+#[no_mangle]
+pub extern "C" fn bigint_to_int(value: *const BigInt) -> i64 {
+    let low_bits = get(value) & BigInt::from(u64::MAX);
+    low_bits.to_u64().unwrap_or(0) as i64
+}
+
+/// Convert to the nearest float (inf when out of range)
+#[no_mangle]
+pub extern "C" fn bigint_to_float(value: *const BigInt) -> f64 {
+    get(value).to_f64().unwrap_or(f64::NAN)
+}
+
+#[no_mangle]
+pub extern "C" fn bigint_add(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    new_handle(get(a) + get(b))
+}
+
+#[no_mangle]
+pub extern "C" fn bigint_sub(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    new_handle(get(a) - get(b))
+}
+
+#[no_mangle]
+pub extern "C" fn bigint_mul(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    new_handle(get(a) * get(b))
+}
+
+/// Division truncating toward zero, matching int division
+#[no_mangle]
+pub extern "C" fn bigint_div(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    check_divisor(get(b));
+    new_handle(get(a) / get(b))
+}
+
+/// Remainder with the sign of the dividend, matching int `%`
+#[no_mangle]
+pub extern "C" fn bigint_rem(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    check_divisor(get(b));
+    new_handle(get(a) % get(b))
+}
+
+#[no_mangle]
+pub extern "C" fn bigint_neg(value: *const BigInt) -> *mut BigInt {
+    new_handle(-get(value))
+}
+
+/// Raise to a non-negative int power
+#[no_mangle]
+pub extern "C" fn bigint_pow(base: *const BigInt, exponent: i64) -> *mut BigInt {
+    let Ok(exponent) = u32::try_from(exponent) else {
+        raise("ValueError", format!("bigint exponent out of range: {}", exponent));
+    };
+    new_handle(get(base).pow(exponent))
+}
+
+/// (base ** exponent) % modulus without building the full power.
+/// The result takes the sign of the modulus.
+#[no_mangle]
+pub extern "C" fn bigint_pow_mod(
+    base: *const BigInt,
+    exponent: *const BigInt,
+    modulus: *const BigInt,
+) -> *mut BigInt {
+    let (exponent, modulus) = (get(exponent), get(modulus));
+    check_divisor(modulus);
+    if exponent.is_negative() {
+        raise("ValueError", "pow_mod() exponent must be non-negative".to_string());
+    }
+    new_handle(get(base).modpow(exponent, modulus))
+}
+
+/// Three-way comparison: -1, 0 or 1
+#[no_mangle]
+pub extern "C" fn bigint_cmp(a: *const BigInt, b: *const BigInt) -> i64 {
+    get(a).cmp(get(b)) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn read(s: *mut u8) -> String {
+        unsafe { CStr::from_ptr(s as *const i8).to_str().unwrap().to_string() }
+    }
+
+    fn parse(text: &str) -> *mut BigInt {
+        let text = CString::new(text).unwrap();
+        bigint_from_str(text.as_ptr())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(read(bigint_to_str(bigint_from_int(-42))), "-42");
+        assert_eq!(read(bigint_to_str(parse("123456789012345678901234567890"))), "123456789012345678901234567890");
+        assert_eq!(read(bigint_to_str(parse(" 1_000_000 "))), "1000000");
+        assert_eq!(read(bigint_to_str(ptr::null())), "0");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let big = parse("9223372036854775807");
+        let one = bigint_from_int(1);
+        assert_eq!(read(bigint_to_str(bigint_add(big, one))), "9223372036854775808");
+        assert_eq!(read(bigint_to_str(bigint_mul(big, big))), "85070591730234615847396907784232501249");
+        assert_eq!(read(bigint_to_str(bigint_sub(one, big))), "-9223372036854775806");
+        assert_eq!(read(bigint_to_str(bigint_neg(one))), "-1");
+        assert_eq!(read(bigint_to_str(bigint_pow(bigint_from_int(2), 100))), "1267650600228229401496703205376");
+    }
+
+    #[test]
+    fn test_division_truncates_like_int() {
+        let (a, b) = (bigint_from_int(-7), bigint_from_int(2));
+        assert_eq!(bigint_to_int(bigint_div(a, b)), -7 / 2);
+        assert_eq!(bigint_to_int(bigint_rem(a, b)), -7 % 2);
+    }
+
+    #[test]
+    fn test_pow_mod() {
+        let result = bigint_pow_mod(bigint_from_int(4), bigint_from_int(13), bigint_from_int(497));
+        assert_eq!(bigint_to_int(result), 445);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let big = parse("18446744073709551617"); // 2**64 + 1
+        assert_eq!(bigint_to_int(big), 1);
+        assert_eq!(bigint_to_int(bigint_from_int(-5)), -5);
+        assert_eq!(bigint_to_float(bigint_from_int(3)), 3.0);
+        assert_eq!(bigint_to_float(big), 18446744073709551617.0);
+    }
+
+    #[test]
+    fn test_cmp() {
+        let (a, b) = (bigint_from_int(1), parse("100000000000000000000"));
+        assert_eq!(bigint_cmp(a, b), -1);
+        assert_eq!(bigint_cmp(b, a), 1);
+        assert_eq!(bigint_cmp(a, bigint_from_int(1)), 0);
+    }
+}
@@ -0,0 +1,108 @@
+//! Byte-order conversions for WadeScript
+//!
+//! Packing ints into `list[u8]` and back, in big-endian (network) or
+//! little-endian order, for binary file formats and wire protocols.
+
+use super::compress::{bytes_to_list, list_to_bytes};
+use super::exceptions::raise;
+use super::list::List;
+
+/// The low `size` bytes of `value`, least significant first
+/// Raises ValueError unless 1 <= size <= 8, and OverflowError if the value
+/// fits neither the signed nor the unsigned range of `size` bytes
+fn int_to_le_bytes(value: i64, size: i64, function: &str) -> Vec<u8> {
+    if !(1..=8).contains(&size) {
+        raise("ValueError", format!("{}() size must be between 1 and 8, got {}", function, size));
+    }
+    let bits = size * 8;
+    if bits < 64 {
+        let min = -(1i64 << (bits - 1));
+        let max = (1i64 << bits) - 1;
+        if value < min || value > max {
+            raise("OverflowError", format!("{}() value {} does not fit in {} bytes", function, value, size));
+        }
+    }
+    value.to_le_bytes()[..size as usize].to_vec()
+}
+
+/// Unsigned int from up to 8 bytes, least significant first
+/// Raises ValueError for more than 8 bytes
+fn int_from_le_bytes(bytes: &[u8], function: &str) -> i64 {
+    if bytes.len() > 8 {
+        raise("ValueError", format!("{}() takes at most 8 bytes, got {}", function, bytes.len()));
+    }
+    let mut buffer = [0u8; 8];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    i64::from_le_bytes(buffer)
+}
+
+/// `value` as `size` bytes, most significant first (network byte order)
+#[no_mangle]
+pub extern "C" fn bytes_pack_be(value: i64, size: i64) -> *mut List {
+    let mut bytes = int_to_le_bytes(value, size, "to_bytes_be");
+    bytes.reverse();
+    bytes_to_list(&bytes)
+}
+
+/// `value` as `size` bytes, least significant first
+#[no_mangle]
+pub extern "C" fn bytes_pack_le(value: i64, size: i64) -> *mut List {
+    bytes_to_list(&int_to_le_bytes(value, size, "to_bytes_le"))
+}
+
+/// Unsigned int from big-endian bytes
+#[no_mangle]
+pub extern "C" fn bytes_unpack_be(bytes: *const List) -> i64 {
+    let mut bytes = list_to_bytes(bytes);
+    bytes.reverse();
+    int_from_le_bytes(&bytes, "from_bytes_be")
+}
+
+/// Unsigned int from little-endian bytes
+#[no_mangle]
+pub extern "C" fn bytes_unpack_le(bytes: *const List) -> i64 {
+    int_from_le_bytes(&list_to_bytes(bytes), "from_bytes_le")
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_int_to_le_bytes() {
+        assert_eq!(int_to_le_bytes(0x1234, 2, "t"), vec![0x34, 0x12]);
+        assert_eq!(int_to_le_bytes(255, 1, "t"), vec![0xff]);
+        // Negative values are written in two's complement
+        assert_eq!(int_to_le_bytes(-1, 2, "t"), vec![0xff, 0xff]);
+        assert_eq!(int_to_le_bytes(-128, 1, "t"), vec![0x80]);
+        assert_eq!(int_to_le_bytes(i64::MIN, 8, "t"), vec![0, 0, 0, 0, 0, 0, 0, 0x80]);
+    }
+
+    #[test]
+    fn test_int_from_le_bytes() {
+        assert_eq!(int_from_le_bytes(&[0x34, 0x12], "t"), 0x1234);
+        // Fewer than 8 bytes read as unsigned
+        assert_eq!(int_from_le_bytes(&[0xff, 0xff], "t"), 65535);
+        assert_eq!(int_from_le_bytes(&[0xff; 8], "t"), -1);
+        assert_eq!(int_from_le_bytes(&[], "t"), 0);
+    }
+
+    #[test]
+    fn test_pack_unpack_round_trip() {
+        for (value, size) in [(0x0102_0304, 4), (1, 8), (-2, 8), (65535, 2)] {
+            let be = bytes_pack_be(value, size);
+            let be_bytes = list_to_bytes(be);
+            assert_eq!(be_bytes.len(), size as usize);
+            let le = bytes_pack_le(value, size);
+            let mut reversed = list_to_bytes(le);
+            reversed.reverse();
+            assert_eq!(be_bytes, reversed);
+            assert_eq!(bytes_unpack_be(be), bytes_unpack_le(le));
+        }
+        assert_eq!(list_to_bytes(bytes_pack_be(0x0102_0304, 4)), vec![1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Compression runtime for WadeScript
+//!
+//! gzip and zlib for `list[u8]` byte lists, plus reading and writing gzip
+//! files as text, using flate2.
+
+use std::fs::File;
+use std::io::{Read, Write};
+
+use flate2::read::{GzDecoder, MultiGzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+
+use super::exceptions::raise;
+use super::http::{alloc_c_string, c_str_to_string};
+use super::list::{list_create, list_push_i64, List};
+use super::rc::ELEMS_PLAIN;
+
+/// Copy a `list[u8]` (one byte per slot) into a byte vector
+pub(crate) fn list_to_bytes(list: *const List) -> Vec<u8> {
+    if list.is_null() {
+        return Vec::new();
+    }
+    unsafe {
+        let list_ref = &*list;
+        if list_ref.length == 0 {
+            return Vec::new();
+        }
+        std::slice::from_raw_parts(list_ref.data, list_ref.length as usize)
+            .iter()
+            .map(|&slot| slot as u8)
+            .collect()
+    }
+}
+
+/// Build a new `list[u8]` from bytes
+pub(crate) fn bytes_to_list(bytes: &[u8]) -> *mut List {
+    let list = list_create(ELEMS_PLAIN);
+    for &byte in bytes {
+        list_push_i64(list, byte as i64);
+    }
+    list
+}
+
+fn encode<W: Write>(mut encoder: W, data: &[u8]) -> W {
+    // Writing to a Vec can't fail
+    encoder.write_all(data).unwrap();
+    encoder
+}
+
+fn decode<R: Read>(mut decoder: R, format: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    if let Err(e) = decoder.read_to_end(&mut bytes) {
+        raise("ValueError", format!("invalid {} data: {}", format, e));
+    }
+    bytes
+}
+
+fn gzip_bytes(data: &[u8]) -> Vec<u8> {
+    encode(GzEncoder::new(Vec::new(), Compression::default()), data)
+        .finish()
+        .unwrap()
+}
+
+fn zlib_bytes(data: &[u8]) -> Vec<u8> {
+    encode(ZlibEncoder::new(Vec::new(), Compression::default()), data)
+        .finish()
+        .unwrap()
+}
+
+/// Compress a byte list to the gzip format
+#[no_mangle]
+pub extern "C" fn compress_gzip(data: *const List) -> *mut List {
+    bytes_to_list(&gzip_bytes(&list_to_bytes(data)))
+}
+
+/// Decompress gzip data, raising ValueError if it is not valid gzip
+#[no_mangle]
+pub extern "C" fn compress_gunzip(data: *const List) -> *mut List {
+    let bytes = list_to_bytes(data);
+    bytes_to_list(&decode(GzDecoder::new(bytes.as_slice()), "gzip"))
+}
+
+/// Compress a byte list to the zlib format
+#[no_mangle]
+pub extern "C" fn compress_zlib(data: *const List) -> *mut List {
+    bytes_to_list(&zlib_bytes(&list_to_bytes(data)))
+}
+
+/// Decompress zlib data, raising ValueError if it is not valid zlib
+#[no_mangle]
+pub extern "C" fn compress_unzlib(data: *const List) -> *mut List {
+    let bytes = list_to_bytes(data);
+    bytes_to_list(&decode(ZlibDecoder::new(bytes.as_slice()), "zlib"))
+}
+
+/// Read a gzip-compressed text file
+/// Raises RuntimeError if the file can't be opened, ValueError if it is not
+/// valid gzip or the text is not UTF-8
+#[no_mangle]
+pub extern "C" fn compress_read_gzip(path: *const u8) -> *mut u8 {
+    let path_str = unsafe { c_str_to_string(path) }.unwrap_or_default();
+    let file = match File::open(&path_str) {
+        Ok(file) => file,
+        Err(e) => raise("RuntimeError", format!("cannot open '{}': {}", path_str, e)),
+    };
+    // Concatenated gzip files (`cat a.gz b.gz`) hold several members; read them all like zcat
+    let bytes = decode(MultiGzDecoder::new(file), "gzip");
+    match String::from_utf8(bytes) {
+        Ok(text) => alloc_c_string(&text),
+        Err(_) => raise("ValueError", format!("'{}' does not contain UTF-8 text", path_str)),
+    }
+}
+
+/// Write text to a gzip-compressed file, replacing it if it exists
+/// Raises RuntimeError if the file can't be written
+#[no_mangle]
+pub extern "C" fn compress_write_gzip(path: *const u8, content: *const u8) {
+    let path_str = unsafe { c_str_to_string(path) }.unwrap_or_default();
+    let content_str = unsafe { c_str_to_string(content) }.unwrap_or_default();
+    let result = File::create(&path_str).and_then(|file| {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        encoder.write_all(content_str.as_bytes())?;
+        encoder.finish().map(|_| ())
+    });
+    if let Err(e) = result {
+        raise("RuntimeError", format!("cannot write '{}': {}", path_str, e));
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::list::{list_free, list_get_i64};
+    use std::ffi::{CStr, CString};
+    use std::os::raw::c_char;
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let text = b"GET /index.html 200\n".repeat(50);
+        let list = bytes_to_list(&text);
+        let compressed = compress_gzip(list);
+        // gzip magic number, and repeated lines shrink
+        assert_eq!(list_get_i64(compressed, 0), 0x1f);
+        assert_eq!(list_get_i64(compressed, 1), 0x8b);
+        assert!(unsafe { (*compressed).length } < text.len() as i64);
+
+        let restored = compress_gunzip(compressed);
+        assert_eq!(list_to_bytes(restored), text);
+        for list in [list, compressed, restored] {
+            list_free(list);
+        }
+    }
+
+    #[test]
+    fn test_zlib_round_trip() {
+        let data = [0u8, 255, 7, 0, 128];
+        let compressed = zlib_bytes(&data);
+        assert_eq!(compressed[0], 0x78);
+        let list = bytes_to_list(&compressed);
+        let restored = compress_unzlib(list);
+        assert_eq!(list_to_bytes(restored), data);
+        list_free(list);
+        list_free(restored);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(list_to_bytes(std::ptr::null()).is_empty());
+        let restored = decode(GzDecoder::new(gzip_bytes(&[]).as_slice()), "gzip");
+        assert!(restored.is_empty());
+    }
+
+    #[test]
+    fn test_gzip_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("ws_compress_{}.log.gz", std::process::id()));
+        let path_c = CString::new(path.to_str().unwrap()).unwrap();
+        let content = CString::new("line one\nline two\n").unwrap();
+        compress_write_gzip(path_c.as_ptr() as *const u8, content.as_ptr() as *const u8);
+
+        let raw = std::fs::read(&path).unwrap();
+        assert_eq!(&raw[..2], &[0x1f, 0x8b]);
+        let text = compress_read_gzip(path_c.as_ptr() as *const u8);
+        let text_str = unsafe { CStr::from_ptr(text as *const c_char) }.to_str().unwrap();
+        assert_eq!(text_str, "line one\nline two\n");
+        std::fs::remove_file(&path).ok();
+    }
+}
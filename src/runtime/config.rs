@@ -0,0 +1,698 @@
+//! Configuration file runtime for WadeScript
+//!
+//! Parses TOML and INI text into a dict of sections, each a dict of keys to
+//! values as strings: `[server]` then `port = 8080` becomes
+//! `{"server": {"port": "8080"}}`. Keys before any section are in the section
+//! "", and nested TOML tables get dotted names ("server.tls"). std/config.ws
+//! converts values to ints, floats, bools and lists on demand.
+//!
+//! TOML arrays are kept as text in a canonical form, `["a", "b"]`, which
+//! `config_list_items` splits again. Arrays of tables (`[[name]]`) are not
+//! supported.
+
+use std::ffi::CString;
+
+use super::dict::{dict_create, dict_set, Dict};
+use super::exceptions::raise;
+use super::list::{list_create, list_push_i64, List};
+use super::rc::{ELEMS_DICT, ELEMS_PLAIN};
+use super::string::{alloc_str, as_bytes};
+
+/// Sections in the order they first appear, each with its keys and values
+type Sections = Vec<(String, Vec<(String, String)>)>;
+
+fn section_mut<'a>(sections: &'a mut Sections, name: &str) -> &'a mut Vec<(String, String)> {
+    let index = match sections.iter().position(|(section, _)| section == name) {
+        Some(index) => index,
+        None => {
+            sections.push((name.to_string(), Vec::new()));
+            sections.len() - 1
+        }
+    };
+    &mut sections[index].1
+}
+
+fn insert(sections: &mut Sections, section: &str, key: &str, value: String) -> Result<(), String> {
+    let entries = section_mut(sections, section);
+    if entries.iter().any(|(existing, _)| existing == key) {
+        let place = if section.is_empty() { "at the top level".to_string() } else { format!("in [{}]", section) };
+        return Err(format!("duplicate key '{}' {}", key, place));
+    }
+    entries.push((key.to_string(), value));
+    Ok(())
+}
+
+/// `parts` appended to the section name `base` with "."
+fn nested_name(base: &str, parts: &[String]) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    if !base.is_empty() {
+        names.push(base);
+    }
+    names.extend(parts.iter().map(String::as_str));
+    names.join(".")
+}
+
+/// A text as a TOML basic string, for the canonical form of arrays
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// ============================================================================
+// TOML
+// ============================================================================
+
+enum Value {
+    /// A string, or a number, bool or date as text
+    Text(String),
+    Array(Vec<Value>),
+    /// An inline table: dotted key paths and their values
+    Table(Vec<(Vec<String>, Value)>),
+}
+
+impl Value {
+    /// The value as stored in a section: arrays in canonical form
+    fn into_text(self) -> String {
+        match self {
+            Value::Text(text) => text,
+            Value::Array(items) => {
+                let items: Vec<String> = items
+                    .into_iter()
+                    .map(|item| match item {
+                        Value::Text(text) => quote(&text),
+                        array => array.into_text(),
+                    })
+                    .collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Table(_) => unreachable!("tables are stored as sections"),
+        }
+    }
+}
+
+/// A bare value: true/false, an integer (normalized to decimal without "_"),
+/// a float, or a date and time, which are kept as written
+fn scalar(token: &str) -> Result<String, String> {
+    if token == "true" || token == "false" {
+        return Ok(token.to_string());
+    }
+    let digits = token.replace('_', "");
+    let (negative, unsigned) = match digits.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, digits.strip_prefix('+').unwrap_or(&digits)),
+    };
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(number) = unsigned.strip_prefix(prefix) {
+            return match i64::from_str_radix(number, radix) {
+                Ok(value) if !negative => Ok(value.to_string()),
+                _ => Err(format!("invalid integer '{}'", token)),
+            };
+        }
+    }
+    if let Ok(value) = digits.parse::<i64>() {
+        return Ok(value.to_string());
+    }
+    let bytes = token.as_bytes();
+    let is_number = digits.chars().any(|c| c.is_ascii_digit()) && digits.parse::<f64>().is_ok();
+    let is_special = matches!(unsigned, "inf" | "nan");
+    let is_date = bytes.len() >= 5 && bytes[..4].iter().all(u8::is_ascii_digit) && bytes[4] == b'-';
+    let is_time = bytes.len() >= 3 && bytes[..2].iter().all(u8::is_ascii_digit) && bytes[2] == b':';
+    if is_number || is_special {
+        Ok(digits)
+    } else if is_date || is_time {
+        Ok(token.to_string())
+    } else {
+        Err(format!("invalid value '{}' (strings need quotes)", token))
+    }
+}
+
+struct TomlParser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl TomlParser {
+    fn new(text: &str) -> Self {
+        TomlParser { chars: text.chars().collect(), pos: 0, line: 1 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected '{}', found '{}'", expected, c)),
+            None => Err(format!("expected '{}', found the end of the text", expected)),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.next();
+        }
+    }
+
+    /// Skip spaces, line breaks and comments between statements and array items
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => {
+                    self.next();
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.next();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// After a statement: only spaces and a comment before the line break
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.next();
+            }
+        }
+        if self.peek() == Some('\r') && self.peek_at(1) == Some('\n') {
+            self.next();
+        }
+        match self.next() {
+            None | Some('\n') => Ok(()),
+            Some(c) => Err(format!("unexpected '{}' after the value", c)),
+        }
+    }
+
+    /// A key, possibly dotted: `name`, `"quoted key"`, `server.tls.cert`
+    fn key_path(&mut self) -> Result<Vec<String>, String> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_spaces();
+            let part = match self.peek() {
+                Some('"') => {
+                    self.next();
+                    self.basic_string()?
+                }
+                Some('\'') => {
+                    self.next();
+                    self.literal_string()?
+                }
+                _ => {
+                    let mut bare = String::new();
+                    while let Some(c) = self.peek().filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')) {
+                        bare.push(c);
+                        self.next();
+                    }
+                    if bare.is_empty() {
+                        return Err("expected a key".to_string());
+                    }
+                    bare
+                }
+            };
+            parts.push(part);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(parts);
+            }
+            self.next();
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let c = match self.next() {
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(u @ ('u' | 'U')) => {
+                let len = if u == 'u' { 4 } else { 8 };
+                let hex: String = (0..len).filter_map(|_| self.next()).collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid escape '\\{}{}'", u, hex))?
+            }
+            Some(c) => return Err(format!("invalid escape '\\{}'", c)),
+            None => return Err("unterminated string".to_string()),
+        };
+        if c == '\0' {
+            return Err("strings can't contain NUL characters".to_string());
+        }
+        Ok(c)
+    }
+
+    /// The rest of a "..." string, after the opening quote
+    fn basic_string(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some('"') => {
+                    self.next();
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.next();
+                    text.push(self.escape()?);
+                }
+                Some(c) => {
+                    self.next();
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    /// The rest of a '...' string, after the opening quote
+    fn literal_string(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c) => {
+                    self.next();
+                    if c == '\'' {
+                        return Ok(text);
+                    }
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    /// The rest of a """...""" or '''...''' string, after the opening quotes.
+    /// A line break right after them is dropped, and in """ strings a "\" at
+    /// the end of a line joins it to the next non-blank text
+    fn multiline_string(&mut self, quote: char) -> Result<String, String> {
+        if self.peek() == Some('\r') && self.peek_at(1) == Some('\n') {
+            self.next();
+        }
+        if self.peek() == Some('\n') {
+            self.next();
+        }
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some(c) if c == quote && self.peek() == Some(quote) && self.peek_at(1) == Some(quote) => {
+                    self.next();
+                    self.next();
+                    // Up to two more quotes belong to the string: """a""""" is a""
+                    for _ in 0..2 {
+                        if self.peek() != Some(quote) {
+                            break;
+                        }
+                        self.next();
+                        text.push(quote);
+                    }
+                    return Ok(text);
+                }
+                Some('\\') if quote == '"' => {
+                    if matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+                        while matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+                            self.next();
+                        }
+                    } else {
+                        text.push(self.escape()?);
+                    }
+                }
+                Some(c) => text.push(c),
+                None => return Err("unterminated multi-line string".to_string()),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some(q @ ('"' | '\'')) => {
+                self.next();
+                if self.peek() == Some(q) && self.peek_at(1) == Some(q) {
+                    self.next();
+                    self.next();
+                    self.multiline_string(q).map(Value::Text)
+                } else if q == '"' {
+                    self.basic_string().map(Value::Text)
+                } else {
+                    self.literal_string().map(Value::Text)
+                }
+            }
+            Some('[') => {
+                self.next();
+                let mut items = Vec::new();
+                loop {
+                    self.skip_blank();
+                    if self.peek() == Some(']') {
+                        self.next();
+                        return Ok(Value::Array(items));
+                    }
+                    let item = self.value()?;
+                    if matches!(item, Value::Table(_)) {
+                        return Err("tables inside arrays are not supported".to_string());
+                    }
+                    items.push(item);
+                    self.skip_blank();
+                    match self.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::Array(items)),
+                        _ => return Err("expected ',' or ']' in array".to_string()),
+                    }
+                }
+            }
+            Some('{') => {
+                self.next();
+                let mut entries = Vec::new();
+                self.skip_spaces();
+                if self.peek() == Some('}') {
+                    self.next();
+                    return Ok(Value::Table(entries));
+                }
+                loop {
+                    let path = self.key_path()?;
+                    self.expect('=')?;
+                    self.skip_spaces();
+                    entries.push((path, self.value()?));
+                    self.skip_spaces();
+                    match self.next() {
+                        Some(',') => {}
+                        Some('}') => return Ok(Value::Table(entries)),
+                        _ => return Err("expected ',' or '}' in inline table".to_string()),
+                    }
+                }
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(c) = self.peek().filter(|c| !matches!(c, ' ' | '\t' | '\r' | '\n' | ',' | ']' | '}' | '#')) {
+                    token.push(c);
+                    self.next();
+                }
+                // A date and time may be separated by a space: 1979-05-27 07:32:00
+                let time_follows = self.peek() == Some(' ')
+                    && self.peek_at(1).is_some_and(|c| c.is_ascii_digit())
+                    && self.peek_at(2).is_some_and(|c| c.is_ascii_digit())
+                    && self.peek_at(3) == Some(':');
+                if token.len() == 10 && token.as_bytes()[4] == b'-' && time_follows {
+                    token.push(' ');
+                    self.next();
+                    while let Some(c) = self.peek().filter(|c| !matches!(c, ' ' | '\t' | '\r' | '\n' | ',' | ']' | '}' | '#')) {
+                        token.push(c);
+                        self.next();
+                    }
+                }
+                if token.is_empty() {
+                    return Err("expected a value".to_string());
+                }
+                scalar(&token).map(Value::Text)
+            }
+        }
+    }
+
+    /// Store `value` under the dotted `path` in `section`. Inline tables
+    /// become sections of their own
+    fn assign(sections: &mut Sections, section: &str, path: &[String], value: Value) -> Result<(), String> {
+        let (key, parents) = path.split_last().expect("key paths are never empty");
+        let target = nested_name(section, parents);
+        match value {
+            Value::Table(entries) => {
+                let table = nested_name(&target, std::slice::from_ref(key));
+                section_mut(sections, &table);
+                for (entry_path, entry_value) in entries {
+                    Self::assign(sections, &table, &entry_path, entry_value)?;
+                }
+                Ok(())
+            }
+            value => insert(sections, &target, key, value.into_text()),
+        }
+    }
+
+    fn document(&mut self) -> Result<Sections, String> {
+        let mut sections: Sections = vec![(String::new(), Vec::new())];
+        let mut current = String::new();
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => return Ok(sections),
+                Some('[') if self.peek_at(1) == Some('[') => {
+                    return Err("arrays of tables ([[name]]) are not supported".to_string());
+                }
+                Some('[') => {
+                    self.next();
+                    let path = self.key_path()?;
+                    self.expect(']')?;
+                    current = path.join(".");
+                    section_mut(&mut sections, &current);
+                }
+                Some(_) => {
+                    let path = self.key_path()?;
+                    self.expect('=')?;
+                    self.skip_spaces();
+                    let value = self.value()?;
+                    Self::assign(&mut sections, &current, &path, value)?;
+                }
+            }
+            self.end_of_line()?;
+        }
+    }
+}
+
+fn parse_toml(text: &str) -> Result<Sections, String> {
+    let mut parser = TomlParser::new(text);
+    parser.document().map_err(|message| format!("line {}: {}", parser.line, message))
+}
+
+/// The items of an array in canonical form, nested arrays as text
+fn list_items(text: &str) -> Option<Vec<String>> {
+    let mut parser = TomlParser::new(text);
+    match parser.value() {
+        Ok(Value::Array(items)) if parser.peek().is_none() => Some(
+            items
+                .into_iter()
+                .map(|item| item.into_text())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// INI
+// ============================================================================
+
+/// A value without surrounding quotes, or without a comment started by ";"
+/// or "#" after a space when it isn't quoted
+fn ini_value(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    if bytes.len() >= 2 && matches!(bytes[0], b'"' | b'\'') && bytes[bytes.len() - 1] == bytes[0] {
+        return raw[1..raw.len() - 1].to_string();
+    }
+    let end = raw
+        .char_indices()
+        .find(|&(i, c)| matches!(c, ';' | '#') && raw[..i].ends_with([' ', '\t']))
+        .map_or(raw.len(), |(i, _)| i);
+    raw[..end].trim_end().to_string()
+}
+
+fn parse_ini(text: &str) -> Result<Sections, String> {
+    let mut sections: Sections = vec![(String::new(), Vec::new())];
+    let mut current = String::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        let error = |message: &str| format!("line {}: {}", index + 1, message);
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header.strip_suffix(']').ok_or_else(|| error("expected ']' after the section name"))?.trim();
+            if name.is_empty() {
+                return Err(error("empty section name"));
+            }
+            current = name.to_string();
+            section_mut(&mut sections, &current);
+            continue;
+        }
+        let split = line.find(['=', ':']).ok_or_else(|| error("expected 'key = value'"))?;
+        let key = line[..split].trim();
+        if key.is_empty() {
+            return Err(error("expected a key before '='"));
+        }
+        insert(&mut sections, &current, key, ini_value(line[split + 1..].trim())).map_err(|message| error(&message))?;
+    }
+    Ok(sections)
+}
+
+// ============================================================================
+// Exported functions
+// ============================================================================
+
+/// Sections as a new dict[str, dict[str, str]]
+fn sections_to_dict(sections: Sections) -> *mut Dict {
+    let dict = dict_create(ELEMS_PLAIN, ELEMS_DICT);
+    for (name, entries) in sections {
+        let section = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        for (key, value) in entries {
+            // dict_set copies the key
+            let key = CString::new(key).unwrap_or_default();
+            dict_set(section, key.as_ptr() as *const u8, unsafe { alloc_str(value.as_bytes()) } as i64);
+        }
+        let name = CString::new(name).unwrap_or_default();
+        dict_set(dict, name.as_ptr() as *const u8, section as i64);
+    }
+    dict
+}
+
+fn parse_with(text: *const u8, format: &str, parse: fn(&str) -> Result<Sections, String>) -> *mut Dict {
+    let text = String::from_utf8_lossy(unsafe { as_bytes(text) }).into_owned();
+    match parse(&text) {
+        Ok(sections) => sections_to_dict(sections),
+        Err(message) => raise("ValueError", format!("invalid {}: {}", format, message)),
+    }
+}
+
+/// Parse TOML text into a new dict of sections
+/// Raises ValueError with the line number if the text isn't valid
+#[no_mangle]
+pub extern "C" fn config_parse_toml(text: *const u8) -> *mut Dict {
+    parse_with(text, "TOML", parse_toml)
+}
+
+/// Parse INI text into a new dict of sections
+/// Raises ValueError with the line number if a line isn't a section, a
+/// `key = value` pair or a comment
+#[no_mangle]
+pub extern "C" fn config_parse_ini(text: *const u8) -> *mut Dict {
+    parse_with(text, "INI", parse_ini)
+}
+
+/// The items of an array value (`[1, "two"]`) as a new list[str]
+/// Raises ValueError if the value isn't an array
+#[no_mangle]
+pub extern "C" fn config_list_items(value: *const u8) -> *mut List {
+    let text = String::from_utf8_lossy(unsafe { as_bytes(value) }).into_owned();
+    let Some(items) = list_items(&text) else {
+        raise("ValueError", format!("config value '{}' is not a list", text));
+    };
+    let list = list_create(ELEMS_PLAIN);
+    for item in items {
+        list_push_i64(list, unsafe { alloc_str(item.as_bytes()) } as i64);
+    }
+    list
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'a>(sections: &'a Sections, section: &str, key: &str) -> Option<&'a str> {
+        let (_, entries) = sections.iter().find(|(name, _)| name == section)?;
+        entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_toml_values() {
+        let text = r#"
+# App settings
+name = "demo"   # trailing comment
+debug = true
+
+[server]
+host = 'localhost'
+port = 8_080
+ratio = 0.75
+mask = 0xff
+started = 1979-05-27 07:32:00Z
+ports = [ 80, 443,
+  8080, ]   # multi-line with a trailing comma
+tags = ["a, b", 'c']
+tls = { cert = "cert.pem", key.path = "key.pem" }
+
+[server."quoted.name"]
+motd = """
+Hello "world"\
+   again"""
+quoted = '''say 'hi'''''
+"#;
+        let sections = parse_toml(text).unwrap();
+        assert_eq!(get(&sections, "", "name"), Some("demo"));
+        assert_eq!(get(&sections, "", "debug"), Some("true"));
+        assert_eq!(get(&sections, "server", "host"), Some("localhost"));
+        assert_eq!(get(&sections, "server", "port"), Some("8080"));
+        assert_eq!(get(&sections, "server", "ratio"), Some("0.75"));
+        assert_eq!(get(&sections, "server", "mask"), Some("255"));
+        assert_eq!(get(&sections, "server", "started"), Some("1979-05-27 07:32:00Z"));
+        assert_eq!(get(&sections, "server", "ports"), Some(r#"["80", "443", "8080"]"#));
+        assert_eq!(get(&sections, "server", "tags"), Some(r#"["a, b", "c"]"#));
+        assert_eq!(get(&sections, "server.tls", "cert"), Some("cert.pem"));
+        assert_eq!(get(&sections, "server.tls.key", "path"), Some("key.pem"));
+        assert_eq!(get(&sections, "server.quoted.name", "motd"), Some("Hello \"world\"again"));
+        assert_eq!(get(&sections, "server.quoted.name", "quoted"), Some("say 'hi''"));
+    }
+
+    #[test]
+    fn test_toml_errors() {
+        let error = |text: &str| parse_toml(text).unwrap_err();
+        assert_eq!(error("a = 1\nb = hello\n"), "line 2: invalid value 'hello' (strings need quotes)");
+        assert_eq!(error("a = 1\na = 2\n"), "line 2: duplicate key 'a' at the top level");
+        assert_eq!(error("[[items]]\n"), "line 1: arrays of tables ([[name]]) are not supported");
+        assert_eq!(error("a = \"open\n"), "line 1: unterminated string");
+        assert_eq!(error("a = '''x\n"), "line 2: unterminated multi-line string");
+        assert_eq!(error("a = 1 2\n"), "line 1: unexpected '2' after the value");
+    }
+
+    #[test]
+    fn test_list_items() {
+        assert_eq!(list_items(r#"["80", "a, \"b\""]"#).unwrap(), vec!["80", "a, \"b\""]);
+        assert_eq!(list_items(r#"[["1", "2"], []]"#).unwrap(), vec![r#"["1", "2"]"#, "[]"]);
+        assert!(list_items("8080").is_none());
+        assert!(list_items(r#"["a"] x"#).is_none());
+    }
+
+    #[test]
+    fn test_ini() {
+        let text = "; comment\nglobal = yes\n\n[database]\nhost = db.local ; inline\nport: 5432\nname = \"main ; db\"\n\n[empty]\n";
+        let sections = parse_ini(text).unwrap();
+        assert_eq!(get(&sections, "", "global"), Some("yes"));
+        assert_eq!(get(&sections, "database", "host"), Some("db.local"));
+        assert_eq!(get(&sections, "database", "port"), Some("5432"));
+        assert_eq!(get(&sections, "database", "name"), Some("main ; db"));
+        assert!(sections.iter().any(|(name, entries)| name == "empty" && entries.is_empty()));
+
+        assert_eq!(parse_ini("[db]\njust text\n").unwrap_err(), "line 2: expected 'key = value'");
+        assert_eq!(parse_ini("[db]\na = 1\na = 2\n").unwrap_err(), "line 3: duplicate key 'a' in [db]");
+    }
+}
@@ -0,0 +1,189 @@
+//! CSV runtime for WadeScript
+//!
+//! Parsing CSV text into rows of fields and writing rows back out, following
+//! RFC 4180: fields containing the delimiter, quotes or line breaks are
+//! quoted, with quotes inside doubled. std/csv.ws builds dicts keyed by the
+//! header row and reads and writes files on top of these.
+
+use super::exceptions::raise;
+use super::list::{list_create, list_push_i64, List};
+use super::rc::{ELEMS_LIST, ELEMS_PLAIN};
+use super::string::{alloc_str, as_bytes};
+
+/// The delimiter as one character. It can't be a quote or a line break, since
+/// those would make fields ambiguous
+fn checked_delimiter(delimiter: *const u8, function: &str) -> char {
+    let text = String::from_utf8_lossy(unsafe { as_bytes(delimiter) }).into_owned();
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !matches!(c, '"' | '\r' | '\n') => c,
+        _ => raise(
+            "ValueError",
+            format!("{}() delimiter must be one character other than a quote or line break, got '{}'", function, text),
+        ),
+    }
+}
+
+/// Split CSV text into rows of fields. "\n" and "\r\n" both end a row, a
+/// final line break is optional, and blank lines are skipped. A quote inside
+/// an unquoted field is kept as-is
+fn parse(text: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field = String::new();
+    // Whether the current row has anything in it, so blank lines can be skipped
+    let mut row_started = false;
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                let start_line = line;
+                row_started = true;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => return Err(format!("unterminated quoted field starting on line {}", start_line)),
+                    }
+                }
+            }
+            c if c == delimiter => {
+                row.push(std::mem::take(&mut field));
+                row_started = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if row_started || !field.is_empty() {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                row_started = false;
+                line += 1;
+            }
+            c => field.push(c),
+        }
+    }
+    if row_started || !field.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// One row as a line of CSV, without the line break
+fn format_row(fields: &[String], delimiter: char) -> String {
+    // A lone empty field would be a blank line, which parsing skips
+    if fields.len() == 1 && fields[0].is_empty() {
+        return "\"\"".to_string();
+    }
+    let quoted: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([delimiter, '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    quoted.join(&delimiter.to_string())
+}
+
+/// Parse CSV text into a new list[list[str]], one list per row
+/// Raises ValueError for an invalid delimiter or a quoted field that never ends
+#[no_mangle]
+pub extern "C" fn csv_parse(text: *const u8, delimiter: *const u8) -> *mut List {
+    let delimiter = checked_delimiter(delimiter, "csv_parse");
+    let text = String::from_utf8_lossy(unsafe { as_bytes(text) }).into_owned();
+    let rows = match parse(&text, delimiter) {
+        Ok(rows) => rows,
+        Err(message) => raise("ValueError", format!("invalid CSV: {}", message)),
+    };
+
+    let list = list_create(ELEMS_LIST);
+    for row in rows {
+        let fields = list_create(ELEMS_PLAIN);
+        for field in row {
+            list_push_i64(fields, unsafe { alloc_str(field.as_bytes()) } as i64);
+        }
+        list_push_i64(list, fields as i64);
+    }
+    list
+}
+
+/// A list[str] of fields as one CSV line (no line break), quoting fields
+/// that need it
+/// Raises ValueError for an invalid delimiter
+#[no_mangle]
+pub extern "C" fn csv_format_row(fields: *const List, delimiter: *const u8) -> *mut u8 {
+    let delimiter = checked_delimiter(delimiter, "csv_format_row");
+    let fields: Vec<String> = match unsafe { fields.as_ref() } {
+        Some(list) => (0..list.length)
+            .map(|i| {
+                let field = unsafe { *list.data.add(i as usize) } as *const u8;
+                String::from_utf8_lossy(unsafe { as_bytes(field) }).into_owned()
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    unsafe { alloc_str(format_row(&fields, delimiter).as_bytes()) }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(text: &str) -> Vec<Vec<String>> {
+        parse(text, ',').unwrap()
+    }
+
+    #[test]
+    fn test_parse_plain_and_quoted_fields() {
+        assert_eq!(rows("name,age\nada,36\n"), vec![vec!["name", "age"], vec!["ada", "36"]]);
+        assert_eq!(rows("a,b\r\nc,d"), vec![vec!["a", "b"], vec!["c", "d"]], "CRLF and no final line break");
+        assert_eq!(
+            rows("\"Lovelace, Ada\",\"said \"\"hi\"\"\",\"two\nlines\"\n"),
+            vec![vec!["Lovelace, Ada", "said \"hi\"", "two\nlines"]]
+        );
+        assert_eq!(rows("a,,\n,\n"), vec![vec!["a", "", ""], vec!["", ""]], "empty fields are kept");
+        assert_eq!(rows("a\n\n\nb\n"), vec![vec!["a"], vec!["b"]], "blank lines are skipped");
+        assert_eq!(rows("\"\"\n"), vec![vec![""]], "a quoted empty field is a row");
+        assert_eq!(rows("5\" disk,x"), vec![vec!["5\" disk", "x"]], "a quote inside an unquoted field");
+        assert!(rows("").is_empty());
+        assert_eq!(parse("a;b\n", ';').unwrap(), vec![vec!["a", "b"]]);
+    }
+
+    #[test]
+    fn test_unterminated_quote() {
+        let err = parse("a,b\n\"oops,c\n", ',').unwrap_err();
+        assert_eq!(err, "unterminated quoted field starting on line 2");
+    }
+
+    #[test]
+    fn test_format_row_round_trips() {
+        let fields: Vec<String> = ["plain", "with, comma", "say \"hi\"", "two\nlines", ""]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let line = format_row(&fields, ',');
+        assert_eq!(line, "plain,\"with, comma\",\"say \"\"hi\"\"\",\"two\nlines\",");
+        assert_eq!(parse(&line, ',').unwrap(), vec![fields]);
+        assert_eq!(format_row(&["a;b".to_string(), "c,d".to_string()], ';'), "\"a;b\";c,d");
+        assert_eq!(parse(&format_row(&[String::new()], ','), ',').unwrap(), vec![vec![""]]);
+    }
+}
@@ -0,0 +1,325 @@
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::ffi::CStr;
+use std::mem::size_of;
+use std::os::raw::c_char;
+use std::ptr;
+
+use super::exceptions::raise;
+use super::float::smart_format;
+use super::rc::{rc_alloc, rc_free};
+use super::string::alloc_str;
+
+/// Fractional digits kept by division when the operands have fewer
+const DIVISION_SCALE: u32 = 20;
+
+/// Largest scale accepted by round(); more digits are never needed for money
+const MAX_SCALE: i64 = 100;
+
+/// Fixed-point decimal: `unscaled / 10^scale`, e.g. 19.99 is (1999, 2).
+/// The scale is kept, so 1.50 prints as "1.50".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decimal {
+    unscaled: BigInt,
+    scale: u32,
+}
+
+/// Value used for a null handle, e.g. a decimal variable that was never assigned
+static ZERO: Decimal = Decimal { unscaled: BigInt::ZERO, scale: 0 };
+
+fn pow10(exponent: u32) -> BigInt {
+    BigInt::from(10u32).pow(exponent)
+}
+
+/// Divide, rounding exact halves to even (banker's rounding)
+fn div_half_even(numerator: &BigInt, denominator: &BigInt) -> BigInt {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    let twice: BigInt = remainder.abs() * 2u32;
+    let round_away = match twice.cmp(&denominator.abs()) {
+        Ordering::Greater => true,
+        Ordering::Equal => (&quotient % 2u32) != BigInt::zero(),
+        Ordering::Less => false,
+    };
+    if !round_away {
+        quotient
+    } else if numerator.is_negative() != denominator.is_negative() {
+        quotient - 1
+    } else {
+        quotient + 1
+    }
+}
+
+impl Decimal {
+    /// The same value with `scale` fractional digits (which must not be fewer)
+    fn rescaled(&self, scale: u32) -> BigInt {
+        &self.unscaled * pow10(scale - self.scale)
+    }
+
+    /// Round to `scale` fractional digits, padding with zeros if there are fewer
+    fn round(&self, scale: u32) -> Decimal {
+        let unscaled = if scale >= self.scale {
+            self.rescaled(scale)
+        } else {
+            div_half_even(&self.unscaled, &pow10(self.scale - scale))
+        };
+        Decimal { unscaled, scale }
+    }
+
+    /// Both operands' unscaled values at their common scale
+    fn aligned(&self, other: &Decimal) -> (BigInt, BigInt, u32) {
+        let scale = self.scale.max(other.scale);
+        (self.rescaled(scale), other.rescaled(scale), scale)
+    }
+
+    /// Parse `[+-]digits[.digits][e[+-]digits]`, ignoring `_` separators
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let text = text.trim().replace('_', "");
+        let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+            None => (text.as_str(), 0),
+        };
+        let (negative, mantissa) = match mantissa.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+        };
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{}{}", whole, fraction);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut unscaled: BigInt = digits.parse().ok()?;
+        if negative {
+            unscaled = -unscaled;
+        }
+        let scale = fraction.len() as i64 - exponent;
+        if scale >= 0 {
+            Some(Decimal { unscaled, scale: u32::try_from(scale).ok()? })
+        } else {
+            let shift = u32::try_from(-scale).ok()?;
+            Some(Decimal { unscaled: unscaled * pow10(shift), scale: 0 })
+        }
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let digits = self.unscaled.abs().to_string();
+        let sign = if self.unscaled.is_negative() { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// Borrow the value behind a decimal handle
+fn get<'a>(value: *const Decimal) -> &'a Decimal {
+    if value.is_null() {
+        &ZERO
+    } else {
+        unsafe { &*value }
+    }
+}
+
+/// Move a decimal into a new RC allocation and hand out its handle.
+/// Decimals are immutable, so handles are shared by retaining them.
+fn new_handle(value: Decimal) -> *mut Decimal {
+    let handle = rc_alloc(size_of::<Decimal>() as i64) as *mut Decimal;
+    unsafe { handle.write(value) };
+    handle
+}
+
+/// Free a decimal whose reference count has reached zero, like list_free
+#[no_mangle]
+pub extern "C" fn decimal_free(value: *mut Decimal) {
+    if value.is_null() {
+        return;
+    }
+    unsafe { ptr::drop_in_place(value) };
+    rc_free(value as *mut u8);
+}
+
+/// Convert an int to a decimal with no fractional digits
+#[no_mangle]
+pub extern "C" fn decimal_from_int(value: i64) -> *mut Decimal {
+    new_handle(Decimal { unscaled: BigInt::from(value), scale: 0 })
+}
+
+/// Parse a decimal string, e.g. "19.99" or "-1_000.5" (also used for `19.99d` literals)
+#[no_mangle]
+pub extern "C" fn decimal_from_str(text: *const c_char) -> *mut Decimal {
+    let text = unsafe { CStr::from_ptr(text) }.to_string_lossy();
+    match Decimal::parse(&text) {
+        Some(value) => new_handle(value),
+        None => raise("ValueError", format!("invalid decimal literal: '{}'", text)),
+    }
+}
+
+/// Convert a float through its shortest text, so 0.1 becomes exactly 0.1
+#[no_mangle]
+pub extern "C" fn decimal_from_float(value: f64) -> *mut Decimal {
+    if !value.is_finite() {
+        raise("ValueError", format!("cannot convert {} to decimal", smart_format(value)));
+    }
+    new_handle(Decimal::parse(&smart_format(value)).unwrap())
+}
+
+/// Text with exactly `scale` fractional digits
+#[no_mangle]
+pub extern "C" fn decimal_to_str(value: *const Decimal) -> *mut u8 {
+    unsafe { alloc_str(get(value).to_string().as_bytes()) }
+}
+
+/// Convert to int, truncating toward zero and keeping the low 64 bits
+#[no_mangle]
+pub extern "C" fn decimal_to_int(value: *const Decimal) -> i64 {
+    let value = get(value);
+    let whole = &value.unscaled / pow10(value.scale);
+    (whole & BigInt::from(u64::MAX)).to_u64().unwrap_or(0) as i64
+}
+
+/// Convert to the nearest float
+#[no_mangle]
+pub extern "C" fn decimal_to_float(value: *const Decimal) -> f64 {
+    get(value).to_string().parse().unwrap_or(f64::NAN)
+}
+
+#[no_mangle]
+pub extern "C" fn decimal_add(a: *const Decimal, b: *const Decimal) -> *mut Decimal {
+    let (a, b, scale) = get(a).aligned(get(b));
+    new_handle(Decimal { unscaled: a + b, scale })
+}
+
+#[no_mangle]
+pub extern "C" fn decimal_sub(a: *const Decimal, b: *const Decimal) -> *mut Decimal {
+    let (a, b, scale) = get(a).aligned(get(b));
+    new_handle(Decimal { unscaled: a - b, scale })
+}
+
+/// Exact product; the scales add up (1.10 * 1.10 is 1.2100)
+#[no_mangle]
+pub extern "C" fn decimal_mul(a: *const Decimal, b: *const Decimal) -> *mut Decimal {
+    let (a, b) = (get(a), get(b));
+    new_handle(Decimal { unscaled: &a.unscaled * &b.unscaled, scale: a.scale + b.scale })
+}
+
+/// Quotient rounded half-to-even to 20 fractional digits (or the operands'
+/// scale, if larger), with trailing zeros beyond the operands' scale removed
+#[no_mangle]
+pub extern "C" fn decimal_div(a: *const Decimal, b: *const Decimal) -> *mut Decimal {
+    let (a, b) = (get(a), get(b));
+    if b.unscaled.is_zero() {
+        raise("ZeroDivisionError", "decimal division by zero".to_string());
+    }
+    let min_scale = a.scale.max(b.scale);
+    let mut scale = min_scale.max(DIVISION_SCALE);
+    // a / b = (a.unscaled * 10^b.scale) / (b.unscaled * 10^a.scale)
+    let numerator = &a.unscaled * pow10(scale + b.scale);
+    let denominator = &b.unscaled * pow10(a.scale);
+    let mut unscaled = div_half_even(&numerator, &denominator);
+    while scale > min_scale && (&unscaled % 10u32).is_zero() {
+        unscaled /= 10u32;
+        scale -= 1;
+    }
+    new_handle(Decimal { unscaled, scale })
+}
+
+#[no_mangle]
+pub extern "C" fn decimal_neg(value: *const Decimal) -> *mut Decimal {
+    let value = get(value);
+    new_handle(Decimal { unscaled: -&value.unscaled, scale: value.scale })
+}
+
+/// Round half-to-even to `digits` fractional digits, padding with zeros
+/// (negative counts as 0)
+#[no_mangle]
+pub extern "C" fn decimal_round(value: *const Decimal, digits: i64) -> *mut Decimal {
+    new_handle(get(value).round(digits.clamp(0, MAX_SCALE) as u32))
+}
+
+/// Three-way comparison by value (1.5 == 1.50): -1, 0 or 1
+#[no_mangle]
+pub extern "C" fn decimal_cmp(a: *const Decimal, b: *const Decimal) -> i64 {
+    let (a, b, _) = get(a).aligned(get(b));
+    a.cmp(&b) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn read(s: *mut u8) -> String {
+        unsafe { CStr::from_ptr(s as *const i8).to_str().unwrap().to_string() }
+    }
+
+    fn dec(text: &str) -> *mut Decimal {
+        let text = CString::new(text).unwrap();
+        decimal_from_str(text.as_ptr())
+    }
+
+    fn show(value: *mut Decimal) -> String {
+        read(decimal_to_str(value))
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        assert_eq!(show(dec("19.99")), "19.99");
+        assert_eq!(show(dec("-0.05")), "-0.05");
+        assert_eq!(show(dec("1_000.50")), "1000.50");
+        assert_eq!(show(dec("2.5e-3")), "0.0025");
+        assert_eq!(show(dec("1.5E2")), "150");
+        assert_eq!(show(decimal_from_int(-7)), "-7");
+        assert_eq!(show(ptr::null_mut()), "0");
+        assert_eq!(Decimal::parse("1.2.3"), None);
+        assert_eq!(Decimal::parse("abc"), None);
+        assert_eq!(Decimal::parse("-"), None);
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        assert_eq!(show(decimal_add(dec("0.1"), dec("0.2"))), "0.3");
+        assert_eq!(show(decimal_sub(dec("19.99"), dec("20"))), "-0.01");
+        assert_eq!(show(decimal_mul(dec("1.10"), dec("1.10"))), "1.2100");
+        assert_eq!(show(decimal_mul(dec("19.99"), decimal_from_int(3))), "59.97");
+        assert_eq!(show(decimal_neg(dec("2.50"))), "-2.50");
+    }
+
+    #[test]
+    fn test_division() {
+        assert_eq!(show(decimal_div(dec("10.00"), decimal_from_int(4))), "2.50");
+        assert_eq!(show(decimal_div(decimal_from_int(1), decimal_from_int(3))), "0.33333333333333333333");
+        assert_eq!(show(decimal_div(decimal_from_int(2), decimal_from_int(3))), "0.66666666666666666667");
+        assert_eq!(show(decimal_div(decimal_from_int(-1), decimal_from_int(8))), "-0.125");
+    }
+
+    #[test]
+    fn test_round_half_even() {
+        assert_eq!(show(decimal_round(dec("2.675"), 2)), "2.68");
+        assert_eq!(show(decimal_round(dec("2.665"), 2)), "2.66");
+        assert_eq!(show(decimal_round(dec("-0.125"), 2)), "-0.12");
+        assert_eq!(show(decimal_round(dec("1.5"), 2)), "1.50");
+        assert_eq!(show(decimal_round(dec("7.5"), -1)), "8");
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(decimal_to_int(dec("-19.99")), -19);
+        assert_eq!(decimal_to_float(dec("0.1")), 0.1);
+        assert_eq!(show(decimal_from_float(0.1)), "0.1");
+        assert_eq!(show(decimal_from_float(2.0)), "2");
+    }
+
+    #[test]
+    fn test_cmp_ignores_scale() {
+        assert_eq!(decimal_cmp(dec("1.5"), dec("1.50")), 0);
+        assert_eq!(decimal_cmp(dec("-0.01"), decimal_from_int(0)), -1);
+        assert_eq!(decimal_cmp(dec("10"), dec("9.99")), 1);
+    }
+}
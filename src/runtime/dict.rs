@@ -1,10 +1,19 @@
-use std::alloc::{alloc, alloc_zeroed, Layout};
+use std::alloc::{alloc, alloc_zeroed, dealloc, Layout};
 use std::ffi::{CStr, CString};
 use std::ptr;
 
+use super::rc::{pool_alloc, pool_free, rc_free, release_element, retain_element};
+
 const INITIAL_CAPACITY: i64 = 16;
 const LOAD_FACTOR_THRESHOLD: f64 = 0.75;
 
+// What a dictionary's keys are, fixed by its first insertion. The type checker
+// gives every dictionary one key type, so codegen only ever calls one family of
+// functions (dict_set / dict_set_int, ...) on it.
+const KEYS_UNSET: i64 = 0;
+const KEYS_STR: i64 = 1; // str keys, hashed and compared by contents
+const KEYS_INT: i64 = 2; // int, sized int and bool keys, and objects (by identity)
+
 // Import the runtime_error and RC functions
 extern "C" {
     fn runtime_error(message: *const i8);
@@ -14,8 +23,8 @@ extern "C" {
 /// Dictionary entry structure (for chaining)
 #[repr(C)]
 struct DictEntry {
-    key: *mut u8,      // C string (strdup'd)
-    value: i64,
+    key: i64,          // Owned copy of a str key (strdup'd), or the int key itself
+    value: i64,        // Ints and bools widened, float bits, or a pointer
     next: *mut DictEntry,
 }
 
@@ -25,6 +34,9 @@ pub struct Dict {
     buckets: *mut *mut DictEntry,  // Array of bucket pointers
     capacity: i64,                  // Number of buckets
     length: i64,                    // Number of entries
+    key_kind: i64,                  // KEYS_UNSET until the first insertion
+    key_elems: i64,                 // ELEMS_* in rc.rs: what freeing the dict releases
+    value_elems: i64,
 }
 
 /// Hash function (djb2 algorithm)
@@ -40,6 +52,31 @@ unsafe fn hash_string(key: *const u8) -> u64 {
     hash
 }
 
+/// Hash function for int keys (splitmix64 finalizer). Object keys are aligned
+/// addresses, so the low bits alone would leave most buckets empty.
+fn hash_int(key: i64) -> u64 {
+    let mut hash = key as u64;
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+unsafe fn hash_key(kind: i64, key: i64) -> u64 {
+    if kind == KEYS_STR {
+        hash_string(key as *const u8)
+    } else {
+        hash_int(key)
+    }
+}
+
+unsafe fn keys_equal(kind: i64, a: i64, b: i64) -> bool {
+    if kind == KEYS_STR {
+        string_cmp(a as *const u8, b as *const u8) == 0
+    } else {
+        a == b
+    }
+}
+
 /// Duplicate a C string (equivalent to strdup)
 unsafe fn string_dup(src: *const u8) -> *mut u8 {
     if src.is_null() {
@@ -96,7 +133,7 @@ unsafe fn dict_rehash(dict: *mut Dict) {
             let next = (*entry).next;
 
             // Reinsert entry into new buckets
-            let hash = hash_string((*entry).key);
+            let hash = hash_key(dict_ref.key_kind, (*entry).key);
             let new_index = (hash % dict_ref.capacity as u64) as isize;
 
             (*entry).next = *dict_ref.buckets.offset(new_index);
@@ -107,13 +144,132 @@ unsafe fn dict_rehash(dict: *mut Dict) {
         }
     }
 
-    // Note: We don't free old_buckets array here as it would require proper deallocation
-    // In production, you'd want to properly deallocate using Layout::array
+    dealloc(old_buckets as *mut u8, Layout::array::<*mut DictEntry>(old_capacity as usize).unwrap());
+}
+
+/// Find the entry for a key, or null. A dictionary holding the other kind of
+/// key (or none yet) has no entry for it.
+unsafe fn find_entry(dict: &Dict, kind: i64, key: i64) -> *mut DictEntry {
+    if dict.key_kind != kind {
+        return ptr::null_mut();
+    }
+
+    // Calculate bucket index
+    let hash = hash_key(kind, key);
+    let index = (hash % dict.capacity as u64) as isize;
+
+    // Search through the bucket chain
+    let mut entry = *dict.buckets.offset(index);
+    while !entry.is_null() {
+        if keys_equal(kind, (*entry).key, key) {
+            return entry;
+        }
+        entry = (*entry).next;
+    }
+    ptr::null_mut()
+}
+
+/// Free an unlinked entry with the dict's hold on its key: the copy of a str
+/// key, or the reference to any other key. The caller deals with the value.
+unsafe fn free_entry(dict: &Dict, entry: *mut DictEntry) {
+    if dict.key_kind == KEYS_STR {
+        let key = (*entry).key as *mut u8;
+        let len = CStr::from_ptr(key as *const i8).to_bytes().len();
+        dealloc(key, Layout::array::<u8>(len + 1).unwrap());
+    } else {
+        release_element(dict.key_elems, (*entry).key);
+    }
+    pool_free(entry as *mut u8, Layout::new::<DictEntry>());
+}
+
+/// Free every entry and release the values. The buckets are left dangling.
+unsafe fn free_entries(dict: &Dict) {
+    for i in 0..dict.capacity {
+        let mut entry = *dict.buckets.offset(i as isize);
+        while !entry.is_null() {
+            let next = (*entry).next;
+            release_element(dict.value_elems, (*entry).value);
+            free_entry(dict, entry);
+            entry = next;
+        }
+    }
+}
+
+/// Take the entry for a key out of its bucket chain, or return null
+unsafe fn unlink_entry(dict: &mut Dict, kind: i64, key: i64) -> *mut DictEntry {
+    if dict.key_kind != kind {
+        return ptr::null_mut();
+    }
+
+    let hash = hash_key(kind, key);
+    let index = (hash % dict.capacity as u64) as isize;
+
+    // `link` is the pointer to the current entry: the bucket, or the previous entry's next
+    let mut link = dict.buckets.offset(index);
+    while !(*link).is_null() {
+        let entry = *link;
+        if keys_equal(kind, (*entry).key, key) {
+            *link = (*entry).next;
+            dict.length -= 1;
+            return entry;
+        }
+        link = &mut (*entry).next;
+    }
+    ptr::null_mut()
+}
+
+/// Insert or update a key. str keys are copied on insertion.
+unsafe fn dict_insert(dict: *mut Dict, kind: i64, key: i64, value: i64) {
+    let dict_ref = &mut *dict;
+
+    if dict_ref.key_kind == KEYS_UNSET {
+        dict_ref.key_kind = kind;
+    } else if dict_ref.key_kind != kind {
+        let msg = CString::new("Dictionary set error: key type does not match the dictionary's keys").unwrap();
+        runtime_error(msg.as_ptr());
+    }
+
+    // Check if key already exists
+    let existing = find_entry(dict_ref, kind, key);
+    if !existing.is_null() {
+        // Update existing value. The dict already holds the key, and its
+        // reference to the old value goes with it
+        let old = (*existing).value;
+        (*existing).value = value;
+        release_element(dict_ref.value_elems, old);
+        release_element(dict_ref.key_elems, key);
+        return;
+    }
+
+    // Check if we need to rehash
+    if (dict_ref.length as f64 / dict_ref.capacity as f64) >= LOAD_FACTOR_THRESHOLD {
+        dict_rehash(dict);
+    }
+
+    // Key doesn't exist, create new entry at head of bucket
+    let hash = hash_key(kind, key);
+    let index = (hash % dict_ref.capacity as u64) as isize;
+
+    let entry_layout = Layout::new::<DictEntry>();
+    let new_entry = pool_alloc(entry_layout) as *mut DictEntry;
+
+    if new_entry.is_null() {
+        eprintln!("Failed to allocate memory for dictionary entry");
+        std::process::exit(1);
+    }
+
+    (*new_entry).key = if kind == KEYS_STR { string_dup(key as *const u8) as i64 } else { key };
+    (*new_entry).value = value;
+    (*new_entry).next = *dict_ref.buckets.offset(index);
+
+    *dict_ref.buckets.offset(index) = new_entry;
+    dict_ref.length += 1;
 }
 
-/// Create a new dictionary
+/// Create a new dictionary. `key_elems` and `value_elems` (ELEMS_* in rc.rs)
+/// say whether its keys and values are lists, dicts or objects to release with it.
 #[no_mangle]
-pub extern "C" fn dict_create() -> *mut Dict {
+pub extern "C" fn dict_create(key_elems: i64, value_elems: i64) -> *mut Dict {
     unsafe {
         let dict_size = std::mem::size_of::<Dict>() as i64;
         let dict = rc_alloc(dict_size) as *mut Dict;
@@ -125,6 +281,9 @@ pub extern "C" fn dict_create() -> *mut Dict {
 
         (*dict).capacity = INITIAL_CAPACITY;
         (*dict).length = 0;
+        (*dict).key_kind = KEYS_UNSET;
+        (*dict).key_elems = key_elems;
+        (*dict).value_elems = value_elems;
 
         // Allocate buckets (zeroed)
         let buckets_layout = Layout::array::<*mut DictEntry>(INITIAL_CAPACITY as usize).unwrap();
@@ -139,7 +298,26 @@ pub extern "C" fn dict_create() -> *mut Dict {
     }
 }
 
-/// Set a key-value pair in the dictionary
+/// Free a dictionary whose reference count reached zero: its entries, the
+/// copies of its str keys, and its references to keys and values
+#[no_mangle]
+pub extern "C" fn dict_free(dict: *mut Dict) {
+    if dict.is_null() {
+        return;
+    }
+
+    unsafe {
+        let dict_ref = &*dict;
+        free_entries(dict_ref);
+        dealloc(
+            dict_ref.buckets as *mut u8,
+            Layout::array::<*mut DictEntry>(dict_ref.capacity as usize).unwrap(),
+        );
+        rc_free(dict as *mut u8);
+    }
+}
+
+/// Set a key-value pair in a dictionary with str keys
 #[no_mangle]
 pub extern "C" fn dict_set(dict: *mut Dict, key: *const u8, value: i64) {
     unsafe {
@@ -153,47 +331,24 @@ pub extern "C" fn dict_set(dict: *mut Dict, key: *const u8, value: i64) {
             runtime_error(msg.as_ptr());
         }
 
-        let dict_ref = &mut *dict;
-
-        // Check if we need to rehash
-        if (dict_ref.length as f64 / dict_ref.capacity as f64) >= LOAD_FACTOR_THRESHOLD {
-            dict_rehash(dict);
-        }
-
-        // Calculate bucket index
-        let hash = hash_string(key);
-        let index = (hash % dict_ref.capacity as u64) as isize;
-
-        // Check if key already exists in this bucket
-        let mut entry = *dict_ref.buckets.offset(index);
-        while !entry.is_null() {
-            if string_cmp((*entry).key, key) == 0 {
-                // Update existing value
-                (*entry).value = value;
-                return;
-            }
-            entry = (*entry).next;
-        }
-
-        // Key doesn't exist, create new entry at head of bucket
-        let entry_layout = Layout::new::<DictEntry>();
-        let new_entry = alloc(entry_layout) as *mut DictEntry;
+        dict_insert(dict, KEYS_STR, key as i64, value);
+    }
+}
 
-        if new_entry.is_null() {
-            eprintln!("Failed to allocate memory for dictionary entry");
-            std::process::exit(1);
+/// Set a key-value pair in a dictionary with int (or object) keys
+#[no_mangle]
+pub extern "C" fn dict_set_int(dict: *mut Dict, key: i64, value: i64) {
+    unsafe {
+        if dict.is_null() {
+            let msg = CString::new("Dictionary set error: null dictionary").unwrap();
+            runtime_error(msg.as_ptr());
         }
 
-        (*new_entry).key = string_dup(key);
-        (*new_entry).value = value;
-        (*new_entry).next = *dict_ref.buckets.offset(index);
-
-        *dict_ref.buckets.offset(index) = new_entry;
-        dict_ref.length += 1;
+        dict_insert(dict, KEYS_INT, key, value);
     }
 }
 
-/// Get a value from the dictionary (errors if key not found)
+/// Get a value from a dictionary with str keys (errors if key not found)
 #[no_mangle]
 pub extern "C" fn dict_get(dict: *const Dict, key: *const u8) -> i64 {
     unsafe {
@@ -207,19 +362,9 @@ pub extern "C" fn dict_get(dict: *const Dict, key: *const u8) -> i64 {
             runtime_error(msg.as_ptr());
         }
 
-        let dict_ref = &*dict;
-
-        // Calculate bucket index
-        let hash = hash_string(key);
-        let index = (hash % dict_ref.capacity as u64) as isize;
-
-        // Search through the bucket chain
-        let mut entry = *dict_ref.buckets.offset(index);
-        while !entry.is_null() {
-            if string_cmp((*entry).key, key) == 0 {
-                return (*entry).value;
-            }
-            entry = (*entry).next;
+        let entry = find_entry(&*dict, KEYS_STR, key as i64);
+        if !entry.is_null() {
+            return (*entry).value;
         }
 
         // Key not found - report error with key name
@@ -233,7 +378,57 @@ pub extern "C" fn dict_get(dict: *const Dict, key: *const u8) -> i64 {
     }
 }
 
-/// Check if a key exists in the dictionary
+/// Get a value from a dictionary with int (or object) keys (errors if key not found)
+#[no_mangle]
+pub extern "C" fn dict_get_int(dict: *const Dict, key: i64) -> i64 {
+    unsafe {
+        if dict.is_null() {
+            let msg = CString::new("Dictionary access error: null dictionary").unwrap();
+            runtime_error(msg.as_ptr());
+        }
+
+        let entry = find_entry(&*dict, KEYS_INT, key);
+        if !entry.is_null() {
+            return (*entry).value;
+        }
+
+        let msg = CString::new(format!("Dictionary key error: key {} not found in dictionary", key)).unwrap();
+        runtime_error(msg.as_ptr());
+        0 // Unreachable, but needed for type checker
+    }
+}
+
+/// Look up a key, or give `default` if it is missing. Like pop(), the result
+/// is retained so that the caller owns it either way.
+unsafe fn get_or_default(dict: *const Dict, kind: i64, key: i64, default: i64) -> i64 {
+    if dict.is_null() {
+        let msg = CString::new("Dictionary access error: null dictionary").unwrap();
+        runtime_error(msg.as_ptr());
+    }
+    let dict_ref = &*dict;
+    let entry = find_entry(dict_ref, kind, key);
+    let value = if entry.is_null() { default } else { (*entry).value };
+    retain_element(dict_ref.value_elems, value);
+    value
+}
+
+/// Get a value from a dictionary with str keys, or `default` if it is missing
+#[no_mangle]
+pub extern "C" fn dict_get_or(dict: *const Dict, key: *const u8, default: i64) -> i64 {
+    if key.is_null() {
+        let msg = CString::new("Dictionary access error: null key").unwrap();
+        unsafe { runtime_error(msg.as_ptr()) };
+    }
+    unsafe { get_or_default(dict, KEYS_STR, key as i64, default) }
+}
+
+/// Get a value from a dictionary with int (or object) keys, or `default` if it is missing
+#[no_mangle]
+pub extern "C" fn dict_get_or_int(dict: *const Dict, key: i64, default: i64) -> i64 {
+    unsafe { get_or_default(dict, KEYS_INT, key, default) }
+}
+
+/// Check if a key exists in a dictionary with str keys
 #[no_mangle]
 pub extern "C" fn dict_has(dict: *const Dict, key: *const u8) -> i32 {
     unsafe {
@@ -241,22 +436,100 @@ pub extern "C" fn dict_has(dict: *const Dict, key: *const u8) -> i32 {
             return 0;
         }
 
-        let dict_ref = &*dict;
-
-        // Calculate bucket index
-        let hash = hash_string(key);
-        let index = (hash % dict_ref.capacity as u64) as isize;
+        (!find_entry(&*dict, KEYS_STR, key as i64).is_null()) as i32
+    }
+}
 
-        // Search through the bucket chain
-        let mut entry = *dict_ref.buckets.offset(index);
-        while !entry.is_null() {
-            if string_cmp((*entry).key, key) == 0 {
-                return 1;
-            }
-            entry = (*entry).next;
+/// Check if a key exists in a dictionary with int (or object) keys
+#[no_mangle]
+pub extern "C" fn dict_has_int(dict: *const Dict, key: i64) -> i32 {
+    unsafe {
+        if dict.is_null() {
+            return 0;
         }
 
-        0
+        (!find_entry(&*dict, KEYS_INT, key).is_null()) as i32
+    }
+}
+
+/// Remove an entry, releasing the dict's references to its key and value
+unsafe fn remove_key(dict: *mut Dict, kind: i64, key: i64) -> i32 {
+    if dict.is_null() {
+        return 0;
+    }
+    let dict_ref = &mut *dict;
+    let entry = unlink_entry(dict_ref, kind, key);
+    if entry.is_null() {
+        return 0;
+    }
+    release_element(dict_ref.value_elems, (*entry).value);
+    free_entry(dict_ref, entry);
+    1
+}
+
+/// Remove an entry and hand its value to the caller, which gets the dict's
+/// reference to it. A missing key gives `default`, retained so that the caller
+/// owns the result either way.
+unsafe fn pop_key(dict: *mut Dict, kind: i64, key: i64, default: i64) -> i64 {
+    if dict.is_null() {
+        let msg = CString::new("Dictionary pop error: null dictionary").unwrap();
+        runtime_error(msg.as_ptr());
+    }
+    let dict_ref = &mut *dict;
+    let entry = unlink_entry(dict_ref, kind, key);
+    if entry.is_null() {
+        retain_element(dict_ref.value_elems, default);
+        return default;
+    }
+    let value = (*entry).value;
+    free_entry(dict_ref, entry);
+    value
+}
+
+/// Remove a key from a dictionary with str keys. Returns 1 if it was there.
+#[no_mangle]
+pub extern "C" fn dict_remove(dict: *mut Dict, key: *const u8) -> i32 {
+    if key.is_null() {
+        return 0;
+    }
+    unsafe { remove_key(dict, KEYS_STR, key as i64) }
+}
+
+/// Remove a key from a dictionary with int (or object) keys. Returns 1 if it was there.
+#[no_mangle]
+pub extern "C" fn dict_remove_int(dict: *mut Dict, key: i64) -> i32 {
+    unsafe { remove_key(dict, KEYS_INT, key) }
+}
+
+/// Remove a key from a dictionary with str keys and return its value, or
+/// `default` if it is missing
+#[no_mangle]
+pub extern "C" fn dict_pop(dict: *mut Dict, key: *const u8, default: i64) -> i64 {
+    if key.is_null() {
+        let msg = CString::new("Dictionary pop error: null key").unwrap();
+        unsafe { runtime_error(msg.as_ptr()) };
+    }
+    unsafe { pop_key(dict, KEYS_STR, key as i64, default) }
+}
+
+/// Remove a key from a dictionary with int (or object) keys and return its
+/// value, or `default` if it is missing
+#[no_mangle]
+pub extern "C" fn dict_pop_int(dict: *mut Dict, key: i64, default: i64) -> i64 {
+    unsafe { pop_key(dict, KEYS_INT, key, default) }
+}
+
+/// Remove every entry, releasing keys and values. The dict keeps its capacity.
+#[no_mangle]
+pub extern "C" fn dict_clear(dict: *mut Dict) {
+    if dict.is_null() {
+        return;
+    }
+    unsafe {
+        let dict_ref = &mut *dict;
+        free_entries(dict_ref);
+        ptr::write_bytes(dict_ref.buckets, 0, dict_ref.capacity as usize);
+        dict_ref.length = 0;
     }
 }
 
@@ -271,28 +544,17 @@ pub extern "C" fn dict_length(dict: *const Dict) -> i64 {
     }
 }
 
-/// Get all keys from the dictionary as a list of strings
+/// Get all keys from the dictionary as a list: pointers to the stored str keys,
+/// or the int keys themselves
 /// Returns a pointer to a newly allocated list
 #[no_mangle]
 pub extern "C" fn dict_get_keys(dict: *const Dict) -> *mut super::list::List {
-    use super::list::{List, list_push_i64};
-    use std::alloc::{alloc_zeroed, Layout};
+    use super::list::{list_create, list_push_i64};
+    use super::rc::ELEMS_PLAIN;
 
     unsafe {
-        // Create a new list manually
-        let list_layout = Layout::new::<List>();
-        let keys_list = alloc_zeroed(list_layout) as *mut List;
-
-        if keys_list.is_null() {
-            std::process::exit(1);
-        }
-
-        // Initialize empty list with default capacity
-        let initial_capacity = 8i64;
-        let data_layout = Layout::array::<i64>(initial_capacity as usize).unwrap();
-        (*keys_list).data = alloc_zeroed(data_layout) as *mut i64;
-        (*keys_list).length = 0;
-        (*keys_list).capacity = initial_capacity;
+        // The list borrows the keys, so freeing it leaves them alone
+        let keys_list = list_create(ELEMS_PLAIN);
 
         if dict.is_null() {
             return keys_list;
@@ -304,8 +566,8 @@ pub extern "C" fn dict_get_keys(dict: *const Dict) -> *mut super::list::List {
         for i in 0..dict_ref.capacity {
             let mut entry = *dict_ref.buckets.offset(i as isize);
             while !entry.is_null() {
-                // Store the key pointer as i64 (keys_list owns these pointers now)
-                list_push_i64(keys_list, (*entry).key as i64);
+                // str keys are pushed as pointers (the list borrows the dict's copies)
+                list_push_i64(keys_list, (*entry).key);
                 entry = (*entry).next;
             }
         }
@@ -314,15 +576,108 @@ pub extern "C" fn dict_get_keys(dict: *const Dict) -> *mut super::list::List {
     }
 }
 
+/// The keys as a new list that owns them: str keys are copied and object keys
+/// retained, so the list stays valid when the dict changes or is freed. The
+/// order is the dict's internal order, the same as for dict_values.
+#[no_mangle]
+pub extern "C" fn dict_keys(dict: *const Dict) -> *mut super::list::List {
+    use super::list::{list_create, list_push_i64};
+    use super::rc::ELEMS_PLAIN;
+
+    unsafe {
+        if dict.is_null() {
+            return list_create(ELEMS_PLAIN);
+        }
+
+        let dict_ref = &*dict;
+        let keys_list = list_create(dict_ref.key_elems);
+        for_each_entry(dict_ref, |entry| list_push_i64(keys_list, owned_key(dict_ref, entry)));
+        keys_list
+    }
+}
+
+/// An entry's key for a collection outside the dict: a copy of a str key, or
+/// the key retained
+unsafe fn owned_key(dict: &Dict, entry: &DictEntry) -> i64 {
+    if dict.key_kind == KEYS_STR {
+        string_dup(entry.key as *const u8) as i64
+    } else {
+        retain_element(dict.key_elems, entry.key);
+        entry.key
+    }
+}
+
+/// The values as a new list holding its own references to them, in the same
+/// order as dict_keys
+#[no_mangle]
+pub extern "C" fn dict_values(dict: *const Dict) -> *mut super::list::List {
+    use super::list::{list_create, list_push_i64};
+    use super::rc::ELEMS_PLAIN;
+
+    unsafe {
+        if dict.is_null() {
+            return list_create(ELEMS_PLAIN);
+        }
+
+        let dict_ref = &*dict;
+        let values_list = list_create(dict_ref.value_elems);
+        for_each_entry(dict_ref, |entry| {
+            retain_element(dict_ref.value_elems, entry.value);
+            list_push_i64(values_list, entry.value);
+        });
+        values_list
+    }
+}
+
+/// The entries as a new list of (key, value) tuples, in the same order as
+/// dict_keys. Each tuple is boxed (see rc::tuple_box) and owns its key and
+/// value the way dict_keys and dict_values do
+#[no_mangle]
+pub extern "C" fn dict_items(dict: *const Dict) -> *mut super::list::List {
+    use super::list::{list_create, list_push_i64};
+    use super::rc::{tuple_box, ELEMS_TUPLE};
+
+    unsafe {
+        let items_list = list_create(ELEMS_TUPLE);
+        if dict.is_null() {
+            return items_list;
+        }
+
+        let dict_ref = &*dict;
+        for_each_entry(dict_ref, |entry| {
+            retain_element(dict_ref.value_elems, entry.value);
+            let pair = tuple_box(&[
+                (dict_ref.key_elems, owned_key(dict_ref, entry)),
+                (dict_ref.value_elems, entry.value),
+            ]);
+            list_push_i64(items_list, pair as i64);
+        });
+        items_list
+    }
+}
+
+/// Visit every entry, bucket by bucket
+unsafe fn for_each_entry(dict: &Dict, mut visit: impl FnMut(&DictEntry)) {
+    for i in 0..dict.capacity {
+        let mut entry = *dict.buckets.offset(i as isize);
+        while !entry.is_null() {
+            visit(&*entry);
+            entry = (*entry).next;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::list::{list_create, list_free, list_push_i64};
+    use super::super::rc::{rc_get_count, rc_retain, ELEMS_LIST, ELEMS_PLAIN};
     use std::ffi::CString;
 
     #[test]
     fn test_dict_create() {
         unsafe {
-            let dict = dict_create();
+            let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
             assert!(!dict.is_null());
 
             let dict_ref = &*dict;
@@ -334,7 +689,7 @@ mod tests {
     #[test]
     fn test_dict_set_and_get() {
         unsafe {
-            let dict = dict_create();
+            let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
 
             let key1 = CString::new("name").unwrap();
             let key2 = CString::new("age").unwrap();
@@ -359,7 +714,7 @@ mod tests {
     #[test]
     fn test_dict_update_existing_key() {
         unsafe {
-            let dict = dict_create();
+            let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
             let key = CString::new("value").unwrap();
 
             // Set initial value
@@ -378,7 +733,7 @@ mod tests {
 
     #[test]
     fn test_dict_has() {
-        let dict = dict_create();
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
 
         let key1 = CString::new("exists").unwrap();
         let key2 = CString::new("missing").unwrap();
@@ -395,7 +750,7 @@ mod tests {
     #[test]
     fn test_dict_rehashing() {
         unsafe {
-            let dict = dict_create();
+            let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
             let dict_ref = &*dict;
 
             // Initial capacity should be INITIAL_CAPACITY (16)
@@ -425,7 +780,7 @@ mod tests {
     #[test]
     fn test_dict_collision_handling() {
         unsafe {
-            let dict = dict_create();
+            let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
 
             // Add many items to increase chance of collisions
             for i in 0..20 {
@@ -447,7 +802,7 @@ mod tests {
 
     #[test]
     fn test_dict_string_keys() {
-        let dict = dict_create();
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
 
         let keys = vec!["hello", "world", "rust", "wadescript", "test"];
         let values = vec![1, 2, 3, 4, 5];
@@ -467,7 +822,7 @@ mod tests {
 
     #[test]
     fn test_dict_empty_string_key() {
-        let dict = dict_create();
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
         let key = CString::new("").unwrap();
 
         dict_set(dict, key.as_ptr() as *const u8, 999);
@@ -492,4 +847,413 @@ mod tests {
             assert_ne!(hash1, hash3);
         }
     }
+
+    /// Keys that all land in the same bucket of a fresh dictionary
+    fn colliding_keys(count: usize) -> Vec<CString> {
+        let bucket = |key: &CString| unsafe { hash_string(key.as_ptr() as *const u8) % INITIAL_CAPACITY as u64 };
+        let target = bucket(&CString::new("k0").unwrap());
+        (0..)
+            .map(|i| CString::new(format!("k{}", i)).unwrap())
+            .filter(|key| bucket(key) == target)
+            .take(count)
+            .collect()
+    }
+
+    #[test]
+    fn test_dict_bucket_collisions() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        let keys = colliding_keys(4);
+
+        for (i, key) in keys.iter().enumerate() {
+            dict_set(dict, key.as_ptr() as *const u8, i as i64);
+        }
+        assert_eq!(dict_length(dict), 4);
+
+        // Update an entry in the middle of the chain
+        dict_set(dict, keys[1].as_ptr() as *const u8, 100);
+        assert_eq!(dict_length(dict), 4);
+
+        for (i, key) in keys.iter().enumerate() {
+            let expected = if i == 1 { 100 } else { i as i64 };
+            assert_eq!(dict_get(dict, key.as_ptr() as *const u8), expected);
+        }
+        let other = CString::new("not-a-key").unwrap();
+        assert_eq!(dict_has(dict, other.as_ptr() as *const u8), 0);
+    }
+
+    #[test]
+    fn test_dict_colliding_keys_survive_rehash() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        let keys = colliding_keys(3);
+        for (i, key) in keys.iter().enumerate() {
+            dict_set(dict, key.as_ptr() as *const u8, i as i64);
+        }
+        for i in 0..20 {
+            let key = CString::new(format!("filler{}", i)).unwrap();
+            dict_set(dict, key.as_ptr() as *const u8, -1);
+        }
+
+        unsafe {
+            assert!((*dict).capacity > INITIAL_CAPACITY);
+        }
+        assert_eq!(dict_length(dict), 23);
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(dict_get(dict, key.as_ptr() as *const u8), i as i64);
+        }
+    }
+
+    #[test]
+    fn test_dict_copies_keys() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        let mut buffer = b"abc\0".to_vec();
+        dict_set(dict, buffer.as_ptr(), 1);
+
+        // Changing the caller's buffer must not change the stored key
+        buffer[0] = b'x';
+        let original = CString::new("abc").unwrap();
+        assert_eq!(dict_get(dict, original.as_ptr() as *const u8), 1);
+        assert_eq!(dict_has(dict, buffer.as_ptr()), 0);
+    }
+
+    #[test]
+    fn test_dict_prefix_keys_are_distinct() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        let keys = ["a", "ab", "abc"].map(|key| CString::new(key).unwrap());
+        for (i, key) in keys.iter().enumerate() {
+            dict_set(dict, key.as_ptr() as *const u8, i as i64);
+        }
+
+        assert_eq!(dict_length(dict), 3);
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(dict_get(dict, key.as_ptr() as *const u8), i as i64);
+        }
+    }
+
+    #[test]
+    fn test_dict_utf8_keys() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        let cafe = CString::new("café").unwrap();
+        let cafe_ascii = CString::new("cafe").unwrap();
+        let japanese = CString::new("日本語").unwrap();
+
+        dict_set(dict, cafe.as_ptr() as *const u8, 1);
+        dict_set(dict, cafe_ascii.as_ptr() as *const u8, 2);
+        dict_set(dict, japanese.as_ptr() as *const u8, 3);
+
+        assert_eq!(dict_length(dict), 3);
+        assert_eq!(dict_get(dict, cafe.as_ptr() as *const u8), 1);
+        assert_eq!(dict_get(dict, cafe_ascii.as_ptr() as *const u8), 2);
+        assert_eq!(dict_get(dict, japanese.as_ptr() as *const u8), 3);
+    }
+
+    #[test]
+    fn test_dict_get_keys() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        let mut expected: Vec<String> = (0..20).map(|i| format!("key{}", i)).collect();
+        for key in &expected {
+            let key = CString::new(key.as_str()).unwrap();
+            dict_set(dict, key.as_ptr() as *const u8, 0);
+        }
+        // Updating a key does not add it twice
+        let first = CString::new("key0").unwrap();
+        dict_set(dict, first.as_ptr() as *const u8, 5);
+
+        let keys = dict_get_keys(dict);
+        let mut actual: Vec<String> = unsafe {
+            (0..(*keys).length)
+                .map(|i| {
+                    let key = *(*keys).data.offset(i as isize) as *const i8;
+                    CStr::from_ptr(key).to_str().unwrap().to_string()
+                })
+                .collect()
+        };
+        actual.sort();
+        expected.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_dict_null_handling() {
+        let key = CString::new("key").unwrap();
+        assert_eq!(dict_has(ptr::null(), key.as_ptr() as *const u8), 0);
+        assert_eq!(dict_length(ptr::null()), 0);
+
+        let keys = dict_get_keys(ptr::null());
+        unsafe {
+            assert_eq!((*keys).length, 0);
+        }
+
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        assert_eq!(dict_has(dict, ptr::null()), 0);
+    }
+
+    #[test]
+    fn test_dict_int_keys() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        for i in -50..50 {
+            dict_set_int(dict, i, i * 3);
+        }
+        dict_set_int(dict, 7, 700);
+
+        assert_eq!(dict_length(dict), 100);
+        assert_eq!(dict_get_int(dict, -50), -150);
+        assert_eq!(dict_get_int(dict, 7), 700);
+        assert_eq!(dict_has_int(dict, 49), 1);
+        assert_eq!(dict_has_int(dict, 50), 0);
+        unsafe {
+            assert!((*dict).capacity > INITIAL_CAPACITY);
+        }
+    }
+
+    #[test]
+    fn test_dict_pointer_keys_and_values() {
+        // Objects are keyed by address; values may be pointers (here, strings)
+        let objects = [0u64; 3];
+        let names = ["a", "b", "c"].map(|name| CString::new(name).unwrap());
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        for (object, name) in objects.iter().zip(&names) {
+            dict_set_int(dict, object as *const u64 as i64, name.as_ptr() as i64);
+        }
+
+        for (object, name) in objects.iter().zip(&names) {
+            let value = dict_get_int(dict, object as *const u64 as i64) as *const i8;
+            assert_eq!(unsafe { CStr::from_ptr(value) }, name.as_c_str());
+        }
+    }
+
+    #[test]
+    fn test_dict_float_values() {
+        let key = CString::new("pi").unwrap();
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        dict_set(dict, key.as_ptr() as *const u8, 3.25f64.to_bits() as i64);
+        assert_eq!(f64::from_bits(dict_get(dict, key.as_ptr() as *const u8) as u64), 3.25);
+    }
+
+    #[test]
+    fn test_dict_get_int_keys() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        for key in [3, 1, 2] {
+            dict_set_int(dict, key, 0);
+        }
+        let keys = dict_get_keys(dict);
+        let mut actual: Vec<i64> = unsafe { (0..(*keys).length).map(|i| *(*keys).data.offset(i as isize)).collect() };
+        actual.sort();
+        assert_eq!(actual, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_dict_lookup_with_other_key_kind() {
+        // A str-keyed dict has no int keys (and an empty one has neither)
+        let key = CString::new("1").unwrap();
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        assert_eq!(dict_has_int(dict, 1), 0);
+        assert_eq!(dict_has(dict, key.as_ptr() as *const u8), 0);
+
+        dict_set(dict, key.as_ptr() as *const u8, 1);
+        assert_eq!(dict_has_int(dict, key.as_ptr() as i64), 0);
+        assert_eq!(dict_has_int(dict, 1), 0);
+    }
+
+    #[test]
+    fn test_dict_of_lists_releases_values() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_LIST);
+        let key = CString::new("evens").unwrap();
+        let first = list_create(ELEMS_PLAIN);
+        list_push_i64(first, 2);
+        rc_retain(first as *mut u8);
+        dict_set(dict, key.as_ptr() as *const u8, first as i64);
+        assert_eq!(rc_get_count(first as *mut u8), 2);
+
+        // Replacing the value releases the dict's reference to the old one
+        dict_set(dict, key.as_ptr() as *const u8, list_create(ELEMS_PLAIN) as i64);
+        assert_eq!(rc_get_count(first as *mut u8), 1);
+
+        // Freeing the dict frees its key copies and releases its values
+        rc_retain(first as *mut u8);
+        dict_set(dict, c"odds".as_ptr() as *const u8, first as i64);
+        dict_free(dict);
+        assert_eq!(rc_get_count(first as *mut u8), 1);
+        assert_eq!(unsafe { *(*first).data }, 2);
+        list_free(first);
+    }
+
+    #[test]
+    fn test_dict_rehash_frees_old_buckets() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        for key in 0..100 {
+            dict_set_int(dict, key, key * 2);
+        }
+        assert_eq!(dict_get_int(dict, 99), 198);
+        dict_free(dict);
+    }
+
+    #[test]
+    fn test_dict_keys_and_values() {
+        unsafe {
+            let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+            for (name, age) in [("ada", 36), ("alan", 41), ("grace", 85)] {
+                let key = CString::new(name).unwrap();
+                dict_set(dict, key.as_ptr() as *const u8, age);
+            }
+
+            let keys = dict_keys(dict);
+            let values = dict_values(dict);
+            assert_eq!((*keys).length, 3);
+            assert_eq!((*values).length, 3);
+            // Same order in both lists, and the keys are copies the dict doesn't own
+            for i in 0..3 {
+                let key = *(*keys).data.offset(i) as *const u8;
+                assert_eq!(dict_get(dict, key), *(*values).data.offset(i));
+            }
+            dict_free(dict);
+            let first = CStr::from_ptr(*(*keys).data as *const i8).to_str().unwrap();
+            assert!(["ada", "alan", "grace"].contains(&first));
+            list_free(keys);
+            list_free(values);
+
+            let empty = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+            assert_eq!((*dict_keys(empty)).length, 0);
+            assert_eq!((*dict_keys(ptr::null())).length, 0);
+        }
+    }
+
+    #[test]
+    fn test_dict_values_retain_lists() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_LIST);
+        let row = list_create(ELEMS_PLAIN);
+        dict_set_int(dict, 1, row as i64);
+
+        let values = dict_values(dict);
+        assert_eq!(rc_get_count(row as *mut u8), 2);
+        dict_free(dict);
+        assert_eq!(rc_get_count(row as *mut u8), 1);
+        list_free(values);
+    }
+
+    #[test]
+    fn test_dict_items() {
+        unsafe {
+            let dict = dict_create(ELEMS_PLAIN, ELEMS_LIST);
+            let row = list_create(ELEMS_PLAIN);
+            let key = CString::new("ada").unwrap();
+            dict_set(dict, key.as_ptr() as *const u8, row as i64);
+
+            // Each pair is a box of [field count, key kind, key, value kind, value]
+            let items = dict_items(dict);
+            assert_eq!((*items).length, 1);
+            let pair = *(*items).data as *const i64;
+            assert_eq!(*pair, 2);
+            assert_eq!(*pair.add(1), ELEMS_PLAIN);
+            assert_eq!(CStr::from_ptr(*pair.add(2) as *const i8).to_str().unwrap(), "ada");
+            assert_eq!(*pair.add(3), ELEMS_LIST);
+            assert_eq!(*pair.add(4), row as i64);
+
+            // The pair holds its own reference to the value
+            assert_eq!(rc_get_count(row as *mut u8), 2);
+            dict_free(dict);
+            assert_eq!(rc_get_count(row as *mut u8), 1);
+            // Freeing the list releases the pair, and the pair the value
+            rc_retain(row as *mut u8);
+            list_free(items);
+            assert_eq!(rc_get_count(row as *mut u8), 1);
+            list_free(row);
+
+            assert_eq!((*dict_items(ptr::null())).length, 0);
+        }
+    }
+
+    #[test]
+    fn test_dict_remove() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        let keys: Vec<CString> = (0..20).map(|i| CString::new(format!("key{}", i)).unwrap()).collect();
+        for (i, key) in keys.iter().enumerate() {
+            dict_set(dict, key.as_ptr() as *const u8, i as i64);
+        }
+
+        // Remove every other key, from the middle of bucket chains too
+        for key in keys.iter().step_by(2) {
+            assert_eq!(dict_remove(dict, key.as_ptr() as *const u8), 1);
+            assert_eq!(dict_remove(dict, key.as_ptr() as *const u8), 0);
+        }
+        assert_eq!(dict_length(dict), 10);
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(dict_has(dict, key.as_ptr() as *const u8), (i % 2) as i32);
+        }
+
+        assert_eq!(dict_remove_int(dict, 1), 0, "int key in a str-keyed dict");
+        dict_free(dict);
+    }
+
+    #[test]
+    fn test_dict_pop_hands_over_value() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_LIST);
+        let row = list_create(ELEMS_PLAIN);
+        let fallback = list_create(ELEMS_PLAIN);
+        dict_set_int(dict, 7, row as i64);
+
+        // The dict's reference goes to the caller
+        assert_eq!(dict_pop_int(dict, 7, fallback as i64), row as i64);
+        assert_eq!(rc_get_count(row as *mut u8), 1);
+        assert_eq!(dict_length(dict), 0);
+
+        // A missing key gives the default, with a reference for the caller
+        assert_eq!(dict_pop_int(dict, 7, fallback as i64), fallback as i64);
+        assert_eq!(rc_get_count(fallback as *mut u8), 2);
+
+        let key = CString::new("x").unwrap();
+        let plain = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        dict_set(plain, key.as_ptr() as *const u8, 5);
+        assert_eq!(dict_pop(plain, key.as_ptr() as *const u8, -1), 5);
+        assert_eq!(dict_pop(plain, key.as_ptr() as *const u8, -1), -1);
+        dict_free(plain);
+        dict_free(dict);
+    }
+
+    #[test]
+    fn test_dict_get_or_default() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_LIST);
+        let row = list_create(ELEMS_PLAIN);
+        let fallback = list_create(ELEMS_PLAIN);
+        dict_set_int(dict, 7, row as i64);
+
+        // The entry stays, and the caller gets its own reference
+        assert_eq!(dict_get_or_int(dict, 7, fallback as i64), row as i64);
+        assert_eq!(rc_get_count(row as *mut u8), 2);
+        assert_eq!(dict_length(dict), 1);
+
+        assert_eq!(dict_get_or_int(dict, 8, fallback as i64), fallback as i64);
+        assert_eq!(rc_get_count(fallback as *mut u8), 2);
+
+        let key = CString::new("x").unwrap();
+        let missing = CString::new("y").unwrap();
+        let plain = dict_create(ELEMS_PLAIN, ELEMS_PLAIN);
+        dict_set(plain, key.as_ptr() as *const u8, 5);
+        assert_eq!(dict_get_or(plain, key.as_ptr() as *const u8, -1), 5);
+        assert_eq!(dict_get_or(plain, missing.as_ptr() as *const u8, -1), -1);
+        dict_free(plain);
+        dict_free(dict);
+    }
+
+    #[test]
+    fn test_dict_clear() {
+        let dict = dict_create(ELEMS_PLAIN, ELEMS_LIST);
+        let row = list_create(ELEMS_PLAIN);
+        rc_retain(row as *mut u8);
+        for i in 0..20 {
+            rc_retain(row as *mut u8);
+            dict_set_int(dict, i, row as i64);
+        }
+        assert_eq!(rc_get_count(row as *mut u8), 22);
+
+        dict_clear(dict);
+        assert_eq!(dict_length(dict), 0);
+        assert_eq!(rc_get_count(row as *mut u8), 2);
+        assert_eq!(dict_has_int(dict, 3), 0);
+
+        // The dict is still usable
+        dict_set_int(dict, 3, row as i64);
+        assert_eq!(dict_get_int(dict, 3), row as i64);
+        dict_free(dict);
+        assert_eq!(rc_get_count(row as *mut u8), 1);
+    }
 }
@@ -1,20 +1,27 @@
-use std::ffi::CStr;
+use std::alloc::Layout;
+use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int};
 use std::ptr;
 
-// Exception structure: { exception_type, message, file, line }
+use super::rc::{pool_alloc, pool_free};
+use super::traceback::report_fatal;
+
+// Exception structure: { exception_type, message, file, line, cause }
 #[repr(C)]
 pub struct Exception {
     pub exception_type: *const c_char,
     pub message: *const c_char,
     pub file: *const c_char,
     pub line: i64,
+    pub cause: *mut Exception, // Set by `raise ... from e`, otherwise null
 }
 
 // Jump buffer for setjmp/longjmp (opaque, platform specific size)
-#[repr(C)]
+// glibc x86_64 needs 200 bytes, glibc aarch64 312, macOS arm64 192.
+// Keep in sync with JMP_BUF_SIZE in codegen.rs.
+#[repr(C, align(16))]
 pub struct JmpBuf {
-    _private: [u8; 200], // Large enough for most platforms
+    _private: [u8; 512],
 }
 
 // External C functions
@@ -31,6 +38,11 @@ static mut CURRENT_EXCEPTION: *mut Exception = ptr::null_mut();
 // In a real implementation, this would use thread-local storage
 static mut EXCEPTION_HANDLERS: Vec<*mut JmpBuf> = Vec::new();
 
+// Exceptions whose except body is running, innermost last, with the handler
+// depth the body runs at. exception_clear leaves these alone when they are
+// the cause of the exception it releases
+static mut HANDLING: Vec<(*mut Exception, usize)> = Vec::new();
+
 /// Create a new exception object
 #[no_mangle]
 pub extern "C" fn exception_create(
@@ -39,13 +51,17 @@ pub extern "C" fn exception_create(
     file: *const c_char,
     line: i64,
 ) -> *mut Exception {
-    let exc = Box::new(Exception {
-        exception_type,
-        message,
-        file,
-        line,
-    });
-    Box::into_raw(exc)
+    unsafe {
+        let exc = pool_alloc(Layout::new::<Exception>()) as *mut Exception;
+        exc.write(Exception {
+            exception_type,
+            message,
+            file,
+            line,
+            cause: ptr::null_mut(),
+        });
+        exc
+    }
 }
 
 /// Get the current exception
@@ -62,14 +78,22 @@ pub extern "C" fn exception_set_current(exc: *mut Exception) {
     }
 }
 
-/// Clear the current exception
+/// Clear the current exception and release its cause chain. A cause whose
+/// except body is still running (`raise ... from e` caught inside that body)
+/// is kept, and becomes the current exception again once the inner one is
+/// cleared
 #[no_mangle]
 pub extern "C" fn exception_clear() {
     unsafe {
-        if !CURRENT_EXCEPTION.is_null() {
-            let _ = Box::from_raw(CURRENT_EXCEPTION);
-            CURRENT_EXCEPTION = ptr::null_mut();
+        let handling = &mut *std::ptr::addr_of_mut!(HANDLING);
+        let mut exc = CURRENT_EXCEPTION;
+        handling.retain(|&(handled, _)| handled != exc);
+        while !exc.is_null() && !handling.iter().any(|&(handled, _)| handled == exc) {
+            let cause = (*exc).cause;
+            pool_free(exc as *mut u8, Layout::new::<Exception>());
+            exc = cause;
         }
+        CURRENT_EXCEPTION = handling.last().map_or(ptr::null_mut(), |&(handled, _)| handled);
     }
 }
 
@@ -138,22 +162,174 @@ pub extern "C" fn exception_raise(
     file: *const c_char,
     line: i64,
 ) -> ! {
+    // Create exception object
+    let exc = exception_create(exception_type, message, file, line);
+    exception_reraise(exc)
+}
+
+/// Raise an exception chained to the exception that caused it (`raise ... from e`)
+#[no_mangle]
+pub extern "C" fn exception_raise_from(
+    exception_type: *const c_char,
+    message: *const c_char,
+    file: *const c_char,
+    line: i64,
+    cause: *mut Exception,
+) -> ! {
+    let exc = exception_create(exception_type, message, file, line);
+    unsafe {
+        (*exc).cause = cause;
+    }
+    exception_reraise(exc)
+}
+
+/// Raise a WadeScript exception from inside the runtime
+///
+/// The exception reaches its handler through longjmp, which skips the
+/// destructors of every Rust frame it leaves. raise therefore has to be the
+/// last call in a frame with nothing left to drop: build the message first,
+/// then drop every owned value (Strings, Vecs, Arcs, lock guards) before
+/// calling. The message itself is moved into the exception
+pub(crate) fn raise(exception_type: &str, message: String) -> ! {
+    let exception_type = CString::new(exception_type).unwrap();
+    let message = CString::new(message).unwrap();
+    // The strings are leaked: the exception object keeps pointers to them
+    exception_raise(exception_type.into_raw(), message.into_raw(), c"<runtime>".as_ptr(), 0)
+}
+
+/// "Type - message" for one exception
+unsafe fn describe(exc: *const Exception) -> String {
+    let exc_type_str = CStr::from_ptr((*exc).exception_type).to_str().unwrap_or("Unknown");
+    let msg_str = CStr::from_ptr((*exc).message).to_str().unwrap_or("");
+    format!("{} - {}", exc_type_str, msg_str)
+}
+
+/// One "caused by" line for each exception in the chain, outermost cause first
+unsafe fn describe_causes(exc: *const Exception) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut cause = (*exc).cause;
+    while !cause.is_null() {
+        lines.push(format!("  caused by: {}", describe(cause)));
+        cause = (*cause).cause;
+    }
+    lines
+}
+
+/// Raise an existing exception object again (does not return). Used for
+/// exceptions that no except clause matched and for a bare `raise`.
+#[no_mangle]
+pub extern "C" fn exception_reraise(exc: *mut Exception) -> ! {
     unsafe {
-        // Create exception object
-        let exc = exception_create(exception_type, message, file, line);
         exception_set_current(exc);
 
         // Try to longjmp to nearest exception handler
-        if let Some(jmp_buf) = (*std::ptr::addr_of_mut!(EXCEPTION_HANDLERS)).pop() {
+        let handlers = &mut *std::ptr::addr_of_mut!(EXCEPTION_HANDLERS);
+        if let Some(jmp_buf) = handlers.pop() {
+            // Except bodies nested deeper than this handler have been left
+            let depth = handlers.len();
+            let handling = &mut *std::ptr::addr_of_mut!(HANDLING);
+            handling.retain(|&(handled, handled_depth)| handled_depth <= depth && handled != exc);
+            handling.push((exc, depth));
+
             // Jump back to the try block with value 1 (indicating exception)
             longjmp(jmp_buf, 1);
         }
 
         // No exception handler found - unhandled exception
-        let exc_type_str = CStr::from_ptr(exception_type).to_str().unwrap_or("Unknown");
-        let msg_str = CStr::from_ptr(message).to_str().unwrap_or("");
-
-        eprintln!("\n\x1b[31;1mUnhandled Exception:\x1b[0m {} - {}", exc_type_str, msg_str);
+        let mut lines = vec![describe(exc)];
+        lines.extend(describe_causes(exc));
+        let file = if (*exc).file.is_null() { "" } else { CStr::from_ptr((*exc).file).to_str().unwrap_or("") };
+        report_fatal("Unhandled Exception:", &lines, Some((file, (*exc).line as usize)));
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jmp_buf_layout() {
+        // Must match JMP_BUF_SIZE / JMP_BUF_ALIGN used for `try` blocks in codegen
+        assert_eq!(std::mem::size_of::<JmpBuf>(), 512);
+        assert_eq!(std::mem::align_of::<JmpBuf>(), 16);
+    }
+
+    #[test]
+    fn test_cause_chain_description() {
+        let root = exception_create(c"ValueError".as_ptr(), c"bad digit".as_ptr(), c"a.ws".as_ptr(), 3);
+        let middle = exception_create(c"KeyError".as_ptr(), c"no port".as_ptr(), c"a.ws".as_ptr(), 7);
+        let top = exception_create(c"RuntimeError".as_ptr(), c"bad config".as_ptr(), c"a.ws".as_ptr(), 9);
+        unsafe {
+            (*middle).cause = root;
+            (*top).cause = middle;
+            assert_eq!(describe(top), "RuntimeError - bad config");
+            assert_eq!(
+                describe_causes(top),
+                ["  caused by: KeyError - no port", "  caused by: ValueError - bad digit"]
+            );
+            assert!(describe_causes(root).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_cleared_exception_is_reused() {
+        // Exceptions come from the rc pool, so raising in a loop reuses one block
+        let first = exception_create(c"ValueError".as_ptr(), c"first".as_ptr(), c"a.ws".as_ptr(), 1);
+        exception_set_current(first);
+        exception_clear();
+        assert!(exception_get_current().is_null());
+        let second = exception_create(c"KeyError".as_ptr(), c"second".as_ptr(), c"a.ws".as_ptr(), 2);
+        assert_eq!(second, first);
+        unsafe {
+            assert_eq!(describe(second), "KeyError - second");
+            assert!((*second).cause.is_null());
+        }
+
+        // The cause chain is released with the exception. (Kept in this test:
+        // tests run in parallel and the current exception is global)
+        let top = exception_create(c"RuntimeError".as_ptr(), c"top".as_ptr(), c"a.ws".as_ptr(), 3);
+        unsafe { (*top).cause = second };
+        exception_set_current(top);
+        exception_clear();
+        let again = [
+            exception_create(c"ValueError".as_ptr(), c"a".as_ptr(), c"a.ws".as_ptr(), 4),
+            exception_create(c"ValueError".as_ptr(), c"b".as_ptr(), c"a.ws".as_ptr(), 5),
+        ];
+        assert!(again.contains(&top) && again.contains(&second), "{:?}", again);
+
+        // A cause whose except body is still running is kept and becomes current again
+        let (handled, inner) = (again[0], again[1]);
+        unsafe {
+            (*std::ptr::addr_of_mut!(HANDLING)).push((handled, 0));
+            (*inner).cause = handled;
+        }
+        exception_set_current(inner);
+        exception_clear();
+        assert_eq!(exception_get_current(), handled);
+        unsafe { assert_eq!(describe(handled), "ValueError - a") };
+        exception_clear();
+        assert!(exception_get_current().is_null());
+        unsafe { assert!((*std::ptr::addr_of!(HANDLING)).is_empty()) };
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+    #[test]
+    fn test_jmp_buf_fits_glibc_aarch64() {
+        // glibc: __jmp_buf (22 x u64) + mask flag + sigset_t
+        assert!(std::mem::size_of::<JmpBuf>() >= 312);
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_os = "macos"))]
+    #[test]
+    fn test_jmp_buf_fits_darwin_arm64() {
+        // Darwin: jmp_buf is int[48]
+        assert!(std::mem::size_of::<JmpBuf>() >= 48 * 4);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_jmp_buf_fits_x86_64() {
+        assert!(std::mem::size_of::<JmpBuf>() >= 200);
+    }
+}
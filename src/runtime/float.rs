@@ -0,0 +1,136 @@
+use super::string::alloc_str;
+
+/// Largest precision accepted by format_float; more digits than this only
+/// expose the binary representation of the value
+const MAX_PRECISION: i64 = 100;
+
+/// Spelling used for NaN and infinities, matching C's printf
+fn non_finite(value: f64) -> Option<&'static str> {
+    if value.is_nan() {
+        Some("nan")
+    } else if value == f64::INFINITY {
+        Some("inf")
+    } else if value == f64::NEG_INFINITY {
+        Some("-inf")
+    } else {
+        None
+    }
+}
+
+/// Shortest text that reads back as the same float, laid out like `%g`:
+/// no trailing zeros, and scientific notation (`1e+20`, `2.5e-07`) for
+/// exponents below -4 or above 15
+pub fn smart_format(value: f64) -> String {
+    if let Some(text) = non_finite(value) {
+        return text.to_string();
+    }
+
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+
+    if (-4..16).contains(&exponent) {
+        format!("{}", value)
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+    }
+}
+
+/// Format a float for f-strings and print()
+#[no_mangle]
+pub extern "C" fn float_to_str(value: f64) -> *mut u8 {
+    unsafe { alloc_str(smart_format(value).as_bytes()) }
+}
+
+/// Format a float with exactly `digits` digits after the decimal point
+/// (negative counts as 0)
+#[no_mangle]
+pub extern "C" fn float_format(value: f64, digits: i64) -> *mut u8 {
+    let text = match non_finite(value) {
+        Some(text) => text.to_string(),
+        None => format!("{:.*}", digits.clamp(0, MAX_PRECISION) as usize, value),
+    };
+    unsafe { alloc_str(text.as_bytes()) }
+}
+
+/// Round to `digits` decimal places, rounding exact halves to even like
+/// Python's round(). Negative digits round to tens, hundreds, and so on.
+#[no_mangle]
+pub extern "C" fn float_round(value: f64, digits: i64) -> f64 {
+    if !value.is_finite() {
+        return value;
+    }
+    if digits < 0 {
+        let scale = 10f64.powi(digits.max(-308).unsigned_abs() as i32);
+        return (value / scale).round_ties_even() * scale;
+    }
+    // Decimal formatting rounds the exact binary value, so 2.675 (really
+    // 2.67499999...) rounds down just as it does in Python
+    format!("{:.*}", digits.min(MAX_PRECISION) as usize, value)
+        .parse()
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn read(s: *mut u8) -> String {
+        unsafe { CStr::from_ptr(s as *const i8).to_str().unwrap().to_string() }
+    }
+
+    #[test]
+    fn test_smart_format() {
+        assert_eq!(smart_format(3.14), "3.14");
+        assert_eq!(smart_format(2.0), "2");
+        assert_eq!(smart_format(-0.5), "-0.5");
+        assert_eq!(smart_format(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(smart_format(0.0001), "0.0001");
+        assert_eq!(smart_format(123456789.0), "123456789");
+    }
+
+    #[test]
+    fn test_smart_format_scientific() {
+        assert_eq!(smart_format(0.00001), "1e-05");
+        assert_eq!(smart_format(2.5e-7), "2.5e-07");
+        assert_eq!(smart_format(1e20), "1e+20");
+        assert_eq!(smart_format(1.5e300), "1.5e+300");
+    }
+
+    #[test]
+    fn test_smart_format_non_finite() {
+        assert_eq!(smart_format(f64::NAN), "nan");
+        assert_eq!(smart_format(f64::INFINITY), "inf");
+        assert_eq!(smart_format(f64::NEG_INFINITY), "-inf");
+    }
+
+    #[test]
+    fn test_float_to_str() {
+        assert_eq!(read(float_to_str(1.25)), "1.25");
+    }
+
+    #[test]
+    fn test_float_format() {
+        assert_eq!(read(float_format(3.14159, 2)), "3.14");
+        assert_eq!(read(float_format(2.0, 3)), "2.000");
+        assert_eq!(read(float_format(2.5, 0)), "2");
+        assert_eq!(read(float_format(1.5, -1)), "2");
+        assert_eq!(read(float_format(f64::NAN, 2)), "nan");
+    }
+
+    #[test]
+    fn test_float_round() {
+        assert_eq!(float_round(3.14159, 2), 3.14);
+        assert_eq!(float_round(2.5, 0), 2.0);
+        assert_eq!(float_round(3.5, 0), 4.0);
+        assert_eq!(float_round(-1.25, 1), -1.2);
+        // 2.675 is stored as 2.67499999..., so it rounds down
+        assert_eq!(float_round(2.675, 2), 2.67);
+        assert_eq!(float_round(1250.0, -2), 1200.0);
+        assert_eq!(float_round(1351.0, -2), 1400.0);
+        assert!(float_round(f64::NAN, 2).is_nan());
+        assert_eq!(float_round(f64::INFINITY, 2), f64::INFINITY);
+    }
+}
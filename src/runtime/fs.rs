@@ -0,0 +1,262 @@
+//! File system runtime for WadeScript
+//!
+//! Pattern-based file matching (`src/**/*.ws`), using the glob crate, and
+//! directory listing, creating, removing, renaming and copying files, and
+//! checking their size and kind.
+
+use std::fs;
+use std::path::Path;
+
+use glob::{glob_with, MatchOptions, Pattern};
+
+use super::exceptions::raise;
+use super::http::{alloc_c_string, c_str_to_string};
+use super::list::{list_create, list_push_i64, List};
+use super::rc::ELEMS_PLAIN;
+
+/// Shell-style matching: `*` and `?` stay within one path component and `**`
+/// crosses directories. Hidden entries are filtered by `is_visible`, because
+/// glob's `require_literal_leading_dot` also drops them for `.*` patterns
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.') && name != "." && name != ".."
+}
+
+/// A hidden file or directory (name starting with `.`) only matches a
+/// pattern component that starts with `.` too, so `**/*.ws` skips `.git`
+fn is_visible(path: &Path, dot_patterns: &[Pattern]) -> bool {
+    path.components().all(|component| match component.as_os_str().to_str() {
+        Some(name) if is_hidden(name) => dot_patterns.iter().any(|p| p.matches(name)),
+        _ => true,
+    })
+}
+
+/// Paths matching `pattern`, sorted. Entries that can't be read and paths
+/// that are not UTF-8 are skipped
+fn glob_paths(pattern: &str) -> Result<Vec<String>, String> {
+    let entries = glob_with(pattern, MATCH_OPTIONS)
+        .map_err(|e| format!("invalid glob pattern '{}': {}", pattern, e))?;
+    let dot_patterns: Vec<Pattern> = pattern
+        .split('/')
+        .filter(|component| is_hidden(component))
+        .filter_map(|component| Pattern::new(component).ok())
+        .collect();
+    let mut paths: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|path| is_visible(path, &dot_patterns))
+        .filter_map(|path| path.to_str().map(str::to_string))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Paths matching a glob pattern, as a new list[str]
+/// Raises ValueError if the pattern is invalid
+#[no_mangle]
+pub extern "C" fn fs_glob(pattern: *const u8) -> *mut List {
+    let pattern_str = unsafe { c_str_to_string(pattern) }.unwrap_or_default();
+    let paths = match glob_paths(&pattern_str) {
+        Ok(paths) => paths,
+        Err(message) => raise("ValueError", message),
+    };
+    let list = list_create(ELEMS_PLAIN);
+    for path in paths {
+        list_push_i64(list, alloc_c_string(&path) as i64);
+    }
+    list
+}
+
+fn path_arg(path: *const u8) -> String {
+    unsafe { c_str_to_string(path) }.unwrap_or_default()
+}
+
+/// The value of a file system operation, raising OSError with the function
+/// and path if it failed
+fn or_raise<T>(result: std::io::Result<T>, function: &str, path: &str) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => raise("OSError", format!("{}() failed for '{}': {}", function, path, e)),
+    }
+}
+
+/// Names of the entries in a directory, sorted, without "." and ".."
+fn dir_entries(path: &str) -> std::io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(path)? {
+        names.push(entry?.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Names of the entries in a directory (not full paths), as a new list[str]
+/// Raises OSError if the directory can't be read
+#[no_mangle]
+pub extern "C" fn fs_list_dir(path: *const u8) -> *mut List {
+    let path = path_arg(path);
+    let names = or_raise(dir_entries(&path), "list_dir", &path);
+    let list = list_create(ELEMS_PLAIN);
+    for name in names {
+        list_push_i64(list, alloc_c_string(&name) as i64);
+    }
+    list
+}
+
+/// Create a directory whose parent exists
+/// Raises OSError if it already exists or the parent doesn't
+#[no_mangle]
+pub extern "C" fn fs_mkdir(path: *const u8) {
+    let path = path_arg(path);
+    or_raise(fs::create_dir(&path), "mkdir", &path)
+}
+
+/// Create a directory and any missing parents. An existing directory is fine
+/// Raises OSError if it can't be created
+#[no_mangle]
+pub extern "C" fn fs_mkdirs(path: *const u8) {
+    let path = path_arg(path);
+    or_raise(fs::create_dir_all(&path), "mkdirs", &path)
+}
+
+/// Delete a file
+/// Raises OSError if it doesn't exist or is a directory
+#[no_mangle]
+pub extern "C" fn fs_remove_file(path: *const u8) {
+    let path = path_arg(path);
+    or_raise(fs::remove_file(&path), "remove_file", &path)
+}
+
+/// Delete a directory: an empty one, or with `recursive` (1) everything in it
+/// Raises OSError if it doesn't exist, or isn't empty and `recursive` is 0
+#[no_mangle]
+pub extern "C" fn fs_remove_dir(path: *const u8, recursive: i64) {
+    let path = path_arg(path);
+    let result = if recursive != 0 { fs::remove_dir_all(&path) } else { fs::remove_dir(&path) };
+    or_raise(result, "remove_dir", &path)
+}
+
+/// Move or rename a file or directory, replacing an existing file at `to`
+/// Raises OSError if `from` doesn't exist or `to` is on another file system
+#[no_mangle]
+pub extern "C" fn fs_rename(from: *const u8, to: *const u8) {
+    let from = path_arg(from);
+    let to = path_arg(to);
+    or_raise(fs::rename(&from, &to), "rename", &from)
+}
+
+/// Copy a file's contents and permissions to `to`, replacing it if it exists
+/// Raises OSError if `from` isn't a readable file
+#[no_mangle]
+pub extern "C" fn fs_copy(from: *const u8, to: *const u8) {
+    let from = path_arg(from);
+    let to = path_arg(to);
+    or_raise(fs::copy(&from, &to), "copy", &from);
+}
+
+/// Size of a file in bytes, following symbolic links
+/// Raises OSError if it doesn't exist
+#[no_mangle]
+pub extern "C" fn fs_file_size(path: *const u8) -> i64 {
+    let path = path_arg(path);
+    or_raise(fs::metadata(&path), "file_size", &path).len() as i64
+}
+
+/// 1 if `path` is a directory (or a link to one), 0 otherwise or if it doesn't exist
+#[no_mangle]
+pub extern "C" fn fs_is_dir(path: *const u8) -> i64 {
+    Path::new(&path_arg(path)).is_dir() as i64
+}
+
+/// 1 if `path` is a regular file (or a link to one), 0 otherwise or if it doesn't exist
+#[no_mangle]
+pub extern "C" fn fs_is_file(path: *const u8) -> i64 {
+    Path::new(&path_arg(path)).is_file() as i64
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::list::{list_free, list_get_i64};
+    use std::ffi::{CStr, CString};
+
+    #[test]
+    fn test_glob_paths() {
+        let root = std::env::temp_dir().join(format!("ws_glob_{}", std::process::id()));
+        fs::create_dir_all(root.join("src/lib")).unwrap();
+        fs::create_dir_all(root.join("src/.cache")).unwrap();
+        for file in ["src/main.ws", "src/lib/util.ws", "src/lib/notes.txt", "src/.hidden.ws", "src/.cache/old.ws"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let base = root.to_str().unwrap();
+
+        let all = glob_paths(&format!("{}/src/**/*.ws", base)).unwrap();
+        assert_eq!(all, vec![format!("{}/src/lib/util.ws", base), format!("{}/src/main.ws", base)]);
+
+        // `*` does not cross directories, and hidden entries need a `.` in the pattern
+        let top = glob_paths(&format!("{}/src/*.ws", base)).unwrap();
+        assert_eq!(top, vec![format!("{}/src/main.ws", base)]);
+
+        let hidden = glob_paths(&format!("{}/src/.*.ws", base)).unwrap();
+        assert_eq!(hidden, vec![format!("{}/src/.hidden.ws", base)]);
+        let cached = glob_paths(&format!("{}/src/.cache/*.ws", base)).unwrap();
+        assert_eq!(cached, vec![format!("{}/src/.cache/old.ws", base)]);
+
+        assert!(glob_paths(&format!("{}/missing/*", base)).unwrap().is_empty());
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let err = glob_paths("src/***.ws").unwrap_err();
+        assert!(err.starts_with("invalid glob pattern 'src/***.ws'"), "{}", err);
+    }
+
+    #[test]
+    fn test_directory_operations() {
+        let root = std::env::temp_dir().join(format!("ws_fs_ops_{}", std::process::id()));
+        let path = |name: &str| CString::new(root.join(name).to_str().unwrap()).unwrap();
+        let arg = |c: &CString| c.as_ptr() as *const u8;
+
+        fs_mkdirs(arg(&path("a/b")));
+        fs_mkdirs(arg(&path("a/b")));
+        fs_mkdir(arg(&path("a/c")));
+        assert_eq!(fs_is_dir(arg(&path("a/b"))), 1);
+        assert_eq!(fs_is_file(arg(&path("a/b"))), 0);
+
+        fs::write(root.join("a/data.txt"), "hello").unwrap();
+        assert_eq!(fs_is_file(arg(&path("a/data.txt"))), 1);
+        assert_eq!(fs_file_size(arg(&path("a/data.txt"))), 5);
+
+        fs_copy(arg(&path("a/data.txt")), arg(&path("a/copy.txt")));
+        fs_rename(arg(&path("a/copy.txt")), arg(&path("a/b/moved.txt")));
+        assert_eq!(fs::read_to_string(root.join("a/b/moved.txt")).unwrap(), "hello");
+
+        let list = fs_list_dir(arg(&path("a")));
+        let names: Vec<String> = (0..unsafe { (*list).length })
+            .map(|i| unsafe { CStr::from_ptr(list_get_i64(list, i) as *const i8) }.to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, vec!["b", "c", "data.txt"]);
+        list_free(list);
+
+        fs_remove_file(arg(&path("a/data.txt")));
+        fs_remove_dir(arg(&path("a/c")), 0);
+        assert!(fs::remove_dir(root.join("a")).is_err(), "a/b is still there");
+        fs_remove_dir(arg(&path("a")), 1);
+        assert_eq!(fs_is_dir(arg(&path("a"))), 0);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_missing_directory_is_an_error() {
+        assert!(dir_entries("/ws-no-such-directory").is_err());
+    }
+}
@@ -51,7 +51,7 @@ impl HttpResponseManager {
 }
 
 /// Helper to convert C string pointer to Rust string
-unsafe fn c_str_to_string(ptr: *const u8) -> Option<String> {
+pub(crate) unsafe fn c_str_to_string(ptr: *const u8) -> Option<String> {
     if ptr.is_null() {
         return None;
     }
@@ -62,7 +62,7 @@ unsafe fn c_str_to_string(ptr: *const u8) -> Option<String> {
 }
 
 /// Helper to allocate and return a C string
-fn alloc_c_string(s: &str) -> *mut u8 {
+pub(crate) fn alloc_c_string(s: &str) -> *mut u8 {
     unsafe {
         let bytes = s.as_bytes();
         let len = bytes.len();
@@ -4,19 +4,34 @@
 // - open(path, mode) -> handle
 // - read(handle) -> string
 // - read_line(handle) -> string
+// - read_lines(handle) -> list of strings
+// - read_bytes(handle, n) -> list of bytes
 // - write(handle, content)
+// - write_bytes(handle, bytes)
+// - seek(handle, offset, whence) -> position
+// - flush(handle)
 // - close(handle)
 // - exists(path) -> bool
+//
+// and standard input:
+// - input(prompt) -> string
+// - read_line() -> string or None
 
 use std::alloc::{alloc, Layout};
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Read, Write};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 use std::ptr;
 use std::sync::Mutex;
 
+use super::compress::{bytes_to_list, list_to_bytes};
+use super::exceptions::raise;
+use super::list::{list_create, list_push_i64, List};
+use super::rc::ELEMS_PLAIN;
+use super::string::alloc_str;
+
 // Global file handle storage
 // Maps handle IDs to file objects
 lazy_static::lazy_static! {
@@ -64,7 +79,8 @@ extern "C" {
 }
 
 /// Open a file
-/// mode: "r" = read, "w" = write (create/truncate), "a" = append
+/// mode: "r" = read, "w" = write (create/truncate), "a" = append; each may end
+/// in "b" ("rb", "wb", "ab"), which changes nothing since files are bytes
 /// Returns: file handle (>0 on success, calls runtime_error on failure)
 #[no_mangle]
 pub extern "C" fn file_open(path: *const u8, mode: *const u8) -> i64 {
@@ -98,7 +114,7 @@ pub extern "C" fn file_open(path: *const u8, mode: *const u8) -> i64 {
             }
         };
 
-        let result = match mode_str {
+        let result = match mode_str.strip_suffix('b').unwrap_or(mode_str) {
             "r" => {
                 File::open(path_str).map(|f| FileHandle::Read(BufReader::new(f)))
             }
@@ -120,7 +136,7 @@ pub extern "C" fn file_open(path: *const u8, mode: *const u8) -> i64 {
             }
             _ => {
                 let msg = CString::new(format!(
-                    "File open error: invalid mode '{}' (use 'r', 'w', or 'a')",
+                    "File open error: invalid mode '{}' (use 'r', 'w', or 'a', optionally followed by 'b')",
                     mode_str
                 )).unwrap();
                 runtime_error(msg.as_ptr());
@@ -167,7 +183,8 @@ pub extern "C" fn file_read(handle: i64) -> *mut u8 {
         let contents = match file_handle {
             FileHandle::Read(reader) => {
                 let mut contents = String::new();
-                if let Err(e) = reader.get_mut().read_to_string(&mut contents) {
+                // Through the reader, so lines it has buffered are not skipped
+                if let Err(e) = reader.read_to_string(&mut contents) {
                     let msg = CString::new(format!("File read error: {}", e)).unwrap();
                     runtime_error(msg.as_ptr());
                     return ptr::null_mut();
@@ -304,6 +321,126 @@ pub extern "C" fn file_write(handle: i64, content: *const u8) {
     }
 }
 
+/// Run `f` on an open file, raising ValueError for an unknown handle and the
+/// error `f` returns. `f` and the handle lock are dropped before raising
+fn with_file<T>(
+    handle: i64,
+    operation: &str,
+    f: impl FnOnce(&mut FileHandle) -> Result<T, (&'static str, String)>,
+) -> T {
+    let result = match FILE_HANDLES.lock().unwrap().get(handle) {
+        Some(file_handle) => f(file_handle),
+        None => Err(("ValueError", format!("invalid handle {}", handle))),
+    };
+    match result {
+        Ok(value) => value,
+        Err((error_type, reason)) => {
+            let message = format!("File {} error: {}", operation, reason);
+            drop(reason);
+            raise(error_type, message)
+        }
+    }
+}
+
+fn os_error(e: std::io::Error) -> (&'static str, String) {
+    ("OSError", e.to_string())
+}
+
+fn not_readable() -> (&'static str, String) {
+    ("ValueError", "file not opened for reading".to_string())
+}
+
+fn not_writable() -> (&'static str, String) {
+    ("ValueError", "file not opened for writing".to_string())
+}
+
+/// Move to `offset` bytes from the start (whence 0), the current position (1)
+/// or the end (2), and return the new position from the start.
+/// Files opened with "a" still write at the end
+/// Raises ValueError for another whence or a position before the start
+#[no_mangle]
+pub extern "C" fn file_seek(handle: i64, offset: i64, whence: i64) -> i64 {
+    with_file(handle, "seek", |file_handle| {
+        let target = match whence {
+            0 if offset < 0 => return Err(("ValueError", format!("negative position {}", offset))),
+            0 => SeekFrom::Start(offset as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return Err(("ValueError", format!("whence must be 0, 1 or 2, got {}", whence))),
+        };
+        let position = match file_handle {
+            FileHandle::Read(reader) => reader.seek(target),
+            FileHandle::Write(file) | FileHandle::Append(file) => file.seek(target),
+        };
+        // A position before the start is an InvalidInput error from the OS
+        position.map(|p| p as i64).map_err(|e| match e.kind() {
+            std::io::ErrorKind::InvalidInput => ("ValueError", format!("cannot move before the start: {}", e)),
+            _ => os_error(e),
+        })
+    })
+}
+
+/// Read up to `n` bytes as a new list[u8]: fewer at the end of the file, and
+/// an empty list once it is reached
+/// Raises ValueError if n is negative
+#[no_mangle]
+pub extern "C" fn file_read_bytes(handle: i64, n: i64) -> *mut List {
+    let bytes = with_file(handle, "read_bytes", |file_handle| {
+        if n < 0 {
+            return Err(("ValueError", format!("byte count must not be negative, got {}", n)));
+        }
+        let FileHandle::Read(reader) = file_handle else {
+            return Err(not_readable());
+        };
+        let mut bytes = Vec::new();
+        reader.take(n as u64).read_to_end(&mut bytes).map_err(os_error)?;
+        Ok(bytes)
+    });
+    bytes_to_list(&bytes)
+}
+
+/// Every line from the current position to the end, without line endings,
+/// as a new list[str]
+#[no_mangle]
+pub extern "C" fn file_read_lines(handle: i64) -> *mut List {
+    let lines = with_file(handle, "read_lines", |file_handle| {
+        let FileHandle::Read(reader) = file_handle else {
+            return Err(not_readable());
+        };
+        let mut lines = Vec::new();
+        while let Some(line) = read_input_line(reader).map_err(os_error)? {
+            lines.push(line);
+        }
+        Ok(lines)
+    });
+    let list = list_create(ELEMS_PLAIN);
+    for line in lines {
+        list_push_i64(list, unsafe { alloc_str(&line) } as i64);
+    }
+    list
+}
+
+/// Write the bytes of a list[u8]
+#[no_mangle]
+pub extern "C" fn file_write_bytes(handle: i64, data: *const List) {
+    let bytes = list_to_bytes(data);
+    // The closure owns the bytes, so they are freed before a write error is raised
+    with_file(handle, "write_bytes", move |file_handle| match file_handle {
+        FileHandle::Write(file) | FileHandle::Append(file) => file.write_all(&bytes).map_err(os_error),
+        FileHandle::Read(_) => Err(not_writable()),
+    })
+}
+
+/// Make sure everything written so far is stored on disk, not only handed to
+/// the operating system. Does nothing for files opened for reading
+#[no_mangle]
+pub extern "C" fn file_flush(handle: i64) {
+    with_file(handle, "flush", |file_handle| match file_handle {
+        FileHandle::Write(file) | FileHandle::Append(file) => file.sync_data().map_err(os_error),
+        FileHandle::Read(_) => Ok(()),
+    })
+}
+
 /// Close a file handle
 #[no_mangle]
 pub extern "C" fn file_close(handle: i64) {
@@ -334,6 +471,61 @@ pub extern "C" fn file_exists(path: *const u8) -> i64 {
     }
 }
 
+/// Read one line without its "\n" or "\r\n", or None at the end of input
+pub(crate) fn read_input_line(reader: &mut impl BufRead) -> std::io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if line.ends_with(b"\n") {
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+    }
+    Ok(Some(line))
+}
+
+/// The next line of standard input. Output printed so far is flushed first,
+/// so a question appears before the program waits for the answer. The stdin
+/// lock is released before raising
+fn stdin_line() -> Option<Vec<u8>> {
+    unsafe { libc::fflush(ptr::null_mut()) };
+    let read = read_input_line(&mut std::io::stdin().lock());
+    match read {
+        Ok(line) => line,
+        Err(e) => {
+            let message = format!("cannot read standard input: {}", e);
+            drop(e);
+            raise("OSError", message)
+        }
+    }
+}
+
+/// Print `prompt` (without a newline) and read a line from standard input,
+/// for `input(prompt)`
+/// Raises EOFError at the end of input
+#[no_mangle]
+pub extern "C" fn stdin_input(prompt: *const u8) -> *mut u8 {
+    if !prompt.is_null() {
+        unsafe { libc::printf(c"%s".as_ptr(), prompt) };
+    }
+    match stdin_line() {
+        Some(line) => unsafe { alloc_str(&line) },
+        None => raise("EOFError", "input() reached the end of standard input".to_string()),
+    }
+}
+
+/// The next line of standard input as a new string, or null (None) at the
+/// end of input, for `read_line()`
+#[no_mangle]
+pub extern "C" fn stdin_read_line() -> *mut u8 {
+    match stdin_line() {
+        Some(line) => unsafe { alloc_str(&line) },
+        None => ptr::null_mut(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,4 +642,77 @@ mod tests {
 
         fs::remove_file(test_path).ok();
     }
+
+    #[test]
+    fn test_binary_modes_seek_and_bytes() {
+        use super::super::list::{list_free, list_get_i64};
+
+        let test_path = "/tmp/wadescript_test_bytes.bin";
+        let path = CString::new(test_path).unwrap();
+        let mode_wb = CString::new("wb").unwrap();
+        let mode_rb = CString::new("rb").unwrap();
+
+        let handle = file_open(path.as_ptr() as *const u8, mode_wb.as_ptr() as *const u8);
+        let data = bytes_to_list(&[0, 1, 2, 200, 255]);
+        file_write_bytes(handle, data);
+        list_free(data);
+        file_flush(handle);
+        assert_eq!(file_seek(handle, 0, 1), 5);
+        file_close(handle);
+
+        let handle = file_open(path.as_ptr() as *const u8, mode_rb.as_ptr() as *const u8);
+        let read = |n: i64| {
+            let list = file_read_bytes(handle, n);
+            let bytes: Vec<i64> = (0..unsafe { (*list).length }).map(|i| list_get_i64(list, i)).collect();
+            list_free(list);
+            bytes
+        };
+        assert_eq!(read(2), vec![0, 1]);
+        assert_eq!(file_seek(handle, -2, 2), 3);
+        assert_eq!(read(10), vec![200, 255], "fewer bytes at the end");
+        assert_eq!(read(10), Vec::<i64>::new());
+        assert_eq!(file_seek(handle, 1, 0), 1);
+        assert_eq!(read(1), vec![1]);
+        file_close(handle);
+
+        fs::remove_file(test_path).ok();
+    }
+
+    #[test]
+    fn test_read_lines_after_read_line() {
+        use super::super::list::{list_free, list_get_i64};
+
+        let test_path = "/tmp/wadescript_test_read_lines.txt";
+        fs::write(test_path, "header\none\r\ntwo\n\nthree").unwrap();
+        let path = CString::new(test_path).unwrap();
+        let mode_r = CString::new("r").unwrap();
+
+        let handle = file_open(path.as_ptr() as *const u8, mode_r.as_ptr() as *const u8);
+        let header = file_read_line(handle);
+        assert_eq!(unsafe { CStr::from_ptr(header as *const i8) }.to_str().unwrap(), "header");
+        let list = file_read_lines(handle);
+        let lines: Vec<String> = (0..unsafe { (*list).length })
+            .map(|i| unsafe { CStr::from_ptr(list_get_i64(list, i) as *const i8) }.to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(lines, vec!["one", "two", "", "three"], "the lines after the one already read");
+        list_free(list);
+
+        // Reading the rest of the file also starts after what was read
+        file_seek(handle, 7, 0);
+        let rest = file_read(handle);
+        assert_eq!(unsafe { CStr::from_ptr(rest as *const i8) }.to_str().unwrap(), "one\r\ntwo\n\nthree");
+        file_close(handle);
+
+        fs::remove_file(test_path).ok();
+    }
+
+    #[test]
+    fn test_read_input_line() {
+        let mut input = std::io::Cursor::new(b"first\r\nsecond\n\nlast".to_vec());
+        assert_eq!(read_input_line(&mut input).unwrap(), Some(b"first".to_vec()));
+        assert_eq!(read_input_line(&mut input).unwrap(), Some(b"second".to_vec()));
+        assert_eq!(read_input_line(&mut input).unwrap(), Some(Vec::new()), "an empty line is not the end");
+        assert_eq!(read_input_line(&mut input).unwrap(), Some(b"last".to_vec()), "a last line without newline");
+        assert_eq!(read_input_line(&mut input).unwrap(), None);
+    }
 }
@@ -8,26 +8,74 @@
 pub mod list;
 pub mod dict;
 pub mod string;
+pub mod float;
+pub mod bigint;
+pub mod decimal;
 pub mod exceptions;
 pub mod rc;
 pub mod io;
 pub mod cli;
 pub mod http;
+pub mod smtp;
+pub mod compress;
+pub mod random;
+pub mod fs;
+pub mod regex;
+pub mod bytes;
+pub mod time;
+pub mod os;
+pub mod process;
+pub mod path;
+pub mod csv;
+pub mod config;
+pub mod net;
+mod source_map;
+mod traceback;
 
 use std::ffi::CStr;
 use std::os::raw::c_char;
 use std::sync::Mutex;
-use backtrace::Backtrace;
 
 // Re-export the functions to ensure they're available for linking
 pub use list::*;
 pub use dict::*;
 pub use string::*;
+pub use float::*;
+pub use bigint::*;
+pub use decimal::*;
 pub use exceptions::*;
 pub use rc::*;
 pub use io::*;
 pub use cli::*;
 pub use http::*;
+pub use smtp::*;
+pub use compress::*;
+pub use random::*;
+pub use fs::*;
+pub use self::regex::*;
+pub use bytes::*;
+pub use self::time::*;
+pub use os::*;
+pub use process::*;
+pub use path::*;
+pub use csv::*;
+pub use config::*;
+pub use net::*;
+
+// The runtime's own allocations (rc_alloc, strings, lists, dicts) go through Rust's
+// global allocator, which an archive built with `--features mimalloc` or
+// `--features jemalloc` replaces (see docs/ALLOCATORS.md). Memory that generated
+// code gets from malloc() is not affected.
+#[cfg(all(feature = "mimalloc", feature = "jemalloc"))]
+compile_error!("the mimalloc and jemalloc features select different allocators; enable only one");
+
+#[cfg(feature = "mimalloc")]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 // Global call stack for stack traces
 static CALL_STACK: Mutex<Vec<String>> = Mutex::new(Vec::new());
@@ -54,49 +102,13 @@ pub extern "C" fn pop_call_stack() {
     }
 }
 
-/// Print runtime error message with stack trace and exit
+/// Report a runtime error (see traceback::report_fatal) and exit
 #[no_mangle]
 pub extern "C" fn runtime_error(message: *const c_char) {
     unsafe {
         if !message.is_null() {
             if let Ok(msg) = CStr::from_ptr(message).to_str() {
-                eprintln!("\n\x1b[31;1mRuntime Error:\x1b[0m {}", msg);
-
-                // Capture backtrace with symbol resolution
-                let bt = Backtrace::new();
-
-                // Collect all .ws frames from the backtrace
-                let mut ws_frames: Vec<(String, u32)> = Vec::new();
-                for frame in bt.frames() {
-                    for symbol in frame.symbols() {
-                        if let Some(filename) = symbol.filename() {
-                            if let Some(filename_str) = filename.to_str() {
-                                if filename_str.ends_with(".ws") {
-                                    if let Some(line) = symbol.lineno() {
-                                        ws_frames.push((filename_str.to_string(), line));
-                                        break; // Only take first .ws symbol per frame
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-
-                // Show stack trace with line numbers from debug info
-                if !ws_frames.is_empty() {
-                    eprintln!("\n\x1b[36;1mStack trace:\x1b[0m");
-                    for (file, line) in ws_frames {
-                        eprintln!("  at {}:{}", file, line);
-                    }
-                } else if let Ok(stack) = CALL_STACK.lock() {
-                    // Fallback to manual call stack if no debug info found
-                    if !stack.is_empty() {
-                        eprintln!("\n\x1b[36;1mCall stack:\x1b[0m");
-                        for (i, func) in stack.iter().rev().enumerate() {
-                            eprintln!("  \x1b[90m{}\x1b[0m. {}", i + 1, func);
-                        }
-                    }
-                }
+                traceback::report_fatal("Runtime Error:", &[msg.to_string()], None);
             }
         }
         std::process::exit(1);
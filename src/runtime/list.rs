@@ -1,12 +1,51 @@
-use std::alloc::{alloc, realloc, Layout};
-use std::ffi::CString;
+use std::alloc::{alloc, dealloc, realloc, Layout};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
 
-/// List structure: { ptr data, i64 length, i64 capacity }
+use super::rc::{rc_alloc, rc_free, release_element, retain_element, ELEMS_PLAIN};
+
+/// List structure: { ptr data, i64 length, i64 capacity, i64 elem_kind }
 #[repr(C)]
 pub struct List {
     pub data: *mut i64,
     pub length: i64,
     pub capacity: i64,
+    pub elem_kind: i64, // ELEMS_* in rc.rs: what freeing the list releases
+}
+
+/// Create an empty list. `elem_kind` (ELEMS_* in rc.rs) says whether its
+/// elements are lists, dicts or objects to release with it.
+#[no_mangle]
+pub extern "C" fn list_create(elem_kind: i64) -> *mut List {
+    let list = rc_alloc(std::mem::size_of::<List>() as i64) as *mut List;
+    unsafe {
+        list.write(List {
+            data: std::ptr::null_mut(),
+            length: 0,
+            capacity: 0,
+            elem_kind,
+        });
+    }
+    list
+}
+
+/// Free a list whose reference count reached zero, releasing its elements
+#[no_mangle]
+pub extern "C" fn list_free(list: *mut List) {
+    if list.is_null() {
+        return;
+    }
+
+    unsafe {
+        let list_ref = &*list;
+        for i in 0..list_ref.length {
+            release_element(list_ref.elem_kind, *list_ref.data.offset(i as isize));
+        }
+        if !list_ref.data.is_null() {
+            dealloc(list_ref.data as *mut u8, Layout::array::<i64>(list_ref.capacity as usize).unwrap());
+        }
+        rc_free(list as *mut u8);
+    }
 }
 
 // Import the runtime_error function
@@ -121,7 +160,11 @@ pub extern "C" fn list_set_i64(list: *mut List, index: i64, value: i64) {
             runtime_error(msg.as_ptr());
         }
 
-        *list_ref.data.offset(index as isize) = value;
+        // The list held a reference to the element it replaces
+        let slot = list_ref.data.offset(index as isize);
+        let old = *slot;
+        *slot = value;
+        release_element(list_ref.elem_kind, old);
     }
 }
 
@@ -163,15 +206,10 @@ pub extern "C" fn list_slice_i64(list: *const List, start: i64, end: i64, step:
             if actual_start <= actual_end { 0 } else { ((actual_start - actual_end - 1) / (-actual_step) + 1) as usize }
         };
 
-        // Allocate new list
-        let layout = Layout::new::<List>();
-        let new_list = alloc(layout) as *mut List;
+        // The slice holds its own references to the elements it shares
+        let new_list = list_create(list_ref.elem_kind);
 
-        if result_size == 0 {
-            (*new_list).data = std::ptr::null_mut();
-            (*new_list).length = 0;
-            (*new_list).capacity = 0;
-        } else {
+        if result_size > 0 {
             // Allocate data array
             let data_layout = Layout::array::<i64>(result_size).unwrap();
             let new_data = alloc(data_layout) as *mut i64;
@@ -194,6 +232,10 @@ pub extern "C" fn list_slice_i64(list: *const List, start: i64, end: i64, step:
                 }
             }
 
+            for i in 0..dest_idx {
+                retain_element(list_ref.elem_kind, *new_data.add(i));
+            }
+
             (*new_list).data = new_data;
             (*new_list).length = dest_idx as i64;
             (*new_list).capacity = result_size as i64;
@@ -203,15 +245,348 @@ pub extern "C" fn list_slice_i64(list: *const List, start: i64, end: i64, step:
     }
 }
 
+/// Insert an element before `index` (0 to length), shifting the rest up
+#[no_mangle]
+pub extern "C" fn list_insert_i64(list: *mut List, index: i64, value: i64) {
+    unsafe {
+        if list.is_null() {
+            let msg = CString::new("List insert error: null list").unwrap();
+            runtime_error(msg.as_ptr());
+        }
+
+        let length = (*list).length;
+        if index < 0 || index > length {
+            let msg = CString::new(format!(
+                "List index out of bounds: cannot insert at index {} in a list of length {}",
+                index, length
+            )).unwrap();
+            runtime_error(msg.as_ptr());
+        }
+
+        // Push to grow the array, then move the tail up over the pushed slot
+        list_push_i64(list, value);
+        let slot = (*list).data.offset(index as isize);
+        std::ptr::copy(slot, slot.add(1), (length - index) as usize);
+        *slot = value;
+    }
+}
+
+/// Remove and return the element at index, shifting the rest down. Like pop(),
+/// the list's reference to the element goes to the caller.
+#[no_mangle]
+pub extern "C" fn list_remove_at_i64(list: *mut List, index: i64) -> i64 {
+    unsafe {
+        if list.is_null() {
+            let msg = CString::new("List remove error: null list").unwrap();
+            runtime_error(msg.as_ptr());
+        }
+
+        let list_ref = &mut *list;
+
+        if index < 0 || index >= list_ref.length {
+            let msg = CString::new(format!(
+                "List index out of bounds: index {} is out of range for list of length {}",
+                index, list_ref.length
+            )).unwrap();
+            runtime_error(msg.as_ptr());
+        }
+
+        let slot = list_ref.data.offset(index as isize);
+        let value = *slot;
+        std::ptr::copy(slot.add(1), slot, (list_ref.length - index - 1) as usize);
+        list_ref.length -= 1;
+        value
+    }
+}
+
+/// Index of the first element equal to `value`, or -1. Ints and bools compare
+/// by value, and lists, dicts and objects by identity.
+#[no_mangle]
+pub extern "C" fn list_index_of_i64(list: *const List, value: i64) -> i64 {
+    list_position(list, |element| element == value)
+}
+
+/// Index of the first float element equal to `value`, or -1
+#[no_mangle]
+pub extern "C" fn list_index_of_f64(list: *const List, value: f64) -> i64 {
+    list_position(list, |element| f64::from_bits(element as u64) == value)
+}
+
+/// Index of the first element that is `value` itself (a list, dict or object), or -1
+#[no_mangle]
+pub extern "C" fn list_index_of_ptr(list: *const List, value: *mut u8) -> i64 {
+    list_index_of_i64(list, value as i64)
+}
+
+/// Index of the first string element with the same contents as `value`, or -1
+#[no_mangle]
+pub extern "C" fn list_index_of_str(list: *const List, value: *const c_char) -> i64 {
+    if value.is_null() {
+        return list_index_of_i64(list, 0);
+    }
+    let wanted = unsafe { CStr::from_ptr(value) };
+    list_position(list, |element| {
+        let element = element as *const c_char;
+        !element.is_null() && unsafe { CStr::from_ptr(element) } == wanted
+    })
+}
+
+fn list_position(list: *const List, mut matches: impl FnMut(i64) -> bool) -> i64 {
+    if list.is_null() {
+        return -1;
+    }
+    unsafe {
+        let list_ref = &*list;
+        (0..list_ref.length)
+            .find(|&i| matches(*list_ref.data.offset(i as isize)))
+            .unwrap_or(-1)
+    }
+}
+
+/// Remove every element, releasing the ones the list owns. The capacity is kept.
+#[no_mangle]
+pub extern "C" fn list_clear(list: *mut List) {
+    if list.is_null() {
+        return;
+    }
+
+    unsafe {
+        let list_ref = &mut *list;
+        for i in 0..list_ref.length {
+            release_element(list_ref.elem_kind, *list_ref.data.offset(i as isize));
+        }
+        list_ref.length = 0;
+    }
+}
+
+/// Sort an int or bool list in place, ascending
+#[no_mangle]
+pub extern "C" fn list_sort_i64(list: *mut List) {
+    if let Some(elements) = list_elements_mut(list) {
+        elements.sort_unstable();
+    }
+}
+
+/// Sort a float list in place, ascending. NaN sorts after every number.
+#[no_mangle]
+pub extern "C" fn list_sort_f64(list: *mut List) {
+    if let Some(elements) = list_elements_mut(list) {
+        elements.sort_unstable_by(|a, b| f64::from_bits(*a as u64).total_cmp(&f64::from_bits(*b as u64)));
+    }
+}
+
+/// Sort a string list in place by bytes, so uppercase sorts before lowercase
+#[no_mangle]
+pub extern "C" fn list_sort_str(list: *mut List) {
+    if let Some(elements) = list_elements_mut(list) {
+        let contents = |element: i64| {
+            let element = element as *const c_char;
+            (!element.is_null()).then(|| unsafe { CStr::from_ptr(element) })
+        };
+        elements.sort_by(|a, b| contents(*a).cmp(&contents(*b)));
+    }
+}
+
+/// Sort a list in place with `before(a, b)`, which returns nonzero when the
+/// element in slot `a` belongs before the one in `b`. The sort is stable.
+/// `before` runs program code, so it sorts a copy of the slots and fails if the
+/// list changed size in the meantime.
+#[no_mangle]
+pub extern "C" fn list_sort_by(list: *mut List, before: extern "C" fn(i64, i64) -> i64) {
+    let Some(elements) = list_elements_mut(list) else {
+        return;
+    };
+    let mut sorted = elements.to_vec();
+    merge_sort_by(&mut sorted, &mut |a, b| before(a, b) != 0);
+
+    unsafe {
+        let list_ref = &mut *list;
+        if list_ref.length as usize != sorted.len() {
+            let msg = CString::new("List sort_by error: the list changed size while sorting").unwrap();
+            runtime_error(msg.as_ptr());
+        }
+        std::ptr::copy_nonoverlapping(sorted.as_ptr(), list_ref.data, sorted.len());
+    }
+}
+
+// A merge sort, so a `before` that isn't a consistent order still gives back
+// every element
+fn merge_sort_by(elements: &mut [i64], before: &mut impl FnMut(i64, i64) -> bool) {
+    if elements.len() < 2 {
+        return;
+    }
+    let mid = elements.len() / 2;
+    merge_sort_by(&mut elements[..mid], before);
+    merge_sort_by(&mut elements[mid..], before);
+
+    // The right half's leftovers are already in place
+    let left = elements[..mid].to_vec();
+    let (mut i, mut j) = (0, mid);
+    for k in 0..elements.len() {
+        if i == left.len() {
+            break;
+        }
+        if j < elements.len() && before(elements[j], left[i]) {
+            elements[k] = elements[j];
+            j += 1;
+        } else {
+            elements[k] = left[i];
+            i += 1;
+        }
+    }
+}
+
+fn list_elements_mut<'a>(list: *mut List) -> Option<&'a mut [i64]> {
+    if list.is_null() {
+        return None;
+    }
+    unsafe {
+        let list_ref = &mut *list;
+        if list_ref.length == 0 {
+            return None;
+        }
+        Some(std::slice::from_raw_parts_mut(list_ref.data, list_ref.length as usize))
+    }
+}
+
+/// Reverse a list in place
+#[no_mangle]
+pub extern "C" fn list_reverse(list: *mut List) {
+    if let Some(elements) = list_elements_mut(list) {
+        elements.reverse();
+    }
+}
+
+/// Append the elements of `other` to `list`, which takes its own references to
+/// them. `xs.extend(xs)` appends a copy of the list to itself.
+#[no_mangle]
+pub extern "C" fn list_extend(list: *mut List, other: *const List) {
+    unsafe {
+        if list.is_null() {
+            let msg = CString::new("List extend error: null list").unwrap();
+            runtime_error(msg.as_ptr());
+        }
+        if other.is_null() {
+            return;
+        }
+
+        // Read through `other` on every step: when it is `list`, pushing can move the data
+        let count = (*other).length;
+        for i in 0..count {
+            let value = *(*other).data.offset(i as isize);
+            retain_element((*list).elem_kind, value);
+            list_push_i64(list, value);
+        }
+    }
+}
+
+/// A new list with the elements of `left` followed by those of `right` (`xs + ys`)
+#[no_mangle]
+pub extern "C" fn list_concat(left: *const List, right: *const List) -> *mut List {
+    unsafe {
+        if left.is_null() || right.is_null() {
+            let msg = CString::new("List concatenation error: null list").unwrap();
+            runtime_error(msg.as_ptr());
+        }
+
+        // A list literal's kind may be unknown (plain); the other operand's then decides
+        let elem_kind = if (*left).elem_kind != ELEMS_PLAIN { (*left).elem_kind } else { (*right).elem_kind };
+        let list = list_create(elem_kind);
+        list_extend(list, left);
+        list_extend(list, right);
+        list
+    }
+}
+
+// Typed element access. Every element is an 8-byte slot; these store floats as
+// their bits and pointers (strings, lists, dicts, objects) as addresses, so
+// generated code can pass and receive them with their own LLVM types. A pushed
+// list, dict or object is owned by the list (codegen retains it first), and
+// pop() hands that reference back to the caller.
+
+/// Push a float to a list
+#[no_mangle]
+pub extern "C" fn list_push_f64(list: *mut List, value: f64) {
+    list_push_i64(list, value.to_bits() as i64);
+}
+
+/// Get a float element
+#[no_mangle]
+pub extern "C" fn list_get_f64(list: *const List, index: i64) -> f64 {
+    f64::from_bits(list_get_i64(list, index) as u64)
+}
+
+/// Set a float element
+#[no_mangle]
+pub extern "C" fn list_set_f64(list: *mut List, index: i64, value: f64) {
+    list_set_i64(list, index, value.to_bits() as i64);
+}
+
+/// Pop a float element
+#[no_mangle]
+pub extern "C" fn list_pop_f64(list: *mut List) -> f64 {
+    f64::from_bits(list_pop_i64(list) as u64)
+}
+
+/// Push a pointer to a list
+#[no_mangle]
+pub extern "C" fn list_push_ptr(list: *mut List, value: *mut u8) {
+    list_push_i64(list, value as i64);
+}
+
+/// Get a pointer element
+#[no_mangle]
+pub extern "C" fn list_get_ptr(list: *const List, index: i64) -> *mut u8 {
+    list_get_i64(list, index) as *mut u8
+}
+
+/// Set a pointer element
+#[no_mangle]
+pub extern "C" fn list_set_ptr(list: *mut List, index: i64, value: *mut u8) {
+    list_set_i64(list, index, value as i64);
+}
+
+/// Pop a pointer element
+#[no_mangle]
+pub extern "C" fn list_pop_ptr(list: *mut List) -> *mut u8 {
+    list_pop_i64(list) as *mut u8
+}
+
+/// Insert a float before index
+#[no_mangle]
+pub extern "C" fn list_insert_f64(list: *mut List, index: i64, value: f64) {
+    list_insert_i64(list, index, value.to_bits() as i64);
+}
+
+/// Remove and return the float at index
+#[no_mangle]
+pub extern "C" fn list_remove_at_f64(list: *mut List, index: i64) -> f64 {
+    f64::from_bits(list_remove_at_i64(list, index) as u64)
+}
+
+/// Insert a pointer before index
+#[no_mangle]
+pub extern "C" fn list_insert_ptr(list: *mut List, index: i64, value: *mut u8) {
+    list_insert_i64(list, index, value as i64);
+}
+
+/// Remove and return the pointer at index
+#[no_mangle]
+pub extern "C" fn list_remove_at_ptr(list: *mut List, index: i64) -> *mut u8 {
+    list_remove_at_i64(list, index) as *mut u8
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::rc::{rc_get_count, rc_release, rc_retain, ELEMS_PLAIN};
 
     fn create_test_list() -> Box<List> {
         Box::new(List {
             data: std::ptr::null_mut(),
             length: 0,
             capacity: 0,
+            elem_kind: ELEMS_PLAIN,
         })
     }
 
@@ -313,4 +688,372 @@ mod tests {
             assert_eq!(list_get_i64(list_ptr, i), i);
         }
     }
+
+    /// Contents of a list returned by the runtime
+    fn contents(list: *const List) -> Vec<i64> {
+        assert!(!list.is_null());
+        unsafe { (0..(*list).length).map(|i| list_get_i64(list, i)).collect() }
+    }
+
+    #[test]
+    fn test_list_push_after_pop_reuses_capacity() {
+        let mut list = create_test_list();
+        let list_ptr = &mut *list as *mut List;
+
+        for i in 0..4 {
+            list_push_i64(list_ptr, i);
+        }
+        assert_eq!(list_pop_i64(list_ptr), 3);
+        assert_eq!(list_pop_i64(list_ptr), 2);
+
+        list_push_i64(list_ptr, 7);
+        list_push_i64(list_ptr, 8);
+        assert_eq!(list.capacity, 4);
+        assert_eq!(contents(list_ptr), vec![0, 1, 7, 8]);
+    }
+
+    #[test]
+    fn test_list_pop_to_empty_and_refill() {
+        let mut list = create_test_list();
+        let list_ptr = &mut *list as *mut List;
+
+        list_push_i64(list_ptr, 1);
+        assert_eq!(list_pop_i64(list_ptr), 1);
+        assert_eq!(list.length, 0);
+
+        list_push_i64(list_ptr, 2);
+        assert_eq!(contents(list_ptr), vec![2]);
+    }
+
+    #[test]
+    fn test_list_extreme_values() {
+        let mut list = create_test_list();
+        let list_ptr = &mut *list as *mut List;
+
+        list_push_i64(list_ptr, i64::MIN);
+        list_push_i64(list_ptr, i64::MAX);
+        list_push_i64(list_ptr, 0);
+        assert_eq!(list_get_i64(list_ptr, 0), i64::MIN);
+        assert_eq!(list_get_i64(list_ptr, 1), i64::MAX);
+
+        list_set_i64(list_ptr, 2, -1);
+        assert_eq!(list_pop_i64(list_ptr), -1);
+        assert_eq!(list_pop_i64(list_ptr), i64::MAX);
+    }
+
+    #[test]
+    fn test_list_slice() {
+        let mut list = create_test_list();
+        let list_ptr = &mut *list as *mut List;
+        for i in 0..10 {
+            list_push_i64(list_ptr, i);
+        }
+
+        // -1 for start/end and 0 for step mean "default"
+        assert_eq!(contents(list_slice_i64(list_ptr, 2, 5, 0)), vec![2, 3, 4]);
+        assert_eq!(contents(list_slice_i64(list_ptr, -1, -1, 3)), vec![0, 3, 6, 9]);
+        assert_eq!(contents(list_slice_i64(list_ptr, -3, -1, 0)), vec![7, 8, 9]);
+        assert_eq!(contents(list_slice_i64(list_ptr, -1, -1, -1)), (0..10).rev().collect::<Vec<_>>());
+        assert_eq!(contents(list_slice_i64(list_ptr, 6, 1, -2)), vec![6, 4, 2]);
+
+        // The source list is untouched
+        assert_eq!(contents(list_ptr), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_list_slice_out_of_range_and_empty() {
+        let mut list = create_test_list();
+        let list_ptr = &mut *list as *mut List;
+        for i in 0..5 {
+            list_push_i64(list_ptr, i);
+        }
+
+        assert_eq!(contents(list_slice_i64(list_ptr, 3, 100, 0)), vec![3, 4]);
+        assert_eq!(contents(list_slice_i64(list_ptr, -100, 2, 0)), vec![0, 1]);
+        assert_eq!(contents(list_slice_i64(list_ptr, 4, 1, 0)), Vec::<i64>::new());
+        assert_eq!(contents(list_slice_i64(list_ptr, 100, 100, 0)), Vec::<i64>::new());
+
+        let mut empty = create_test_list();
+        let empty_ptr = &mut *empty as *mut List;
+        let slice = list_slice_i64(empty_ptr, -1, -1, -1);
+        assert_eq!(contents(slice), Vec::<i64>::new());
+        assert!(unsafe { (*slice).data.is_null() });
+    }
+
+    #[test]
+    fn test_nested_lists_are_released_with_their_parent() {
+        use super::super::rc::ELEMS_LIST;
+
+        let grid = list_create(ELEMS_LIST);
+        let row = list_create(ELEMS_PLAIN);
+        list_push_i64(row, 7);
+        // Owned by the grid and by this test
+        rc_retain(row as *mut u8);
+        list_push_ptr(grid, row as *mut u8);
+        assert_eq!(rc_get_count(row as *mut u8), 2);
+
+        // A slice shares the row
+        let slice = list_slice_i64(grid, -1, -1, 0);
+        assert_eq!(rc_get_count(row as *mut u8), 3);
+        list_free(slice);
+        assert_eq!(rc_get_count(row as *mut u8), 2);
+
+        // Replacing the row releases the grid's reference to it
+        list_set_ptr(grid, 0, list_create(ELEMS_PLAIN) as *mut u8);
+        assert_eq!(rc_get_count(row as *mut u8), 1);
+        rc_retain(row as *mut u8);
+        list_push_ptr(grid, row as *mut u8);
+
+        // Freeing the grid frees the new row and releases this one
+        list_free(grid);
+        assert_eq!(rc_get_count(row as *mut u8), 1);
+        assert_eq!(list_get_i64(row, 0), 7);
+        list_free(row);
+    }
+
+    #[test]
+    fn test_list_slice_is_rc_allocated() {
+        // Slices are released like any other list
+        let mut list = create_test_list();
+        let list_ptr = &mut *list as *mut List;
+        list_push_i64(list_ptr, 1);
+        let slice = list_slice_i64(list_ptr, -1, -1, 0);
+        assert_eq!(rc_get_count(slice as *mut u8), 1);
+        rc_release(slice as *mut u8);
+    }
+
+    #[test]
+    fn test_list_float_elements() {
+        let mut list = create_test_list();
+        let list_ptr = &mut *list as *mut List;
+        list_push_f64(list_ptr, 1.5);
+        list_push_f64(list_ptr, -0.25);
+        list_push_f64(list_ptr, f64::INFINITY);
+        assert_eq!(list_get_f64(list_ptr, 0), 1.5);
+        assert_eq!(list_get_f64(list_ptr, 1), -0.25);
+
+        list_set_f64(list_ptr, 0, 3.75);
+        assert_eq!(list_get_f64(list_ptr, 0), 3.75);
+        assert_eq!(list_pop_f64(list_ptr), f64::INFINITY);
+        assert_eq!(list.length, 2);
+    }
+
+    #[test]
+    fn test_list_pointer_elements() {
+        let names = [c"ada", c"grace", c"linus"];
+        let mut list = create_test_list();
+        let list_ptr = &mut *list as *mut List;
+        for name in &names {
+            list_push_ptr(list_ptr, name.as_ptr() as *mut u8);
+        }
+        assert_eq!(list_get_ptr(list_ptr, 1), names[1].as_ptr() as *mut u8);
+
+        list_set_ptr(list_ptr, 0, std::ptr::null_mut());
+        assert!(list_get_ptr(list_ptr, 0).is_null());
+        assert_eq!(list_pop_ptr(list_ptr), names[2].as_ptr() as *mut u8);
+        assert_eq!(list.length, 2);
+    }
+
+    #[test]
+    fn test_list_insert_and_remove_at() {
+        let list = list_create(ELEMS_PLAIN);
+        for value in [1, 2, 4] {
+            list_push_i64(list, value);
+        }
+        list_insert_i64(list, 2, 3);
+        list_insert_i64(list, 0, 0);
+        list_insert_i64(list, 5, 5);
+        assert_eq!(contents(list), vec![0, 1, 2, 3, 4, 5]);
+
+        assert_eq!(list_remove_at_i64(list, 0), 0);
+        assert_eq!(list_remove_at_i64(list, 4), 5);
+        assert_eq!(list_remove_at_i64(list, 1), 2);
+        assert_eq!(contents(list), vec![1, 3, 4]);
+
+        list_insert_f64(list, 1, 2.5);
+        assert_eq!(list_remove_at_f64(list, 1), 2.5);
+        list_free(list);
+    }
+
+    #[test]
+    fn test_list_insert_into_empty_list() {
+        let list = list_create(ELEMS_PLAIN);
+        list_insert_i64(list, 0, 9);
+        assert_eq!(contents(list), vec![9]);
+        list_free(list);
+    }
+
+    #[test]
+    fn test_list_index_of() {
+        let list = list_create(ELEMS_PLAIN);
+        for value in [5, 7, 5] {
+            list_push_i64(list, value);
+        }
+        assert_eq!(list_index_of_i64(list, 5), 0);
+        assert_eq!(list_index_of_i64(list, 7), 1);
+        assert_eq!(list_index_of_i64(list, 8), -1);
+        assert_eq!(list_index_of_i64(std::ptr::null(), 5), -1);
+        list_free(list);
+
+        let floats = list_create(ELEMS_PLAIN);
+        list_push_f64(floats, 0.5);
+        list_push_f64(floats, -0.0);
+        assert_eq!(list_index_of_f64(floats, 0.0), 1);
+        assert_eq!(list_index_of_f64(floats, f64::NAN), -1);
+        list_free(floats);
+
+        // Strings match by contents, not by address
+        let names = list_create(ELEMS_PLAIN);
+        let stored = CString::new("grace").unwrap();
+        list_push_ptr(names, c"ada".as_ptr() as *mut u8);
+        list_push_ptr(names, std::ptr::null_mut());
+        list_push_ptr(names, stored.as_ptr() as *mut u8);
+        let wanted = CString::new("grace").unwrap();
+        assert_eq!(list_index_of_str(names, wanted.as_ptr()), 2);
+        assert_eq!(list_index_of_str(names, c"linus".as_ptr()), -1);
+        assert_eq!(list_index_of_ptr(names, stored.as_ptr() as *mut u8), 2);
+        assert_eq!(list_index_of_ptr(names, wanted.as_ptr() as *mut u8), -1);
+        list_free(names);
+    }
+
+    #[test]
+    fn test_list_clear_releases_elements() {
+        use super::super::rc::ELEMS_LIST;
+
+        let grid = list_create(ELEMS_LIST);
+        let row = list_create(ELEMS_PLAIN);
+        rc_retain(row as *mut u8);
+        list_push_ptr(grid, row as *mut u8);
+        list_push_ptr(grid, list_create(ELEMS_PLAIN) as *mut u8);
+
+        list_clear(grid);
+        assert_eq!(contents(grid), Vec::<i64>::new());
+        assert_eq!(rc_get_count(row as *mut u8), 1);
+
+        // The capacity is kept for refilling
+        let capacity = unsafe { (*grid).capacity };
+        rc_retain(row as *mut u8);
+        list_push_ptr(grid, row as *mut u8);
+        assert_eq!(unsafe { (*grid).capacity }, capacity);
+        list_free(grid);
+        list_free(row);
+    }
+
+    #[test]
+    fn test_list_reverse() {
+        let list = list_create(ELEMS_PLAIN);
+        list_reverse(list);
+        for value in [1, 2, 3, 4] {
+            list_push_i64(list, value);
+        }
+        list_reverse(list);
+        assert_eq!(contents(list), vec![4, 3, 2, 1]);
+        list_free(list);
+    }
+
+    #[test]
+    fn test_list_extend() {
+        let list = list_create(ELEMS_PLAIN);
+        let other = list_create(ELEMS_PLAIN);
+        list_push_i64(list, 1);
+        for value in [2, 3, 4, 5] {
+            list_push_i64(other, value);
+        }
+        list_extend(list, other);
+        assert_eq!(contents(list), vec![1, 2, 3, 4, 5]);
+        assert_eq!(contents(other), vec![2, 3, 4, 5]);
+
+        // Extending a list with itself grows it while reading it
+        list_extend(list, list);
+        assert_eq!(contents(list), vec![1, 2, 3, 4, 5, 1, 2, 3, 4, 5]);
+        list_free(list);
+        list_free(other);
+    }
+
+    #[test]
+    fn test_list_concat_retains_elements() {
+        use super::super::rc::ELEMS_LIST;
+
+        let row = list_create(ELEMS_PLAIN);
+        let left = list_create(ELEMS_LIST);
+        list_push_ptr(left, row as *mut u8);
+        // A list literal whose element kind wasn't known
+        let right = list_create(ELEMS_PLAIN);
+
+        let joined = list_concat(left, right);
+        assert_eq!(unsafe { (*joined).elem_kind }, ELEMS_LIST);
+        assert_eq!(contents(joined), vec![row as i64]);
+        assert_eq!(rc_get_count(row as *mut u8), 2);
+
+        list_free(joined);
+        assert_eq!(rc_get_count(row as *mut u8), 1);
+        list_free(left);
+        list_free(right);
+    }
+
+    #[test]
+    fn test_list_sort() {
+        let list = list_create(ELEMS_PLAIN);
+        for value in [3, -1, 2, 3, 0] {
+            list_push_i64(list, value);
+        }
+        list_sort_i64(list);
+        assert_eq!(contents(list), vec![-1, 0, 2, 3, 3]);
+        list_free(list);
+
+        // Empty and null lists are left alone
+        let empty = list_create(ELEMS_PLAIN);
+        list_sort_i64(empty);
+        list_sort_f64(std::ptr::null_mut());
+        list_free(empty);
+
+        let floats = list_create(ELEMS_PLAIN);
+        for value in [2.5, f64::NAN, -1.0, 0.5] {
+            list_push_f64(floats, value);
+        }
+        list_sort_f64(floats);
+        assert_eq!(list_get_f64(floats, 0), -1.0);
+        assert_eq!(list_get_f64(floats, 2), 2.5);
+        assert!(list_get_f64(floats, 3).is_nan());
+        list_free(floats);
+
+        let words = [c"pear", c"Apple", c"apple", c"fig"];
+        let names = list_create(ELEMS_PLAIN);
+        for word in &words {
+            list_push_ptr(names, word.as_ptr() as *mut u8);
+        }
+        list_sort_str(names);
+        let sorted: Vec<&CStr> = (0..4)
+            .map(|i| unsafe { CStr::from_ptr(list_get_ptr(names, i) as *const c_char) })
+            .collect();
+        assert_eq!(sorted, vec![c"Apple", c"apple", c"fig", c"pear"]);
+        list_free(names);
+    }
+
+    #[test]
+    fn test_list_sort_by() {
+        extern "C" fn by_tens(a: i64, b: i64) -> i64 {
+            (a / 10 < b / 10) as i64
+        }
+        extern "C" fn descending(a: i64, b: i64) -> i64 {
+            (a > b) as i64
+        }
+
+        // Elements in the same ten keep their order
+        let list = list_create(ELEMS_PLAIN);
+        for value in [31, 12, 35, 5, 17, 30] {
+            list_push_i64(list, value);
+        }
+        list_sort_by(list, by_tens);
+        assert_eq!(contents(list), vec![5, 12, 17, 31, 35, 30]);
+
+        list_sort_by(list, descending);
+        assert_eq!(contents(list), vec![35, 31, 30, 17, 12, 5]);
+        list_free(list);
+
+        let empty = list_create(ELEMS_PLAIN);
+        list_sort_by(empty, descending);
+        list_sort_by(std::ptr::null_mut(), descending);
+        list_free(empty);
+    }
 }
@@ -4,11 +4,29 @@
 pub mod list;
 pub mod dict;
 pub mod string;
+pub mod float;
+pub mod bigint;
+pub mod decimal;
 pub mod rc;
 pub mod io;
 pub mod exceptions;
 pub mod cli;
 pub mod http;
+pub mod smtp;
+pub mod compress;
+pub mod random;
+pub mod fs;
+pub mod regex;
+pub mod bytes;
+pub mod time;
+pub mod os;
+pub mod process;
+pub mod path;
+pub mod csv;
+pub mod config;
+pub mod net;
+pub mod source_map;
+mod traceback;
 
 use std::ffi::CStr;
 use std::os::raw::c_char;
@@ -39,23 +57,13 @@ pub extern "C" fn pop_call_stack() {
     }
 }
 
-/// Print runtime error message with stack trace and exit
+/// Report a runtime error (see traceback::report_fatal) and exit
 #[no_mangle]
 pub extern "C" fn runtime_error(message: *const c_char) {
     unsafe {
         if !message.is_null() {
             if let Ok(msg) = CStr::from_ptr(message).to_str() {
-                eprintln!("\n\x1b[31;1mRuntime Error:\x1b[0m {}", msg);
-
-                // Show call stack if available
-                if let Ok(stack) = CALL_STACK.lock() {
-                    if !stack.is_empty() {
-                        eprintln!("\n\x1b[36;1mCall stack:\x1b[0m");
-                        for (i, func) in stack.iter().rev().enumerate() {
-                            eprintln!("  \x1b[90m{}\x1b[0m. {}", i + 1, func);
-                        }
-                    }
-                }
+                traceback::report_fatal("Runtime Error:", &[msg.to_string()], None);
             }
         }
         std::process::exit(1);
@@ -0,0 +1,324 @@
+//! TCP socket runtime for WadeScript
+//!
+//! Connecting to servers, listening for and accepting connections, and
+//! reading and writing text over them. Listeners and connections are kept
+//! behind integer handles, like open files. std/net.ws wraps these for
+//! clients and simple servers that the http_* helpers don't cover.
+//!
+//! Everything blocks: reads wait for data unless a timeout is set, and
+//! accept waits for the next client.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::exceptions::raise;
+use super::io::read_input_line;
+use super::string::{alloc_str, as_bytes};
+
+lazy_static::lazy_static! {
+    static ref SOCKETS: Mutex<SocketManager> = Mutex::new(SocketManager::new());
+}
+
+/// An open connection. Reads go through `reader`, so text a line read
+/// buffered past the line end is seen by the next read
+struct Connection {
+    stream: TcpStream,
+    reader: Mutex<BufReader<TcpStream>>,
+}
+
+enum Socket {
+    Listener(TcpListener),
+    Connection(Connection),
+}
+
+struct SocketManager {
+    // Behind an Arc so a blocking read or accept doesn't hold the manager lock
+    sockets: HashMap<i64, Arc<Socket>>,
+    next_id: i64,
+}
+
+impl SocketManager {
+    fn new() -> Self {
+        SocketManager {
+            sockets: HashMap::new(),
+            next_id: 1,
+        }
+    }
+}
+
+fn to_text(s: *const u8) -> String {
+    String::from_utf8_lossy(unsafe { as_bytes(s) }).into_owned()
+}
+
+/// Store a new listener or connection and return its handle
+fn add(socket: Socket) -> i64 {
+    let mut manager = SOCKETS.lock().unwrap();
+    let id = manager.next_id;
+    manager.next_id += 1;
+    manager.sockets.insert(id, Arc::new(socket));
+    id
+}
+
+/// The socket behind a handle. The lock is released before raising
+/// ValueError for a handle that was never created or is closed
+fn socket(handle: i64, function: &str) -> Arc<Socket> {
+    let socket = SOCKETS.lock().unwrap().sockets.get(&handle).cloned();
+    match socket {
+        Some(socket) => socket,
+        None => raise("ValueError", format!("{}() got an invalid socket handle {}", function, handle)),
+    }
+}
+
+/// Run `f` on a connection, raising ValueError if the handle is a listener
+/// and OSError if `f` fails. The socket is released before raising
+fn with_connection<T>(handle: i64, function: &str, f: impl FnOnce(&Connection) -> std::io::Result<T>) -> T {
+    let socket = socket(handle, function);
+    let result = match &*socket {
+        Socket::Connection(connection) => f(connection).map_err(|e| match e.kind() {
+            // A read or write that runs past the timeout fails with one of these
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
+                ("OSError", format!("{}() timed out", function))
+            }
+            _ => ("OSError", format!("{}() failed: {}", function, e)),
+        }),
+        Socket::Listener(_) => Err((
+            "ValueError",
+            format!("{}() needs a connection, but handle {} is a listener", function, handle),
+        )),
+    };
+    drop(socket);
+    match result {
+        Ok(value) => value,
+        Err((error_type, message)) => raise(error_type, message),
+    }
+}
+
+/// The port as a u16
+/// Raises ValueError if it is outside 0-65535
+fn checked_port(port: i64, function: &str) -> u16 {
+    match u16::try_from(port) {
+        Ok(port) => port,
+        Err(_) => raise("ValueError", format!("{}() port must be between 0 and 65535, got {}", function, port)),
+    }
+}
+
+fn new_connection(stream: TcpStream) -> std::io::Result<Socket> {
+    let reader = BufReader::new(stream.try_clone()?);
+    Ok(Socket::Connection(Connection {
+        stream,
+        reader: Mutex::new(reader),
+    }))
+}
+
+/// Up to `max_bytes` of what is available, waiting for at least one byte.
+/// Empty once the other side has closed the connection
+fn read_some(reader: &mut impl BufRead, max_bytes: usize) -> std::io::Result<Vec<u8>> {
+    let available = reader.fill_buf()?;
+    let data = available[..available.len().min(max_bytes)].to_vec();
+    reader.consume(data.len());
+    Ok(data)
+}
+
+/// Connect to `host` (a name or an IP address) on `port` and return a
+/// connection handle. Each address the name resolves to is tried in turn
+/// Raises OSError if no connection can be made
+#[no_mangle]
+pub extern "C" fn tcp_connect(host: *const u8, port: i64) -> i64 {
+    let port = checked_port(port, "tcp_connect");
+    let host = to_text(host);
+    let connected = TcpStream::connect((host.as_str(), port)).and_then(new_connection);
+    match connected {
+        Ok(socket) => add(socket),
+        Err(e) => {
+            let message = format!("tcp_connect() failed for '{}:{}': {}", host, port, e);
+            drop((host, e));
+            raise("OSError", message)
+        }
+    }
+}
+
+/// Listen on `host` and `port` and return a listener handle. Port 0 picks a
+/// free port (see tcp_local_port); host "0.0.0.0" accepts connections from
+/// other machines, "127.0.0.1" only from this one
+/// Raises OSError if the address can't be used (e.g. the port is taken)
+#[no_mangle]
+pub extern "C" fn tcp_listen(host: *const u8, port: i64) -> i64 {
+    let port = checked_port(port, "tcp_listen");
+    let host = to_text(host);
+    let bound = TcpListener::bind((host.as_str(), port));
+    match bound {
+        Ok(listener) => add(Socket::Listener(listener)),
+        Err(e) => {
+            let message = format!("tcp_listen() failed for '{}:{}': {}", host, port, e);
+            drop((host, e));
+            raise("OSError", message)
+        }
+    }
+}
+
+/// Wait for the next client of a listener and return a handle to its
+/// connection
+/// Raises ValueError if the handle isn't a listener
+#[no_mangle]
+pub extern "C" fn tcp_accept(handle: i64) -> i64 {
+    let socket = socket(handle, "tcp_accept");
+    let accepted = match &*socket {
+        Socket::Listener(listener) => listener
+            .accept()
+            .and_then(|(stream, _)| new_connection(stream))
+            .map_err(|e| ("OSError", format!("tcp_accept() failed: {}", e))),
+        Socket::Connection(_) => Err((
+            "ValueError",
+            format!("tcp_accept() needs a listener, but handle {} is a connection", handle),
+        )),
+    };
+    drop(socket);
+    match accepted {
+        Ok(connection) => add(connection),
+        Err((error_type, message)) => raise(error_type, message),
+    }
+}
+
+/// Up to `max_bytes` bytes as a new string, waiting until some arrive. The
+/// result is "" once the other side has closed the connection
+/// Raises ValueError if max_bytes isn't positive
+#[no_mangle]
+pub extern "C" fn tcp_read(handle: i64, max_bytes: i64) -> *mut u8 {
+    if max_bytes <= 0 {
+        raise("ValueError", format!("tcp_read() byte count must be positive, got {}", max_bytes));
+    }
+    let data = with_connection(handle, "tcp_read", |connection| {
+        read_some(&mut *connection.reader.lock().unwrap(), max_bytes as usize)
+    });
+    unsafe { alloc_str(&data) }
+}
+
+/// The next line without its "\n" or "\r\n" as a new string, or null (None)
+/// once the other side has closed the connection. A last line without a
+/// line break is still returned
+#[no_mangle]
+pub extern "C" fn tcp_read_line(handle: i64) -> *mut u8 {
+    let line = with_connection(handle, "tcp_read_line", |connection| {
+        read_input_line(&mut *connection.reader.lock().unwrap())
+    });
+    match line {
+        Some(line) => unsafe { alloc_str(&line) },
+        None => ptr::null_mut(),
+    }
+}
+
+/// Send all of `data`, waiting while the other side catches up
+#[no_mangle]
+pub extern "C" fn tcp_write(handle: i64, data: *const u8) {
+    let data = unsafe { as_bytes(data) };
+    with_connection(handle, "tcp_write", |connection| (&connection.stream).write_all(data))
+}
+
+/// Make tcp_read, tcp_read_line and tcp_write raise OSError when they wait
+/// longer than `ms` milliseconds. 0 waits forever, the default
+/// Raises ValueError if ms is negative
+#[no_mangle]
+pub extern "C" fn tcp_set_timeout(handle: i64, ms: i64) {
+    if ms < 0 {
+        raise("ValueError", format!("tcp_set_timeout() timeout must not be negative, got {}", ms));
+    }
+    let timeout = (ms > 0).then(|| Duration::from_millis(ms as u64));
+    with_connection(handle, "tcp_set_timeout", |connection| {
+        connection.stream.set_read_timeout(timeout)?;
+        connection.stream.set_write_timeout(timeout)
+    })
+}
+
+/// The local port of a listener or connection, e.g. the one tcp_listen
+/// picked for port 0
+#[no_mangle]
+pub extern "C" fn tcp_local_port(handle: i64) -> i64 {
+    let address = match &*socket(handle, "tcp_local_port") {
+        Socket::Listener(listener) => listener.local_addr(),
+        Socket::Connection(connection) => connection.stream.local_addr(),
+    };
+    match address {
+        Ok(address) => address.port() as i64,
+        Err(e) => {
+            let message = format!("tcp_local_port() failed: {}", e);
+            drop(e);
+            raise("OSError", message)
+        }
+    }
+}
+
+/// Close a listener or connection; the other side of a connection then
+/// reads the end of it. Unknown handles are ignored
+#[no_mangle]
+pub extern "C" fn tcp_close(handle: i64) {
+    let socket = SOCKETS.lock().unwrap().sockets.remove(&handle);
+    if let Some(Socket::Connection(connection)) = socket.as_deref() {
+        // The reader holds a second copy of the stream, so dropping one isn't enough
+        let _ = connection.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn read(s: *const u8) -> String {
+        String::from_utf8(unsafe { as_bytes(s) }.to_vec()).unwrap()
+    }
+
+    /// A listener on a free local port, a client connected to it, and the
+    /// server's side of that connection
+    fn connected_pair() -> (i64, i64, i64) {
+        let host = CString::new("127.0.0.1").unwrap();
+        let listener = tcp_listen(host.as_ptr() as *const u8, 0);
+        let port = tcp_local_port(listener);
+        assert!(port > 0);
+        let client = tcp_connect(host.as_ptr() as *const u8, port);
+        let server = tcp_accept(listener);
+        (listener, client, server)
+    }
+
+    #[test]
+    fn test_lines_and_reads_share_a_buffer() {
+        let (listener, client, server) = connected_pair();
+        let message = CString::new("HELLO one\r\nsecond\nrest").unwrap();
+        tcp_write(client, message.as_ptr() as *const u8);
+        tcp_close(client);
+
+        assert_eq!(read(tcp_read_line(server)), "HELLO one");
+        assert_eq!(read(tcp_read(server, 3)), "sec");
+        assert_eq!(read(tcp_read_line(server)), "ond");
+        assert_eq!(read(tcp_read_line(server)), "rest", "a last line without a line break");
+        assert!(tcp_read_line(server).is_null());
+        assert_eq!(read(tcp_read(server, 10)), "", "reads after the end are empty");
+        tcp_close(server);
+        tcp_close(listener);
+    }
+
+    #[test]
+    fn test_read_some_stops_at_max_bytes() {
+        let mut reader = BufReader::new(&b"abcdef"[..]);
+        assert_eq!(read_some(&mut reader, 4).unwrap(), b"abcd");
+        assert_eq!(read_some(&mut reader, 4).unwrap(), b"ef");
+        assert!(read_some(&mut reader, 4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_close_ignores_unknown_handles() {
+        tcp_close(-1);
+        let (listener, client, server) = connected_pair();
+        for handle in [client, server, listener, client] {
+            tcp_close(handle);
+        }
+        assert!(!SOCKETS.lock().unwrap().sockets.contains_key(&client));
+    }
+}
@@ -0,0 +1,90 @@
+//! Operating system runtime for WadeScript
+//!
+//! Reading and changing the process's environment variables, for
+//! configuration like PATH, HOME or API keys.
+
+use super::exceptions::raise;
+use super::string::{alloc_str, as_bytes};
+
+/// A variable name as text. Names that are empty or contain '=' can't be
+/// stored in the environment, so they raise ValueError
+fn checked_name(name: *const u8, function: &str) -> String {
+    let name = String::from_utf8_lossy(unsafe { as_bytes(name) }).into_owned();
+    if name.is_empty() || name.contains('=') {
+        raise("ValueError", format!("{}() invalid environment variable name '{}'", function, name));
+    }
+    name
+}
+
+/// The value of an environment variable as a new string, or null (None) if
+/// it isn't set. Bytes that aren't valid UTF-8 become U+FFFD
+#[no_mangle]
+pub extern "C" fn env_get(name: *const u8) -> *mut u8 {
+    let name = String::from_utf8_lossy(unsafe { as_bytes(name) }).into_owned();
+    if name.is_empty() || name.contains('=') {
+        return std::ptr::null_mut();
+    }
+    match std::env::var_os(&name) {
+        Some(value) => unsafe { alloc_str(value.to_string_lossy().as_bytes()) },
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Set an environment variable for this process and the programs it starts
+/// Raises ValueError if the name is empty or contains '='
+#[no_mangle]
+pub extern "C" fn env_set(name: *const u8, value: *const u8) {
+    let name = checked_name(name, "env_set");
+    let value = String::from_utf8_lossy(unsafe { as_bytes(value) }).into_owned();
+    std::env::set_var(name, value);
+}
+
+/// Remove an environment variable. Variables that aren't set are ignored
+/// Raises ValueError if the name is empty or contains '='
+#[no_mangle]
+pub extern "C" fn env_unset(name: *const u8) {
+    let name = checked_name(name, "env_unset");
+    std::env::remove_var(name);
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn c(text: &str) -> CString {
+        CString::new(text).unwrap()
+    }
+
+    fn get(name: &str) -> Option<String> {
+        let value = env_get(c(name).as_ptr() as *const u8);
+        if value.is_null() {
+            return None;
+        }
+        Some(String::from_utf8(unsafe { as_bytes(value) }.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_env_set_get_and_unset() {
+        let name = c("WS_RUNTIME_TEST_VARIABLE");
+        assert_eq!(get("WS_RUNTIME_TEST_VARIABLE"), None);
+
+        env_set(name.as_ptr() as *const u8, c("héllo").as_ptr() as *const u8);
+        assert_eq!(get("WS_RUNTIME_TEST_VARIABLE").as_deref(), Some("héllo"));
+
+        env_set(name.as_ptr() as *const u8, c("").as_ptr() as *const u8);
+        assert_eq!(get("WS_RUNTIME_TEST_VARIABLE").as_deref(), Some(""), "set but empty is not None");
+
+        env_unset(name.as_ptr() as *const u8);
+        assert_eq!(get("WS_RUNTIME_TEST_VARIABLE"), None);
+        env_unset(name.as_ptr() as *const u8);
+
+        // A name that can't exist is simply not set
+        assert_eq!(get(""), None);
+        assert_eq!(get("A=B"), None);
+    }
+}
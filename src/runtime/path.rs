@@ -0,0 +1,210 @@
+//! Path manipulation runtime for WadeScript
+//!
+//! Joining and splitting "/"-separated paths as text, Python os.path style.
+//! Apart from `path_absolute`, which reads the current directory, nothing
+//! here touches the file system: ".." is resolved lexically, without
+//! following symbolic links.
+
+use super::exceptions::raise;
+use super::string::{alloc_str, as_bytes};
+
+/// `tail` appended to `head` with one "/" between them. An absolute `tail`
+/// replaces `head`, and an empty `head` gives `tail` unchanged
+fn join(head: &[u8], tail: &[u8]) -> Vec<u8> {
+    if tail.starts_with(b"/") || head.is_empty() {
+        return tail.to_vec();
+    }
+    let mut joined = head.to_vec();
+    if !joined.ends_with(b"/") {
+        joined.push(b'/');
+    }
+    joined.extend_from_slice(tail);
+    joined
+}
+
+/// Index just past the last "/", or 0 if there is none
+fn split_point(path: &[u8]) -> usize {
+    path.iter().rposition(|&b| b == b'/').map_or(0, |i| i + 1)
+}
+
+/// Everything before the last component, without trailing slashes unless
+/// that leaves only the root: "a/b" -> "a", "/a" -> "/", "a" -> ""
+fn dirname(path: &[u8]) -> &[u8] {
+    let head = &path[..split_point(path)];
+    let trimmed = head.iter().rposition(|&b| b != b'/').map_or(0, |i| i + 1);
+    if trimmed == 0 {
+        head
+    } else {
+        &head[..trimmed]
+    }
+}
+
+/// The last component: "a/b.txt" -> "b.txt", and "" for "a/"
+fn basename(path: &[u8]) -> &[u8] {
+    &path[split_point(path)..]
+}
+
+/// The extension of the last component including its ".", or "". Leading dots
+/// don't start one: "a.tar.gz" -> ".gz", ".bashrc" -> ""
+fn extension(path: &[u8]) -> &[u8] {
+    let name = basename(path);
+    let start = name.iter().position(|&b| b != b'.').unwrap_or(name.len());
+    match name[start..].iter().rposition(|&b| b == b'.') {
+        Some(dot) => &name[start + dot..],
+        None => b"",
+    }
+}
+
+/// Remove "." components and repeated and trailing slashes, and resolve ".."
+/// against the component before it. ".." that can't be resolved is kept in a
+/// relative path and dropped at the root. An empty result is "."
+fn normalize(path: &[u8]) -> Vec<u8> {
+    let absolute = path.starts_with(b"/");
+    let mut parts: Vec<&[u8]> = Vec::new();
+    for part in path.split(|&b| b == b'/') {
+        match part {
+            b"" | b"." => {}
+            b".." => match parts.last() {
+                Some(&last) if last != b".." => {
+                    parts.pop();
+                }
+                _ if absolute => {}
+                _ => parts.push(part),
+            },
+            _ => parts.push(part),
+        }
+    }
+    let mut normalized = Vec::new();
+    if absolute {
+        normalized.push(b'/');
+    }
+    normalized.extend_from_slice(&parts.join(&b'/'));
+    if normalized.is_empty() {
+        normalized.push(b'.');
+    }
+    normalized
+}
+
+/// Join two paths as a new string
+#[no_mangle]
+pub extern "C" fn path_join(head: *const u8, tail: *const u8) -> *mut u8 {
+    let joined = join(unsafe { as_bytes(head) }, unsafe { as_bytes(tail) });
+    unsafe { alloc_str(&joined) }
+}
+
+/// The directory part of a path as a new string
+#[no_mangle]
+pub extern "C" fn path_dirname(path: *const u8) -> *mut u8 {
+    unsafe { alloc_str(dirname(as_bytes(path))) }
+}
+
+/// The last component of a path as a new string
+#[no_mangle]
+pub extern "C" fn path_basename(path: *const u8) -> *mut u8 {
+    unsafe { alloc_str(basename(as_bytes(path))) }
+}
+
+/// The extension of a path, with its ".", as a new string
+#[no_mangle]
+pub extern "C" fn path_extension(path: *const u8) -> *mut u8 {
+    unsafe { alloc_str(extension(as_bytes(path))) }
+}
+
+/// A normalized copy of a path as a new string
+#[no_mangle]
+pub extern "C" fn path_normalize(path: *const u8) -> *mut u8 {
+    let normalized = normalize(unsafe { as_bytes(path) });
+    unsafe { alloc_str(&normalized) }
+}
+
+/// A path made absolute against the current directory, then normalized
+/// Raises OSError if the current directory can't be read (e.g. it was deleted)
+#[no_mangle]
+pub extern "C" fn path_absolute(path: *const u8) -> *mut u8 {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = unsafe { as_bytes(path) };
+    let joined = if path.starts_with(b"/") {
+        path.to_vec()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => join(cwd.as_os_str().as_bytes(), path),
+            Err(e) => raise("OSError", format!("absolute() cannot read the current directory: {}", e)),
+        }
+    };
+    unsafe { alloc_str(&normalize(&joined)) }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(bytes: &[u8]) -> &str {
+        std::str::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(text(&join(b"a", b"b")), "a/b");
+        assert_eq!(text(&join(b"a/", b"b")), "a/b");
+        assert_eq!(text(&join(b"a", b"/etc")), "/etc", "an absolute tail replaces the head");
+        assert_eq!(text(&join(b"", b"b")), "b");
+        assert_eq!(text(&join(b"a", b"")), "a/");
+    }
+
+    #[test]
+    fn test_dirname_and_basename() {
+        let cases: [(&str, &str, &str); 7] = [
+            ("src/lib/util.ws", "src/lib", "util.ws"),
+            ("util.ws", "", "util.ws"),
+            ("/util.ws", "/", "util.ws"),
+            ("/", "/", ""),
+            ("src/lib/", "src/lib", ""),
+            ("src//lib", "src", "lib"),
+            ("", "", ""),
+        ];
+        for (path, dir, base) in cases {
+            assert_eq!(text(dirname(path.as_bytes())), dir, "dirname of {:?}", path);
+            assert_eq!(text(basename(path.as_bytes())), base, "basename of {:?}", path);
+        }
+    }
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(text(extension(b"notes.txt")), ".txt");
+        assert_eq!(text(extension(b"archive.tar.gz")), ".gz");
+        assert_eq!(text(extension(b"dir.d/Makefile")), "", "dots in directories don't count");
+        assert_eq!(text(extension(b".bashrc")), "");
+        assert_eq!(text(extension(b"..config.yml")), ".yml");
+        assert_eq!(text(extension(b"trailing.")), ".");
+    }
+
+    #[test]
+    fn test_normalize() {
+        let cases = [
+            ("a/./b//c/", "a/b/c"),
+            ("a/b/../c", "a/c"),
+            ("../a/../../b", "../../b"),
+            ("/../a", "/a"),
+            ("a/..", "."),
+            ("", "."),
+            ("//a", "/a"),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(text(&normalize(path.as_bytes())), expected, "normalize {:?}", path);
+        }
+    }
+
+    #[test]
+    fn test_absolute() {
+        let cwd = std::env::current_dir().unwrap();
+        let path = std::ffi::CString::new("x/../y").unwrap();
+        let absolute = path_absolute(path.as_ptr() as *const u8);
+        let expected = format!("{}/y", cwd.to_str().unwrap());
+        assert_eq!(text(unsafe { as_bytes(absolute) }), expected);
+    }
+}
@@ -0,0 +1,185 @@
+//! Subprocess runtime for WadeScript
+//!
+//! Runs a program to completion with arguments and optional standard input,
+//! capturing its standard output, standard error and exit code. The result is
+//! kept behind an integer handle that std/process.ws reads and frees, like
+//! HTTP responses.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use super::exceptions::raise;
+use super::list::List;
+use super::string::{alloc_str, as_bytes};
+
+lazy_static::lazy_static! {
+    static ref PROCESSES: Mutex<ProcessManager> = Mutex::new(ProcessManager::new());
+}
+
+/// What a finished process left behind
+struct ProcessOutput {
+    exit_code: i64,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+struct ProcessManager {
+    outputs: HashMap<i64, ProcessOutput>,
+    next_id: i64,
+}
+
+impl ProcessManager {
+    fn new() -> Self {
+        ProcessManager {
+            outputs: HashMap::new(),
+            next_id: 1,
+        }
+    }
+}
+
+fn to_text(s: *const u8) -> String {
+    String::from_utf8_lossy(unsafe { as_bytes(s) }).into_owned()
+}
+
+/// Read one part of a finished process's output. The lock is released
+/// before raising ValueError for a handle that was never created or is freed
+fn with_output<T>(handle: i64, read: impl FnOnce(&ProcessOutput) -> T) -> T {
+    let value = PROCESSES.lock().unwrap().outputs.get(&handle).map(read);
+    match value {
+        Some(value) => value,
+        None => raise("ValueError", format!("invalid process handle {}", handle)),
+    }
+}
+
+/// Start `program` with `args`, feed it `stdin`, and wait for it to exit
+fn run(program: &str, args: &[String], stdin: &[u8]) -> std::io::Result<ProcessOutput> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Write the input on another thread: a child that fills its output pipe
+    // before reading all of its input would otherwise never finish
+    let mut pipe = child.stdin.take().expect("stdin is piped");
+    let input = stdin.to_vec();
+    let writer = std::thread::spawn(move || {
+        // A child that exits without reading its input closes the pipe; that isn't an error here
+        let _ = pipe.write_all(&input);
+    });
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+
+    Ok(ProcessOutput {
+        // A process killed by a signal has no exit code
+        exit_code: output.status.code().map_or(-1, i64::from),
+        stdout: output.stdout,
+        stderr: output.stderr,
+    })
+}
+
+/// Run a program to completion and return a handle to its output.
+/// `program` is found on PATH unless it contains a '/'; `args` is a list[str]
+/// passed as-is, without a shell
+/// Raises OSError if the program can't be started
+#[no_mangle]
+pub extern "C" fn process_run(program: *const u8, args: *const List, stdin: *const u8) -> i64 {
+    let program = to_text(program);
+    let args: Vec<String> = match unsafe { args.as_ref() } {
+        Some(list) => (0..list.length)
+            .map(|i| to_text(unsafe { *list.data.add(i as usize) } as *const u8))
+            .collect(),
+        None => Vec::new(),
+    };
+    let input = unsafe { as_bytes(stdin) };
+    let output = match run(&program, &args, input) {
+        Ok(output) => output,
+        Err(e) => {
+            let message = format!("cannot run '{}': {}", program, e);
+            drop((program, args, e));
+            raise("OSError", message)
+        }
+    };
+
+    let mut manager = PROCESSES.lock().unwrap();
+    let id = manager.next_id;
+    manager.next_id += 1;
+    manager.outputs.insert(id, output);
+    id
+}
+
+/// The exit code: 0 usually means success, -1 that a signal killed the process
+#[no_mangle]
+pub extern "C" fn process_exit_code(handle: i64) -> i64 {
+    with_output(handle, |output| output.exit_code)
+}
+
+/// Everything the process wrote to standard output, as a new string
+#[no_mangle]
+pub extern "C" fn process_stdout(handle: i64) -> *mut u8 {
+    let text = with_output(handle, |output| output.stdout.clone());
+    unsafe { alloc_str(&text) }
+}
+
+/// Everything the process wrote to standard error, as a new string
+#[no_mangle]
+pub extern "C" fn process_stderr(handle: i64) -> *mut u8 {
+    let text = with_output(handle, |output| output.stderr.clone());
+    unsafe { alloc_str(&text) }
+}
+
+/// Free a process's output. Unknown handles are ignored
+#[no_mangle]
+pub extern "C" fn process_free(handle: i64) {
+    PROCESSES.lock().unwrap().outputs.remove(&handle);
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::list::{list_create, list_free, list_push_i64};
+    use super::super::rc::ELEMS_PLAIN;
+    use std::ffi::CString;
+
+    fn read(s: *const u8) -> String {
+        String::from_utf8(unsafe { as_bytes(s) }.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_run_captures_output_and_exit_code() {
+        let program = CString::new("sh").unwrap();
+        let script = CString::new("read name; echo \"hi $name\"; echo oops >&2; exit 3").unwrap();
+        let flag = CString::new("-c").unwrap();
+        let args = list_create(ELEMS_PLAIN);
+        list_push_i64(args, flag.as_ptr() as i64);
+        list_push_i64(args, script.as_ptr() as i64);
+        let input = CString::new("ada\n").unwrap();
+
+        let handle = process_run(program.as_ptr() as *const u8, args, input.as_ptr() as *const u8);
+        assert_eq!(process_exit_code(handle), 3);
+        assert_eq!(read(process_stdout(handle)), "hi ada\n");
+        assert_eq!(read(process_stderr(handle)), "oops\n");
+        process_free(handle);
+        list_free(args);
+    }
+
+    #[test]
+    fn test_large_input_and_output_do_not_block() {
+        let input = "x".repeat(1 << 20);
+        let output = run("cat", &[], input.as_bytes()).unwrap();
+        assert_eq!(output.exit_code, 0);
+        assert_eq!(output.stdout.len(), 1 << 20);
+    }
+
+    #[test]
+    fn test_missing_program_is_an_error() {
+        assert!(run("ws-no-such-program", &[], b"").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Random identifiers and numbers for WadeScript
+//!
+//! UUIDs and random hex/token strings, drawn from the operating system's
+//! secure random source, so they are safe for session ids and API keys.
+//!
+//! Numbers and shuffles come from a fast generator (xoshiro256**) for
+//! simulations and tests. It starts from an OS-random seed; `random_seed`
+//! makes the sequence reproducible. It is not suitable for secrets.
+
+use std::sync::Mutex;
+
+use super::exceptions::raise;
+use super::http::alloc_c_string;
+use super::list::List;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// The URL-safe base64 alphabet: 64 characters, so each takes 6 bits of a byte
+const TOKEN_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn random_bytes(count: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; count];
+    if let Err(e) = getrandom::getrandom(&mut bytes) {
+        raise("RuntimeError", format!("cannot read random bytes: {}", e));
+    }
+    bytes
+}
+
+fn checked_length(length: i64, function: &str) -> usize {
+    if length < 0 {
+        raise("ValueError", format!("{}() length must not be negative, got {}", function, length));
+    }
+    length as usize
+}
+
+/// xoshiro256**: 256 bits of state, period 2^256 - 1, passes BigCrush
+struct Generator {
+    state: [u64; 4],
+}
+
+impl Generator {
+    /// Expand a 64-bit seed into the full state with splitmix64, as the
+    /// xoshiro authors recommend, so similar seeds give unrelated sequences
+    fn from_seed(mut seed: u64) -> Self {
+        let mut state = [0u64; 4];
+        for word in state.iter_mut() {
+            seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *word = z ^ (z >> 31);
+        }
+        Generator { state }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// A uniform value in 0..span (span 0 means all 2^64 values). Draws that
+    /// fall in the last partial copy of the range are rejected, so no value
+    /// is more likely than another
+    fn below(&mut self, span: u64) -> u64 {
+        if span == 0 {
+            return self.next_u64();
+        }
+        let threshold = span.wrapping_neg() % span; // 2^64 mod span
+        loop {
+            let x = self.next_u64();
+            if x >= threshold {
+                return x % span;
+            }
+        }
+    }
+}
+
+static GENERATOR: Mutex<Option<Generator>> = Mutex::new(None);
+
+/// Run `f` on the generator, seeding it from the OS on first use
+fn with_generator<T>(f: impl FnOnce(&mut Generator) -> T) -> T {
+    let mut generator = GENERATOR.lock().unwrap();
+    let generator = generator.get_or_insert_with(|| {
+        let mut seed = [0u8; 8];
+        // An unreadable OS source still leaves a working, if predictable, generator
+        let _ = getrandom::getrandom(&mut seed);
+        Generator::from_seed(u64::from_le_bytes(seed))
+    });
+    f(generator)
+}
+
+/// Format 16 bytes as a version 4 (random) UUID
+fn format_uuid4(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+    let mut uuid = String::with_capacity(36);
+    for (i, byte) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+        uuid.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        uuid.push(HEX_DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    uuid
+}
+
+/// Map each random byte to one character of `alphabet` (whose length divides 256)
+fn chars_from(bytes: &[u8], alphabet: &[u8]) -> String {
+    bytes.iter().map(|&byte| alphabet[byte as usize % alphabet.len()] as char).collect()
+}
+
+/// A random version 4 UUID, e.g. "9f1c8e2a-4b7d-4e0f-a3c5-1d2e3f405162"
+#[no_mangle]
+pub extern "C" fn random_uuid4() -> *mut u8 {
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&random_bytes(16));
+    alloc_c_string(&format_uuid4(bytes))
+}
+
+/// `length` random lowercase hex digits
+/// Raises ValueError if length is negative
+#[no_mangle]
+pub extern "C" fn random_hex_string(length: i64) -> *mut u8 {
+    let length = checked_length(length, "random_hex");
+    alloc_c_string(&chars_from(&random_bytes(length), HEX_DIGITS))
+}
+
+/// `length` random URL-safe characters (A-Z, a-z, 0-9, '-' and '_')
+/// Raises ValueError if length is negative
+#[no_mangle]
+pub extern "C" fn random_token_string(length: i64) -> *mut u8 {
+    let length = checked_length(length, "random_token");
+    alloc_c_string(&chars_from(&random_bytes(length), TOKEN_CHARS))
+}
+
+/// Restart the number generator from `seed`: the same seed gives the same
+/// random_int / random_float / shuffle results on every run
+#[no_mangle]
+pub extern "C" fn random_seed(seed: i64) {
+    *GENERATOR.lock().unwrap() = Some(Generator::from_seed(seed as u64));
+}
+
+/// A random int from lo to hi, both included
+/// Raises ValueError if lo is greater than hi
+#[no_mangle]
+pub extern "C" fn random_int_between(lo: i64, hi: i64) -> i64 {
+    if lo > hi {
+        raise("ValueError", format!("random_int() lo must not be greater than hi, got {} and {}", lo, hi));
+    }
+    let span = (hi.wrapping_sub(lo) as u64).wrapping_add(1);
+    lo.wrapping_add(with_generator(|g| g.below(span)) as i64)
+}
+
+/// A random float in [0.0, 1.0), with 53 random bits
+#[no_mangle]
+pub extern "C" fn random_unit_float() -> f64 {
+    (with_generator(Generator::next_u64) >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Shuffle a list in place (Fisher-Yates), for `items.shuffle()`. Elements are
+/// moved as whole 8-byte slots, so this works for every element type
+#[no_mangle]
+pub extern "C" fn list_shuffle(list: *mut List) {
+    let Some(list) = (unsafe { list.as_mut() }) else {
+        return;
+    };
+    if list.length < 2 {
+        return;
+    }
+    let slots = unsafe { std::slice::from_raw_parts_mut(list.data, list.length as usize) };
+    with_generator(|g| {
+        for i in (1..slots.len()).rev() {
+            let j = g.below(i as u64 + 1) as usize;
+            slots.swap(i, j);
+        }
+    });
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    fn to_string(ptr: *mut u8) -> String {
+        unsafe { CStr::from_ptr(ptr as *const c_char) }.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_format_uuid4() {
+        assert_eq!(format_uuid4([0; 16]), "00000000-0000-4000-8000-000000000000");
+        assert_eq!(format_uuid4([0xff; 16]), "ffffffff-ffff-4fff-bfff-ffffffffffff");
+    }
+
+    #[test]
+    fn test_uuid4_is_random() {
+        let first = to_string(random_uuid4());
+        let second = to_string(random_uuid4());
+        assert_eq!(first.len(), 36);
+        assert_eq!(&first[14..15], "4");
+        assert!("89ab".contains(&first[19..20]));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_hex_and_token_alphabets() {
+        let hex = to_string(random_hex_string(40));
+        assert_eq!(hex.len(), 40);
+        assert!(hex.bytes().all(|c| HEX_DIGITS.contains(&c)));
+
+        let token = to_string(random_token_string(64));
+        assert_eq!(token.len(), 64);
+        assert!(token.bytes().all(|c| TOKEN_CHARS.contains(&c)));
+
+        assert_eq!(to_string(random_token_string(0)), "");
+    }
+
+    #[test]
+    fn test_generator_known_values() {
+        // Reference output of splitmix64-seeded xoshiro256** for seed 0
+        let mut g = Generator::from_seed(0);
+        assert_eq!(g.state[0], 0xe220_a839_7b1d_cdaf);
+        let first = g.next_u64();
+        let mut again = Generator::from_seed(0);
+        assert_eq!(again.next_u64(), first);
+        assert_ne!(Generator::from_seed(1).next_u64(), first);
+    }
+
+    #[test]
+    fn test_seeded_numbers_and_shuffle() {
+        use super::super::list::{list_create, list_free, list_get_i64, list_push_i64};
+        use super::super::rc::ELEMS_PLAIN;
+
+        // The generator is shared, so one test covers everything that seeds it
+        random_seed(42);
+        let ints: Vec<i64> = (0..100).map(|_| random_int_between(1, 6)).collect();
+        let floats: Vec<f64> = (0..100).map(|_| random_unit_float()).collect();
+        assert!(ints.iter().all(|n| (1..=6).contains(n)));
+        assert!((1..=6).all(|face| ints.contains(&face)), "every face comes up in 100 rolls");
+        assert!(floats.iter().all(|x| (0.0..1.0).contains(x)));
+
+        random_seed(42);
+        assert_eq!((0..100).map(|_| random_int_between(1, 6)).collect::<Vec<_>>(), ints);
+        assert_eq!(random_unit_float(), floats[0]);
+
+        assert_eq!(random_int_between(7, 7), 7);
+        let full = random_int_between(i64::MIN, i64::MAX);
+        assert!((i64::MIN..=i64::MAX).contains(&full));
+
+        let list = list_create(ELEMS_PLAIN);
+        for i in 0..50 {
+            list_push_i64(list, i);
+        }
+        list_shuffle(list);
+        let mut shuffled: Vec<i64> = (0..50).map(|i| list_get_i64(list, i)).collect();
+        assert_ne!(shuffled, (0..50).collect::<Vec<_>>());
+        shuffled.sort();
+        assert_eq!(shuffled, (0..50).collect::<Vec<_>>(), "a permutation of the items");
+        list_free(list);
+    }
+}
@@ -1,16 +1,174 @@
 // Reference Counting Runtime for WadeScript
 //
 // Memory layout: [RcHeader][Object Data]
-// Header contains ref_count and size for proper deallocation
+// Header contains ref_count and size for proper deallocation.
+// Objects are handed out as a pointer to the data; generated code updates the
+// count inline at `data + RC_REF_COUNT_OFFSET` (see build_rc_retain_inline in
+// codegen.rs), so the header layout below is shared with the compiler.
+//
+// Small blocks come from a size-class pool: when one is freed it goes on a
+// per-thread free list for its class instead of back to the allocator, so a
+// loop that creates and drops objects, lists, dicts or exceptions keeps
+// reusing the same few blocks.
 
 use std::alloc::{alloc, dealloc, Layout};
+use std::cell::RefCell;
+use std::mem::{offset_of, size_of};
+use std::ptr;
+
+/// Pool size classes are multiples of this many bytes
+const POOL_GRANULE: usize = 16;
+const POOL_CLASSES: usize = 8;
+/// Largest block (header included) served from the pool
+const POOL_MAX_BLOCK: usize = POOL_GRANULE * POOL_CLASSES;
+const POOL_ALIGN: usize = 8;
+/// Free blocks kept per class; any more go back to the allocator
+const POOL_MAX_FREE: usize = 1024;
+
+/// Free lists, one per size class. A free block's first word links to the
+/// next free block of its class.
+struct Pool {
+    free: [*mut u8; POOL_CLASSES],
+    counts: [usize; POOL_CLASSES],
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        for class in 0..POOL_CLASSES {
+            let mut block = self.free[class];
+            while !block.is_null() {
+                unsafe {
+                    let next = *(block as *mut *mut u8);
+                    dealloc(block, class_layout(class));
+                    block = next;
+                }
+            }
+        }
+    }
+}
+
+thread_local! {
+    static POOL: RefCell<Pool> = const {
+        RefCell::new(Pool { free: [ptr::null_mut(); POOL_CLASSES], counts: [0; POOL_CLASSES] })
+    };
+}
+
+/// Size class for `layout`, or None if it is too large or too aligned to pool
+fn pool_class(layout: Layout) -> Option<usize> {
+    if layout.size() == 0 || layout.size() > POOL_MAX_BLOCK || layout.align() > POOL_ALIGN {
+        None
+    } else {
+        Some((layout.size() - 1) / POOL_GRANULE)
+    }
+}
+
+/// Layout every block of a class is allocated with, whatever size was asked for
+fn class_layout(class: usize) -> Layout {
+    Layout::from_size_align((class + 1) * POOL_GRANULE, POOL_ALIGN).unwrap()
+}
+
+/// Allocate a block for `layout`, reusing a pooled one when it is small.
+/// Free it with `pool_free` and the same layout.
+pub(crate) unsafe fn pool_alloc(layout: Layout) -> *mut u8 {
+    let class = match pool_class(layout) {
+        Some(class) => class,
+        None => return alloc(layout),
+    };
+    // try_with: the pool is gone while the thread's destructors run
+    let reused = POOL
+        .try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            let block = pool.free[class];
+            if !block.is_null() {
+                pool.free[class] = *(block as *mut *mut u8);
+                pool.counts[class] -= 1;
+            }
+            block
+        })
+        .unwrap_or(ptr::null_mut());
+    if reused.is_null() {
+        alloc(class_layout(class))
+    } else {
+        reused
+    }
+}
+
+/// Free a block from `pool_alloc`, keeping it for reuse when it is small
+pub(crate) unsafe fn pool_free(block: *mut u8, layout: Layout) {
+    let class = match pool_class(layout) {
+        Some(class) => class,
+        None => return dealloc(block, layout),
+    };
+    let kept = POOL
+        .try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.counts[class] >= POOL_MAX_FREE {
+                return false;
+            }
+            *(block as *mut *mut u8) = pool.free[class];
+            pool.free[class] = block;
+            pool.counts[class] += 1;
+            true
+        })
+        .unwrap_or(false);
+    if !kept {
+        dealloc(block, class_layout(class));
+    }
+}
 
 /// Reference counted object header
 /// Placed immediately before object data in memory
 #[repr(C)]
-struct RcHeader {
-    ref_count: i64,
-    size: i64,  // Size of object data (for deallocation)
+pub struct RcHeader {
+    pub ref_count: i64,
+    pub size: i64,  // Size of object data (for deallocation)
+}
+
+/// Offset of the reference count from the data pointer (negative: it lives in
+/// the header)
+pub const RC_REF_COUNT_OFFSET: i64 = offset_of!(RcHeader, ref_count) as i64 - size_of::<RcHeader>() as i64;
+
+// What a list's elements or a dict's keys and values are, fixed when the
+// collection is created (codegen passes them from the declared type). Freeing
+// the collection releases each RC element with it.
+pub const ELEMS_PLAIN: i64 = 0; // ints, floats, bools and strs: nothing to release
+pub const ELEMS_LIST: i64 = 1;
+pub const ELEMS_DICT: i64 = 2;
+pub const ELEMS_OBJECT: i64 = 3; // class instances, freed by their drop function
+pub const ELEMS_BOX: i64 = 4; // int?, float? and bool? boxes
+pub const ELEMS_BIGINT: i64 = 5;
+pub const ELEMS_DECIMAL: i64 = 6;
+pub const ELEMS_TUPLE: i64 = 7; // tuples, each in a box of its own (see tuple_box)
+
+/// Box a tuple for a list slot: an RC block of i64 words holding the field
+/// count, then each field's ELEMS_* kind and slot. Releasing the box releases
+/// the fields with it. build_tuple_box in codegen.rs builds the same layout
+pub(crate) fn tuple_box(fields: &[(i64, i64)]) -> *mut u8 {
+    let words = rc_alloc((1 + 2 * fields.len() as i64) * 8) as *mut i64;
+    unsafe {
+        words.write(fields.len() as i64);
+        for (i, &(kind, slot)) in fields.iter().enumerate() {
+            words.add(1 + 2 * i).write(kind);
+            words.add(2 + 2 * i).write(slot);
+        }
+    }
+    words as *mut u8
+}
+
+/// Layout of the whole allocation (header and data) for `size` bytes of data
+fn rc_layout(size: i64) -> Layout {
+    let data = Layout::from_size_align(size as usize, std::mem::align_of::<RcHeader>())
+        .expect("rc_alloc: allocation too large");
+    let (layout, data_offset) = Layout::new::<RcHeader>()
+        .extend(data)
+        .expect("rc_alloc: allocation too large");
+    debug_assert_eq!(data_offset, size_of::<RcHeader>());
+    layout
+}
+
+/// Header of an object allocated by `rc_alloc`
+fn header_of(ptr: *mut u8) -> *mut RcHeader {
+    (ptr as *mut RcHeader).wrapping_sub(1)
 }
 
 /// Allocate reference counted memory
@@ -22,23 +180,38 @@ pub extern "C" fn rc_alloc(size: i64) -> *mut u8 {
             return std::ptr::null_mut();
         }
 
-        let total_size = std::mem::size_of::<RcHeader>() + size as usize;
-        let layout = Layout::from_size_align_unchecked(total_size, 8);
-        let ptr = alloc(layout) as *mut RcHeader;
+        let ptr = pool_alloc(rc_layout(size)) as *mut RcHeader;
 
         if ptr.is_null() {
             panic!("rc_alloc: Out of memory");
         }
 
         // Initialize header
-        (*ptr).ref_count = 1;  // Start with count of 1
-        (*ptr).size = size;
+        ptr.write(RcHeader {
+            ref_count: 1,  // Start with count of 1
+            size,
+        });
 
         // Return pointer to data (after header)
         ptr.add(1) as *mut u8
     }
 }
 
+/// Free an object whose reference count has reached zero. Generated code calls
+/// this after its inline decrement; everything else goes through `rc_release`.
+#[no_mangle]
+pub extern "C" fn rc_free(ptr: *mut u8) {
+    if ptr.is_null() {
+        return;
+    }
+
+    unsafe {
+        let header = header_of(ptr);
+        let layout = rc_layout((*header).size);
+        pool_free(header as *mut u8, layout);
+    }
+}
+
 /// Increment reference count
 #[no_mangle]
 pub extern "C" fn rc_retain(ptr: *mut u8) {
@@ -47,7 +220,7 @@ pub extern "C" fn rc_retain(ptr: *mut u8) {
     }
 
     unsafe {
-        let header = (ptr as *mut RcHeader).sub(1);
+        let header = header_of(ptr);
         (*header).ref_count += 1;
     }
 }
@@ -60,21 +233,61 @@ pub extern "C" fn rc_release(ptr: *mut u8) {
     }
 
     unsafe {
-        let header = (ptr as *mut RcHeader).sub(1);
+        let header = header_of(ptr);
         (*header).ref_count -= 1;
 
         if (*header).ref_count == 0 {
-            // Free the memory
-            let size = (*header).size;
-            let total_size = std::mem::size_of::<RcHeader>() + size as usize;
-            let layout = Layout::from_size_align_unchecked(total_size, 8);
-            dealloc(header as *mut u8, layout);
+            rc_free(ptr);
         } else if (*header).ref_count < 0 {
             panic!("rc_release: ref_count went negative! Double-free detected.");
         }
     }
 }
 
+/// Retain an element stored in a collection of `kind`
+pub(crate) fn retain_element(kind: i64, value: i64) {
+    if kind != ELEMS_PLAIN {
+        rc_retain(value as *mut u8);
+    }
+}
+
+/// Release an element stored in a collection of `kind`, freeing it the way
+/// generated code would (see build_rc_release_inline in codegen.rs)
+pub(crate) fn release_element(kind: i64, value: i64) {
+    let ptr = value as *mut u8;
+    if kind == ELEMS_PLAIN || ptr.is_null() {
+        return;
+    }
+
+    unsafe {
+        let header = header_of(ptr);
+        (*header).ref_count -= 1;
+        if (*header).ref_count != 0 {
+            return;
+        }
+        match kind {
+            ELEMS_LIST => super::list::list_free(ptr as *mut super::list::List),
+            ELEMS_DICT => super::dict::dict_free(ptr as *mut super::dict::Dict),
+            ELEMS_OBJECT => {
+                // An object starts with its vtable, whose entry -1 is the drop function
+                let vtable = *(ptr as *const *const extern "C" fn(*mut u8));
+                let drop = *vtable.offset(-1);
+                drop(ptr);
+            }
+            ELEMS_BIGINT => super::bigint::bigint_free(ptr as *mut num_bigint::BigInt),
+            ELEMS_DECIMAL => super::decimal::decimal_free(ptr as *mut super::decimal::Decimal),
+            ELEMS_TUPLE => {
+                let words = ptr as *const i64;
+                for i in 0..*words as usize {
+                    release_element(*words.add(1 + 2 * i), *words.add(2 + 2 * i));
+                }
+                rc_free(ptr)
+            }
+            _ => rc_free(ptr),
+        }
+    }
+}
+
 /// Get current reference count (for debugging)
 #[no_mangle]
 pub extern "C" fn rc_get_count(ptr: *mut u8) -> i64 {
@@ -83,7 +296,7 @@ pub extern "C" fn rc_get_count(ptr: *mut u8) -> i64 {
     }
 
     unsafe {
-        let header = (ptr as *mut RcHeader).sub(1);
+        let header = header_of(ptr);
         (*header).ref_count
     }
 }
@@ -96,7 +309,7 @@ pub extern "C" fn rc_is_valid(ptr: *mut u8) -> i32 {
     }
 
     unsafe {
-        let header = (ptr as *mut RcHeader).sub(1);
+        let header = header_of(ptr);
         if (*header).ref_count > 0 && (*header).ref_count < 1000000 {
             1
         } else {
@@ -275,7 +488,7 @@ mod tests {
                 assert!(!ptr.is_null());
 
                 // Get header and verify size was stored
-                let header = (ptr as *mut RcHeader).sub(1);
+                let header = header_of(ptr);
                 assert_eq!((*header).size, size);
                 assert_eq!((*header).ref_count, 1);
 
@@ -283,4 +496,159 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_rc_header_layout() {
+        // Generated code updates the count at RC_REF_COUNT_OFFSET from the data
+        // pointer, so a change here must be matched in codegen
+        assert_eq!(size_of::<RcHeader>(), 16);
+        assert_eq!(std::mem::align_of::<RcHeader>(), 8);
+        assert_eq!(RC_REF_COUNT_OFFSET, -16);
+
+        let ptr = rc_alloc(24);
+        assert_eq!(ptr as usize % std::mem::align_of::<i64>(), 0);
+        unsafe {
+            let count = ptr.offset(RC_REF_COUNT_OFFSET as isize) as *mut i64;
+            assert_eq!(*count, 1);
+            rc_retain(ptr);
+            assert_eq!(*count, 2);
+        }
+        rc_release(ptr);
+        rc_release(ptr);
+    }
+
+    #[test]
+    fn test_rc_free_after_inline_release() {
+        // What generated code does: decrement in place, then hand the object back
+        let ptr = rc_alloc(40);
+        unsafe {
+            let count = ptr.offset(RC_REF_COUNT_OFFSET as isize) as *mut i64;
+            *count -= 1;
+            assert_eq!(*count, 0);
+        }
+        rc_free(ptr);
+        rc_free(std::ptr::null_mut());
+    }
+
+    static DROPPED: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+
+    extern "C" fn count_drop(ptr: *mut u8) {
+        DROPPED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        rc_free(ptr);
+    }
+
+    #[test]
+    fn test_release_element_runs_drop_function() {
+        // A vtable like codegen's: the drop function, then the methods
+        static VTABLE: [extern "C" fn(*mut u8); 2] = [count_drop, count_drop];
+        let object = rc_alloc(16);
+        unsafe {
+            *(object as *mut *const extern "C" fn(*mut u8)) = VTABLE.as_ptr().add(1);
+        }
+        retain_element(ELEMS_OBJECT, object as i64);
+        release_element(ELEMS_OBJECT, object as i64);
+        assert_eq!(DROPPED.load(std::sync::atomic::Ordering::SeqCst), 0);
+        release_element(ELEMS_OBJECT, object as i64);
+        assert_eq!(DROPPED.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Plain values and null elements are left alone
+        release_element(ELEMS_PLAIN, 42);
+        release_element(ELEMS_LIST, 0);
+    }
+
+    #[test]
+    fn test_release_element_frees_optional_boxes() {
+        // An int? box held by a list[int?] and by a variable
+        let boxed = rc_alloc(8);
+        retain_element(ELEMS_BOX, boxed as i64);
+        assert_eq!(rc_get_count(boxed), 2);
+        release_element(ELEMS_BOX, boxed as i64);
+        assert_eq!(rc_get_count(boxed), 1);
+        release_element(ELEMS_BOX, boxed as i64);
+    }
+
+    #[test]
+    fn test_release_element_frees_bigints_and_decimals() {
+        use super::super::bigint::bigint_from_int;
+        use super::super::decimal::decimal_from_int;
+
+        let big = bigint_from_int(7) as *mut u8;
+        retain_element(ELEMS_BIGINT, big as i64);
+        release_element(ELEMS_BIGINT, big as i64);
+        assert_eq!(rc_get_count(big), 1);
+        release_element(ELEMS_BIGINT, big as i64);
+
+        let money = decimal_from_int(3) as *mut u8;
+        assert_eq!(rc_get_count(money), 1);
+        release_element(ELEMS_DECIMAL, money as i64);
+    }
+
+    #[test]
+    fn test_release_element_frees_tuple_boxes_and_their_fields() {
+        use super::super::bigint::bigint_from_int;
+
+        // A (int, bigint) tuple in a list[(int, bigint)], with the bigint also held elsewhere
+        let big = bigint_from_int(7) as *mut u8;
+        rc_retain(big);
+        let pair = tuple_box(&[(ELEMS_PLAIN, 42), (ELEMS_BIGINT, big as i64)]);
+        unsafe {
+            assert_eq!(*(pair as *const i64), 2);
+            assert_eq!(*(pair as *const i64).add(2), 42);
+        }
+        retain_element(ELEMS_TUPLE, pair as i64);
+        release_element(ELEMS_TUPLE, pair as i64);
+        assert_eq!(rc_get_count(big), 2);
+        release_element(ELEMS_TUPLE, pair as i64);
+        assert_eq!(rc_get_count(big), 1);
+        release_element(ELEMS_BIGINT, big as i64);
+    }
+
+    #[test]
+    fn test_rc_pool_reuses_small_blocks() {
+        // A list header (24 bytes) and a 20-byte object share a size class
+        let first = rc_alloc(24);
+        rc_release(first);
+        let second = rc_alloc(20);
+        assert_eq!(second, first);
+        assert_eq!(rc_get_count(second), 1);
+        unsafe {
+            assert_eq!((*header_of(second)).size, 20);
+        }
+
+        // A different class doesn't get the block
+        rc_release(second);
+        let other = rc_alloc(100);
+        assert_ne!(other, first);
+        rc_release(other);
+    }
+
+    #[test]
+    fn test_pool_classes() {
+        let layout = |size| Layout::from_size_align(size, 8).unwrap();
+        assert_eq!(pool_class(layout(1)), Some(0));
+        assert_eq!(pool_class(layout(16)), Some(0));
+        assert_eq!(pool_class(layout(17)), Some(1));
+        assert_eq!(pool_class(layout(POOL_MAX_BLOCK)), Some(POOL_CLASSES - 1));
+        assert_eq!(pool_class(layout(POOL_MAX_BLOCK + 1)), None);
+        assert_eq!(pool_class(Layout::from_size_align(16, 16).unwrap()), None);
+        assert_eq!(pool_class(rc_layout(1024 * 1024)), None);
+    }
+
+    #[test]
+    fn test_pool_keeps_at_most_max_free() {
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let class = pool_class(layout).unwrap();
+        unsafe {
+            let blocks: Vec<*mut u8> = (0..POOL_MAX_FREE + 10).map(|_| pool_alloc(layout)).collect();
+            for &block in &blocks {
+                pool_free(block, layout);
+            }
+            assert_eq!(POOL.with(|pool| pool.borrow().counts[class]), POOL_MAX_FREE);
+
+            // Reused in last-freed-first order
+            let block = pool_alloc(layout);
+            assert_eq!(block, blocks[POOL_MAX_FREE - 1]);
+            pool_free(block, layout);
+        }
+    }
 }
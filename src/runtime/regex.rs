@@ -0,0 +1,196 @@
+//! Regular expression runtime for WadeScript
+//!
+//! Compiled patterns (regex crate syntax) are kept in a registry and used
+//! through integer handles by std/regex.ws. Matching works on the string's
+//! bytes, so text that isn't valid UTF-8 is searched and copied unchanged.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use regex::bytes::Regex;
+
+use super::exceptions::raise;
+use super::list::{list_create, list_push_i64, List};
+use super::rc::ELEMS_PLAIN;
+use super::string::{alloc_str, as_bytes};
+
+lazy_static::lazy_static! {
+    static ref REGEXES: Mutex<RegexManager> = Mutex::new(RegexManager::new());
+}
+
+struct RegexManager {
+    patterns: HashMap<i64, Regex>,
+    next_id: i64,
+}
+
+impl RegexManager {
+    fn new() -> Self {
+        RegexManager {
+            patterns: HashMap::new(),
+            next_id: 1,
+        }
+    }
+}
+
+/// The compiled pattern for a handle. Cloning a Regex is cheap and lets the
+/// lock go before matching, or before raising ValueError for a handle that was
+/// never created or has been freed
+fn regex_for(handle: i64) -> Regex {
+    let found = REGEXES.lock().unwrap().patterns.get(&handle).cloned();
+    match found {
+        Some(regex) => regex,
+        None => raise("ValueError", format!("invalid Regex handle {}", handle)),
+    }
+}
+
+/// A new list[str] holding copies of `parts`
+fn str_list<'a>(parts: impl Iterator<Item = &'a [u8]>) -> *mut List {
+    let list = list_create(ELEMS_PLAIN);
+    for part in parts {
+        list_push_i64(list, unsafe { alloc_str(part) } as i64);
+    }
+    list
+}
+
+/// Compile a pattern and return its handle
+/// Raises ValueError if the pattern is invalid
+#[no_mangle]
+pub extern "C" fn regex_compile(pattern: *const u8) -> i64 {
+    let pattern = String::from_utf8_lossy(unsafe { as_bytes(pattern) }).into_owned();
+    let regex = match Regex::new(&pattern) {
+        Ok(regex) => regex,
+        Err(e) => raise("ValueError", format!("invalid regex '{}': {}", pattern, e)),
+    };
+    let mut manager = REGEXES.lock().unwrap();
+    let id = manager.next_id;
+    manager.next_id += 1;
+    manager.patterns.insert(id, regex);
+    id
+}
+
+/// 1 if the pattern matches anywhere in the text, 0 if not
+#[no_mangle]
+pub extern "C" fn regex_is_match(handle: i64, text: *const u8) -> i64 {
+    let regex = regex_for(handle);
+    regex.is_match(unsafe { as_bytes(text) }) as i64
+}
+
+/// The first match as a new string, or null (None) if there is none
+#[no_mangle]
+pub extern "C" fn regex_find(handle: i64, text: *const u8) -> *mut u8 {
+    let regex = regex_for(handle);
+    match regex.find(unsafe { as_bytes(text) }) {
+        Some(found) => unsafe { alloc_str(found.as_bytes()) },
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Every non-overlapping match, in order, as a new list[str]
+#[no_mangle]
+pub extern "C" fn regex_find_all(handle: i64, text: *const u8) -> *mut List {
+    let regex = regex_for(handle);
+    str_list(regex.find_iter(unsafe { as_bytes(text) }).map(|found| found.as_bytes()))
+}
+
+/// The groups of the first match as a new list[str]: the whole match at 0,
+/// then each group, "" for a group that took no part. Empty if nothing matches
+#[no_mangle]
+pub extern "C" fn regex_captures(handle: i64, text: *const u8) -> *mut List {
+    let regex = regex_for(handle);
+    match regex.captures(unsafe { as_bytes(text) }) {
+        Some(groups) => str_list(groups.iter().map(|group| group.map_or(&b""[..], |g| g.as_bytes()))),
+        None => list_create(ELEMS_PLAIN),
+    }
+}
+
+/// Replace every match, as a new string. `$1` or `${name}` in the replacement
+/// insert a group; `$$` is a literal `$`
+#[no_mangle]
+pub extern "C" fn regex_replace(handle: i64, text: *const u8, replacement: *const u8) -> *mut u8 {
+    let regex = regex_for(handle);
+    let replaced = regex.replace_all(unsafe { as_bytes(text) }, unsafe { as_bytes(replacement) });
+    unsafe { alloc_str(&replaced) }
+}
+
+/// The text between matches, as a new list[str]
+#[no_mangle]
+pub extern "C" fn regex_split(handle: i64, text: *const u8) -> *mut List {
+    let regex = regex_for(handle);
+    str_list(regex.split(unsafe { as_bytes(text) }))
+}
+
+/// Free a compiled pattern. Unknown handles are ignored
+#[no_mangle]
+pub extern "C" fn regex_free(handle: i64) {
+    REGEXES.lock().unwrap().patterns.remove(&handle);
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::list::{list_free, list_get_i64};
+    use std::ffi::CString;
+
+    fn c(text: &str) -> CString {
+        CString::new(text).unwrap()
+    }
+
+    fn read(s: *const u8) -> String {
+        String::from_utf8(unsafe { as_bytes(s) }.to_vec()).unwrap()
+    }
+
+    fn texts(list: *mut List) -> Vec<String> {
+        let texts = (0..unsafe { (*list).length }).map(|i| read(list_get_i64(list, i) as *const u8)).collect();
+        list_free(list);
+        texts
+    }
+
+    #[test]
+    fn test_match_find_and_captures() {
+        let pattern = c(r"(\w+)@(\w+)\.com");
+        let handle = regex_compile(pattern.as_ptr() as *const u8);
+        let text = c("mail ada@example.com or bob@test.com");
+        let text = text.as_ptr() as *const u8;
+
+        assert_eq!(regex_is_match(handle, text), 1);
+        assert_eq!(read(regex_find(handle, text)), "ada@example.com");
+        assert_eq!(texts(regex_find_all(handle, text)), vec!["ada@example.com", "bob@test.com"]);
+        assert_eq!(texts(regex_captures(handle, text)), vec!["ada@example.com", "ada", "example"]);
+
+        let none = c("no addresses here");
+        assert_eq!(regex_is_match(handle, none.as_ptr() as *const u8), 0);
+        assert!(regex_find(handle, none.as_ptr() as *const u8).is_null());
+        assert!(texts(regex_captures(handle, none.as_ptr() as *const u8)).is_empty());
+        regex_free(handle);
+    }
+
+    #[test]
+    fn test_replace_and_split() {
+        let pattern = c(r"(?P<y>\d{4})-(?P<m>\d{2})");
+        let handle = regex_compile(pattern.as_ptr() as *const u8);
+        let text = c("from 2024-01 to 2025-12");
+        let replacement = c("${m}/$y");
+        let replaced = regex_replace(handle, text.as_ptr() as *const u8, replacement.as_ptr() as *const u8);
+        assert_eq!(read(replaced), "from 01/2024 to 12/2025");
+        regex_free(handle);
+
+        let pattern = c(r"\s*,\s*");
+        let handle = regex_compile(pattern.as_ptr() as *const u8);
+        let text = c("a , b,c ,  d");
+        assert_eq!(texts(regex_split(handle, text.as_ptr() as *const u8)), vec!["a", "b", "c", "d"]);
+        regex_free(handle);
+    }
+
+    #[test]
+    fn test_optional_groups_and_utf8() {
+        let pattern = c(r"(é+)(x)?");
+        let handle = regex_compile(pattern.as_ptr() as *const u8);
+        let text = c("caféé!");
+        assert_eq!(texts(regex_captures(handle, text.as_ptr() as *const u8)), vec!["éé", "éé", ""]);
+        regex_free(handle);
+    }
+}
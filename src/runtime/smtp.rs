@@ -0,0 +1,219 @@
+//! SMTP client runtime for WadeScript
+//!
+//! Sends plain-text email over a blocking SMTP connection using lettre, with
+//! TLS from rustls (the same stack ureq uses for HTTPS).
+
+use lettre::message::header::ContentType;
+use lettre::message::{Mailbox, Message};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{SmtpTransport, Transport};
+
+use super::http::{alloc_c_string, c_str_to_string};
+
+/// How the connection to the server is secured
+#[derive(Debug, PartialEq)]
+enum Security {
+    /// TLS from the first byte (usually port 465)
+    Tls,
+    /// Plain connection upgraded with STARTTLS, which the server must offer (usually port 587)
+    StartTls,
+    /// No encryption, for a relay on localhost or a trusted network (usually port 25)
+    None,
+}
+
+fn parse_security(security: &str) -> Result<Security, String> {
+    match security {
+        "tls" => Ok(Security::Tls),
+        "starttls" => Ok(Security::StartTls),
+        "none" => Ok(Security::None),
+        _ => Err(format!(
+            "Unknown SMTP security '{}' (expected \"tls\", \"starttls\" or \"none\")",
+            security
+        )),
+    }
+}
+
+/// Parse comma-separated recipient addresses
+fn parse_recipients(to: &str) -> Result<Vec<Mailbox>, String> {
+    let recipients: Vec<Mailbox> = to
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| {
+            address
+                .parse::<Mailbox>()
+                .map_err(|e| format!("Invalid recipient '{}': {}", address, e))
+        })
+        .collect::<Result<_, _>>()?;
+    if recipients.is_empty() {
+        return Err("No recipients".to_string());
+    }
+    Ok(recipients)
+}
+
+fn build_message(from: &str, to: &str, subject: &str, body: &str) -> Result<Message, String> {
+    let sender = from
+        .trim()
+        .parse::<Mailbox>()
+        .map_err(|e| format!("Invalid sender '{}': {}", from, e))?;
+    let mut builder = Message::builder().from(sender).subject(subject);
+    for recipient in parse_recipients(to)? {
+        builder = builder.to(recipient);
+    }
+    builder
+        .header(ContentType::TEXT_PLAIN)
+        .body(body.to_string())
+        .map_err(|e| format!("Cannot build message: {}", e))
+}
+
+fn build_transport(
+    host: &str,
+    port: u16,
+    security: Security,
+    username: &str,
+    password: &str,
+) -> Result<SmtpTransport, String> {
+    let builder = match security {
+        Security::Tls => SmtpTransport::relay(host),
+        Security::StartTls => SmtpTransport::starttls_relay(host),
+        Security::None => Ok(SmtpTransport::builder_dangerous(host)),
+    }
+    .map_err(|e| format!("SMTP error: {}", e))?
+    .port(port);
+
+    // An empty username means the server accepts mail without logging in
+    let builder = if username.is_empty() {
+        builder
+    } else {
+        builder.credentials(Credentials::new(username.to_string(), password.to_string()))
+    };
+    Ok(builder.build())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn send_mail(
+    host: &str,
+    port: i64,
+    security: &str,
+    username: &str,
+    password: &str,
+    from: &str,
+    to: &str,
+    subject: &str,
+    body: &str,
+) -> Result<(), String> {
+    let port = u16::try_from(port).map_err(|_| format!("Invalid SMTP port {}", port))?;
+    let security = parse_security(security)?;
+    // Build the message first so a bad address fails without touching the network
+    let message = build_message(from, to, subject, body)?;
+    let transport = build_transport(host, port, security, username, password)?;
+    transport
+        .send(&message)
+        .map(|_| ())
+        .map_err(|e| format!("SMTP error: {}", e))
+}
+
+// ============================================================================
+// Public API Functions
+// ============================================================================
+
+/// Send a plain-text email
+/// security: "tls", "starttls" or "none"
+/// to: comma-separated recipient addresses
+/// username: empty to send without logging in
+/// Returns: empty string on success, otherwise the error message
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn smtp_send(
+    host: *const u8,
+    port: i64,
+    security: *const u8,
+    username: *const u8,
+    password: *const u8,
+    from: *const u8,
+    to: *const u8,
+    subject: *const u8,
+    body: *const u8,
+) -> *mut u8 {
+    unsafe {
+        let host_str = c_str_to_string(host).unwrap_or_default();
+        let security_str = c_str_to_string(security).unwrap_or_default();
+        let username_str = c_str_to_string(username).unwrap_or_default();
+        let password_str = c_str_to_string(password).unwrap_or_default();
+        let from_str = c_str_to_string(from).unwrap_or_default();
+        let to_str = c_str_to_string(to).unwrap_or_default();
+        let subject_str = c_str_to_string(subject).unwrap_or_default();
+        let body_str = c_str_to_string(body).unwrap_or_default();
+
+        let result = send_mail(
+            &host_str,
+            port,
+            &security_str,
+            &username_str,
+            &password_str,
+            &from_str,
+            &to_str,
+            &subject_str,
+            &body_str,
+        );
+        match result {
+            Ok(()) => alloc_c_string(""),
+            Err(message) => alloc_c_string(&message),
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_security() {
+        assert_eq!(parse_security("tls"), Ok(Security::Tls));
+        assert_eq!(parse_security("starttls"), Ok(Security::StartTls));
+        assert_eq!(parse_security("none"), Ok(Security::None));
+        let err = parse_security("ssl").unwrap_err();
+        assert!(err.contains("Unknown SMTP security 'ssl'"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_recipients() {
+        let recipients = parse_recipients("ops@example.com, Pager <pager@example.com>,").unwrap();
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[1].email.to_string(), "pager@example.com");
+
+        assert_eq!(parse_recipients(" , ").unwrap_err(), "No recipients");
+        let err = parse_recipients("ops@example.com, not an address").unwrap_err();
+        assert!(err.contains("Invalid recipient 'not an address'"), "{}", err);
+    }
+
+    #[test]
+    fn test_build_message() {
+        let message = build_message("alerts@example.com", "ops@example.com", "Disk full", "/var is at 98%").unwrap();
+        let text = String::from_utf8(message.formatted()).unwrap();
+        assert!(text.contains("From: alerts@example.com"), "{}", text);
+        assert!(text.contains("To: ops@example.com"), "{}", text);
+        assert!(text.contains("Subject: Disk full"), "{}", text);
+        assert!(text.contains("Content-Type: text/plain; charset=utf-8"), "{}", text);
+        assert!(text.contains("/var is at 98%"), "{}", text);
+
+        let err = build_message("alerts", "ops@example.com", "", "").unwrap_err();
+        assert!(err.contains("Invalid sender 'alerts'"), "{}", err);
+    }
+
+    #[test]
+    fn test_send_mail_reports_errors() {
+        let err = send_mail("localhost", 70000, "none", "", "", "a@example.com", "b@example.com", "", "").unwrap_err();
+        assert_eq!(err, "Invalid SMTP port 70000");
+
+        // Nothing listens on a port we just released, so the connection is refused
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let err = send_mail("127.0.0.1", port as i64, "none", "", "", "a@example.com", "b@example.com", "", "").unwrap_err();
+        assert!(err.starts_with("SMTP error:"), "{}", err);
+    }
+}
@@ -0,0 +1,225 @@
+//! Source maps: which .ws lines each piece of an optimized program's machine code came from
+//!
+//! `wadescript -O1` to `-O3` write `<program>.wsmap` next to the executable
+//! (see the compiler's source_map.rs). Traces read it instead of the DWARF
+//! line info: it lists every WadeScript function inlined at a return address,
+//! and it keeps working after the executable is stripped.
+//!
+//! The format is text:
+//!
+//! ```text
+//! wsmap 1 9c1d0e44f7a2b316
+//! f /home/me/app.ws
+//! 1a40 0:12
+//! 1a52 0:4,0:12
+//! 1a60 -
+//! ```
+//!
+//! The header holds a hash of the code the rows cover, which tells a map
+//! written for another build of the program apart. `f` lines name the source
+//! files. Each row starts a range of code that runs to the next row: its
+//! offset from where the executable is loaded (hex), then file:line pairs,
+//! innermost inlined call first, or `-` for code that isn't WadeScript.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extension of the sidecar file, added to the executable's full name
+pub const EXTENSION: &str = "wsmap";
+
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SourceMap {
+    /// code_hash of the code from the first row to the last
+    pub code_hash: u64,
+    pub files: Vec<String>,
+    /// Start offset of each range and its (file index, line) chain, innermost
+    /// first; sorted by offset. An empty chain is code that isn't WadeScript
+    pub rows: Vec<(u64, Vec<(usize, u32)>)>,
+}
+
+impl SourceMap {
+    pub fn render(&self) -> String {
+        let mut text = format!("wsmap {} {:016x}\n", FORMAT_VERSION, self.code_hash);
+        for file in &self.files {
+            text.push_str(&format!("f {}\n", file));
+        }
+        for (offset, chain) in &self.rows {
+            let locations: Vec<String> = chain.iter().map(|(file, line)| format!("{}:{}", file, line)).collect();
+            let locations = if locations.is_empty() { "-".to_string() } else { locations.join(",") };
+            text.push_str(&format!("{:x} {}\n", offset, locations));
+        }
+        text
+    }
+
+    /// None if the text isn't a source map this runtime can read
+    pub fn parse(text: &str) -> Option<SourceMap> {
+        let mut lines = text.lines();
+        let header: Vec<&str> = lines.next()?.split(' ').collect();
+        let ["wsmap", version, code_hash] = header[..] else {
+            return None;
+        };
+        if version.parse::<u32>().ok()? != FORMAT_VERSION {
+            return None;
+        }
+
+        let mut map = SourceMap {
+            code_hash: u64::from_str_radix(code_hash, 16).ok()?,
+            ..SourceMap::default()
+        };
+        for line in lines {
+            if let Some(file) = line.strip_prefix("f ") {
+                map.files.push(file.to_string());
+                continue;
+            }
+            let (offset, locations) = line.split_once(' ')?;
+            let mut chain = Vec::new();
+            if locations != "-" {
+                for location in locations.split(',') {
+                    let (file, line) = location.split_once(':')?;
+                    let file: usize = file.parse().ok()?;
+                    if file >= map.files.len() {
+                        return None;
+                    }
+                    chain.push((file, line.parse().ok()?));
+                }
+            }
+            map.rows.push((u64::from_str_radix(offset, 16).ok()?, chain));
+        }
+        map.rows.windows(2).all(|pair| pair[0].0 < pair[1].0).then_some(map)
+    }
+
+    /// Start and end offsets of the code the rows cover
+    pub fn code_range(&self) -> Option<(u64, u64)> {
+        Some((self.rows.first()?.0, self.rows.last()?.0))
+    }
+
+    /// The file:line chain of the code at `offset`, innermost first; empty
+    /// outside WadeScript code
+    pub fn lookup(&self, offset: u64) -> Vec<(&str, u32)> {
+        let row = self.rows.partition_point(|(start, _)| *start <= offset);
+        let Some(row) = row.checked_sub(1) else {
+            return Vec::new();
+        };
+        self.rows[row].1.iter().map(|(file, line)| (self.files[*file].as_str(), *line)).collect()
+    }
+}
+
+/// 64-bit FNV-1a hash of machine code
+pub fn code_hash(code: &[u8]) -> u64 {
+    code.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Where the source map of `exe` is written: `app` -> `app.wsmap`
+pub fn sidecar_path(exe: &Path) -> PathBuf {
+    let mut path = OsString::from(exe.as_os_str());
+    path.push(".");
+    path.push(EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Where the executable or library mapped at `address` was loaded, from the
+/// dynamic loader; None if nothing is mapped there
+fn load_base(address: usize) -> Option<usize> {
+    let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+    if unsafe { libc::dladdr(address as *const libc::c_void, &mut info) } == 0 || info.dli_fbase.is_null() {
+        return None;
+    }
+    Some(info.dli_fbase as usize)
+}
+
+/// The running program's source map and load base, if it has a map from
+/// this build
+fn load() -> Option<(SourceMap, usize)> {
+    let exe = env::current_exe().ok()?;
+    let map = SourceMap::parse(&fs::read_to_string(sidecar_path(&exe)).ok()?)?;
+    let base = load_base(load as *const () as usize)?;
+    let (start, end) = map.code_range()?;
+    let (start, end) = (base.checked_add(start as usize)?, base.checked_add(end as usize)?);
+    // Only read code this executable has mapped, then check it is the code the
+    // map was written from
+    if load_base(start)? != base || load_base(end - 1)? != base {
+        return None;
+    }
+    let code = unsafe { std::slice::from_raw_parts(start as *const u8, end - start) };
+    (code_hash(code) == map.code_hash).then_some((map, base))
+}
+
+lazy_static::lazy_static! {
+    static ref LOADED: Option<(SourceMap, usize)> = load();
+}
+
+/// (file, line) of every WadeScript frame at these return addresses,
+/// innermost first, or None if the program has no source map
+pub(crate) fn ws_frames(return_addresses: impl Iterator<Item = usize>) -> Option<Vec<(String, u32)>> {
+    let (map, base) = LOADED.as_ref()?;
+    let mut frames = Vec::new();
+    for address in return_addresses {
+        // A return address is just past the call; step back into it
+        let Some(offset) = address.checked_sub(base + 1) else {
+            continue;
+        };
+        frames.extend(map.lookup(offset as u64).into_iter().map(|(file, line)| (file.to_string(), line)));
+    }
+    Some(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SourceMap {
+        SourceMap {
+            code_hash: 0x9c1d0e44f7a2b316,
+            files: vec!["/home/me/app.ws".to_string(), "/opt/std/json.ws".to_string()],
+            rows: vec![(0x1a40, vec![(0, 12)]), (0x1a52, vec![(1, 4), (0, 12)]), (0x1a60, vec![])],
+        }
+    }
+
+    #[test]
+    fn test_render_and_parse_round_trip() {
+        let text = sample().render();
+        assert_eq!(
+            text,
+            "wsmap 1 9c1d0e44f7a2b316\nf /home/me/app.ws\nf /opt/std/json.ws\n1a40 0:12\n1a52 1:4,0:12\n1a60 -\n"
+        );
+        assert_eq!(SourceMap::parse(&text), Some(sample()));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_maps() {
+        assert_eq!(SourceMap::parse(""), None);
+        assert_eq!(SourceMap::parse("wsmap 2 10\n"), None, "another format version");
+        assert_eq!(SourceMap::parse("wsmap 1 10\n1a40 0:12\n"), None, "file index without a file");
+        assert_eq!(SourceMap::parse("wsmap 1 10\nf a.ws\n1a40 0:12\n1a40 -\n"), None, "unsorted rows");
+        assert_eq!(SourceMap::parse("wsmap 1 10\nf a.ws\n1a40 0:x\n"), None);
+    }
+
+    #[test]
+    fn test_lookup_finds_the_enclosing_range() {
+        let map = sample();
+        assert!(map.lookup(0x1a3f).is_empty(), "before the first range");
+        assert_eq!(map.lookup(0x1a40), vec![("/home/me/app.ws", 12)]);
+        assert_eq!(map.lookup(0x1a51), vec![("/home/me/app.ws", 12)]);
+        assert_eq!(map.lookup(0x1a58), vec![("/opt/std/json.ws", 4), ("/home/me/app.ws", 12)]);
+        assert!(map.lookup(0x1a60).is_empty());
+        assert!(map.lookup(u64::MAX).is_empty());
+    }
+
+    #[test]
+    fn test_code_hash() {
+        assert_eq!(code_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(code_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(code_hash(b"\x90\xc3"), code_hash(b"\xc3\x90"));
+        assert_eq!(sample().code_range(), Some((0x1a40, 0x1a60)));
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(sidecar_path(Path::new("app")), PathBuf::from("app.wsmap"));
+        assert_eq!(sidecar_path(Path::new("/tmp/app.bin")), PathBuf::from("/tmp/app.bin.wsmap"));
+    }
+}
@@ -1,64 +1,69 @@
 use std::alloc::{alloc, Layout};
+use std::collections::HashMap;
 use std::ffi::CStr;
+use std::num::IntErrorKind;
 use std::ptr;
+use std::sync::Mutex;
+
+use super::exceptions::raise;
+use super::list::{list_create, list_push_i64, List};
+use super::rc::ELEMS_PLAIN;
+
+/// Split a string into its characters (Unicode code points), each as its UTF-8
+/// bytes. A byte that isn't part of valid UTF-8 counts as a character of its
+/// own, so such text is carried through unchanged rather than dropped
+fn characters(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    bytes.utf8_chunks().flat_map(|chunk| {
+        let valid = chunk.valid();
+        valid
+            .char_indices()
+            .map(move |(i, c)| &valid.as_bytes()[i..i + c.len_utf8()])
+            .chain(chunk.invalid().chunks(1))
+    })
+}
 
-/// Get the length of a C string
+/// Number of characters in a string ("héllo".length -> 5)
 #[no_mangle]
 pub extern "C" fn str_length(s: *const u8) -> i64 {
     unsafe {
-        if s.is_null() {
-            return 0;
+        let bytes = as_bytes(s);
+        if bytes.is_ascii() {
+            return bytes.len() as i64;
         }
-        CStr::from_ptr(s as *const i8).to_bytes().len() as i64
+        characters(bytes).count() as i64
     }
 }
 
-/// Convert string to uppercase
+/// Number of bytes in a string's UTF-8 encoding ("héllo".byte_length -> 6)
 #[no_mangle]
-pub extern "C" fn str_upper(s: *const u8) -> *mut u8 {
-    unsafe {
-        if s.is_null() {
-            return ptr::null_mut();
-        }
-
-        let c_str = CStr::from_ptr(s as *const i8);
-        let rust_str = c_str.to_str().unwrap_or("");
-        let upper = rust_str.to_uppercase();
-
-        // Allocate new C string
-        let len = upper.len();
-        let layout = Layout::array::<u8>(len + 1).unwrap();
-        let dest = alloc(layout);
-
-        ptr::copy_nonoverlapping(upper.as_ptr(), dest, len);
-        *dest.add(len) = 0; // Null terminator
+pub extern "C" fn str_byte_length(s: *const u8) -> i64 {
+    unsafe { as_bytes(s).len() as i64 }
+}
 
-        dest
+/// Apply a case mapping to the valid UTF-8 in a string, copying any other
+/// bytes unchanged, into a new string
+unsafe fn map_case(s: *const u8, map: fn(&str) -> String) -> *mut u8 {
+    if s.is_null() {
+        return ptr::null_mut();
+    }
+    let mut mapped = Vec::new();
+    for chunk in as_bytes(s).utf8_chunks() {
+        mapped.extend_from_slice(map(chunk.valid()).as_bytes());
+        mapped.extend_from_slice(chunk.invalid());
     }
+    alloc_str(&mapped)
+}
+
+/// Convert string to uppercase ("straße".upper() -> "STRASSE")
+#[no_mangle]
+pub extern "C" fn str_upper(s: *const u8) -> *mut u8 {
+    unsafe { map_case(s, str::to_uppercase) }
 }
 
 /// Convert string to lowercase
 #[no_mangle]
 pub extern "C" fn str_lower(s: *const u8) -> *mut u8 {
-    unsafe {
-        if s.is_null() {
-            return ptr::null_mut();
-        }
-
-        let c_str = CStr::from_ptr(s as *const i8);
-        let rust_str = c_str.to_str().unwrap_or("");
-        let lower = rust_str.to_lowercase();
-
-        // Allocate new C string
-        let len = lower.len();
-        let layout = Layout::array::<u8>(len + 1).unwrap();
-        let dest = alloc(layout);
-
-        ptr::copy_nonoverlapping(lower.as_ptr(), dest, len);
-        *dest.add(len) = 0; // Null terminator
-
-        dest
-    }
+    unsafe { map_case(s, str::to_lowercase) }
 }
 
 /// Check if string contains substring
@@ -79,38 +84,40 @@ pub extern "C" fn str_contains(s: *const u8, substring: *const u8) -> i32 {
     }
 }
 
-/// Get character at index as a single-character string
+/// Get the character at index as a single-character string (null when out of range)
 #[no_mangle]
 pub extern "C" fn str_char_at(s: *const u8, index: i64) -> *mut u8 {
     unsafe {
         if s.is_null() || index < 0 {
             return ptr::null_mut();
         }
-
-        let c_str = CStr::from_ptr(s as *const i8);
-        let rust_str = c_str.to_str().unwrap_or("");
-        let chars: Vec<char> = rust_str.chars().collect();
-
-        if (index as usize) >= chars.len() {
-            return ptr::null_mut();
+        let bytes = as_bytes(s);
+        // Each ASCII character is one byte, so index directly
+        let ch = if bytes.is_ascii() {
+            bytes.get(index as usize).map(std::slice::from_ref)
+        } else {
+            characters(bytes).nth(index as usize)
+        };
+        match ch {
+            Some(ch) => alloc_str(ch),
+            None => ptr::null_mut(),
         }
+    }
+}
 
-        let ch = chars[index as usize];
-        let char_str = ch.to_string();
-
-        // Allocate new C string for single character
-        let len = char_str.len();
-        let layout = Layout::array::<u8>(len + 1).unwrap();
-        let dest = alloc(layout);
-
-        ptr::copy_nonoverlapping(char_str.as_ptr(), dest, len);
-        *dest.add(len) = 0; // Null terminator
-
-        dest
+/// The characters of a string as a new list[str], for `s.chars()` and `for c in s`
+#[no_mangle]
+pub extern "C" fn str_chars(s: *const u8) -> *mut List {
+    let list = list_create(ELEMS_PLAIN);
+    unsafe {
+        for ch in characters(as_bytes(s)) {
+            list_push_i64(list, alloc_str(ch) as i64);
+        }
     }
+    list
 }
 
-/// Slice a string and return a new string
+/// Slice a string by character positions and return a new string
 /// start: -1 means from beginning (0)
 /// end: -1 means to end (length)
 /// step: 0 means default step (1)
@@ -121,9 +128,7 @@ pub extern "C" fn str_slice(s: *const u8, start: i64, end: i64, step: i64) -> *m
             return ptr::null_mut();
         }
 
-        let c_str = CStr::from_ptr(s as *const i8);
-        let rust_str = c_str.to_str().unwrap_or("");
-        let chars: Vec<char> = rust_str.chars().collect();
+        let chars: Vec<&[u8]> = characters(as_bytes(s)).collect();
         let len = chars.len() as i64;
 
         // Determine actual step
@@ -143,35 +148,430 @@ pub extern "C" fn str_slice(s: *const u8, start: i64, end: i64, step: i64) -> *m
         };
 
         // Collect characters based on slice
-        let mut result_chars: Vec<char> = Vec::new();
+        let mut result: Vec<u8> = Vec::new();
         let mut idx = actual_start;
 
         if actual_step > 0 {
             while idx < actual_end && idx < len {
-                result_chars.push(chars[idx as usize]);
+                result.extend_from_slice(chars[idx as usize]);
                 idx += actual_step;
             }
         } else {
             while idx > actual_end && idx >= 0 {
-                result_chars.push(chars[idx as usize]);
+                result.extend_from_slice(chars[idx as usize]);
                 idx += actual_step; // step is negative
             }
         }
 
-        let result_str: String = result_chars.into_iter().collect();
+        alloc_str(&result)
+    }
+}
+
+/// Repeat a string `count` times ("-" * 3 -> "---")
+/// A count of zero or less produces an empty string
+#[no_mangle]
+pub extern "C" fn str_repeat(s: *const u8, count: i64) -> *mut u8 {
+    unsafe {
+        if s.is_null() {
+            return ptr::null_mut();
+        }
+
+        let src = CStr::from_ptr(s as *const i8).to_bytes();
+        let times = count.max(0) as usize;
+        let total_len = src.len()
+            .checked_mul(times)
+            .expect("String repetition result is too large");
 
         // Allocate new C string
-        let result_len = result_str.len();
-        let layout = Layout::array::<u8>(result_len + 1).unwrap();
+        let layout = Layout::array::<u8>(total_len + 1).unwrap();
         let dest = alloc(layout);
 
-        ptr::copy_nonoverlapping(result_str.as_ptr(), dest, result_len);
-        *dest.add(result_len) = 0; // Null terminator
+        for i in 0..times {
+            ptr::copy_nonoverlapping(src.as_ptr(), dest.add(i * src.len()), src.len());
+        }
+        *dest.add(total_len) = 0; // Null terminator
+
+        dest
+    }
+}
+
+/// Copy bytes into a new null-terminated string
+pub(crate) unsafe fn alloc_str(bytes: &[u8]) -> *mut u8 {
+    let layout = Layout::array::<u8>(bytes.len() + 1).unwrap();
+    let dest = alloc(layout);
+    ptr::copy_nonoverlapping(bytes.as_ptr(), dest, bytes.len());
+    *dest.add(bytes.len()) = 0; // Null terminator
+    dest
+}
+
+/// Split a string on every occurrence of `sep` into a new list[str]
+/// ("a,,b".split(",") -> ["a", "", "b"]). Raises ValueError for an empty separator
+#[no_mangle]
+pub extern "C" fn str_split(s: *const u8, sep: *const u8) -> *mut List {
+    unsafe {
+        let sep_str = if sep.is_null() { "" } else { CStr::from_ptr(sep as *const i8).to_str().unwrap_or("") };
+        if sep_str.is_empty() {
+            raise("ValueError", "split() separator must not be empty".to_string());
+        }
+        let list = list_create(ELEMS_PLAIN);
+        if s.is_null() {
+            return list;
+        }
+        let rust_str = CStr::from_ptr(s as *const i8).to_str().unwrap_or("");
+        for part in rust_str.split(sep_str) {
+            list_push_i64(list, alloc_str(part.as_bytes()) as i64);
+        }
+        list
+    }
+}
+
+/// Join a list[str] into one new string with `sep` between the parts
+/// (", ".join(["a", "b"]) -> "a, b")
+#[no_mangle]
+pub extern "C" fn str_join(sep: *const u8, parts: *const List) -> *mut u8 {
+    unsafe {
+        let sep_bytes = if sep.is_null() { &[][..] } else { CStr::from_ptr(sep as *const i8).to_bytes() };
+        let mut joined = Vec::new();
+        if !parts.is_null() {
+            let parts_ref = &*parts;
+            for i in 0..parts_ref.length {
+                if i > 0 {
+                    joined.extend_from_slice(sep_bytes);
+                }
+                let part = *parts_ref.data.add(i as usize) as *const i8;
+                if !part.is_null() {
+                    joined.extend_from_slice(CStr::from_ptr(part).to_bytes());
+                }
+            }
+        }
+        alloc_str(&joined)
+    }
+}
+
+/// Fill the placeholders of a format() template: `{}` takes the next argument,
+/// `{n}` argument n, and `{{` / `}}` are literal braces
+fn expand_template(template: &[u8], args: &[&[u8]]) -> Result<Vec<u8>, (&'static str, String)> {
+    let mut out = Vec::with_capacity(template.len() + args.iter().map(|a| a.len()).sum::<usize>());
+    let mut next_auto = 0;
+    let mut numbered = None;
+    let mut i = 0;
+    while i < template.len() {
+        match template[i] {
+            b'{' if template.get(i + 1) == Some(&b'{') => {
+                out.push(b'{');
+                i += 2;
+            }
+            b'}' if template.get(i + 1) == Some(&b'}') => {
+                out.push(b'}');
+                i += 2;
+            }
+            b'{' => {
+                let close = template[i..]
+                    .iter()
+                    .position(|&b| b == b'}')
+                    .ok_or(("ValueError", "format(): '{' without a matching '}'".to_string()))?;
+                let field = &template[i + 1..i + close];
+                let index = if field.is_empty() {
+                    if numbered == Some(true) {
+                        return Err(("ValueError", "format(): can't mix {} and {0} placeholders".to_string()));
+                    }
+                    numbered = Some(false);
+                    next_auto += 1;
+                    next_auto - 1
+                } else {
+                    let text = String::from_utf8_lossy(field);
+                    let index = text
+                        .parse::<usize>()
+                        .map_err(|_| ("ValueError", format!("format(): bad placeholder '{{{}}}'", text)))?;
+                    if numbered == Some(false) {
+                        return Err(("ValueError", "format(): can't mix {} and {0} placeholders".to_string()));
+                    }
+                    numbered = Some(true);
+                    index
+                };
+                let arg = args.get(index).ok_or_else(|| {
+                    ("IndexError", format!("format(): placeholder {} is out of range for {} argument(s)", index, args.len()))
+                })?;
+                out.extend_from_slice(arg);
+                i += close + 1;
+            }
+            b'}' => return Err(("ValueError", "format(): '}' without a matching '{'".to_string())),
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// format(template, ...): the template with each placeholder replaced by the
+/// text of an argument. `args` is a list of the arguments already as strings
+#[no_mangle]
+pub extern "C" fn str_format(template: *const u8, args: *const List) -> *mut u8 {
+    let result = unsafe {
+        let args: Vec<&[u8]> = match args.as_ref() {
+            Some(list) => (0..list.length).map(|i| as_bytes(*list.data.add(i as usize) as *const u8)).collect(),
+            None => Vec::new(),
+        };
+        expand_template(as_bytes(template), &args)
+    };
+    match result {
+        Ok(text) => unsafe { alloc_str(&text) },
+        Err((error_type, message)) => raise(error_type, message),
+    }
+}
+
+/// Apply a trimming function to a string and copy the result into a new string
+unsafe fn trim_with(s: *const u8, trim: fn(&str) -> &str) -> *mut u8 {
+    if s.is_null() {
+        return ptr::null_mut();
+    }
+    let rust_str = CStr::from_ptr(s as *const i8).to_str().unwrap_or("");
+    alloc_str(trim(rust_str).as_bytes())
+}
+
+/// Remove leading and trailing whitespace ("  a b \n".strip() -> "a b")
+#[no_mangle]
+pub extern "C" fn str_strip(s: *const u8) -> *mut u8 {
+    unsafe { trim_with(s, str::trim) }
+}
+
+/// Remove leading whitespace
+#[no_mangle]
+pub extern "C" fn str_lstrip(s: *const u8) -> *mut u8 {
+    unsafe { trim_with(s, str::trim_start) }
+}
+
+/// Remove trailing whitespace
+#[no_mangle]
+pub extern "C" fn str_rstrip(s: *const u8) -> *mut u8 {
+    unsafe { trim_with(s, str::trim_end) }
+}
+
+/// Borrow a C string as &str; null and invalid UTF-8 read as ""
+unsafe fn as_str<'a>(s: *const u8) -> &'a str {
+    if s.is_null() {
+        return "";
+    }
+    CStr::from_ptr(s as *const i8).to_str().unwrap_or("")
+}
+
+/// Replace every occurrence of `old` with `new`, as a new string
+/// ("a-b-c".replace("-", "+") -> "a+b+c"). An empty `old` matches between
+/// every character, as in Python
+#[no_mangle]
+pub extern "C" fn str_replace(s: *const u8, old: *const u8, new: *const u8) -> *mut u8 {
+    unsafe {
+        if s.is_null() {
+            return ptr::null_mut();
+        }
+        alloc_str(as_str(s).replace(as_str(old), as_str(new)).as_bytes())
+    }
+}
+
+/// Character index of the first occurrence of `sub`, or -1 if there is none
+/// The index counts characters, like slicing, not bytes
+#[no_mangle]
+pub extern "C" fn str_find(s: *const u8, sub: *const u8) -> i64 {
+    unsafe {
+        if s.is_null() {
+            return -1;
+        }
+        let text = as_str(s);
+        match text.find(as_str(sub)) {
+            Some(byte_index) => text[..byte_index].chars().count() as i64,
+            None => -1,
+        }
+    }
+}
+
+/// Check if string starts with prefix
+#[no_mangle]
+pub extern "C" fn str_starts_with(s: *const u8, prefix: *const u8) -> i32 {
+    unsafe { (!s.is_null() && as_str(s).starts_with(as_str(prefix))) as i32 }
+}
+
+/// Check if string ends with suffix
+#[no_mangle]
+pub extern "C" fn str_ends_with(s: *const u8, suffix: *const u8) -> i32 {
+    unsafe { (!s.is_null() && as_str(s).ends_with(as_str(suffix))) as i32 }
+}
+
+/// Parse a boolean: "true"/"false", "yes"/"no", "on"/"off" or "1"/"0", in any case and
+/// with surrounding whitespace ignored. Returns 1 or 0, or -1 for anything else
+#[no_mangle]
+pub extern "C" fn str_parse_bool(s: *const u8) -> i64 {
+    if s.is_null() {
+        return -1;
+    }
+    let text = unsafe { CStr::from_ptr(s as *const i8) }.to_str().unwrap_or("").trim().to_lowercase();
+    match text.as_str() {
+        "true" | "yes" | "on" | "1" => 1,
+        "false" | "no" | "off" | "0" => 0,
+        _ => -1,
+    }
+}
+
+/// Parse an int for int(s): optional sign, digits and surrounding whitespace.
+/// Raises ValueError for other text and OverflowError past the int range
+#[no_mangle]
+pub extern "C" fn str_to_int(s: *const u8) -> i64 {
+    let text = unsafe { as_str(s) };
+    match text.trim().parse::<i64>() {
+        Ok(value) => value,
+        Err(err) if matches!(err.kind(), IntErrorKind::PosOverflow | IntErrorKind::NegOverflow) => {
+            raise("OverflowError", format!("int() value out of range: '{}'", text))
+        }
+        Err(_) => raise("ValueError", format!("invalid literal for int(): '{}'", text)),
+    }
+}
+
+/// Parse a float for float(s), including exponents, "inf" and "nan".
+/// Raises ValueError for anything else
+#[no_mangle]
+pub extern "C" fn str_to_float(s: *const u8) -> f64 {
+    let text = unsafe { as_str(s) };
+    match text.trim().parse::<f64>() {
+        Ok(value) => value,
+        Err(_) => raise("ValueError", format!("could not convert string to float: '{}'", text)),
+    }
+}
+
+/// Decimal text of an int, for str(n)
+#[no_mangle]
+pub extern "C" fn int_to_str(value: i64) -> *mut u8 {
+    unsafe { alloc_str(value.to_string().as_bytes()) }
+}
+
+/// Borrow a C string's bytes; null reads as empty
+pub(crate) unsafe fn as_bytes<'a>(s: *const u8) -> &'a [u8] {
+    if s.is_null() {
+        return &[];
+    }
+    CStr::from_ptr(s as *const i8).to_bytes()
+}
 
+/// Concatenate two strings into a new one allocated at the combined length ("ab" + "c")
+#[no_mangle]
+pub extern "C" fn str_concat(left: *const u8, right: *const u8) -> *mut u8 {
+    unsafe {
+        let left = as_bytes(left);
+        let right = as_bytes(right);
+        let layout = Layout::array::<u8>(left.len() + right.len() + 1).unwrap();
+        let dest = alloc(layout);
+        ptr::copy_nonoverlapping(left.as_ptr(), dest, left.len());
+        ptr::copy_nonoverlapping(right.as_ptr(), dest.add(left.len()), right.len());
+        *dest.add(left.len() + right.len()) = 0; // Null terminator
         dest
     }
 }
 
+// ============================================================================
+// String builders
+// ============================================================================
+
+/// Growable buffer behind f-strings. Appends are amortized O(1) and the text
+/// is copied out once, at its final length
+pub struct StrBuilder {
+    bytes: Vec<u8>,
+}
+
+/// Start a buffer with room for `capacity` bytes; it grows as needed
+#[no_mangle]
+pub extern "C" fn str_builder_new(capacity: i64) -> *mut StrBuilder {
+    let bytes = Vec::with_capacity(capacity.max(0) as usize);
+    Box::into_raw(Box::new(StrBuilder { bytes }))
+}
+
+/// Append a string to the buffer
+#[no_mangle]
+pub extern "C" fn str_builder_append(builder: *mut StrBuilder, s: *const u8) {
+    unsafe {
+        (*builder).bytes.extend_from_slice(as_bytes(s));
+    }
+}
+
+/// Copy the buffer out as a new string and free the buffer
+#[no_mangle]
+pub extern "C" fn str_builder_finish(builder: *mut StrBuilder) -> *mut u8 {
+    unsafe {
+        let builder = Box::from_raw(builder);
+        alloc_str(&builder.bytes)
+    }
+}
+
+// Builders held by std/strings.ws StringBuilder objects, by handle
+lazy_static::lazy_static! {
+    static ref STRING_BUILDERS: Mutex<StringBuilderManager> = Mutex::new(StringBuilderManager::new());
+}
+
+struct StringBuilderManager {
+    builders: HashMap<i64, Vec<u8>>,
+    next_id: i64,
+}
+
+impl StringBuilderManager {
+    fn new() -> Self {
+        StringBuilderManager {
+            builders: HashMap::new(),
+            next_id: 1,
+        }
+    }
+}
+
+/// Run `f` on the buffer for a handle. Raises ValueError for a handle that was
+/// never created or has been freed, after the lock is released: raising jumps
+/// past the guard, which would otherwise stay locked
+fn with_builder<T>(handle: i64, f: impl FnOnce(&mut Vec<u8>) -> T) -> T {
+    let result = STRING_BUILDERS.lock().unwrap().builders.get_mut(&handle).map(f);
+    match result {
+        Some(value) => value,
+        None => raise("ValueError", format!("invalid StringBuilder handle {}", handle)),
+    }
+}
+
+/// Create an empty builder with room for `capacity` bytes and return its handle
+#[no_mangle]
+pub extern "C" fn string_builder_new(capacity: i64) -> i64 {
+    let mut manager = STRING_BUILDERS.lock().unwrap();
+    let id = manager.next_id;
+    manager.next_id += 1;
+    manager.builders.insert(id, Vec::with_capacity(capacity.max(0) as usize));
+    id
+}
+
+/// Append text to a builder
+#[no_mangle]
+pub extern "C" fn string_builder_append(handle: i64, s: *const u8) {
+    let text = unsafe { as_bytes(s) };
+    with_builder(handle, |bytes| bytes.extend_from_slice(text));
+}
+
+/// Number of bytes appended so far
+#[no_mangle]
+pub extern "C" fn string_builder_length(handle: i64) -> i64 {
+    with_builder(handle, |bytes| bytes.len() as i64)
+}
+
+/// The text so far as a new string; the builder stays usable
+#[no_mangle]
+pub extern "C" fn string_builder_build(handle: i64) -> *mut u8 {
+    with_builder(handle, |bytes| unsafe { alloc_str(bytes) })
+}
+
+/// Empty a builder, keeping its capacity
+#[no_mangle]
+pub extern "C" fn string_builder_clear(handle: i64) {
+    with_builder(handle, |bytes| bytes.clear());
+}
+
+/// Free a builder; freeing an unknown handle does nothing
+#[no_mangle]
+pub extern "C" fn string_builder_free(handle: i64) {
+    STRING_BUILDERS.lock().unwrap().builders.remove(&handle);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -395,4 +795,310 @@ mod tests {
             assert_eq!(str_length(s.as_ptr() as *const u8), expected_len);
         }
     }
+
+    #[test]
+    fn test_str_repeat() {
+        let s = CString::new("ab").unwrap();
+        let result = str_repeat(s.as_ptr() as *const u8, 3);
+
+        unsafe {
+            let result_cstr = CStr::from_ptr(result as *const i8);
+            assert_eq!(result_cstr.to_str().unwrap(), "ababab");
+        }
+    }
+
+    #[test]
+    fn test_str_repeat_zero_and_negative() {
+        let s = CString::new("ab").unwrap();
+        let zero = str_repeat(s.as_ptr() as *const u8, 0);
+        let negative = str_repeat(s.as_ptr() as *const u8, -2);
+
+        unsafe {
+            assert_eq!(CStr::from_ptr(zero as *const i8).to_str().unwrap(), "");
+            assert_eq!(CStr::from_ptr(negative as *const i8).to_str().unwrap(), "");
+        }
+        assert!(str_repeat(ptr::null(), 3).is_null());
+    }
+
+    /// Read back a string returned by the runtime
+    fn read(result: *mut u8) -> String {
+        assert!(!result.is_null());
+        unsafe { CStr::from_ptr(result as *const i8).to_str().unwrap().to_string() }
+    }
+
+    #[test]
+    fn test_str_length_counts_characters() {
+        let s = CString::new("héllo").unwrap();
+        assert_eq!(str_length(s.as_ptr() as *const u8), 5);
+        assert_eq!(str_byte_length(s.as_ptr() as *const u8), 6);
+
+        let s = CString::new("日本語").unwrap();
+        assert_eq!(str_length(s.as_ptr() as *const u8), 3);
+        assert_eq!(str_byte_length(s.as_ptr() as *const u8), 9);
+
+        assert_eq!(str_byte_length(ptr::null()), 0);
+    }
+
+    #[test]
+    fn test_str_char_at_utf8() {
+        let s = CString::new("日本語").unwrap();
+        let ptr = s.as_ptr() as *const u8;
+
+        // Indexing is by character, not byte
+        assert_eq!(read(str_char_at(ptr, 0)), "日");
+        assert_eq!(read(str_char_at(ptr, 2)), "語");
+        assert!(str_char_at(ptr, 3).is_null());
+    }
+
+    #[test]
+    fn test_str_slice() {
+        let s = CString::new("abcdef").unwrap();
+        let ptr = s.as_ptr() as *const u8;
+
+        assert_eq!(read(str_slice(ptr, 1, 4, 0)), "bcd");
+        assert_eq!(read(str_slice(ptr, -1, -1, 2)), "ace");
+        assert_eq!(read(str_slice(ptr, -1, -1, -1)), "fedcba");
+        assert_eq!(read(str_slice(ptr, -2, -1, 0)), "ef");
+        // Out-of-range bounds are clamped, empty ranges give ""
+        assert_eq!(read(str_slice(ptr, 3, 100, 0)), "def");
+        assert_eq!(read(str_slice(ptr, 4, 2, 0)), "");
+        assert!(str_slice(ptr::null(), 0, 1, 0).is_null());
+    }
+
+    #[test]
+    fn test_str_slice_utf8() {
+        let s = CString::new("héllo wörld").unwrap();
+        let ptr = s.as_ptr() as *const u8;
+
+        assert_eq!(read(str_slice(ptr, 1, 3, 0)), "él");
+        assert_eq!(read(str_slice(ptr, 6, -1, 0)), "wörld");
+        assert_eq!(read(str_slice(ptr, 8, 5, -1)), "röw");
+
+        let empty = CString::new("").unwrap();
+        assert_eq!(read(str_slice(empty.as_ptr() as *const u8, -1, -1, -1)), "");
+    }
+
+    #[test]
+    fn test_str_case_conversion_utf8() {
+        let s = CString::new("Ünïcödé").unwrap();
+        assert_eq!(read(str_upper(s.as_ptr() as *const u8)), "ÜNÏCÖDÉ");
+        assert_eq!(read(str_lower(s.as_ptr() as *const u8)), "ünïcödé");
+
+        // Uppercasing can change the length
+        let s = CString::new("straße").unwrap();
+        assert_eq!(read(str_upper(s.as_ptr() as *const u8)), "STRASSE");
+    }
+
+    #[test]
+    fn test_str_contains_utf8() {
+        let s = CString::new("naïve café").unwrap();
+        let found = CString::new("café").unwrap();
+        let missing = CString::new("cafe").unwrap();
+
+        assert_eq!(str_contains(s.as_ptr() as *const u8, found.as_ptr() as *const u8), 1);
+        assert_eq!(str_contains(s.as_ptr() as *const u8, missing.as_ptr() as *const u8), 0);
+    }
+
+    #[test]
+    fn test_str_chars_and_case_keep_utf8() {
+        use super::super::list::{list_free, list_get_i64};
+
+        let s = CString::new("aé日").unwrap();
+        let chars = str_chars(s.as_ptr() as *const u8);
+        let texts: Vec<String> = (0..unsafe { (*chars).length })
+            .map(|i| read(list_get_i64(chars, i) as *mut u8))
+            .collect();
+        assert_eq!(texts, vec!["a", "é", "日"]);
+        list_free(chars);
+
+        let s = CString::new("straße ÉTÉ").unwrap();
+        assert_eq!(read(str_upper(s.as_ptr() as *const u8)), "STRASSE ÉTÉ");
+        assert_eq!(read(str_lower(s.as_ptr() as *const u8)), "straße été");
+
+        // Invalid UTF-8 is neither dropped nor merged: each stray byte is one character
+        let s = CString::new(vec![b'a', 0xff, b'b']).unwrap();
+        let ptr = s.as_ptr() as *const u8;
+        assert_eq!(str_length(ptr), 3);
+        let upper = str_upper(ptr);
+        assert_eq!(unsafe { as_bytes(upper) }, &[b'A', 0xff, b'B']);
+        assert_eq!(unsafe { as_bytes(str_char_at(ptr, 1)) }, &[0xff]);
+    }
+
+    #[test]
+    fn test_str_format() {
+        use super::super::list::{list_free, list_push_ptr};
+
+        let args = list_create(ELEMS_PLAIN);
+        for arg in ["Ada", "36"] {
+            list_push_ptr(args, unsafe { alloc_str(arg.as_bytes()) });
+        }
+        let format = |template: &str| {
+            let template = CString::new(template).unwrap();
+            expand_template(template.as_bytes(), &["Ada".as_bytes(), "36".as_bytes()]).map(|t| String::from_utf8(t).unwrap())
+        };
+
+        let template = CString::new("Hello {} you are {}").unwrap();
+        assert_eq!(read(str_format(template.as_ptr() as *const u8, args)), "Hello Ada you are 36");
+        assert_eq!(format("{1} is {0}'s age").unwrap(), "36 is Ada's age");
+        assert_eq!(format("{0}{0}").unwrap(), "AdaAda");
+        assert_eq!(format("{{}} {}").unwrap(), "{} Ada");
+        assert_eq!(format("no placeholders").unwrap(), "no placeholders");
+
+        assert_eq!(format("{} {} {}").unwrap_err().0, "IndexError");
+        assert_eq!(format("{2}").unwrap_err().0, "IndexError");
+        assert!(format("{} {1}").unwrap_err().1.contains("can't mix"));
+        assert!(format("{name}").unwrap_err().1.contains("bad placeholder '{name}'"));
+        assert!(format("open {").unwrap_err().1.contains("without a matching '}'"));
+        assert!(format("close }").unwrap_err().1.contains("without a matching '{'"));
+        list_free(args);
+    }
+
+    #[test]
+    fn test_str_repeat_utf8() {
+        let s = CString::new("é").unwrap();
+        let result = str_repeat(s.as_ptr() as *const u8, 3);
+        assert_eq!(read(result), "ééé");
+        assert_eq!(str_length(result), 3);
+        assert_eq!(str_byte_length(result), 6);
+    }
+
+    #[test]
+    fn test_str_split_and_join() {
+        use super::super::list::{list_free, list_get_i64};
+
+        let s = CString::new("a,,b,ç").unwrap();
+        let comma = CString::new(",").unwrap();
+        let parts = str_split(s.as_ptr() as *const u8, comma.as_ptr() as *const u8);
+        let texts: Vec<String> = (0..unsafe { (*parts).length })
+            .map(|i| read(list_get_i64(parts, i) as *mut u8))
+            .collect();
+        assert_eq!(texts, ["a", "", "b", "ç"]);
+
+        let sep = CString::new(" | ").unwrap();
+        assert_eq!(read(str_join(sep.as_ptr() as *const u8, parts)), "a |  | b | ç");
+
+        // An empty string is one empty part, and joining nothing gives ""
+        let empty = CString::new("").unwrap();
+        let one = str_split(empty.as_ptr() as *const u8, comma.as_ptr() as *const u8);
+        assert_eq!(unsafe { (*one).length }, 1);
+        let none = list_create(ELEMS_PLAIN);
+        assert_eq!(read(str_join(sep.as_ptr() as *const u8, none)), "");
+        for list in [parts, one, none] {
+            list_free(list);
+        }
+    }
+
+    #[test]
+    fn test_str_strip() {
+        let s = CString::new(" \t name = ada\r\n").unwrap();
+        assert_eq!(read(str_strip(s.as_ptr() as *const u8)), "name = ada");
+        assert_eq!(read(str_lstrip(s.as_ptr() as *const u8)), "name = ada\r\n");
+        assert_eq!(read(str_rstrip(s.as_ptr() as *const u8)), " \t name = ada");
+
+        // Unicode whitespace counts too, and a blank string strips to ""
+        let s = CString::new("\u{3000}ç\u{a0}").unwrap();
+        assert_eq!(read(str_strip(s.as_ptr() as *const u8)), "ç");
+        let blank = CString::new("   ").unwrap();
+        assert_eq!(read(str_strip(blank.as_ptr() as *const u8)), "");
+        assert!(str_strip(ptr::null()).is_null());
+    }
+
+    #[test]
+    fn test_str_parse_bool() {
+        for (text, expected) in [("true", 1), (" YES\n", 1), ("On", 1), ("1", 1), ("False", 0), ("no", 0), ("off", 0), ("0", 0)] {
+            let s = CString::new(text).unwrap();
+            assert_eq!(str_parse_bool(s.as_ptr() as *const u8), expected, "{:?}", text);
+        }
+        for text in ["", "maybe", "2", "t"] {
+            let s = CString::new(text).unwrap();
+            assert_eq!(str_parse_bool(s.as_ptr() as *const u8), -1, "{:?}", text);
+        }
+        assert_eq!(str_parse_bool(ptr::null()), -1);
+    }
+
+    #[test]
+    fn test_str_replace_and_find() {
+        let s = CString::new("çà-va-çà").unwrap();
+        let dash = CString::new("-").unwrap();
+        let plus = CString::new(" + ").unwrap();
+        let ca = CString::new("çà").unwrap();
+        let missing = CString::new("x").unwrap();
+        let empty = CString::new("").unwrap();
+        let s_ptr = s.as_ptr() as *const u8;
+
+        assert_eq!(read(str_replace(s_ptr, dash.as_ptr() as *const u8, plus.as_ptr() as *const u8)), "çà + va + çà");
+        assert_eq!(read(str_replace(s_ptr, ca.as_ptr() as *const u8, empty.as_ptr() as *const u8)), "-va-");
+        assert_eq!(read(str_replace(s_ptr, missing.as_ptr() as *const u8, plus.as_ptr() as *const u8)), "çà-va-çà");
+
+        // Indexes count characters, so "va" is at 3 even though "çà" takes 4 bytes
+        let va = CString::new("va").unwrap();
+        assert_eq!(str_find(s_ptr, va.as_ptr() as *const u8), 3);
+        assert_eq!(str_find(s_ptr, ca.as_ptr() as *const u8), 0);
+        assert_eq!(str_find(s_ptr, missing.as_ptr() as *const u8), -1);
+        assert_eq!(str_find(s_ptr, empty.as_ptr() as *const u8), 0);
+    }
+
+    #[test]
+    fn test_str_starts_and_ends_with() {
+        let s = CString::new("report.csv").unwrap();
+        let prefix = CString::new("report").unwrap();
+        let suffix = CString::new(".csv").unwrap();
+        let empty = CString::new("").unwrap();
+        let s_ptr = s.as_ptr() as *const u8;
+
+        assert_eq!(str_starts_with(s_ptr, prefix.as_ptr() as *const u8), 1);
+        assert_eq!(str_starts_with(s_ptr, suffix.as_ptr() as *const u8), 0);
+        assert_eq!(str_ends_with(s_ptr, suffix.as_ptr() as *const u8), 1);
+        assert_eq!(str_ends_with(s_ptr, prefix.as_ptr() as *const u8), 0);
+        assert_eq!(str_starts_with(s_ptr, empty.as_ptr() as *const u8), 1);
+        assert_eq!(str_ends_with(ptr::null(), empty.as_ptr() as *const u8), 0);
+    }
+
+    #[test]
+    fn test_str_number_conversions() {
+        for (text, expected) in [("42", 42), (" -7\n", -7), ("+3", 3), ("9223372036854775807", i64::MAX)] {
+            let s = CString::new(text).unwrap();
+            assert_eq!(str_to_int(s.as_ptr() as *const u8), expected, "{:?}", text);
+        }
+        for (text, expected) in [("3.5", 3.5), (" -2 ", -2.0), ("1e3", 1000.0), ("inf", f64::INFINITY)] {
+            let s = CString::new(text).unwrap();
+            assert_eq!(str_to_float(s.as_ptr() as *const u8), expected, "{:?}", text);
+        }
+        let nan = CString::new("nan").unwrap();
+        assert!(str_to_float(nan.as_ptr() as *const u8).is_nan());
+
+        assert_eq!(read(int_to_str(42)), "42");
+        assert_eq!(read(int_to_str(-1)), "-1");
+        assert_eq!(read(int_to_str(i64::MIN)), "-9223372036854775808");
+    }
+
+    #[test]
+    fn test_str_concat_and_builders() {
+        let left = CString::new("ab").unwrap();
+        let right = CString::new("cde").unwrap();
+        assert_eq!(read(str_concat(left.as_ptr() as *const u8, right.as_ptr() as *const u8)), "abcde");
+        assert_eq!(read(str_concat(ptr::null(), right.as_ptr() as *const u8)), "cde");
+
+        // Appends past the initial capacity grow the buffer
+        let builder = str_builder_new(2);
+        for _ in 0..100 {
+            str_builder_append(builder, right.as_ptr() as *const u8);
+        }
+        str_builder_append(builder, ptr::null());
+        assert_eq!(read(str_builder_finish(builder)), "cde".repeat(100));
+
+        let handle = string_builder_new(0);
+        string_builder_append(handle, left.as_ptr() as *const u8);
+        string_builder_append(handle, right.as_ptr() as *const u8);
+        assert_eq!(string_builder_length(handle), 5);
+        assert_eq!(read(string_builder_build(handle)), "abcde");
+        // Building leaves the text in place
+        string_builder_append(handle, left.as_ptr() as *const u8);
+        assert_eq!(read(string_builder_build(handle)), "abcdeab");
+        string_builder_clear(handle);
+        assert_eq!(string_builder_length(handle), 0);
+        string_builder_free(handle);
+        string_builder_free(handle);
+        assert_ne!(string_builder_new(16), handle, "handles are not reused");
+    }
 }
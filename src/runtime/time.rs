@@ -0,0 +1,385 @@
+//! Clock and date runtime for WadeScript
+//!
+//! Wall-clock time as Unix timestamps, a monotonic clock for measuring
+//! durations, sleeping, and strftime-style formatting and parsing of dates.
+//! Dates are always in UTC: there is no time zone database.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::exceptions::raise;
+use super::string::{alloc_str, as_bytes};
+
+lazy_static::lazy_static! {
+    /// The zero point of time_monotonic(): the first time the clock is read
+    static ref MONOTONIC_START: Instant = Instant::now();
+}
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+const WEEKDAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// Time since the Unix epoch. A clock set before 1970 gives negative values
+fn since_epoch() -> (i64, i64) {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => (elapsed.as_secs() as i64, elapsed.as_millis() as i64),
+        Err(e) => {
+            let before = e.duration();
+            (-(before.as_secs() as i64), -(before.as_millis() as i64))
+        }
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+/// (Howard Hinnant's days_from_civil)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The (year, month, day) of a day count from 1970-01-01, the inverse of days_from_civil
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// A UTC timestamp broken into calendar fields
+struct DateTime {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+}
+
+impl DateTime {
+    fn from_timestamp(seconds: i64) -> Self {
+        let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+        let time_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+        DateTime {
+            year,
+            month,
+            day,
+            hour: time_of_day / 3600,
+            minute: time_of_day / 60 % 60,
+            second: time_of_day % 60,
+        }
+    }
+
+    fn timestamp(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * SECONDS_PER_DAY
+            + self.hour * 3600
+            + self.minute * 60
+            + self.second
+    }
+
+    /// 0 for Sunday; 1970-01-01 was a Thursday
+    fn weekday(&self) -> usize {
+        (days_from_civil(self.year, self.month, self.day) + 4).rem_euclid(7) as usize
+    }
+
+    fn day_of_year(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) - days_from_civil(self.year, 1, 1) + 1
+    }
+}
+
+/// Format a timestamp with strftime-style directives (see docs/TIME.md)
+fn format_timestamp(seconds: i64, format: &str) -> Result<String, String> {
+    let date = DateTime::from_timestamp(seconds);
+    let mut out = String::with_capacity(format.len() + 16);
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{:04}", date.year)),
+            Some('m') => out.push_str(&format!("{:02}", date.month)),
+            Some('d') => out.push_str(&format!("{:02}", date.day)),
+            Some('H') => out.push_str(&format!("{:02}", date.hour)),
+            Some('M') => out.push_str(&format!("{:02}", date.minute)),
+            Some('S') => out.push_str(&format!("{:02}", date.second)),
+            Some('j') => out.push_str(&format!("{:03}", date.day_of_year())),
+            Some('F') => out.push_str(&format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)),
+            Some('T') => out.push_str(&format!("{:02}:{:02}:{:02}", date.hour, date.minute, date.second)),
+            Some('A') => out.push_str(WEEKDAYS[date.weekday()]),
+            Some('a') => out.push_str(&WEEKDAYS[date.weekday()][..3]),
+            Some('B') => out.push_str(MONTHS[date.month as usize - 1]),
+            Some('b') => out.push_str(&MONTHS[date.month as usize - 1][..3]),
+            Some('s') => out.push_str(&seconds.to_string()),
+            Some('z') => out.push_str("+0000"),
+            Some('%') => out.push('%'),
+            Some(other) => return Err(format!("unknown directive '%{}' in format '{}'", other, format)),
+            None => return Err(format!("format '{}' ends with a lone '%'", format)),
+        }
+    }
+    Ok(out)
+}
+
+/// Reads the fields of a date from text, one directive at a time
+struct DateParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl DateParser<'_> {
+    /// Up to `max` digits, after an optional sign if `signed`
+    fn number(&mut self, max: usize, signed: bool) -> Option<i64> {
+        let rest = &self.text[self.pos..];
+        let sign_len = if signed && rest.starts_with(['-', '+']) { 1 } else { 0 };
+        let digits = rest[sign_len..].bytes().take(max).take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        let value = rest[..sign_len + digits].parse().ok()?;
+        self.pos += sign_len + digits;
+        Some(value)
+    }
+
+    fn literal(&mut self, expected: char) -> Option<()> {
+        let c = self.text[self.pos..].chars().next()?;
+        if c != expected {
+            return None;
+        }
+        self.pos += c.len_utf8();
+        Some(())
+    }
+
+    /// The index of the name at this position, full or abbreviated to three
+    /// letters, ignoring case
+    fn name(&mut self, names: &[&str]) -> Option<usize> {
+        let rest = self.text[self.pos..].to_ascii_lowercase();
+        let (index, len) = names.iter().enumerate().find_map(|(i, name)| {
+            let name = name.to_ascii_lowercase();
+            if rest.starts_with(&name) {
+                Some((i, name.len()))
+            } else if rest.starts_with(&name[..3]) {
+                Some((i, 3))
+            } else {
+                None
+            }
+        })?;
+        self.pos += len;
+        Some(index)
+    }
+
+    /// "Z", "+HHMM" or "+HH:MM", as seconds east of UTC
+    fn utc_offset(&mut self) -> Option<i64> {
+        if self.literal('Z').is_some() {
+            return Some(0);
+        }
+        let sign = match self.text[self.pos..].chars().next()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return None,
+        };
+        self.pos += 1;
+        let hours = self.number(2, false)?;
+        let _ = self.literal(':');
+        let minutes = self.number(2, false)?;
+        Some(sign * (hours * 3600 + minutes * 60))
+    }
+}
+
+/// Parse text laid out as `format` into a UTC timestamp. Fields the format
+/// leaves out default to 1970-01-01 00:00:00
+fn parse_timestamp(text: &str, format: &str) -> Result<i64, String> {
+    let mismatch = || format!("'{}' does not match format '{}'", text, format);
+    let mut date = DateTime { year: 1970, month: 1, day: 1, hour: 0, minute: 0, second: 0 };
+    let mut offset = 0;
+    let mut parser = DateParser { text, pos: 0 };
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            parser.literal(c).ok_or_else(mismatch)?;
+            continue;
+        }
+        let parsed = match chars.next() {
+            Some('Y') => parser.number(4, true).map(|y| date.year = y),
+            Some('m') => parser.number(2, false).map(|m| date.month = m),
+            Some('d') => parser.number(2, false).map(|d| date.day = d),
+            Some('H') => parser.number(2, false).map(|h| date.hour = h),
+            Some('M') => parser.number(2, false).map(|m| date.minute = m),
+            Some('S') => parser.number(2, false).map(|s| date.second = s),
+            Some('F') => (|| {
+                date.year = parser.number(4, true)?;
+                parser.literal('-')?;
+                date.month = parser.number(2, false)?;
+                parser.literal('-')?;
+                date.day = parser.number(2, false)?;
+                Some(())
+            })(),
+            Some('T') => (|| {
+                date.hour = parser.number(2, false)?;
+                parser.literal(':')?;
+                date.minute = parser.number(2, false)?;
+                parser.literal(':')?;
+                date.second = parser.number(2, false)?;
+                Some(())
+            })(),
+            // The weekday follows from the date, so it is only checked to be a name
+            Some('A') | Some('a') => parser.name(&WEEKDAYS).map(|_| ()),
+            Some('B') | Some('b') => parser.name(&MONTHS).map(|m| date.month = m as i64 + 1),
+            Some('z') => parser.utc_offset().map(|o| offset = o),
+            Some('%') => parser.literal('%'),
+            Some(other) => return Err(format!("unsupported directive '%{}' in format '{}'", other, format)),
+            None => return Err(format!("format '{}' ends with a lone '%'", format)),
+        };
+        parsed.ok_or_else(mismatch)?;
+    }
+    if parser.pos != text.len() {
+        return Err(mismatch());
+    }
+
+    if !(1..=12).contains(&date.month) || !(1..=days_in_month(date.year, date.month)).contains(&date.day) {
+        return Err(format!("'{}' is not a valid date", text));
+    }
+    if date.hour > 23 || date.minute > 59 || date.second > 59 {
+        return Err(format!("'{}' is not a valid time of day", text));
+    }
+    Ok(date.timestamp() - offset)
+}
+
+/// Seconds since 1970-01-01 00:00:00 UTC
+#[no_mangle]
+pub extern "C" fn time_now() -> i64 {
+    since_epoch().0
+}
+
+/// Milliseconds since 1970-01-01 00:00:00 UTC
+#[no_mangle]
+pub extern "C" fn time_now_ms() -> i64 {
+    since_epoch().1
+}
+
+/// Seconds on a clock that never goes backwards, for measuring durations.
+/// Only differences between readings are meaningful
+#[no_mangle]
+pub extern "C" fn time_monotonic() -> f64 {
+    MONOTONIC_START.elapsed().as_secs_f64()
+}
+
+/// Pause the program for `ms` milliseconds
+/// Raises ValueError if ms is negative
+#[no_mangle]
+pub extern "C" fn time_sleep_ms(ms: i64) {
+    if ms < 0 {
+        raise("ValueError", format!("sleep() milliseconds must not be negative, got {}", ms));
+    }
+    std::thread::sleep(Duration::from_millis(ms as u64));
+}
+
+/// Format a timestamp (seconds, UTC) as a new string
+/// Raises ValueError for an unknown directive
+#[no_mangle]
+pub extern "C" fn time_format(seconds: i64, format: *const u8) -> *mut u8 {
+    let format = String::from_utf8_lossy(unsafe { as_bytes(format) }).into_owned();
+    match format_timestamp(seconds, &format) {
+        Ok(text) => unsafe { alloc_str(text.as_bytes()) },
+        Err(e) => raise("ValueError", format!("format_time() {}", e)),
+    }
+}
+
+/// Parse a date into a timestamp (seconds, UTC)
+/// Raises ValueError if the text doesn't match the format or isn't a real date
+#[no_mangle]
+pub extern "C" fn time_parse(text: *const u8, format: *const u8) -> i64 {
+    let text = String::from_utf8_lossy(unsafe { as_bytes(text) }).into_owned();
+    let format = String::from_utf8_lossy(unsafe { as_bytes(format) }).into_owned();
+    match parse_timestamp(&text, &format) {
+        Ok(seconds) => seconds,
+        Err(e) => raise("ValueError", format!("parse_time() {}", e)),
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_days_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        for days in (-800_000..800_000).step_by(997) {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        // 2024-02-29 13:05:09 UTC, a Thursday
+        let leap_day = 1_709_211_909;
+        assert_eq!(format_timestamp(leap_day, "%Y-%m-%dT%H:%M:%SZ").unwrap(), "2024-02-29T13:05:09Z");
+        assert_eq!(format_timestamp(leap_day, "%a %d %b %Y, day %j").unwrap(), "Thu 29 Feb 2024, day 060");
+        assert_eq!(format_timestamp(leap_day, "%A %B %F %T %z %s %%").unwrap(),
+            "Thursday February 2024-02-29 13:05:09 +0000 1709211909 %");
+        assert_eq!(format_timestamp(-1, "%F %T").unwrap(), "1969-12-31 23:59:59");
+        assert!(format_timestamp(0, "%q").unwrap_err().contains("unknown directive '%q'"));
+        assert!(format_timestamp(0, "100%").unwrap_err().contains("lone '%'"));
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("2024-02-29T13:05:09Z", "%Y-%m-%dT%H:%M:%SZ"), Ok(1_709_211_909));
+        assert_eq!(parse_timestamp("2024-02-29 14:05:09 +01:00", "%F %T %z"), Ok(1_709_211_909));
+        assert_eq!(parse_timestamp("thu 29 FEB 2024", "%a %d %b %Y"), Ok(1_709_164_800));
+        assert_eq!(parse_timestamp("1 March 2024", "%d %B %Y"), Ok(1_709_251_200));
+        assert_eq!(parse_timestamp("12:30", "%H:%M"), Ok(45_000));
+
+        assert!(parse_timestamp("2023-02-29", "%F").unwrap_err().contains("not a valid date"));
+        assert!(parse_timestamp("24:00", "%H:%M").unwrap_err().contains("not a valid time"));
+        assert!(parse_timestamp("2024-01-01x", "%F").unwrap_err().contains("does not match"));
+        assert!(parse_timestamp("2024/01/01", "%F").unwrap_err().contains("does not match"));
+        assert!(parse_timestamp("5", "%j").unwrap_err().contains("unsupported directive '%j'"));
+    }
+
+    #[test]
+    fn test_clocks() {
+        let (seconds, millis) = since_epoch();
+        assert!(seconds > 1_700_000_000);
+        assert!((millis / 1000 - seconds).abs() <= 1);
+
+        let start = time_monotonic();
+        time_sleep_ms(20);
+        let elapsed = time_monotonic() - start;
+        assert!(elapsed >= 0.02, "slept {}s", elapsed);
+    }
+}
@@ -0,0 +1,221 @@
+//! Stack traces and source snippets for runtime errors and unhandled exceptions
+
+use backtrace::Backtrace;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use super::source_map;
+use super::CALL_STACK;
+
+/// (file, line) of every WadeScript frame on the stack, innermost first, from
+/// the program's source map if it has one, else from the debug info codegen
+/// attaches to each function
+pub(crate) fn ws_frames() -> Vec<(String, u32)> {
+    let mut bt = Backtrace::new_unresolved();
+    if let Some(frames) = source_map::ws_frames(bt.frames().iter().map(|frame| frame.ip() as usize)) {
+        return frames;
+    }
+    bt.resolve();
+
+    let mut ws_frames: Vec<(String, u32)> = Vec::new();
+    for frame in bt.frames() {
+        for symbol in frame.symbols() {
+            let Some(filename) = symbol.filename().and_then(|f| f.to_str()) else {
+                continue;
+            };
+            if filename.ends_with(".ws") {
+                if let Some(line) = symbol.lineno() {
+                    ws_frames.push((filename.to_string(), line));
+                    break; // Only take first .ws symbol per frame
+                }
+            }
+        }
+    }
+    ws_frames
+}
+
+/// The source line at `file:line` with the code on it underlined:
+///
+/// ```text
+///   --> app.ws:5
+///    |
+///  5 |     raise ValueError("bad")
+///    |     ^^^^^^^^^^^^^^^^^^^^^^^
+/// ```
+///
+/// None if the file can no longer be read or has no such line.
+pub(crate) fn source_snippet(file: &str, line: usize) -> Option<String> {
+    let source = fs::read_to_string(file).ok()?;
+    let text = source.lines().nth(line.checked_sub(1)?)?.trim_end();
+    let code = text.trim_start();
+    if code.is_empty() {
+        return None;
+    }
+
+    let indent = &text[..text.len() - code.len()];
+    let gutter = " ".repeat(line.to_string().len());
+    Some(format!(
+        "{gutter}--> {file}:{line}\n{gutter} |\n{line} | {text}\n{gutter} | {indent}{carets}",
+        carets = "^".repeat(code.chars().count())
+    ))
+}
+
+/// How much of a fatal error is reported, from WS_BACKTRACE
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TraceMode {
+    /// `0`: only the error message
+    Message,
+    /// `1`, the default: the WadeScript call stack and the source line
+    Stack,
+    /// `full`: the call stack and source line, then every native frame
+    Full,
+}
+
+impl TraceMode {
+    fn parse(value: Option<&str>) -> TraceMode {
+        match value.map(str::trim) {
+            Some("0") => TraceMode::Message,
+            Some(value) if value.eq_ignore_ascii_case("full") => TraceMode::Full,
+            _ => TraceMode::Stack,
+        }
+    }
+}
+
+fn paint(color: bool, code: &str, text: &str) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// The report for a fatal error: `title` and the first line of `lines` as the
+/// headline, the remaining lines (exception causes), then as much trace as `mode` asks
+fn render_report(
+    title: &str,
+    lines: &[String],
+    location: Option<(&str, usize)>,
+    mode: TraceMode,
+    color: bool,
+) -> String {
+    let mut report = format!("\n{} {}\n", paint(color, "31;1", title), lines.first().map_or("", String::as_str));
+    for line in lines.iter().skip(1) {
+        report.push_str(line);
+        report.push('\n');
+    }
+    if mode == TraceMode::Message {
+        return report;
+    }
+
+    let frames = ws_frames();
+    if !frames.is_empty() {
+        report.push_str(&format!("\n{}\n", paint(color, "36;1", "Stack trace:")));
+        for (file, line) in &frames {
+            report.push_str(&format!("  at {}:{}\n", file, line));
+        }
+    } else if let Ok(stack) = CALL_STACK.lock() {
+        // Fallback to manual call stack if no debug info found
+        if !stack.is_empty() {
+            report.push_str(&format!("\n{}\n", paint(color, "36;1", "Call stack:")));
+            for (i, func) in stack.iter().rev().enumerate() {
+                report.push_str(&format!("  {} {}\n", paint(color, "90", &format!("{}.", i + 1)), func));
+            }
+        }
+    }
+
+    let innermost = frames.first().map(|(file, line)| (file.as_str(), *line as usize));
+    if let Some(snippet) = location.filter(|(_, line)| *line > 0).or(innermost)
+        .and_then(|(file, line)| source_snippet(file, line))
+    {
+        report.push_str(&format!("\n{}\n", snippet));
+    }
+
+    if mode == TraceMode::Full {
+        report.push_str(&format!("\n{}\n{:?}\n", paint(color, "36;1", "Native backtrace:"), Backtrace::new()));
+    }
+    report
+}
+
+/// Report a fatal error (a runtime error or an unhandled exception). `location`
+/// is where it was raised, if known; otherwise the innermost frame's line is shown.
+/// WS_BACKTRACE picks how much is printed; WS_ERROR_LOG names a file the
+/// report is appended to instead, leaving only the headline on stderr.
+pub(crate) fn report_fatal(title: &str, lines: &[String], location: Option<(&str, usize)>) {
+    let mode = TraceMode::parse(env::var("WS_BACKTRACE").ok().as_deref());
+    let Some(log_path) = env::var_os("WS_ERROR_LOG").filter(|path| !path.is_empty()) else {
+        eprint!("{}", render_report(title, lines, location, mode, true));
+        return;
+    };
+
+    let report = render_report(title, lines, location, mode, false);
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .and_then(|mut log| log.write_all(report.as_bytes()));
+    let headline = lines.first().map_or("", String::as_str);
+    match written {
+        Ok(()) => eprintln!("{} {} (details in {})", paint(true, "31;1", title), headline, log_path.to_string_lossy()),
+        Err(e) => {
+            eprint!("{}", render_report(title, lines, location, mode, true));
+            eprintln!("(could not write to WS_ERROR_LOG {}: {})", log_path.to_string_lossy(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_snippet_underlines_the_code() {
+        let path = std::env::temp_dir().join("ws_traceback_snippet.ws");
+        fs::write(&path, "def main() -> int {\n    raise ValueError(\"bad\")  \n\n}\n").unwrap();
+        let file = path.to_str().unwrap();
+
+        let snippet = source_snippet(file, 2).unwrap();
+        let expected = format!(
+            " --> {}:2\n  |\n2 |     raise ValueError(\"bad\")\n  |     ^^^^^^^^^^^^^^^^^^^^^^^",
+            file
+        );
+        assert_eq!(snippet, expected);
+
+        // Blank lines, missing lines, and line 0 have nothing to show
+        assert!(source_snippet(file, 3).is_none());
+        assert!(source_snippet(file, 99).is_none());
+        assert!(source_snippet(file, 0).is_none());
+        assert!(source_snippet("/nonexistent/file.ws", 1).is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_trace_mode_from_env_value() {
+        assert_eq!(TraceMode::parse(None), TraceMode::Stack);
+        assert_eq!(TraceMode::parse(Some("1")), TraceMode::Stack);
+        assert_eq!(TraceMode::parse(Some("0")), TraceMode::Message);
+        assert_eq!(TraceMode::parse(Some("full")), TraceMode::Full);
+        assert_eq!(TraceMode::parse(Some("FULL")), TraceMode::Full);
+    }
+
+    #[test]
+    fn test_render_report_modes() {
+        let path = std::env::temp_dir().join("ws_traceback_report.ws");
+        fs::write(&path, "def main() -> int {\n    raise ValueError(\"bad\")\n}\n").unwrap();
+        let file = path.to_str().unwrap();
+        let lines = vec!["ValueError - bad".to_string(), "  caused by: KeyError - k".to_string()];
+
+        let report = render_report("Unhandled Exception:", &lines, Some((file, 2)), TraceMode::Message, false);
+        assert_eq!(report, "\nUnhandled Exception: ValueError - bad\n  caused by: KeyError - k\n");
+
+        let report = render_report("Unhandled Exception:", &lines, Some((file, 2)), TraceMode::Stack, false);
+        assert!(report.contains("2 |     raise ValueError(\"bad\")"), "{}", report);
+        assert!(!report.contains("Native backtrace:"), "{}", report);
+        assert!(!report.contains('\x1b'), "{}", report);
+
+        let report = render_report("Runtime Error:", &lines[..1], None, TraceMode::Full, true);
+        assert!(report.starts_with("\n\x1b[31;1mRuntime Error:\x1b[0m ValueError - bad\n"), "{}", report);
+        assert!(report.contains("Native backtrace:"), "{}", report);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -21,11 +21,36 @@ pub struct RuntimeSymbol {
 /// This is the single source of truth for runtime functions
 pub fn get_runtime_symbols() -> Vec<RuntimeSymbol> {
     // Import from each submodule explicitly
-    use crate::runtime::list::{list_get_i64, list_push_i64, list_pop_i64, list_set_i64, list_slice_i64};
-    use crate::runtime::dict::{dict_create, dict_set, dict_get, dict_has};
-    use crate::runtime::string::{str_length, str_upper, str_lower, str_contains, str_char_at, str_slice};
-    use crate::runtime::rc::{rc_alloc, rc_retain, rc_release, rc_get_count, rc_is_valid};
-    use crate::runtime::io::{file_open, file_read, file_read_line, file_write, file_close, file_exists};
+    use crate::runtime::list::{
+        list_create, list_free, list_get_i64, list_push_i64, list_pop_i64, list_set_i64, list_slice_i64,
+        list_get_f64, list_push_f64, list_pop_f64, list_set_f64,
+        list_get_ptr, list_push_ptr, list_pop_ptr, list_set_ptr,
+        list_insert_i64, list_insert_f64, list_insert_ptr, list_remove_at_i64, list_remove_at_f64, list_remove_at_ptr,
+        list_index_of_i64, list_index_of_f64, list_index_of_ptr, list_index_of_str, list_clear,
+        list_reverse, list_extend, list_concat,
+        list_sort_i64, list_sort_f64, list_sort_str, list_sort_by,
+    };
+    use crate::runtime::dict::{dict_create, dict_free, dict_set, dict_get, dict_has, dict_set_int, dict_get_int, dict_has_int, dict_keys, dict_values, dict_items,
+        dict_remove, dict_remove_int, dict_pop, dict_pop_int, dict_clear, dict_get_or, dict_get_or_int};
+    use crate::runtime::string::{str_length, str_upper, str_lower, str_contains, str_char_at, str_byte_length, str_chars, str_slice, str_repeat,
+        str_split, str_join, str_strip, str_lstrip, str_rstrip, str_parse_bool, str_replace, str_find, str_starts_with, str_ends_with,
+        str_to_int, str_to_float, int_to_str, str_concat, str_format, str_builder_new, str_builder_append, str_builder_finish,
+        string_builder_new, string_builder_append, string_builder_length, string_builder_build,
+        string_builder_clear, string_builder_free};
+    use crate::runtime::float::{float_to_str, float_format, float_round};
+    use crate::runtime::bigint::{
+        bigint_from_int, bigint_from_str, bigint_to_str, bigint_to_int, bigint_to_float,
+        bigint_add, bigint_sub, bigint_mul, bigint_div, bigint_rem, bigint_neg,
+        bigint_pow, bigint_pow_mod, bigint_cmp, bigint_free,
+    };
+    use crate::runtime::decimal::{
+        decimal_from_int, decimal_from_str, decimal_from_float, decimal_to_str, decimal_to_int,
+        decimal_to_float, decimal_add, decimal_sub, decimal_mul, decimal_div, decimal_neg,
+        decimal_round, decimal_cmp, decimal_free,
+    };
+    use crate::runtime::rc::{rc_alloc, rc_retain, rc_release, rc_free, rc_get_count, rc_is_valid};
+    use crate::runtime::io::{file_open, file_read, file_read_line, file_write, file_close, file_exists, file_seek,
+        file_read_bytes, file_read_lines, file_write_bytes, file_flush, stdin_input, stdin_read_line};
     use crate::runtime::cli::{
         cli_get_argc, cli_get_argv, cli_get_argv_copy, cli_parse_int, cli_parse_bool,
         cli_starts_with, cli_str_eq, cli_after_prefix
@@ -33,28 +58,94 @@ pub fn get_runtime_symbols() -> Vec<RuntimeSymbol> {
     use crate::runtime::exceptions::{
         exception_create, exception_get_current, exception_set_current, exception_clear,
         exception_get_type, exception_get_message, exception_matches,
-        exception_push_handler, exception_pop_handler, exception_raise
+        exception_push_handler, exception_pop_handler, exception_raise,
+        exception_raise_from, exception_reraise
     };
     use crate::runtime::http::{
         http_get, http_get_with_headers, http_post, http_put, http_delete,
         http_patch, http_head, http_response_status, http_response_body,
         http_response_headers, http_response_get_header, http_response_free
     };
+    use crate::runtime::smtp::smtp_send;
+    use crate::runtime::compress::{
+        compress_gzip, compress_gunzip, compress_zlib, compress_unzlib,
+        compress_read_gzip, compress_write_gzip
+    };
+    use crate::runtime::random::{random_uuid4, random_hex_string, random_token_string, random_seed, random_int_between,
+        random_unit_float, list_shuffle};
+    use crate::runtime::fs::{
+        fs_glob, fs_list_dir, fs_mkdir, fs_mkdirs, fs_remove_file, fs_remove_dir, fs_rename, fs_copy, fs_file_size,
+        fs_is_dir, fs_is_file,
+    };
+    use crate::runtime::regex::{regex_compile, regex_is_match, regex_find, regex_find_all, regex_captures, regex_replace,
+        regex_split, regex_free};
+    use crate::runtime::bytes::{bytes_pack_be, bytes_pack_le, bytes_unpack_be, bytes_unpack_le};
+    use crate::runtime::time::{time_now, time_now_ms, time_monotonic, time_sleep_ms, time_format, time_parse};
+    use crate::runtime::os::{env_get, env_set, env_unset};
+    use crate::runtime::process::{process_run, process_exit_code, process_stdout, process_stderr, process_free};
+    use crate::runtime::path::{path_join, path_dirname, path_basename, path_extension, path_normalize, path_absolute};
+    use crate::runtime::csv::{csv_parse, csv_format_row};
+    use crate::runtime::config::{config_parse_toml, config_parse_ini, config_list_items};
+    use crate::runtime::net::{
+        tcp_connect, tcp_listen, tcp_accept, tcp_read, tcp_read_line, tcp_write, tcp_set_timeout, tcp_local_port, tcp_close,
+    };
     use crate::runtime::{push_call_stack, pop_call_stack, runtime_error};
 
     vec![
         // List operations
+        RuntimeSymbol { name: "list_create", addr: list_create as usize },
+        RuntimeSymbol { name: "list_free", addr: list_free as usize },
         RuntimeSymbol { name: "list_get_i64", addr: list_get_i64 as usize },
         RuntimeSymbol { name: "list_push_i64", addr: list_push_i64 as usize },
         RuntimeSymbol { name: "list_pop_i64", addr: list_pop_i64 as usize },
         RuntimeSymbol { name: "list_set_i64", addr: list_set_i64 as usize },
         RuntimeSymbol { name: "list_slice_i64", addr: list_slice_i64 as usize },
+        RuntimeSymbol { name: "list_get_f64", addr: list_get_f64 as usize },
+        RuntimeSymbol { name: "list_push_f64", addr: list_push_f64 as usize },
+        RuntimeSymbol { name: "list_pop_f64", addr: list_pop_f64 as usize },
+        RuntimeSymbol { name: "list_set_f64", addr: list_set_f64 as usize },
+        RuntimeSymbol { name: "list_get_ptr", addr: list_get_ptr as usize },
+        RuntimeSymbol { name: "list_push_ptr", addr: list_push_ptr as usize },
+        RuntimeSymbol { name: "list_pop_ptr", addr: list_pop_ptr as usize },
+        RuntimeSymbol { name: "list_set_ptr", addr: list_set_ptr as usize },
+        RuntimeSymbol { name: "list_insert_i64", addr: list_insert_i64 as usize },
+        RuntimeSymbol { name: "list_insert_f64", addr: list_insert_f64 as usize },
+        RuntimeSymbol { name: "list_insert_ptr", addr: list_insert_ptr as usize },
+        RuntimeSymbol { name: "list_remove_at_i64", addr: list_remove_at_i64 as usize },
+        RuntimeSymbol { name: "list_remove_at_f64", addr: list_remove_at_f64 as usize },
+        RuntimeSymbol { name: "list_remove_at_ptr", addr: list_remove_at_ptr as usize },
+        RuntimeSymbol { name: "list_index_of_i64", addr: list_index_of_i64 as usize },
+        RuntimeSymbol { name: "list_index_of_f64", addr: list_index_of_f64 as usize },
+        RuntimeSymbol { name: "list_index_of_ptr", addr: list_index_of_ptr as usize },
+        RuntimeSymbol { name: "list_index_of_str", addr: list_index_of_str as usize },
+        RuntimeSymbol { name: "list_clear", addr: list_clear as usize },
+        RuntimeSymbol { name: "list_reverse", addr: list_reverse as usize },
+        RuntimeSymbol { name: "list_extend", addr: list_extend as usize },
+        RuntimeSymbol { name: "list_concat", addr: list_concat as usize },
+        RuntimeSymbol { name: "list_sort_i64", addr: list_sort_i64 as usize },
+        RuntimeSymbol { name: "list_sort_f64", addr: list_sort_f64 as usize },
+        RuntimeSymbol { name: "list_sort_str", addr: list_sort_str as usize },
+        RuntimeSymbol { name: "list_sort_by", addr: list_sort_by as usize },
 
         // Dict operations
         RuntimeSymbol { name: "dict_create", addr: dict_create as usize },
+        RuntimeSymbol { name: "dict_free", addr: dict_free as usize },
         RuntimeSymbol { name: "dict_set", addr: dict_set as usize },
         RuntimeSymbol { name: "dict_get", addr: dict_get as usize },
         RuntimeSymbol { name: "dict_has", addr: dict_has as usize },
+        RuntimeSymbol { name: "dict_set_int", addr: dict_set_int as usize },
+        RuntimeSymbol { name: "dict_get_int", addr: dict_get_int as usize },
+        RuntimeSymbol { name: "dict_has_int", addr: dict_has_int as usize },
+        RuntimeSymbol { name: "dict_keys", addr: dict_keys as usize },
+        RuntimeSymbol { name: "dict_values", addr: dict_values as usize },
+        RuntimeSymbol { name: "dict_items", addr: dict_items as usize },
+        RuntimeSymbol { name: "dict_remove", addr: dict_remove as usize },
+        RuntimeSymbol { name: "dict_remove_int", addr: dict_remove_int as usize },
+        RuntimeSymbol { name: "dict_pop", addr: dict_pop as usize },
+        RuntimeSymbol { name: "dict_pop_int", addr: dict_pop_int as usize },
+        RuntimeSymbol { name: "dict_clear", addr: dict_clear as usize },
+        RuntimeSymbol { name: "dict_get_or", addr: dict_get_or as usize },
+        RuntimeSymbol { name: "dict_get_or_int", addr: dict_get_or_int as usize },
 
         // String operations
         RuntimeSymbol { name: "str_length", addr: str_length as usize },
@@ -62,12 +153,78 @@ pub fn get_runtime_symbols() -> Vec<RuntimeSymbol> {
         RuntimeSymbol { name: "str_lower", addr: str_lower as usize },
         RuntimeSymbol { name: "str_contains", addr: str_contains as usize },
         RuntimeSymbol { name: "str_char_at", addr: str_char_at as usize },
+        RuntimeSymbol { name: "str_byte_length", addr: str_byte_length as usize },
+        RuntimeSymbol { name: "str_chars", addr: str_chars as usize },
         RuntimeSymbol { name: "str_slice", addr: str_slice as usize },
+        RuntimeSymbol { name: "str_repeat", addr: str_repeat as usize },
+        RuntimeSymbol { name: "str_split", addr: str_split as usize },
+        RuntimeSymbol { name: "str_join", addr: str_join as usize },
+        RuntimeSymbol { name: "str_strip", addr: str_strip as usize },
+        RuntimeSymbol { name: "str_lstrip", addr: str_lstrip as usize },
+        RuntimeSymbol { name: "str_rstrip", addr: str_rstrip as usize },
+        RuntimeSymbol { name: "str_parse_bool", addr: str_parse_bool as usize },
+        RuntimeSymbol { name: "str_to_int", addr: str_to_int as usize },
+        RuntimeSymbol { name: "str_to_float", addr: str_to_float as usize },
+        RuntimeSymbol { name: "int_to_str", addr: int_to_str as usize },
+        RuntimeSymbol { name: "str_replace", addr: str_replace as usize },
+        RuntimeSymbol { name: "str_find", addr: str_find as usize },
+        RuntimeSymbol { name: "str_starts_with", addr: str_starts_with as usize },
+        RuntimeSymbol { name: "str_ends_with", addr: str_ends_with as usize },
+        RuntimeSymbol { name: "str_concat", addr: str_concat as usize },
+        RuntimeSymbol { name: "str_format", addr: str_format as usize },
+        RuntimeSymbol { name: "str_builder_new", addr: str_builder_new as usize },
+        RuntimeSymbol { name: "str_builder_append", addr: str_builder_append as usize },
+        RuntimeSymbol { name: "str_builder_finish", addr: str_builder_finish as usize },
+        RuntimeSymbol { name: "string_builder_new", addr: string_builder_new as usize },
+        RuntimeSymbol { name: "string_builder_append", addr: string_builder_append as usize },
+        RuntimeSymbol { name: "string_builder_length", addr: string_builder_length as usize },
+        RuntimeSymbol { name: "string_builder_build", addr: string_builder_build as usize },
+        RuntimeSymbol { name: "string_builder_clear", addr: string_builder_clear as usize },
+        RuntimeSymbol { name: "string_builder_free", addr: string_builder_free as usize },
+
+        // Float operations
+        RuntimeSymbol { name: "float_to_str", addr: float_to_str as usize },
+        RuntimeSymbol { name: "float_format", addr: float_format as usize },
+        RuntimeSymbol { name: "float_round", addr: float_round as usize },
+
+        // Bigint operations
+        RuntimeSymbol { name: "bigint_from_int", addr: bigint_from_int as usize },
+        RuntimeSymbol { name: "bigint_from_str", addr: bigint_from_str as usize },
+        RuntimeSymbol { name: "bigint_to_str", addr: bigint_to_str as usize },
+        RuntimeSymbol { name: "bigint_to_int", addr: bigint_to_int as usize },
+        RuntimeSymbol { name: "bigint_to_float", addr: bigint_to_float as usize },
+        RuntimeSymbol { name: "bigint_add", addr: bigint_add as usize },
+        RuntimeSymbol { name: "bigint_sub", addr: bigint_sub as usize },
+        RuntimeSymbol { name: "bigint_mul", addr: bigint_mul as usize },
+        RuntimeSymbol { name: "bigint_div", addr: bigint_div as usize },
+        RuntimeSymbol { name: "bigint_rem", addr: bigint_rem as usize },
+        RuntimeSymbol { name: "bigint_neg", addr: bigint_neg as usize },
+        RuntimeSymbol { name: "bigint_pow", addr: bigint_pow as usize },
+        RuntimeSymbol { name: "bigint_pow_mod", addr: bigint_pow_mod as usize },
+        RuntimeSymbol { name: "bigint_cmp", addr: bigint_cmp as usize },
+        RuntimeSymbol { name: "bigint_free", addr: bigint_free as usize },
+
+        // Decimal operations
+        RuntimeSymbol { name: "decimal_from_int", addr: decimal_from_int as usize },
+        RuntimeSymbol { name: "decimal_from_str", addr: decimal_from_str as usize },
+        RuntimeSymbol { name: "decimal_from_float", addr: decimal_from_float as usize },
+        RuntimeSymbol { name: "decimal_to_str", addr: decimal_to_str as usize },
+        RuntimeSymbol { name: "decimal_to_int", addr: decimal_to_int as usize },
+        RuntimeSymbol { name: "decimal_to_float", addr: decimal_to_float as usize },
+        RuntimeSymbol { name: "decimal_add", addr: decimal_add as usize },
+        RuntimeSymbol { name: "decimal_sub", addr: decimal_sub as usize },
+        RuntimeSymbol { name: "decimal_mul", addr: decimal_mul as usize },
+        RuntimeSymbol { name: "decimal_div", addr: decimal_div as usize },
+        RuntimeSymbol { name: "decimal_neg", addr: decimal_neg as usize },
+        RuntimeSymbol { name: "decimal_round", addr: decimal_round as usize },
+        RuntimeSymbol { name: "decimal_cmp", addr: decimal_cmp as usize },
+        RuntimeSymbol { name: "decimal_free", addr: decimal_free as usize },
 
         // RC operations
         RuntimeSymbol { name: "rc_alloc", addr: rc_alloc as usize },
         RuntimeSymbol { name: "rc_retain", addr: rc_retain as usize },
         RuntimeSymbol { name: "rc_release", addr: rc_release as usize },
+        RuntimeSymbol { name: "rc_free", addr: rc_free as usize },
         RuntimeSymbol { name: "rc_get_count", addr: rc_get_count as usize },
         RuntimeSymbol { name: "rc_is_valid", addr: rc_is_valid as usize },
 
@@ -78,6 +235,15 @@ pub fn get_runtime_symbols() -> Vec<RuntimeSymbol> {
         RuntimeSymbol { name: "file_write", addr: file_write as usize },
         RuntimeSymbol { name: "file_close", addr: file_close as usize },
         RuntimeSymbol { name: "file_exists", addr: file_exists as usize },
+        RuntimeSymbol { name: "file_seek", addr: file_seek as usize },
+        RuntimeSymbol { name: "file_read_bytes", addr: file_read_bytes as usize },
+        RuntimeSymbol { name: "file_read_lines", addr: file_read_lines as usize },
+        RuntimeSymbol { name: "file_write_bytes", addr: file_write_bytes as usize },
+        RuntimeSymbol { name: "file_flush", addr: file_flush as usize },
+
+        // Standard input
+        RuntimeSymbol { name: "stdin_input", addr: stdin_input as usize },
+        RuntimeSymbol { name: "stdin_read_line", addr: stdin_read_line as usize },
 
         // CLI operations
         RuntimeSymbol { name: "cli_get_argc", addr: cli_get_argc as usize },
@@ -100,6 +266,8 @@ pub fn get_runtime_symbols() -> Vec<RuntimeSymbol> {
         RuntimeSymbol { name: "exception_push_handler", addr: exception_push_handler as usize },
         RuntimeSymbol { name: "exception_pop_handler", addr: exception_pop_handler as usize },
         RuntimeSymbol { name: "exception_raise", addr: exception_raise as usize },
+        RuntimeSymbol { name: "exception_raise_from", addr: exception_raise_from as usize },
+        RuntimeSymbol { name: "exception_reraise", addr: exception_reraise as usize },
 
         // Call stack functions
         RuntimeSymbol { name: "push_call_stack", addr: push_call_stack as usize },
@@ -120,6 +288,103 @@ pub fn get_runtime_symbols() -> Vec<RuntimeSymbol> {
         RuntimeSymbol { name: "http_response_get_header", addr: http_response_get_header as usize },
         RuntimeSymbol { name: "http_response_free", addr: http_response_free as usize },
 
+        // SMTP functions
+        RuntimeSymbol { name: "smtp_send", addr: smtp_send as usize },
+
+        // Compression functions
+        RuntimeSymbol { name: "compress_gzip", addr: compress_gzip as usize },
+        RuntimeSymbol { name: "compress_gunzip", addr: compress_gunzip as usize },
+        RuntimeSymbol { name: "compress_zlib", addr: compress_zlib as usize },
+        RuntimeSymbol { name: "compress_unzlib", addr: compress_unzlib as usize },
+        RuntimeSymbol { name: "compress_read_gzip", addr: compress_read_gzip as usize },
+        RuntimeSymbol { name: "compress_write_gzip", addr: compress_write_gzip as usize },
+
+        // Random identifier and number functions
+        RuntimeSymbol { name: "random_uuid4", addr: random_uuid4 as usize },
+        RuntimeSymbol { name: "random_hex_string", addr: random_hex_string as usize },
+        RuntimeSymbol { name: "random_token_string", addr: random_token_string as usize },
+        RuntimeSymbol { name: "random_seed", addr: random_seed as usize },
+        RuntimeSymbol { name: "random_int_between", addr: random_int_between as usize },
+        RuntimeSymbol { name: "random_unit_float", addr: random_unit_float as usize },
+        RuntimeSymbol { name: "list_shuffle", addr: list_shuffle as usize },
+
+        // File system functions
+        RuntimeSymbol { name: "fs_glob", addr: fs_glob as usize },
+        RuntimeSymbol { name: "fs_list_dir", addr: fs_list_dir as usize },
+        RuntimeSymbol { name: "fs_mkdir", addr: fs_mkdir as usize },
+        RuntimeSymbol { name: "fs_mkdirs", addr: fs_mkdirs as usize },
+        RuntimeSymbol { name: "fs_remove_file", addr: fs_remove_file as usize },
+        RuntimeSymbol { name: "fs_remove_dir", addr: fs_remove_dir as usize },
+        RuntimeSymbol { name: "fs_rename", addr: fs_rename as usize },
+        RuntimeSymbol { name: "fs_copy", addr: fs_copy as usize },
+        RuntimeSymbol { name: "fs_file_size", addr: fs_file_size as usize },
+        RuntimeSymbol { name: "fs_is_dir", addr: fs_is_dir as usize },
+        RuntimeSymbol { name: "fs_is_file", addr: fs_is_file as usize },
+
+        // Regular expression functions
+        RuntimeSymbol { name: "regex_compile", addr: regex_compile as usize },
+        RuntimeSymbol { name: "regex_is_match", addr: regex_is_match as usize },
+        RuntimeSymbol { name: "regex_find", addr: regex_find as usize },
+        RuntimeSymbol { name: "regex_find_all", addr: regex_find_all as usize },
+        RuntimeSymbol { name: "regex_captures", addr: regex_captures as usize },
+        RuntimeSymbol { name: "regex_replace", addr: regex_replace as usize },
+        RuntimeSymbol { name: "regex_split", addr: regex_split as usize },
+        RuntimeSymbol { name: "regex_free", addr: regex_free as usize },
+
+        // Byte-order functions
+        RuntimeSymbol { name: "bytes_pack_be", addr: bytes_pack_be as usize },
+        RuntimeSymbol { name: "bytes_pack_le", addr: bytes_pack_le as usize },
+        RuntimeSymbol { name: "bytes_unpack_be", addr: bytes_unpack_be as usize },
+        RuntimeSymbol { name: "bytes_unpack_le", addr: bytes_unpack_le as usize },
+
+        // Clock and date functions
+        RuntimeSymbol { name: "time_now", addr: time_now as usize },
+        RuntimeSymbol { name: "time_now_ms", addr: time_now_ms as usize },
+        RuntimeSymbol { name: "time_monotonic", addr: time_monotonic as usize },
+        RuntimeSymbol { name: "time_sleep_ms", addr: time_sleep_ms as usize },
+        RuntimeSymbol { name: "time_format", addr: time_format as usize },
+        RuntimeSymbol { name: "time_parse", addr: time_parse as usize },
+
+        // Environment variable functions
+        RuntimeSymbol { name: "env_get", addr: env_get as usize },
+        RuntimeSymbol { name: "env_set", addr: env_set as usize },
+        RuntimeSymbol { name: "env_unset", addr: env_unset as usize },
+
+        // Subprocess functions
+        RuntimeSymbol { name: "process_run", addr: process_run as usize },
+        RuntimeSymbol { name: "process_exit_code", addr: process_exit_code as usize },
+        RuntimeSymbol { name: "process_stdout", addr: process_stdout as usize },
+        RuntimeSymbol { name: "process_stderr", addr: process_stderr as usize },
+        RuntimeSymbol { name: "process_free", addr: process_free as usize },
+
+        // Path functions
+        RuntimeSymbol { name: "path_join", addr: path_join as usize },
+        RuntimeSymbol { name: "path_dirname", addr: path_dirname as usize },
+        RuntimeSymbol { name: "path_basename", addr: path_basename as usize },
+        RuntimeSymbol { name: "path_extension", addr: path_extension as usize },
+        RuntimeSymbol { name: "path_normalize", addr: path_normalize as usize },
+        RuntimeSymbol { name: "path_absolute", addr: path_absolute as usize },
+
+        // CSV functions
+        RuntimeSymbol { name: "csv_parse", addr: csv_parse as usize },
+        RuntimeSymbol { name: "csv_format_row", addr: csv_format_row as usize },
+
+        // Config file functions
+        RuntimeSymbol { name: "config_parse_toml", addr: config_parse_toml as usize },
+        RuntimeSymbol { name: "config_parse_ini", addr: config_parse_ini as usize },
+        RuntimeSymbol { name: "config_list_items", addr: config_list_items as usize },
+
+        // TCP socket functions
+        RuntimeSymbol { name: "tcp_connect", addr: tcp_connect as usize },
+        RuntimeSymbol { name: "tcp_listen", addr: tcp_listen as usize },
+        RuntimeSymbol { name: "tcp_accept", addr: tcp_accept as usize },
+        RuntimeSymbol { name: "tcp_read", addr: tcp_read as usize },
+        RuntimeSymbol { name: "tcp_read_line", addr: tcp_read_line as usize },
+        RuntimeSymbol { name: "tcp_write", addr: tcp_write as usize },
+        RuntimeSymbol { name: "tcp_set_timeout", addr: tcp_set_timeout as usize },
+        RuntimeSymbol { name: "tcp_local_port", addr: tcp_local_port as usize },
+        RuntimeSymbol { name: "tcp_close", addr: tcp_close as usize },
+
         // Standard C library functions
         RuntimeSymbol { name: "printf", addr: libc::printf as usize },
         RuntimeSymbol { name: "malloc", addr: libc::malloc as usize },
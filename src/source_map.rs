@@ -0,0 +1,151 @@
+//! Writing the source map of an optimized program
+//!
+//! After linking, the DWARF line table and inline info of the executable
+//! (or of its .dSYM bundle on macOS) are flattened into the `.wsmap`
+//! sidecar that runtime traces read; the format and the runtime side are in
+//! runtime/source_map.rs.
+
+use addr2line::gimli;
+use object::{Object, ObjectSection, ObjectSegment};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::runtime::source_map::{code_hash, sidecar_path, SourceMap};
+
+/// The file holding the executable's DWARF: its .dSYM bundle after dsymutil
+/// has run, else the executable itself
+fn debug_file(exe: &Path) -> PathBuf {
+    let name = exe.file_name().unwrap_or_default();
+    let mut bundle = exe.as_os_str().to_owned();
+    bundle.push(".dSYM");
+    let dsym = Path::new(&bundle).join("Contents/Resources/DWARF").join(name);
+    if dsym.exists() {
+        dsym
+    } else {
+        exe.to_path_buf()
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("Cannot read '{}': {}", path.display(), e))
+}
+
+fn parse<'data>(path: &Path, data: &'data [u8]) -> Result<object::File<'data>, String> {
+    object::File::parse(data).map_err(|e| format!("Cannot parse '{}': {}", path.display(), e))
+}
+
+/// Start and end address of a range of code and its file:line chain,
+/// innermost first
+type Range = (u64, u64, Vec<(String, u32)>);
+
+/// Each line table range of WadeScript code, at the addresses the debug info gives
+fn ws_ranges(path: &Path, debug: &object::File) -> Result<Vec<Range>, String> {
+    let endian = if debug.is_little_endian() {
+        gimli::RunTimeEndian::Little
+    } else {
+        gimli::RunTimeEndian::Big
+    };
+    let dwarf = gimli::Dwarf::load(|id| -> Result<_, gimli::Error> {
+        let data = debug.section_by_name(id.name()).and_then(|section| section.data().ok()).unwrap_or(&[]);
+        Ok(gimli::EndianSlice::new(data, endian))
+    })
+    .map_err(|e| format!("Cannot read the debug info of '{}': {}", path.display(), e))?;
+    let context = addr2line::Context::from_dwarf(dwarf)
+        .map_err(|e| format!("Cannot read the debug info of '{}': {}", path.display(), e))?;
+    let read_error = |e: gimli::Error| format!("Cannot read the line table of '{}': {}", path.display(), e);
+
+    let mut ranges = Vec::new();
+    for (start, length, location) in context.find_location_range(0, u64::MAX).map_err(read_error)? {
+        // Only WadeScript is inlined into WadeScript, so the innermost line tells
+        if !location.file.is_some_and(|file| file.ends_with(".ws")) {
+            continue;
+        }
+        let mut chain = Vec::new();
+        let mut frames = context.find_frames(start).skip_all_loads().map_err(read_error)?;
+        while let Some(frame) = frames.next().map_err(read_error)? {
+            let Some(location) = frame.location else { continue };
+            if let (Some(file), Some(line)) = (location.file, location.line) {
+                if file.ends_with(".ws") {
+                    chain.push((file.to_string(), line));
+                }
+            }
+        }
+        ranges.push((start, start + length, chain));
+    }
+    Ok(ranges)
+}
+
+/// Build the source map of a linked executable
+pub fn build(exe: &Path) -> Result<SourceMap, String> {
+    let exe_data = read(exe)?;
+    let exe_file = parse(exe, &exe_data)?;
+    let debug_path = debug_file(exe);
+    let debug_data = if debug_path == exe { None } else { Some(read(&debug_path)?) };
+    let debug_file = match &debug_data {
+        Some(data) => parse(&debug_path, data)?,
+        None => parse(exe, &exe_data)?,
+    };
+
+    // Offsets are from the start of the first segment mapped from the file,
+    // which is where the loader reports the executable to be
+    let base = exe_file
+        .segments()
+        .filter(|segment| segment.file_range().1 > 0)
+        .map(|segment| segment.address())
+        .min()
+        .ok_or_else(|| format!("'{}' has no loadable segments", exe.display()))?;
+
+    let mut ranges = ws_ranges(&debug_path, &debug_file)?;
+    ranges.sort_by_key(|(start, _, _)| *start);
+
+    let mut map = SourceMap::default();
+    let mut file_indexes: HashMap<String, usize> = HashMap::new();
+    let mut covered = 0;
+    for (start, end, chain) in ranges {
+        // Ranges of functions the linker dropped sit below the base or overlap
+        let Some(start) = start.checked_sub(base).filter(|start| *start >= covered) else {
+            continue;
+        };
+        // A gap since the last range is code that isn't WadeScript
+        if start > covered && !map.rows.is_empty() {
+            map.rows.push((covered, Vec::new()));
+        }
+        let chain: Vec<(usize, u32)> = chain
+            .into_iter()
+            .map(|(file, line)| {
+                let next_index = file_indexes.len();
+                let index = *file_indexes.entry(file.clone()).or_insert(next_index);
+                if index == next_index {
+                    map.files.push(file);
+                }
+                (index, line)
+            })
+            .collect();
+        if map.rows.last().map(|(_, last)| last) != Some(&chain) {
+            map.rows.push((start, chain));
+        }
+        covered = end - base;
+    }
+    if map.rows.is_empty() {
+        return Err(format!("'{}' has no line info for WadeScript code", debug_path.display()));
+    }
+    map.rows.push((covered, Vec::new()));
+
+    // The runtime checks the code it is running against this hash
+    let (start, end) = map.code_range().unwrap();
+    let code = exe_file
+        .segments()
+        .find_map(|segment| segment.data_range(base + start, end - start).ok().flatten())
+        .ok_or_else(|| format!("the WadeScript code of '{}' is not in one segment", exe.display()))?;
+    map.code_hash = code_hash(code);
+    Ok(map)
+}
+
+/// Write the source map of `exe` next to it and return the sidecar's path
+pub fn write(exe: &Path) -> Result<PathBuf, String> {
+    let map = build(exe)?;
+    let path = sidecar_path(exe);
+    fs::write(&path, map.render()).map_err(|e| format!("Cannot write '{}': {}", path.display(), e))?;
+    Ok(path)
+}
@@ -0,0 +1,114 @@
+//! "Did you mean ...?" suggestions for misspelled identifiers
+//!
+//! The type checker appends these to errors about undefined variables,
+//! functions, methods, fields, and modules.
+
+/// Levenshtein edit distance between two strings, counted in characters.
+/// Swapping two adjacent characters counts as one edit (optimal string
+/// alignment), since transpositions like `lenght` are the most common typo.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // dist[i][j] = distance between the first i chars of a and the first j of b
+    let mut dist = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (dist[i - 1][j] + 1)
+                .min(dist[i][j - 1] + 1)
+                .min(dist[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(dist[i - 2][j - 2] + 1);
+            }
+            dist[i][j] = best;
+        }
+    }
+
+    dist[a.len()][b.len()]
+}
+
+/// The candidate closest to `name`, if one is close enough to be a typo.
+/// Allows about one edit per three characters, and never suggests a name
+/// that shares nothing with `name` (e.g. `x` for `y`). Ties are broken
+/// alphabetically so suggestions don't depend on hash map order.
+pub fn closest_match<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let name_len = name.chars().count();
+    let max_distance = (name_len / 3).max(1);
+
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .filter_map(|candidate| {
+            let distance = edit_distance(name, candidate);
+            let longest = name_len.max(candidate.chars().count());
+            if distance <= max_distance && distance < longest {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Suffix for an error message: "; did you mean 'x'?" or an empty string
+pub fn did_you_mean<'a, I>(name: &str, candidates: I) -> String
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    match closest_match(name, candidates) {
+        Some(candidate) => format!("; did you mean '{}'?", candidate),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("count", "count"), 0);
+        // Adjacent transpositions are a single edit
+        assert_eq!(edit_distance("lenght", "length"), 1);
+        assert_eq!(edit_distance("nrom", "norm"), 1);
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let candidates = ["length", "push", "pop", "get"];
+        assert_eq!(closest_match("lenght", candidates), Some("length"));
+        assert_eq!(closest_match("psh", candidates), Some("push"));
+        assert_eq!(closest_match("frobnicate", candidates), None);
+    }
+
+    #[test]
+    fn test_closest_match_rejects_unrelated_short_names() {
+        assert_eq!(closest_match("x", ["y"]), None);
+        assert_eq!(closest_match("x", ["xs"]), Some("xs"));
+    }
+
+    #[test]
+    fn test_closest_match_breaks_ties_alphabetically() {
+        assert_eq!(closest_match("cat", ["hat", "bat"]), Some("bat"));
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        assert_eq!(did_you_mean("prnt", ["print"]), "; did you mean 'print'?");
+        assert_eq!(did_you_mean("zzz", ["print"]), "");
+    }
+}
@@ -0,0 +1,397 @@
+// Where the compiler finds its runtime archive and standard library, and
+// `wadescript install`, which copies the compiler, runtime, and std into a
+// prefix (`~/.wadescript` unless WADESCRIPT_HOME is set):
+//
+//     <prefix>/bin/wadescript
+//     <prefix>/lib/libwadescript_runtime.a
+//     <prefix>/lib/<allocator>/libwadescript_runtime.a (mimalloc/jemalloc builds, if present)
+//     <prefix>/lib/<triple>/libwadescript_runtime.a    (cross targets, copied by hand)
+//     <prefix>/std/*.ws
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const RUNTIME_LIB: &str = "libwadescript_runtime.a";
+
+/// Cargo profile the compiler was built with, which the runtime archive must match
+fn profile() -> &'static str {
+    if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    }
+}
+
+/// Allocator a runtime archive was built with (`--allocator=<name>`). The system
+/// archive is the one `cargo build` makes; the others are built with a cargo
+/// feature into their own target directory, so all of them can sit side by side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Allocator {
+    System,
+    Mimalloc,
+    Jemalloc,
+}
+
+impl Allocator {
+    /// The allocators with an archive of their own
+    pub const ALTERNATIVES: [Allocator; 2] = [Allocator::Mimalloc, Allocator::Jemalloc];
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "system" => Ok(Allocator::System),
+            "mimalloc" => Ok(Allocator::Mimalloc),
+            "jemalloc" => Ok(Allocator::Jemalloc),
+            _ => Err(format!(
+                "Invalid allocator '{}' (expected system, mimalloc, or jemalloc)",
+                value
+            )),
+        }
+    }
+
+    /// Name of the allocator, which is also its cargo feature
+    pub fn name(self) -> &'static str {
+        match self {
+            Allocator::System => "system",
+            Allocator::Mimalloc => "mimalloc",
+            Allocator::Jemalloc => "jemalloc",
+        }
+    }
+
+    /// The cargo command that builds the runtime archive for this allocator
+    pub fn build_command(self, target: Option<&str>) -> String {
+        let mut command = String::from("cargo build --lib");
+        if !cfg!(debug_assertions) {
+            command.push_str(" --release");
+        }
+        if self != Allocator::System {
+            command.push_str(&format!(" --features {} --target-dir target/alloc/{}", self.name(), self.name()));
+        }
+        if let Some(triple) = target {
+            command.push_str(&format!(" --target {}", triple));
+        }
+        command
+    }
+}
+
+/// Install prefix used when none is given: $WADESCRIPT_HOME, else ~/.wadescript
+pub fn default_prefix() -> Option<PathBuf> {
+    if let Some(home) = env::var_os("WADESCRIPT_HOME").filter(|home| !home.is_empty()) {
+        return Some(PathBuf::from(home));
+    }
+    env::var_os("HOME").map(|home| Path::new(&home).join(".wadescript"))
+}
+
+/// Directory of the running compiler
+fn exe_dir() -> Option<PathBuf> {
+    env::current_exe().ok()?.parent().map(Path::to_path_buf)
+}
+
+/// Places to look for the runtime archive, in order: an installed prefix
+/// around the compiler, cargo's target directory the compiler was built in,
+/// `target/` under the working directory, then the default prefix
+pub fn runtime_lib_candidates(
+    exe_dir: Option<&Path>,
+    target: Option<&str>,
+    allocator: Allocator,
+    prefix: Option<&Path>,
+) -> Vec<PathBuf> {
+    let in_prefix = |prefix: &Path| {
+        let mut lib_dir = prefix.join("lib");
+        if allocator != Allocator::System {
+            lib_dir.push(allocator.name());
+        }
+        if let Some(triple) = target {
+            lib_dir.push(triple);
+        }
+        lib_dir.join(RUNTIME_LIB)
+    };
+    let mut candidates = Vec::new();
+    if let Some(exe_dir) = exe_dir {
+        if let Some(installed) = exe_dir.parent() {
+            candidates.push(in_prefix(installed));
+        }
+        // target/<profile>/wadescript sits next to its runtime; cross and
+        // allocator archives are elsewhere under target/
+        match (target, allocator) {
+            (None, Allocator::System) => candidates.push(exe_dir.join(RUNTIME_LIB)),
+            _ => {
+                if let Some(target_dir) = exe_dir.parent() {
+                    candidates.push(cargo_runtime_lib(target_dir, target, allocator));
+                }
+            }
+        }
+    }
+    candidates.push(dev_runtime_lib(target, allocator));
+    if let Some(prefix) = prefix {
+        candidates.push(in_prefix(prefix));
+    }
+    candidates
+}
+
+/// Runtime archive in cargo's layout under a target directory:
+/// `[alloc/<allocator>/][<triple>/]<profile>/libwadescript_runtime.a`
+fn cargo_runtime_lib(target_dir: &Path, target: Option<&str>, allocator: Allocator) -> PathBuf {
+    let mut dir = target_dir.to_path_buf();
+    if allocator != Allocator::System {
+        dir.push("alloc");
+        dir.push(allocator.name());
+    }
+    if let Some(triple) = target {
+        dir.push(triple);
+    }
+    dir.join(profile()).join(RUNTIME_LIB)
+}
+
+/// Runtime archive in cargo's layout under the working directory.
+/// Cross builds use `cargo build --lib --target <triple>`; see
+/// `Allocator::build_command` for the allocator builds.
+pub fn dev_runtime_lib(target: Option<&str>, allocator: Allocator) -> PathBuf {
+    cargo_runtime_lib(Path::new("target"), target, allocator)
+}
+
+/// The runtime archive to link against, or None when no candidate exists
+pub fn find_runtime_lib(target: Option<&str>, allocator: Allocator) -> Option<PathBuf> {
+    runtime_lib_candidates(exe_dir().as_deref(), target, allocator, default_prefix().as_deref())
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+/// Places to look for the standard library, in order: next to the compiler,
+/// an installed prefix around it, the repository it was built in
+/// (target/<profile>/wadescript), the working directory, then the default prefix
+pub fn std_dir_candidates(exe_dir: Option<&Path>, cwd: Option<&Path>, prefix: Option<&Path>) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(exe_dir) = exe_dir {
+        candidates.push(exe_dir.join("std"));
+        if let Some(parent) = exe_dir.parent() {
+            candidates.push(parent.join("std"));
+            if let Some(grandparent) = parent.parent() {
+                candidates.push(grandparent.join("std"));
+            }
+        }
+    }
+    if let Some(cwd) = cwd {
+        candidates.push(cwd.join("std"));
+    }
+    if let Some(prefix) = prefix {
+        candidates.push(prefix.join("std"));
+    }
+    candidates
+}
+
+/// The standard library directory, or None when no candidate exists
+pub fn find_std_dir() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok();
+    std_dir_candidates(exe_dir().as_deref(), cwd.as_deref(), default_prefix().as_deref())
+        .into_iter()
+        .find(|path| path.is_dir())
+}
+
+/// Copy the compiler, runtime archives, and std directory into `prefix`,
+/// returning the installed paths. `allocator_libs` are the archives built for
+/// other allocators, if any. A std directory already there is replaced, so
+/// modules removed in this release don't linger.
+pub fn install(
+    prefix: &Path,
+    compiler: &Path,
+    runtime_lib: &Path,
+    allocator_libs: &[(Allocator, PathBuf)],
+    std_dir: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    let bin_dir = prefix.join("bin");
+    let lib_dir = prefix.join("lib");
+    for dir in [&bin_dir, &lib_dir] {
+        fs::create_dir_all(dir).map_err(|e| format!("Cannot create {:?}: {}", dir, e))?;
+    }
+
+    let installed_compiler = bin_dir.join(compiler.file_name().unwrap_or("wadescript".as_ref()));
+    copy_file(compiler, &installed_compiler)?;
+    let installed_runtime = lib_dir.join(RUNTIME_LIB);
+    copy_file(runtime_lib, &installed_runtime)?;
+    let mut installed = vec![installed_compiler, installed_runtime];
+    for (allocator, archive) in allocator_libs {
+        let dir = lib_dir.join(allocator.name());
+        fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {:?}: {}", dir, e))?;
+        let installed_archive = dir.join(RUNTIME_LIB);
+        copy_file(archive, &installed_archive)?;
+        installed.push(installed_archive);
+    }
+
+    let installed_std = prefix.join("std");
+    if !same_path(std_dir, &installed_std) {
+        if installed_std.exists() {
+            fs::remove_dir_all(&installed_std).map_err(|e| format!("Cannot replace {:?}: {}", installed_std, e))?;
+        }
+        copy_dir(std_dir, &installed_std)?;
+    }
+
+    installed.push(installed_std);
+    Ok(installed)
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Copy through a temporary file and rename it into place, so a running
+/// compiler being replaced keeps its old binary
+fn copy_file(from: &Path, to: &Path) -> Result<(), String> {
+    if same_path(from, to) {
+        return Ok(());
+    }
+    let mut tmp_name = to.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = to.with_file_name(tmp_name);
+    fs::copy(from, &tmp).map_err(|e| format!("Cannot copy {:?} to {:?}: {}", from, tmp, e))?;
+    fs::rename(&tmp, to).map_err(|e| format!("Cannot move {:?} to {:?}: {}", tmp, to, e))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("Cannot create {:?}: {}", to, e))?;
+    let entries = fs::read_dir(from).map_err(|e| format!("Cannot read {:?}: {}", from, e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Cannot read {:?}: {}", from, e))?;
+        let path = entry.path();
+        let dest = to.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &dest)?;
+        } else {
+            fs::copy(&path, &dest).map_err(|e| format!("Cannot copy {:?} to {:?}: {}", path, dest, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_lib_candidates_native() {
+        let candidates = runtime_lib_candidates(
+            Some(Path::new("/opt/ws/bin")),
+            None,
+            Allocator::System,
+            Some(Path::new("/home/u/.wadescript")),
+        );
+        assert_eq!(candidates[0], Path::new("/opt/ws/lib/libwadescript_runtime.a"));
+        assert_eq!(candidates[1], Path::new("/opt/ws/bin/libwadescript_runtime.a"));
+        assert_eq!(candidates[2], dev_runtime_lib(None, Allocator::System));
+        assert_eq!(candidates[3], Path::new("/home/u/.wadescript/lib/libwadescript_runtime.a"));
+    }
+
+    #[test]
+    fn test_runtime_lib_candidates_cross() {
+        let triple = "aarch64-unknown-linux-gnu";
+        let candidates =
+            runtime_lib_candidates(Some(Path::new("/src/ws/target/debug")), Some(triple), Allocator::System, None);
+        assert_eq!(candidates[0], Path::new("/src/ws/target/lib/aarch64-unknown-linux-gnu/libwadescript_runtime.a"));
+        assert_eq!(
+            candidates[1],
+            Path::new("/src/ws/target/aarch64-unknown-linux-gnu").join(profile()).join(RUNTIME_LIB)
+        );
+        assert!(dev_runtime_lib(Some(triple), Allocator::System).starts_with("target/aarch64-unknown-linux-gnu/"));
+        assert_eq!(candidates.len(), 3);
+    }
+
+    #[test]
+    fn test_runtime_lib_candidates_allocator() {
+        let candidates = runtime_lib_candidates(
+            Some(Path::new("/src/ws/target/release")),
+            None,
+            Allocator::Mimalloc,
+            Some(Path::new("/home/u/.wadescript")),
+        );
+        assert_eq!(candidates[0], Path::new("/src/ws/target/lib/mimalloc/libwadescript_runtime.a"));
+        assert_eq!(candidates[1], Path::new("/src/ws/target/alloc/mimalloc").join(profile()).join(RUNTIME_LIB));
+        assert_eq!(candidates[2], Path::new("target/alloc/mimalloc").join(profile()).join(RUNTIME_LIB));
+        assert_eq!(candidates[3], Path::new("/home/u/.wadescript/lib/mimalloc/libwadescript_runtime.a"));
+
+        let cross = dev_runtime_lib(Some("aarch64-unknown-linux-gnu"), Allocator::Jemalloc);
+        assert!(cross.starts_with("target/alloc/jemalloc/aarch64-unknown-linux-gnu/"), "{:?}", cross);
+    }
+
+    #[test]
+    fn test_allocator_parse_and_build_command() {
+        assert_eq!(Allocator::parse("system"), Ok(Allocator::System));
+        assert_eq!(Allocator::parse("jemalloc"), Ok(Allocator::Jemalloc));
+        assert!(Allocator::parse("tcmalloc").unwrap_err().contains("expected system, mimalloc, or jemalloc"));
+
+        let command = Allocator::Mimalloc.build_command(None);
+        assert!(command.starts_with("cargo build --lib"), "{}", command);
+        assert!(command.ends_with("--features mimalloc --target-dir target/alloc/mimalloc"), "{}", command);
+        assert!(!Allocator::System.build_command(None).contains("--features"));
+        assert!(Allocator::System.build_command(Some("x86_64-apple-darwin")).ends_with("--target x86_64-apple-darwin"));
+    }
+
+    #[test]
+    fn test_std_dir_candidates() {
+        let candidates = std_dir_candidates(
+            Some(Path::new("/home/u/.wadescript/bin")),
+            Some(Path::new("/work")),
+            Some(Path::new("/home/u/.wadescript")),
+        );
+        let expected = [
+            "/home/u/.wadescript/bin/std",
+            "/home/u/.wadescript/std",
+            "/home/u/std",
+            "/work/std",
+            "/home/u/.wadescript/std",
+        ];
+        assert_eq!(candidates, expected.iter().map(PathBuf::from).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_install_layout() {
+        let root = env::temp_dir().join(format!("ws_install_{}", std::process::id()));
+        let build = root.join("build");
+        fs::create_dir_all(build.join("std")).unwrap();
+        fs::write(build.join("wadescript"), "compiler").unwrap();
+        fs::write(build.join(RUNTIME_LIB), "runtime").unwrap();
+        fs::write(build.join("std").join("VERSION"), "0.1.0\n").unwrap();
+        fs::write(build.join("std").join("io.ws"), "# io").unwrap();
+
+        // A module left over from an earlier install is removed
+        let prefix = root.join("prefix");
+        fs::create_dir_all(prefix.join("std")).unwrap();
+        fs::write(prefix.join("std").join("old.ws"), "# old").unwrap();
+
+        fs::create_dir_all(build.join("mimalloc")).unwrap();
+        fs::write(build.join("mimalloc").join(RUNTIME_LIB), "mimalloc runtime").unwrap();
+        let allocator_libs = [(Allocator::Mimalloc, build.join("mimalloc").join(RUNTIME_LIB))];
+
+        let installed = install(
+            &prefix,
+            &build.join("wadescript"),
+            &build.join(RUNTIME_LIB),
+            &allocator_libs,
+            &build.join("std"),
+        )
+        .unwrap();
+        assert_eq!(
+            installed,
+            vec![
+                prefix.join("bin/wadescript"),
+                prefix.join("lib").join(RUNTIME_LIB),
+                prefix.join("lib/mimalloc").join(RUNTIME_LIB),
+                prefix.join("std"),
+            ]
+        );
+        assert_eq!(fs::read_to_string(prefix.join("bin/wadescript")).unwrap(), "compiler");
+        assert_eq!(fs::read_to_string(prefix.join("lib").join(RUNTIME_LIB)).unwrap(), "runtime");
+        assert_eq!(fs::read_to_string(prefix.join("lib/mimalloc").join(RUNTIME_LIB)).unwrap(), "mimalloc runtime");
+        assert_eq!(fs::read_to_string(prefix.join("std/io.ws")).unwrap(), "# io");
+        assert!(prefix.join("std/VERSION").exists());
+        assert!(!prefix.join("std/old.ws").exists());
+
+        // Reinstalling from the prefix itself leaves it intact
+        install(&prefix, &prefix.join("bin/wadescript"), &prefix.join("lib").join(RUNTIME_LIB), &[], &prefix.join("std")).unwrap();
+        assert_eq!(fs::read_to_string(prefix.join("bin/wadescript")).unwrap(), "compiler");
+        assert_eq!(fs::read_to_string(prefix.join("std/io.ws")).unwrap(), "# io");
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -1,9 +1,41 @@
 use crate::ast::*;
-use std::collections::HashMap;
+use crate::suggest::did_you_mean;
+use std::collections::{HashMap, HashSet};
+
+/// Exception types `raise` and `except` know without a class definition
+const BUILTIN_EXCEPTIONS: &[&str] = &[
+    "Exception",
+    "ValueError",
+    "KeyError",
+    "IndexError",
+    "RuntimeError",
+    "TypeError",
+    "OverflowError",
+    "ZeroDivisionError",
+    "OSError",
+    "EOFError",
+];
+
+/// Builtins a program may define its own version of, with `@shadows_builtin`
+const REPLACEABLE_BUILTINS: &[&str] = &["print", "Ok", "Err", "read_line", "parse_bool"];
 
 struct ClassInfo {
-    fields: Vec<(String, Type)>, // Ordered fields for constructor
+    fields: Vec<(String, Type)>, // Ordered fields for constructor, inherited ones first
     field_map: HashMap<String, Type>, // Quick lookup for field access
+    base: Option<String>,
+    abstract_methods: Vec<String>, // @abstract methods, own or inherited, with no override yet
+    is_abstract: bool, // Marked @abstract or has abstract_methods; can't be constructed
+    defaulted_fields: Vec<String>, // Fields with a default value, which the constructor doesn't take
+    init_params: Option<Vec<ParamInfo>>, // Parameters after self of the nearest init, when it takes any
+}
+
+/// A `for i in range(xs.length)` loop being checked, counting how `i` is used
+struct RangeIndexLoop {
+    list: String,
+    index: String,
+    uses: usize,
+    /// Uses that are `xs[i]` reads
+    element_reads: usize,
 }
 
 /// Parameter info for type checking function calls with named args and defaults
@@ -20,7 +52,23 @@ pub struct TypeChecker {
     function_params: HashMap<String, Vec<ParamInfo>>,  // Full param info for named args
     classes: HashMap<String, ClassInfo>,
     current_function_return_type: Option<Type>,
-    modules: HashMap<String, Vec<String>>, // module_name -> function_names
+    names: FunctionNames, // Imported modules, and which function a call reaches
+    // Module whose file the statement being checked comes from (None for the program)
+    current_module: Option<String>,
+    strict: bool,
+    warnings: Vec<String>,
+    // Optional variables known to be non-None (inside `if x != None { ... }`)
+    narrowed_optionals: Vec<String>,
+    // Number of except blocks around the current statement (a bare `raise` needs one)
+    except_depth: usize,
+    // Enclosing `for i in range(xs.length)` loops
+    range_index_loops: Vec<RangeIndexLoop>,
+    // Class whose methods are being checked, for `super` calls
+    current_class: Option<String>,
+    // Functions the compiler provides, which user definitions must not collide with
+    builtin_functions: HashSet<String>,
+    // Runtime functions codegen calls by name, which the program can't define
+    reserved_names: HashSet<String>,
 }
 
 impl TypeChecker {
@@ -36,6 +84,41 @@ impl TypeChecker {
         // Register built-in utility functions
         functions.insert("range".to_string(), (vec![Type::Int], Type::List(Box::new(Type::Int))));
 
+        // Register checked arithmetic: None instead of wrapping on overflow
+        let checked_int = Type::Optional(Box::new(Type::Int));
+        functions.insert("checked_add".to_string(), (vec![Type::Int, Type::Int], checked_int.clone()));
+        functions.insert("checked_mul".to_string(), (vec![Type::Int, Type::Int], checked_int));
+
+        // Register parse_bool: None for text that isn't a boolean (std/cli.ws
+        // replaces it with a version that returns False instead)
+        functions.insert("parse_bool".to_string(), (vec![Type::Str], Type::Optional(Box::new(Type::Bool))));
+
+        // Register float rounding and formatting functions
+        functions.insert("round".to_string(), (vec![Type::Float, Type::Int], Type::Float));
+        functions.insert("floor".to_string(), (vec![Type::Float], Type::Float));
+        functions.insert("ceil".to_string(), (vec![Type::Float], Type::Float));
+        functions.insert("trunc".to_string(), (vec![Type::Float], Type::Float));
+        functions.insert("format_float".to_string(), (vec![Type::Float, Type::Int], Type::Str));
+        functions.insert("is_nan".to_string(), (vec![Type::Float], Type::Bool));
+        functions.insert("is_finite".to_string(), (vec![Type::Float], Type::Bool));
+
+        // Register bit-level and byte-order conversions (for binary formats)
+        functions.insert("float_to_bits".to_string(), (vec![Type::Float], Type::Int));
+        functions.insert("bits_to_float".to_string(), (vec![Type::Int], Type::Float));
+        functions.insert("byte_swap".to_string(), (vec![Type::Int], Type::Int));
+        let byte_list = Type::List(Box::new(Type::U8));
+        for name in ["to_bytes_be", "to_bytes_le"] {
+            functions.insert(name.to_string(), (vec![Type::Int, Type::Int], byte_list.clone()));
+        }
+        for name in ["from_bytes_be", "from_bytes_le"] {
+            functions.insert(name.to_string(), (vec![byte_list.clone()], Type::Int));
+        }
+
+        // Register bigint functions
+        functions.insert("parse_bigint".to_string(), (vec![Type::Str], Type::BigInt));
+        functions.insert("pow_mod".to_string(), (vec![Type::BigInt, Type::BigInt, Type::BigInt], Type::BigInt));
+        functions.insert("parse_decimal".to_string(), (vec![Type::Str], Type::Decimal));
+
         // Register file I/O functions (used by std/io.ws)
         functions.insert("file_open".to_string(), (vec![Type::Str, Type::Str], Type::Int));
         functions.insert("file_read".to_string(), (vec![Type::Int], Type::Str));
@@ -43,6 +126,11 @@ impl TypeChecker {
         functions.insert("file_write".to_string(), (vec![Type::Int, Type::Str], Type::Void));
         functions.insert("file_close".to_string(), (vec![Type::Int], Type::Void));
         functions.insert("file_exists".to_string(), (vec![Type::Str], Type::Int));
+        functions.insert("file_seek".to_string(), (vec![Type::Int, Type::Int, Type::Int], Type::Int));
+        functions.insert("file_read_bytes".to_string(), (vec![Type::Int, Type::Int], Type::List(Box::new(Type::U8))));
+        functions.insert("file_read_lines".to_string(), (vec![Type::Int], Type::List(Box::new(Type::Str))));
+        functions.insert("file_write_bytes".to_string(), (vec![Type::Int, Type::List(Box::new(Type::U8))], Type::Void));
+        functions.insert("file_flush".to_string(), (vec![Type::Int], Type::Void));
 
         // Register CLI functions (used by std/cli.ws)
         functions.insert("cli_get_argc".to_string(), (vec![], Type::Int));
@@ -68,16 +156,174 @@ impl TypeChecker {
         functions.insert("http_response_get_header".to_string(), (vec![Type::Int, Type::Str], Type::Str));
         functions.insert("http_response_free".to_string(), (vec![Type::Int], Type::Void));
 
+        // Register SMTP functions (used by std/smtp.ws)
+        let smtp_send_params = vec![
+            Type::Str,
+            Type::Int,
+            Type::Str,
+            Type::Str,
+            Type::Str,
+            Type::Str,
+            Type::Str,
+            Type::Str,
+            Type::Str,
+        ];
+        functions.insert("smtp_send".to_string(), (smtp_send_params, Type::Str));
+
+        // Register compression functions (used by std/compress.ws)
+        let bytes = Type::List(Box::new(Type::U8));
+        for name in ["compress_gzip", "compress_gunzip", "compress_zlib", "compress_unzlib"] {
+            functions.insert(name.to_string(), (vec![bytes.clone()], bytes.clone()));
+        }
+        functions.insert("compress_read_gzip".to_string(), (vec![Type::Str], Type::Str));
+        functions.insert("compress_write_gzip".to_string(), (vec![Type::Str, Type::Str], Type::Void));
+
+        // Register random identifier functions (used by std/random.ws)
+        functions.insert("random_uuid4".to_string(), (vec![], Type::Str));
+        functions.insert("random_hex_string".to_string(), (vec![Type::Int], Type::Str));
+        functions.insert("random_token_string".to_string(), (vec![Type::Int], Type::Str));
+        functions.insert("random_seed".to_string(), (vec![Type::Int], Type::Void));
+        functions.insert("random_int_between".to_string(), (vec![Type::Int, Type::Int], Type::Int));
+        functions.insert("random_unit_float".to_string(), (vec![], Type::Float));
+
+        // Register file system functions (used by std/fs.ws)
+        functions.insert("fs_glob".to_string(), (vec![Type::Str], Type::List(Box::new(Type::Str))));
+        functions.insert("fs_list_dir".to_string(), (vec![Type::Str], Type::List(Box::new(Type::Str))));
+        for name in ["fs_mkdir", "fs_mkdirs", "fs_remove_file"] {
+            functions.insert(name.to_string(), (vec![Type::Str], Type::Void));
+        }
+        functions.insert("fs_remove_dir".to_string(), (vec![Type::Str, Type::Int], Type::Void));
+        functions.insert("fs_rename".to_string(), (vec![Type::Str, Type::Str], Type::Void));
+        functions.insert("fs_copy".to_string(), (vec![Type::Str, Type::Str], Type::Void));
+        for name in ["fs_file_size", "fs_is_dir", "fs_is_file"] {
+            functions.insert(name.to_string(), (vec![Type::Str], Type::Int));
+        }
+
+        // Register regex functions (used by std/regex.ws)
+        functions.insert("regex_compile".to_string(), (vec![Type::Str], Type::Int));
+        functions.insert("regex_is_match".to_string(), (vec![Type::Int, Type::Str], Type::Int));
+        functions.insert("regex_find".to_string(), (vec![Type::Int, Type::Str], Type::Optional(Box::new(Type::Str))));
+        functions.insert("regex_find_all".to_string(), (vec![Type::Int, Type::Str], Type::List(Box::new(Type::Str))));
+        functions.insert("regex_captures".to_string(), (vec![Type::Int, Type::Str], Type::List(Box::new(Type::Str))));
+        functions.insert("regex_replace".to_string(), (vec![Type::Int, Type::Str, Type::Str], Type::Str));
+        functions.insert("regex_split".to_string(), (vec![Type::Int, Type::Str], Type::List(Box::new(Type::Str))));
+        functions.insert("regex_free".to_string(), (vec![Type::Int], Type::Void));
+
+        // Register clock and date functions (used by std/time.ws)
+        functions.insert("time_now".to_string(), (vec![], Type::Int));
+        functions.insert("time_now_ms".to_string(), (vec![], Type::Int));
+        functions.insert("time_monotonic".to_string(), (vec![], Type::Float));
+        functions.insert("time_sleep_ms".to_string(), (vec![Type::Int], Type::Void));
+        functions.insert("time_format".to_string(), (vec![Type::Int, Type::Str], Type::Str));
+        functions.insert("time_parse".to_string(), (vec![Type::Str, Type::Str], Type::Int));
+
+        // Register environment variable functions (used by std/os.ws)
+        functions.insert("env_get".to_string(), (vec![Type::Str], Type::Optional(Box::new(Type::Str))));
+        functions.insert("env_set".to_string(), (vec![Type::Str, Type::Str], Type::Void));
+        functions.insert("env_unset".to_string(), (vec![Type::Str], Type::Void));
+
+        // Register subprocess functions (used by std/process.ws)
+        functions.insert("process_run".to_string(), (vec![Type::Str, Type::List(Box::new(Type::Str)), Type::Str], Type::Int));
+        functions.insert("process_exit_code".to_string(), (vec![Type::Int], Type::Int));
+        functions.insert("process_stdout".to_string(), (vec![Type::Int], Type::Str));
+        functions.insert("process_stderr".to_string(), (vec![Type::Int], Type::Str));
+        functions.insert("process_free".to_string(), (vec![Type::Int], Type::Void));
+
+        // Register path functions (used by std/path.ws)
+        functions.insert("path_join".to_string(), (vec![Type::Str, Type::Str], Type::Str));
+        for name in ["path_dirname", "path_basename", "path_extension", "path_normalize", "path_absolute"] {
+            functions.insert(name.to_string(), (vec![Type::Str], Type::Str));
+        }
+
+        // Register CSV functions (used by std/csv.ws)
+        let csv_row = Type::List(Box::new(Type::Str));
+        functions.insert("csv_parse".to_string(), (vec![Type::Str, Type::Str], Type::List(Box::new(csv_row.clone()))));
+        functions.insert("csv_format_row".to_string(), (vec![csv_row, Type::Str], Type::Str));
+
+        // Register config file functions (used by std/config.ws)
+        let sections = Type::Dict(Box::new(Type::Str), Box::new(Type::Dict(Box::new(Type::Str), Box::new(Type::Str))));
+        functions.insert("config_parse_toml".to_string(), (vec![Type::Str], sections.clone()));
+        functions.insert("config_parse_ini".to_string(), (vec![Type::Str], sections));
+        functions.insert("config_list_items".to_string(), (vec![Type::Str], Type::List(Box::new(Type::Str))));
+
+        // Register TCP socket functions (used by std/net.ws)
+        functions.insert("tcp_connect".to_string(), (vec![Type::Str, Type::Int], Type::Int));
+        functions.insert("tcp_listen".to_string(), (vec![Type::Str, Type::Int], Type::Int));
+        functions.insert("tcp_accept".to_string(), (vec![Type::Int], Type::Int));
+        functions.insert("tcp_read".to_string(), (vec![Type::Int, Type::Int], Type::Str));
+        functions.insert("tcp_read_line".to_string(), (vec![Type::Int], Type::Optional(Box::new(Type::Str))));
+        functions.insert("tcp_write".to_string(), (vec![Type::Int, Type::Str], Type::Void));
+        functions.insert("tcp_set_timeout".to_string(), (vec![Type::Int, Type::Int], Type::Void));
+        functions.insert("tcp_local_port".to_string(), (vec![Type::Int], Type::Int));
+        functions.insert("tcp_close".to_string(), (vec![Type::Int], Type::Void));
+
+        // Register string builder functions (used by std/strings.ws)
+        functions.insert("string_builder_new".to_string(), (vec![Type::Int], Type::Int));
+        functions.insert("string_builder_append".to_string(), (vec![Type::Int, Type::Str], Type::Void));
+        functions.insert("string_builder_length".to_string(), (vec![Type::Int], Type::Int));
+        functions.insert("string_builder_build".to_string(), (vec![Type::Int], Type::Str));
+        functions.insert("string_builder_clear".to_string(), (vec![Type::Int], Type::Void));
+        functions.insert("string_builder_free".to_string(), (vec![Type::Int], Type::Void));
+
+        let mut builtin_functions: HashSet<String> = functions.keys().cloned().collect();
+        builtin_functions.extend(REPLACEABLE_BUILTINS.iter().map(|name| name.to_string()));
+        // truthy() and format() take several types, and input() an optional prompt,
+        // so they are checked on their own rather than registered
+        builtin_functions.insert("truthy".to_string());
+        builtin_functions.insert("format".to_string());
+        builtin_functions.insert("input".to_string());
+
         TypeChecker {
             symbol_table: vec![HashMap::new()],
             functions,
             function_params: HashMap::new(),
             classes: HashMap::new(),
             current_function_return_type: None,
-            modules: HashMap::new(),
+            names: FunctionNames::default(),
+            current_module: None,
+            strict: false,
+            warnings: Vec::new(),
+            narrowed_optionals: Vec::new(),
+            except_depth: 0,
+            range_index_loops: Vec::new(),
+            current_class: None,
+            builtin_functions,
+            reserved_names: HashSet::new(),
         }
     }
 
+    /// Return types of the builtin functions, so codegen can tell what a call to one
+    /// evaluates to (a str and a bigint are both pointers to it)
+    pub fn builtin_return_types() -> HashMap<String, Type> {
+        Self::new()
+            .functions
+            .into_iter()
+            .map(|(name, (_, return_type))| (name, return_type))
+            .collect()
+    }
+
+    /// Enable `--strict` checks: every path returns, no int/float comparisons,
+    /// Optionals checked before use, no shadowing, and unused-result warnings
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Names the program's functions and classes can't take, because codegen
+    /// calls runtime functions by them (see `CodeGen::runtime_function_names`)
+    pub fn set_reserved_names(&mut self, names: HashSet<String>) {
+        self.reserved_names = names;
+    }
+
+    /// The modules seen so far, and which function a call reaches
+    pub fn names(&self) -> &FunctionNames {
+        &self.names
+    }
+
+    /// Warnings collected during `check_program`
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     fn enter_scope(&mut self) {
         self.symbol_table.push(HashMap::new());
     }
@@ -101,6 +347,157 @@ impl TypeChecker {
         None
     }
 
+    /// `nan` and `inf` are floats unless a variable of the same name hides them
+    fn builtin_constant_type(name: &str) -> Option<Type> {
+        matches!(name, "nan" | "inf").then_some(Type::Float)
+    }
+
+    /// In strict mode, reject declarations that hide an already visible variable.
+    /// A variable named after a builtin function doesn't hide it, so that only warns.
+    fn check_shadowing(&mut self, name: &str) -> Result<(), String> {
+        if self.strict && self.lookup_variable(name).is_some() {
+            return Err(format!(
+                "Variable '{}' shadows an existing variable (strict mode)",
+                name
+            ));
+        }
+        if self.builtin_functions.contains(name) {
+            self.warnings.push(format!(
+                "Variable '{}' has the name of the builtin function '{}', which calls still reach; consider renaming it",
+                name, name
+            ));
+        }
+        Ok(())
+    }
+
+    /// A top-level function or class may only take a builtin's name when it
+    /// replaces a builtin that allows it and says so with `@shadows_builtin`
+    fn check_builtin_collision(&self, kind: &str, name: &str, decorators: &[Decorator]) -> Result<(), String> {
+        let marked = decorators.iter().any(|d| d.name == "shadows_builtin");
+        let builtin = if BUILTIN_EXCEPTIONS.contains(&name) {
+            Some("exception type")
+        } else if self.builtin_functions.contains(name) {
+            Some("function")
+        } else {
+            None
+        };
+        match builtin {
+            Some("function") if !REPLACEABLE_BUILTINS.contains(&name) => Err(format!(
+                "{} '{}' has the name of the builtin function '{}', which can't be replaced; rename it",
+                kind, name, name
+            )),
+            Some(what) if !marked => Err(format!(
+                "{} '{}' shadows the builtin {} '{}'; mark it @shadows_builtin if that is intended, or rename it",
+                kind, name, what, name
+            )),
+            None if marked => Err(format!(
+                "{} '{}' is marked @shadows_builtin, but there is no builtin named '{}'",
+                kind, name, name
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// In strict mode, an Optional may only be used as its inner type once it
+    /// has been checked against None
+    fn check_optional_use(&self, object: &Expression, obj_type: &Type) -> Result<(), String> {
+        if !self.strict || !matches!(obj_type, Type::Optional(_)) {
+            return Ok(());
+        }
+        match object {
+            Expression::Variable(name) if self.narrowed_optionals.contains(name) => Ok(()),
+            Expression::Variable(name) => Err(format!(
+                "Optional value '{}' of type {} must be checked against None before use (strict mode)",
+                name, obj_type
+            )),
+            _ => Err(format!(
+                "Optional value of type {} must be checked against None before use (strict mode)",
+                obj_type
+            )),
+        }
+    }
+
+    /// Whether a block returns (or raises) on every path, for strict mode.
+    /// Loops other than `while True` are assumed to possibly run zero times.
+    fn always_returns(statements: &[Statement]) -> bool {
+        statements.iter().any(|statement| match statement {
+            Statement::Return(_) | Statement::Raise { .. } | Statement::Reraise { .. } => true,
+            Statement::If { then_branch, elif_branches, else_branch, .. } => {
+                Self::always_returns(then_branch)
+                    && elif_branches.iter().all(|(_, body)| Self::always_returns(body))
+                    && else_branch.as_ref().is_some_and(|body| Self::always_returns(body))
+            }
+            Statement::While { condition: Expression::BoolLiteral(true), body } => !Self::contains_break(body),
+            Statement::Try { try_block, except_clauses, finally_block } => {
+                finally_block.as_ref().is_some_and(|body| Self::always_returns(body))
+                    || (Self::always_returns(try_block)
+                        && except_clauses.iter().all(|clause| Self::always_returns(&clause.body)))
+            }
+            _ => false,
+        })
+    }
+
+    /// Whether a loop body contains a `break` for that loop (not a nested one)
+    fn contains_break(statements: &[Statement]) -> bool {
+        statements.iter().any(|statement| match statement {
+            Statement::Break => true,
+            Statement::If { then_branch, elif_branches, else_branch, .. } => {
+                Self::contains_break(then_branch)
+                    || elif_branches.iter().any(|(_, body)| Self::contains_break(body))
+                    || else_branch.as_ref().is_some_and(|body| Self::contains_break(body))
+            }
+            Statement::Try { try_block, except_clauses, finally_block } => {
+                Self::contains_break(try_block)
+                    || except_clauses.iter().any(|clause| Self::contains_break(&clause.body))
+                    || finally_block.as_ref().is_some_and(|body| Self::contains_break(body))
+            }
+            _ => false,
+        })
+    }
+
+    /// The variable narrowed by a condition of the form `x != None` (or `None != x`)
+    fn narrowed_by_condition(condition: &Expression) -> Option<&String> {
+        match condition {
+            Expression::Binary { left, op: BinaryOp::NotEqual, right } => match (&**left, &**right) {
+                (Expression::Variable(name), Expression::NoneLiteral)
+                | (Expression::NoneLiteral, Expression::Variable(name)) => Some(name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Codegen calls runtime functions by name, so a function or class of the
+    /// program with one's name would replace it in every call it generates
+    fn check_not_reserved(&self, kind: &str, name: &str) -> Result<(), String> {
+        if self.reserved_names.contains(name) {
+            return Err(format!(
+                "{} '{}' has the name of a runtime function, which is reserved; rename it",
+                kind, name
+            ));
+        }
+        Ok(())
+    }
+
+    /// "Undefined variable" error, suggesting a similarly named variable or module
+    fn undefined_variable_error(&self, name: &str) -> String {
+        let candidates = self
+            .symbol_table
+            .iter()
+            .flat_map(|scope| scope.keys())
+            .chain(self.names.modules().keys())
+            .map(String::as_str);
+        format!("Undefined variable '{}'{}", name, did_you_mean(name, candidates))
+    }
+
+    /// Names of top-level functions (methods are stored as `Class::method`)
+    fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.functions
+            .keys()
+            .filter(|name| !name.contains("::"))
+            .map(String::as_str)
+    }
+
     /// Register a REPL variable in the global scope (for variable persistence)
     pub fn register_repl_variable(&mut self, name: &str, var_type: &Type) {
         if let Some(scope) = self.symbol_table.first_mut() {
@@ -108,23 +505,85 @@ impl TypeChecker {
         }
     }
 
-    pub fn check_program(&mut self, program: &Program) -> Result<(), String> {
-        // Store module information
-        self.modules = program.modules.clone();
+    pub fn check_program(&mut self, program: &mut Program) -> Result<(), String> {
+        // Store module information; the REPL adds each input's to the last
+        self.names.define(program);
+        Self::check_unique_definitions(program)?;
+
+        // Compiled libraries only bring their signatures
+        for function in program.libraries.iter().flat_map(|library| &library.functions) {
+            let param_types: Vec<Type> = function.params.iter().map(|p| p.param_type.clone()).collect();
+            self.functions
+                .insert(function.name.clone(), (param_types, function.return_type.clone()));
+            let param_info: Vec<ParamInfo> = function.params.iter().map(|p| ParamInfo {
+                name: p.name.clone(),
+                param_type: p.param_type.clone(),
+                has_default: false,
+            }).collect();
+            self.function_params.insert(function.name.clone(), param_info);
+        }
 
-        for statement in &program.statements {
+        for statement in &mut program.statements {
+            self.current_module = self.names.statement_module(statement);
             self.check_statement(statement)?;
         }
+        self.current_module = None;
+        Ok(())
+    }
+
+    /// Each name is defined once: a function within its file (an imported
+    /// file's are named `module.name`, and the program shares its namespace
+    /// with compiled libraries), a class across the program and its imports
+    fn check_unique_definitions(program: &Program) -> Result<(), String> {
+        let library_functions = program
+            .libraries
+            .iter()
+            .flat_map(|library| &library.functions)
+            .map(|function| (&function.name, "function"));
+        let definitions = program.statements.iter().filter_map(|statement| match statement {
+            Statement::FunctionDef { name, .. } => Some((name, "function")),
+            Statement::ClassDef { name, .. } => Some((name, "class")),
+            _ => None,
+        });
+
+        let mut defined: HashMap<&String, &str> = HashMap::new();
+        for (name, kind) in library_functions.chain(definitions) {
+            match defined.insert(name, kind) {
+                Some(previous) if previous == kind => {
+                    return Err(match name.split_once('.') {
+                        Some((module, function)) => {
+                            format!("The function '{}' is defined more than once in module '{}'", function, module)
+                        }
+                        None => format!("The {} '{}' is defined more than once", kind, name),
+                    });
+                }
+                Some(_) => return Err(format!("'{}' is defined as both a function and a class", name)),
+                None => {}
+            }
+        }
         Ok(())
     }
 
-    fn check_statement(&mut self, statement: &Statement) -> Result<(), String> {
+    fn check_statement(&mut self, statement: &mut Statement) -> Result<(), String> {
         match statement {
             Statement::VarDecl {
                 name,
                 type_annotation,
                 initializer,
             } => {
+                // `let x = expr`: the initializer decides the type, and the
+                // declaration is rewritten so codegen sees a concrete type
+                if *type_annotation == Type::Inferred {
+                    let init_expr = initializer.as_ref().ok_or_else(|| {
+                        format!("Cannot infer type of '{}' without an initializer", name)
+                    })?;
+                    let inferred = self.infer_initializer_type(name, init_expr)?;
+                    *type_annotation = inferred.clone();
+                    self.check_shadowing(name)?;
+                    self.declare_variable(name.clone(), inferred);
+                    return Ok(());
+                }
+
                 if let Some(init_expr) = initializer {
                     // For empty list literals, use the type annotation
                     let init_type = if let Expression::ListLiteral { elements } = init_expr {
@@ -144,13 +603,14 @@ impl TypeChecker {
                         self.check_expression(init_expr)?
                     };
 
-                    if !self.types_compatible(type_annotation, &init_type) {
+                    if !self.value_compatible(type_annotation, &init_type, init_expr) {
                         return Err(format!(
                             "Type mismatch in variable '{}': expected {}, got {}",
                             name, type_annotation, init_type
                         ));
                     }
                 }
+                self.check_shadowing(name)?;
                 self.declare_variable(name.clone(), type_annotation.clone());
                 Ok(())
             }
@@ -160,10 +620,24 @@ impl TypeChecker {
                 params,
                 return_type,
                 body,
+                decorators,
+                ..
             } => {
+                self.validate_function_decorators(name, params, return_type, decorators)?;
+                if self.current_class.is_none() {
+                    // An imported file's function is `module.name`
+                    match name.split_once('.') {
+                        Some((_, function)) => self.check_builtin_collision("Function", function, decorators)?,
+                        None => {
+                            self.check_builtin_collision("Function", name, decorators)?;
+                            self.check_not_reserved("Function", name)?;
+                        }
+                    }
+                }
+
                 // Validate default parameters: params with defaults must come after those without
                 let mut seen_default = false;
-                for param in params {
+                for param in params.iter() {
                     if param.default_value.is_some() {
                         seen_default = true;
                     } else if seen_default {
@@ -186,10 +660,21 @@ impl TypeChecker {
                 }).collect();
                 self.function_params.insert(name.clone(), param_info);
 
+                let returns_value = *return_type != Type::Void && *return_type != Type::Custom("void".to_string());
+                // An @abstract method's body is only a placeholder
+                let is_abstract = decorators.iter().any(|d| d.name == "abstract");
+                if self.strict && returns_value && !is_abstract && !Self::always_returns(body) {
+                    return Err(format!(
+                        "Function '{}' does not return a value on every path (strict mode)",
+                        name
+                    ));
+                }
+
                 self.enter_scope();
                 self.current_function_return_type = Some(return_type.clone());
 
-                for param in params {
+                for param in params.iter() {
+                    self.check_shadowing(&param.name)?;
                     self.declare_variable(param.name.clone(), param.param_type.clone());
                 }
 
@@ -204,40 +689,163 @@ impl TypeChecker {
 
             Statement::ClassDef {
                 name,
-                _base_class: _,
+                base_class,
                 fields,
                 methods,
+                decorators,
+                ..
             } => {
-                // Validate decorators on fields
-                for field in fields {
+                // Validate decorators on the class and its fields
+                for decorator in decorators.iter() {
+                    if decorator.name != "abstract" && decorator.name != "shadows_builtin" {
+                        return Err(format!("Unknown decorator '@{}' on class '{}'", decorator.name, name));
+                    }
+                }
+                self.check_builtin_collision("Class", name, decorators)?;
+                self.check_not_reserved("Class", name)?;
+                for field in fields.iter() {
                     self.validate_field_decorators(name, field)?;
                 }
 
+                // Inherited fields come first, so a subclass instance starts
+                // with the layout of its base class
+                let (mut ordered_fields, mut field_map, mut abstract_methods, mut defaulted_fields, base_init_params) =
+                    match base_class {
+                        Some(base) => {
+                            let base_info = self.classes.get(base).ok_or_else(|| {
+                                format!("Class '{}' inherits from undefined class '{}'", name, base)
+                            })?;
+                            (
+                                base_info.fields.clone(),
+                                base_info.field_map.clone(),
+                                base_info.abstract_methods.clone(),
+                                base_info.defaulted_fields.clone(),
+                                base_info.init_params.clone(),
+                            )
+                        }
+                        None => (Vec::new(), HashMap::new(), Vec::new(), Vec::new(), None),
+                    };
+                let inherited_count = ordered_fields.len();
+
                 // Store class fields in order and in a map
-                let mut ordered_fields = Vec::new();
-                let mut field_map = HashMap::new();
                 for field in fields {
+                    if ordered_fields[..inherited_count].iter().any(|(f, _)| *f == field.name) {
+                        return Err(format!(
+                            "Field '{}' of class '{}' is already defined in base class '{}'",
+                            field.name,
+                            name,
+                            base_class.as_deref().unwrap_or_default()
+                        ));
+                    }
+                    if let Some(default) = &field.default_value {
+                        // Empty list and dict literals take the field's type
+                        let default_type = match default {
+                            Expression::ListLiteral { elements }
+                                if elements.is_empty() && matches!(field.field_type, Type::List(_)) =>
+                            {
+                                field.field_type.clone()
+                            }
+                            Expression::DictLiteral { pairs }
+                                if pairs.is_empty() && matches!(field.field_type, Type::Dict(_, _)) =>
+                            {
+                                field.field_type.clone()
+                            }
+                            _ => self.check_expression(default)?,
+                        };
+                        if !self.value_compatible(&field.field_type, &default_type, default) {
+                            return Err(format!(
+                                "Cannot assign {} to field '{}.{}' of type {}",
+                                default_type, name, field.name, field.field_type
+                            ));
+                        }
+                        defaulted_fields.push(field.name.clone());
+                    }
                     ordered_fields.push((field.name.clone(), field.field_type.clone()));
                     field_map.insert(field.name.clone(), field.field_type.clone());
                 }
 
+                // An init that takes parameters besides self replaces the
+                // field-order constructor; a class without init inherits its base's
+                let own_init = methods.iter().find_map(|method| match method {
+                    Statement::FunctionDef { name: method_name, params, .. } if method_name == "init" => Some(params),
+                    _ => None,
+                });
+                // deinit is called by the runtime with nothing but the object
+                for method in methods.iter() {
+                    if let Statement::FunctionDef { name: method_name, params, return_type, .. } = method {
+                        // An explicit `-> void` annotation parses as Custom("void")
+                        let returns_value = *return_type != Type::Void && *return_type != Type::Custom("void".to_string());
+                        if method_name == "deinit" && (params.len() != 1 || returns_value) {
+                            return Err(format!("Method '{}.deinit' must take only self and return nothing", name));
+                        }
+                    }
+                }
+                let init_params = match own_init {
+                    Some(params) if params.len() > 1 => Some(
+                        params[1..]
+                            .iter()
+                            .map(|p| ParamInfo {
+                                name: p.name.clone(),
+                                param_type: p.param_type.clone(),
+                                has_default: p.default_value.is_some(),
+                            })
+                            .collect(),
+                    ),
+                    Some(_) => None,
+                    None => base_init_params,
+                };
+
+                // Defining a method implements the inherited abstract one, unless
+                // it is itself @abstract
+                for method in methods.iter() {
+                    if let Statement::FunctionDef { name: method_name, decorators, .. } = method {
+                        abstract_methods.retain(|m| m != method_name);
+                        if decorators.iter().any(|d| d.name == "abstract") {
+                            abstract_methods.push(method_name.clone());
+                        }
+                    }
+                }
+                let is_abstract = !abstract_methods.is_empty() || decorators.iter().any(|d| d.name == "abstract");
+
                 let class_info = ClassInfo {
                     fields: ordered_fields,
                     field_map,
+                    base: base_class.clone(),
+                    abstract_methods,
+                    is_abstract,
+                    defaulted_fields,
+                    init_params,
                 };
                 self.classes.insert(name.clone(), class_info);
 
                 // Register methods as functions with Class::method naming
-                for method in methods {
+                for method in methods.iter() {
                     if let Statement::FunctionDef {
                         name: method_name,
                         params,
                         return_type,
-                        body: _,
+                        ..
                     } = method
                     {
                         let param_types: Vec<Type> =
                             params.iter().map(|p| p.param_type.clone()).collect();
+                        // An override must be callable wherever the base method is (init
+                        // is only called by constructors, so it can change its parameters)
+                        if let Some((base_params, base_return)) = base_class
+                            .as_ref()
+                            .filter(|_| method_name != "init")
+                            .and_then(|base| self.find_method(base, method_name))
+                        {
+                            if base_params.get(1..) != param_types.get(1..) || base_return != *return_type {
+                                return Err(format!(
+                                    "Method '{}.{}' overrides '{}.{}' with a different signature",
+                                    name,
+                                    method_name,
+                                    base_class.as_deref().unwrap_or_default(),
+                                    method_name
+                                ));
+                            }
+                        }
                         self.functions.insert(
                             format!("{}::{}", name, method_name),
                             (param_types, return_type.clone()),
@@ -246,9 +854,10 @@ impl TypeChecker {
                 }
 
                 // Type check methods
-                for method in methods {
-                    self.check_statement(method)?;
-                }
+                self.current_class = Some(name.clone());
+                let result = methods.iter_mut().try_for_each(|method| self.check_statement(method));
+                self.current_class = None;
+                result?;
 
                 Ok(())
             }
@@ -267,11 +876,18 @@ impl TypeChecker {
                     ));
                 }
 
+                let narrowed = Self::narrowed_by_condition(condition).cloned();
+                if let Some(name) = &narrowed {
+                    self.narrowed_optionals.push(name.clone());
+                }
                 self.enter_scope();
                 for stmt in then_branch {
                     self.check_statement(stmt)?;
                 }
                 self.exit_scope();
+                if narrowed.is_some() {
+                    self.narrowed_optionals.pop();
+                }
 
                 for (elif_cond, elif_body) in elif_branches {
                     let elif_cond_type = self.check_expression(elif_cond)?;
@@ -320,12 +936,28 @@ impl TypeChecker {
 
             Statement::For {
                 variable,
+                value_variable,
                 iterable,
                 body,
             } => {
                 // Check iterable type and determine element type
                 let iterable_type = self.check_expression(iterable)?;
 
+                // `for key, value in dict` binds each key's value too
+                let value_binding = match (value_variable, &iterable_type) {
+                    (None, _) => None,
+                    (Some(name), _) if name == variable => {
+                        return Err(format!("Loop variable '{}' is used for both the key and the value", name));
+                    }
+                    (Some(name), Type::Dict(_, val_type)) => Some((name.clone(), (**val_type).clone())),
+                    (Some(_), _) => {
+                        return Err(format!(
+                            "Cannot iterate over type {} with two loop variables. Only a dict gives 'for key, value'.",
+                            iterable_type
+                        ));
+                    }
+                };
+
                 let element_type = match iterable_type {
                     Type::List(elem_type) => *elem_type,
                     Type::Array(elem_type, _) => *elem_type,
@@ -339,12 +971,38 @@ impl TypeChecker {
                     }
                 };
 
+                self.check_shadowing(variable)?;
+                if let Some((name, _)) = &value_binding {
+                    self.check_shadowing(name)?;
+                }
                 self.enter_scope();
                 self.declare_variable(variable.clone(), element_type);
+                if let Some((name, val_type)) = value_binding {
+                    self.declare_variable(name, val_type);
+                }
 
+                let range_list = self.range_over_list_length(iterable);
+                if let Some(list) = &range_list {
+                    self.range_index_loops.push(RangeIndexLoop {
+                        list: list.clone(),
+                        index: variable.clone(),
+                        uses: 0,
+                        element_reads: 0,
+                    });
+                }
                 for stmt in body {
                     self.check_statement(stmt)?;
                 }
+                if range_list.is_some() {
+                    let range_loop = self.range_index_loops.pop().unwrap();
+                    if range_loop.uses > 0 && range_loop.uses == range_loop.element_reads {
+                        self.warnings.push(format!(
+                            "'for {i} in range({xs}.length)' only uses {i} to read {xs}[{i}]; iterate over the list directly with 'for item in {xs}'",
+                            i = range_loop.index,
+                            xs = range_loop.list
+                        ));
+                    }
+                }
                 self.exit_scope();
 
                 Ok(())
@@ -358,7 +1016,8 @@ impl TypeChecker {
                 };
 
                 if let Some(expected_return_type) = &self.current_function_return_type {
-                    if !self.types_compatible(expected_return_type, &return_type) {
+                    let literal_fits = expr.as_ref().is_some_and(|e| Self::literal_fits(expected_return_type, e));
+                    if !literal_fits && !self.types_compatible(expected_return_type, &return_type) {
                         return Err(format!(
                             "Return type mismatch: expected {}, got {}",
                             expected_return_type, return_type
@@ -387,13 +1046,16 @@ impl TypeChecker {
                 for except_clause in except_clauses {
                     // If there's a variable binding, declare it with type Exception
                     if let Some(ref var_name) = except_clause.var_name {
+                        self.check_shadowing(var_name)?;
                         self.enter_scope();
                         self.declare_variable(var_name.clone(), Type::Exception);
                     }
 
-                    for stmt in &except_clause.body {
+                    self.except_depth += 1;
+                    for stmt in &mut except_clause.body {
                         self.check_statement(stmt)?;
                     }
+                    self.except_depth -= 1;
 
                     if except_clause.var_name.is_some() {
                         self.exit_scope();
@@ -410,19 +1072,59 @@ impl TypeChecker {
                 Ok(())
             }
 
-            Statement::Raise { exception_type: _, message, line: _ } => {
+            Statement::Raise { exception_type: _, message, cause, line } => {
                 // Check that message is a string
                 let msg_type = self.check_expression(message)?;
                 if msg_type != Type::Str {
                     return Err(format!("Exception message must be str, got {}", msg_type));
                 }
+                if let Some(cause) = cause {
+                    let cause_type = self.check_expression(cause)?;
+                    if cause_type != Type::Exception {
+                        return Err(format!(
+                            "'raise ... from' needs a caught exception (except X as e), got {} at line {}",
+                            cause_type, line
+                        ));
+                    }
+                }
+                Ok(())
+            }
+
+            Statement::Reraise { line } => {
+                if self.except_depth == 0 {
+                    return Err(format!(
+                        "'raise' without an exception can only be used inside an except block at line {}",
+                        line
+                    ));
+                }
                 Ok(())
             }
 
             Statement::Break | Statement::Continue | Statement::Pass | Statement::Import { .. } => Ok(()),
 
             Statement::Expression(expr) => {
-                self.check_expression(expr)?;
+                let result_type = self.check_expression(expr)?;
+                if self.strict {
+                    let callee = match expr {
+                        Expression::Call { callee, .. } => match &**callee {
+                            Expression::Variable(name) => Some(name.clone()),
+                            Expression::MemberAccess { member, .. } => Some(member.clone()),
+                            _ => None,
+                        },
+                        Expression::MethodCall { method, .. } | Expression::SuperCall { method, .. } => {
+                            Some(method.clone())
+                        }
+                        _ => None,
+                    };
+                    // An explicit `-> void` annotation parses as Custom("void")
+                    let returns_value = result_type != Type::Void && result_type != Type::Custom("void".to_string());
+                    if let (Some(callee), true) = (callee, returns_value) {
+                        self.warnings.push(format!(
+                            "Result of '{}' ({}) is unused (strict mode)",
+                            callee, result_type
+                        ));
+                    }
+                }
                 Ok(())
             }
 
@@ -438,6 +1140,7 @@ impl TypeChecker {
                     }
                     // Add each name to the symbol table with its corresponding type
                     for (name, ty) in names.iter().zip(types.iter()) {
+                        self.check_shadowing(name)?;
                         self.declare_variable(name.clone(), ty.clone());
                     }
                     Ok(())
@@ -451,37 +1154,141 @@ impl TypeChecker {
         }
     }
 
+    /// Infer a variable's type from its initializer (for `let` declarations).
+    /// Empty list/dict literals and `None` carry no type, so they still need
+    /// an explicit annotation.
+    pub fn infer_initializer_type(&mut self, name: &str, init_expr: &Expression) -> Result<Type, String> {
+        match init_expr {
+            Expression::ListLiteral { elements } if elements.is_empty() => {
+                return Err(format!(
+                    "Cannot infer type of empty list for '{}'; add a type annotation, e.g. 'let {}: list[int] = []'",
+                    name, name
+                ));
+            }
+            Expression::DictLiteral { pairs } if pairs.is_empty() => {
+                return Err(format!(
+                    "Cannot infer type of empty dict for '{}'; add a type annotation, e.g. 'let {}: dict[str, int] = {{}}'",
+                    name, name
+                ));
+            }
+            Expression::NoneLiteral => {
+                return Err(format!(
+                    "Cannot infer type of '{}' from None; add an optional type annotation, e.g. 'let {}: str? = None'",
+                    name, name
+                ));
+            }
+            _ => {}
+        }
+
+        let init_type = self.check_expression(init_expr)?;
+        // An explicit `-> void` annotation parses as Custom("void")
+        if init_type == Type::Void || init_type == Type::Custom("void".to_string()) {
+            return Err(format!(
+                "Cannot infer type of '{}': initializer does not return a value",
+                name
+            ));
+        }
+        // `Ok(1)` alone does not say what the error type is
+        if let Type::Result(ok_type, err_type) = &init_type {
+            if **ok_type == Type::Inferred || **err_type == Type::Inferred {
+                return Err(format!(
+                    "Cannot infer the full Result type of '{}'; add a type annotation, e.g. 'let {}: Result[int, str] = ...'",
+                    name, name
+                ));
+            }
+        }
+        Ok(init_type)
+    }
+
+    /// `xs` for a `range(xs.length)` iterable over a list variable
+    fn range_over_list_length(&self, iterable: &Expression) -> Option<String> {
+        let Expression::Call { callee, args, .. } = iterable else {
+            return None;
+        };
+        if !matches!(&**callee, Expression::Variable(name) if name == "range") || args.len() != 1 {
+            return None;
+        }
+        match &args[0] {
+            Expression::MemberAccess { object, member } if member == "length" => match &**object {
+                Expression::Variable(list) if matches!(self.lookup_variable(list), Some(Type::List(_))) => {
+                    Some(list.clone())
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Record a use of a `range(xs.length)` loop variable (`element_read` for `xs[i]`)
+    fn count_range_index_use(&mut self, name: &str, element_read: bool) {
+        if let Some(range_loop) = self.range_index_loops.iter_mut().rev().find(|range_loop| range_loop.index == name) {
+            if element_read {
+                range_loop.element_reads += 1;
+            } else {
+                range_loop.uses += 1;
+            }
+        }
+    }
+
     fn check_expression(&mut self, expression: &Expression) -> Result<Type, String> {
         match expression {
             Expression::IntLiteral(_) => Ok(Type::Int),
             Expression::FloatLiteral(_) => Ok(Type::Float),
+            Expression::DecimalLiteral(_) => Ok(Type::Decimal),
             Expression::StringLiteral(_) => Ok(Type::Str),
             Expression::BoolLiteral(_) => Ok(Type::Bool),
             Expression::NoneLiteral => Ok(Type::Void),
 
-            Expression::Variable(name) => self
-                .lookup_variable(name)
-                .ok_or_else(|| format!("Undefined variable '{}'", name)),
+            Expression::Variable(name) => {
+                self.count_range_index_use(name, false);
+                self.lookup_variable(name)
+                    .or_else(|| Self::builtin_constant_type(name))
+                    .ok_or_else(|| self.undefined_variable_error(name))
+            }
 
             Expression::Binary { left, op, right } => {
                 let left_type = self.check_expression(left)?;
                 let right_type = self.check_expression(right)?;
 
+                let is_runtime_number = |t: &Type| matches!(t, Type::BigInt | Type::Decimal);
+                if is_runtime_number(&left_type) || is_runtime_number(&right_type) {
+                    return Self::runtime_number_binary_type(op, &left_type, &right_type);
+                }
+
                 match op {
                     BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide => {
-                        if (left_type == Type::Int || left_type == Type::Float)
-                            && (right_type == Type::Int || right_type == Type::Float)
+                        if left_type.integer_rank().is_some() && right_type.integer_rank().is_some() {
+                            Ok(Self::integer_result_type(&left_type, &right_type, left, right))
+                        } else if (left_type == Type::Float || left_type.integer_rank().is_some())
+                            && (right_type == Type::Float || right_type.integer_rank().is_some())
                         {
-                            if left_type == Type::Float || right_type == Type::Float {
-                                Ok(Type::Float)
-                            } else {
-                                Ok(Type::Int)
-                            }
+                            Ok(Type::Float)
                         } else if left_type == Type::Str
                             && right_type == Type::Str
                             && *op == BinaryOp::Add
                         {
                             Ok(Type::Str)
+                        } else if *op == BinaryOp::Multiply
+                            && ((left_type == Type::Str && right_type == Type::Int)
+                                || (left_type == Type::Int && right_type == Type::Str))
+                        {
+                            // String repetition: "-" * 40
+                            Ok(Type::Str)
+                        } else if *op == BinaryOp::Add
+                            && matches!(left_type, Type::List(_))
+                            && matches!(right_type, Type::List(_))
+                        {
+                            // List concatenation: xs + ys, or xs + [1] with xs: list[u8]
+                            if self.value_compatible(&left_type, &right_type, right) {
+                                Ok(left_type)
+                            } else if self.value_compatible(&right_type, &left_type, left) {
+                                Ok(right_type)
+                            } else {
+                                Err(format!(
+                                    "Invalid operands for Add: {} and {} (lists must have the same element type)",
+                                    left_type, right_type
+                                ))
+                            }
                         } else {
                             Err(format!(
                                 "Invalid operands for {:?}: {} and {}",
@@ -491,8 +1298,8 @@ impl TypeChecker {
                     }
 
                     BinaryOp::Modulo | BinaryOp::FloorDivide => {
-                        if left_type == Type::Int && right_type == Type::Int {
-                            Ok(Type::Int)
+                        if left_type.integer_rank().is_some() && right_type.integer_rank().is_some() {
+                            Ok(Self::integer_result_type(&left_type, &right_type, left, right))
                         } else {
                             Err(format!(
                                 "Invalid operands for {:?}: {} and {}",
@@ -524,7 +1331,29 @@ impl TypeChecker {
                     | BinaryOp::Greater
                     | BinaryOp::LessEqual
                     | BinaryOp::GreaterEqual => {
-                        if self.types_compatible(&left_type, &right_type) {
+                        let is_integer = |t: &Type| t.integer_rank().is_some();
+                        let mixed_numeric = (is_integer(&left_type) && right_type == Type::Float)
+                            || (left_type == Type::Float && is_integer(&right_type));
+                        // Strings order by content (strcmp); lists, dicts and objects only have identity
+                        let orderable = |t: &Type| is_integer(t) || matches!(t, Type::Float | Type::Bool | Type::Str);
+                        let ordering = !matches!(op, BinaryOp::Equal | BinaryOp::NotEqual);
+                        if self.strict && mixed_numeric {
+                            Err(format!(
+                                "Comparing {} with {} requires an explicit conversion (strict mode)",
+                                left_type, right_type
+                            ))
+                        } else if matches!(left_type, Type::Result(..)) || matches!(right_type, Type::Result(..)) {
+                            Err("Cannot compare Result values; check is_ok() or compare the unwrapped value".to_string())
+                        } else if ordering && !(orderable(&left_type) && orderable(&right_type)) {
+                            Err(format!(
+                                "Cannot order {} and {}: <, >, <= and >= compare numbers and strings",
+                                left_type, right_type
+                            ))
+                        } else if mixed_numeric
+                            || (is_integer(&left_type) && is_integer(&right_type))
+                            || self.types_compatible(&left_type, &right_type)
+                        {
+                            // Mixed numeric comparisons widen the narrower side in codegen
                             Ok(Type::Bool)
                         } else {
                             Err(format!(
@@ -561,7 +1390,7 @@ impl TypeChecker {
                         }
                     }
                     UnaryOp::Negate => {
-                        if operand_type == Type::Int || operand_type == Type::Float {
+                        if matches!(operand_type, Type::Int | Type::I32 | Type::I16 | Type::Float | Type::BigInt | Type::Decimal) {
                             Ok(operand_type)
                         } else {
                             Err(format!(
@@ -573,24 +1402,100 @@ impl TypeChecker {
                 }
             }
 
-            Expression::Call { callee, args, named_args, line: _ } => {
+            Expression::NullCoalesce { value, default } => {
+                let value_type = self.check_expression(value)?;
+                let inner = match &value_type {
+                    Type::Optional(inner) => inner.as_ref().clone(),
+                    other => {
+                        return Err(format!(
+                            "Left side of '??' must be an Optional value, got {}",
+                            other
+                        ))
+                    }
+                };
+                let default_type = self.check_expression(default)?;
+
+                if self.types_compatible(&inner, &default_type) {
+                    Ok(inner)
+                } else if matches!(default_type, Type::Optional(_))
+                    && self.types_compatible(&value_type, &default_type)
+                {
+                    // `a ?? b` with an Optional fallback is still Optional
+                    Ok(value_type)
+                } else {
+                    Err(format!(
+                        "Default for '??' must be {}, got {}",
+                        inner, default_type
+                    ))
+                }
+            }
+
+            Expression::Propagate { value } => {
+                let value_type = self.check_expression(value)?;
+                let Type::Result(ok_type, err_type) = value_type else {
+                    return Err(format!("'?' can only be applied to a Result, got {}", value_type));
+                };
+                match &self.current_function_return_type {
+                    Some(Type::Result(_, return_err_type)) => {
+                        if !self.types_compatible(return_err_type, &err_type) {
+                            return Err(format!(
+                                "'?' cannot propagate error type {} from a function whose errors are {}",
+                                err_type, return_err_type
+                            ));
+                        }
+                    }
+                    _ => {
+                        return Err("'?' can only be used in a function that returns a Result".to_string());
+                    }
+                }
+                Ok(*ok_type)
+            }
+
+            Expression::Cast { expr, target } => {
+                let source_type = self.check_expression(expr)?;
+                let numeric = |t: &Type| {
+                    t.integer_rank().is_some() || matches!(t, Type::Float | Type::BigInt | Type::Decimal)
+                };
+                if !numeric(target) {
+                    Err(format!(
+                        "Cannot cast to {}; 'as' only supports numeric targets",
+                        target
+                    ))
+                } else if *target == Type::BigInt && source_type == Type::Float {
+                    Err("Cannot cast float to bigint; cast to int first".to_string())
+                } else if matches!((target, &source_type), (Type::BigInt, Type::Decimal) | (Type::Decimal, Type::BigInt)) {
+                    Err(format!("Cannot cast {} to {}", source_type, target))
+                } else if numeric(&source_type) || (source_type == Type::Bool && *target != Type::Float) {
+                    Ok(target.clone())
+                } else {
+                    Err(format!(
+                        "Cannot cast {} to {}; 'as' only converts between numeric types",
+                        source_type, target
+                    ))
+                }
+            }
+
+            Expression::Call { callee, args, named_args, line } => {
                 // Check if this is a module.function() call
                 if let Expression::MemberAccess { object, member } = &**callee {
                     if let Expression::Variable(module_name) = &**object {
                         // Check if this is a known module
-                        if let Some(module_functions) = self.modules.get(module_name) {
+                        if let Some(module) = self.names.modules().get(module_name) {
                             // Check if the function exists in this module
-                            if !module_functions.contains(member) {
+                            if !module.functions.contains(member) {
                                 return Err(format!(
-                                    "Module '{}' has no function '{}'",
-                                    module_name, member
+                                    "Module '{}' has no function '{}'{}",
+                                    module_name,
+                                    member,
+                                    did_you_mean(member, module.functions.iter().map(String::as_str))
                                 ));
                             }
 
                             // Look up the function signature
-                            if let Some((param_types, return_type)) = self.functions.get(member).cloned() {
+                            let function = self.names.resolve_qualified(module_name, member)?;
+                            if let Some((param_types, return_type)) = self.functions.get(&function).cloned() {
                                 // Check if we have full param info (for named args/defaults)
-                                if let Some(param_info) = self.function_params.get(member).cloned() {
+                                if let Some(param_info) = self.function_params.get(&function).cloned() {
                                     // Track which parameters have been provided
                                     let mut provided = vec![false; param_info.len()];
 
@@ -604,7 +1509,7 @@ impl TypeChecker {
 
                                     for (i, arg) in args.iter().enumerate() {
                                         let arg_type = self.check_expression(arg)?;
-                                        if !self.types_compatible(&param_info[i].param_type, &arg_type) {
+                                        if !self.value_compatible(&param_info[i].param_type, &arg_type, arg) {
                                             return Err(format!(
                                                 "Argument {} of function '{}.{}': expected {}, got {}",
                                                 i + 1, module_name, member, param_info[i].param_type, arg_type
@@ -625,7 +1530,7 @@ impl TypeChecker {
                                                     ));
                                                 }
                                                 let arg_type = self.check_expression(value)?;
-                                                if !self.types_compatible(&param_info[idx].param_type, &arg_type) {
+                                                if !self.value_compatible(&param_info[idx].param_type, &arg_type, value) {
                                                     return Err(format!(
                                                         "Named argument '{}' of function '{}.{}': expected {}, got {}",
                                                         name, module_name, member, param_info[idx].param_type, arg_type
@@ -667,7 +1572,7 @@ impl TypeChecker {
 
                                     for (i, arg) in args.iter().enumerate() {
                                         let arg_type = self.check_expression(arg)?;
-                                        if !self.types_compatible(&param_types[i], &arg_type) {
+                                        if !self.value_compatible(&param_types[i], &arg_type, arg) {
                                             return Err(format!(
                                                 "Argument {} of function '{}.{}': expected {}, got {}",
                                                 i + 1,
@@ -691,8 +1596,32 @@ impl TypeChecker {
                 // Check if this is a class constructor call
                 if let Expression::Variable(class_name) = &**callee {
                     if let Some(class_info) = self.classes.get(class_name) {
-                        // This is a constructor call - arguments must match field types in order
+                        if class_info.is_abstract {
+                            let missing: Vec<String> =
+                                class_info.abstract_methods.iter().map(|m| format!("'{}'", m)).collect();
+                            return Err(match missing.len() {
+                                0 => format!("Cannot instantiate abstract class '{}'", class_name),
+                                1 => format!(
+                                    "Cannot instantiate abstract class '{}': abstract method {} is not implemented",
+                                    class_name, missing[0]
+                                ),
+                                _ => format!(
+                                    "Cannot instantiate abstract class '{}': abstract methods {} are not implemented",
+                                    class_name,
+                                    missing.join(", ")
+                                ),
+                            });
+                        }
+
+                        // A custom init's parameters, with named arguments and defaults
+                        if let Some(params) = class_info.init_params.clone() {
+                            self.check_constructor_arguments(class_name, &params, args, named_args)?;
+                            return Ok(Type::Custom(class_name.clone()));
+                        }
+
+                        // Otherwise the fields without a default, in order
                         let field_types: Vec<Type> = class_info.fields.iter()
+                            .filter(|(field, _)| !class_info.defaulted_fields.contains(field))
                             .map(|(_, field_type)| field_type.clone())
                             .collect();
 
@@ -707,7 +1636,7 @@ impl TypeChecker {
 
                         for (i, arg) in args.iter().enumerate() {
                             let arg_type = self.check_expression(arg)?;
-                            if !self.types_compatible(&field_types[i], &arg_type) {
+                            if !self.value_compatible(&field_types[i], &arg_type, arg) {
                                 return Err(format!(
                                     "Argument {} of constructor '{}': expected {}, got {}",
                                     i + 1,
@@ -722,25 +1651,176 @@ impl TypeChecker {
                     }
                 }
 
-                // Regular function call
+                // A name that reaches an imported module's function calls it,
+                // even when a builtin has the same name
                 if let Expression::Variable(func_name) = &**callee {
-                    if let Some((param_types, return_type)) = self.functions.get(func_name).cloned() {
-                        // Get full parameter info if available (for named args support)
-                        if let Some(param_info) = self.function_params.get(func_name).cloned() {
-                            // Track which parameters have been provided
-                            let mut provided = vec![false; param_info.len()];
+                    let function = self.names.resolve(self.current_module.as_deref(), func_name)?;
+                    if function != *func_name {
+                        return self.check_expression(&Expression::Call {
+                            callee: Box::new(Expression::Variable(function)),
+                            args: args.clone(),
+                            named_args: named_args.clone(),
+                            line: *line,
+                        });
+                    }
+                }
 
-                            // Process positional arguments
-                            if args.len() > param_info.len() {
+                // Generic print() accepts any scalar (a user-defined print takes precedence)
+                if let Expression::Variable(func_name) = &**callee {
+                    if func_name == "print" && !self.functions.contains_key("print") {
+                        if args.len() != 1 || !named_args.is_empty() {
+                            return Err(format!("print() takes exactly 1 argument, got {}", args.len() + named_args.len()));
+                        }
+                        let arg_type = self.check_expression(&args[0])?;
+                        if let Type::Custom(class_name) = &arg_type {
+                            self.check_printable(class_name)?;
+                            return Ok(Type::Void);
+                        }
+                        if arg_type.integer_rank().is_none() && !matches!(arg_type, Type::Float | Type::BigInt | Type::Decimal | Type::Bool | Type::Str) {
+                            return Err(format!(
+                                "print() only accepts int, float, bool, or str values, got {}",
+                                arg_type
+                            ));
+                        }
+                        return Ok(Type::Void);
+                    }
+                }
+
+                // truthy(x): an explicit conversion, since conditions only take bool
+                if let Expression::Variable(func_name) = &**callee {
+                    if func_name == "truthy" {
+                        if args.len() != 1 || !named_args.is_empty() {
+                            return Err(format!("truthy() takes exactly 1 argument, got {}", args.len() + named_args.len()));
+                        }
+                        let arg_type = self.check_expression(&args[0])?;
+                        let convertible = arg_type.integer_rank().is_some()
+                            || matches!(
+                                arg_type,
+                                Type::Float | Type::Bool | Type::Str | Type::List(_) | Type::Dict(_, _) | Type::Optional(_)
+                            );
+                        if !convertible {
+                            return Err(format!(
+                                "truthy() takes an int, float, bool, str, list, dict or optional value, got {}",
+                                arg_type
+                            ));
+                        }
+                        return Ok(Type::Bool);
+                    }
+                }
+
+                // int(s) / float(s) parse a str; str(x) formats a number, bool or printable object.
+                // Numeric conversions go through `as`, which has its own rules
+                if let Expression::Variable(func_name) = &**callee {
+                    if matches!(func_name.as_str(), "int" | "float" | "str") {
+                        if args.len() != 1 || !named_args.is_empty() {
+                            return Err(format!("{}() takes exactly 1 argument, got {}", func_name, args.len() + named_args.len()));
+                        }
+                        let arg_type = self.check_expression(&args[0])?;
+                        if func_name == "str" {
+                            self.check_text_value("str() takes", &arg_type)?;
+                            return Ok(Type::Str);
+                        }
+                        if arg_type != Type::Str {
+                            let hint = if arg_type.integer_rank().is_some() || arg_type == Type::Float {
+                                format!("; use 'x as {}' to convert between numbers", func_name)
+                            } else {
+                                String::new()
+                            };
+                            return Err(format!("{}() takes a str to parse, got {}{}", func_name, arg_type, hint));
+                        }
+                        return Ok(if func_name == "int" { Type::Int } else { Type::Float });
+                    }
+                }
+
+                // format(template, ...): any number of values that str() takes. A literal
+                // template is checked here, so a missing value isn't left to the runtime
+                if let Expression::Variable(func_name) = &**callee {
+                    if func_name == "format" {
+                        if !named_args.is_empty() {
+                            return Err("format() takes no named arguments".to_string());
+                        }
+                        let Some((template, values)) = args.split_first() else {
+                            return Err("format() takes a template and the values to fill it".to_string());
+                        };
+                        let template_type = self.check_expression(template)?;
+                        if template_type != Type::Str {
+                            return Err(format!("format() template must be str, got {}", template_type));
+                        }
+                        if let Expression::StringLiteral(text) = template {
+                            let needed = Self::format_placeholders(text)?;
+                            if needed > values.len() {
                                 return Err(format!(
-                                    "Function '{}' takes at most {} arguments, got {}",
-                                    func_name, param_info.len(), args.len()
+                                    "format() template needs {} value(s), got {}",
+                                    needed,
+                                    values.len()
                                 ));
                             }
-
+                        }
+                        for (i, value) in values.iter().enumerate() {
+                            let value_type = self.check_expression(value)?;
+                            self.check_text_value(&format!("format() value {} must be", i + 1), &value_type)?;
+                        }
+                        return Ok(Type::Str);
+                    }
+                }
+
+                // input() / input(prompt) and read_line() read standard input
+                // (a read_line the program or std/io.ws defines takes precedence)
+                if let Expression::Variable(func_name) = &**callee {
+                    if func_name == "input" {
+                        if args.len() > 1 || !named_args.is_empty() {
+                            return Err(format!("input() takes at most 1 argument, got {}", args.len() + named_args.len()));
+                        }
+                        if let Some(prompt) = args.first() {
+                            let prompt_type = self.check_expression(prompt)?;
+                            if prompt_type != Type::Str {
+                                return Err(format!("input() prompt must be str, got {}", prompt_type));
+                            }
+                        }
+                        return Ok(Type::Str);
+                    }
+                    if func_name == "read_line" && !self.functions.contains_key("read_line") {
+                        if !args.is_empty() || !named_args.is_empty() {
+                            return Err(format!("read_line() takes no arguments, got {}", args.len() + named_args.len()));
+                        }
+                        return Ok(Type::Optional(Box::new(Type::Str)));
+                    }
+                }
+
+                // Ok(value) / Err(error): the other side is filled in by the expected type
+                if let Expression::Variable(func_name) = &**callee {
+                    if (func_name == "Ok" || func_name == "Err") && !self.functions.contains_key(func_name) {
+                        if args.len() != 1 || !named_args.is_empty() {
+                            return Err(format!("{}() takes exactly 1 argument", func_name));
+                        }
+                        let payload_type = self.check_expression(&args[0])?;
+                        return Ok(if func_name == "Ok" {
+                            Type::Result(Box::new(payload_type), Box::new(Type::Inferred))
+                        } else {
+                            Type::Result(Box::new(Type::Inferred), Box::new(payload_type))
+                        });
+                    }
+                }
+
+                // Regular function call
+                if let Expression::Variable(func_name) = &**callee {
+                    if let Some((param_types, return_type)) = self.functions.get(func_name).cloned() {
+                        // Get full parameter info if available (for named args support)
+                        if let Some(param_info) = self.function_params.get(func_name).cloned() {
+                            // Track which parameters have been provided
+                            let mut provided = vec![false; param_info.len()];
+
+                            // Process positional arguments
+                            if args.len() > param_info.len() {
+                                return Err(format!(
+                                    "Function '{}' takes at most {} arguments, got {}",
+                                    func_name, param_info.len(), args.len()
+                                ));
+                            }
+
                             for (i, arg) in args.iter().enumerate() {
                                 let arg_type = self.check_expression(arg)?;
-                                if !self.types_compatible(&param_info[i].param_type, &arg_type) {
+                                if !self.value_compatible(&param_info[i].param_type, &arg_type, arg) {
                                     return Err(format!(
                                         "Argument {} of function '{}': expected {}, got {}",
                                         i + 1, func_name, param_info[i].param_type, arg_type
@@ -762,7 +1842,7 @@ impl TypeChecker {
                                             ));
                                         }
                                         let arg_type = self.check_expression(value)?;
-                                        if !self.types_compatible(&param_info[idx].param_type, &arg_type) {
+                                        if !self.value_compatible(&param_info[idx].param_type, &arg_type, value) {
                                             return Err(format!(
                                                 "Named argument '{}' of function '{}': expected {}, got {}",
                                                 name, func_name, param_info[idx].param_type, arg_type
@@ -801,7 +1881,7 @@ impl TypeChecker {
 
                             for (i, arg) in args.iter().enumerate() {
                                 let arg_type = self.check_expression(arg)?;
-                                if !self.types_compatible(&param_types[i], &arg_type) {
+                                if !self.value_compatible(&param_types[i], &arg_type, arg) {
                                     return Err(format!(
                                         "Argument {} of function '{}': expected {}, got {}",
                                         i + 1, func_name, param_types[i], arg_type
@@ -812,7 +1892,11 @@ impl TypeChecker {
                             Ok(return_type)
                         }
                     } else {
-                        Err(format!("Undefined function '{}'", func_name))
+                        Err(format!(
+                            "Undefined function '{}'{}",
+                            func_name,
+                            did_you_mean(func_name, self.function_names())
+                        ))
                     }
                 } else {
                     Err("Only simple function calls are supported".to_string())
@@ -822,7 +1906,7 @@ impl TypeChecker {
             Expression::MemberAccess { object, member } => {
                 // Check if this is a module.function reference
                 if let Expression::Variable(module_name) = &**object {
-                    if self.modules.contains_key(module_name) {
+                    if self.names.modules().contains_key(module_name) {
                         // This is a module reference - it will be validated in the Call/MethodCall context
                         // For now, return void as a placeholder since this should only appear in calls
                         return Ok(Type::Void);
@@ -846,8 +1930,10 @@ impl TypeChecker {
                             return Ok(field_type.clone());
                         } else {
                             return Err(format!(
-                                "Class '{}' has no field '{}'",
-                                class_name, member
+                                "Class '{}' has no field '{}'{}",
+                                class_name,
+                                member,
+                                did_you_mean(member, class_info.field_map.keys().map(String::as_str))
                             ));
                         }
                     }
@@ -856,6 +1942,7 @@ impl TypeChecker {
                 // Handle .length property for arrays, lists, and strings
                 // Also handle Optional types by unwrapping and checking inner type
                 if member == "length" {
+                    self.check_optional_use(object, &obj_type)?;
                     match &obj_type {
                         Type::Array(_, _) | Type::List(_) | Type::Str => Ok(Type::Int),
                         Type::Optional(inner) => {
@@ -867,18 +1954,83 @@ impl TypeChecker {
                         }
                         _ => Err(format!("Type {} has no property '{}'", obj_type, member)),
                     }
+                } else if member == "byte_length" && obj_type == Type::Str {
+                    // .length counts characters; .byte_length is the size of the UTF-8 encoding
+                    Ok(Type::Int)
                 } else {
-                    Err(format!("Unknown property '{}' on type {}", member, obj_type))
+                    let has_length = matches!(
+                        &obj_type,
+                        Type::Array(_, _) | Type::List(_) | Type::Str | Type::Optional(_)
+                    );
+                    let properties: &[&str] = match &obj_type {
+                        Type::Str => &["length", "byte_length"],
+                        _ if has_length => &["length"],
+                        _ => &[],
+                    };
+                    Err(format!(
+                        "Unknown property '{}' on type {}{}",
+                        member,
+                        obj_type,
+                        did_you_mean(member, properties.iter().copied())
+                    ))
+                }
+            }
+
+            Expression::OptionalMemberAccess { object, member } => {
+                let obj_type = self.check_expression(object)?;
+                let class_name = match &obj_type {
+                    Type::Optional(inner) => match inner.as_ref() {
+                        Type::Custom(class_name) if self.classes.contains_key(class_name) => class_name.clone(),
+                        _ => {
+                            return Err(format!(
+                                "Optional chaining '?.{}' requires an optional class instance, got {}",
+                                member, obj_type
+                            ))
+                        }
+                    },
+                    _ => {
+                        return Err(format!(
+                            "Optional chaining '?.{}' requires an Optional value, got {}; use '.' instead",
+                            member, obj_type
+                        ))
+                    }
+                };
+
+                if member.starts_with('_') {
+                    return Err(format!(
+                        "Cannot access private field '{}' of class '{}'",
+                        member, class_name
+                    ));
+                }
+                let class_info = &self.classes[&class_name];
+                let field_type = class_info.field_map.get(member).cloned().ok_or_else(|| {
+                    format!(
+                        "Class '{}' has no field '{}'{}",
+                        class_name,
+                        member,
+                        did_you_mean(member, class_info.field_map.keys().map(String::as_str))
+                    )
+                })?;
+
+                match field_type {
+                    // Optional[int/float/bool] has no boxed representation yet
+                    Type::Int | Type::Float | Type::Bool => Err(format!(
+                        "Optional chaining '?.{}' is not supported for {} fields; only str, list, dict, and class fields can be chained",
+                        member, field_type
+                    )),
+                    Type::Optional(_) => Ok(field_type),
+                    other => Ok(Type::Optional(Box::new(other))),
                 }
             }
 
             Expression::Assignment { target, value } => {
+                self.count_range_index_use(target, false);
                 let var_type = self
                     .lookup_variable(target)
-                    .ok_or_else(|| format!("Undefined variable '{}'", target))?;
+                    .ok_or_else(|| self.undefined_variable_error(target))?;
                 let value_type = self.check_expression(value)?;
 
-                if !self.types_compatible(&var_type, &value_type) {
+                if !self.value_compatible(&var_type, &value_type, value) {
                     return Err(format!(
                         "Cannot assign {} to variable '{}' of type {}",
                         value_type, target, var_type
@@ -953,11 +2105,19 @@ impl TypeChecker {
                     }
                 }
 
+                self.check_dict_types(&key_type, &val_type)?;
                 Ok(Type::Dict(Box::new(key_type), Box::new(val_type)))
             }
 
             Expression::Index { object, index, line: _ } => {
+                if let (Expression::Variable(list), Expression::Variable(idx)) = (&**object, &**index) {
+                    let range_loop = self.range_index_loops.iter().rev().find(|range_loop| range_loop.index == *idx);
+                    if range_loop.is_some_and(|range_loop| range_loop.list == *list) {
+                        self.count_range_index_use(idx, true);
+                    }
+                }
                 let obj_type = self.check_expression(object)?;
+                self.check_optional_use(object, &obj_type)?;
                 let idx_type = self.check_expression(index)?;
 
                 // Handle Optional types by unwrapping
@@ -977,6 +2137,7 @@ impl TypeChecker {
                         Ok(*elem_type)
                     }
                     Type::Dict(key_type, val_type) => {
+                        self.check_dict_types(&key_type, &val_type)?;
                         if !self.types_compatible(&key_type, &idx_type) {
                             return Err(format!(
                                 "Dict key type mismatch: expected {}, got {}",
@@ -991,7 +2152,8 @@ impl TypeChecker {
 
             Expression::IndexAssignment { object, index, value, line: _ } => {
                 let obj_type = self.lookup_variable(object)
-                    .ok_or_else(|| format!("Undefined variable '{}'", object))?;
+                    .ok_or_else(|| self.undefined_variable_error(object))?;
+                self.check_optional_use(&Expression::Variable(object.clone()), &obj_type)?;
                 let idx_type = self.check_expression(index)?;
                 let val_type = self.check_expression(value)?;
 
@@ -1006,7 +2168,7 @@ impl TypeChecker {
                         if idx_type != Type::Int {
                             return Err(format!("Array/List index must be int, got {}", idx_type));
                         }
-                        if !self.types_compatible(elem_type, &val_type) {
+                        if !self.value_compatible(elem_type, &val_type, value) {
                             return Err(format!(
                                 "Cannot assign {} to {}[int] (expected {})",
                                 val_type, obj_type, elem_type
@@ -1015,13 +2177,14 @@ impl TypeChecker {
                         Ok(Type::Void)
                     }
                     Type::Dict(key_type, elem_type) => {
+                        self.check_dict_types(key_type, elem_type)?;
                         if !self.types_compatible(key_type, &idx_type) {
                             return Err(format!(
                                 "Dict key type mismatch: expected {}, got {}",
                                 key_type, idx_type
                             ));
                         }
-                        if !self.types_compatible(elem_type, &val_type) {
+                        if !self.value_compatible(elem_type, &val_type, value) {
                             return Err(format!(
                                 "Cannot assign {} to {}[{}] (expected {})",
                                 val_type, obj_type, key_type, elem_type
@@ -1033,22 +2196,56 @@ impl TypeChecker {
                 }
             }
 
-            Expression::MethodCall { object, method, args } => {
+            Expression::FieldAssignment { object, field, value } => {
+                let obj_type = self.check_expression(object)?;
+                let Type::Custom(class_name) = &obj_type else {
+                    return Err(format!("Cannot assign to field '{}' of type {}", field, obj_type));
+                };
+                let class_info = self.classes.get(class_name).ok_or_else(|| format!("Unknown class '{}'", class_name))?;
+                let field_type = match class_info.field_map.get(field) {
+                    Some(field_type) => field_type.clone(),
+                    None => {
+                        return Err(format!(
+                            "Class '{}' has no field '{}'{}",
+                            class_name,
+                            field,
+                            did_you_mean(field, class_info.field_map.keys().map(String::as_str))
+                        ))
+                    }
+                };
+                if field.starts_with('_') {
+                    return Err(format!("Cannot access private field '{}' of class '{}'", field, class_name));
+                }
+
+                let val_type = self.check_expression(value)?;
+                if !self.value_compatible(&field_type, &val_type, value) {
+                    return Err(format!(
+                        "Cannot assign {} to field '{}.{}' of type {}",
+                        val_type, class_name, field, field_type
+                    ));
+                }
+                Ok(Type::Void)
+            }
+
+            Expression::MethodCall { object, method, args, named_args } => {
                 // Check if this is a module.function() call
                 if let Expression::Variable(module_name) = &**object {
-                    if let Some(module_functions) = self.modules.get(module_name) {
+                    if let Some(module) = self.names.modules().get(module_name) {
                         // This is a module function call
-                        if !module_functions.contains(method) {
+                        if !module.functions.contains(method) {
                             return Err(format!(
-                                "Module '{}' has no function '{}'",
-                                module_name, method
+                                "Module '{}' has no function '{}'{}",
+                                module_name,
+                                method,
+                                did_you_mean(method, module.functions.iter().map(String::as_str))
                             ));
                         }
 
                         // Look up the function signature
-                        if let Some((param_types, return_type)) = self.functions.get(method).cloned() {
+                        let function = self.names.resolve_qualified(module_name, method)?;
+                        if let Some((param_types, return_type)) = self.functions.get(&function).cloned() {
                             // Check if we have full param info (for defaults)
-                            if let Some(param_info) = self.function_params.get(method).cloned() {
+                            if let Some(param_info) = self.function_params.get(&function).cloned() {
                                 // Track which parameters have been provided
                                 let mut provided = vec![false; param_info.len()];
 
@@ -1062,7 +2259,7 @@ impl TypeChecker {
 
                                 for (i, arg) in args.iter().enumerate() {
                                     let arg_type = self.check_expression(arg)?;
-                                    if !self.types_compatible(&param_info[i].param_type, &arg_type) {
+                                    if !self.value_compatible(&param_info[i].param_type, &arg_type, arg) {
                                         return Err(format!(
                                             "Argument {} of function '{}.{}': expected {}, got {}",
                                             i + 1, module_name, method, param_info[i].param_type, arg_type
@@ -1071,6 +2268,30 @@ impl TypeChecker {
                                     provided[i] = true;
                                 }
 
+                                // Process named arguments
+                                for (name, value) in named_args {
+                                    let Some(idx) = param_info.iter().position(|p| &p.name == name) else {
+                                        return Err(format!(
+                                            "Function '{}.{}' has no parameter named '{}'",
+                                            module_name, method, name
+                                        ));
+                                    };
+                                    if provided[idx] {
+                                        return Err(format!(
+                                            "Function '{}.{}': parameter '{}' specified multiple times",
+                                            module_name, method, name
+                                        ));
+                                    }
+                                    let arg_type = self.check_expression(value)?;
+                                    if !self.value_compatible(&param_info[idx].param_type, &arg_type, value) {
+                                        return Err(format!(
+                                            "Named argument '{}' of function '{}.{}': expected {}, got {}",
+                                            name, module_name, method, param_info[idx].param_type, arg_type
+                                        ));
+                                    }
+                                    provided[idx] = true;
+                                }
+
                                 // Check all required parameters are provided
                                 for (i, info) in param_info.iter().enumerate() {
                                     if !provided[i] && !info.has_default {
@@ -1084,6 +2305,9 @@ impl TypeChecker {
                                 return Ok(return_type);
                             } else {
                                 // Fallback: no param info, use simple validation
+                                if !named_args.is_empty() {
+                                    return Err(format!("Function '{}.{}' does not take named arguments", module_name, method));
+                                }
                                 if args.len() != param_types.len() {
                                     return Err(format!(
                                         "Function '{}.{}' expects {} arguments, got {}",
@@ -1096,7 +2320,7 @@ impl TypeChecker {
 
                                 for (i, arg) in args.iter().enumerate() {
                                     let arg_type = self.check_expression(arg)?;
-                                    if !self.types_compatible(&param_types[i], &arg_type) {
+                                    if !self.value_compatible(&param_types[i], &arg_type, arg) {
                                         return Err(format!(
                                             "Argument {} of function '{}.{}': expected {}, got {}",
                                             i + 1,
@@ -1116,10 +2340,21 @@ impl TypeChecker {
                     }
                 }
 
+                // Only module functions take named arguments
+                if let Some((name, _)) = named_args.first() {
+                    return Err(format!("Method '{}' does not take named arguments (got '{}')", method, name));
+                }
+
                 let obj_type = self.check_expression(object)?;
 
                 // Handle class methods
                 if let Type::Custom(class_name) = &obj_type {
+                    if method == "deinit" {
+                        return Err(format!(
+                            "'deinit' cannot be called directly; it runs when the last reference to a '{}' goes away",
+                            class_name
+                        ));
+                    }
                     // Check for private method access
                     if method.starts_with('_') {
                         return Err(format!(
@@ -1128,9 +2363,8 @@ impl TypeChecker {
                         ));
                     }
 
-                    // Look up the method in functions as Class::method
-                    let method_full_name = format!("{}::{}", class_name, method);
-                    if let Some((param_types, return_type)) = self.functions.get(&method_full_name).cloned() {
+                    // Look up the method as Class::method, falling back to the base classes
+                    if let Some((param_types, return_type)) = self.find_method(class_name, method) {
                         // First parameter should be self
                         if param_types.is_empty() {
                             return Err(format!(
@@ -1153,7 +2387,7 @@ impl TypeChecker {
 
                         for (i, arg) in args.iter().enumerate() {
                             let arg_type = self.check_expression(arg)?;
-                            if !self.types_compatible(&method_params[i], &arg_type) {
+                            if !self.value_compatible(&method_params[i], &arg_type, arg) {
                                 return Err(format!(
                                     "Argument {} of method '{}.{}': expected {}, got {}",
                                     i + 1,
@@ -1167,9 +2401,13 @@ impl TypeChecker {
 
                         return Ok(return_type);
                     } else {
+                        let prefix = format!("{}::", class_name);
+                        let methods = self.functions.keys().filter_map(|key| key.strip_prefix(prefix.as_str()));
                         return Err(format!(
-                            "Class '{}' has no method '{}'",
-                            class_name, method
+                            "Class '{}' has no method '{}'{}",
+                            class_name,
+                            method,
+                            did_you_mean(method, methods)
                         ));
                     }
                 }
@@ -1181,7 +2419,7 @@ impl TypeChecker {
                                 return Err("push() takes exactly 1 argument".to_string());
                             }
                             let arg_type = self.check_expression(&args[0])?;
-                            if !self.types_compatible(&elem_type, &arg_type) {
+                            if !self.value_compatible(&elem_type, &arg_type, &args[0]) {
                                 return Err(format!(
                                     "push() argument type mismatch: expected {}, got {}",
                                     elem_type, arg_type
@@ -1205,10 +2443,176 @@ impl TypeChecker {
                             }
                             Ok(*elem_type)
                         }
-                        _ => Err(format!("Unknown method '{}' on list", method)),
+                        "insert" => {
+                            if args.len() != 2 {
+                                return Err("insert() takes exactly 2 arguments".to_string());
+                            }
+                            let idx_type = self.check_expression(&args[0])?;
+                            if idx_type != Type::Int {
+                                return Err("insert() index must be int".to_string());
+                            }
+                            let arg_type = self.check_expression(&args[1])?;
+                            if !self.value_compatible(&elem_type, &arg_type, &args[1]) {
+                                return Err(format!(
+                                    "insert() argument type mismatch: expected {}, got {}",
+                                    elem_type, arg_type
+                                ));
+                            }
+                            Ok(Type::Void)
+                        }
+                        "remove_at" => {
+                            if args.len() != 1 {
+                                return Err("remove_at() takes exactly 1 argument".to_string());
+                            }
+                            let idx_type = self.check_expression(&args[0])?;
+                            if idx_type != Type::Int {
+                                return Err("remove_at() index must be int".to_string());
+                            }
+                            Ok(*elem_type)
+                        }
+                        "index_of" | "contains" => {
+                            if args.len() != 1 {
+                                return Err(format!("{}() takes exactly 1 argument", method));
+                            }
+                            if matches!(*elem_type, Type::Tuple(_)) {
+                                return Err(format!(
+                                    "{}() can't compare tuples; check their fields in a loop",
+                                    method
+                                ));
+                            }
+                            let arg_type = self.check_expression(&args[0])?;
+                            if !self.value_compatible(&elem_type, &arg_type, &args[0]) {
+                                return Err(format!(
+                                    "{}() argument type mismatch: expected {}, got {}",
+                                    method, elem_type, arg_type
+                                ));
+                            }
+                            Ok(if method == "index_of" { Type::Int } else { Type::Bool })
+                        }
+                        "clear" => {
+                            if !args.is_empty() {
+                                return Err("clear() takes no arguments".to_string());
+                            }
+                            Ok(Type::Void)
+                        }
+                        "map" | "filter" => {
+                            if args.len() != 1 {
+                                return Err(format!("{}() takes exactly 1 argument", method));
+                            }
+                            let (name, params, return_type) = self.passed_function(method, &args[0], 1)?;
+                            if !self.types_compatible(&params[0], &elem_type) {
+                                return Err(format!(
+                                    "{}(): function '{}' takes {}, but the list holds {}",
+                                    method, name, params[0], elem_type
+                                ));
+                            }
+                            if method == "filter" {
+                                if return_type != Type::Bool {
+                                    return Err(format!(
+                                        "filter(): function '{}' must return bool, not {}",
+                                        name, return_type
+                                    ));
+                                }
+                                return Ok(Type::List(elem_type));
+                            }
+                            if return_type == Type::Void || return_type == Type::Custom("void".to_string()) {
+                                return Err(format!("map(): function '{}' returns nothing", name));
+                            }
+                            Ok(Type::List(Box::new(return_type)))
+                        }
+                        "reduce" => {
+                            if args.len() != 2 {
+                                return Err("reduce() takes exactly 2 arguments".to_string());
+                            }
+                            // reduce(f, init) with f(total: A, item: T) -> A
+                            let (name, params, return_type) = self.passed_function(method, &args[0], 2)?;
+                            if !self.types_compatible(&params[1], &elem_type) {
+                                return Err(format!(
+                                    "reduce(): function '{}' takes {} as its item, but the list holds {}",
+                                    name, params[1], elem_type
+                                ));
+                            }
+                            if !self.types_compatible(&params[0], &return_type) {
+                                return Err(format!(
+                                    "reduce(): function '{}' must return its total's type {}, not {}",
+                                    name, params[0], return_type
+                                ));
+                            }
+                            let init_type = self.check_expression(&args[1])?;
+                            if !self.value_compatible(&params[0], &init_type, &args[1]) {
+                                return Err(format!(
+                                    "reduce() argument type mismatch: expected {}, got {}",
+                                    params[0], init_type
+                                ));
+                            }
+                            Ok(params[0].clone())
+                        }
+                        "reverse" | "shuffle" => {
+                            if !args.is_empty() {
+                                return Err(format!("{}() takes no arguments", method));
+                            }
+                            Ok(Type::Void)
+                        }
+                        "extend" => {
+                            if args.len() != 1 {
+                                return Err("extend() takes exactly 1 argument".to_string());
+                            }
+                            let list_type = Type::List(elem_type.clone());
+                            let arg_type = self.check_expression(&args[0])?;
+                            if !self.value_compatible(&list_type, &arg_type, &args[0]) {
+                                return Err(format!(
+                                    "extend() argument type mismatch: expected {}, got {}",
+                                    list_type, arg_type
+                                ));
+                            }
+                            Ok(Type::Void)
+                        }
+                        "sort" => {
+                            if !args.is_empty() {
+                                return Err("sort() takes no arguments".to_string());
+                            }
+                            if !matches!(*elem_type, Type::Int | Type::Float | Type::Str | Type::Bool) {
+                                return Err(format!(
+                                    "sort() needs a list of int, float, str or bool, not list[{}]",
+                                    elem_type
+                                ));
+                            }
+                            Ok(Type::Void)
+                        }
+                        "sort_by" => {
+                            if args.len() != 1 {
+                                return Err("sort_by() takes exactly 1 argument".to_string());
+                            }
+                            // sort_by(before) with before(a: T, b: T) -> bool, True when a goes first
+                            let (name, params, return_type) = self.passed_function(method, &args[0], 2)?;
+                            if let Some(param) = params.iter().find(|param| !self.types_compatible(param, &elem_type)) {
+                                return Err(format!(
+                                    "sort_by(): function '{}' takes {}, but the list holds {}",
+                                    name, param, elem_type
+                                ));
+                            }
+                            if return_type != Type::Bool {
+                                return Err(format!(
+                                    "sort_by(): function '{}' must return bool, not {}",
+                                    name, return_type
+                                ));
+                            }
+                            Ok(Type::Void)
+                        }
+                        _ => Err(format!(
+                            "Unknown method '{}' on list{}",
+                            method,
+                            did_you_mean(
+                                method,
+                                [
+                                    "push", "pop", "get", "insert", "remove_at", "index_of", "contains", "clear", "sort",
+                                    "sort_by", "reverse", "shuffle", "extend", "map", "filter", "reduce",
+                                ]
+                            )
+                        )),
                     },
                     Type::Str => match method.as_str() {
-                        "upper" | "lower" => {
+                        "upper" | "lower" | "strip" | "lstrip" | "rstrip" => {
                             if !args.is_empty() {
                                 return Err(format!("{}() takes no arguments", method));
                             }
@@ -1227,6 +2631,28 @@ impl TypeChecker {
                             }
                             Ok(Type::Bool)
                         }
+                        "replace" => {
+                            if args.len() != 2 {
+                                return Err("replace() takes exactly 2 arguments".to_string());
+                            }
+                            for arg in args {
+                                let arg_type = self.check_expression(arg)?;
+                                if arg_type != Type::Str {
+                                    return Err(format!("replace() arguments must be str, got {}", arg_type));
+                                }
+                            }
+                            Ok(Type::Str)
+                        }
+                        "find" | "startswith" | "endswith" => {
+                            if args.len() != 1 {
+                                return Err(format!("{}() takes exactly 1 argument", method));
+                            }
+                            let arg_type = self.check_expression(&args[0])?;
+                            if arg_type != Type::Str {
+                                return Err(format!("{}() argument must be str, got {}", method, arg_type));
+                            }
+                            Ok(if method == "find" { Type::Int } else { Type::Bool })
+                        }
                         "split" => {
                             if args.len() != 1 {
                                 return Err("split() takes exactly 1 argument".to_string());
@@ -1240,25 +2666,205 @@ impl TypeChecker {
                             }
                             Ok(Type::List(Box::new(Type::Str)))
                         }
-                        _ => Err(format!("Unknown method '{}' on str", method)),
+                        "chars" => {
+                            if !args.is_empty() {
+                                return Err("chars() takes no arguments".to_string());
+                            }
+                            Ok(Type::List(Box::new(Type::Str)))
+                        }
+                        // ", ".join(parts)
+                        "join" => {
+                            if args.len() != 1 {
+                                return Err("join() takes exactly 1 argument".to_string());
+                            }
+                            let arg_type = self.check_expression(&args[0])?;
+                            if arg_type != Type::List(Box::new(Type::Str)) {
+                                return Err(format!(
+                                    "join() argument must be list[str], got {}",
+                                    arg_type
+                                ));
+                            }
+                            Ok(Type::Str)
+                        }
+                        _ => Err(format!(
+                            "Unknown method '{}' on str{}",
+                            method,
+                            did_you_mean(
+                                method,
+                                [
+                                    "upper", "lower", "strip", "lstrip", "rstrip", "contains", "find", "startswith", "endswith",
+                                    "replace", "split", "join", "chars",
+                                ]
+                            )
+                        )),
+                    },
+                    Type::Result(ok_type, err_type) => match method.as_str() {
+                        "is_ok" | "is_err" | "unwrap" | "unwrap_err" => {
+                            if !args.is_empty() {
+                                return Err(format!("{}() takes no arguments", method));
+                            }
+                            Ok(match method.as_str() {
+                                "unwrap" => *ok_type,
+                                "unwrap_err" => *err_type,
+                                _ => Type::Bool,
+                            })
+                        }
+                        "unwrap_or" => {
+                            if args.len() != 1 {
+                                return Err("unwrap_or() takes exactly 1 argument".to_string());
+                            }
+                            let arg_type = self.check_expression(&args[0])?;
+                            if !self.value_compatible(&ok_type, &arg_type, &args[0]) {
+                                return Err(format!(
+                                    "unwrap_or() default must be {}, got {}",
+                                    ok_type, arg_type
+                                ));
+                            }
+                            Ok(*ok_type)
+                        }
+                        _ => Err(format!(
+                            "Unknown method '{}' on Result{}",
+                            method,
+                            did_you_mean(method, ["is_ok", "is_err", "unwrap", "unwrap_err", "unwrap_or"])
+                        )),
+                    },
+                    Type::Dict(key_type, val_type) => match method.as_str() {
+                        "keys" | "values" | "items" => {
+                            if !args.is_empty() {
+                                return Err(format!("{}() takes no arguments", method));
+                            }
+                            Ok(Type::List(match method.as_str() {
+                                "keys" => key_type,
+                                "values" => val_type,
+                                _ => Box::new(Type::Tuple(vec![*key_type, *val_type])),
+                            }))
+                        }
+                        "remove" | "pop" | "get" => {
+                            let expected_args = if method == "remove" { 1 } else { 2 };
+                            if args.len() != expected_args {
+                                return Err(if method == "remove" {
+                                    "remove() takes exactly 1 argument".to_string()
+                                } else {
+                                    format!("{}() takes a key and a default value", method)
+                                });
+                            }
+                            let arg_type = self.check_expression(&args[0])?;
+                            if !self.types_compatible(&key_type, &arg_type) {
+                                return Err(format!(
+                                    "{}() key type mismatch: expected {}, got {}",
+                                    method, key_type, arg_type
+                                ));
+                            }
+                            if method == "remove" {
+                                return Ok(Type::Bool);
+                            }
+                            let default_type = self.check_expression(&args[1])?;
+                            if !self.value_compatible(&val_type, &default_type, &args[1]) {
+                                return Err(format!(
+                                    "{}() default must be {}, got {}",
+                                    method, val_type, default_type
+                                ));
+                            }
+                            Ok(*val_type)
+                        }
+                        "clear" => {
+                            if !args.is_empty() {
+                                return Err("clear() takes no arguments".to_string());
+                            }
+                            Ok(Type::Void)
+                        }
+                        _ => Err(format!(
+                            "Unknown method '{}' on dict{}",
+                            method,
+                            did_you_mean(method, ["keys", "values", "items", "get", "remove", "pop", "clear"])
+                        )),
+                    },
+                    Type::Decimal => match method.as_str() {
+                        "round" => {
+                            if args.len() != 1 {
+                                return Err("round() takes exactly 1 argument".to_string());
+                            }
+                            let arg_type = self.check_expression(&args[0])?;
+                            if arg_type.integer_rank().is_none() {
+                                return Err(format!("round() digits must be int, got {}", arg_type));
+                            }
+                            Ok(Type::Decimal)
+                        }
+                        _ => Err(format!(
+                            "Unknown method '{}' on decimal{}",
+                            method,
+                            did_you_mean(method, ["round"])
+                        )),
                     },
                     _ => Err(format!("Type {} has no methods", obj_type)),
                 }
             }
 
-            Expression::FString { parts: _, expressions } => {
-                // Type check all embedded expressions
-                for expr in expressions {
-                    self.check_expression(expr)?;
+            Expression::SuperCall { method, args } => {
+                let class_name = self.current_class.clone()
+                    .ok_or_else(|| format!("'super.{}()' can only be used inside a method", method))?;
+                let base = self.classes.get(&class_name).and_then(|info| info.base.clone())
+                    .ok_or_else(|| format!("'super.{}()' used in class '{}', which has no base class", method, class_name))?;
+                if method == "deinit" {
+                    return Err(format!(
+                        "'deinit' cannot be called directly; '{}.deinit' runs automatically after '{}.deinit'",
+                        base, class_name
+                    ));
                 }
-                // F-strings always result in a string
-                Ok(Type::Str)
-            }
-
-            Expression::TupleLiteral { elements } => {
-                let mut types = Vec::new();
-                for elem in elements {
-                    types.push(self.check_expression(elem)?);
+                let Some((param_types, return_type)) = self.find_method(&base, method) else {
+                    return Err(format!("Base class '{}' of '{}' has no method '{}'", base, class_name, method));
+                };
+                if self.classes[&base].abstract_methods.contains(method) {
+                    return Err(format!(
+                        "'super.{}()' calls abstract method '{}.{}', which has no implementation",
+                        method, base, method
+                    ));
+                }
+
+                // Arguments after the implicit self
+                let method_params = param_types.get(1..).unwrap_or_default();
+                if args.len() != method_params.len() {
+                    return Err(format!(
+                        "Method '{}.{}' expects {} arguments, got {}",
+                        base,
+                        method,
+                        method_params.len(),
+                        args.len()
+                    ));
+                }
+                for (i, arg) in args.iter().enumerate() {
+                    let arg_type = self.check_expression(arg)?;
+                    if !self.value_compatible(&method_params[i], &arg_type, arg) {
+                        return Err(format!(
+                            "Argument {} of method '{}.{}': expected {}, got {}",
+                            i + 1,
+                            base,
+                            method,
+                            method_params[i],
+                            arg_type
+                        ));
+                    }
+                }
+
+                Ok(return_type)
+            }
+
+            Expression::FString { parts: _, expressions } => {
+                // Type check all embedded expressions; class instances are
+                // formatted with their to_string()
+                for expr in expressions {
+                    if let Type::Custom(class_name) = self.check_expression(expr)? {
+                        self.check_printable(&class_name)?;
+                    }
+                }
+                // F-strings always result in a string
+                Ok(Type::Str)
+            }
+
+            Expression::TupleLiteral { elements } => {
+                let mut types = Vec::new();
+                for elem in elements {
+                    types.push(self.check_expression(elem)?);
                 }
                 Ok(Type::Tuple(types))
             }
@@ -1381,10 +2987,384 @@ impl TypeChecker {
         Ok(())
     }
 
+    fn validate_function_decorators(
+        &self,
+        function_name: &str,
+        params: &[Parameter],
+        return_type: &Type,
+        decorators: &[Decorator],
+    ) -> Result<(), String> {
+        for decorator in decorators {
+            match decorator.name.as_str() {
+                "memoize" => {
+                    // Cache keys are built from the arguments, so only value types are allowed
+                    for param in params {
+                        match &param.param_type {
+                            Type::Int | Type::Str | Type::Bool => {}
+                            other => {
+                                return Err(format!(
+                                    "Function '{}': @memoize requires int, str, or bool parameters, but '{}' has type {}",
+                                    function_name, param.name, other
+                                ));
+                            }
+                        }
+                    }
+
+                    // Cached results are stored in a dict[str, int] slot
+                    match return_type {
+                        Type::Int | Type::Float | Type::Bool | Type::Str => {}
+                        other => {
+                            return Err(format!(
+                                "Function '{}': @memoize requires a return type of int, float, bool, or str, got {}",
+                                function_name, other
+                            ));
+                        }
+                    }
+                }
+                "abstract" => {
+                    if self.current_class.is_none() {
+                        return Err(format!(
+                            "Function '{}': @abstract decorator is only allowed on class methods",
+                            function_name
+                        ));
+                    }
+                }
+                "shadows_builtin" => {
+                    if self.current_class.is_some() {
+                        return Err(format!(
+                            "Function '{}': @shadows_builtin decorator is only allowed on top-level functions",
+                            function_name
+                        ));
+                    }
+                }
+                "inline" | "noinline" => {
+                    // Lowered to LLVM alwaysinline/noinline; the two are contradictory
+                    let conflicting = if decorator.name == "inline" { "noinline" } else { "inline" };
+                    if decorators.iter().any(|d| d.name == conflicting) {
+                        return Err(format!(
+                            "Function '{}' cannot be both @inline and @noinline",
+                            function_name
+                        ));
+                    }
+                }
+                other => {
+                    return Err(format!(
+                        "Unknown decorator '@{}' on function '{}'",
+                        other, function_name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The function passed by name to `map`, `filter`, `reduce` or `sort_by`, with
+    /// its parameter and return types. There are no function values yet, so the
+    /// argument has to name a function the program defines.
+    fn passed_function(&self, method: &str, arg: &Expression, param_count: usize) -> Result<(String, Vec<Type>, Type), String> {
+        let name = match arg {
+            Expression::Variable(name) if self.lookup_variable(name).is_none() => name,
+            _ => return Err(format!("{}() takes the name of a function, like {}(double)", method, method)),
+        };
+        let function = self.names.resolve(self.current_module.as_deref(), name)?;
+        let (params, return_type) = self
+            .functions
+            .get(&function)
+            .filter(|_| !self.builtin_functions.contains(&function))
+            .ok_or_else(|| format!("{}(): '{}' is not a function defined in the program", method, name))?;
+        if params.len() != param_count {
+            return Err(format!(
+                "{}(): function '{}' must take {} parameter{}, but takes {}",
+                method,
+                name,
+                param_count,
+                if param_count == 1 { "" } else { "s" },
+                params.len()
+            ));
+        }
+        Ok((function, params.clone(), return_type.clone()))
+    }
+
+    /// Like `types_compatible`, but also accepts int literals (or a list of them) for a
+    /// sized integer type when the values are in range (`b: u8 = 255`)
+    fn value_compatible(&self, expected: &Type, actual: &Type, expr: &Expression) -> bool {
+        self.types_compatible(expected, actual) || Self::literal_fits(expected, expr)
+    }
+
+    fn literal_fits(expected: &Type, expr: &Expression) -> bool {
+        match (expected, Self::int_literal_value(expr)) {
+            (Type::Optional(inner), _) => Self::literal_fits(inner, expr),
+            // `r: Result[u8, str] = Ok(200)`
+            (Type::Result(ok_type, err_type), None) => match expr {
+                Expression::Call { callee, args, .. } if args.len() == 1 => match callee.as_ref() {
+                    Expression::Variable(name) if name == "Ok" => Self::literal_fits(ok_type, &args[0]),
+                    Expression::Variable(name) if name == "Err" => Self::literal_fits(err_type, &args[0]),
+                    _ => false,
+                },
+                _ => false,
+            },
+            // `bytes: list[u8] = [0, 255]`
+            (Type::List(inner), None) => match expr {
+                Expression::ListLiteral { elements } => {
+                    !elements.is_empty() && elements.iter().all(|e| Self::literal_fits(inner, e))
+                }
+                _ => false,
+            },
+            (expected, Some(value)) => expected
+                .integer_range()
+                .map(|(min, max)| (min..=max).contains(&value))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Value of `5` or `-5`
+    fn int_literal_value(expr: &Expression) -> Option<i64> {
+        match expr {
+            Expression::IntLiteral(value) => Some(*value),
+            Expression::Unary { op: UnaryOp::Negate, operand } => match operand.as_ref() {
+                Expression::IntLiteral(value) => Some(value.wrapping_neg()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Result type of a binary operator with a bigint or decimal operand. The other
+    /// operand may be the same type or any integer type, which is converted; floats
+    /// have to be converted explicitly so precision is never lost silently.
+    fn runtime_number_binary_type(op: &BinaryOp, left_type: &Type, right_type: &Type) -> Result<Type, String> {
+        let (number, other) = if matches!(left_type, Type::BigInt | Type::Decimal) {
+            (left_type, right_type)
+        } else {
+            (right_type, left_type)
+        };
+        let compatible = other == number || other.integer_rank().is_some();
+        match op {
+            BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide if compatible => {
+                Ok(number.clone())
+            }
+            // Decimal division always rounds, so there is no separate floor division
+            BinaryOp::FloorDivide | BinaryOp::Modulo if compatible && *number == Type::BigInt => Ok(Type::BigInt),
+            BinaryOp::Power if *left_type == Type::BigInt && right_type.integer_rank().is_some() => {
+                Ok(Type::BigInt)
+            }
+            BinaryOp::Equal
+            | BinaryOp::NotEqual
+            | BinaryOp::Less
+            | BinaryOp::Greater
+            | BinaryOp::LessEqual
+            | BinaryOp::GreaterEqual
+                if compatible => Ok(Type::Bool),
+            _ if *other == Type::Float && *number == Type::BigInt => Err(format!(
+                "Cannot mix bigint and float in {:?}; convert the bigint with 'as float'",
+                op
+            )),
+            _ if *other == Type::Float => Err(format!(
+                "Cannot mix decimal and float in {:?}; convert with 'as decimal' or 'as float'",
+                op
+            )),
+            _ if matches!(other, Type::BigInt | Type::Decimal) => Err(format!(
+                "Cannot mix bigint and decimal in {:?}",
+                op
+            )),
+            _ => Err(format!(
+                "Invalid operands for {:?}: {} and {}",
+                op, left_type, right_type
+            )),
+        }
+    }
+
+    /// Result type of integer arithmetic: the wider operand type, except that an
+    /// int literal takes the other operand's type (`b + 1` with b: u8 is u8)
+    fn integer_result_type(left_type: &Type, right_type: &Type, left: &Expression, right: &Expression) -> Type {
+        if left_type == right_type {
+            left_type.clone()
+        } else if *left_type == Type::Int && Self::literal_fits(right_type, left) {
+            right_type.clone()
+        } else if (*right_type == Type::Int && Self::literal_fits(left_type, right))
+            || left_type.integer_rank() > right_type.integer_rank()
+        {
+            left_type.clone()
+        } else {
+            right_type.clone()
+        }
+    }
+
+    /// Signature of `class_name.method`, looking through the base classes
+    fn find_method(&self, class_name: &str, method: &str) -> Option<(Vec<Type>, Type)> {
+        let mut class = Some(class_name);
+        while let Some(current) = class {
+            if let Some(signature) = self.functions.get(&format!("{}::{}", current, method)) {
+                return Some(signature.clone());
+            }
+            class = self.classes.get(current).and_then(|info| info.base.as_deref());
+        }
+        None
+    }
+
+    /// Check the arguments of `class_name(...)` against the parameters of its init
+    fn check_constructor_arguments(
+        &mut self,
+        class_name: &str,
+        params: &[ParamInfo],
+        args: &[Expression],
+        named_args: &[(String, Expression)],
+    ) -> Result<(), String> {
+        if args.len() > params.len() {
+            return Err(format!(
+                "Constructor '{}' takes at most {} arguments, got {}",
+                class_name, params.len(), args.len()
+            ));
+        }
+
+        let mut provided = vec![false; params.len()];
+        for (i, arg) in args.iter().enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            if !self.value_compatible(&params[i].param_type, &arg_type, arg) {
+                return Err(format!(
+                    "Argument {} of constructor '{}': expected {}, got {}",
+                    i + 1, class_name, params[i].param_type, arg_type
+                ));
+            }
+            provided[i] = true;
+        }
+
+        for (name, value) in named_args {
+            let idx = params.iter().position(|p| &p.name == name).ok_or_else(|| {
+                format!("Constructor '{}' has no parameter named '{}'", class_name, name)
+            })?;
+            if provided[idx] {
+                return Err(format!(
+                    "Constructor '{}': parameter '{}' specified multiple times",
+                    class_name, name
+                ));
+            }
+            let arg_type = self.check_expression(value)?;
+            if !self.value_compatible(&params[idx].param_type, &arg_type, value) {
+                return Err(format!(
+                    "Named argument '{}' of constructor '{}': expected {}, got {}",
+                    name, class_name, params[idx].param_type, arg_type
+                ));
+            }
+            provided[idx] = true;
+        }
+
+        if let Some(missing) = params.iter().zip(&provided).find(|(p, provided)| !**provided && !p.has_default) {
+            return Err(format!(
+                "Constructor '{}': missing required argument '{}'",
+                class_name, missing.0.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Instances print (and format in f-strings) through a `to_string(self) -> str`
+    /// method, defined on the class or inherited
+    fn check_printable(&self, class_name: &str) -> Result<(), String> {
+        match self.find_method(class_name, "to_string") {
+            Some((params, Type::Str)) if params.len() == 1 => Ok(()),
+            Some(_) => Err(format!(
+                "Class '{}' cannot be converted to a string: its 'to_string' method must take only self and return str",
+                class_name
+            )),
+            None => Err(format!(
+                "Class '{}' cannot be converted to a string: define 'to_string(self: {}) -> str'",
+                class_name, class_name
+            )),
+        }
+    }
+
+    /// A value str() and format() can turn into text: a number, bool, str, or an
+    /// object with to_string()
+    fn check_text_value(&self, what: &str, value_type: &Type) -> Result<(), String> {
+        if let Type::Custom(class_name) = value_type {
+            return self.check_printable(class_name);
+        }
+        if value_type.integer_rank().is_none()
+            && !matches!(value_type, Type::Float | Type::BigInt | Type::Decimal | Type::Bool | Type::Str)
+        {
+            return Err(format!("{} an int, float, bool or str value, got {}", what, value_type));
+        }
+        Ok(())
+    }
+
+    /// How many values a format() template uses, by the rules the runtime fills
+    /// it with: `{}` takes the next value, `{n}` value n, `{{` and `}}` are braces
+    fn format_placeholders(template: &str) -> Result<usize, String> {
+        let mut needed = 0;
+        let mut auto = None;
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                }
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err("format(): '{' without a matching '}'".to_string()),
+                        }
+                    }
+                    let index = if field.is_empty() {
+                        needed
+                    } else {
+                        field.parse::<usize>().map_err(|_| format!("format(): bad placeholder '{{{}}}'", field))?
+                    };
+                    if *auto.get_or_insert(field.is_empty()) != field.is_empty() {
+                        return Err("format(): can't mix {} and {0} placeholders".to_string());
+                    }
+                    needed = needed.max(index + 1);
+                }
+                '}' => return Err("format(): '}' without a matching '{'".to_string()),
+                _ => {}
+            }
+        }
+        Ok(needed)
+    }
+
+    /// Whether `class` is `ancestor` or inherits from it
+    fn is_subclass(&self, class: &str, ancestor: &str) -> bool {
+        let mut class = Some(class);
+        while let Some(current) = class {
+            if current == ancestor {
+                return true;
+            }
+            class = self.classes.get(current).and_then(|info| info.base.as_deref());
+        }
+        false
+    }
+
+    /// Dicts hash str keys by their contents and integer, bool and object keys by
+    /// value, and keep each value in a 64-bit slot
+    fn check_dict_types(&self, key_type: &Type, val_type: &Type) -> Result<(), String> {
+        if !matches!(key_type, Type::Str | Type::Bool | Type::Custom(_)) && key_type.integer_rank().is_none() {
+            return Err(format!(
+                "Unsupported dict key type {}: keys must be str, an integer type, bool, or a class",
+                key_type
+            ));
+        }
+        if matches!(val_type, Type::Tuple(_) | Type::Result(_, _) | Type::Array(_, _)) {
+            return Err(format!(
+                "Unsupported dict value type {}: tuples, Results and arrays cannot be stored in a dict",
+                val_type
+            ));
+        }
+        Ok(())
+    }
+
     fn types_compatible(&self, expected: &Type, actual: &Type) -> bool {
         match (expected, actual) {
-            // Float accepts Int
-            (Type::Float, Type::Int) => true,
+            // Float, bigint and decimal accept Int (and the sized integer types)
+            (Type::Float | Type::BigInt | Type::Decimal, actual) if actual.integer_rank().is_some() => true,
+            // Integers widen implicitly: u8 -> i16 -> i32 -> int
+            (expected, actual) if expected.integer_rank() > actual.integer_rank() && actual.integer_rank().is_some() => true,
             // Array compatibility
             (Type::Array(e1, s1), Type::Array(e2, s2)) => {
                 s1 == s2 && self.types_compatible(e1, e2)
@@ -1398,10 +3378,19 @@ impl TypeChecker {
             // Optional type compatibility:
             // - None (Void) can be assigned to any Optional[T]
             (Type::Optional(_), Type::Void) => true,
+            // - Optional[T] can be assigned to Optional[T]
+            (Type::Optional(expected_inner), Type::Optional(actual_inner)) => {
+                self.types_compatible(expected_inner, actual_inner)
+            }
             // - T can be assigned to Optional[T]
             (Type::Optional(inner), actual) => self.types_compatible(inner, actual),
-            // - Optional[T] == Optional[T] if inner types match
-            // (handled by default case since Type derives PartialEq)
+            // Ok(x) and Err(e) leave the other side Inferred, which matches anything
+            (Type::Result(ok1, err1), Type::Result(ok2, err2)) => {
+                (**ok2 == Type::Inferred || self.types_compatible(ok1, ok2))
+                    && (**err2 == Type::Inferred || self.types_compatible(err1, err2))
+            }
+            // A subclass instance can be used where its base class is expected
+            (Type::Custom(base), Type::Custom(class)) => self.is_subclass(class, base),
             _ => expected == actual,
         }
     }
@@ -1416,9 +3405,19 @@ mod tests {
     fn typecheck_source(source: &str) -> Result<(), String> {
         let lexer = Lexer::new(source.to_string());
         let mut parser = Parser::new(lexer);
-        let program = parser.parse();
+        let mut program = parser.parse();
+        let mut typechecker = TypeChecker::new();
+        typechecker.check_program(&mut program)
+    }
+
+    /// Type check with `--strict`, returning the warnings on success
+    fn typecheck_strict(source: &str) -> Result<Vec<String>, String> {
+        let lexer = Lexer::new(source.to_string());
+        let mut program = Parser::new(lexer).parse();
         let mut typechecker = TypeChecker::new();
-        typechecker.check_program(&program)
+        typechecker.set_strict(true);
+        typechecker.check_program(&mut program)?;
+        Ok(typechecker.warnings().to_vec())
     }
 
     #[test]
@@ -1502,6 +3501,19 @@ def main() -> int {
         assert!(result.unwrap_err().contains("missing required argument"));
     }
 
+    #[test]
+    fn test_method_call_rejects_named_args() {
+        let source = r#"
+def main() -> int {
+    xs: list[int] = [1]
+    xs.push(value=2)
+    return 0
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.unwrap_err().contains("does not take named arguments"));
+    }
+
     #[test]
     fn test_function_call_wrong_arg_type() {
         let source = r#"
@@ -1585,6 +3597,27 @@ def main() -> int {
         assert!(typecheck_source(source).is_ok());
     }
 
+    #[test]
+    fn test_string_ordering_comparisons() {
+        let source = r#"
+def main() -> int {
+    a: bool = "apple" < "banana"
+    b: bool = "b" >= "a"
+    name: str = "x"
+    c: bool = name <= "y" and name != "z"
+    return 0
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    xs: list[int] = [1]\n    b: bool = xs < xs\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("Cannot order list[int] and list[int]"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    b: bool = \"1\" < 2\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("Cannot compare str and int"), "{}", err);
+        // Equality is still allowed: it asks whether both sides are the same list
+        assert!(typecheck_source("def main() -> int {\n    xs: list[int] = [1]\n    b: bool = xs == xs\n    return 0\n}\n").is_ok());
+    }
+
     #[test]
     fn test_logical_operators() {
         let source = r#"
@@ -1729,128 +3762,533 @@ def main() -> int {
     }
 
     #[test]
-    fn test_list_length_property() {
+    fn test_list_editing_methods() {
         let source = r#"
 def main() -> int {
-    nums: list[int] = [1, 2, 3]
-    len: int = nums.length
+    names: list[str] = ["ada", "linus"]
+    names.insert(1, "grace")
+    first: str = names.remove_at(0)
+    at: int = names.index_of("linus")
+    found: bool = names.contains("ada")
+    names.clear()
     return 0
 }
 "#;
         assert!(typecheck_source(source).is_ok());
-    }
 
-    #[test]
-    fn test_dict_literal() {
         let source = r#"
 def main() -> int {
-    ages: dict[str, int] = {"Alice": 25, "Bob": 30}
-    return 0
+    nums: list[int] = [1, 2, 3]
+    return nums.index_of("2")
 }
 "#;
-        assert!(typecheck_source(source).is_ok());
-    }
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("index_of() argument type mismatch: expected int, got str"), "{}", err);
 
-    #[test]
-    fn test_dict_inconsistent_key_types() {
         let source = r#"
 def main() -> int {
-    ages: dict[str, int] = {"Alice": 25, 42: 30}
+    nums: list[int] = [1, 2, 3]
+    nums.insert(4)
     return 0
 }
 "#;
-        let result = typecheck_source(source);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Inconsistent key types"));
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("insert() takes exactly 2 arguments"), "{}", err);
     }
 
     #[test]
-    fn test_dict_index_access() {
+    fn test_list_sort() {
         let source = r#"
 def main() -> int {
-    ages: dict[str, int] = {"Alice": 25}
-    x: int = ages["Alice"]
-    return 0
+    nums: list[int] = [3, 1, 2]
+    nums.sort()
+    names: list[str] = ["b", "a"]
+    names.sort()
+    return nums[0]
 }
 "#;
         assert!(typecheck_source(source).is_ok());
-    }
 
-    #[test]
-    fn test_dict_index_assignment() {
         let source = r#"
 def main() -> int {
-    ages: dict[str, int] = {}
-    ages["Alice"] = 25
+    grid: list[list[int]] = [[2], [1]]
+    grid.sort()
     return 0
 }
 "#;
-        assert!(typecheck_source(source).is_ok());
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("sort() needs a list of int, float, str or bool, not list[list[int]]"), "{}", err);
     }
 
     #[test]
-    fn test_for_loop_over_list() {
+    fn test_definitions_are_unique() {
+        // A method may share its name with a function or another class's method
         let source = r#"
-def main() -> int {
-    nums: list[int] = [1, 2, 3]
-    for n in nums {
-        x: int = n
+class Shape {
+    size: int
+    def area(self: Shape) -> int {
+        return self.size
     }
-    return 0
+}
+class Square {
+    side: int
+    def area(self: Square) -> int {
+        return self.side * self.side
+    }
+}
+def area(s: Shape) -> int {
+    return s.area()
 }
 "#;
         assert!(typecheck_source(source).is_ok());
+
+        let source = "def f() -> int {\n    return 1\n}\ndef f() -> int {\n    return 2\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("The function 'f' is defined more than once"), "{}", err);
+
+        let source = "class Point {\n    x: int\n}\ndef Point() -> int {\n    return 0\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("'Point' is defined as both a function and a class"), "{}", err);
+    }
+
+    /// A program that imports `modules` (name and source), built the way the
+    /// loader does: each module's functions become `module.name`
+    fn program_with_modules(modules: &[(&str, &str)], source: &str) -> Program {
+        let mut program = Program::new();
+        for (module_name, module_source) in modules {
+            let mut module = Module::default();
+            for mut statement in Parser::new(Lexer::new(module_source.to_string())).parse().statements {
+                match &mut statement {
+                    Statement::FunctionDef { name, .. } => {
+                        module.functions.push(name.clone());
+                        *name = format!("{}.{}", module_name, name);
+                    }
+                    Statement::ClassDef { name, .. } => module.classes.push(name.clone()),
+                    _ => {}
+                }
+                program.statements.push(statement);
+            }
+            program.modules.insert(module_name.to_string(), module);
+        }
+        program.statements.extend(Parser::new(Lexer::new(source.to_string())).parse().statements);
+        program
+    }
+
+    #[test]
+    fn test_module_functions_have_their_own_namespace() {
+        let shapes = "def area(w: int, h: int) -> int {\n    return w * h\n}\ndef square(n: int) -> int {\n    return area(n, n)\n}\n";
+        let rooms = "def area(name: str) -> float {\n    return 1.5\n}\n";
+
+        // The same name in two modules, called through them
+        let source = "def main() -> int {\n    x: int = shapes.area(2, 3) + shapes.square(2)\n    y: float = rooms.area(\"hall\")\n    return x\n}\n";
+        let mut program = program_with_modules(&[("shapes", shapes), ("rooms", rooms)], source);
+        assert!(TypeChecker::new().check_program(&mut program).is_ok());
+
+        // Unqualified, it could be either
+        let source = "def main() -> int {\n    return area(2, 3)\n}\n";
+        let mut program = program_with_modules(&[("shapes", shapes), ("rooms", rooms)], source);
+        let err = TypeChecker::new().check_program(&mut program).unwrap_err();
+        assert!(err.contains("call it as rooms.area() or shapes.area()"), "{}", err);
+
+        // The program's own definition comes first
+        let source = "def area(w: int, h: int) -> int {\n    return 0\n}\ndef main() -> int {\n    return area(2, 3) + shapes.area(2, 3)\n}\n";
+        let mut program = program_with_modules(&[("shapes", shapes)], source);
+        assert!(TypeChecker::new().check_program(&mut program).is_ok());
+
+        // A module-qualified call reaches the module's function, not the builtin
+        let io = "@shadows_builtin\ndef read_line(handle: int) -> str {\n    return \"\"\n}\n";
+        let source = "def main() -> int {\n    line: str = io.read_line(0)\n    return 0\n}\n";
+        let mut program = program_with_modules(&[("io", io)], source);
+        assert!(TypeChecker::new().check_program(&mut program).is_ok());
+
+        // cli's parse_bool() replaces the builtin only where cli is imported
+        let cli = "@shadows_builtin\ndef parse_bool(s: str) -> bool {\n    return cli_parse_bool(s) == 1\n}\n";
+        let config = "def flag(s: str) -> bool? {\n    return parse_bool(s)\n}\n";
+        let source = "def main() -> int {\n    a: bool = cli.parse_bool(\"yes\")\n    b: bool = parse_bool(\"yes\")\n    c: bool? = config.flag(\"yes\")\n    return 0\n}\n";
+        let mut program = program_with_modules(&[("cli", cli), ("config", config)], source);
+        assert!(TypeChecker::new().check_program(&mut program).is_ok());
+
+        let source = "def main() -> int {\n    return 0\n}\n";
+        let mut program = program_with_modules(&[("shapes", &format!("{}{}", shapes, shapes))], source);
+        let err = TypeChecker::new().check_program(&mut program).unwrap_err();
+        assert!(err.contains("The function 'area' is defined more than once in module 'shapes'"), "{}", err);
+    }
+
+    #[test]
+    fn test_reserved_runtime_names() {
+        let check = |source: &str| {
+            let mut program = Parser::new(Lexer::new(source.to_string())).parse();
+            let mut typechecker = TypeChecker::new();
+            typechecker.set_reserved_names(["strlen", "free"].iter().map(|name| name.to_string()).collect());
+            typechecker.check_program(&mut program)
+        };
+        let err = check("def strlen(s: str) -> int {\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("Function 'strlen' has the name of a runtime function"), "{}", err);
+        let err = check("class free {\n    x: int\n}\n").unwrap_err();
+        assert!(err.contains("Class 'free' has the name of a runtime function"), "{}", err);
+        // A method's symbol carries its class
+        assert!(check("class Buffer {\n    n: int\n    def free(self: Buffer) -> int {\n        return 0\n    }\n}\n").is_ok());
+    }
+
+    #[test]
+    fn test_builtin_collisions() {
+        let source = "def range(n: int) -> int {\n    return n\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("builtin function 'range', which can't be replaced"), "{}", err);
+
+        let source = "class Exception {\n    message: str\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("Class 'Exception' shadows the builtin exception type"), "{}", err);
+
+        // Replacing print, Ok, Err or an exception type is fine when marked
+        let source = "@shadows_builtin\ndef print(s: str) -> void {\n    print_str(s)\n}\n\
+                      @shadows_builtin\nclass ValueError {\n    message: str\n}\n";
+        assert!(typecheck_source(source).is_ok());
+
+        let source = "@shadows_builtin\ndef greet() -> void {\n    pass\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("there is no builtin named 'greet'"), "{}", err);
+
+        // A method can't replace anything, so the marker makes no sense there
+        let source = "class C {\n    x: int\n    @shadows_builtin\n    def print(self: C) -> void {\n        pass\n    }\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("only allowed on top-level functions"), "{}", err);
+
+        // A variable doesn't hide the builtin function, so it only warns
+        let source = "def main() -> int {\n    round: int = 2\n    return round\n}\n";
+        let warnings = typecheck_strict(source).unwrap();
+        assert!(warnings.iter().any(|w| w.contains("Variable 'round' has the name of the builtin function")), "{:?}", warnings);
     }
 
     #[test]
-    fn test_for_loop_over_non_iterable() {
+    fn test_list_reverse_extend_and_concat() {
         let source = r#"
 def main() -> int {
-    for n in 42 {
-        pass
+    nums: list[int] = [3, 1, 2]
+    nums.reverse()
+    nums.shuffle()
+    nums.extend([4, 5])
+    more: list[int] = nums + [6] + nums
+    return more.length
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+
+        let source = "def main() -> int {\n    nums: list[int] = [1]\n    nums.shuffle(1)\n    return 0\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("shuffle() takes no arguments"), "{}", err);
+
+        let source = "def main() -> int {\n    nums: list[int] = [1]\n    nums.extend([\"a\"])\n    return 0\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("extend() argument type mismatch: expected list[int], got list[str]"), "{}", err);
+
+        let source = "def main() -> int {\n    nums: list[int] = [1]\n    both: list[int] = nums + [\"a\"]\n    return 0\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("lists must have the same element type"), "{}", err);
     }
-    return 0
+
+    #[test]
+    fn test_list_map_filter_reduce() {
+        let functions = r#"
+def double(x: int) -> int {
+    return x * 2
+}
+def is_even(x: int) -> bool {
+    return x % 2 == 0
+}
+def add(total: float, x: int) -> float {
+    return total + x
+}
+def label(x: int) -> str {
+    return "n"
+}
+def larger(a: int, b: int) -> bool {
+    return a > b
+}
+def longer(a: str, b: str) -> bool {
+    return a.length > b.length
 }
 "#;
-        let result = typecheck_source(source);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Cannot iterate"));
+        let source = format!(
+            "{}def main() -> int {{\n    nums: list[int] = [1, 2, 3]\n    names: list[str] = nums.map(label)\n    \
+             evens: list[int] = nums.map(double).filter(is_even)\n    total: float = nums.reduce(add, 0)\n    \
+             nums.sort_by(larger)\n    return evens.length\n}}\n",
+            functions
+        );
+        assert!(typecheck_source(&source).is_ok(), "{:?}", typecheck_source(&source));
+
+        let cases = [
+            ("nums.filter(double)", "filter(): function 'double' must return bool, not int"),
+            ("nums.map(add)", "map(): function 'add' must take 1 parameter, but takes 2"),
+            ("nums.map(nums)", "map() takes the name of a function"),
+            ("nums.map(print_int)", "'print_int' is not a function defined in the program"),
+            ("nums.reduce(add, \"zero\")", "reduce() argument type mismatch: expected float, got str"),
+            ("nums.sort_by(double)", "sort_by(): function 'double' must take 2 parameters, but takes 1"),
+            ("nums.sort_by(add)", "sort_by(): function 'add' must return bool, not float"),
+            ("nums.sort_by(longer)", "sort_by(): function 'longer' takes str, but the list holds int"),
+        ];
+        for (call, expected) in cases {
+            let source = format!("{}def main() -> int {{\n    nums: list[int] = [1]\n    {}\n    return 0\n}}\n", functions, call);
+            let err = typecheck_source(&source).unwrap_err();
+            assert!(err.contains(expected), "{}: {}", call, err);
+        }
     }
 
     #[test]
-    fn test_class_definition() {
+    fn test_dict_keys_and_values() {
         let source = r#"
-class Person {
-    name: str
-    age: int
+def main() -> int {
+    ages: dict[str, int] = {"ada": 36, "alan": 41}
+    names: list[str] = ages.keys()
+    years: list[int] = ages.values()
+    return names.length + years[0]
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
 
-    def greet(self: Person) -> void {
-        pass
-    }
+        let source = "def main() -> int {\n    ages: dict[str, int] = {\"ada\": 36}\n    years: list[str] = ages.values()\n    return 0\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("expected list[str], got list[int]"), "{}", err);
+
+        let source = r#"
+def main() -> int {
+    ages: dict[str, int] = {"ada": 36}
+    pairs: list[(str, int)] = ages.items()
+    name, age = pairs[0]
+    return age + pairs[0].1
 }
 "#;
         assert!(typecheck_source(source).is_ok());
+
+        let source = "def main() -> int {\n    ages: dict[str, int] = {\"ada\": 36}\n    pairs: list[(int, str)] = ages.items()\n    return 0\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("expected list[(int, str)], got list[(str, int)]"), "{}", err);
+
+        let source = "def main() -> int {\n    ages: dict[str, int] = {\"ada\": 36}\n    ages.items().contains((\"ada\", 36))\n    return 0\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.contains("contains() can't compare tuples"), "{}", err);
     }
 
     #[test]
-    fn test_class_constructor() {
+    fn test_dict_remove_pop_clear() {
         let source = r#"
-class Person {
-    name: str
-    age: int
-}
 def main() -> int {
-    p: Person = Person("Alice", 25)
-    return 0
+    ages: dict[str, int] = {"ada": 36, "alan": 41}
+    removed: bool = ages.remove("ada")
+    years: int = ages.pop("alan", 0)
+    fallback: int = ages.get("grace", -1)
+    ages.clear()
+    return years + fallback
 }
 "#;
         assert!(typecheck_source(source).is_ok());
+
+        let cases = [
+            ("ages.remove(1)", "remove() key type mismatch: expected str, got int"),
+            ("ages.pop(\"ada\")", "pop() takes a key and a default value"),
+            ("ages.pop(\"ada\", \"none\")", "pop() default must be int, got str"),
+            ("ages.clear(1)", "clear() takes no arguments"),
+            ("ages.clean()", "did you mean 'clear'"),
+            ("ages.get(\"ada\")", "get() takes a key and a default value"),
+            ("ages.get(36, 0)", "get() key type mismatch: expected str, got int"),
+            ("ages.get(\"ada\", 1.5)", "get() default must be int, got float"),
+        ];
+        for (call, expected) in cases {
+            let source = format!("def main() -> int {{\n    ages: dict[str, int] = {{\"ada\": 36}}\n    {}\n    return 0\n}}\n", call);
+            let err = typecheck_source(&source).unwrap_err();
+            assert!(err.contains(expected), "{}: {}", call, err);
+        }
     }
 
     #[test]
-    fn test_class_constructor_wrong_args() {
+    fn test_for_key_value_over_dict() {
         let source = r#"
-class Person {
+def main() -> int {
+    ages: dict[str, int] = {"ada": 36, "alan": 41}
+    total: int = 0
+    for name, age in ages {
+        print_str(name)
+        total = total + age
+    }
+    return total
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+
+        let cases = [
+            ("for name, age in ages { print_int(name) }", "expected int, got str"),
+            ("for i, x in [1, 2] { print_int(x) }", "Only a dict gives 'for key, value'"),
+            ("for k, k in ages { print_str(k) }", "used for both the key and the value"),
+        ];
+        for (stmt, expected) in cases {
+            let source = format!("def main() -> int {{\n    ages: dict[str, int] = {{\"ada\": 36}}\n    {}\n    return 0\n}}\n", stmt);
+            let err = typecheck_source(&source).unwrap_err();
+            assert!(err.contains(expected), "{}: {}", stmt, err);
+        }
+    }
+
+    #[test]
+    fn test_list_length_property() {
+        let source = r#"
+def main() -> int {
+    nums: list[int] = [1, 2, 3]
+    len: int = nums.length
+    return 0
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_dict_literal() {
+        let source = r#"
+def main() -> int {
+    ages: dict[str, int] = {"Alice": 25, "Bob": 30}
+    return 0
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_dict_inconsistent_key_types() {
+        let source = r#"
+def main() -> int {
+    ages: dict[str, int] = {"Alice": 25, 42: 30}
+    return 0
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Inconsistent key types"));
+    }
+
+    #[test]
+    fn test_dict_index_access() {
+        let source = r#"
+def main() -> int {
+    ages: dict[str, int] = {"Alice": 25}
+    x: int = ages["Alice"]
+    return 0
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_dict_index_assignment() {
+        let source = r#"
+def main() -> int {
+    ages: dict[str, int] = {}
+    ages["Alice"] = 25
+    return 0
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_dict_key_types() {
+        let source = r#"
+class Point {
+    x: int
+}
+
+def main() -> int {
+    names: dict[int, str] = {1: "one", 2: "two"}
+    names[3] = "three"
+    flags: dict[bool, float] = {True: 1.5}
+    p: Point = Point(1)
+    seen: dict[Point, bool] = {}
+    seen[p] = True
+    return names[1].length
+}
+"#;
+        assert!(typecheck_source(source).is_ok(), "{:?}", typecheck_source(source));
+
+        let source = r#"
+def main() -> int {
+    weights: dict[float, int] = {}
+    weights[1.5] = 2
+    return 0
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.unwrap_err().contains("Unsupported dict key type float"));
+
+        let source = r#"
+def main() -> int {
+    pairs: dict[str, (int, int)] = {"a": (1, 2)}
+    return 0
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.unwrap_err().contains("Unsupported dict value type (int, int)"));
+    }
+
+    #[test]
+    fn test_for_loop_over_list() {
+        let source = r#"
+def main() -> int {
+    nums: list[int] = [1, 2, 3]
+    for n in nums {
+        x: int = n
+    }
+    return 0
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_for_loop_over_non_iterable() {
+        let source = r#"
+def main() -> int {
+    for n in 42 {
+        pass
+    }
+    return 0
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Cannot iterate"));
+    }
+
+    #[test]
+    fn test_class_definition() {
+        let source = r#"
+class Person {
+    name: str
+    age: int
+
+    def greet(self: Person) -> void {
+        pass
+    }
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_class_constructor() {
+        let source = r#"
+class Person {
+    name: str
+    age: int
+}
+def main() -> int {
+    p: Person = Person("Alice", 25)
+    return 0
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_class_constructor_wrong_args() {
+        let source = r#"
+class Person {
     name: str
     age: int
 }
@@ -1864,6 +4302,86 @@ def main() -> int {
         assert!(result.unwrap_err().contains("expects 2 arguments"));
     }
 
+    #[test]
+    fn test_class_field_defaults() {
+        let source = r#"
+class Counter {
+    name: str
+    count: int = 0
+}
+c: Counter = Counter("clicks")
+"#;
+        assert!(typecheck_source(source).is_ok());
+        let err = typecheck_source("class Counter {\n    count: int = 0\n}\nc: Counter = Counter(5)").unwrap_err();
+        assert_eq!(err, "Constructor for 'Counter' expects 0 arguments, got 1");
+        let err = typecheck_source("class Counter {\n    count: int = \"zero\"\n}").unwrap_err();
+        assert_eq!(err, "Cannot assign str to field 'Counter.count' of type int");
+    }
+
+    #[test]
+    fn test_field_assignment() {
+        let class = "class Counter {\n    count: int\n    _secret: int = 0\n}\nc: Counter = Counter(1)\n";
+        assert!(typecheck_source(&format!("{}c.count = 5\nc.count += 1", class)).is_ok());
+        let err = typecheck_source(&format!("{}c.count = \"five\"", class)).unwrap_err();
+        assert_eq!(err, "Cannot assign str to field 'Counter.count' of type int");
+        let err = typecheck_source(&format!("{}c.cuont = 5", class)).unwrap_err();
+        assert!(err.starts_with("Class 'Counter' has no field 'cuont'"), "{}", err);
+        let err = typecheck_source(&format!("{}c._secret = 5", class)).unwrap_err();
+        assert!(err.contains("Cannot access private field"), "{}", err);
+        let err = typecheck_source("n: int = 1\nn.x = 2").unwrap_err();
+        assert_eq!(err, "Cannot assign to field 'x' of type int");
+    }
+
+    #[test]
+    fn test_custom_init_constructor() {
+        let source = r#"
+class Point {
+    x: float
+    y: float
+    label: str = "origin"
+
+    def init(self: Point, radius: float, scale: float = 1.0) -> void {
+        self.x = radius * scale
+        self.y = 0.0
+    }
+}
+class Point3(Point) {
+    z: float
+}
+a: Point = Point(2.0)
+b: Point = Point(2.0, scale=3.0)
+c: Point3 = Point3(1.0)
+"#;
+        assert!(typecheck_source(source).is_ok());
+
+        let class = "class Point {\n    x: float\n\n    def init(self: Point, radius: float) -> void {\n        self.x = radius\n    }\n}\n";
+        let err = typecheck_source(&format!("{}p: Point = Point(1.0, 2.0)", class)).unwrap_err();
+        assert_eq!(err, "Constructor 'Point' takes at most 1 arguments, got 2");
+        let err = typecheck_source(&format!("{}p: Point = Point()", class)).unwrap_err();
+        assert_eq!(err, "Constructor 'Point': missing required argument 'radius'");
+        let err = typecheck_source(&format!("{}p: Point = Point(\"far\")", class)).unwrap_err();
+        assert_eq!(err, "Argument 1 of constructor 'Point': expected float, got str");
+        let err = typecheck_source(&format!("{}p: Point = Point(r=1.0)", class)).unwrap_err();
+        assert_eq!(err, "Constructor 'Point' has no parameter named 'r'");
+
+        // A subclass init may take different parameters than the base init
+        let sub = "class Point2(Point) {\n    y: float\n\n    def init(self: Point2, x: float, y: float) -> void {\n        super.init(x)\n        self.y = y\n    }\n}\np: Point2 = Point2(1.0, 2.0)";
+        assert!(typecheck_source(&format!("{}{}", class, sub)).is_ok());
+    }
+
+    #[test]
+    fn test_deinit() {
+        let class = "class Handle {\n    fd: int\n\n    def deinit(self: Handle) -> void {\n        print_int(self.fd)\n    }\n}\n";
+        assert!(typecheck_source(&format!("{}h: Handle = Handle(3)", class)).is_ok());
+        let err = typecheck_source(&format!("{}h: Handle = Handle(3)\nh.deinit()", class)).unwrap_err();
+        assert_eq!(err, "'deinit' cannot be called directly; it runs when the last reference to a 'Handle' goes away");
+        let sub = "class Socket(Handle) {\n    def deinit(self: Socket) -> void {\n        super.deinit()\n    }\n}\n";
+        let err = typecheck_source(&format!("{}{}", class, sub)).unwrap_err();
+        assert_eq!(err, "'deinit' cannot be called directly; 'Handle.deinit' runs automatically after 'Socket.deinit'");
+        let err = typecheck_source("class Handle {\n    def deinit(self: Handle, force: bool) -> void {\n        pass\n    }\n}").unwrap_err();
+        assert_eq!(err, "Method 'Handle.deinit' must take only self and return nothing");
+    }
+
     #[test]
     fn test_class_field_access() {
         let source = r#"
@@ -1989,14 +4507,11 @@ def main() -> int {
     }
 
     #[test]
-    fn test_scope_visibility() {
+    fn test_string_repetition() {
         let source = r#"
 def main() -> int {
-    x: int = 10
-    if True {
-        y: int = 20
-        z: int = x + y
-    }
+    line: str = "-" * 40
+    banner: str = 3 * "="
     return 0
 }
 "#;
@@ -2004,87 +4519,322 @@ def main() -> int {
     }
 
     #[test]
-    fn test_scope_variable_not_visible() {
+    fn test_string_split_and_join() {
         let source = r#"
 def main() -> int {
-    if True {
-        x: int = 10
-    }
-    y: int = x
-    return 0
+    parts: list[str] = "a,b".split(",")
+    line: str = ", ".join(parts)
+    return line.length
 }
 "#;
-        let result = typecheck_source(source);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Undefined variable"));
+        assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    s: str = \",\".join([1, 2])\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("join() argument must be list[str], got list[int]"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = \",\".joined([\"a\"])\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("did you mean 'join'"), "{}", err);
     }
 
     #[test]
-    fn test_decorator_arg_valid() {
+    fn test_parse_bool_and_truthy() {
         let source = r#"
-class Args {
-    @arg(help="Input file")
-    input_file: str
+def main() -> int {
+    verbose: bool? = parse_bool("yes")
+    debug: bool = parse_bool("off") ?? False
+    names: list[str] = []
+    if truthy(names) or truthy("x") or truthy(3) or truthy(0.5) or truthy(verbose) or debug {
+        return 1
+    }
+    return 0
 }
 "#;
         assert!(typecheck_source(source).is_ok());
+
+        // parse_bool may fail, so its result is Optional
+        let err = typecheck_source("def main() -> int {\n    b: bool = parse_bool(\"true\")\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("bool?"), "{}", err);
+        // Conditions stay strictly bool; truthy() is the explicit conversion
+        let err = typecheck_source("def main() -> int {\n    n: int = 3\n    if n {\n        return 1\n    }\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("If condition must be bool"), "{}", err);
+        let err = typecheck_source("class P {\n    x: int\n}\ndef main() -> int {\n    ok: bool = truthy(P(1))\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("truthy() takes an int, float, bool, str, list, dict or optional value, got P"), "{}", err);
+        let err = typecheck_source("def truthy(x: int) -> bool {\n    return x > 0\n}\n").unwrap_err();
+        assert!(err.contains("builtin function 'truthy'"), "{}", err);
+
+        // std/cli.ws's parse_bool replaces the builtin
+        let source = "@shadows_builtin\ndef parse_bool(s: str) -> bool {\n    return cli_parse_bool(s) == 1\n}\n\
+                      def main() -> int {\n    b: bool = parse_bool(\"true\")\n    return 0\n}\n";
+        assert!(typecheck_source(source).is_ok());
     }
 
     #[test]
-    fn test_decorator_arg_wrong_type() {
+    fn test_string_number_conversions() {
         let source = r#"
-class Args {
-    @arg(help="Count")
-    count: int
+class Point {
+    x: int
+
+    def to_string(self: Point) -> str {
+        return f"Point({self.x})"
+    }
+}
+
+def main() -> int {
+    n: int = int("42")
+    x: float = float("3.5")
+    small: u8 = 7
+    text: str = str(n) + str(x) + str(True) + str(small) + str(Point(1)) + str("as is")
+    return n
 }
 "#;
-        let result = typecheck_source(source);
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(err.contains("@arg decorator") && err.contains("requires type str"));
+        assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    n: int = int(3.7)\n    return n\n}\n").unwrap_err();
+        assert!(err.contains("int() takes a str to parse, got float; use 'x as int'"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    x: float = float([1])\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("float() takes a str to parse, got list[int]"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = str([1])\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("str() takes an int, float, bool or str value, got list[int]"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = int(\"1\", \"2\")\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("int() takes exactly 1 argument, got 2"), "{}", err);
     }
 
     #[test]
-    fn test_decorator_option_valid_types() {
+    fn test_format_builtin() {
         let source = r#"
-class Args {
-    @option(short="o", long="output")
-    output: str
+class Point {
+    x: int
 
-    @option(short="n", long="number")
-    number: int
+    def to_string(self: Point) -> str {
+        return f"Point({self.x})"
+    }
+}
 
-    @option(short="v", long="verbose")
-    verbose: bool
+def main() -> int {
+    template: str = "{} at {}"
+    a: str = format("Hello {} you are {}", "Ada", 36)
+    b: str = format("{1}, {0} {{braces}}", 2.5, True)
+    c: str = format(template, Point(1), "home")
+    d: str = format("no placeholders")
+    return a.length + b.length + c.length + d.length
 }
 "#;
         assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    s: str = format(\"{} and {}\", 1)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("format() template needs 2 value(s), got 1"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = format(\"{} {0}\", 1, 2)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("can't mix {} and {0} placeholders"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = format(\"{name}\", 1)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("bad placeholder '{name}'"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = format(\"{}\", [1])\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("format() value 1 must be an int, float, bool or str value, got list[int]"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = format(1)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("format() template must be str, got int"), "{}", err);
+        let err = typecheck_source("def format(s: str) -> str {\n    return s\n}\n").unwrap_err();
+        assert!(err.contains("builtin function 'format', which can't be replaced"), "{}", err);
     }
 
     #[test]
-    fn test_decorator_option_wrong_type() {
+    fn test_input_and_read_line_builtins() {
         let source = r#"
-class Args {
-    @option(long="data")
-    data: list[int]
+def main() -> int {
+    name: str = input("Name: ")
+    again: str = input()
+    line: str? = read_line()
+    try {
+        input()
+    } except EOFError {
+        pass
+    }
+    return name.length + again.length + (line ?? "").length
 }
 "#;
-        let result = typecheck_source(source);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("@option decorator"));
+        assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    s: str = input(1)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("input() prompt must be str, got int"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str? = read_line(1)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("read_line() takes no arguments, got 1"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = read_line()\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("str?"), "{}", err);
+
+        // A read_line of the program's own (like std/io.ws's) replaces the builtin
+        let source = "@shadows_builtin\ndef read_line(handle: int) -> str {\n    return \"\"\n}\ndef main() -> int {\n    s: str = read_line(3)\n    return s.length\n}\n";
+        assert!(typecheck_source(source).is_ok());
+        let err = typecheck_source("def input(prompt: str) -> str {\n    return prompt\n}\n").unwrap_err();
+        assert!(err.contains("builtin function 'input', which can't be replaced"), "{}", err);
     }
 
     #[test]
-    fn test_decorator_option_short_single_char() {
+    fn test_string_replace_find_and_affixes() {
         let source = r#"
-class Args {
-    @option(short="ab", long="output")
-    output: str
+def main() -> int {
+    path: str = "src/main.ws"
+    if path.startswith("src/") and not path.endswith(".txt") {
+        dotted: str = path.replace("/", ".")
+        return dotted.find(".ws")
+    }
+    return -1
 }
 "#;
-        let result = typecheck_source(source);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("single character"));
+        assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    s: str = \"a\".replace(\"a\")\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("replace() takes exactly 2 arguments"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    return \"abc\".find(1)\n}\n").unwrap_err();
+        assert!(err.contains("find() argument must be str, got int"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    b: bool = \"abc\".starts_with(\"a\")\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("did you mean 'startswith'"), "{}", err);
+    }
+
+    #[test]
+    fn test_string_strip_methods() {
+        let source = r#"
+def main() -> int {
+    line: str = "  key = value \n"
+    parts: list[str] = line.strip().split("=")
+    key: str = parts[0].rstrip()
+    value: str = parts[1].lstrip()
+    return key.length + value.length
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    s: str = \" a \".strip(\" \")\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("strip() takes no arguments"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    s: str = \" a \".rstirp()\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("did you mean 'rstrip'"), "{}", err);
+    }
+
+    #[test]
+    fn test_string_chars_and_byte_length() {
+        let source = r#"
+def main() -> int {
+    word: str = "héllo"
+    letters: list[str] = word.chars()
+    return word.byte_length - letters.length
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    xs: list[int] = [1]\n    return xs.byte_length\n}\n").unwrap_err();
+        assert!(err.contains("Unknown property 'byte_length' on type list[int]"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    return \"abc\".bytelength\n}\n").unwrap_err();
+        assert!(err.contains("did you mean 'byte_length'"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    xs: list[str] = \"abc\".chars(1)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("chars() takes no arguments"), "{}", err);
+    }
+
+    #[test]
+    fn test_string_repetition_rejects_non_int() {
+        let source = r#"
+def main() -> int {
+    s: str = "ab" * "cd"
+    return 0
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Invalid operands for Multiply"));
+    }
+
+    #[test]
+    fn test_scope_visibility() {
+        let source = r#"
+def main() -> int {
+    x: int = 10
+    if True {
+        y: int = 20
+        z: int = x + y
+    }
+    return 0
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_scope_variable_not_visible() {
+        let source = r#"
+def main() -> int {
+    if True {
+        x: int = 10
+    }
+    y: int = x
+    return 0
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Undefined variable"));
+    }
+
+    #[test]
+    fn test_decorator_arg_valid() {
+        let source = r#"
+class Args {
+    @arg(help="Input file")
+    input_file: str
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_decorator_arg_wrong_type() {
+        let source = r#"
+class Args {
+    @arg(help="Count")
+    count: int
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(err.contains("@arg decorator") && err.contains("requires type str"));
+    }
+
+    #[test]
+    fn test_decorator_option_valid_types() {
+        let source = r#"
+class Args {
+    @option(short="o", long="output")
+    output: str
+
+    @option(short="n", long="number")
+    number: int
+
+    @option(short="v", long="verbose")
+    verbose: bool
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_decorator_option_wrong_type() {
+        let source = r#"
+class Args {
+    @option(long="data")
+    data: list[int]
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("@option decorator"));
+    }
+
+    #[test]
+    fn test_decorator_option_short_single_char() {
+        let source = r#"
+class Args {
+    @option(short="ab", long="output")
+    output: str
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("single character"));
     }
 
     #[test]
@@ -2193,4 +4943,771 @@ def main() -> int {
 "#;
         assert!(typecheck_source(source).is_ok());
     }
+
+    #[test]
+    fn test_memoize_decorator() {
+        let source = r#"
+@memoize
+def fib(n: int) -> int {
+    if n < 2 {
+        return n
+    }
+    return fib(n - 1) + fib(n - 2)
+}
+@memoize
+def label(name: str, loud: bool) -> str {
+    return name
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_memoize_rejects_list_param() {
+        let source = r#"
+@memoize
+def total(xs: list[int]) -> int {
+    return 0
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("@memoize requires int, str, or bool parameters"));
+    }
+
+    #[test]
+    fn test_memoize_rejects_void_return() {
+        let source = r#"
+@memoize
+def log(n: int) {
+    print_int(n)
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("return type"));
+    }
+
+    #[test]
+    fn test_inline_decorators() {
+        let source = r#"
+@inline
+def square(n: int) -> int {
+    return n * n
+}
+
+@noinline
+def cube(n: int) -> int {
+    return n * n * n
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_inline_and_noinline_conflict() {
+        let source = r#"
+@inline
+@noinline
+def square(n: int) -> int {
+    return n * n
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("cannot be both @inline and @noinline"));
+    }
+
+    #[test]
+    fn test_unknown_function_decorator() {
+        let source = r#"
+@cached
+def f(n: int) -> int {
+    return n
+}
+"#;
+        let result = typecheck_source(source);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Unknown decorator '@cached'"));
+    }
+
+    #[test]
+    fn test_let_infers_type_from_initializer() {
+        let source = r#"
+def compute() -> float {
+    return 1.5
+}
+def main() -> int {
+    let x = compute()
+    let names = ["a", "b"]
+    y: float = x
+    first: str = names[0]
+    return 0
+}
+"#;
+        let lexer = Lexer::new(source.to_string());
+        let mut program = Parser::new(lexer).parse();
+        TypeChecker::new().check_program(&mut program).unwrap();
+
+        // The inferred types are written back for codegen
+        if let Statement::FunctionDef { body, .. } = &program.statements[1] {
+            assert!(matches!(&body[0], Statement::VarDecl { type_annotation: Type::Float, .. }));
+            assert!(matches!(
+                &body[1],
+                Statement::VarDecl { type_annotation: Type::List(elem), .. } if **elem == Type::Str
+            ));
+        } else {
+            panic!("Expected FunctionDef");
+        }
+    }
+
+    #[test]
+    fn test_let_inferred_type_is_enforced() {
+        let result = typecheck_source("let x = 42\ny: str = x");
+        assert!(result.unwrap_err().contains("expected str, got int"));
+    }
+
+    #[test]
+    fn test_let_with_annotation() {
+        assert!(typecheck_source("let x: float = 1").is_ok());
+        assert!(typecheck_source("let x: int = \"a\"").is_err());
+    }
+
+    #[test]
+    fn test_let_requires_annotation_for_empty_literals() {
+        let result = typecheck_source("let xs = []");
+        assert!(result.unwrap_err().contains("Cannot infer type of empty list for 'xs'"));
+        let result = typecheck_source("let d = {}");
+        assert!(result.unwrap_err().contains("Cannot infer type of empty dict for 'd'"));
+        let result = typecheck_source("let name = None");
+        assert!(result.unwrap_err().contains("Cannot infer type of 'name' from None"));
+        assert!(typecheck_source("let xs: list[int] = []").is_ok());
+    }
+
+    #[test]
+    fn test_let_rejects_void_initializer() {
+        let source = r#"
+def log() -> void {
+    pass
+}
+let x = log()
+"#;
+        let result = typecheck_source(source);
+        assert!(result.unwrap_err().contains("initializer does not return a value"));
+    }
+
+    #[test]
+    fn test_suggest_similar_variable() {
+        let source = "counter: int = 1\ny: int = countr + 1";
+        let err = typecheck_source(source).unwrap_err();
+        assert_eq!(err, "Undefined variable 'countr'; did you mean 'counter'?");
+    }
+
+    #[test]
+    fn test_suggest_similar_function() {
+        let source = r#"
+def compute(n: int) -> int {
+    return n
+}
+x: int = compte(1)
+"#;
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.ends_with("did you mean 'compute'?"), "{}", err);
+    }
+
+    #[test]
+    fn test_suggest_property_and_methods() {
+        let err = typecheck_source("xs: list[int] = [1]\nn: int = xs.lenght").unwrap_err();
+        assert!(err.ends_with("did you mean 'length'?"), "{}", err);
+        let err = typecheck_source("xs: list[int] = [1]\nxs.psh(2)").unwrap_err();
+        assert!(err.ends_with("did you mean 'push'?"), "{}", err);
+        let err = typecheck_source("s: str = \"a\"\nt: str = s.uper()").unwrap_err();
+        assert!(err.ends_with("did you mean 'upper'?"), "{}", err);
+    }
+
+    #[test]
+    fn test_suggest_class_field_and_method() {
+        let source = r#"
+class Point {
+    x: int
+    total: int
+
+    def norm(self: Point) -> int {
+        return self.x
+    }
+}
+p: Point = Point(1, 2)
+a: int = p.totl
+"#;
+        let err = typecheck_source(source).unwrap_err();
+        assert!(err.ends_with("did you mean 'total'?"), "{}", err);
+        let err = typecheck_source(&source.replace("p.totl", "p.nrom()")).unwrap_err();
+        assert!(err.ends_with("did you mean 'norm'?"), "{}", err);
+    }
+
+    #[test]
+    fn test_no_suggestion_for_unrelated_name() {
+        let err = typecheck_source("value: int = 1\ny: int = zzz").unwrap_err();
+        assert_eq!(err, "Undefined variable 'zzz'");
+    }
+
+    #[test]
+    fn test_strict_requires_return_on_every_path() {
+        let source = r#"
+def sign(n: int) -> int {
+    if n > 0 {
+        return 1
+    }
+}
+"#;
+        // Accepted without --strict
+        assert!(typecheck_source(source).is_ok());
+        let err = typecheck_strict(source).unwrap_err();
+        assert!(err.contains("Function 'sign' does not return a value on every path"), "{}", err);
+
+        let source = r#"
+def sign(n: int) -> int {
+    if n > 0 {
+        return 1
+    } elif n < 0 {
+        return -1
+    } else {
+        raise ValueError("zero")
+    }
+}
+def forever() -> int {
+    while True {
+        pass
+    }
+}
+def log(msg: str) -> void {
+    print_str(msg)
+}
+"#;
+        assert!(typecheck_strict(source).is_ok());
+    }
+
+    #[test]
+    fn test_strict_while_true_with_break_needs_return() {
+        let source = r#"
+def f() -> int {
+    while True {
+        break
+    }
+}
+"#;
+        assert!(typecheck_strict(source).is_err());
+    }
+
+    #[test]
+    fn test_strict_rejects_mixed_numeric_comparison() {
+        let source = "x: int = 3\nb: bool = 2.5 > x";
+        assert!(typecheck_source(source).is_ok());
+        let err = typecheck_strict(source).unwrap_err();
+        assert_eq!(err, "Comparing float with int requires an explicit conversion (strict mode)");
+        assert!(typecheck_strict("x: float = 3.0\nb: bool = 2.5 > x").is_ok());
+    }
+
+    #[test]
+    fn test_numeric_casts() {
+        assert!(typecheck_source("x: float = 2.5\nn: int = x as int").is_ok());
+        assert!(typecheck_source("f: float = 3 as float\nb: int = True as int").is_ok());
+        // Without the cast a float is not an int
+        assert!(typecheck_source("x: float = 2.5\nn: int = x").is_err());
+    }
+
+    #[test]
+    fn test_invalid_casts() {
+        let err = typecheck_source("n: int = \"42\" as int").unwrap_err();
+        assert_eq!(err, "Cannot cast str to int; 'as' only converts between numeric types");
+        let err = typecheck_source("f: float = True as float").unwrap_err();
+        assert!(err.contains("Cannot cast bool to float"), "{}", err);
+        let err = typecheck_source("s: str = 3 as str").unwrap_err();
+        assert!(err.contains("'as' only supports numeric targets"), "{}", err);
+    }
+
+    #[test]
+    fn test_checked_arithmetic_builtins() {
+        let source = r#"
+def main() -> int {
+    small: i32 = 7
+    total: int? = checked_add(1, small)
+    product: int = checked_mul(total ?? 0, 2) ?? 0
+    return product
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+
+        // The result is Optional, so it needs a fallback or a check
+        let err = typecheck_source("def main() -> int {\n    x: int = checked_add(1, 2)\n    return x\n}\n").unwrap_err();
+        assert!(err.contains("int?"), "{}", err);
+        let err = typecheck_source("def main() -> int {\n    x: int? = checked_mul(1.5, 2)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("checked_mul"), "{}", err);
+    }
+
+    #[test]
+    fn test_bit_conversion_builtins() {
+        let source = r#"
+def main() -> int {
+    bits: int = float_to_bits(1.5)
+    x: float = bits_to_float(byte_swap(bits))
+    header: list[u8] = to_bytes_be(bits, 8)
+    return from_bytes_be(header) + from_bytes_le(to_bytes_le(7, 2))
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+
+        let err = typecheck_source("def main() -> int {\n    x: str = float_to_bits(1.5)\n    return 0\n}\n").unwrap_err();
+        assert!(err.contains("int"), "{}", err);
+        assert!(typecheck_source("def main() -> int {\n    return from_bytes_be([1.5])\n}\n").is_err());
+    }
+
+    #[test]
+    fn test_sized_int_literals_are_range_checked() {
+        assert!(typecheck_source("b: u8 = 255\nh: i16 = -32768\nw: i32 = 2147483647").is_ok());
+        assert!(typecheck_source("bytes: list[u8] = [0, 127, 255]").is_ok());
+        let err = typecheck_source("b: u8 = 256").unwrap_err();
+        assert!(err.contains("expected u8, got int"), "{}", err);
+        assert!(typecheck_source("b: u8 = -1").is_err());
+        assert!(typecheck_source("bytes: list[u8] = [0, 256]").is_err());
+        // Only literals narrow implicitly; variables need `as`
+        assert!(typecheck_source("n: int = 5\nb: u8 = n").is_err());
+        assert!(typecheck_source("n: int = 5\nb: u8 = n as u8").is_ok());
+    }
+
+    #[test]
+    fn test_sized_ints_widen() {
+        assert!(typecheck_source("b: u8 = 1\nh: i16 = b\nw: i32 = h\nn: int = w\nf: float = b").is_ok());
+        assert!(typecheck_source("w: i32 = 1\nh: i16 = w").unwrap_err().contains("expected i16, got i32"));
+        assert!(typecheck_source("h: i16 = 1\nb: u8 = h").is_err());
+    }
+
+    #[test]
+    fn test_sized_int_arithmetic_types() {
+        // Literals take the sized type, mixed sizes widen to the larger one
+        assert!(typecheck_source("b: u8 = 1\nc: u8 = b + 1").is_ok());
+        assert!(typecheck_source("b: u8 = 1\nh: i16 = 2\nr: i16 = b * h").is_ok());
+        assert!(typecheck_source("b: u8 = 1\nh: i16 = 2\nr: u8 = b * h").is_err());
+        assert!(typecheck_source("b: u8 = 1\nn: int = 2\nr: u8 = b + n").is_err());
+        assert!(typecheck_source("b: u8 = 1\nok: bool = b < 300").is_ok());
+        assert!(typecheck_source("b: u8 = 1\nf: float = b / 2.0").is_ok());
+    }
+
+    #[test]
+    fn test_float_rounding_builtins() {
+        assert!(typecheck_source("x: float = round(3.14159, 2)\ny: float = floor(x) + ceil(x) + trunc(x)").is_ok());
+        assert!(typecheck_source("s: str = format_float(2.5, 1)").is_ok());
+        // round() always returns a float, even for whole-number precision
+        assert!(typecheck_source("n: int = round(2.5, 0)").unwrap_err().contains("expected int, got float"));
+        assert!(typecheck_source("x: float = floor(\"2\")").is_err());
+    }
+
+    #[test]
+    fn test_nan_and_inf_constants() {
+        assert!(typecheck_source("x: float = nan\ny: float = -inf\nb: bool = is_nan(x) or is_finite(y)").is_ok());
+        assert!(typecheck_source("n: int = inf").unwrap_err().contains("expected int, got float"));
+        // A variable of the same name hides the constant
+        assert!(typecheck_source("inf: str = \"infinity\"\ns: str = inf").is_ok());
+        assert!(typecheck_source("b: bool = is_nan(\"x\")").is_err());
+    }
+
+    #[test]
+    fn test_bigint_operators() {
+        assert!(typecheck_source("a: bigint = 5\nb: bigint = a * a + 1\nc: bool = b > 3\nd: bigint = -a ** 3").is_ok());
+        assert!(typecheck_source("a: bigint = parse_bigint(\"123\")\nn: int = a as int\nx: float = a as float").is_ok());
+        assert!(typecheck_source("n: u8 = 3\nb: bigint = n as bigint\nc: bigint = pow_mod(b, 2, 7)").is_ok());
+        // Results are bigint, not int
+        assert!(typecheck_source("a: bigint = 5\nn: int = a + 1").unwrap_err().contains("expected int, got bigint"));
+        assert!(typecheck_source("a: bigint = 5\nx: float = a + 1.5").unwrap_err().contains("Cannot mix bigint and float"));
+        assert!(typecheck_source("b: bigint = 2.5 as bigint").unwrap_err().contains("Cannot cast float to bigint"));
+        assert!(typecheck_source("a: bigint = 5\ns: str = a + \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_decimal_operators() {
+        assert!(typecheck_source("a: decimal = 19.99d\nb: decimal = a * 3 - 0.01d\nc: bool = b >= a\nd: decimal = -a / 4").is_ok());
+        assert!(typecheck_source("a: decimal = parse_decimal(\"1.5\")\nn: int = a as int\nx: float = a as float\nd: decimal = x as decimal").is_ok());
+        assert!(typecheck_source("a: decimal = 2.675d\nb: decimal = a.round(2)").is_ok());
+        assert!(typecheck_source("a: decimal = 1d\nx: float = a + 1.5").unwrap_err().contains("Cannot mix decimal and float"));
+        assert!(typecheck_source("a: decimal = 1d\nb: bigint = 2\nc: decimal = a + b").unwrap_err().contains("Cannot mix bigint and decimal"));
+        assert!(typecheck_source("a: decimal = 7d\nb: decimal = a % 2").is_err());
+        assert!(typecheck_source("a: decimal = 1d\nb: decimal = a.rond(2)").unwrap_err().contains("did you mean 'round'"));
+    }
+
+    #[test]
+    fn test_bare_raise_needs_except_block() {
+        assert!(typecheck_source("try {\n    x: int = 1\n} except ValueError {\n    raise\n}").is_ok());
+        let err = typecheck_source("def f() -> int {\n    raise\n}").unwrap_err();
+        assert_eq!(err, "'raise' without an exception can only be used inside an except block at line 2");
+    }
+
+    #[test]
+    fn test_raise_from_needs_exception() {
+        assert!(typecheck_source("try {\n    x: int = 1\n} except ValueError as e {\n    raise RuntimeError(\"failed\") from e\n}").is_ok());
+        let err = typecheck_source("n: int = 1\nraise RuntimeError(\"failed\") from n").unwrap_err();
+        assert_eq!(err, "'raise ... from' needs a caught exception (except X as e), got int at line 2");
+    }
+
+    #[test]
+    fn test_generic_print() {
+        assert!(typecheck_source("print(1)\nprint(2.5)\nprint(True)\nprint(\"hi\")").is_ok());
+        assert!(typecheck_source("b: u8 = 7\nprint(b)").is_ok());
+        let err = typecheck_source("xs: list[int] = [1]\nprint(xs)").unwrap_err();
+        assert_eq!(err, "print() only accepts int, float, bool, or str values, got list[int]");
+        assert!(typecheck_source("print(1, 2)").unwrap_err().contains("takes exactly 1 argument"));
+    }
+
+    #[test]
+    fn test_print_objects_with_to_string() {
+        let point = "class Point {\n    x: int\n    y: int\n\n    def to_string(self: Point) -> str {\n        return f\"({self.x}, {self.y})\"\n    }\n}\n";
+        assert!(typecheck_source(&format!("{}p: Point = Point(1, 2)\nprint(p)\ns: str = f\"at {{p}}\"", point)).is_ok());
+        // Inherited to_string
+        assert!(typecheck_source(&format!("{}class Point3(Point) {{\n    z: int\n}}\nq: Point3 = Point3(1, 2, 3)\nprint(q)", point)).is_ok());
+
+        let err = typecheck_source("class Box {\n    n: int\n}\nb: Box = Box(1)\nprint(b)").unwrap_err();
+        assert_eq!(err, "Class 'Box' cannot be converted to a string: define 'to_string(self: Box) -> str'");
+        let err = typecheck_source("class Box {\n    n: int\n}\nb: Box = Box(1)\ns: str = f\"{b}\"").unwrap_err();
+        assert!(err.contains("define 'to_string(self: Box) -> str'"), "{}", err);
+        let err = typecheck_source("class Box {\n    n: int\n\n    def to_string(self: Box) -> int {\n        return self.n\n    }\n}\nb: Box = Box(1)\nprint(b)").unwrap_err();
+        assert!(err.contains("must take only self and return str"), "{}", err);
+    }
+
+    #[test]
+    fn test_null_coalescing_unwraps() {
+        assert!(typecheck_source("x: int? = None\ny: int = x ?? 0").is_ok());
+        assert!(typecheck_source("x: float? = None\ny: float = x ?? 1").is_ok());
+        assert!(typecheck_source("s: str? = None\nt: str = s ?? \"none\"").is_ok());
+        // An Optional fallback keeps the result Optional
+        assert!(typecheck_source("a: int? = None\nb: int? = 2\nc: int? = a ?? b").is_ok());
+        assert!(typecheck_source("a: int? = None\nb: int? = 2\nc: int = a ?? b")
+            .unwrap_err()
+            .contains("expected int, got int?"));
+        assert!(typecheck_source("a: int? = None\nb: int? = 2\nc: int = a ?? b ?? 0").is_ok());
+    }
+
+    #[test]
+    fn test_null_coalescing_errors() {
+        let err = typecheck_source("x: int = 1\ny: int = x ?? 0").unwrap_err();
+        assert_eq!(err, "Left side of '??' must be an Optional value, got int");
+        let err = typecheck_source("x: int? = None\ny: int = x ?? \"zero\"").unwrap_err();
+        assert_eq!(err, "Default for '??' must be int, got str");
+    }
+
+    #[test]
+    fn test_result_constructors_take_the_expected_type() {
+        assert!(typecheck_source("r: Result[int, str] = Ok(1)\ne: Result[int, str] = Err(\"bad\")").is_ok());
+        assert!(typecheck_source("f: Result[float, str] = Ok(1)\nb: Result[u8, str] = Ok(255)").is_ok());
+        assert!(typecheck_source("b: Result[u8, str] = Ok(256)").is_err());
+        let err = typecheck_source("r: Result[int, str] = Ok(\"one\")").unwrap_err();
+        assert!(err.contains("expected Result[int, str], got Result[str, _]"), "{}", err);
+        let err = typecheck_source("let r = Ok(1)").unwrap_err();
+        assert!(err.contains("Cannot infer the full Result type of 'r'"), "{}", err);
+    }
+
+    #[test]
+    fn test_result_methods() {
+        let source = "r: Result[int, str] = Ok(1)\nok: bool = r.is_ok() and not r.is_err()\nn: int = r.unwrap() + r.unwrap_or(0)\ne: str = r.unwrap_err()";
+        assert!(typecheck_source(source).is_ok());
+        let err = typecheck_source("r: Result[int, str] = Ok(1)\nn: int = r.unwrap_or(\"x\")").unwrap_err();
+        assert_eq!(err, "unwrap_or() default must be int, got str");
+        let err = typecheck_source("r: Result[int, str] = Ok(1)\nn: int = r.unwarp()").unwrap_err();
+        assert!(err.contains("did you mean 'unwrap'?"), "{}", err);
+    }
+
+    #[test]
+    fn test_propagate_operator() {
+        let helper = "def parse(s: str) -> Result[int, str] {\n    return Ok(1)\n}\n";
+        let ok = format!("{}def twice(s: str) -> Result[int, str] {{\n    return Ok(parse(s)? * 2)\n}}\n", helper);
+        assert!(typecheck_source(&ok).is_ok());
+
+        let not_result_fn = format!("{}def twice(s: str) -> int {{\n    return parse(s)? * 2\n}}\n", helper);
+        assert_eq!(
+            typecheck_source(&not_result_fn).unwrap_err(),
+            "'?' can only be used in a function that returns a Result"
+        );
+
+        let wrong_error = format!("{}def twice(s: str) -> Result[int, int] {{\n    return Ok(parse(s)?)\n}}\n", helper);
+        assert_eq!(
+            typecheck_source(&wrong_error).unwrap_err(),
+            "'?' cannot propagate error type str from a function whose errors are int"
+        );
+
+        let not_result = "def f(n: int) -> Result[int, str] {\n    return Ok(n?)\n}\n";
+        assert_eq!(typecheck_source(not_result).unwrap_err(), "'?' can only be applied to a Result, got int");
+    }
+
+    const OPTIONAL_CHAIN_CLASSES: &str = r#"
+class Address {
+    city: str
+    zip: int
+}
+
+class User {
+    name: str
+    address: Address?
+}
+"#;
+
+    #[test]
+    fn test_optional_chaining_yields_optional() {
+        let source = format!(
+            "{}\ndef city_of(user: User?) -> str? {{\n    return user?.address?.city\n}}\n",
+            OPTIONAL_CHAIN_CLASSES
+        );
+        assert!(typecheck_source(&source).is_ok());
+
+        // The result is optional even though the field is not
+        let source = format!("{}\nuser: User? = None\nname: str = user?.name\n", OPTIONAL_CHAIN_CLASSES);
+        assert!(typecheck_source(&source).unwrap_err().contains("expected str, got str?"));
+    }
+
+    #[test]
+    fn test_optional_chaining_errors() {
+        let source = format!("{}\nuser: User = User(\"a\", None)\nn: str? = user?.name\n", OPTIONAL_CHAIN_CLASSES);
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("requires an Optional value, got User; use '.' instead"), "{}", err);
+
+        let source = format!("{}\nuser: User? = None\nn: str? = user?.nmae\n", OPTIONAL_CHAIN_CLASSES);
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("did you mean 'name'?"), "{}", err);
+
+        let source = format!("{}\naddr: Address? = None\nz: int? = addr?.zip\n", OPTIONAL_CHAIN_CLASSES);
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("not supported for int fields"), "{}", err);
+    }
+
+    #[test]
+    fn test_strict_requires_optional_check() {
+        let source = r#"
+def count(names: list[str]?) -> int {
+    return names.length
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+        let err = typecheck_strict(source).unwrap_err();
+        assert!(err.contains("Optional value 'names' of type list[str]? must be checked against None"), "{}", err);
+
+        let source = r#"
+def count(names: list[str]?) -> int {
+    if names != None {
+        first: str = names[0]
+        return names.length
+    }
+    return 0
+}
+"#;
+        assert!(typecheck_strict(source).is_ok());
+    }
+
+    #[test]
+    fn test_strict_denies_shadowing() {
+        let source = r#"
+total: int = 0
+def add(total: int) -> int {
+    return total
+}
+"#;
+        assert!(typecheck_source(source).is_ok());
+        let err = typecheck_strict(source).unwrap_err();
+        assert_eq!(err, "Variable 'total' shadows an existing variable (strict mode)");
+
+        let source = r#"
+def f(xs: list[int]) -> int {
+    for x in xs {
+        x: int = 1
+    }
+    return 0
+}
+"#;
+        assert!(typecheck_strict(source).unwrap_err().contains("'x' shadows"));
+
+        // Sibling scopes may reuse names
+        let source = r#"
+def f(xs: list[int]) -> int {
+    for x in xs {
+        pass
+    }
+    for x in xs {
+        pass
+    }
+    return 0
+}
+"#;
+        assert!(typecheck_strict(source).is_ok());
+    }
+
+    #[test]
+    fn test_strict_warns_on_unused_result() {
+        let source = r#"
+def compute(n: int) -> int {
+    return n * 2
+}
+def main() -> int {
+    compute(1)
+    xs: list[int] = [1, 2]
+    xs.pop()
+    xs.push(3)
+    print_int(compute(2))
+    return 0
+}
+"#;
+        let warnings = typecheck_strict(source).unwrap();
+        assert_eq!(
+            warnings,
+            vec![
+                "Result of 'compute' (int) is unused (strict mode)".to_string(),
+                "Result of 'pop' (int) is unused (strict mode)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_range_length_loop_warning() {
+        let source = r#"
+def main() -> int {
+    xs: list[int] = [1, 2, 3]
+    total: int = 0
+    for i in range(xs.length) {
+        total = total + xs[i]
+    }
+    for i in range(xs.length) {
+        total = total + i * xs[i]
+    }
+    for j in range(xs.length) {
+        xs[j] = 0
+    }
+    return total
+}
+"#;
+        let warnings = typecheck_strict(source).unwrap();
+        assert_eq!(
+            warnings,
+            vec!["'for i in range(xs.length)' only uses i to read xs[i]; iterate over the list directly with 'for item in xs'".to_string()]
+        );
+    }
+
+    const ANIMALS: &str = "class Animal {\n    name: str\n    def sound(self: Animal) -> str {\n        return \"...\"\n    }\n}\nclass Dog(Animal) {\n    tricks: int\n}\n";
+
+    #[test]
+    fn test_inheritance_fields_methods_and_assignability() {
+        let source = format!(
+            "{}def f() -> str {{\n    d: Dog = Dog(\"Rex\", 3)\n    a: Animal = d\n    n: str = d.name\n    return d.sound()\n}}\n",
+            ANIMALS
+        );
+        assert!(typecheck_source(&source).is_ok(), "{:?}", typecheck_source(&source));
+
+        // A base class instance is not a subclass instance
+        let source = format!("{}def f() -> void {{\n    d: Dog = Animal(\"Rex\")\n}}\n", ANIMALS);
+        assert!(typecheck_source(&source).is_err());
+
+        // The constructor takes the inherited fields first
+        let source = format!("{}def f() -> void {{\n    d: Dog = Dog(3, \"Rex\")\n}}\n", ANIMALS);
+        assert!(typecheck_source(&source).is_err());
+    }
+
+    #[test]
+    fn test_inheritance_errors() {
+        let err = typecheck_source("class Dog(Animal) {\n    tricks: int\n}\n").unwrap_err();
+        assert!(err.contains("inherits from undefined class 'Animal'"), "{}", err);
+
+        let source = format!("{}class Cat(Animal) {{\n    name: str\n}}\n", ANIMALS);
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("Field 'name' of class 'Cat' is already defined in base class 'Animal'"), "{}", err);
+
+        let source = format!(
+            "{}class Cat(Animal) {{\n    def sound(self: Cat) -> int {{\n        return 1\n    }}\n}}\n",
+            ANIMALS
+        );
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("overrides 'Animal.sound' with a different signature"), "{}", err);
+    }
+
+    const SHAPES: &str = "class Shape {\n    name: str\n    @abstract\n    def area(self: Shape) -> float {\n        pass\n    }\n    def label(self: Shape) -> str {\n        return self.name\n    }\n}\nclass Square(Shape) {\n    side: float\n    def area(self: Square) -> float {\n        return self.side * self.side\n    }\n}\n";
+
+    #[test]
+    fn test_abstract_classes() {
+        let source = format!(
+            "{}def f() -> float {{\n    s: Shape = Square(\"sq\", 2.0)\n    return s.area()\n}}\n",
+            SHAPES
+        );
+        assert!(typecheck_source(&source).is_ok(), "{:?}", typecheck_source(&source));
+
+        let source = format!("{}def f() -> void {{\n    s: Shape = Shape(\"s\")\n}}\n", SHAPES);
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(
+            err.contains("Cannot instantiate abstract class 'Shape': abstract method 'area' is not implemented"),
+            "{}",
+            err
+        );
+
+        // A subclass that doesn't override the abstract method is abstract too
+        let source = format!(
+            "{}class Blob(Shape) {{\n    size: int\n}}\ndef f() -> void {{\n    b: Blob = Blob(\"b\", 1)\n}}\n",
+            SHAPES
+        );
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("Cannot instantiate abstract class 'Blob'"), "{}", err);
+
+        // @abstract on a class without abstract methods
+        let source = "@abstract\nclass Base {\n    id: int\n}\nclass Leaf(Base) {\n}\ndef f() -> void {\n    l: Leaf = Leaf(1)\n    b: Base = Base(2)\n}\n";
+        let err = typecheck_source(source).unwrap_err();
+        assert_eq!(err, "Cannot instantiate abstract class 'Base'");
+
+        // The abstract method has no implementation to call
+        let source = format!(
+            "{}class Circle(Shape) {{\n    def area(self: Circle) -> float {{\n        return super.area()\n    }}\n}}\n",
+            SHAPES
+        );
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("calls abstract method 'Shape.area'"), "{}", err);
+    }
+
+    #[test]
+    fn test_strict_allows_placeholder_abstract_body() {
+        assert!(typecheck_strict(SHAPES).is_ok(), "{:?}", typecheck_strict(SHAPES));
+    }
+
+    #[test]
+    fn test_abstract_decorator_errors() {
+        let err = typecheck_source("@abstract\ndef f() -> int {\n    return 1\n}\n").unwrap_err();
+        assert!(err.contains("@abstract decorator is only allowed on class methods"), "{}", err);
+
+        let err = typecheck_source("@memoize\nclass C {\n    x: int\n}\n").unwrap_err();
+        assert!(err.contains("Unknown decorator '@memoize' on class 'C'"), "{}", err);
+    }
+
+    #[test]
+    fn test_super_calls() {
+        let source = format!(
+            "{}class Cat(Animal) {{\n    def sound(self: Cat) -> str {{\n        return super.sound() + \"meow\"\n    }}\n}}\n",
+            ANIMALS
+        );
+        assert!(typecheck_source(&source).is_ok(), "{:?}", typecheck_source(&source));
+
+        let source = format!(
+            "{}class Cat(Animal) {{\n    def sound(self: Cat) -> str {{\n        return super.purr()\n    }}\n}}\n",
+            ANIMALS
+        );
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("Base class 'Animal' of 'Cat' has no method 'purr'"), "{}", err);
+
+        let source = format!(
+            "{}class Cat(Animal) {{\n    def sound(self: Cat) -> str {{\n        return super.sound(1)\n    }}\n}}\n",
+            ANIMALS
+        );
+        let err = typecheck_source(&source).unwrap_err();
+        assert!(err.contains("Method 'Animal.sound' expects 0 arguments, got 1"), "{}", err);
+
+        let err = typecheck_source(&format!("{}def f() -> str {{\n    return super.sound()\n}}\n", ANIMALS)).unwrap_err();
+        assert!(err.contains("can only be used inside a method"), "{}", err);
+
+        let err = typecheck_source("class Solo {\n    def f(self: Solo) -> void {\n        super.f()\n    }\n}\n").unwrap_err();
+        assert!(err.contains("which has no base class"), "{}", err);
+    }
+
+    #[test]
+    fn test_mixed_int_float_comparison_both_orders() {
+        assert!(typecheck_source("x: int = 3\nb: bool = x < 2.5").is_ok());
+        assert!(typecheck_source("x: int = 3\nb: bool = 2.5 > x").is_ok());
+    }
 }
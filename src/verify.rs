@@ -0,0 +1,109 @@
+// LLVM module verification, run right after codegen. Invalid IR is always a
+// compiler bug, so failures are reported against the WadeScript functions and
+// source lines the IR came from instead of surfacing later as a clang error.
+
+use inkwell::module::Module;
+use inkwell::values::AnyValue;
+use std::collections::HashMap;
+
+/// Run LLVM's verifier on `module`, describing a failure by function and line span
+pub fn verify_module(module: &Module) -> Result<(), String> {
+    let Err(message) = module.verify() else {
+        return Ok(());
+    };
+
+    let module_ir = module.print_to_string().to_string();
+    let mut report = String::from("LLVM rejected the generated IR");
+    for function in module.get_functions() {
+        if function.count_basic_blocks() == 0 || function.verify(false) {
+            continue;
+        }
+        let llvm_name = function.get_name().to_string_lossy().into_owned();
+        let name = llvm_name.strip_prefix("ws_").unwrap_or(&llvm_name);
+        report.push_str(&format!("\n  in function '{}'", name));
+        match source_line_span(&function.print_to_string().to_string(), &module_ir) {
+            Some((first, last)) if first == last => report.push_str(&format!(" (line {})", first)),
+            Some((first, last)) => report.push_str(&format!(" (lines {}-{})", first, last)),
+            None => {}
+        }
+    }
+    for line in message.to_string().lines().filter(|line| !line.trim().is_empty()) {
+        report.push_str(&format!("\n    {}", line.trim_end()));
+    }
+    Err(report)
+}
+
+/// First and last source line attached (via `!dbg` locations) to the
+/// instructions of a function
+fn source_line_span(function_ir: &str, module_ir: &str) -> Option<(u32, u32)> {
+    // `!12 = !DILocation(line: 5, column: 0, scope: !8)`
+    let locations: HashMap<&str, u32> = module_ir
+        .lines()
+        .filter_map(|line| {
+            let (id, rest) = line.split_once(" = !DILocation(line: ")?;
+            let digits = rest.split(|c: char| !c.is_ascii_digit()).next()?;
+            Some((id.trim_start_matches('!'), digits.parse().ok()?))
+        })
+        .collect();
+
+    let lines = function_ir
+        .split("!dbg !")
+        .skip(1)
+        .filter_map(|rest| {
+            let id = rest.split(|c: char| !c.is_ascii_digit()).next()?;
+            locations.get(id).copied()
+        })
+        .filter(|&line| line > 0);
+    lines.fold(None, |span, line| match span {
+        None => Some((line, line)),
+        Some((first, last)) => Some((first.min(line), last.max(line))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use inkwell::context::Context;
+
+    #[test]
+    fn test_verify_module_ok() {
+        let context = Context::create();
+        let module = context.create_module("test");
+        let function = module.add_function("ws_one", context.i64_type().fn_type(&[], false), None);
+        let builder = context.create_builder();
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+        builder.build_return(Some(&context.i64_type().const_int(1, false))).unwrap();
+        assert_eq!(verify_module(&module), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_module_names_broken_function() {
+        let context = Context::create();
+        let module = context.create_module("test");
+        let builder = context.create_builder();
+        let fn_type = context.i64_type().fn_type(&[], false);
+
+        let good = module.add_function("ws_good", fn_type, None);
+        builder.position_at_end(context.append_basic_block(good, "entry"));
+        builder.build_return(Some(&context.i64_type().const_int(1, false))).unwrap();
+
+        // Returning nothing from an int function
+        let bad = module.add_function("ws_bad", fn_type, None);
+        builder.position_at_end(context.append_basic_block(bad, "entry"));
+        builder.build_return(None).unwrap();
+
+        let err = verify_module(&module).unwrap_err();
+        assert!(err.contains("in function 'bad'"), "{}", err);
+        assert!(!err.contains("'good'"), "{}", err);
+        // The verifier's own message follows
+        assert!(err.contains("return"), "{}", err);
+    }
+
+    #[test]
+    fn test_source_line_span() {
+        let module_ir = "!7 = !DILocation(line: 4, column: 0, scope: !3)\n!8 = !DILocation(line: 9, column: 0, scope: !3)\n!9 = !DILocation(line: 0, column: 0, scope: !3)\n";
+        let function_ir = "define i64 @ws_f() !dbg !3 {\nentry:\n  %a = call i64 @g(), !dbg !8\n  %b = call i64 @g(), !dbg !7\n  ret i64 %a, !dbg !9\n}\n";
+        assert_eq!(source_line_span(function_ir, module_ir), Some((4, 9)));
+        assert_eq!(source_line_span("define void @ws_h() {\n  ret void\n}\n", module_ir), None);
+    }
+}
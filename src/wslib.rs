@@ -0,0 +1,249 @@
+//! Compiled libraries (`.wslib`)
+//!
+//! `wadescript geometry.ws --emit-lib` packages a module's typed interface with
+//! its object code, so the module can be shared without its source. When
+//! `import "geometry"` finds no `geometry.ws` but a `geometry.wslib`, the
+//! interface is handed to the type checker and code generator as external
+//! declarations and the object code is linked into the program.
+//!
+//! An archive is a text header followed by the object file:
+//!
+//!     WSLIB 1
+//!     version 0.1.0
+//!     target x86_64-unknown-linux-gnu
+//!     def area(w: int, h: int) -> int
+//!     object 1832
+//!     <1832 bytes of object code>
+
+use std::fs;
+use std::path::Path;
+
+use crate::ast::{Library, LibraryFunction, Program, Statement, Type};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+pub const EXTENSION: &str = "wslib";
+const MAGIC: &str = "WSLIB 1";
+const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The functions a library exports: the entry file's own functions, except
+/// `main`. Modules it imports are compiled into the library but not exported.
+pub fn exports(program: &Program) -> Result<Vec<LibraryFunction>, String> {
+    if let Some(library) = program.libraries.first() {
+        return Err(format!(
+            "a library can't import another compiled library ('{}') yet",
+            library.path.display()
+        ));
+    }
+
+    let mut functions = Vec::new();
+    for statement in &program.statements {
+        let Statement::FunctionDef { name, params, return_type, .. } = statement else {
+            continue;
+        };
+        // An imported module's functions are `module.name`
+        if name == "main" || name.contains('.') {
+            continue;
+        }
+        if let Some(param) = params.iter().find(|p| p.default_value.is_some()) {
+            return Err(format!(
+                "Function '{}': parameter '{}' has a default value, which a library interface can't carry yet",
+                name, param.name
+            ));
+        }
+        let types = params.iter().map(|p| &p.param_type).chain([return_type]);
+        if let Some(class_type) = types.into_iter().find(|t| mentions_class(t)) {
+            return Err(format!(
+                "Function '{}' uses the class type '{}'; classes can't cross a library boundary yet",
+                name, class_type
+            ));
+        }
+        functions.push(LibraryFunction {
+            name: name.clone(),
+            params: params.clone(),
+            return_type: return_type.clone(),
+        });
+    }
+
+    if functions.is_empty() {
+        return Err("The module defines no functions to export".to_string());
+    }
+    Ok(functions)
+}
+
+fn mentions_class(ws_type: &Type) -> bool {
+    match ws_type {
+        Type::Custom(name) => name != "void",
+        Type::Array(inner, _) | Type::List(inner) | Type::Optional(inner) => mentions_class(inner),
+        Type::Dict(key, value) | Type::Result(key, value) => mentions_class(key) || mentions_class(value),
+        Type::Tuple(types) => types.iter().any(mentions_class),
+        _ => false,
+    }
+}
+
+/// Write a library archive
+pub fn write(path: &Path, target: &str, functions: &[LibraryFunction], object: &[u8]) -> Result<(), String> {
+    let mut header = format!("{}\nversion {}\ntarget {}\n", MAGIC, COMPILER_VERSION, target);
+    for function in functions {
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .map(|p| format!("{}: {}", p.name, p.param_type))
+            .collect();
+        header.push_str(&format!("def {}({}) -> {}\n", function.name, params.join(", "), function.return_type));
+    }
+    header.push_str(&format!("object {}\n", object.len()));
+
+    let mut archive = header.into_bytes();
+    archive.extend_from_slice(object);
+    fs::write(path, archive).map_err(|e| format!("Cannot write '{}': {}", path.display(), e))
+}
+
+/// Read a library's interface, checking that this compiler built it
+pub fn load(path: &Path) -> Result<Library, String> {
+    read(path).map(|(library, _)| library)
+}
+
+/// Read a library's object code, for linking
+pub fn object(path: &Path) -> Result<Vec<u8>, String> {
+    read(path).map(|(_, object)| object)
+}
+
+fn read(path: &Path) -> Result<(Library, Vec<u8>), String> {
+    let bytes = fs::read(path).map_err(|e| format!("Error reading library '{}': {}", path.display(), e))?;
+    let invalid = |problem: &str| format!("'{}' is not a WadeScript library: {}", path.display(), problem);
+
+    let (magic, mut rest) = split_line(&bytes).ok_or_else(|| invalid("no header"))?;
+    if magic != MAGIC {
+        return Err(invalid("no header"));
+    }
+    let mut version = None;
+    let mut target = None;
+    let mut functions = Vec::new();
+    loop {
+        let (line, after) = split_line(rest).ok_or_else(|| invalid("truncated header"))?;
+        rest = after;
+        if let Some(value) = line.strip_prefix("version ") {
+            version = Some(value);
+        } else if let Some(value) = line.strip_prefix("target ") {
+            target = Some(value.to_string());
+        } else if line.starts_with("def ") {
+            let function = parse_signature(line).ok_or_else(|| invalid(&format!("bad signature '{}'", line)))?;
+            functions.push(function);
+        } else if let Some(size) = line.strip_prefix("object ") {
+            if size.parse::<usize>().ok() != Some(rest.len()) {
+                return Err(invalid("truncated object code"));
+            }
+            break;
+        } else {
+            return Err(invalid(&format!("unexpected line '{}'", line)));
+        }
+    }
+
+    let version = version.ok_or_else(|| invalid("no compiler version"))?;
+    if version != COMPILER_VERSION {
+        return Err(format!(
+            "'{}' was built by WadeScript {}, but this compiler is WadeScript {}; rebuild it with --emit-lib",
+            path.display(),
+            version,
+            COMPILER_VERSION
+        ));
+    }
+    let target = target.ok_or_else(|| invalid("no target"))?;
+    let library = Library { path: path.to_path_buf(), target, functions };
+    Ok((library, rest.to_vec()))
+}
+
+/// The first line of `bytes` and what follows it
+fn split_line(bytes: &[u8]) -> Option<(&str, &[u8])> {
+    let end = bytes.iter().position(|&b| b == b'\n')?;
+    let line = std::str::from_utf8(&bytes[..end]).ok()?;
+    Some((line, &bytes[end + 1..]))
+}
+
+/// Parse a `def name(params) -> type` line with the language's own parser
+fn parse_signature(line: &str) -> Option<LibraryFunction> {
+    let source = format!("{} {{\n    pass\n}}\n", line);
+    let program = Parser::new(Lexer::new(source)).parse();
+    match program.statements.as_slice() {
+        [Statement::FunctionDef { name, params, return_type, .. }] => Some(LibraryFunction {
+            name: name.clone(),
+            params: params.clone(),
+            return_type: return_type.clone(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn parse(source: &str) -> Program {
+        Parser::new(Lexer::new(source.to_string())).parse()
+    }
+
+    #[test]
+    fn test_exports_skip_main_and_imports() {
+        let mut program = parse(
+            "def helper(x: int) -> int {\n    return x\n}\n\n\
+             def scale(values: list[float], by: float) -> list[float]? {\n    return None\n}\n\n\
+             def main() -> int {\n    return 0\n}\n",
+        );
+        // What the loader makes of `helper` when it comes from `import "util"`
+        if let Statement::FunctionDef { name, .. } = &mut program.statements[0] {
+            *name = "util.helper".to_string();
+        }
+        let functions = exports(&program).unwrap();
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].name, "scale");
+    }
+
+    #[test]
+    fn test_exports_reject_defaults_and_classes() {
+        let err = exports(&parse("def f(x: int = 1) -> int {\n    return x\n}\n")).unwrap_err();
+        assert!(err.contains("parameter 'x' has a default value"), "{}", err);
+
+        let source = "class Point {\n    x: int\n}\n\ndef origin() -> list[Point] {\n    return []\n}\n";
+        let err = exports(&parse(source)).unwrap_err();
+        assert!(err.contains("class type 'list[Point]'"), "{}", err);
+
+        let err = exports(&parse("def main() -> int {\n    return 0\n}\n")).unwrap_err();
+        assert!(err.contains("no functions to export"), "{}", err);
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let program = parse(
+            "def area(w: int, h: int) -> int {\n    return w * h\n}\n\n\
+             def label(pairs: dict[str, (int, bool)]) -> void {\n    pass\n}\n",
+        );
+        let functions = exports(&program).unwrap();
+        let path = env::temp_dir().join(format!("ws_wslib_{}.wslib", std::process::id()));
+        let object = b"\x7fELF\nnot really\n".to_vec();
+        write(&path, "x86_64-unknown-linux-gnu", &functions, &object).unwrap();
+
+        let library = load(&path).unwrap();
+        assert_eq!(library.target, "x86_64-unknown-linux-gnu");
+        assert_eq!(library.functions.len(), 2);
+        assert_eq!(library.functions[0].name, "area");
+        assert_eq!(library.functions[0].params[1].name, "h");
+        assert_eq!(library.functions[0].return_type, Type::Int);
+        let pair = Type::Tuple(vec![Type::Int, Type::Bool]);
+        assert_eq!(library.functions[1].params[0].param_type, Type::Dict(Box::new(Type::Str), Box::new(pair)));
+        assert_eq!(library.functions[1].return_type.to_string(), "void");
+        assert_eq!(super::object(&path).unwrap(), object);
+
+        // A truncated archive is rejected instead of linking half an object
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+        let err = load(&path).unwrap_err();
+        assert!(err.contains("truncated object code"), "{}", err);
+
+        fs::write(&path, String::from_utf8_lossy(&bytes).replace(COMPILER_VERSION, "0.0.1").as_bytes()).unwrap();
+        let err = load(&path).unwrap_err();
+        assert!(err.contains("was built by WadeScript 0.0.1"), "{}", err);
+        fs::remove_file(&path).ok();
+    }
+}
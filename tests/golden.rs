@@ -0,0 +1,129 @@
+// Golden-file tests for the programs in examples/: each `<name>.ws` is
+// compiled and run, and its stdout and exit code are compared against
+// `<name>.expected`.
+//
+// Regenerate the snapshots after an intended behavior change with:
+//
+//     UPDATE_EXPECTED=1 cargo test --test golden
+//
+// Every example needs a `.expected` file; a new example fails until its
+// snapshot is created the same way. Modules without a `main` (math_lib.ws)
+// only exist to be imported, and are covered by the examples importing them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const EXAMPLES_DIR: &str = "examples";
+
+/// What an example did, in the form stored in its `.expected` file
+fn render_outcome(stdout: &str, exit_code: Option<i32>) -> String {
+    let trailer = match exit_code {
+        Some(0) => return stdout.to_string(),
+        Some(code) => format!("[exit code: {}]\n", code),
+        None => "[killed by signal]\n".to_string(),
+    };
+    let mut rendered = stdout.to_string();
+    if !rendered.is_empty() && !rendered.ends_with('\n') {
+        rendered.push('\n');
+    }
+    rendered.push_str(&trailer);
+    rendered
+}
+
+/// Compile one example in a scratch directory under cargo's target dir, where
+/// the executable lands (the compiler finds its runtime and std next to
+/// itself), and run it from the repository root
+fn run_example(root: &Path, source: &Path) -> String {
+    let scratch = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    fs::create_dir_all(&scratch).expect("failed to create the scratch directory");
+    let compile = Command::new(env!("CARGO_BIN_EXE_wadescript"))
+        .arg(root.join(source))
+        .args(["--quiet", "--color=never"])
+        .current_dir(&scratch)
+        .output()
+        .expect("failed to run the compiler");
+    if !compile.status.success() {
+        // Compile errors are part of the snapshot too
+        let stderr = String::from_utf8_lossy(&compile.stderr);
+        return format!("[compile error]\n{}", render_outcome(&stderr, compile.status.code()));
+    }
+
+    let stem = source.file_stem().unwrap().to_string_lossy().into_owned();
+    let executable = scratch.join(&stem);
+    let run = Command::new(&executable)
+        .current_dir(root)
+        .output()
+        .expect("failed to run the compiled example");
+    fs::remove_file(&executable).ok();
+
+    render_outcome(&String::from_utf8_lossy(&run.stdout), run.status.code())
+}
+
+#[test]
+fn examples_match_expected_output() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let profile = if cfg!(debug_assertions) { "debug" } else { "release" };
+    let runtime_lib = root.join("target").join(profile).join("libwadescript_runtime.a");
+    assert!(
+        runtime_lib.exists(),
+        "{} is missing; build it with `cargo build` first",
+        runtime_lib.display()
+    );
+    let update = std::env::var_os("UPDATE_EXPECTED").is_some();
+
+    let mut sources: Vec<PathBuf> = fs::read_dir(root.join(EXAMPLES_DIR))
+        .expect("failed to read examples/")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ws"))
+        .filter(|path| fs::read_to_string(path).unwrap().contains("def main("))
+        .collect();
+    sources.sort();
+
+    let mut failures = Vec::new();
+    let mut missing = Vec::new();
+    for path in &sources {
+        let expected_path = path.with_extension("expected");
+        if !update && !expected_path.exists() {
+            missing.push(path.strip_prefix(&root).unwrap().display().to_string());
+            continue;
+        }
+        let source = path.strip_prefix(&root).unwrap();
+        let actual = run_example(&root, source);
+
+        if update {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap();
+        if actual != expected {
+            failures.push(format!(
+                "{}\n--- expected\n{}--- actual\n{}",
+                source.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+
+    assert!(
+        missing.is_empty(),
+        "{} example(s) have no .expected file (create them with UPDATE_EXPECTED=1): {}",
+        missing.len(),
+        missing.join(", ")
+    );
+    assert!(
+        failures.is_empty(),
+        "{} example(s) differ from their .expected file (rerun with UPDATE_EXPECTED=1 if the change is intended):\n\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_render_outcome() {
+    assert_eq!(render_outcome("hi\n", Some(0)), "hi\n");
+    assert_eq!(render_outcome("", Some(52)), "[exit code: 52]\n");
+    assert_eq!(render_outcome("partial", Some(1)), "partial\n[exit code: 1]\n");
+    assert_eq!(render_outcome("", None), "[killed by signal]\n");
+}
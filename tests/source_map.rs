@@ -0,0 +1,79 @@
+// Traces of optimized programs: `-O2` inlines the functions below into main,
+// and the runtime error trace still lists each of their lines, read from the
+// `.wsmap` source map written next to the executable.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PROGRAM: &str = r#"def check(n: int) -> int {
+    if n > 2 {
+        raise ValueError("too big")
+    }
+    return n * 2
+}
+
+def helper(n: int) -> int {
+    total: int = check(n)
+    return total + 1
+}
+
+def main() -> int {
+    x: int = 0
+    for i in range(5) {
+        x = x + helper(i)
+    }
+    print_int(x)
+    return 0
+}
+"#;
+
+/// Compile `source` from the repository root (where the runtime archive is
+/// found) with extra flags
+fn compile(root: &Path, source: &Path, flags: &[&str]) {
+    let compile = Command::new(env!("CARGO_BIN_EXE_wadescript"))
+        .arg(source)
+        .args(["--quiet", "--color=never"])
+        .args(flags)
+        .current_dir(root)
+        .output()
+        .expect("failed to run the compiler");
+    assert!(compile.status.success(), "{}", String::from_utf8_lossy(&compile.stderr));
+}
+
+#[test]
+fn optimized_traces_list_inlined_lines() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let stem = format!("ws_source_map_{}", std::process::id());
+    let source = std::env::temp_dir().join(format!("{}.ws", stem));
+    fs::write(&source, PROGRAM).unwrap();
+    let source = fs::canonicalize(&source).unwrap();
+    let executable = root.join(&stem);
+    let map = root.join(format!("{}.wsmap", stem));
+
+    compile(&root, &source, &["-O2"]);
+    assert!(map.exists(), "-O2 writes {}", map.display());
+    let run = Command::new(&executable)
+        .current_dir(&root)
+        .env_remove("WS_BACKTRACE")
+        .env_remove("WS_ERROR_LOG")
+        .output()
+        .expect("failed to run the compiled program");
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert_eq!(run.status.code(), Some(1), "{}", stderr);
+    let trace: Vec<&str> = stderr.lines().filter(|line| line.trim_start().starts_with("at ")).collect();
+    let file = source.display();
+    assert_eq!(
+        trace,
+        vec![format!("  at {}:3", file), format!("  at {}:9", file), format!("  at {}:16", file)],
+        "{}",
+        stderr
+    );
+
+    compile(&root, &source, &["-O0"]);
+    assert!(!map.exists(), "-O0 removes the map of an earlier build");
+
+    fs::remove_file(&executable).ok();
+    fs::remove_dir_all(root.join(format!("{}.dSYM", stem))).ok();
+    fs::remove_file(&source).ok();
+}